@@ -63,10 +63,16 @@ _original_methods = {
     # Column operations
     "remove_column": _PyBundleBuilder.remove_column,
     "rename_column": _PyBundleBuilder.rename_column,
+    "column_alias": _PyBundleBuilder.column_alias,
 
     # Row operations
     "filter": _PyBundleBuilder.filter,
+    "delete_rows": _PyBundleBuilder.delete_rows,
     "join": _PyBundleBuilder.join,
+    "merge": _PyBundleBuilder.merge,
+    "declare_key": _PyBundleBuilder.declare_key,
+    "set_nullable": _PyBundleBuilder.set_nullable,
+    "label": _PyBundleBuilder.label,
     "attach_to_join": _PyBundleBuilder.attach_to_join,
 
     # Query operations
@@ -134,7 +140,8 @@ def _wrap_mutation_method(method_name: str) -> Callable[..., OperationChain]:
 # Wrap mutation methods to return OperationChain
 # (but NOT read-only methods like schema, num_rows, explain)
 mutation_methods = [
-    "attach", "remove_column", "rename_column", "filter", "select", "join",
+    "attach", "remove_column", "rename_column", "column_alias", "filter", "delete_rows", "select", "join", "merge",
+    "declare_key", "set_nullable", "label",
     "create_view", "set_name", "set_description", "set_config", "define_function",
     "create_index", "rebuild_index", "reindex"
 ]
@@ -262,6 +262,26 @@ class PyChange:
         """Number of individual operations in this change."""
         ...
 
+    @property
+    def summary(self) -> str:
+        """Human-readable summary of every operation in this change."""
+        ...
+
+    @property
+    def files(self) -> List[str]:
+        """Source files attached by this change."""
+        ...
+
+    @property
+    def columns(self) -> List[str]:
+        """Columns removed, renamed, indexed or constrained by this change."""
+        ...
+
+    @property
+    def undoable(self) -> bool:
+        """Whether the change is uncommitted and can be reverted with undo()."""
+        ...
+
 
 class PyBundleStatus:
     """Bundle status showing uncommitted changes."""
@@ -462,6 +482,28 @@ class PyBundleBuilder:
         """
         ...
 
+    def column_alias(self, original: str, alias: str) -> "OperationChain":
+        """
+        Queue a column_alias operation.
+
+        Unlike rename_column, the original name is kept in the field metadata
+        under "bundlebase.original_name".
+
+        Args:
+            original: Current column name
+            alias: Name to expose the column as
+
+        Returns:
+            OperationChain for fluent chaining
+
+        Raises:
+            ValueError: If the column doesn't exist or the alias is already taken
+
+        Example:
+            c = await c.column_alias("First Name", "first_name")
+        """
+        ...
+
     def set_name(self, name: str) -> "OperationChain":
         """
         Queue a set_name operation.
@@ -526,6 +568,22 @@ class PyBundleBuilder:
         """
         ...
 
+    def delete_rows(self, where_clause: str, params: Optional[List[Any]] = None) -> "OperationChain":
+        """
+        Queue a delete_rows operation, removing the rows matching the WHERE clause.
+
+        Args:
+            where_clause: SQL WHERE clause (e.g., "salary < $1")
+            params: Optional list of parameters for parameterized queries
+
+        Returns:
+            OperationChain for fluent chaining
+
+        Example:
+            c = await c.delete_rows("salary < $1", [50000])
+        """
+        ...
+
 
     def join(self, url: str, expression: str, join_type: Optional[str] = None) -> "OperationChain":
         """
@@ -544,6 +602,76 @@ class PyBundleBuilder:
         """
         ...
 
+    def merge(self, url: str, key_columns: Optional[List[str]] = None) -> "OperationChain":
+        """
+        Queue a merge operation.
+
+        Rows whose key matches a row in the merged data are replaced by it,
+        and rows with new keys are added. Each merged row needs a key without
+        nulls that no other merged row shares. Existing rows with a null key are kept.
+
+        Args:
+            url: Data source URL with the same columns as the bundle
+            key_columns: Columns identifying a row. Defaults to the key declared with declare_key()
+
+        Returns:
+            OperationChain for fluent chaining
+
+        Example:
+            c = await c.merge("customers_update.csv", ["customer_id"])
+        """
+        ...
+
+    def declare_key(self, columns: List[str], verify_unique: bool = False) -> "OperationChain":
+        """
+        Queue a key declaration.
+
+        The key is marked on the schema fields and used as the default key for merge().
+
+        Args:
+            columns: Columns that uniquely identify a row, in key order
+            verify_unique: Check the current data and fail if any key value is repeated
+
+        Returns:
+            OperationChain for fluent chaining
+
+        Example:
+            c = await c.declare_key(["customer_id"], verify_unique=True)
+        """
+        ...
+
+    def set_nullable(self, column: str, nullable: bool) -> "OperationChain":
+        """
+        Queue a nullability declaration for a column.
+
+        Args:
+            column: Column name
+            nullable: False to mark the column as NOT NULL in the schema
+
+        Returns:
+            OperationChain for fluent chaining
+        """
+        ...
+
+    def label(self, key: str, value: str) -> "OperationChain":
+        """
+        Queue a label for the most recent change.
+
+        Labels are saved with the change in the manifest and can be used to find
+        its operations later with operations_with_label().
+
+        Args:
+            key: Label key
+            value: Label value
+
+        Returns:
+            OperationChain for fluent chaining
+
+        Example:
+            c = await c.attach("sales.parquet").label("dataset", "sales")
+        """
+        ...
+
     def select(self, sql: str, params: Optional[List[Any]] = None) -> "OperationChain":
         """
         Queue a select operation.
@@ -730,14 +858,38 @@ class OperationChain:
         """Queue a rename_column operation."""
         ...
 
+    def column_alias(self, original: str, alias: str) -> "OperationChain":
+        """Queue a column_alias operation."""
+        ...
+
     def filter(self, where_clause: str, params: Optional[List[Any]] = None) -> "OperationChain":
         """Queue a filter operation."""
         ...
 
+    def delete_rows(self, where_clause: str, params: Optional[List[Any]] = None) -> "OperationChain":
+        """Queue a delete_rows operation."""
+        ...
+
     def join(self, url: str, expression: str, join_type: Optional[str] = None) -> "OperationChain":
         """Queue a join operation."""
         ...
 
+    def merge(self, url: str, key_columns: Optional[List[str]] = None) -> "OperationChain":
+        """Queue a merge operation."""
+        ...
+
+    def declare_key(self, columns: List[str], verify_unique: bool = False) -> "OperationChain":
+        """Queue a key declaration."""
+        ...
+
+    def set_nullable(self, column: str, nullable: bool) -> "OperationChain":
+        """Queue a nullability declaration."""
+        ...
+
+    def label(self, key: str, value: str) -> "OperationChain":
+        """Queue a label for the most recent change."""
+        ...
+
     def select(self, sql: str, params: Optional[List[Any]] = None) -> "OperationChain":
         """Queue a select operation."""
         ...
@@ -790,14 +942,38 @@ class CreateChain:
         """Queue a rename_column operation."""
         ...
 
+    def column_alias(self, original: str, alias: str) -> "CreateChain":
+        """Queue a column_alias operation."""
+        ...
+
     def filter(self, where_clause: str, params: Optional[List[Any]] = None) -> "CreateChain":
         """Queue a filter operation."""
         ...
 
+    def delete_rows(self, where_clause: str, params: Optional[List[Any]] = None) -> "CreateChain":
+        """Queue a delete_rows operation."""
+        ...
+
     def join(self, url: str, expression: str, join_type: Optional[str] = None) -> "CreateChain":
         """Queue a join operation."""
         ...
 
+    def merge(self, url: str, key_columns: Optional[List[str]] = None) -> "CreateChain":
+        """Queue a merge operation."""
+        ...
+
+    def declare_key(self, columns: List[str], verify_unique: bool = False) -> "CreateChain":
+        """Queue a key declaration."""
+        ...
+
+    def set_nullable(self, column: str, nullable: bool) -> "CreateChain":
+        """Queue a nullability declaration."""
+        ...
+
+    def label(self, key: str, value: str) -> "CreateChain":
+        """Queue a label for the most recent change."""
+        ...
+
     def select(self, sql: str, params: Optional[List[Any]] = None) -> "CreateChain":
         """Queue a select operation."""
         ...
@@ -850,14 +1026,38 @@ class ExtendChain:
         """Queue a rename_column operation."""
         ...
 
+    def column_alias(self, original: str, alias: str) -> "ExtendChain":
+        """Queue a column_alias operation."""
+        ...
+
     def filter(self, where_clause: str, params: Optional[List[Any]] = None) -> "ExtendChain":
         """Queue a filter operation."""
         ...
 
+    def delete_rows(self, where_clause: str, params: Optional[List[Any]] = None) -> "ExtendChain":
+        """Queue a delete_rows operation."""
+        ...
+
     def join(self, url: str, expression: str, join_type: Optional[str] = None) -> "ExtendChain":
         """Queue a join operation."""
         ...
 
+    def merge(self, url: str, key_columns: Optional[List[str]] = None) -> "ExtendChain":
+        """Queue a merge operation."""
+        ...
+
+    def declare_key(self, columns: List[str], verify_unique: bool = False) -> "ExtendChain":
+        """Queue a key declaration."""
+        ...
+
+    def set_nullable(self, column: str, nullable: bool) -> "ExtendChain":
+        """Queue a nullability declaration."""
+        ...
+
+    def label(self, key: str, value: str) -> "ExtendChain":
+        """Queue a label for the most recent change."""
+        ...
+
     def select(self, sql: str, params: Optional[List[Any]] = None) -> "ExtendChain":
         """Queue a select operation."""
         ...
@@ -236,6 +236,20 @@ class SyncBundleBuilder(SyncBundle):
         self._async = _loop_manager.run_sync(coro)
         return self
 
+    def column_alias(self, original: str, alias: str) -> "SyncBundleBuilder":
+        """Expose a column under an alias, keeping its original name in the field metadata.
+
+        Args:
+            original: Current column name
+            alias: Name to expose the column as
+
+        Returns:
+            Self for fluent chaining
+        """
+        coro = _call_original_method(self._async, "column_alias", original, alias)
+        self._async = _loop_manager.run_sync(coro)
+        return self
+
     def filter(self, where_clause: str, params: Optional[List[Any]] = None) -> "SyncBundleBuilder":
         """Filter rows based on a SQL WHERE clause.
 
@@ -253,6 +267,23 @@ class SyncBundleBuilder(SyncBundle):
         self._async = _loop_manager.run_sync(coro)
         return self
 
+    def delete_rows(self, where_clause: str, params: Optional[List[Any]] = None) -> "SyncBundleBuilder":
+        """Delete rows matching a SQL WHERE clause.
+
+        Args:
+            where_clause: SQL WHERE condition (e.g., "salary < $1")
+            params: Optional list of parameters for parameterized queries.
+                    If None, defaults to empty list.
+
+        Returns:
+            Self for fluent chaining
+        """
+        if params is None:
+            params = []
+        coro = _call_original_method(self._async, "delete_rows", where_clause, params)
+        self._async = _loop_manager.run_sync(coro)
+        return self
+
     def join(
         self, name: str, url: str, on: str, how: str = "inner"
     ) -> "SyncBundleBuilder":
@@ -261,6 +292,62 @@ class SyncBundleBuilder(SyncBundle):
         self._async = _loop_manager.run_sync(coro)
         return self
 
+    def merge(self, url: str, key_columns: Optional[List[str]] = None) -> "SyncBundleBuilder":
+        """Merge a data source into the bundle, replacing rows with matching keys.
+
+        Args:
+            url: Data source URL with the same columns as the bundle
+            key_columns: Columns identifying a row (defaults to the declared key)
+
+        Returns:
+            Self for fluent chaining
+        """
+        coro = _call_original_method(self._async, "merge", url, key_columns)
+        self._async = _loop_manager.run_sync(coro)
+        return self
+
+    def declare_key(self, columns: List[str], verify_unique: bool = False) -> "SyncBundleBuilder":
+        """Declare the columns that uniquely identify a row.
+
+        Args:
+            columns: Key columns, in key order
+            verify_unique: Check the current data and fail if the key has duplicates
+
+        Returns:
+            Self for fluent chaining
+        """
+        coro = _call_original_method(self._async, "declare_key", columns, verify_unique)
+        self._async = _loop_manager.run_sync(coro)
+        return self
+
+    def set_nullable(self, column: str, nullable: bool) -> "SyncBundleBuilder":
+        """Declare whether a column may contain nulls.
+
+        Args:
+            column: Column name
+            nullable: False to mark the column as NOT NULL
+
+        Returns:
+            Self for fluent chaining
+        """
+        coro = _call_original_method(self._async, "set_nullable", column, nullable)
+        self._async = _loop_manager.run_sync(coro)
+        return self
+
+    def label(self, key: str, value: str) -> "SyncBundleBuilder":
+        """Label the most recent change with a key/value pair.
+
+        Args:
+            key: Label key
+            value: Label value
+
+        Returns:
+            Self for fluent chaining
+        """
+        coro = _call_original_method(self._async, "label", key, value)
+        self._async = _loop_manager.run_sync(coro)
+        return self
+
     def attach_to_join(self, name: str, url: str) -> "SyncBundleBuilder":
         """Attach a data source for joining."""
         coro = _call_original_method(self._async, "attach_to_join", name, url)
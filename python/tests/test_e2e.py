@@ -253,6 +253,16 @@ async def test_filter():
     assert len(results["id"]) == 798
 
 
+@pytest.mark.asyncio
+async def test_delete_rows():
+    c = await bundlebase.create(random_bundle())
+    c = await (c.attach(datafile("userdata.parquet"))
+               .delete_rows("salary > $1", [50000.0]))
+
+    results = await c.to_dict()
+    assert len(results["id"]) == 202
+
+
 @pytest.mark.asyncio
 async def test_python_function_with_multiple_pages():
     """Test Python function that returns data across multiple pages"""
@@ -0,0 +1,217 @@
+use base64::Engine;
+use bundlebase::metrics::{OperationCategory, OperationOutcome, Span};
+use bundlebase::BundlebaseError;
+use chrono::{SecondsFormat, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+
+/// What an audit record is about
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    /// A bundle was opened to be served
+    Open,
+    /// A read query ran
+    Query,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Open => "open",
+            AuditAction::Query => "query",
+        }
+    }
+}
+
+/// One entry of the audit log. Unlike the bundle's history, which records changes to the data,
+/// this records who read it and when.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEvent {
+    /// RFC 3339 time the event happened, in UTC
+    pub timestamp: String,
+    pub action: AuditAction,
+    /// URL of the bundle opened, or the bundles queried
+    pub bundle: String,
+    /// User name the client authenticated as, if it sent credentials
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// Address the request came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, bundle: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            action,
+            bundle: bundle.into(),
+            identity: None,
+            client: None,
+            sql: None,
+            outcome: None,
+            duration_ms: None,
+        }
+    }
+}
+
+/// Where audit records are written.
+///
+/// `record` is called once the work it describes is done, so it should return quickly.
+/// Failing to record is logged by the caller rather than failing the request.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, event: &AuditEvent) -> Result<(), BundlebaseError>;
+}
+
+/// Appends each record as a line of JSON to a local file, which is created if needed
+pub struct FileAuditSink {
+    file: Mutex<File>,
+}
+
+impl FileAuditSink {
+    pub fn open(path: &str) -> Result<Self, BundlebaseError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Cannot open audit log {}: {}", path, e))?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, event: &AuditEvent) -> Result<(), BundlebaseError> {
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+        // One write per record, so concurrent servers appending to the same file don't interleave
+        self.file.lock().write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Prints each record as a line of JSON to stdout
+pub struct StdoutAuditSink;
+
+impl AuditSink for StdoutAuditSink {
+    fn record(&self, event: &AuditEvent) -> Result<(), BundlebaseError> {
+        println!("{}", serde_json::to_string(event)?);
+        Ok(())
+    }
+}
+
+/// Exports each record as an `audit.<action>` span through the configured OpenTelemetry tracer
+pub struct OtelAuditSink;
+
+impl AuditSink for OtelAuditSink {
+    fn record(&self, event: &AuditEvent) -> Result<(), BundlebaseError> {
+        let mut span = Span::start(
+            OperationCategory::Select,
+            format!("audit.{}", event.action.as_str()),
+        );
+        span.set_attribute("timestamp", event.timestamp.clone());
+        span.set_attribute("bundle", event.bundle.clone());
+        let optional = [
+            ("identity", &event.identity),
+            ("client", &event.client),
+            ("sql", &event.sql),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                span.set_attribute(key, value.clone());
+            }
+        }
+        if let Some(duration_ms) = event.duration_ms {
+            span.set_attribute("duration_ms", duration_ms.to_string());
+        }
+        if event.outcome.as_deref() == Some(OperationOutcome::Error.as_str()) {
+            span.set_outcome(OperationOutcome::Error);
+        } else {
+            span.set_outcome(OperationOutcome::Success);
+        }
+        Ok(())
+    }
+}
+
+/// The sink for an `--audit-log` target: `stdout`, `otel`, or the path of a file to append to
+pub fn audit_sink(target: &str) -> Result<Arc<dyn AuditSink>, BundlebaseError> {
+    Ok(match target {
+        "stdout" => Arc::new(StdoutAuditSink),
+        "otel" => Arc::new(OtelAuditSink),
+        path => Arc::new(FileAuditSink::open(path)?),
+    })
+}
+
+/// The user name from a basic `authorization` header, without the password.
+/// Other schemes are recorded by name only, so tokens never end up in the log.
+pub fn request_identity(metadata: &MetadataMap) -> Option<String> {
+    let header = metadata.get("authorization")?.to_str().ok()?;
+    let (scheme, credentials) = header.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return Some(format!("<{} token>", scheme.to_lowercase()));
+    }
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(credentials.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    Some(decoded.split(':').next().unwrap_or_default().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_sink_appends_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("audit.log");
+        let path = path.to_str().unwrap();
+
+        let mut event = AuditEvent::new(AuditAction::Query, "memory:///audit");
+        event.sql = Some("SELECT 1".to_string());
+        FileAuditSink::open(path).unwrap().record(&event).unwrap();
+        // Reopening appends rather than truncating
+        FileAuditSink::open(path).unwrap().record(&event).unwrap();
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(2, lines.len());
+        assert_eq!("query", lines[0]["action"]);
+        assert_eq!("SELECT 1", lines[0]["sql"]);
+        assert!(lines[0].get("identity").is_none());
+    }
+
+    #[test]
+    fn test_request_identity() {
+        let mut metadata = MetadataMap::new();
+        assert_eq!(None, request_identity(&metadata));
+
+        // "admin:password"
+        metadata.insert(
+            "authorization",
+            "Basic YWRtaW46cGFzc3dvcmQ=".parse().unwrap(),
+        );
+        assert_eq!(Some("admin".to_string()), request_identity(&metadata));
+
+        metadata.insert("authorization", "Bearer secret".parse().unwrap());
+        assert_eq!(
+            Some("<bearer token>".to_string()),
+            request_identity(&metadata)
+        );
+    }
+}
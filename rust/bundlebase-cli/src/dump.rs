@@ -0,0 +1,76 @@
+use bundlebase::bundle::{AnyOperation, BundleFacade, Operation};
+use bundlebase::io::{ObjectStoreDir, ObjectStoreFile};
+use bundlebase::{Bundle, BundlebaseError};
+use std::fmt::Write;
+use url::Url;
+
+/// Describe the bundle at `url` for debugging: the manifest YAML of the latest commit (or of
+/// `commit_id`), every operation across the `from` chain, and the files they reference with
+/// whether each exists. Only reads the manifests and checks files, no queries are planned.
+pub async fn dump_manifest(url: &str, commit_id: Option<&str>) -> Result<String, BundlebaseError> {
+    let bundle = match commit_id {
+        Some(commit_id) => Bundle::open_at(url, commit_id, None).await?,
+        None => Bundle::open(url, None).await?,
+    };
+    let history = bundle.history();
+    let commit = history
+        .last()
+        .ok_or_else(|| format!("No commits in {}", url))?;
+
+    let mut out = String::new();
+    let manifest_url = commit
+        .url
+        .as_ref()
+        .ok_or_else(|| format!("Commit {} has no manifest", commit.id))?;
+    let manifest = ObjectStoreFile::from_url(manifest_url, bundle.config())?
+        .read_str()
+        .await?
+        .ok_or_else(|| format!("Manifest {} does not exist", manifest_url))?;
+    writeln!(out, "Manifest {} ({}):", commit.id, manifest_url)?;
+    writeln!(out, "{}", manifest.trim_end())?;
+
+    writeln!(out)?;
+    writeln!(out, "Operations:")?;
+    let mut index_files: Vec<Url> = vec![];
+    for commit in &history {
+        let data_dir = commit.data_dir.as_ref().unwrap_or(bundle.url());
+        writeln!(out, "  {} in {}: {}", commit.id, data_dir, commit.message)?;
+        for op in commit.operations() {
+            writeln!(out, "    {}", op.describe())?;
+            // Index files are stored relative to the bundle that built them
+            if let AnyOperation::IndexBlocks(index) = &op {
+                let dir = ObjectStoreDir::from_url(data_dir, bundle.config())?;
+                let file = ObjectStoreFile::from_str(&index.path, &dir, bundle.config())?;
+                index_files.push(file.url().clone());
+            }
+        }
+    }
+
+    // Attached files come from the opened blocks, which resolved their sources
+    let mut referenced: Vec<Url> = vec![];
+    let attached = bundle.packs().into_iter().flat_map(|pack| pack.blocks);
+    for url in attached.filter_map(|block| block.url).chain(index_files) {
+        if !referenced.contains(&url) {
+            referenced.push(url);
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(out, "Referenced files:")?;
+    if referenced.is_empty() {
+        writeln!(out, "  (none)")?;
+    }
+    for url in referenced {
+        let status = match ObjectStoreFile::from_url(&url, bundle.config())?
+            .exists()
+            .await
+        {
+            Ok(true) => "present",
+            Ok(false) => "MISSING",
+            Err(_) => "UNREADABLE",
+        };
+        writeln!(out, "  {:<10} {}", status, url)?;
+    }
+
+    Ok(out)
+}
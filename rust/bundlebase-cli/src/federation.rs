@@ -0,0 +1,273 @@
+use crate::state::State;
+use bundlebase::bundle::{BundleFacade, DATAFRAME_ALIAS};
+use bundlebase::BundlebaseError;
+use datafusion::catalog::{MemorySchemaProvider, SchemaProvider};
+use datafusion::common::DataFusionError;
+use datafusion::execution::object_store::ObjectStoreRegistry;
+use datafusion::execution::runtime_env::RuntimeEnvBuilder;
+use datafusion::object_store::ObjectStore;
+use datafusion::prelude::{DataFrame, SessionConfig, SessionContext};
+use std::collections::HashSet;
+use std::sync::Arc;
+use url::Url;
+
+/// Several bundles served as one queryable surface.
+///
+/// Each bundle is exposed as `<name>.bundle`. When `union` is set and all schemas match,
+/// their rows are also exposed together as `bundle`.
+pub struct Federation {
+    bundles: Vec<(String, Arc<State>)>,
+    union: bool,
+}
+
+impl Federation {
+    pub fn new(bundles: Vec<(String, Arc<State>)>, union: bool) -> Result<Self, BundlebaseError> {
+        let mut names = HashSet::new();
+        for (name, _) in &bundles {
+            if !names.insert(name) {
+                return Err(format!(
+                    "More than one bundle is named '{}', bundle names must be unique",
+                    name
+                )
+                .into());
+            }
+        }
+        Ok(Self { bundles, union })
+    }
+
+    /// URLs of the served bundles, in the order they were given
+    pub fn urls(&self) -> Vec<String> {
+        self.bundles
+            .iter()
+            .map(|(_, state)| state.bundle.read().url().to_string())
+            .collect()
+    }
+
+    /// Builds a context with every bundle registered, for running a single query.
+    /// With `raw`, bundles are registered without their default views applied.
+    pub async fn context(&self, raw: bool) -> Result<SessionContext, BundlebaseError> {
+        // Clone the builders to drop the lock guards before awaiting
+        let builders: Vec<_> = self
+            .bundles
+            .iter()
+            .map(|(name, state)| (name.clone(), state.bundle.read().clone()))
+            .collect();
+
+        // Bundles register the stores they read from on their own contexts, so look stores
+        // up through all of them
+        let registry = FederatedStoreRegistry {
+            registries: builders
+                .iter()
+                .map(|(_, builder)| {
+                    builder
+                        .bundle
+                        .ctx()
+                        .runtime_env()
+                        .object_store_registry
+                        .clone()
+                })
+                .collect(),
+        };
+        let runtime = RuntimeEnvBuilder::new()
+            .with_object_store_registry(Arc::new(registry))
+            .build_arc()?;
+        let mut config = SessionConfig::new();
+        config.options_mut().sql_parser.enable_ident_normalization = false;
+        let ctx = SessionContext::new_with_config_rt(config, runtime);
+
+        let catalog_name = ctx.state().config_options().catalog.default_catalog.clone();
+        let catalog = ctx
+            .catalog(&catalog_name)
+            .ok_or_else(|| format!("Default catalog '{}' not found", catalog_name))?;
+
+        let mut union: Option<(String, DataFrame)> = None;
+        for (name, builder) in builders {
+            let df = if raw {
+                builder.bundle.raw_dataframe().await?
+            } else {
+                builder.dataframe().await?
+            };
+            let df = df.as_ref().clone();
+
+            let schema = MemorySchemaProvider::new();
+            schema.register_table(DATAFRAME_ALIAS.to_string(), df.clone().into_view())?;
+            catalog.register_schema(&name, Arc::new(schema))?;
+
+            if self.union {
+                union = Some(match union {
+                    None => (name, df),
+                    Some((first, combined)) => {
+                        combined
+                            .schema()
+                            .has_equivalent_names_and_types(df.schema())
+                            .map_err(|e| {
+                                format!(
+                                    "Cannot union bundles: the schema of '{}' does not match '{}': {}",
+                                    name, first, e
+                                )
+                            })?;
+                        (first, combined.union(df)?)
+                    }
+                });
+            }
+        }
+
+        if let Some((_, combined)) = union {
+            ctx.register_table(DATAFRAME_ALIAS, combined.into_view())?;
+        }
+
+        Ok(ctx)
+    }
+}
+
+/// Name a bundle is served under, from the last segment of its URL (e.g. `sales` for
+/// `s3://bucket/sales/`)
+pub fn bundle_name(url: &Url) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+        .unwrap_or("");
+    let stem = segment.strip_suffix(".tar").unwrap_or(segment);
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        "bundle".to_string()
+    } else {
+        name
+    }
+}
+
+/// Finds object stores in whichever of several registries has them
+#[derive(Debug)]
+struct FederatedStoreRegistry {
+    registries: Vec<Arc<dyn ObjectStoreRegistry>>,
+}
+
+impl ObjectStoreRegistry for FederatedStoreRegistry {
+    fn register_store(
+        &self,
+        url: &Url,
+        store: Arc<dyn ObjectStore>,
+    ) -> Option<Arc<dyn ObjectStore>> {
+        self.registries
+            .first()
+            .and_then(|registry| registry.register_store(url, store))
+    }
+
+    fn get_store(&self, url: &Url) -> Result<Arc<dyn ObjectStore>, DataFusionError> {
+        let mut last_error = None;
+        for registry in &self.registries {
+            match registry.get_store(url) {
+                Ok(store) => return Ok(store),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            DataFusionError::Execution(format!("No object store registered for {}", url))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bundlebase::test_utils::{random_memory_url, test_datafile};
+    use bundlebase::BundleBuilder;
+
+    async fn served(file: &str) -> Arc<State> {
+        let mut builder = BundleBuilder::create(random_memory_url().as_str(), None)
+            .await
+            .unwrap();
+        builder.attach(test_datafile(file)).await.unwrap();
+        Arc::new(State::new(builder))
+    }
+
+    async fn count(ctx: &SessionContext, sql: &str) -> usize {
+        let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        batches.iter().map(|b| b.num_rows()).sum()
+    }
+
+    #[tokio::test]
+    async fn test_query_by_name() {
+        let federation = Federation::new(
+            vec![
+                ("customers".to_string(), served("customers-0-100.csv").await),
+                ("users".to_string(), served("userdata.parquet").await),
+            ],
+            false,
+        )
+        .unwrap();
+        let ctx = federation.context(false).await.unwrap();
+
+        assert_eq!(100, count(&ctx, "SELECT * FROM customers.bundle").await);
+        assert_eq!(1000, count(&ctx, "SELECT * FROM users.bundle").await);
+        // Without --union there is no combined table
+        assert!(ctx.sql("SELECT * FROM bundle").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_union() {
+        let federation = Federation::new(
+            vec![
+                ("first".to_string(), served("customers-0-100.csv").await),
+                ("second".to_string(), served("customers-101-150.csv").await),
+            ],
+            true,
+        )
+        .unwrap();
+        let ctx = federation.context(false).await.unwrap();
+
+        assert_eq!(150, count(&ctx, "SELECT * FROM bundle").await);
+        assert_eq!(50, count(&ctx, "SELECT * FROM second.bundle").await);
+        assert_eq!(
+            150,
+            count(
+                &ctx,
+                "SELECT * FROM bundle WHERE \"Customer Id\" IS NOT NULL"
+            )
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_union_schema_mismatch() {
+        let federation = Federation::new(
+            vec![
+                ("customers".to_string(), served("customers-0-100.csv").await),
+                ("users".to_string(), served("userdata.parquet").await),
+            ],
+            true,
+        )
+        .unwrap();
+        let err = federation.context(false).await.err().unwrap();
+        assert!(err.to_string().contains("does not match"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_names() {
+        let state = served("userdata.parquet").await;
+        let result = Federation::new(
+            vec![
+                ("users".to_string(), state.clone()),
+                ("users".to_string(), state),
+            ],
+            false,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_bundle_name() {
+        for (url, expected) in [
+            ("memory:///sales", "sales"),
+            ("s3://bucket/data/sales/", "sales"),
+            ("file:///tmp/archive.tar", "archive"),
+            ("file:///tmp/sales-2024", "sales_2024"),
+            ("memory:///", "bundle"),
+        ] {
+            assert_eq!(expected, bundle_name(&Url::parse(url).unwrap()), "{}", url);
+        }
+    }
+}
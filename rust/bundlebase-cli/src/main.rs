@@ -1,12 +1,22 @@
+mod audit;
+mod dump;
+mod federation;
 mod repl;
 mod service;
 mod state;
 
+use crate::audit::{AuditAction, AuditEvent};
+use crate::federation::{bundle_name, Federation};
 use crate::service::BundlebaseFlightService;
 use crate::state::State;
 use arrow_flight::flight_service_server::FlightServiceServer;
+use bundlebase::bundle::{BundleFacade, SqlDialect};
+use bundlebase::metrics::CategoryProfile;
 use bundlebase::{Bundle, BundleBuilder, BundlebaseError};
 use clap::Parser;
+use comfy_table::{presets::UTF8_FULL, Cell, Color, Table};
+use std::io::IsTerminal;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tonic::transport::Server;
 use tracing::info;
@@ -17,17 +27,54 @@ use tracing_log::LogTracer;
 #[command(about = "Bundlebase Server", long_about = None)]
 struct Args {
     /// Path to bundle to load
+    /// Repeat to serve several bundles, queryable as <name>.bundle (name from the last path segment)
+    #[arg(long, required = true)]
+    bundle: Vec<String>,
+
+    /// When serving several bundles with matching schemas, also expose all their rows as `bundle`
     #[arg(long)]
-    bundle: String,
+    union: bool,
 
     /// Start interactive REPL mode
     #[arg(long)]
     repl: bool,
 
+    /// Run a REPL command and exit instead of starting the server. Repeat to run several in order
+    /// Results are the only output on stdout; a query prints all its rows
+    #[arg(long, conflicts_with = "repl")]
+    exec: Vec<String>,
+
     /// Create a new bundle if it doesn't exist or is empty
     #[arg(long)]
     create: bool,
 
+    /// Print the manifest of the latest commit (or the given commit id), every operation across
+    /// the `from` chain and the files they reference, then exit. Nothing is locked or queried
+    #[arg(long, conflicts_with_all = ["repl", "exec", "create"])]
+    dump_manifest: Option<Option<String>>,
+
+    /// Check the bundle's schema matches the expected one in this JSON file, then exit
+    /// Exits with an error listing the differences if it doesn't
+    #[arg(long, conflicts_with_all = ["repl", "exec", "create", "dump_manifest"])]
+    check_schema: Option<String>,
+
+    /// File to persist REPL history to (default: ~/.bundlebase_history)
+    #[arg(long)]
+    history_file: Option<PathBuf>,
+
+    /// Maximum number of commands kept in the REPL history file
+    #[arg(long, default_value_t = repl::DEFAULT_HISTORY_SIZE)]
+    history_size: usize,
+
+    /// SQL dialect for REPL commands (bundlebase, ansi)
+    /// ansi: standard SQL queries run exactly as written; bundlebase extensions still work
+    #[arg(long, default_value = "bundlebase")]
+    sql_dialect: String,
+
+    /// Format for tables printed by REPL commands (table, markdown, tsv, json)
+    #[arg(long, default_value = "table")]
+    output: String,
+
     /// Host address to bind to
     #[arg(long, default_value = "0.0.0.0")]
     host: String,
@@ -36,10 +83,37 @@ struct Args {
     #[arg(long, default_value = "50051")]
     port: u16,
 
-    /// Logging level (ui, trace, debug, info, warn, error)
-    /// ui: Minimal format (message only), INFO level - good for interactive use
-    #[arg(long, default_value = "ui")]
-    log_level: String,
+    /// Maximum number of queries the server executes at once (default: unlimited)
+    /// Queries beyond the limit are rejected with RESOURCE_EXHAUSTED
+    #[arg(long)]
+    max_concurrent_queries: Option<usize>,
+
+    /// Record who opened the served bundles and every query they ran, with timestamps
+    /// Target is a file to append JSON lines to, `stdout`, or `otel` to export them as spans
+    #[arg(long)]
+    audit_log: Option<String>,
+
+    /// Logging level (ui, trace, debug, info, warn, error). Logs always go to stderr
+    /// ui: Minimal format (message only), INFO level - the default when stderr is a terminal, info otherwise
+    #[arg(long)]
+    log_level: Option<String>,
+
+    /// Only log errors, and don't show progress bars. Same as --log-level error
+    #[arg(short, long, conflicts_with_all = ["verbose", "log_level"])]
+    quiet: bool,
+
+    /// Log debug details. Same as --log-level debug
+    #[arg(short, long, conflicts_with = "log_level")]
+    verbose: bool,
+
+    /// Print a summary of time spent per operation category on exit
+    #[arg(long)]
+    profile: bool,
+
+    /// Logging format (text, json)
+    /// json: One JSON object per line - good for log aggregators
+    #[arg(long, default_value = "text")]
+    log_format: String,
 }
 
 /// Configuration for logging
@@ -48,6 +122,25 @@ struct LogConfig {
     ui_mode: bool,
 }
 
+/// Format of emitted log lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Parse a log format string into a LogFormat
+fn parse_log_format(format_str: &str) -> Result<LogFormat, String> {
+    match format_str.to_lowercase().as_str() {
+        "text" => Ok(LogFormat::Text),
+        "json" => Ok(LogFormat::Json),
+        _ => Err(format!(
+            "unknown log format '{}', must be one of: text, json",
+            format_str
+        )),
+    }
+}
+
 /// Parse a log level string into a LogConfig
 fn parse_log_level(level_str: &str) -> Result<LogConfig, String> {
     match level_str.to_lowercase().as_str() {
@@ -88,25 +181,103 @@ async fn main() -> Result<(), BundlebaseError> {
 
     int_logging(&args);
 
+    if args.profile {
+        bundlebase::metrics::enable_profiling();
+    }
+
+    let result = run(&args).await;
+
+    if args.profile {
+        eprintln!(
+            "{}",
+            display_profile(&bundlebase::metrics::profile_summary())
+        );
+    }
+
+    result
+}
+
+async fn run(args: &Args) -> Result<(), BundlebaseError> {
     if args.repl {
         repl::print_header();
     }
 
-    let bundle = if args.create {
-        info!("Creating bundle at: {}", args.bundle);
-        Arc::new(State::new(BundleBuilder::create(&args.bundle, None).await?))
-    } else {
-        info!("Loading bundle from: {}", args.bundle);
-        Arc::new(State::new(
-            Bundle::open(&args.bundle, None)
-                .await?
-                .extend(None)?,
-        ))
-    };
+    if (args.repl || !args.exec.is_empty()) && args.bundle.len() > 1 {
+        return Err("The REPL works on a single bundle, pass only one --bundle".into());
+    }
 
-    if args.repl {
-        // REPL mode
-        repl::run(bundle).await?;
+    if let Some(commit_id) = &args.dump_manifest {
+        for url in &args.bundle {
+            print!("{}", dump::dump_manifest(url, commit_id.as_deref()).await?);
+        }
+        return Ok(());
+    }
+
+    if let Some(expected) = &args.check_schema {
+        for url in &args.bundle {
+            let bundle = Bundle::open(url, None).await?;
+            if let Err(e) = bundle.verify_schema_file(expected).await {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            println!("Schema of {} matches {}", bundle.url(), expected);
+        }
+        return Ok(());
+    }
+
+    // The Flight server is read-only, so only the REPL needs to keep other writers out
+    let lock = args.repl || !args.exec.is_empty();
+    let mut states = Vec::with_capacity(args.bundle.len());
+    for url in &args.bundle {
+        states.push(open_bundle(url, args.create, lock).await?);
+    }
+
+    let result = serve(args, &states).await;
+
+    // Release write locks so the next writer doesn't have to wait for them to go stale
+    for state in &states {
+        let mut builder = state.bundle.read().clone();
+        builder.unlock().await?;
+    }
+
+    result
+}
+
+/// Run the REPL or Flight server over the opened bundles until the user exits
+async fn serve(args: &Args, states: &[Arc<State>]) -> Result<(), BundlebaseError> {
+    let bundle = states[0].clone();
+
+    if args.repl || !args.exec.is_empty() {
+        let dialect: SqlDialect = args.sql_dialect.parse().unwrap_or_else(|e| {
+            eprintln!("Invalid SQL dialect '{}': {}", args.sql_dialect, e);
+            std::process::exit(1);
+        });
+        let output: repl::OutputFormat = args.output.parse().unwrap_or_else(|e| {
+            eprintln!("Invalid output format '{}': {}", args.output, e);
+            std::process::exit(1);
+        });
+        let renderer = output.renderer();
+        if !args.quiet {
+            repl::install_progress_tracker();
+        }
+
+        if args.repl {
+            // REPL mode
+            let history_file = args
+                .history_file
+                .clone()
+                .unwrap_or_else(repl::default_history_file);
+            repl::run(
+                bundle,
+                history_file,
+                args.history_size,
+                dialect,
+                renderer.as_ref(),
+            )
+            .await?;
+        } else {
+            repl::exec(bundle, &args.exec, dialect, renderer.as_ref()).await?;
+        }
     } else {
         // Flight server mode
         let addr = format!("{}:{}", args.host, args.port).parse()?;
@@ -114,12 +285,43 @@ async fn main() -> Result<(), BundlebaseError> {
         info!("Starting Arrow Flight SQL server on {}", addr);
 
         // Create Flight SQL service
-        let flight_service = BundlebaseFlightService::new(bundle);
+        let mut flight_service = BundlebaseFlightService::new(bundle);
+        if states.len() > 1 {
+            let named = states
+                .iter()
+                .map(|state| (bundle_name(state.bundle.read().url()), state.clone()))
+                .collect::<Vec<_>>();
+            let names = named
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let federation = Federation::new(named, args.union)?;
+            // Fail at startup rather than on every query if the bundles can't be combined
+            federation.context(false).await?;
+            info!("Serving bundles: {}", names);
+            flight_service = flight_service.with_federation(federation);
+        }
+        if let Some(target) = &args.audit_log {
+            let sink = audit::audit_sink(target)?;
+            for state in states {
+                let event = AuditEvent::new(AuditAction::Open, state.bundle.read().url().as_str());
+                sink.record(&event)?;
+            }
+            info!("Recording audit log to {}", target);
+            flight_service = flight_service.with_audit(sink);
+        }
+        if let Some(max) = args.max_concurrent_queries {
+            info!("Limiting to {} concurrent queries", max);
+            flight_service = flight_service.with_max_concurrent_queries(max);
+        }
 
-        // Start server
+        // Start server, stopping cleanly on Ctrl-C so exit reporting (e.g. --profile) still runs
         let server = Server::builder()
             .add_service(FlightServiceServer::new(flight_service))
-            .serve(addr);
+            .serve_with_shutdown(addr, async {
+                let _ = tokio::signal::ctrl_c().await;
+            });
 
         info!("Server listening on {}", addr);
         server.await?;
@@ -128,10 +330,71 @@ async fn main() -> Result<(), BundlebaseError> {
     Ok(())
 }
 
+/// Open (or with `create`, create) the bundle at `url`, with `lock` locked against other writers
+async fn open_bundle(url: &str, create: bool, lock: bool) -> Result<Arc<State>, BundlebaseError> {
+    let mut builder = if create {
+        info!("Creating bundle at: {}", url);
+        BundleBuilder::create(url, None).await?
+    } else {
+        info!("Loading bundle from: {}", url);
+        Bundle::open(url, None).await?.extend(None)?
+    };
+    if lock {
+        builder.lock().await?;
+    }
+    Ok(Arc::new(State::new(builder)))
+}
+
+/// Format collected operation timings as a table, slowest category first
+fn display_profile(summary: &[CategoryProfile]) -> String {
+    if summary.is_empty() {
+        return "No operations were timed".to_string();
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        Cell::new("Category").fg(Color::Cyan),
+        Cell::new("Count").fg(Color::Cyan),
+        Cell::new("Total (ms)").fg(Color::Cyan),
+        Cell::new("Max (ms)").fg(Color::Cyan),
+    ]);
+    for entry in summary {
+        table.add_row(vec![
+            Cell::new(entry.category),
+            Cell::new(entry.count),
+            Cell::new(format!("{:.1}", entry.total_ms)),
+            Cell::new(format!("{:.1}", entry.max_ms)),
+        ]);
+    }
+    table.to_string()
+}
+
+/// The log level to use: from --quiet or --verbose if given, else --log-level, else
+/// "ui" for a person at a terminal and "info" when stderr is redirected
+fn effective_log_level(args: &Args, stderr_is_terminal: bool) -> String {
+    if args.quiet {
+        "error".to_string()
+    } else if args.verbose {
+        "debug".to_string()
+    } else if let Some(level) = &args.log_level {
+        level.clone()
+    } else if stderr_is_terminal {
+        "ui".to_string()
+    } else {
+        "info".to_string()
+    }
+}
+
 fn int_logging(args: &Args) {
-    // Parse log level from CLI argument
-    let log_config = parse_log_level(&args.log_level).unwrap_or_else(|e| {
-        eprintln!("Invalid log level '{}': {}", args.log_level, e);
+    // Parse log level from CLI arguments
+    let log_level = effective_log_level(args, std::io::stderr().is_terminal());
+    let log_config = parse_log_level(&log_level).unwrap_or_else(|e| {
+        eprintln!("Invalid log level '{}': {}", log_level, e);
+        std::process::exit(1);
+    });
+    let log_format = parse_log_format(&args.log_format).unwrap_or_else(|e| {
+        eprintln!("Invalid log format '{}': {}", args.log_format, e);
         std::process::exit(1);
     });
 
@@ -140,7 +403,13 @@ fn int_logging(args: &Args) {
     let _ = LogTracer::init();
 
     // Initialize tracing/logging with the configured level
-    if log_config.ui_mode {
+    if log_format == LogFormat::Json {
+        // JSON mode: one object per line with event fields at the top level
+        let _ = tracing::subscriber::set_global_default(json_subscriber(
+            log_config.level,
+            std::io::stderr,
+        ));
+    } else if log_config.ui_mode {
         // UI mode: minimal format (message only)
         let _ = tracing_subscriber::fmt()
             .with_max_level(log_config.level)
@@ -162,10 +431,102 @@ fn int_logging(args: &Args) {
     }
 }
 
+/// Build a subscriber that writes each event as a single JSON line.
+///
+/// Event fields (e.g. `operation`, `outcome`, `duration_ms`) are flattened into the top-level object.
+fn json_subscriber<W>(level: tracing::Level, writer: W) -> impl tracing::Subscriber + Send + Sync
+where
+    W: for<'a> tracing_subscriber::fmt::MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .flatten_event(true)
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_max_level(level)
+        .with_writer(writer)
+        .finish()
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use bundlebase::bundle::BundleFacade;
     use bundlebase::{Bundle, BundleBuilder};
+    use parking_lot::Mutex;
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!(LogFormat::Text, parse_log_format("text").unwrap());
+        assert_eq!(LogFormat::Json, parse_log_format("JSON").unwrap());
+        assert!(parse_log_format("xml").is_err());
+    }
+
+    #[test]
+    fn test_effective_log_level() {
+        let parse = |extra: &[&str]| {
+            Args::try_parse_from([&["bundlebase-cli", "--bundle", "memory:///b"], extra].concat())
+        };
+
+        let args = parse(&[]).unwrap();
+        assert_eq!("ui", effective_log_level(&args, true));
+        assert_eq!("info", effective_log_level(&args, false));
+
+        let args = parse(&["--log-level", "warn"]).unwrap();
+        assert_eq!("warn", effective_log_level(&args, true));
+        assert_eq!("error", effective_log_level(&parse(&["-q"]).unwrap(), true));
+        assert_eq!(
+            "debug",
+            effective_log_level(&parse(&["--verbose"]).unwrap(), false)
+        );
+
+        assert!(parse(&["--quiet", "--verbose"]).is_err());
+        assert!(parse(&["--quiet", "--log-level", "info"]).is_err());
+        assert!(parse(&["--repl", "--exec", "COUNT"]).is_err());
+    }
+
+    #[test]
+    fn test_json_log_lines_are_parseable() {
+        #[derive(Clone, Default)]
+        struct BufferWriter(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for BufferWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = BufferWriter::default();
+        let writer = buffer.clone();
+        let subscriber = json_subscriber(tracing::Level::INFO, move || writer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(
+                operation = "select",
+                outcome = "success",
+                duration_ms = 12u64,
+                "Query complete"
+            );
+            tracing::debug!("Filtered out by level");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(1, lines.len());
+
+        let json: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!("INFO", json["level"]);
+        assert_eq!("Query complete", json["message"]);
+        assert_eq!("select", json["operation"]);
+        assert_eq!("success", json["outcome"]);
+        assert_eq!(12, json["duration_ms"]);
+        assert!(json["timestamp"].is_string());
+    }
 
     #[tokio::test]
     async fn test_create_bundle_with_memory_url() {
@@ -232,6 +593,15 @@ mod tests {
 
         // Empty bundle should have no fields
         assert_eq!(schema.fields().len(), 0);
+
+        // And its dataframe is valid, with no rows
+        let df = builder
+            .bundle
+            .dataframe()
+            .await
+            .expect("Failed to get dataframe");
+        let batches = df.as_ref().clone().collect().await.unwrap();
+        assert_eq!(0, batches.iter().map(|b| b.num_rows()).sum::<usize>());
     }
 
     #[tokio::test]
@@ -275,4 +645,38 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_profile_attach_and_index() {
+        bundlebase::metrics::enable_profiling();
+
+        let mut builder = BundleBuilder::create("memory:///profile_test", None)
+            .await
+            .unwrap();
+        builder
+            .attach(bundlebase::test_utils::test_datafile("userdata.parquet"))
+            .await
+            .unwrap();
+        builder.index("id").await.unwrap();
+
+        let summary = bundlebase::metrics::profile_summary();
+        for category in ["attach", "index"] {
+            let entry = summary
+                .iter()
+                .find(|p| p.category == category)
+                .unwrap_or_else(|| panic!("No timings for {}", category));
+            assert!(entry.count > 0);
+            assert!(entry.total_ms > 0.0, "No time recorded for {}", category);
+        }
+
+        let table = display_profile(&summary);
+        assert!(table.contains("attach"), "{}", table);
+        assert!(table.contains("index"), "{}", table);
+        assert!(table.contains("Total (ms)"), "{}", table);
+    }
+
+    #[test]
+    fn test_display_profile_empty() {
+        assert_eq!("No operations were timed", display_profile(&[]));
+    }
 }
@@ -1,19 +1,25 @@
 mod commands;
 mod completion;
 mod display;
+mod history;
 mod progress_impl;
+mod render;
 
 use crate::state::State;
-use bundlebase::bundle::BundleFacade;
+use bundlebase::bundle::{BundleCommand, BundleFacade, SqlDialect};
 use bundlebase::BundlebaseError;
 use commands::{Command, ExecuteResult};
 use completion::BundleCompleter;
+use history::ReplHistory;
+pub use history::{default_history_file, DEFAULT_HISTORY_SIZE};
 use reedline::{
-    default_emacs_keybindings, DefaultPrompt, DefaultPromptSegment, Emacs, FileBackedHistory,
-    Reedline, Signal,
+    default_emacs_keybindings, DefaultPrompt, DefaultPromptSegment, Emacs, Reedline, Signal,
 };
+pub use render::OutputFormat;
+use render::OutputRenderer;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub fn print_header() {
     info!("Bundlebase REPL");
@@ -21,16 +27,70 @@ pub fn print_header() {
     info!("----------------------------------------------------------");
 }
 
-pub async fn run(state: Arc<State>) -> Result<(), BundlebaseError> {
-    // Install progress tracker for REPL
+/// Show progress bars for long-running operations.
+/// They are drawn on stderr, and only when it is a terminal.
+pub fn install_progress_tracker() {
     let tracker = Box::new(progress_impl::IndicatifTracker::new());
     bundlebase::progress::set_tracker(tracker);
+}
+
+/// Runs each of `inputs` as if typed into the REPL, stopping at the first error.
+///
+/// Results go to stdout and nothing else does, so the output can be redirected to a file.
+/// A query prints all its result rows, leaving the bundle unchanged.
+pub async fn exec(
+    state: Arc<State>,
+    inputs: &[String],
+    dialect: SqlDialect,
+    renderer: &dyn OutputRenderer,
+) -> Result<(), BundlebaseError> {
+    for input in inputs {
+        let cmd = commands::parse(input, dialect)
+            .map_err(|e| format!("Error parsing command '{}': {}", input, e))?;
+        match cmd {
+            Command::Exit => break,
+            Command::Sql(BundleCommand::Select { sql, params }) => {
+                let bundle = state.bundle.read().clone();
+                let query = bundle.select(&sql, params).await?;
+                let df = query.dataframe().await?;
+                println!("{}", display::display_all_rows(&df, renderer).await?);
+            }
+            cmd => print_result(commands::execute(cmd, &state, renderer).await?),
+        }
+    }
+    Ok(())
+}
+
+/// Print a command's result to stdout
+fn print_result(result: ExecuteResult) {
+    match result {
+        ExecuteResult::Message(msg) => println!("{}", msg),
+        ExecuteResult::Table(table) => println!("{}", table),
+        ExecuteResult::List(items) => items.iter().for_each(|item| println!("- {}", item)),
+        ExecuteResult::None => {}
+    }
+}
 
-    // Setup history
-    let history = Box::new(
-        FileBackedHistory::with_file(1000, "repl-history.txt".into())
-            .unwrap_or_else(|_| FileBackedHistory::default()),
-    );
+pub async fn run(
+    state: Arc<State>,
+    history_file: PathBuf,
+    history_size: usize,
+    dialect: SqlDialect,
+    renderer: &dyn OutputRenderer,
+) -> Result<(), BundlebaseError> {
+    // Setup history, persisted across sessions. Ctrl-R searches it.
+    let history = match ReplHistory::with_file(history_size, history_file.clone()) {
+        Ok(history) => history,
+        Err(e) => {
+            warn!(
+                "Cannot use history file {}: {}. History will not be saved",
+                history_file.display(),
+                e
+            );
+            ReplHistory::in_memory(history_size)?
+        }
+    };
+    let history = Box::new(history);
 
     // Setup completer
     let completer = Box::new(BundleCompleter::new(state.clone()));
@@ -58,7 +118,7 @@ pub async fn run(state: Arc<State>) -> Result<(), BundlebaseError> {
                 }
 
                 // Parse command
-                let cmd = match commands::parse(input) {
+                let cmd = match commands::parse(input, dialect) {
                     Ok(cmd) => cmd,
                     Err(e) => {
                         error!("Error parsing command: {}", e);
@@ -73,15 +133,8 @@ pub async fn run(state: Arc<State>) -> Result<(), BundlebaseError> {
                 }
 
                 // Execute command
-                match commands::execute(cmd, &state).await {
-                    Ok(result) => match result {
-                        ExecuteResult::Message(msg) => println!("{}", msg),
-                        ExecuteResult::Table(table) => println!("{}", table),
-                        ExecuteResult::List(items) => {
-                            items.iter().for_each(|item| println!("- {}", item))
-                        }
-                        ExecuteResult::None => {}
-                    },
+                match commands::execute(cmd, &state, renderer).await {
+                    Ok(result) => print_result(result),
                     Err(e) => {
                         error!("Error executing command: {}", e);
                     }
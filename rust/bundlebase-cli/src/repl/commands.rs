@@ -1,7 +1,10 @@
+use crate::repl::render::OutputRenderer;
 use crate::state::State;
 use bundlebase::bundle::BundleCommand;
+use bundlebase::io::ObjectStoreDir;
+use bundlebase::metrics::{OperationCategory, OperationOutcome, OperationTimer};
 use bundlebase::{
-    bundle::{parse_command, BundleFacade},
+    bundle::{parse_command_with_dialect, BundleFacade, SqlDialect},
     BundlebaseError,
 };
 use std::fmt::Display;
@@ -17,8 +20,11 @@ pub enum Command {
     Schema,
     Count,
     Explain,
+    TimeIt { sql: String },
     History,
     Status,
+    Validate,
+    Ls { path: Option<String> },
 
     // Meta commands
     Help,
@@ -33,8 +39,8 @@ pub enum ExecuteResult {
     None,
 }
 
-/// Parse input string into Command using SQL syntax
-pub fn parse(input: &str) -> Result<Command, String> {
+/// Parse input string into Command using SQL syntax in the given dialect
+pub fn parse(input: &str, dialect: SqlDialect) -> Result<Command, String> {
     let input = input.trim();
     if input.is_empty() {
         return Err("Empty command".to_string());
@@ -59,6 +65,24 @@ pub fn parse(input: &str) -> Result<Command, String> {
         return Ok(Command::History);
     } else if upper == "STATUS" {
         return Ok(Command::Status);
+    } else if upper == "VALIDATE" {
+        return Ok(Command::Validate);
+    } else if upper == "TIMEIT" || upper.starts_with("TIMEIT ") {
+        // Parse: TIMEIT <sql>
+        let sql = input[6..].trim().to_string();
+        if sql.is_empty() {
+            return Err("TIMEIT requires a query".to_string());
+        }
+        return Ok(Command::TimeIt { sql });
+    } else if upper == "LS" || upper.starts_with("LS ") {
+        // Parse: LS ['<path>']
+        let path = input[2..]
+            .trim()
+            .trim_matches(|c| c == '\'' || c == '"')
+            .to_string();
+        return Ok(Command::Ls {
+            path: (!path.is_empty()).then_some(path),
+        });
     } else if upper.starts_with("SHOW") {
         // Parse: SHOW [LIMIT <n>]
         let limit = if let Some(limit_str) = upper
@@ -92,13 +116,18 @@ pub fn parse(input: &str) -> Result<Command, String> {
     }
 
     // Everything else is SQL - parse and wrap
-    let sql_cmd = parse_command(input).map_err(|e| format!("Invalid SQL: {}", e))?;
+    let sql_cmd =
+        parse_command_with_dialect(input, dialect).map_err(|e| format!("Invalid SQL: {}", e))?;
 
     Ok(Command::Sql(sql_cmd))
 }
 
 /// Execute a command
-pub async fn execute(cmd: Command, state: &Arc<State>) -> Result<ExecuteResult, BundlebaseError> {
+pub async fn execute(
+    cmd: Command,
+    state: &Arc<State>,
+    renderer: &dyn OutputRenderer,
+) -> Result<ExecuteResult, BundlebaseError> {
     use crate::repl::display;
 
     match cmd {
@@ -111,12 +140,12 @@ pub async fn execute(cmd: Command, state: &Arc<State>) -> Result<ExecuteResult,
         // REPL-only commands
         Command::Show { limit } => {
             let df = state.bundle.read().dataframe().await?;
-            let table = display::display_dataframe(&df, limit).await?;
+            let table = display::display_dataframe(&df, limit, renderer).await?;
             Ok(ExecuteResult::Table(table))
         }
         Command::Schema => {
             let schema = state.bundle.read().schema().await?;
-            let table = display::display_schema(schema);
+            let table = display::display_schema(schema, renderer);
             Ok(ExecuteResult::Table(table))
         }
         Command::Count => {
@@ -127,49 +156,122 @@ pub async fn execute(cmd: Command, state: &Arc<State>) -> Result<ExecuteResult,
             let plan = state.bundle.read().bundle.explain().await?;
             Ok(ExecuteResult::Message(plan))
         }
+        Command::TimeIt { sql } => {
+            // Runs against the current data like SHOW, so no operation is added
+            let bundle = state.bundle.read().bundle.clone();
+            let timer =
+                OperationTimer::start(OperationCategory::Select, "timeit").with_label("sql", &sql);
+            let result = bundle.sql(&sql, vec![]).await;
+            let elapsed = timer.elapsed();
+            timer.finish(if result.is_ok() {
+                OperationOutcome::Success
+            } else {
+                OperationOutcome::Error
+            });
+            let rows: usize = result?.iter().map(|batch| batch.num_rows()).sum();
+            Ok(ExecuteResult::Message(format!(
+                "{} rows in {:.3} ms",
+                rows,
+                elapsed.as_secs_f64() * 1000.0
+            )))
+        }
         Command::History => {
             let commits = state.bundle.read().history();
-            let table = display::display_history(commits);
+            let table = display::display_history(commits, renderer);
             Ok(ExecuteResult::Table(table))
         }
         Command::Status => {
-            let guard = state.bundle.read();
-            let status = guard.status();
-            Ok(ExecuteResult::Message(status.to_string()))
+            let (status, bundle) = {
+                let guard = state.bundle.read();
+                (guard.status().to_string(), guard.bundle.clone())
+            };
+            let warnings = bundle.validate().await?;
+            let mut message = status;
+            for warning in warnings {
+                message.push_str(&format!("\nWarning: {}", warning));
+            }
+            Ok(ExecuteResult::Message(message))
+        }
+        Command::Validate => {
+            let bundle = state.bundle.read().bundle.clone();
+            let warnings = bundle.validate().await?;
+            if warnings.is_empty() {
+                Ok(ExecuteResult::Message("No problems found".to_string()))
+            } else {
+                Ok(ExecuteResult::List(
+                    warnings
+                        .into_iter()
+                        .map(|warning| Box::new(warning) as Box<dyn Display>)
+                        .collect(),
+                ))
+            }
+        }
+        Command::Ls { path } => {
+            // Paths resolve like ATTACH: URLs as-is, anything else relative to the bundle
+            let dir = {
+                let guard = state.bundle.read();
+                match &path {
+                    Some(path) if path.contains(':') => {
+                        ObjectStoreDir::from_str(path, guard.bundle.config())?
+                    }
+                    Some(path) => guard.data_dir().subdir(path)?,
+                    None => guard.data_dir().clone(),
+                }
+            };
+            let (dirs, files) = dir.list_level().await?;
+            if dirs.is_empty() && files.is_empty() {
+                return Ok(ExecuteResult::Message(format!("No files in {}", dir)));
+            }
+            let mut items: Vec<Box<dyn Display>> = Vec::new();
+            for sub in dirs {
+                items.push(Box::new(format!("{}/", display_name(sub.url().as_str()))));
+            }
+            for file in files {
+                items.push(Box::new(display_name(file.url().as_str())));
+            }
+            Ok(ExecuteResult::List(items))
         }
         Command::Help => {
             let help_text = r#"
 Bundlebase REPL - SQL Interface
 
 Data Operations:
-  ATTACH '<path>'                      Attach data source
+  ATTACH '<path>'                    * Attach data source
   SHOW [LIMIT <n>]                     Display rows (default: 10)
 
 Query & Transform:
   SELECT col1, col2, ... FROM bundle     Select columns (supports full SQL)
-  FILTER WHERE <condition>             Filter rows by condition
+  FILTER WHERE <condition>           * Filter rows by condition
+  DELETE FROM bundle WHERE <condition>   Delete rows matching condition
   ALTER TABLE bundle DROP COLUMN <col>   Remove column
   ALTER TABLE bundle RENAME COLUMN <old> TO <new>  Rename column
 
 Join Data:
-  [LEFT|RIGHT|FULL|INNER] JOIN AS <name> ON <expression>
+  [LEFT|RIGHT|FULL|INNER] JOIN AS <name> ON <expression>  *
     Example: LEFT JOIN AS users ON bundle.user_id = users.id
 
 Indexing:
   CREATE INDEX ON bundle(<column>)       Create index on column
-  REINDEX                              Rebuild all indexes
+  DROP INDEX <column>                    Remove index on column
+  REINDEX                            * Rebuild all indexes
 
 Persistence:
   COMMIT '<message>'                   Commit changes with message
   RESET                                Discard all uncommitted changes
   UNDO                                 Undo the last operation
 
+Commands marked * are bundlebase extensions to SQL. They are available in both
+--sql-dialect modes; with --sql-dialect ansi, queries run exactly as written.
+
 Schema & Info:
   SCHEMA                               Show table schema
   COUNT                                Show row count
   EXPLAIN                              Show query plan
+  TIMEIT <query>                       Run a query, showing its time and row count
   HISTORY                              Show commit history
+  LS ['<path>']                        List directories and files one level down
   STATUS                               Show uncommitted changes
+  VALIDATE                             Warn about likely mistakes, such as duplicate files
 
 Meta Commands:
   HELP                                 Show this help
@@ -194,6 +296,12 @@ Examples:
     }
 }
 
+/// The last path segment of a listed URL
+fn display_name(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    url.rsplit('/').next().unwrap_or(url).to_string()
+}
+
 /// Get SQL command suggestions (for tab completion)
 pub fn get_parameter_names(_command_name: &str) -> Vec<String> {
     // With SQL syntax, we don't need parameter completion
@@ -204,19 +312,38 @@ pub fn get_parameter_names(_command_name: &str) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::repl::render::TableRenderer;
 
     #[test]
     fn test_parse_attach() {
-        let cmd = parse("ATTACH 'data.parquet'").unwrap();
+        let cmd = parse("ATTACH 'data.parquet'", SqlDialect::Bundlebase).unwrap();
         match cmd {
             Command::Sql(BundleCommand::Attach { path }) => assert_eq!(path, "data.parquet"),
             _ => panic!("Expected Sql(Attach) command"),
         }
     }
 
+    #[test]
+    fn test_parse_ls() {
+        assert!(matches!(
+            parse("ls", SqlDialect::Bundlebase).unwrap(),
+            Command::Ls { path: None }
+        ));
+        match parse("LS 'data/2024'", SqlDialect::Bundlebase).unwrap() {
+            Command::Ls { path } => assert_eq!(Some("data/2024".to_string()), path),
+            _ => panic!("Expected Ls command"),
+        }
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!("b.csv", display_name("memory:///data/sub/b.csv"));
+        assert_eq!("sub", display_name("file:///data.tar/sub/"));
+    }
+
     #[test]
     fn test_parse_filter() {
-        let cmd = parse("FILTER WHERE country = 'USA'").unwrap();
+        let cmd = parse("FILTER WHERE country = 'USA'", SqlDialect::Bundlebase).unwrap();
         match cmd {
             Command::Sql(BundleCommand::Filter { where_clause, .. }) => {
                 assert_eq!(where_clause, "country = 'USA'")
@@ -225,17 +352,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_ansi_dialect() {
+        let sql = "SELECT name, email FROM bundle WHERE age > 21";
+        match parse(sql, SqlDialect::Ansi).unwrap() {
+            Command::Sql(BundleCommand::Select { sql: parsed, .. }) => assert_eq!(sql, parsed),
+            _ => panic!("Expected Sql(Select) command"),
+        }
+
+        // Bundlebase verbs and REPL commands are still recognized
+        assert!(matches!(
+            parse("FILTER WHERE country = 'USA'", SqlDialect::Ansi).unwrap(),
+            Command::Sql(BundleCommand::Filter { .. })
+        ));
+        assert!(matches!(
+            parse("REINDEX", SqlDialect::Ansi).unwrap(),
+            Command::Sql(BundleCommand::Reindex)
+        ));
+        assert!(matches!(
+            parse("SCHEMA", SqlDialect::Ansi).unwrap(),
+            Command::Schema
+        ));
+    }
+
     #[test]
     fn test_parse_meta_commands() {
-        assert!(matches!(parse("HELP").unwrap(), Command::Help));
-        assert!(matches!(parse("EXIT").unwrap(), Command::Exit));
-        assert!(matches!(parse("SCHEMA").unwrap(), Command::Schema));
-        assert!(matches!(parse("COUNT").unwrap(), Command::Count));
+        assert!(matches!(
+            parse("HELP", SqlDialect::Bundlebase).unwrap(),
+            Command::Help
+        ));
+        assert!(matches!(
+            parse("EXIT", SqlDialect::Bundlebase).unwrap(),
+            Command::Exit
+        ));
+        assert!(matches!(
+            parse("SCHEMA", SqlDialect::Bundlebase).unwrap(),
+            Command::Schema
+        ));
+        assert!(matches!(
+            parse("COUNT", SqlDialect::Bundlebase).unwrap(),
+            Command::Count
+        ));
+    }
+
+    #[test]
+    fn test_parse_timeit() {
+        match parse("timeit SELECT * FROM bundle", SqlDialect::Bundlebase).unwrap() {
+            Command::TimeIt { sql } => assert_eq!("SELECT * FROM bundle", sql),
+            _ => panic!("Expected TimeIt command"),
+        }
+        assert!(parse("TIMEIT", SqlDialect::Bundlebase).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_timeit() {
+        let url = bundlebase::test_utils::random_memory_url();
+        let mut builder = bundlebase::BundleBuilder::create(url.as_str(), None)
+            .await
+            .unwrap();
+        builder
+            .attach(bundlebase::test_utils::test_datafile("customers-0-100.csv"))
+            .await
+            .unwrap();
+        let state = Arc::new(State::new(builder));
+        let operations = state.bundle.read().bundle.operations().len();
+
+        let cmd = parse(
+            "TIMEIT SELECT * FROM bundle WHERE \"Index\" <= 25",
+            SqlDialect::Bundlebase,
+        )
+        .unwrap();
+        match execute(cmd, &state, &TableRenderer).await.unwrap() {
+            ExecuteResult::Message(msg) => {
+                let ms = msg
+                    .strip_prefix("25 rows in ")
+                    .and_then(|rest| rest.strip_suffix(" ms"))
+                    .unwrap_or_else(|| panic!("Unexpected message: {}", msg));
+                assert!(ms.parse::<f64>().unwrap() > 0.0, "{}", msg);
+            }
+            _ => panic!("Expected a message"),
+        }
+        assert_eq!(operations, state.bundle.read().bundle.operations().len());
     }
 
     #[test]
     fn test_parse_commit() {
-        let cmd = parse("COMMIT 'my commit message'").unwrap();
+        let cmd = parse("COMMIT 'my commit message'", SqlDialect::Bundlebase).unwrap();
         match cmd {
             Command::Sql(BundleCommand::Commit { message }) => {
                 assert_eq!(message, "my commit message")
@@ -246,7 +448,7 @@ mod tests {
 
     #[test]
     fn test_parse_show() {
-        let cmd = parse("SHOW LIMIT 20").unwrap();
+        let cmd = parse("SHOW LIMIT 20", SqlDialect::Bundlebase).unwrap();
         match cmd {
             Command::Show { limit } => assert_eq!(limit, Some(20)),
             _ => panic!("Expected Show command"),
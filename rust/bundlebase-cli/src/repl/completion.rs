@@ -22,7 +22,10 @@ impl BundleCompleter {
             "schema".to_string(),
             "count".to_string(),
             "explain".to_string(),
+            "timeit".to_string(),
             "history".to_string(),
+            "validate".to_string(),
+            "ls".to_string(),
             "index".to_string(),
             "drop-index".to_string(),
             "reindex".to_string(),
@@ -1,6 +1,8 @@
+use super::render::OutputRenderer;
 use arrow_schema::SchemaRef;
-use bundlebase::{bundle::BundleCommit, AnyOperation, BundlebaseError, Operation};
-use comfy_table::{presets::UTF8_FULL, Cell, Color, ContentArrangement, Table};
+use bundlebase::bundle::{BundleCommit, KEY_FIELD_METADATA};
+use bundlebase::{AnyOperation, BundlebaseError, Operation};
+use comfy_table::{presets::UTF8_FULL, Cell, ContentArrangement, Table};
 use datafusion::prelude::DataFrame;
 use futures::StreamExt;
 use std::sync::Arc;
@@ -9,90 +11,98 @@ use std::sync::Arc;
 pub async fn display_dataframe(
     df: &Arc<DataFrame>,
     limit: Option<usize>,
+    renderer: &dyn OutputRenderer,
 ) -> Result<String, BundlebaseError> {
-    let limit = limit.unwrap_or(10);
-    let mut table = Table::new();
-    table.load_preset(UTF8_FULL);
-    table.set_content_arrangement(ContentArrangement::Dynamic);
+    display_rows(df, Some(limit.unwrap_or(10)), renderer).await
+}
 
-    let stream: datafusion::execution::SendableRecordBatchStream =
-        df.as_ref().clone().execute_stream().await?;
-    let mut row_count = 0;
+/// Display every row of a DataFrame as a formatted table
+pub async fn display_all_rows(
+    df: &Arc<DataFrame>,
+    renderer: &dyn OutputRenderer,
+) -> Result<String, BundlebaseError> {
+    display_rows(df, None, renderer).await
+}
+
+async fn display_rows(
+    df: &Arc<DataFrame>,
+    limit: Option<usize>,
+    renderer: &dyn OutputRenderer,
+) -> Result<String, BundlebaseError> {
+    let header: Vec<String> = df
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect();
+
+    // Limit the plan itself so the scan stops early, fetching one extra row to tell if there are more
+    let mut df = df.as_ref().clone();
+    if let Some(limit) = limit {
+        df = df.limit(0, Some(limit + 1))?;
+    }
+    let stream: datafusion::execution::SendableRecordBatchStream = df.execute_stream().await?;
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut has_more = false;
 
     futures::pin_mut!(stream);
 
     while let Some(batch) = stream.next().await {
         let batch = batch?;
 
-        // Add header on first batch
-        if row_count == 0 {
-            let header: Vec<Cell> = batch
-                .schema()
-                .fields()
-                .iter()
-                .map(|f| Cell::new(f.name()).fg(Color::Cyan))
-                .collect();
-            table.set_header(header);
-        }
-
-        // Add rows
         for row_idx in 0..batch.num_rows() {
-            if row_count >= limit {
+            if limit.is_some_and(|limit| rows.len() >= limit) {
+                has_more = true;
                 break;
             }
 
-            let row: Vec<Cell> = (0..batch.num_columns())
-                .map(|col_idx| {
-                    let column = batch.column(col_idx);
-                    let value = format_array_value(column, row_idx);
-                    Cell::new(value)
-                })
-                .collect();
-
-            table.add_row(row);
-            row_count += 1;
+            rows.push(
+                (0..batch.num_columns())
+                    .map(|col_idx| format_array_value(batch.column(col_idx), row_idx))
+                    .collect(),
+            );
         }
 
-        if row_count >= limit {
+        if has_more {
             break;
         }
     }
 
-    if row_count == 0 {
-        Ok("No rows to display".to_string())
+    if rows.is_empty() {
+        Ok(renderer.render_empty(&header, "No rows to display"))
     } else {
-        let mut output = table.to_string();
-        if row_count >= limit {
-            output.push_str(&format!("\n(Showing first {} rows)", limit));
+        let mut output = renderer.render(&header, &rows);
+        if has_more && renderer.allows_notes() {
+            output.push_str(&format!("\n(Showing first {} rows)", rows.len()));
         }
         Ok(output)
     }
 }
 
 /// Display schema as a formatted table
-pub fn display_schema(schema: SchemaRef) -> String {
-    let mut table = Table::new();
-    table.load_preset(UTF8_FULL);
-    table.set_content_arrangement(ContentArrangement::Dynamic);
-
-    table.set_header(vec![
-        Cell::new("Column").fg(Color::Cyan),
-        Cell::new("Type").fg(Color::Cyan),
-        Cell::new("Nullable").fg(Color::Cyan),
-    ]);
-
-    for field in schema.fields() {
-        table.add_row(vec![
-            Cell::new(field.name()),
-            Cell::new(field.data_type().to_string()),
-            Cell::new(if field.is_nullable() { "Yes" } else { "No" }),
-        ]);
-    }
-
-    if schema.fields().is_empty() {
-        "No columns in schema".to_string()
+pub fn display_schema(schema: SchemaRef, renderer: &dyn OutputRenderer) -> String {
+    let header = strings(&["Column", "Type", "Nullable", "Key"]);
+    let rows: Vec<Vec<String>> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            vec![
+                field.name().clone(),
+                field.data_type().to_string(),
+                if field.is_nullable() { "Yes" } else { "No" }.to_string(),
+                field
+                    .metadata()
+                    .get(KEY_FIELD_METADATA)
+                    .cloned()
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    if rows.is_empty() {
+        renderer.render_empty(&header, "No columns in schema")
     } else {
-        table.to_string()
+        renderer.render(&header, &rows)
     }
 }
 
@@ -113,32 +123,24 @@ pub fn display_status(new_ops: Vec<AnyOperation>) -> String {
 }
 
 /// Display commit history as a formatted table
-pub fn display_history(commits: Vec<BundleCommit>) -> String {
-    let mut table = Table::new();
-    table.load_preset(UTF8_FULL);
-    table.set_content_arrangement(ContentArrangement::Dynamic);
-
-    table.set_header(vec![
-        Cell::new("Timestamp").fg(Color::Cyan),
-        Cell::new("Author").fg(Color::Cyan),
-        Cell::new("Message").fg(Color::Cyan),
-    ]);
-
-    for commit in commits {
-        table.add_row(vec![
-            Cell::new(&commit.timestamp),
-            Cell::new(&commit.author),
-            Cell::new(&commit.message),
-        ]);
-    }
-
-    if table.is_empty() {
-        "No commit history".to_string()
+pub fn display_history(commits: Vec<BundleCommit>, renderer: &dyn OutputRenderer) -> String {
+    let header = strings(&["Id", "Timestamp", "Author", "Message"]);
+    let rows: Vec<Vec<String>> = commits
+        .into_iter()
+        .map(|commit| vec![commit.id, commit.timestamp, commit.author, commit.message])
+        .collect();
+
+    if rows.is_empty() {
+        renderer.render_empty(&header, "No commit history")
     } else {
-        table.to_string()
+        renderer.render(&header, &rows)
     }
 }
 
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
 /// Format an array value at a specific index for display
 fn format_array_value(column: &arrow::array::ArrayRef, row_idx: usize) -> String {
     use arrow::array::*;
@@ -233,30 +235,20 @@ fn format_array_value(column: &arrow::array::ArrayRef, row_idx: usize) -> String
             .unwrap()
             .value(row_idx)
             .to_string(),
-        DataType::Date32 => {
-            let value = column
-                .as_any()
-                .downcast_ref::<Date32Array>()
-                .unwrap()
-                .value(row_idx);
-            format!("Date32({})", value)
-        }
-        DataType::Date64 => {
-            let value = column
-                .as_any()
-                .downcast_ref::<Date64Array>()
-                .unwrap()
-                .value(row_idx);
-            format!("Date64({})", value)
-        }
-        DataType::Timestamp(unit, tz) => {
-            let value = column
-                .as_any()
-                .downcast_ref::<TimestampNanosecondArray>()
-                .map(|arr| arr.value(row_idx))
-                .unwrap_or(0);
-            format!("Timestamp({:?}, {:?})({})", unit, tz, value)
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => {
+            format_temporal_value(column, row_idx)
         }
         _ => format!("{:?}", column.slice(row_idx, 1)),
     }
 }
+
+/// Formats dates and timestamps as ISO-8601, e.g. `2024-01-01T12:00:00+02:00`.
+/// Timestamps with a time zone are shown in that zone with its offset, those without as written.
+fn format_temporal_value(column: &arrow::array::ArrayRef, row_idx: usize) -> String {
+    use arrow::util::display::{ArrayFormatter, FormatOptions};
+
+    match ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default()) {
+        Ok(formatter) => formatter.value(row_idx).to_string(),
+        Err(_) => format!("{:?}", column.slice(row_idx, 1)),
+    }
+}
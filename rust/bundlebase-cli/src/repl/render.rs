@@ -0,0 +1,226 @@
+use comfy_table::{presets::UTF8_FULL, Cell, Color, ContentArrangement, Table};
+use std::str::FromStr;
+
+/// Renders the tables REPL commands print, such as query results, schemas and history
+pub trait OutputRenderer: Send + Sync {
+    /// Renders a table from its column names and rows of already formatted values
+    fn render(&self, header: &[String], rows: &[Vec<String>]) -> String;
+
+    /// Rendered in place of a table with no rows. Defaults to `message`, for people to read
+    fn render_empty(&self, _header: &[String], message: &str) -> String {
+        message.to_string()
+    }
+
+    /// Whether notes such as "(Showing first 10 rows)" may follow a table.
+    /// Formats meant for other programs leave them out so the output stays parseable
+    fn allows_notes(&self) -> bool {
+        true
+    }
+}
+
+/// Box-drawn tables for terminals. The default
+pub struct TableRenderer;
+
+impl OutputRenderer for TableRenderer {
+    fn render(&self, header: &[String], rows: &[Vec<String>]) -> String {
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_content_arrangement(ContentArrangement::Dynamic);
+        table.set_header(
+            header
+                .iter()
+                .map(|name| Cell::new(name).fg(Color::Cyan))
+                .collect::<Vec<_>>(),
+        );
+        for row in rows {
+            table.add_row(row.iter().map(Cell::new).collect::<Vec<_>>());
+        }
+        table.to_string()
+    }
+}
+
+/// GitHub-flavored markdown tables, for pasting into docs
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+    fn line(values: &[String]) -> String {
+        let cells: Vec<String> = values
+            .iter()
+            .map(|value| value.replace('|', "\\|").replace('\n', "<br>"))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    }
+}
+
+impl OutputRenderer for MarkdownRenderer {
+    fn render(&self, header: &[String], rows: &[Vec<String>]) -> String {
+        let mut lines = vec![
+            Self::line(header),
+            format!("|{}", " --- |".repeat(header.len())),
+        ];
+        lines.extend(rows.iter().map(|row| Self::line(row)));
+        lines.join("\n")
+    }
+}
+
+/// Tab-separated values with a header line, for piping into other tools.
+/// Tabs, newlines and backslashes in values are escaped as `\t`, `\n` and `\\`
+pub struct TsvRenderer;
+
+impl TsvRenderer {
+    fn line(values: &[String]) -> String {
+        values
+            .iter()
+            .map(|value| {
+                value
+                    .replace('\\', "\\\\")
+                    .replace('\t', "\\t")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r")
+            })
+            .collect::<Vec<_>>()
+            .join("\t")
+    }
+}
+
+impl OutputRenderer for TsvRenderer {
+    fn render(&self, header: &[String], rows: &[Vec<String>]) -> String {
+        let mut lines = vec![Self::line(header)];
+        lines.extend(rows.iter().map(|row| Self::line(row)));
+        lines.join("\n")
+    }
+
+    fn render_empty(&self, header: &[String], _message: &str) -> String {
+        self.render(header, &[])
+    }
+
+    fn allows_notes(&self) -> bool {
+        false
+    }
+}
+
+/// A JSON array with an object per row, keyed by column name in column order
+pub struct JsonRenderer;
+
+impl OutputRenderer for JsonRenderer {
+    fn render(&self, header: &[String], rows: &[Vec<String>]) -> String {
+        if rows.is_empty() {
+            return "[]".to_string();
+        }
+        let objects: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = header
+                    .iter()
+                    .zip(row)
+                    .map(|(name, value)| format!("{}: {}", json_string(name), json_string(value)))
+                    .collect();
+                format!("  {{{}}}", fields.join(", "))
+            })
+            .collect();
+        format!("[\n{}\n]", objects.join(",\n"))
+    }
+
+    fn render_empty(&self, header: &[String], _message: &str) -> String {
+        self.render(header, &[])
+    }
+
+    fn allows_notes(&self) -> bool {
+        false
+    }
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// Output formats selectable with `--output`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Markdown,
+    Tsv,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn renderer(self) -> Box<dyn OutputRenderer> {
+        match self {
+            OutputFormat::Table => Box::new(TableRenderer),
+            OutputFormat::Markdown => Box::new(MarkdownRenderer),
+            OutputFormat::Tsv => Box::new(TsvRenderer),
+            OutputFormat::Json => Box::new(JsonRenderer),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "unknown output format '{}', must be one of: table, markdown, tsv, json",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_markdown_escapes_pipes() {
+        let output = MarkdownRenderer.render(
+            &strings(&["name", "note"]),
+            &[strings(&["a|b", "two\nlines"])],
+        );
+        assert_eq!(
+            "| name | note |\n| --- | --- |\n| a\\|b | two<br>lines |",
+            output
+        );
+    }
+
+    #[test]
+    fn test_tsv_escapes_separators() {
+        let output = TsvRenderer.render(&strings(&["a", "b"]), &[strings(&["x\ty", "c:\\d"])]);
+        assert_eq!("a\tb\nx\\ty\tc:\\\\d", output);
+        assert_eq!(
+            "a\tb",
+            TsvRenderer.render_empty(&strings(&["a", "b"]), "No rows")
+        );
+    }
+
+    #[test]
+    fn test_json_keeps_column_order() {
+        let output = JsonRenderer.render(
+            &strings(&["z", "a"]),
+            &[strings(&["1", "say \"hi\""]), strings(&["2", ""])],
+        );
+        assert_eq!(
+            "[\n  {\"z\": \"1\", \"a\": \"say \\\"hi\\\"\"},\n  {\"z\": \"2\", \"a\": \"\"}\n]",
+            output
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(2, parsed.as_array().unwrap().len());
+        assert_eq!("[]", JsonRenderer.render_empty(&strings(&["z"]), "No rows"));
+    }
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!(Ok(OutputFormat::Markdown), "MD".parse());
+        assert_eq!(Ok(OutputFormat::Json), "json".parse());
+        assert!("html".parse::<OutputFormat>().is_err());
+    }
+}
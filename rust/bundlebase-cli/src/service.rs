@@ -1,3 +1,5 @@
+use crate::audit::{request_identity, AuditAction, AuditEvent, AuditSink};
+use crate::federation::Federation;
 use crate::state::State;
 use arrow::datatypes::SchemaRef;
 use arrow::ipc::writer::{DictionaryTracker, IpcDataGenerator, IpcWriteOptions};
@@ -5,26 +7,78 @@ use arrow::record_batch::RecordBatch;
 use arrow_flight::flight_service_server::FlightService;
 use arrow_flight::{
     Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
-    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, Result as FlightResult, SchemaResult,
-    Ticket,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, Result as FlightResult, SchemaAsIpc,
+    SchemaResult, Ticket,
 };
 use bundlebase::bundle::BundleFacade;
+use bundlebase::metrics::{
+    extract_trace_context, OperationCategory, OperationOutcome, Span, TraceContext, TraceFutureExt,
+};
 use bytes::Bytes;
+use datafusion::dataframe::DataFrame;
+use datafusion::execution::context::SQLOptions;
 use futures::stream::Stream;
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tonic::metadata::{KeyAndValueRef, MetadataMap};
 use tonic::{Request, Response, Status, Streaming};
 
 pub struct BundlebaseFlightService {
     state: Arc<State>,
+    /// Limits how many queries execute at once; `None` means unlimited
+    query_limit: Option<QueryLimit>,
+    /// When serving several bundles, queries run against all of them instead of `state`
+    federation: Option<Federation>,
+    /// Where to record each query, if auditing is enabled
+    audit: Option<Arc<dyn AuditSink>>,
+}
+
+struct QueryLimit {
+    max: usize,
+    permits: Arc<Semaphore>,
 }
 
 impl BundlebaseFlightService {
     pub fn new(state: Arc<State>) -> Self {
-        Self { state }
+        Self {
+            state,
+            query_limit: None,
+            federation: None,
+            audit: None,
+        }
+    }
+
+    /// Serve several bundles, queryable as `<name>.bundle` (and as a unioned `bundle` if the
+    /// federation was created with `union`).
+    pub fn with_federation(mut self, federation: Federation) -> Self {
+        self.federation = Some(federation);
+        self
+    }
+
+    /// Record every query, with the SQL, who ran it and how it went, to `sink`
+    pub fn with_audit(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(sink);
+        self
+    }
+
+    /// Cap the number of queries executing at the same time.
+    ///
+    /// Queries arriving while `max` are already running are rejected with `RESOURCE_EXHAUSTED`
+    /// so clients can back off and retry.
+    pub fn with_max_concurrent_queries(mut self, max: usize) -> Self {
+        self.query_limit = Some(QueryLimit {
+            max,
+            permits: Arc::new(Semaphore::new(max)),
+        });
+        self
     }
 }
 
+/// Request metadata key that, set to `true`, queries the data without the default view applied
+pub const RAW_METADATA: &str = "raw";
+
 type BoxedFlightStream = Pin<Box<dyn Stream<Item = Result<FlightData, Status>> + Send>>;
 type BoxedPutResultStream = Pin<Box<dyn Stream<Item = Result<PutResult, Status>> + Send>>;
 type BoxedResultStream = Pin<Box<dyn Stream<Item = Result<FlightResult, Status>> + Send>>;
@@ -44,19 +98,71 @@ impl FlightService for BundlebaseFlightService {
         &self,
         request: Request<Ticket>,
     ) -> Result<Response<Self::DoGetStream>, Status> {
+        let parent = trace_context(request.metadata());
+        let raw = raw_requested(request.metadata());
+        // Only looked up when auditing, to keep the query path unchanged otherwise
+        let caller = self.audit.as_ref().map(|_| {
+            (
+                request_identity(request.metadata()),
+                request.remote_addr().map(|addr| addr.to_string()),
+            )
+        });
         let ticket = request.into_inner();
 
         // Extract SQL query from ticket
         let sql = String::from_utf8(ticket.ticket.to_vec())
             .map_err(|e| Status::invalid_argument(format!("Invalid SQL: {}", e)))?;
 
+        // Held until the query has finished executing
+        let _permit = match &self.query_limit {
+            Some(limit) => Some(limit.permits.clone().try_acquire_owned().map_err(|_| {
+                Status::resource_exhausted(format!(
+                    "Too many concurrent queries (limit {}), try again later",
+                    limit.max
+                ))
+            })?),
+            None => None,
+        };
+
         tracing::info!("Executing query: {}", sql);
 
         // Clone Arc for async execution
         let state = self.state.clone();
 
-        // Execute the query upfront
-        let flight_data = execute_query_impl(&state, sql).await?;
+        // Execute the query upfront, under the caller's trace if one was passed
+        let mut span = Span::start_with_parent(OperationCategory::Select, "flight.do_get", &parent);
+        span.set_attribute("sql", sql.clone());
+        let start = std::time::Instant::now();
+        let result = execute_query_impl(&state, self.federation.as_ref(), sql.clone(), raw)
+            .with_context(span.context())
+            .await;
+        let outcome = match result {
+            Ok(_) => OperationOutcome::Success,
+            Err(_) => OperationOutcome::Error,
+        };
+        span.set_outcome(outcome);
+        tracing::info!(
+            operation = OperationCategory::Select.as_str(),
+            outcome = outcome.as_str(),
+            duration_ms = start.elapsed().as_millis() as u64,
+            "Query complete"
+        );
+        if let (Some(audit), Some((identity, client))) = (&self.audit, caller) {
+            let bundle = match &self.federation {
+                Some(federation) => federation.urls().join(", "),
+                None => state.bundle.read().url().to_string(),
+            };
+            let mut event = AuditEvent::new(AuditAction::Query, bundle);
+            event.identity = identity;
+            event.client = client;
+            event.sql = Some(sql);
+            event.outcome = Some(outcome.as_str().to_string());
+            event.duration_ms = Some(start.elapsed().as_millis() as u64);
+            if let Err(e) = audit.record(&event) {
+                tracing::warn!("Failed to record query in audit log: {}", e);
+            }
+        }
+        let flight_data = result?;
 
         // Convert to a stream
         let stream = futures::stream::iter(flight_data.into_iter().map(Ok));
@@ -99,34 +205,46 @@ impl FlightService for BundlebaseFlightService {
 
     async fn get_schema(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> Result<Response<SchemaResult>, Status> {
-        // Get schema by reading from the locked bundle
-        // Clone builder to drop lock guard before await
-        let builder = {
-            let guard = self.state.bundle.read();
-            guard.clone()
+        let parent = trace_context(request.metadata());
+        let raw = raw_requested(request.metadata());
+        let mut span =
+            Span::start_with_parent(OperationCategory::Select, "flight.get_schema", &parent);
+        let descriptor = request.into_inner();
+
+        // A query in the descriptor is planned the same way do_get runs it, so the schemas match
+        let schema = if !descriptor.cmd.is_empty() {
+            let sql = String::from_utf8(descriptor.cmd.to_vec())
+                .map_err(|e| Status::invalid_argument(format!("Invalid SQL: {}", e)))?;
+            span.set_attribute("sql", sql.clone());
+            query_dataframe(&self.state, self.federation.as_ref(), &sql, raw)
+                .with_context(span.context())
+                .await
+                .map(|df| Arc::new(df.schema().as_arrow().clone()))
+        } else if self.federation.is_some() {
+            Err(Status::invalid_argument(
+                "Pass a query in the descriptor's cmd to get its schema when serving several bundles",
+            ))
+        } else {
+            // Clone builder to drop lock guard before await
+            let builder = {
+                let guard = self.state.bundle.read();
+                guard.clone()
+            };
+            builder
+                .schema()
+                .with_context(span.context())
+                .await
+                .map_err(|e| Status::internal(e.to_string()))
         };
+        let schema = schema.inspect_err(|e| span.record_error(e.message()))?;
+        span.set_outcome(OperationOutcome::Success);
 
-        let schema = builder
-            .schema()
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
-
+        // Encoded as an IPC message with its length prefix, which is what clients decode
         let options = IpcWriteOptions::default();
-        let gen = IpcDataGenerator::default();
-        let mut dict_tracker = DictionaryTracker::new(false);
-
-        // Encode schema to bytes with dictionary tracker
-        let encoded_data = gen.schema_to_bytes_with_dictionary_tracker(
-            schema.as_ref(),
-            &mut dict_tracker,
-            &options,
-        );
-
-        let result = SchemaResult {
-            schema: encoded_data.ipc_message.into(),
-        };
+        let result = SchemaResult::try_from(SchemaAsIpc::new(schema.as_ref(), &options))
+            .map_err(|e| Status::internal(format!("Failed to encode schema: {}", e)))?;
         Ok(Response::new(result))
     }
 
@@ -178,47 +296,89 @@ impl FlightService for BundlebaseFlightService {
     }
 }
 
-/// Execute a query and return FlightData messages
-async fn execute_query_impl(state: &Arc<State>, sql: String) -> Result<Vec<FlightData>, Status> {
-    // Clone the builder to execute the query (drop lock guard before await)
-    let builder = {
-        let guard = state.bundle.read();
-        guard.clone()
-    };
-
-    // Execute the query
-    let bundle = builder
-        .select(&sql, vec![])
-        .await
-        .map_err(|e| Status::internal(format!("Failed to execute query: {}", e)))?;
+/// Extract the caller's W3C trace context (`traceparent`) from gRPC request metadata
+fn trace_context(metadata: &MetadataMap) -> TraceContext {
+    let headers: HashMap<String, String> = metadata
+        .iter()
+        .filter_map(|entry| match entry {
+            KeyAndValueRef::Ascii(key, value) => value
+                .to_str()
+                .ok()
+                .map(|value| (key.as_str().to_string(), value.to_string())),
+            KeyAndValueRef::Binary(_, _) => None,
+        })
+        .collect();
+    extract_trace_context(&headers)
+}
 
-    // Get the dataframe and collect results
-    let df = bundle
-        .dataframe()
-        .await
-        .map_err(|e| Status::internal(format!("Failed to get dataframe: {}", e)))?;
+/// Whether the caller asked, with `raw: true` metadata, to query without the default view
+fn raw_requested(metadata: &MetadataMap) -> bool {
+    metadata
+        .get(RAW_METADATA)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
 
+/// Plan a query against the served bundle, or against all of them when federating
+async fn query_dataframe(
+    state: &Arc<State>,
+    federation: Option<&Federation>,
+    sql: &str,
+    raw: bool,
+) -> Result<DataFrame, Status> {
+    match federation {
+        Some(federation) => {
+            let ctx = federation
+                .context(raw)
+                .await
+                .map_err(|e| Status::internal(format!("Failed to register bundles: {}", e)))?;
+            // The server is read-only, so only queries are accepted
+            let options = SQLOptions::new()
+                .with_allow_ddl(false)
+                .with_allow_dml(false)
+                .with_allow_statements(false);
+            ctx.sql_with_options(sql, options)
+                .await
+                .map_err(|e| Status::internal(format!("Failed to execute query: {}", e)))
+        }
+        None => {
+            // Clone the bundle to execute the query (drop lock guard before await)
+            let bundle = {
+                let guard = state.bundle.read();
+                guard.bundle.clone()
+            };
+
+            let df = if raw {
+                bundle.raw_sql_dataframe(sql, vec![]).await
+            } else {
+                bundle.sql_dataframe(sql, vec![]).await
+            };
+            df.map_err(|e| Status::internal(format!("Failed to execute query: {}", e)))
+        }
+    }
+}
+
+/// Execute a query and return FlightData messages
+async fn execute_query_impl(
+    state: &Arc<State>,
+    federation: Option<&Federation>,
+    sql: String,
+    raw: bool,
+) -> Result<Vec<FlightData>, Status> {
+    let df = query_dataframe(state, federation, &sql, raw).await?;
+
+    // Taken from the plan rather than the first batch so queries returning no rows still
+    // tell the client their columns
+    let schema: SchemaRef = Arc::new(df.schema().as_arrow().clone());
     let batches = df
-        .as_ref()
-        .clone()
         .collect()
         .await
         .map_err(|e| Status::internal(format!("Failed to collect batches: {}", e)))?;
 
-    let mut messages = vec![];
-
-    if let Some(first_batch) = batches.first() {
-        let schema = first_batch.schema();
-
-        // Send schema as first message
-        let schema_message = create_schema_message(&schema)?;
-        messages.push(schema_message);
-
-        // Send each record batch
-        for batch in batches {
-            let msg = create_batch_message(&batch)?;
-            messages.push(msg);
-        }
+    // Send schema as first message, then each record batch
+    let mut messages = vec![create_schema_message(&schema)?];
+    for batch in batches {
+        messages.push(create_batch_message(&batch)?);
     }
 
     Ok(messages)
@@ -263,8 +423,9 @@ fn create_batch_message(batch: &RecordBatch) -> Result<FlightData, Status> {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use bundlebase::BundleBuilder;
-use super::*;
+    use futures::TryStreamExt;
 
     #[tokio::test]
     async fn test_flight_service_with_memory_bundle() {
@@ -285,6 +446,222 @@ use super::*;
         assert!(result.is_ok(), "Failed to get schema from flight service");
     }
 
+    #[tokio::test]
+    async fn test_query_without_rows_sends_schema() {
+        let mut builder =
+            BundleBuilder::create(bundlebase::test_utils::random_memory_url().as_str(), None)
+                .await
+                .unwrap();
+        builder
+            .attach(bundlebase::test_utils::test_datafile("customers-0-100.csv"))
+            .await
+            .unwrap();
+        let service = BundlebaseFlightService::new(Arc::new(State::new(builder)));
+
+        let response = service
+            .do_get(tonic::Request::new(Ticket::new(
+                r#"SELECT "Index", "Email" FROM bundle WHERE "Index" < 0"#,
+            )))
+            .await
+            .unwrap();
+        let messages: Vec<FlightData> = response.into_inner().try_collect().await.unwrap();
+
+        let schema = arrow::datatypes::Schema::try_from(&messages[0]).unwrap();
+        let names: Vec<&String> = schema.fields().iter().map(|f| f.name()).collect();
+        assert_eq!(vec!["Index", "Email"], names);
+        let batches = arrow_flight::utils::flight_data_to_batches(&messages).unwrap();
+        assert_eq!(0, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+
+        // A bundle with no data still answers with its (empty) schema
+        let builder = BundleBuilder::create("memory:///flight_empty_test", None)
+            .await
+            .unwrap();
+        let service = BundlebaseFlightService::new(Arc::new(State::new(builder)));
+        let response = service
+            .do_get(tonic::Request::new(Ticket::new("SELECT * FROM bundle")))
+            .await
+            .unwrap();
+        let messages: Vec<FlightData> = response.into_inner().try_collect().await.unwrap();
+        assert_eq!(1, messages.len());
+        let schema = arrow::datatypes::Schema::try_from(&messages[0]).unwrap();
+        assert_eq!(0, schema.fields().len());
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_queries() {
+        let builder = BundleBuilder::create("memory:///flight_limit_test", None)
+            .await
+            .expect("Failed to create bundle");
+        let service = BundlebaseFlightService::new(Arc::new(State::new(builder)))
+            .with_max_concurrent_queries(2);
+        let permits = service.query_limit.as_ref().unwrap().permits.clone();
+
+        let query = || tonic::Request::new(Ticket::new("SELECT 1"));
+
+        // Two queries in flight: the third is rejected
+        let in_flight = permits.clone().acquire_many_owned(2).await.unwrap();
+        let status = service.do_get(query()).await.err().unwrap();
+        assert_eq!(tonic::Code::ResourceExhausted, status.code());
+        assert!(status.message().contains("limit 2"));
+
+        // Once one finishes, the next query runs and releases its permit when done
+        drop(in_flight);
+        let _one_in_flight = permits.clone().acquire_owned().await.unwrap();
+        let result = service.do_get(query()).await;
+        assert!(
+            result
+                .as_ref()
+                .err()
+                .is_none_or(|status| status.code() != tonic::Code::ResourceExhausted),
+            "Query should not be rejected below the limit"
+        );
+        assert_eq!(1, permits.available_permits());
+    }
+
+    #[tokio::test]
+    async fn test_federated_query() {
+        let mut states = vec![];
+        for file in ["customers-0-100.csv", "customers-101-150.csv"] {
+            let mut builder =
+                BundleBuilder::create(bundlebase::test_utils::random_memory_url().as_str(), None)
+                    .await
+                    .unwrap();
+            builder
+                .attach(bundlebase::test_utils::test_datafile(file))
+                .await
+                .unwrap();
+            states.push(Arc::new(State::new(builder)));
+        }
+        let federation = Federation::new(
+            vec![
+                ("first".to_string(), states[0].clone()),
+                ("second".to_string(), states[1].clone()),
+            ],
+            true,
+        )
+        .unwrap();
+        let service = BundlebaseFlightService::new(states[0].clone()).with_federation(federation);
+
+        for sql in [
+            "SELECT * FROM first.bundle",
+            "SELECT * FROM second.bundle",
+            "SELECT * FROM bundle",
+        ] {
+            let result = service.do_get(tonic::Request::new(Ticket::new(sql))).await;
+            assert!(result.is_ok(), "{} failed: {:?}", sql, result.err());
+        }
+        let status = service
+            .do_get(tonic::Request::new(Ticket::new(
+                "SELECT * FROM third.bundle",
+            )))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(tonic::Code::Internal, status.code());
+
+        // Federated queries are read-only
+        let status = service
+            .do_get(tonic::Request::new(Ticket::new(
+                "CREATE TABLE copied AS SELECT * FROM first.bundle",
+            )))
+            .await
+            .err()
+            .unwrap();
+        assert!(status.message().contains("DDL not supported"), "{}", status);
+
+        // The schema of a query comes from the same federated context
+        let descriptor = FlightDescriptor::new_cmd(r#"SELECT "Index", "Email" FROM second.bundle"#);
+        let result = service
+            .get_schema(tonic::Request::new(descriptor))
+            .await
+            .unwrap()
+            .into_inner();
+        let schema = arrow::datatypes::Schema::try_from(result).unwrap();
+        let names: Vec<&String> = schema.fields().iter().map(|f| f.name()).collect();
+        assert_eq!(vec!["Index", "Email"], names);
+        let status = service
+            .get_schema(tonic::Request::new(FlightDescriptor::default()))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(tonic::Code::InvalidArgument, status.code());
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_queries() {
+        let mut builder =
+            BundleBuilder::create(bundlebase::test_utils::random_memory_url().as_str(), None)
+                .await
+                .unwrap();
+        builder
+            .attach(bundlebase::test_utils::test_datafile("customers-0-100.csv"))
+            .await
+            .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("audit.log");
+        let sink = crate::audit::audit_sink(path.to_str().unwrap()).unwrap();
+        let service = BundlebaseFlightService::new(Arc::new(State::new(builder))).with_audit(sink);
+
+        let queries = [
+            r#"SELECT "Email" FROM bundle WHERE "Index" = 1"#,
+            "SELECT COUNT(*) FROM bundle",
+        ];
+        for sql in queries {
+            let mut request = tonic::Request::new(Ticket::new(sql));
+            // "admin:password"
+            request.metadata_mut().insert(
+                "authorization",
+                "Basic YWRtaW46cGFzc3dvcmQ=".parse().unwrap(),
+            );
+            service.do_get(request).await.unwrap();
+        }
+
+        let records: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(2, records.len());
+        for (record, sql) in records.iter().zip(queries) {
+            assert_eq!("query", record["action"]);
+            assert_eq!(sql, record["sql"]);
+            assert_eq!("admin", record["identity"]);
+            assert_eq!("success", record["outcome"]);
+            assert!(record["timestamp"].as_str().unwrap().ends_with('Z'));
+        }
+    }
+
+    #[test]
+    fn test_trace_context_from_metadata() {
+        let mut request = tonic::Request::new(arrow_flight::FlightDescriptor::default());
+        request.metadata_mut().insert(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                .parse()
+                .unwrap(),
+        );
+
+        let parent = trace_context(request.metadata());
+        let span = Span::start_with_parent(OperationCategory::Select, "flight.do_get", &parent);
+        assert_eq!("4bf92f3577b34da6a3ce929d0e0e4736", span.trace_id());
+    }
+
+    #[test]
+    fn test_raw_requested() {
+        let mut request = tonic::Request::new(Ticket::new("SELECT 1"));
+        assert!(!raw_requested(request.metadata()));
+
+        request
+            .metadata_mut()
+            .insert(RAW_METADATA, "TRUE".parse().unwrap());
+        assert!(raw_requested(request.metadata()));
+
+        request
+            .metadata_mut()
+            .insert(RAW_METADATA, "false".parse().unwrap());
+        assert!(!raw_requested(request.metadata()));
+    }
+
     #[tokio::test]
     async fn test_server_startup_scenario() {
         // Simulates the server startup scenario with --create flag
@@ -0,0 +1,310 @@
+use bundlebase::bundle::BundleFacade;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn test_datafile(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../test_data")
+        .join(name)
+        .canonicalize()
+        .unwrap();
+    url::Url::from_file_path(path).unwrap().to_string()
+}
+
+fn run_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bundlebase-cli"))
+        .args(args)
+        .output()
+        .expect("Failed to run bundlebase-cli")
+}
+
+#[test]
+fn test_quiet_exec_only_writes_results() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let bundle = dir.path().to_str().unwrap();
+    let attach = format!("ATTACH '{}'", test_datafile("customers-0-100.csv"));
+
+    let output = run_cli(&[
+        "--bundle",
+        bundle,
+        "--create",
+        "--quiet",
+        "--exec",
+        &attach,
+        "--exec",
+        r#"SELECT "Customer Id" FROM bundle WHERE "Index" <= 3"#,
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!("", stderr);
+    assert!(stdout.contains("Customer Id"), "{}", stdout);
+    assert!(stdout.contains("DD37Cf93aecA6Dc"), "{}", stdout);
+    // The header and the 3 matching rows, and none of the log lines
+    assert_eq!(
+        3 + 1,
+        stdout.lines().filter(|line| line.starts_with('│')).count(),
+        "{}",
+        stdout
+    );
+    assert!(
+        !stdout.contains("Loading") && !stdout.contains("Creating"),
+        "{}",
+        stdout
+    );
+
+    // Errors still go to stderr, and nothing to stdout
+    let output = run_cli(&[
+        "--bundle",
+        bundle,
+        "--create",
+        "--quiet",
+        "--exec",
+        "SELECT no_such_column FROM bundle",
+    ]);
+    assert!(!output.status.success());
+    assert_eq!("", String::from_utf8(output.stdout).unwrap());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no_such_column"), "{}", stderr);
+}
+
+#[test]
+fn test_exec_logs_to_stderr() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let output = run_cli(&[
+        "--bundle",
+        dir.path().to_str().unwrap(),
+        "--create",
+        "--exec",
+        "COUNT",
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!("Row count: 0\n", stdout);
+    // stderr isn't a terminal here, so logs use the full info format
+    assert!(stderr.contains("INFO"), "{}", stderr);
+    assert!(stderr.contains("Creating bundle at"), "{}", stderr);
+}
+
+#[test]
+fn test_exec_shows_readable_timestamps() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let csv = dir.path().join("readings.csv");
+    std::fs::write(
+        &csv,
+        "ts,reading\n2023-12-31T23:30:00,1\n2024-01-02T08:15:00,2\n",
+    )
+    .unwrap();
+    let attach = format!("ATTACH '{}'", url::Url::from_file_path(&csv).unwrap());
+
+    let output = run_cli(&[
+        "--bundle",
+        dir.path().join("bundle").to_str().unwrap(),
+        "--create",
+        "--quiet",
+        "--exec",
+        &attach,
+        "--exec",
+        "SELECT ts, reading FROM bundle WHERE ts > '2024-01-01'",
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8(output.stderr).unwrap()
+    );
+    assert!(stdout.contains("2024-01-02T08:15:00"), "{}", stdout);
+    assert!(!stdout.contains("2023-12-31"), "{}", stdout);
+    assert!(!stdout.contains("Timestamp("), "{}", stdout);
+}
+
+#[test]
+fn test_exec_markdown_schema() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let attach = format!("ATTACH '{}'", test_datafile("customers-0-100.csv"));
+
+    let output = run_cli(&[
+        "--bundle",
+        dir.path().to_str().unwrap(),
+        "--create",
+        "--quiet",
+        "--output",
+        "markdown",
+        "--exec",
+        &attach,
+        "--exec",
+        "SCHEMA",
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8(output.stderr).unwrap()
+    );
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!("| Column | Type | Nullable | Key |", lines[0]);
+    assert_eq!("| --- | --- | --- | --- |", lines[1]);
+    assert_eq!("| Index | Int64 | Yes |  |", lines[2]);
+    assert!(
+        lines.contains(&"| Customer Id | Utf8 | Yes |  |"),
+        "{}",
+        stdout
+    );
+    // Every row has the same number of cells as the header
+    assert_eq!(2 + 12, lines.len(), "{}", stdout);
+    assert!(
+        lines.iter().all(|line| line.starts_with("| ")
+            && line.ends_with(" |")
+            && line.matches(" | ").count() == 3),
+        "{}",
+        stdout
+    );
+
+    let output = run_cli(&[
+        "--bundle",
+        dir.path().to_str().unwrap(),
+        "--create",
+        "--output",
+        "html",
+        "--exec",
+        "SCHEMA",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Invalid output format 'html'"),
+        "{}",
+        stderr
+    );
+}
+
+#[tokio::test]
+async fn test_dump_manifest() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let bundle = dir.path().to_str().unwrap();
+
+    // The REPL can't create indexes yet
+    let mut builder = bundlebase::BundleBuilder::create(bundle, None)
+        .await
+        .unwrap();
+    builder
+        .attach(&test_datafile("customers-0-100.csv"))
+        .await
+        .unwrap();
+    builder.index("Customer Id").await.unwrap();
+    builder.commit("Attach and index").await.unwrap();
+
+    let output = run_cli(&["--bundle", bundle, "--quiet", "--dump-manifest"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8(output.stderr).unwrap()
+    );
+
+    let (manifest, rest) = stdout.split_once("\nOperations:\n").unwrap();
+    let (operations, files) = rest.split_once("\nReferenced files:\n").unwrap();
+    assert!(manifest.starts_with("Manifest 00001"), "{}", stdout);
+    assert!(manifest.contains("message: Attach and index"), "{}", stdout);
+    assert!(operations.contains("Attach and index"), "{}", stdout);
+    assert!(operations.contains("ATTACH: "), "{}", stdout);
+    assert!(operations.contains("INDEX BLOCKS"), "{}", stdout);
+
+    let files: Vec<&str> = files.lines().collect();
+    assert_eq!(2, files.len(), "{}", stdout);
+    assert!(files[0].contains("customers-0-100.csv"), "{}", stdout);
+    assert!(files[1].contains(".idx"), "{}", stdout);
+    assert!(
+        files
+            .iter()
+            .all(|line| line.trim_start().starts_with("present ")),
+        "{}",
+        stdout
+    );
+
+    // Dumping by commit id gives the same manifest
+    let commit_id = manifest["Manifest ".len()..].split(' ').next().unwrap();
+    let output = run_cli(&["--bundle", bundle, "--quiet", "--dump-manifest", commit_id]);
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with(manifest));
+}
+
+#[tokio::test]
+async fn test_check_schema() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let bundle = dir.path().join("bundle");
+    let bundle = bundle.to_str().unwrap();
+    let attach = format!("ATTACH '{}'", test_datafile("customers-0-100.csv"));
+    let output = run_cli(&[
+        "--bundle",
+        bundle,
+        "--create",
+        "--quiet",
+        "--exec",
+        &attach,
+        "--exec",
+        "COMMIT 'Attach customers'",
+    ]);
+    assert!(output.status.success());
+
+    let schema = bundlebase::Bundle::open(bundle, None)
+        .await
+        .unwrap()
+        .schema()
+        .await
+        .unwrap();
+    let expected = dir.path().join("schema.json");
+    std::fs::write(
+        &expected,
+        bundlebase::bundle::schema_to_json(&schema).unwrap(),
+    )
+    .unwrap();
+
+    let output = run_cli(&[
+        "--bundle",
+        bundle,
+        "--quiet",
+        "--check-schema",
+        expected.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8(output.stderr).unwrap()
+    );
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("matches"));
+
+    let drifted = dir.path().join("drifted.json");
+    std::fs::write(
+        &drifted,
+        r#"{"fields": [{"name": "Index", "type": "Utf8"}, {"name": "Customer Id", "type": "Utf8"}]}"#,
+    )
+    .unwrap();
+    let output = run_cli(&[
+        "--bundle",
+        bundle,
+        "--quiet",
+        "--check-schema",
+        drifted.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("column 'Index' is Int64, expected Utf8"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("unexpected column 'First Name' (Utf8)"),
+        "{}",
+        stderr
+    );
+}
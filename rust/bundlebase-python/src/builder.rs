@@ -22,14 +22,31 @@ pub struct PyChange {
     description: String,
     #[pyo3(get)]
     operation_count: usize,
+    #[pyo3(get)]
+    summary: String,
+    #[pyo3(get)]
+    files: Vec<String>,
+    #[pyo3(get)]
+    columns: Vec<String>,
+    #[pyo3(get)]
+    undoable: bool,
 }
 
 impl PyChange {
+    /// Convert a committed change, which can no longer be undone
     pub fn from_rust(change: &BundleChange) -> Self {
+        Self::from_rust_with_undoable(change, false)
+    }
+
+    fn from_rust_with_undoable(change: &BundleChange, undoable: bool) -> Self {
         PyChange {
             id: change.id.to_string(),
             description: change.description.clone(),
             operation_count: change.operations.len(),
+            summary: change.summary(),
+            files: change.files(),
+            columns: change.columns(),
+            undoable,
         }
     }
 }
@@ -66,7 +83,11 @@ impl PyBundleStatus {
 
 impl PyBundleStatus {
     fn from_rust(status: &BundleStatus) -> Self {
-        let changes: Vec<PyChange> = status.changes().iter().map(PyChange::from_rust).collect();
+        let changes: Vec<PyChange> = status
+            .changes()
+            .iter()
+            .map(|change| PyChange::from_rust_with_undoable(change, status.is_undoable(&change.id)))
+            .collect();
         let change_count = changes.len();
         let total_operations = status.operations_count();
 
@@ -387,6 +408,163 @@ impl PyBundleBuilder {
         })
     }
 
+    fn column_alias<'py>(
+        slf: PyRef<'_, Self>,
+        original: &str,
+        alias: &str,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = slf.inner.clone();
+        let original = original.to_string();
+        let alias = alias.to_string();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let mut builder = inner.lock().await;
+            builder
+                .column_alias(original.as_str(), alias.as_str())
+                .await
+                .map_err(|e| {
+                    to_py_error(
+                        &format!("Failed to alias column '{}' as '{}'", original, alias),
+                        e,
+                    )
+                })?;
+            drop(builder);
+            Python::attach(|py| {
+                Py::new(
+                    py,
+                    PyBundleBuilder {
+                        inner: inner.clone(),
+                    },
+                )
+                .map_err(|e| to_py_error("Failed to create bundle", e))
+            })
+        })
+    }
+
+    #[pyo3(signature = (url, key_columns=None))]
+    fn merge<'py>(
+        slf: PyRef<'_, Self>,
+        url: &str,
+        key_columns: Option<Vec<String>>,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = slf.inner.clone();
+        let url = url.to_string();
+        let key_columns = key_columns.unwrap_or_default();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let mut builder = inner.lock().await;
+            builder
+                .merge(
+                    url.as_str(),
+                    key_columns.iter().map(|c| c.as_str()).collect(),
+                )
+                .await
+                .map_err(|e| to_py_error(&format!("Failed to merge '{}'", url), e))?;
+            drop(builder);
+            Python::attach(|py| {
+                Py::new(
+                    py,
+                    PyBundleBuilder {
+                        inner: inner.clone(),
+                    },
+                )
+                .map_err(|e| to_py_error("Failed to create bundle", e))
+            })
+        })
+    }
+
+    #[pyo3(signature = (columns, verify_unique=false))]
+    fn declare_key<'py>(
+        slf: PyRef<'_, Self>,
+        columns: Vec<String>,
+        verify_unique: bool,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = slf.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let mut builder = inner.lock().await;
+            builder
+                .declare_key(columns.iter().map(|c| c.as_str()).collect(), verify_unique)
+                .await
+                .map_err(|e| {
+                    to_py_error(
+                        &format!("Failed to declare key ({})", columns.join(", ")),
+                        e,
+                    )
+                })?;
+            drop(builder);
+            Python::attach(|py| {
+                Py::new(
+                    py,
+                    PyBundleBuilder {
+                        inner: inner.clone(),
+                    },
+                )
+                .map_err(|e| to_py_error("Failed to create bundle", e))
+            })
+        })
+    }
+
+    fn label<'py>(
+        slf: PyRef<'_, Self>,
+        key: &str,
+        value: &str,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = slf.inner.clone();
+        let key = key.to_string();
+        let value = value.to_string();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let mut builder = inner.lock().await;
+            builder
+                .label(key.as_str(), value.as_str())
+                .await
+                .map_err(|e| to_py_error(&format!("Failed to add label '{}'", key), e))?;
+            drop(builder);
+            Python::attach(|py| {
+                Py::new(
+                    py,
+                    PyBundleBuilder {
+                        inner: inner.clone(),
+                    },
+                )
+                .map_err(|e| to_py_error("Failed to create bundle", e))
+            })
+        })
+    }
+
+    fn set_nullable<'py>(
+        slf: PyRef<'_, Self>,
+        column: &str,
+        nullable: bool,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = slf.inner.clone();
+        let column = column.to_string();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let mut builder = inner.lock().await;
+            builder
+                .set_nullable(column.as_str(), nullable)
+                .await
+                .map_err(|e| {
+                    to_py_error(
+                        &format!("Failed to set nullability of column '{}'", column),
+                        e,
+                    )
+                })?;
+            drop(builder);
+            Python::attach(|py| {
+                Py::new(
+                    py,
+                    PyBundleBuilder {
+                        inner: inner.clone(),
+                    },
+                )
+                .map_err(|e| to_py_error("Failed to create bundle", e))
+            })
+        })
+    }
+
     #[pyo3(signature = (name, url, expression, join_type=None))]
     fn join<'py>(
         slf: PyRef<'_, Self>,
@@ -583,6 +761,40 @@ impl PyBundleBuilder {
         })
     }
 
+    #[pyo3(signature = (where_clause, params=None))]
+    fn delete_rows<'py>(
+        slf: PyRef<'_, Self>,
+        where_clause: &str,
+        params: Option<Vec<Py<PyAny>>>,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let inner = slf.inner.clone();
+        let where_clause = where_clause.to_string();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let params_vec = if let Some(params_list) = params {
+                convert_py_params(params_list)?
+            } else {
+                vec![]
+            };
+
+            let mut builder = inner.lock().await;
+            builder
+                .delete_rows(where_clause.as_str(), params_vec)
+                .await
+                .map_err(|e| to_py_error("Failed to delete rows", e))?;
+            drop(builder);
+            Python::attach(|py| {
+                Py::new(
+                    py,
+                    PyBundleBuilder {
+                        inner: inner.clone(),
+                    },
+                )
+                .map_err(|e| to_py_error("Failed to create bundle", e))
+            })
+        })
+    }
+
     fn num_rows<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
         let inner = self.inner.clone();
         pyo3_async_runtimes::tokio::future_into_py(py, async move {
@@ -853,7 +1065,10 @@ impl PyBundleBuilder {
         pyo3_async_runtimes::tokio::future_into_py(py, async move {
             let builder = inner.lock().await;
 
-            let ctx = builder.bundle.ctx();
+            let ctx = builder
+                .bundle
+                .query_ctx()
+                .map_err(|e| to_py_error("Failed to get session context", e))?;
 
             Python::attach(|py| {
                 Py::new(py, super::session_context::PySessionContext::new(ctx))
@@ -1022,6 +1237,17 @@ impl PyBundleBuilder {
                 .collect()
         })
     }
+
+    fn operations_with_label(&self, key: &str, value: &str) -> Vec<super::operation::PyOperation> {
+        Python::attach(|_py| {
+            self.inner
+                .blocking_lock()
+                .operations_with_label(key, value)
+                .into_iter()
+                .map(super::operation::PyOperation::new)
+                .collect()
+        })
+    }
 }
 
 impl PyBundleBuilder {
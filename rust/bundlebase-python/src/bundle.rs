@@ -140,7 +140,9 @@ impl PyBundle {
     fn ctx<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
         let inner = self.inner.clone();
         pyo3_async_runtimes::tokio::future_into_py(py, async move {
-            let ctx = inner.ctx();
+            let ctx = inner
+                .query_ctx()
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
             Python::attach(|py| {
                 Py::new(py, super::session_context::PySessionContext::new(ctx))
                     .map(|obj| obj.into_any())
@@ -191,6 +193,14 @@ impl PyBundle {
             .collect()
     }
 
+    fn operations_with_label(&self, key: &str, value: &str) -> Vec<super::operation::PyOperation> {
+        self.inner
+            .operations_with_label(key, value)
+            .into_iter()
+            .map(super::operation::PyOperation::new)
+            .collect()
+    }
+
     fn export_tar<'py>(
         &self,
         tar_path: &str,
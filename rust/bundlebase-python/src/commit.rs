@@ -11,6 +11,11 @@ pub struct PyCommit {
 
 #[pymethods]
 impl PyCommit {
+    #[getter]
+    fn id(&self) -> String {
+        self.inner.id.clone()
+    }
+
     #[getter]
     fn author(&self) -> String {
         self.inner.author.clone()
@@ -46,7 +51,8 @@ impl PyCommit {
 
     fn __repr__(&self) -> String {
         format!(
-            "PyCommit(author='{}', message='{}', timestamp='{}', changes={})",
+            "PyCommit(id='{}', author='{}', message='{}', timestamp='{}', changes={})",
+            self.inner.id,
             self.inner.author,
             self.inner.message,
             self.inner.timestamp,
@@ -13,23 +13,45 @@ impl PyOperation {
     #[getter]
     fn op_type(&self) -> String {
         match &self.inner {
+            AnyOperation::AddColumn(_) => "addColumn".to_string(),
+            AnyOperation::Aggregate(_) => "aggregate".to_string(),
             AnyOperation::AttachBlock(_) => "attachBlock".to_string(),
+            AnyOperation::AttachBundle(_) => "attachBundle".to_string(),
             AnyOperation::CreateView(_) => "CreateView".to_string(),
             AnyOperation::RemoveColumns(_) => "removeColumns".to_string(),
             AnyOperation::RenameColumn(_) => "renameColumn".to_string(),
+            AnyOperation::CastColumn(_) => "castColumn".to_string(),
+            AnyOperation::ColumnAlias(_) => "columnAlias".to_string(),
             AnyOperation::RenameView(_) => "renameView".to_string(),
+            AnyOperation::FillNull(_) => "fillNull".to_string(),
             AnyOperation::Filter(_) => "filter".to_string(),
             AnyOperation::Join(_) => "join".to_string(),
+            AnyOperation::Limit(_) => "limit".to_string(),
+            AnyOperation::MaskColumn(_) => "maskColumn".to_string(),
+            AnyOperation::MaterializeView(_) => "materializeView".to_string(),
+            AnyOperation::Merge(_) => "merge".to_string(),
+            AnyOperation::OrderBy(_) => "orderBy".to_string(),
+            AnyOperation::Pivot(_) => "pivot".to_string(),
+            AnyOperation::RevertCommit(_) => "revertCommit".to_string(),
+            AnyOperation::SchemaConstraint(_) => "schemaConstraint".to_string(),
             AnyOperation::DefineFunction(_) => "defineFunction".to_string(),
+            AnyOperation::RegisterUdf(_) => "registerUdf".to_string(),
             AnyOperation::SetConfig(_) => "setConfig".to_string(),
+            AnyOperation::SetDefaultView(_) => "setDefaultView".to_string(),
             AnyOperation::SetName(_) => "setName".to_string(),
+            AnyOperation::SetSecurityFilter(_) => "setSecurityFilter".to_string(),
             AnyOperation::SetDescription(_) => "setDescription".to_string(),
+            AnyOperation::Union(_) => "union".to_string(),
+            AnyOperation::Unpivot(_) => "unpivot".to_string(),
             AnyOperation::IndexBlocks(_) => "indexBlocks".to_string(),
             AnyOperation::CreateIndex(_) => "createIndex".to_string(),
             AnyOperation::DefinePack(_) => "definePack".to_string(),
+            AnyOperation::DeleteRows(_) => "deleteRows".to_string(),
+            AnyOperation::Distinct(_) => "distinct".to_string(),
             AnyOperation::DropIndex(_) => "dropIndex".to_string(),
             AnyOperation::DropView(_) => "dropView".to_string(),
             AnyOperation::RebuildIndex(_) => "rebuildIndex".to_string(),
+            AnyOperation::RepairRefs(_) => "repairRefs".to_string(),
             AnyOperation::Select(_) => "select".to_string(),
         }
     }
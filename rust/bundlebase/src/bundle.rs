@@ -1,36 +1,72 @@
 mod builder;
+mod change_export;
 mod column_lineage;
 mod command;
 mod commit;
+mod csv_export;
+mod diff;
 mod facade;
 mod init;
+mod json_export;
 mod operation;
+mod pack_info;
+mod parquet_export;
+mod schema_contract;
 mod sql;
+mod sql_export;
+mod validation;
+mod write_lock;
 
+pub use crate::catalog::DATAFRAME_ALIAS;
 use crate::io::EMPTY_SCHEME;
 pub use builder::{BundleBuilder, BundleStatus};
+use change_export::write_changes;
+pub use change_export::{ChangeExport, ADDED_FILENAME, CHANGES_FILENAME, REMOVED_FILENAME};
 pub use column_lineage::{ColumnLineageAnalyzer, ColumnSource};
-pub use command::parser::parse_command;
+pub use command::parser::{parse_command, parse_command_with_dialect, SqlDialect};
 pub use command::BundleCommand;
-pub use commit::{manifest_version, BundleCommit};
+use commit::parse_manifest;
+pub use commit::{manifest_commit_id, manifest_version, BundleCommit, FORMAT_VERSION};
+use csv_export::write_csv;
+pub use csv_export::CsvExportOptions;
+pub use diff::BundleDiff;
 pub use facade::BundleFacade;
 pub use init::{InitCommit, INIT_FILENAME};
-pub use operation::JoinTypeOption;
-pub use operation::{AnyOperation, BundleChange, Operation};
+use json_export::write_json;
+pub use json_export::JsonExportOptions;
+pub use operation::SortDirection;
+use operation::{repair_operation, repair_ref, AttachBlockOp, ExternalBundle, FilterOp};
+pub use operation::{AggFunc, AttachSplit, JoinTypeOption, MaskStrategy, PivotAggregate};
+pub use operation::{AnyOperation, BundleChange, Operation, ORIGINAL_NAME_METADATA};
+pub use operation::{RefMapping, RepairRefsOp};
+use pack_info::block_name;
+pub use pack_info::{BlockInfo, PackInfo};
+use parquet_export::{write_parquet, write_parquet_files};
+use schema_contract::schema_differences;
+pub use schema_contract::{schema_from_json, schema_to_json};
 use std::collections::{HashMap, HashSet};
-
-use crate::catalog::{BlockSchemaProvider, BundleSchemaProvider, PackSchemaProvider, CATALOG_NAME};
-use crate::data::{DataPack, DataReaderFactory, ObjectId, PackJoin, VersionedBlockId};
+use validation::find_duplicate_content;
+pub use validation::ValidationWarning;
+pub use write_lock::{LockInfo, WriteLock, LOCK_FILENAME};
+
+use crate::catalog::{
+    BlockSchemaProvider, BundleSchemaProvider, ConstrainedTable, PackSchemaProvider, CATALOG_NAME,
+};
+use crate::data::{
+    ColumnStats, DataBlock, DataPack, DataReader, DataReaderFactory, ObjectId, PackJoin,
+    VersionedBlockId,
+};
 use crate::functions::FunctionRegistry;
 use crate::index::{IndexDefinition, IndexedBlocks};
-use crate::io::{DataStorage, ObjectStoreDir, EMPTY_URL};
+use crate::io::{str_to_url, DataStorage, ObjectStoreDir, ObjectStoreFile, EMPTY_URL};
 use crate::{BundleConfig, BundlebaseError};
-use arrow::array::Array;
+use arrow::array::{Array, RecordBatch};
 use arrow_schema::SchemaRef;
 use async_trait::async_trait;
 use datafusion::catalog::MemorySchemaProvider;
 use datafusion::common::{DFSchema, DFSchemaRef};
 use datafusion::datasource::object_store::ObjectStoreUrl;
+use datafusion::execution::SessionStateBuilder;
 use datafusion::logical_expr::{EmptyRelation, ExplainFormat, ExplainOption, LogicalPlan};
 use datafusion::prelude::*;
 use datafusion::scalar::ScalarValue;
@@ -43,6 +79,10 @@ use uuid::Uuid;
 
 pub static META_DIR: &str = "_bundlebase";
 
+/// Arrow field metadata key marking a column as part of the declared primary key.
+/// The value is the column's 1-based position in the key.
+pub static KEY_FIELD_METADATA: &str = "bundlebase.key";
+
 /// A read-only view of a Bundle loaded from persistent storage.
 ///
 /// `Bundle` represents a bundle that has been committed and persisted to disk.
@@ -67,9 +107,30 @@ pub struct Bundle {
     data_packs: Arc<RwLock<HashMap<ObjectId, Arc<DataPack>>>>,
     base_pack: Option<ObjectId>,
     joins: HashMap<String, PackJoin>,
+    /// Bundles attached by name with `attach_bundle`, shared with the "public" schema provider
+    pub(crate) external_bundles: ExternalBundles,
     indexes: Arc<RwLock<Vec<Arc<IndexDefinition>>>>,
     pub(crate) views: HashMap<String, ObjectId>,
+    /// View applied on top of the data when the bundle is queried, set with `set_default_view`
+    pub(crate) default_view: Option<ObjectId>,
+    /// Views stored with `materialize_view`, mapped to their parquet file within the view's dir
+    pub(crate) materialized_views: HashMap<ObjectId, String>,
+    /// Positions in `operations` of those undone by `revert_commit`, left out of the dataframe
+    pub(crate) reverted_operations: HashSet<usize>,
+    /// Conditions set with `set_security_filter`. Each filters the rows at its place in the
+    /// operations, and the columns they use can't be changed by later operations
+    pub(crate) security_filters: Vec<String>,
+    /// While `reload` reopens the bundle, the previous snapshot's attach operations and blocks,
+    /// so blocks that haven't changed keep their reader, see `reloaded_reader`
+    reloaded_blocks: HashMap<ObjectId, (AttachBlockOp, Arc<DataBlock>)>,
     dataframe: DataFrameHolder,
+    /// Cached dataframe with the default view applied
+    default_dataframe: DataFrameHolder,
+
+    /// Columns declared as the primary key, in key order
+    key_columns: Vec<String>,
+    /// Nullability declared for individual columns, overriding what the data reports
+    nullable_columns: HashMap<String, bool>,
 
     ctx: Arc<SessionContext>,
     storage: Arc<DataStorage>,
@@ -116,11 +177,22 @@ impl Clone for Bundle {
             base_pack: self.base_pack.clone(),
             data_packs: Arc::clone(&self.data_packs),
             joins: self.joins.clone(),
+            external_bundles: Arc::clone(&self.external_bundles),
             indexes,
             views: self.views.clone(),
+            default_view: self.default_view,
+            materialized_views: self.materialized_views.clone(),
+            reverted_operations: self.reverted_operations.clone(),
+            security_filters: self.security_filters.clone(),
+            reloaded_blocks: self.reloaded_blocks.clone(),
+            key_columns: self.key_columns.clone(),
+            nullable_columns: self.nullable_columns.clone(),
             dataframe: DataFrameHolder {
                 dataframe: Arc::new(RwLock::new(self.dataframe.dataframe.read().clone())),
             },
+            default_dataframe: DataFrameHolder {
+                dataframe: Arc::new(RwLock::new(self.default_dataframe.dataframe.read().clone())),
+            },
             ctx: Arc::clone(&self.ctx),
             storage: Arc::clone(&self.storage),
             adapter_factory: Arc::clone(&self.adapter_factory),
@@ -148,6 +220,7 @@ impl Bundle {
 
         // Create data_packs bundle and dataframe cache
         let data_packs = Arc::new(RwLock::new(HashMap::new()));
+        let external_bundles = Arc::new(RwLock::new(HashMap::new()));
 
         let empty_dataframe = DataFrame::new(
             ctx.state(),
@@ -173,9 +246,13 @@ impl Bundle {
         )?;
         catalog.register_schema(
             "public",
-            Arc::new(BundleSchemaProvider::new(dataframe.clone())),
+            Arc::new(BundleSchemaProvider::new(
+                dataframe.clone(),
+                external_bundles.clone(),
+            )),
         )?;
         catalog.register_schema("temp", Arc::new(MemorySchemaProvider::new()))?;
+        catalog.register_schema("unions", Arc::new(MemorySchemaProvider::new()))?;
 
         ctx.register_object_store(
             ObjectStoreUrl::parse("memory://")?.as_ref(),
@@ -192,8 +269,16 @@ impl Bundle {
             base_pack: None,
             data_packs,
             joins: HashMap::new(),
+            external_bundles,
             indexes: Arc::new(RwLock::new(Vec::new())),
             views: HashMap::new(),
+            default_view: None,
+            materialized_views: HashMap::new(),
+            reverted_operations: HashSet::new(),
+            security_filters: vec![],
+            reloaded_blocks: HashMap::new(),
+            key_columns: vec![],
+            nullable_columns: HashMap::new(),
             storage: Arc::clone(&storage),
             adapter_factory: DataReaderFactory::new(
                 Arc::clone(&function_registry),
@@ -210,6 +295,7 @@ impl Bundle {
             data_dir: ObjectStoreDir::from_url(&url, BundleConfig::default().into())?,
             commits: vec![],
             dataframe,
+            default_dataframe: DataFrameHolder::new(None),
             config: Arc::new(crate::BundleConfig::new()),
             passed_config: None,
             stored_config: BundleConfig::new(),
@@ -233,8 +319,23 @@ impl Bundle {
     /// let schema = bundle.schema();
     /// ```
     pub async fn open(path: &str, config: Option<BundleConfig>) -> Result<Self, BundlebaseError> {
+        Self::open_with_functions(path, config, &FunctionRegistry::new()).await
+    }
+
+    /// Loads a Bundle like `open()`, with the given function signatures and implementations
+    /// registered before the manifest is replayed.
+    ///
+    /// Function implementations are not stored in the manifest, only the `function://` URL that
+    /// was attached. A bundle built from a custom function can therefore only be reopened if the
+    /// embedder registers the same function again, which is what this is for.
+    pub async fn open_with_functions(
+        path: &str,
+        config: Option<BundleConfig>,
+        functions: &FunctionRegistry,
+    ) -> Result<Self, BundlebaseError> {
         let mut visited = HashSet::new();
         let mut bundle = Bundle::empty().await?;
+        bundle.function_registry.write().register_all(functions);
 
         // Set explicit config if provided and recompute merged config
         bundle.passed_config = config;
@@ -246,17 +347,292 @@ impl Bundle {
                 .as_str(),
             &mut visited,
             &mut bundle,
+            None,
+            &[],
+        )
+        .await?;
+
+        Ok(bundle)
+    }
+
+    /// Loads the Bundle as it was right after the commit with the given id, see
+    /// `BundleCommit::id`. Later commits are ignored, so the result is a read-only snapshot of
+    /// an earlier state, e.g. `Bundle::open_at(url, &bundle.history()[0].id, None)`.
+    pub async fn open_at(
+        path: &str,
+        commit_id: &str,
+        config: Option<BundleConfig>,
+    ) -> Result<Self, BundlebaseError> {
+        let mut visited = HashSet::new();
+        let mut bundle = Bundle::empty().await?;
+        bundle.passed_config = config;
+        bundle.recompute_config()?;
+
+        Self::open_internal(
+            ObjectStoreDir::from_str(path, BundleConfig::default().into())?
+                .url()
+                .as_str(),
+            &mut visited,
+            &mut bundle,
+            Some(commit_id),
+            &[],
+        )
+        .await?;
+
+        Ok(bundle)
+    }
+
+    /// Compares the bundle at two commits from its history, see `BundleCommit::id`: the
+    /// operations added or removed in between, and the columns added, removed or renamed.
+    pub async fn diff(
+        path: &str,
+        from_commit: &str,
+        to_commit: &str,
+        config: Option<BundleConfig>,
+    ) -> Result<BundleDiff, BundlebaseError> {
+        let from = Bundle::open_at(path, from_commit, config.clone()).await?;
+        let to = Bundle::open_at(path, to_commit, config).await?;
+        Ok(BundleDiff::new(
+            from_commit,
+            to_commit,
+            &from.operations,
+            &to.operations,
+            from.schema().await?.as_ref(),
+            to.schema().await?.as_ref(),
+        ))
+    }
+
+    /// Opens a bundle from in-memory file contents, without touching a filesystem.
+    ///
+    /// `files` maps paths relative to the bundle root (e.g. `_bundlebase/00000000000000000.yaml`)
+    /// to their contents. The files are copied into a fresh `memory:///` location which is then
+    /// opened like any other bundle. Useful for embedding a small bundle in an application binary.
+    pub async fn from_bytes(
+        files: HashMap<String, bytes::Bytes>,
+        config: Option<BundleConfig>,
+    ) -> Result<Self, BundlebaseError> {
+        let url = Url::parse(&format!("memory:///embedded/{}", Uuid::new_v4()))?;
+        let data_dir = ObjectStoreDir::from_url(&url, BundleConfig::default().into())?;
+        for (path, contents) in files {
+            data_dir
+                .file(path.trim_start_matches('/'))?
+                .write(contents)
+                .await?;
+        }
+
+        Self::open(url.as_str(), config).await
+    }
+
+    /// Opens a bundle from the bytes of a tar archive, such as one written by `export_tar`.
+    pub async fn from_tar_bytes(
+        tar_bytes: &[u8],
+        config: Option<BundleConfig>,
+    ) -> Result<Self, BundlebaseError> {
+        use std::io::Read;
+
+        let mut files = HashMap::new();
+        let mut archive = tar::Archive::new(tar_bytes);
+        for entry in archive
+            .entries()
+            .map_err(|e| format!("Failed to read tar archive: {}", e))?
+        {
+            let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry
+                .path()
+                .map_err(|e| format!("Invalid tar entry path: {}", e))?
+                .to_string_lossy()
+                .to_string();
+            let mut contents = Vec::with_capacity(entry.size() as usize);
+            entry
+                .read_to_end(&mut contents)
+                .map_err(|e| format!("Failed to read tar entry '{}': {}", path, e))?;
+            files.insert(path, bytes::Bytes::from(contents));
+        }
+
+        Self::from_bytes(files, config).await
+    }
+
+    /// Returns true if another process has committed to this bundle's data directory since it
+    /// was opened. Only lists the manifest directory, so it is cheap to poll.
+    pub async fn has_new_commits(&self) -> Result<bool, BundlebaseError> {
+        let manifest_dir = self.data_dir.subdir(META_DIR)?;
+        let latest = Self::manifest_files(&manifest_dir)
+            .await?
+            .last()
+            .map(|f| manifest_version(f.filename()))
+            .unwrap_or(0);
+        Ok(latest > self.last_manifest_version)
+    }
+
+    /// Returns this bundle as of its latest commit, picking up commits made by other processes.
+    ///
+    /// If nothing new has been committed, this is a cheap clone. Otherwise the bundle is
+    /// reopened with the same config and registered functions. Blocks attached the same way
+    /// in both snapshots keep their existing reader and object store, only new or changed ones
+    /// are opened again. Either way `self` is left unchanged and keeps reflecting the snapshot
+    /// it was opened at.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let latest = bundle.reload().await?;
+    /// ```
+    pub async fn reload(&self) -> Result<Bundle, BundlebaseError> {
+        if !self.has_new_commits().await? {
+            return Ok(self.clone());
+        }
+
+        let mut bundle = Bundle::empty().await?;
+        bundle
+            .function_registry
+            .write()
+            .register_all(&self.function_registry.read());
+        bundle.passed_config = self.passed_config.clone();
+        bundle.recompute_config()?;
+
+        let blocks: HashMap<ObjectId, Arc<DataBlock>> = self
+            .data_packs
+            .read()
+            .values()
+            .flat_map(|pack| pack.blocks())
+            .map(|block| (*block.id(), block))
+            .collect();
+        bundle.reloaded_blocks = self
+            .operations
+            .iter()
+            .filter_map(|op| match op {
+                AnyOperation::AttachBlock(attach) => blocks
+                    .get(&attach.id)
+                    .map(|block| (attach.id, (attach.clone(), block.clone()))),
+                _ => None,
+            })
+            .collect();
+
+        Self::open_internal(
+            self.data_dir.url().as_str(),
+            &mut HashSet::new(),
+            &mut bundle,
+            None,
+            &[],
         )
         .await?;
+        bundle.reloaded_blocks.clear();
 
         Ok(bundle)
     }
 
-    /// Internal implementation of open() that tracks visited URLs to detect cycles
+    /// The reader of `attach`'s block in the snapshot being reloaded from, if the block was
+    /// attached the same way and with the same config there. Blocks whose schema is inferred
+    /// again are always reopened.
+    pub(crate) fn reloaded_reader(&self, attach: &AttachBlockOp) -> Option<Arc<dyn DataReader>> {
+        let (previous, block) = self.reloaded_blocks.get(&attach.id)?;
+        let reader = block.reader();
+        let unchanged = previous == attach
+            && block.config() == self.config.as_ref()
+            && !self.config.reinfer_schema(reader.url());
+        unchanged.then_some(reader)
+    }
+
+    /// Fixes a bundle whose parent or attached files moved, so it opens again.
+    ///
+    /// Each mapping replaces an old location prefix with the new one, in the `from` links to
+    /// parent bundles and in absolute paths of attached files, bundles and indexes. References
+    /// are checked to exist at their new location before a repair commit recording the mappings
+    /// is written to the bundle at `path`. The repaired bundle is returned.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let bundle = Bundle::repair_refs(
+    ///     "s3://bucket/child",
+    ///     vec![RefMapping::new("s3://old-bucket/parent", "s3://bucket/parent")],
+    ///     None,
+    /// ).await?;
+    /// ```
+    pub async fn repair_refs(
+        path: &str,
+        mappings: Vec<RefMapping>,
+        config: Option<BundleConfig>,
+    ) -> Result<Bundle, BundlebaseError> {
+        if mappings.is_empty() {
+            return Err("No reference mappings given".into());
+        }
+        let url = ObjectStoreDir::from_str(path, BundleConfig::default().into())?
+            .url()
+            .clone();
+
+        let mut bundle = Bundle::empty().await?;
+        bundle.passed_config = config;
+        bundle.recompute_config()?;
+        Self::open_internal(
+            url.as_str(),
+            &mut HashSet::new(),
+            &mut bundle,
+            None,
+            &mappings,
+        )
+        .await
+        .map_err(|e| format!("Bundle {} still can't be opened: {}", url, e))?;
+
+        // Opening found the parents, but attached files are only read when queried
+        let mut repaired = false;
+        let mut data_dirs: Vec<&Url> = bundle
+            .commits
+            .iter()
+            .filter_map(|c| c.data_dir.as_ref())
+            .collect();
+        data_dirs.dedup();
+        for data_dir in data_dirs {
+            let init_file = ObjectStoreDir::from_url(data_dir, bundle.config())?
+                .subdir(META_DIR)?
+                .file(INIT_FILENAME)?;
+            if let Some(contents) = init_file.read_str().await? {
+                let init: InitCommit = parse_manifest(data_dir.as_str(), INIT_FILENAME, &contents)?;
+                repaired |= init
+                    .from
+                    .is_some_and(|from| repair_ref(from.as_str(), &mappings).is_some());
+            }
+        }
+        for commit in &bundle.commits {
+            for mut op in commit.operations() {
+                if !repair_operation(&mut op, &mappings) {
+                    continue;
+                }
+                repaired = true;
+                if let AnyOperation::AttachBlock(attach) = &op {
+                    let file =
+                        ObjectStoreFile::from_url(&str_to_url(&attach.source)?, bundle.config())?;
+                    if !file.exists().await? {
+                        return Err(format!(
+                            "Attached file {} does not exist at its repaired location",
+                            attach.source
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+        if !repaired {
+            return Err(format!("None of the references in {} match the mappings", url).into());
+        }
+
+        let description = RepairRefsOp::setup(mappings.clone()).describe();
+        let mut builder = bundle.extend(None)?;
+        builder.record_repair_refs(mappings).await?;
+        builder.commit(&description).await?;
+        Ok(builder.bundle)
+    }
+
+    /// Internal implementation of open() that tracks visited URLs to detect cycles.
+    /// With `until` set, stops after applying that commit of this bundle, not its parents.
+    /// `repairs` are mappings for moved locations, added to those recorded by `repair_refs`.
     async fn open_internal(
         url: &str,
         visited: &mut HashSet<String>,
         bundle: &mut Bundle,
+        until: Option<&str>,
+        repairs: &[RefMapping],
     ) -> Result<(), BundlebaseError> {
         if !visited.insert(url.to_string()) {
             return Err(
@@ -269,9 +645,72 @@ impl Bundle {
 
         debug!("Loading initial commit from {}", INIT_FILENAME);
 
-        let init_commit: Option<InitCommit> = manifest_dir.file(INIT_FILENAME)?.read_yaml().await?;
-        let init_commit = init_commit
-            .expect(format!("No {}/{} found in {}", META_DIR, INIT_FILENAME, url).as_str());
+        let init_file = manifest_dir.file(INIT_FILENAME)?;
+        let init_commit: InitCommit = match init_file.read_str().await? {
+            Some(contents) => {
+                parse_manifest(url, &format!("{}/{}", META_DIR, INIT_FILENAME), &contents)?
+            }
+            None => return Err(Self::not_a_bundle_error(url, &data_dir).await?),
+        };
+
+        let manifest_files = Self::manifest_files(&manifest_dir).await?;
+        if manifest_files.is_empty() {
+            return Err(format!("No data bundle in: {}", url).into());
+        }
+
+        // Commits are read before the parent is loaded, since a repair commit can move it
+        let mut commits: Vec<(u32, BundleCommit)> = Vec::with_capacity(manifest_files.len());
+        for manifest_file in manifest_files {
+            let contents = manifest_file.read_str().await?.ok_or_else(|| {
+                format!(
+                    "Manifest {} disappeared while opening {}",
+                    manifest_file.url(),
+                    url
+                )
+            })?;
+            let mut commit: BundleCommit = parse_manifest(
+                url,
+                &format!("{}/{}", META_DIR, manifest_file.filename()),
+                &contents,
+            )?;
+            commit.id = manifest_commit_id(manifest_file.filename());
+            commit.url = Some(manifest_file.url().clone());
+            commit.data_dir = Some(data_dir.url().clone());
+            let version = manifest_version(manifest_file.filename());
+            if commits.last().is_some_and(|(last, _)| *last == version) {
+                return Err(format!(
+                    "Bundle at {} has more than one manifest for version {}",
+                    url, version
+                )
+                .into());
+            }
+            // An amending commit replaces the commit it amends, which is always the one before it
+            if let Some(amended) = &commit.amends {
+                match commits.last() {
+                    Some((_, previous)) if &previous.id == amended => {
+                        commits.pop();
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Commit {} in {} amends {}, which is not the commit before it",
+                            commit.id, url, amended
+                        )
+                        .into())
+                    }
+                }
+            }
+            commits.push((version, commit));
+        }
+
+        // This bundle's repairs apply to its parents too, after any it was opened with
+        let mut repairs = repairs.to_vec();
+        for (_, commit) in &commits {
+            for op in commit.operations() {
+                if let AnyOperation::RepairRefs(repair) = op {
+                    repairs.splice(0..0, repair.mappings);
+                }
+            }
+        }
 
         // Recursively load the base bundle and store the Arc reference
         // Handle views: if view field is set, load parent from "../"
@@ -298,9 +737,23 @@ impl Bundle {
             } else {
                 from_url.clone()
             };
+            let resolved_url = match repair_ref(resolved_url.as_str(), &repairs) {
+                Some(repaired) => {
+                    debug!("Parent {} moved to {}", resolved_url, repaired);
+                    Url::parse(&repaired)?
+                }
+                None => resolved_url,
+            };
 
             // Box the recursive call to avoid infinite future size
-            Box::pin(Self::open_internal(resolved_url.as_str(), visited, bundle)).await?;
+            Box::pin(Self::open_internal(
+                resolved_url.as_str(),
+                visited,
+                bundle,
+                None,
+                &repairs,
+            ))
+            .await?;
         };
 
         // Only set id if provided in init_commit
@@ -313,50 +766,14 @@ impl Bundle {
         // Mark this bundle as a view if it has a view field in the init commit
         bundle.is_view = init_commit.view.is_some();
 
-        // List files in the manifest directory
-        let manifest_files = manifest_dir.list_files().await?;
-
-        // Filter out init file AND files from subdirectories (like view_* directories)
-        // We only want files directly in the manifest directory
-        let manifest_dir_url_str = manifest_dir.url().to_string();
-        let manifest_files = manifest_files
-            .iter()
-            .filter(|x| {
-                let file_url = x.url().to_string();
-                // File should start with manifest dir URL
-                if !file_url.starts_with(&manifest_dir_url_str) {
-                    return false;
-                }
-                // Get the path after the manifest dir
-                let relative_path = &file_url[manifest_dir_url_str.len()..];
-                // Skip init file
-                if x.filename() == INIT_FILENAME {
-                    return false;
-                }
-                // Only include files directly in manifest dir (no "/" in relative path except leading one)
-                !relative_path.trim_start_matches('/').contains('/')
-            })
-            .collect::<Vec<_>>();
-
-        if manifest_files.is_empty() {
-            return Err(format!("No data bundle in: {}", url).into());
-        }
-
-        // Sort manifest files by version to ensure commits are loaded in chronological order
-        // ObjectStore.list() does not guarantee any particular ordering
-        let mut manifest_files = manifest_files.into_iter().cloned().collect::<Vec<_>>();
-        manifest_files.sort_by_key(|f| manifest_version(f.filename()));
-
-        // Load and apply each manifest in order
-        for manifest_file in manifest_files {
-            bundle.last_manifest_version = manifest_version(manifest_file.filename());
-            let mut commit: BundleCommit = manifest_file.read_yaml().await?.unwrap();
-            commit.url = Some(manifest_file.url().clone());
-            commit.data_dir = Some(data_dir.url().clone());
+        // Apply each manifest in order
+        for (version, commit) in commits {
+            bundle.last_manifest_version = version;
+            let last = until == Some(commit.id.as_str());
 
             debug!(
-                "Loading commit from {}: {} changes",
-                manifest_file.filename(),
+                "Loading commit {}: {} changes",
+                commit.id,
                 commit.changes.len()
             );
 
@@ -369,7 +786,10 @@ impl Bundle {
                     change.description,
                     change.operations.len()
                 );
-                for op in change.operations {
+                for mut op in change.operations {
+                    if repair_operation(&mut op, &repairs) {
+                        debug!("    Repaired reference: {}", op.describe());
+                    }
                     // Skip view-related operations when loading a view
                     if bundle.is_view {
                         match &op {
@@ -384,8 +804,81 @@ impl Bundle {
                     bundle.apply_operation(op).await?;
                 }
             }
+            if last {
+                return Ok(());
+            }
+        }
+        match until {
+            Some(commit_id) => Err(format!("No commit '{}' in {}", commit_id, url).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Lists the versioned manifests directly in `manifest_dir`, sorted by version.
+    /// The init and lock files and files in subdirectories (like view_* directories) are skipped.
+    async fn manifest_files(
+        manifest_dir: &ObjectStoreDir,
+    ) -> Result<Vec<ObjectStoreFile>, BundlebaseError> {
+        let manifest_files = manifest_dir.list_files().await?;
+
+        let manifest_dir_url_str = manifest_dir.url().to_string();
+        let mut manifest_files = manifest_files
+            .into_iter()
+            .filter(|x| {
+                let file_url = x.url().to_string();
+                // File should start with manifest dir URL
+                if !file_url.starts_with(&manifest_dir_url_str) {
+                    return false;
+                }
+                // Get the path after the manifest dir
+                let relative_path = &file_url[manifest_dir_url_str.len()..];
+                // Skip init and lock files, including the files claiming a stale lock's takeover
+                if x.filename() == INIT_FILENAME || x.filename().starts_with(LOCK_FILENAME) {
+                    return false;
+                }
+                // Only include files directly in manifest dir (no "/" in relative path except leading one)
+                !relative_path.trim_start_matches('/').contains('/')
+            })
+            .collect::<Vec<_>>();
+
+        // Sort manifest files by version to ensure commits are loaded in chronological order
+        // ObjectStore.list() does not guarantee any particular ordering
+        manifest_files.sort_by_key(|f| manifest_version(f.filename()));
+        Ok(manifest_files)
+    }
+
+    /// Builds the error for a location without an init commit, distinguishing a directory
+    /// that doesn't exist at all from an empty one and from one that doesn't contain a bundle.
+    /// Object stores have no directories, so there an empty prefix is reported as missing.
+    async fn not_a_bundle_error(
+        url: &str,
+        data_dir: &ObjectStoreDir,
+    ) -> Result<BundlebaseError, BundlebaseError> {
+        if !data_dir.list_files().await?.is_empty() {
+            return Ok(format!(
+                "Not a bundle: {} exists but has no {}/{}. Use --create to create a new bundle there",
+                url, META_DIR, INIT_FILENAME
+            )
+            .into());
+        }
+        let is_local_dir = data_dir.url().scheme() == "file"
+            && data_dir
+                .url()
+                .to_file_path()
+                .is_ok_and(|path| path.is_dir());
+        if is_local_dir {
+            Ok(format!(
+                "Not a bundle: {} is an empty directory. Use --create to create a new bundle there",
+                url
+            )
+            .into())
+        } else {
+            Ok(format!(
+                "Bundle directory does not exist: {}. Use --create to create a new bundle there",
+                url
+            )
+            .into())
         }
-        Ok(())
     }
 
     /// Creates a BundleBuilder that extends this bundle.
@@ -454,11 +947,51 @@ impl Bundle {
         self.data_packs.read().len()
     }
 
+    /// Columns declared as the bundle's primary key via `declare_key`, in key order.
+    /// Empty if no key has been declared.
+    pub fn key_columns(&self) -> &[String] {
+        &self.key_columns
+    }
+
     /// Check if this bundle is a view
     pub fn is_view(&self) -> bool {
         self.is_view
     }
 
+    /// Name of the view applied when the bundle is queried, if one was set with `set_default_view`
+    pub fn default_view(&self) -> Option<String> {
+        let view_id = self.default_view.as_ref()?;
+        self.views
+            .iter()
+            .find(|(_, id)| *id == view_id)
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Conditions set with `set_security_filter`, which every later operation and read of the
+    /// data is filtered by
+    pub fn security_filters(&self) -> &[String] {
+        &self.security_filters
+    }
+
+    /// Fails if a security filter uses `column`, so later operations can't `change` it (e.g.
+    /// "rename column") and leave it holding other values than the rows were filtered on
+    pub(crate) fn check_not_security_column(
+        &self,
+        column: &str,
+        change: &str,
+    ) -> Result<(), BundlebaseError> {
+        for where_clause in &self.security_filters {
+            if sql::referenced_columns(where_clause)?.contains(column) {
+                return Err(format!(
+                    "Cannot {} '{}': the security filter '{}' uses it",
+                    change, column, where_clause
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
     /// Modifies this bundle with the given operation
     async fn apply_operation(&mut self, op: AnyOperation) -> Result<(), BundlebaseError> {
         let description = &op.describe();
@@ -474,6 +1007,7 @@ impl Bundle {
         self.compute_version();
         // clear cached values
         self.dataframe.clear();
+        self.default_dataframe.clear();
         debug!("Cleared dataframe");
 
         debug!("Applying operation to bundle: {}...DONE", &description);
@@ -485,6 +1019,23 @@ impl Bundle {
         &self.data_dir
     }
 
+    /// Returns a single SQL query equivalent to this bundle's operations, reading directly from
+    /// the attached source files. Useful for reproducing the bundle's data outside bundlebase.
+    ///
+    /// Operations with no SQL equivalent are listed as `-- NOTE:` comments at the top.
+    pub fn to_sql(&self) -> String {
+        sql_export::bundle_to_sql(self)
+    }
+
+    /// Names of the bundles attached with `attach_bundle`, and the URL each one refers to
+    pub fn attached_bundles(&self) -> HashMap<String, String> {
+        self.external_bundles
+            .read()
+            .iter()
+            .map(|(name, external)| (name.clone(), external.url.clone()))
+            .collect()
+    }
+
     pub fn config(&self) -> Arc<BundleConfig> {
         Arc::clone(&self.config)
     }
@@ -512,6 +1063,24 @@ impl Bundle {
         let url = self.data_dir.url().clone();
         self.data_dir = ObjectStoreDir::from_url(&url, self.config.clone())?;
 
+        self.configure_runtime()?;
+
+        Ok(())
+    }
+
+    /// Applies the memory limit and spill settings from the config to the session's runtime,
+    /// and the time zone to the session. The config is also set as a session config extension
+    /// so operations can read it when applied to the dataframe.
+    fn configure_runtime(&self) -> Result<(), BundlebaseError> {
+        let time_zone = self.config.time_zone()?;
+        let state_ref = self.ctx.state_ref();
+        let mut state = state_ref.write();
+        let runtime = self.config.runtime_env(state.runtime_env())?;
+        *state = SessionStateBuilder::new_from_existing(state.clone())
+            .with_runtime_env(runtime)
+            .build();
+        state.config_mut().options_mut().execution.time_zone = Some(time_zone);
+        state.config_mut().set_extension(self.config.clone());
         Ok(())
     }
 
@@ -519,6 +1088,296 @@ impl Bundle {
         self.ctx.clone()
     }
 
+    /// The session context, for running queries directly against the bundle's `packs` and `blocks`
+    /// tables. Those tables aren't filtered by `set_security_filter`, so this fails if the bundle has one
+    pub fn query_ctx(&self) -> Result<Arc<SessionContext>, BundlebaseError> {
+        if !self.security_filters.is_empty() {
+            return Err(format!(
+                "{} has a security filter, which querying its session context directly would bypass. Use sql() instead",
+                self.url()
+            )
+            .into());
+        }
+        Ok(self.ctx())
+    }
+
+    /// Runs a SQL query against the bundle's current data and returns the result batches.
+    /// "SELECT" keyword in SQL is optional, and `$1`, `$2`... are replaced by `params`.
+    ///
+    /// Unlike `select()`, no operation is added: the data is registered as `bundle`, along with
+    /// any attached bundles, in a throwaway context sharing this bundle's runtime, so the bundle
+    /// is left unchanged.
+    pub async fn sql(
+        &self,
+        query: &str,
+        params: Vec<ScalarValue>,
+    ) -> Result<Vec<RecordBatch>, BundlebaseError> {
+        Ok(self.sql_dataframe(query, params).await?.collect().await?)
+    }
+
+    /// Like `sql()`, but `bundle` is the raw data, without the default view applied
+    pub async fn raw_sql(
+        &self,
+        query: &str,
+        params: Vec<ScalarValue>,
+    ) -> Result<Vec<RecordBatch>, BundlebaseError> {
+        Ok(self
+            .raw_sql_dataframe(query, params)
+            .await?
+            .collect()
+            .await?)
+    }
+
+    /// Like `sql()`, but returns the query's dataframe without executing it. Its schema is
+    /// available even when the query returns no rows.
+    pub async fn sql_dataframe(
+        &self,
+        query: &str,
+        params: Vec<ScalarValue>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let df = self.dataframe().await?.as_ref().clone();
+        self.sql_against(df, query, params).await
+    }
+
+    /// Like `sql_dataframe()`, but `bundle` is the raw data, without the default view applied
+    pub async fn raw_sql_dataframe(
+        &self,
+        query: &str,
+        params: Vec<ScalarValue>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let df = self.raw_dataframe().await?.as_ref().clone();
+        self.sql_against(df, query, params).await
+    }
+
+    async fn sql_against(
+        &self,
+        df: DataFrame,
+        query: &str,
+        params: Vec<ScalarValue>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let mut sql = sql::with_select_keyword(query);
+        for (i, param) in params.iter().enumerate() {
+            let placeholder = format!("${}", i + 1);
+            sql = sql.replace(&placeholder, &scalar_value_to_sql_literal(param));
+        }
+
+        let mut config = SessionConfig::new();
+        config.options_mut().sql_parser.enable_ident_normalization = false;
+        config.options_mut().execution.time_zone = Some(self.config.time_zone()?);
+        let ctx = SessionContext::new_with_config_rt(config, self.ctx.runtime_env());
+        for udf in self.function_registry.read().udfs() {
+            ctx.register_udf(udf.as_ref().clone());
+        }
+        ctx.catalog("datafusion")
+            .expect("Default catalog not found")
+            .register_schema(
+                "public",
+                Arc::new(BundleSchemaProvider::new(
+                    DataFrameHolder::new(Some(df)),
+                    self.external_bundles.clone(),
+                )),
+            )?;
+
+        Ok(ctx.sql(&sql).await?)
+    }
+
+    /// The bundle's data as built by its operations, without the default view applied
+    pub async fn raw_dataframe(&self) -> Result<Arc<DataFrame>, BundlebaseError> {
+        // Check cache first
+        if let Some(df) = self.dataframe.maybe_dataframe() {
+            debug!("dataframe: Using cached dataframe");
+            return Ok(df);
+        }
+
+        debug!("Building dataframe...");
+        let df = match self.base_pack {
+            Some(base_pack) => {
+                let table_name = format!("packs.{}", DataPack::table_name(&base_pack));
+                let mut df = self.ctx.table(&table_name).await?;
+
+                for pack_join in self.joins.values() {
+                    debug!("Executing join with pack {}", pack_join.pack_id());
+                    df = self.dataframe_join(df, pack_join).await?;
+                }
+
+                // Apply operations to the base DataFrame
+                debug!(
+                    "dataframe: Applying {} operations to dataframe...",
+                    self.operations().len()
+                );
+
+                for (i, op) in self.operations().iter().enumerate() {
+                    if self.reverted_operations.contains(&i) {
+                        debug!("Skipping reverted: {}", &op.describe());
+                        continue;
+                    }
+                    debug!("Applying to dataframe: {}", &op.describe());
+                    df = op.apply_dataframe(df, self.ctx.clone()).await?;
+                }
+                debug!(
+                    "dataframe: Applying {} operations to dataframe...DONE",
+                    self.operations().len()
+                );
+
+                // Declared nullability is carried into the plan, see `ConstrainedTable`
+                if !self.key_columns.is_empty() || !self.nullable_columns.is_empty() {
+                    let schema = self.constrained_schema(&df);
+                    df = self
+                        .ctx
+                        .read_table(Arc::new(ConstrainedTable::new(df, schema)))?;
+                }
+
+                df
+            }
+            None => {
+                debug!("No base pack, using empty dataframe");
+                DataFrame::new(
+                    self.ctx().state(),
+                    LogicalPlan::EmptyRelation(EmptyRelation {
+                        produce_one_row: false,
+                        schema: DFSchemaRef::new(DFSchema::empty()),
+                    }),
+                )
+            }
+        };
+        self.dataframe.replace(df);
+        debug!("Building dataframe...DONE");
+        Ok(self.dataframe.dataframe())
+    }
+
+    /// Filters `df` by each of the `security_filters` conditions
+    async fn apply_security_filters(
+        mut df: DataFrame,
+        security_filters: &[String],
+        ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        for where_clause in security_filters {
+            debug!("Applying security filter: {}", where_clause);
+            df = FilterOp::setup(where_clause, vec![])
+                .await?
+                .apply_dataframe(df, ctx.clone())
+                .await?;
+        }
+        Ok(df)
+    }
+
+    /// The raw dataframe with the default view applied on top
+    async fn default_view_dataframe(
+        &self,
+        view_id: &ObjectId,
+    ) -> Result<Arc<DataFrame>, BundlebaseError> {
+        if let Some(df) = self.default_dataframe.maybe_dataframe() {
+            return Ok(df);
+        }
+
+        let df = match self.materialized_views.get(view_id) {
+            Some(file) => {
+                self.read_parquet(&self.view_dir(view_id)?.file(file)?)
+                    .await?
+            }
+            None => self.lazy_view_dataframe(view_id).await?.as_ref().clone(),
+        };
+        Ok(self.default_dataframe.replace(df))
+    }
+
+    /// The raw dataframe with a view's operations applied on top, ignoring any materialized copy
+    pub(crate) async fn lazy_view_dataframe(
+        &self,
+        view_id: &ObjectId,
+    ) -> Result<Arc<DataFrame>, BundlebaseError> {
+        let mut df = self.raw_dataframe().await?.as_ref().clone();
+        for op in self.view_operations(view_id).await? {
+            debug!("Applying view operation: {}", &op.describe());
+            df = op.apply_dataframe(df, self.ctx.clone()).await?;
+        }
+        Ok(Arc::new(df))
+    }
+
+    /// Directory holding a view's manifests and materialized data
+    pub(crate) fn view_dir(&self, view_id: &ObjectId) -> Result<ObjectStoreDir, BundlebaseError> {
+        self.data_dir.subdir(&format!("view_{}", view_id))
+    }
+
+    /// Reads a parquet file written by the bundle, such as a materialized view
+    async fn read_parquet(&self, file: &ObjectStoreFile) -> Result<DataFrame, BundlebaseError> {
+        // Stores such as tar archives aren't registered globally, so register this file's store
+        let store_url = file.store_url()?;
+        if self.ctx.runtime_env().object_store(&store_url).is_err() {
+            self.ctx
+                .register_object_store(store_url.as_ref(), file.store());
+        }
+        Ok(self
+            .ctx
+            .read_parquet(file.url().as_str(), ParquetReadOptions::default())
+            .await?)
+    }
+
+    /// Operations a view adds on top of this bundle, read from the view's own manifests
+    async fn view_operations(
+        &self,
+        view_id: &ObjectId,
+    ) -> Result<Vec<AnyOperation>, BundlebaseError> {
+        let view_dir = self.view_dir(view_id)?;
+        let mut operations = vec![];
+        for manifest_file in Self::manifest_files(&view_dir.subdir(META_DIR)?).await? {
+            let contents = manifest_file.read_str().await?.ok_or_else(|| {
+                format!(
+                    "Manifest {} disappeared while reading view {}",
+                    manifest_file.url(),
+                    view_id
+                )
+            })?;
+            let commit: BundleCommit = parse_manifest(
+                view_dir.url().as_str(),
+                &format!("{}/{}", META_DIR, manifest_file.filename()),
+                &contents,
+            )?;
+            operations.extend(commit.changes.into_iter().flat_map(|c| c.operations));
+        }
+        Ok(operations)
+    }
+
+    /// Schema of the raw data, without the default view applied. Operations check against this.
+    pub(crate) async fn raw_schema(&self) -> Result<SchemaRef, BundlebaseError> {
+        Ok(self.constrained_schema(self.raw_dataframe().await?.as_ref()))
+    }
+
+    /// The dataframe's schema with declared constraints surfaced: key columns are never null
+    /// and are tagged with their position in the key, other columns take any declared nullability
+    fn constrained_schema(&self, df: &DataFrame) -> SchemaRef {
+        let schema = df.schema().as_arrow().clone();
+        if self.key_columns.is_empty() && self.nullable_columns.is_empty() {
+            return Arc::new(schema);
+        }
+
+        let fields: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let name = field.name();
+                match self.key_columns.iter().position(|k| k == name) {
+                    Some(idx) => {
+                        let mut metadata = field.metadata().clone();
+                        metadata.insert(KEY_FIELD_METADATA.to_string(), (idx + 1).to_string());
+                        field
+                            .as_ref()
+                            .clone()
+                            .with_nullable(false)
+                            .with_metadata(metadata)
+                    }
+                    None => match self.nullable_columns.get(name) {
+                        Some(nullable) => field.as_ref().clone().with_nullable(*nullable),
+                        None => field.as_ref().clone(),
+                    },
+                }
+            })
+            .collect();
+        Arc::new(arrow_schema::Schema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        ))
+    }
+
     pub async fn explain(&self) -> Result<String, BundlebaseError> {
         let mut result = String::new();
 
@@ -598,14 +1457,14 @@ impl Bundle {
         &self.indexes
     }
 
-    /// Check if an index already exists at the correct version
+    /// Check if an index on the columns already exists at the correct version
     pub(crate) fn get_index(
         &self,
-        column: &str,
+        columns: &[String],
         block: &VersionedBlockId,
     ) -> Option<Arc<IndexedBlocks>> {
         for index in self.indexes.read().iter() {
-            if index.column() == column {
+            if index.columns() == columns {
                 let indexed_blocks = index.indexed_blocks(block);
 
                 if indexed_blocks.is_some() {
@@ -659,10 +1518,70 @@ impl BundleFacade for Bundle {
         self.operations.clone()
     }
 
+    fn packs(&self) -> Vec<PackInfo> {
+        let mut packs: Vec<PackInfo> = vec![];
+        for op in &self.operations {
+            let AnyOperation::AttachBlock(attach) = op else {
+                continue;
+            };
+            let index = match packs.iter().position(|p| p.id == attach.pack_id) {
+                Some(index) => index,
+                None => {
+                    packs.push(PackInfo {
+                        id: attach.pack_id,
+                        blocks: vec![],
+                    });
+                    packs.len() - 1
+                }
+            };
+            let pack = &mut packs[index];
+
+            // Blocks attached before names were recorded get the name they would have had
+            let name = attach.name.clone().unwrap_or_else(|| {
+                block_name(&attach.source, &attach.version, pack.blocks.len() + 1)
+            });
+            let url = self.get_pack(&attach.pack_id).and_then(|data_pack| {
+                data_pack
+                    .blocks()
+                    .iter()
+                    .find(|block| block.id() == &attach.id)
+                    .map(|block| block.reader().url().clone())
+            });
+            pack.blocks.push(BlockInfo {
+                name,
+                id: attach.id,
+                source: attach.source.clone(),
+                url,
+                num_rows: attach.num_rows,
+                bytes: attach.bytes,
+            });
+        }
+        packs
+    }
+
+    fn block_stats(&self) -> HashMap<ObjectId, Vec<ColumnStats>> {
+        self.operations
+            .iter()
+            .filter_map(|op| match op {
+                AnyOperation::AttachBlock(attach) => {
+                    attach.stats.clone().map(|stats| (attach.id, stats))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn operations_with_label(&self, key: &str, value: &str) -> Vec<AnyOperation> {
+        self.commits
+            .iter()
+            .flat_map(|commit| commit.changes.iter())
+            .filter(|change| change.has_label(key, value))
+            .flat_map(|change| change.operations.clone())
+            .collect()
+    }
+
     async fn schema(&self) -> Result<SchemaRef, BundlebaseError> {
-        Ok(Arc::new(
-            self.dataframe().await?.schema().clone().as_arrow().clone(),
-        ))
+        Ok(self.constrained_schema(self.dataframe().await?.as_ref()))
     }
 
     async fn num_rows(&self) -> Result<usize, BundlebaseError> {
@@ -674,54 +1593,11 @@ impl BundleFacade for Bundle {
     }
 
     async fn dataframe(&self) -> Result<Arc<DataFrame>, BundlebaseError> {
-        // Check cache first
-        if let Some(df) = self.dataframe.maybe_dataframe() {
-            debug!("dataframe: Using cached dataframe");
-            return Ok(df);
+        match &self.default_view {
+            // A view's own dataframe is the default view's operations applied, don't reapply them
+            Some(view_id) if !self.is_view => self.default_view_dataframe(view_id).await,
+            _ => self.raw_dataframe().await,
         }
-
-        debug!("Building dataframe...");
-        let df = match self.base_pack {
-            Some(base_pack) => {
-                let table_name = format!("packs.{}", DataPack::table_name(&base_pack));
-                let mut df = self.ctx.table(&table_name).await?;
-
-                for (_, pack_join) in &self.joins {
-                    debug!("Executing join with pack {}", pack_join.pack_id());
-                    df = self.dataframe_join(df, pack_join).await?;
-                }
-
-                // Apply operations to the base DataFrame
-                debug!(
-                    "dataframe: Applying {} operations to dataframe...",
-                    self.operations().len()
-                );
-
-                for op in self.operations().iter() {
-                    debug!("Applying to dataframe: {}", &op.describe());
-                    df = op.apply_dataframe(df, self.ctx.clone()).await?;
-                }
-                debug!(
-                    "dataframe: Applying {} operations to dataframe...DONE",
-                    self.operations().len()
-                );
-
-                df
-            }
-            None => {
-                debug!("No base pack, using empty dataframe");
-                DataFrame::new(
-                    self.ctx().state(),
-                    LogicalPlan::EmptyRelation(EmptyRelation {
-                        produce_one_row: false,
-                        schema: DFSchemaRef::new(DFSchema::empty()),
-                    }),
-                )
-            }
-        };
-        self.dataframe.replace(df);
-        debug!("Building dataframe...DONE");
-        Ok(self.dataframe.dataframe())
     }
 
     async fn select(
@@ -738,26 +1614,63 @@ impl BundleFacade for Bundle {
         let (view_id, _name) = self.get_view_id_by_name_or_id(identifier)?;
 
         // Construct view path: view_{id}/
-        let view_path = self
-            .data_dir()
-            .subdir(&format!("view_{}", view_id))?
-            .url()
-            .to_string();
+        let view_dir = self.view_dir(&view_id)?;
+        let view_path = view_dir.url().to_string();
 
         // Open view as Bundle (automatically loads parent via FROM)
-        // Preserve explicit_config from current bundle
+        // Preserve explicit_config and registered functions from current bundle
         let config = self.passed_config.clone();
-        Bundle::open(&view_path, config).await
+        let functions = self.function_registry.read().clone();
+        let view = Bundle::open_with_functions(&view_path, config, &functions).await?;
+
+        // A materialized view reads its stored data rather than running its operations
+        if let Some(file) = self.materialized_views.get(&view_id) {
+            let df = view.read_parquet(&view_dir.file(file)?).await?;
+            view.dataframe.replace(df);
+        }
+        Ok(view)
     }
 
     fn views(&self) -> HashMap<ObjectId, String> {
         // Reverse the name->id HashMap to id->name
         self.views
             .iter()
-            .map(|(name, id)| (id.clone(), name.clone()))
+            .map(|(name, id)| (*id, name.clone()))
             .collect()
     }
 
+    async fn export_csv(
+        &self,
+        path: &str,
+        options: &CsvExportOptions,
+    ) -> Result<u64, BundlebaseError> {
+        let file = ObjectStoreFile::from_url(&str_to_url(path)?, self.config())?;
+        let df = self.dataframe().await?.as_ref().clone();
+        let rows = write_csv(df, &file, options).await?;
+        info!("Exported {} rows to {}", rows, file.url());
+        Ok(rows)
+    }
+
+    async fn export_json(
+        &self,
+        path: &str,
+        options: &JsonExportOptions,
+    ) -> Result<u64, BundlebaseError> {
+        let file = ObjectStoreFile::from_url(&str_to_url(path)?, self.config())?;
+        let df = self.dataframe().await?.as_ref().clone();
+        let rows = write_json(df, &file, options).await?;
+        info!("Exported {} rows to {}", rows, file.url());
+        Ok(rows)
+    }
+
+    async fn export_parquet(&self, path: &str) -> Result<u64, BundlebaseError> {
+        let file = ObjectStoreFile::from_url(&str_to_url(path)?, self.config())?;
+        let df = self.dataframe().await?.as_ref().clone();
+        let rows = write_parquet(df, &file, self.config().parquet_writer_properties()?).await?;
+        info!("Exported {} rows to {}", rows, file.url());
+        Ok(rows)
+    }
+
     async fn export_tar(&self, tar_path: &str) -> Result<String, BundlebaseError> {
         use futures::StreamExt;
         use std::fs::File;
@@ -791,6 +1704,11 @@ impl BundleFacade for Bundle {
             // Remove leading slash if present
             let relative_path = relative_path.trim_start_matches('/');
 
+            // The writer lock belongs to this process, not the exported bundle
+            if relative_path == format!("{}/{}", META_DIR, LOCK_FILENAME) {
+                continue;
+            }
+
             debug!("Adding file to tar: {}", relative_path);
 
             // Read file contents via stream
@@ -831,8 +1749,84 @@ impl BundleFacade for Bundle {
         info!("Exported bundle to tar archive: {}", tar_path);
         Ok(format!("Exported bundle to {}", tar_path))
     }
+
+    async fn export_changes(
+        &self,
+        since_commit: &str,
+        dest: &str,
+        include_removed: bool,
+    ) -> Result<ChangeExport, BundlebaseError> {
+        let history = self.history();
+        if !history.iter().any(|commit| commit.id == since_commit) {
+            return Err(format!(
+                "Commit '{}' is not in the history of {}, it may have been squashed away. Use export_csv for a full export instead",
+                since_commit,
+                self.url()
+            )
+            .into());
+        }
+        let commit = history
+            .last()
+            .map(|commit| commit.id.clone())
+            .unwrap_or_default();
+
+        let previous = Bundle::open_at(
+            self.url().as_str(),
+            since_commit,
+            self.passed_config.clone(),
+        )
+        .await?;
+        // Rows hidden by the current security filters must not show up as removed either
+        let before = Self::apply_security_filters(
+            previous.dataframe().await?.as_ref().clone(),
+            &self.security_filters,
+            previous.ctx(),
+        )
+        .await?;
+        let after = self.dataframe().await?.as_ref().clone();
+
+        let dest = ObjectStoreDir::from_url(&str_to_url(dest)?, self.config())?;
+        let export =
+            write_changes(before, after, &dest, include_removed, since_commit, &commit).await?;
+        info!(
+            "Exported {} added rows since commit {} to {}",
+            export.added_rows,
+            since_commit,
+            dest.url()
+        );
+        Ok(export)
+    }
+
+    async fn validate(&self) -> Result<Vec<ValidationWarning>, BundlebaseError> {
+        find_duplicate_content(self, None).await
+    }
+
+    async fn verify_schema(&self, expected: SchemaRef) -> Result<(), BundlebaseError> {
+        let differences = schema_differences(&expected, self.schema().await?.as_ref());
+        if differences.is_empty() {
+            return Ok(());
+        }
+        Err(format!(
+            "Schema of {} does not match the expected schema:\n  {}",
+            self.url(),
+            differences.join("\n  ")
+        )
+        .into())
+    }
+
+    async fn verify_schema_file(&self, path: &str) -> Result<(), BundlebaseError> {
+        let file = ObjectStoreFile::from_url(&str_to_url(path)?, self.config())?;
+        let json = file
+            .read_str()
+            .await?
+            .ok_or_else(|| format!("Schema file {} does not exist", file.url()))?;
+        self.verify_schema(schema_from_json(&json)?).await
+    }
 }
 
+/// Bundles attached by name, shared between a bundle and its schema provider
+pub(crate) type ExternalBundles = Arc<RwLock<HashMap<String, ExternalBundle>>>;
+
 #[derive(Debug)]
 pub struct DataFrameHolder {
     pub(crate) dataframe: Arc<RwLock<Option<Arc<DataFrame>>>>,
@@ -935,4 +1929,87 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_reload_keeps_unchanged_readers() -> Result<(), BundlebaseError> {
+        let url = crate::test_utils::random_memory_url();
+        let mut builder = crate::BundleBuilder::create(url.as_str(), None).await?;
+        builder
+            .attach(crate::test_utils::test_datafile("customers-0-100.csv"))
+            .await?;
+        builder.commit("Initial").await?;
+        let bundle = Bundle::open(url.as_str(), None).await?;
+
+        let mut other = Bundle::open(url.as_str(), None).await?.extend(None)?;
+        other
+            .attach(crate::test_utils::test_datafile("customers-101-150.csv"))
+            .await?;
+        other.commit("More customers").await?;
+
+        let readers = |bundle: &Bundle| -> HashMap<ObjectId, Arc<dyn DataReader>> {
+            bundle
+                .data_packs
+                .read()
+                .values()
+                .flat_map(|pack| pack.blocks())
+                .map(|block| (*block.id(), block.reader()))
+                .collect()
+        };
+        let reloaded = bundle.reload().await?;
+        let before = readers(&bundle);
+        let after = readers(&reloaded);
+        assert_eq!(1, before.len());
+        assert_eq!(2, after.len());
+        for (id, reader) in &before {
+            assert!(Arc::ptr_eq(reader, &after[id]));
+        }
+        assert!(reloaded.reloaded_blocks.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit_spills_aggregation() -> Result<(), BundlebaseError> {
+        let spill_dir = tempfile::tempdir()?;
+        let mut config = BundleConfig::new();
+        config.set(crate::bundle_config::MEMORY_LIMIT_MB, "2", None);
+        config.set(
+            crate::bundle_config::SPILL_DIR,
+            spill_dir.path().to_str().unwrap(),
+            None,
+        );
+
+        let builder = crate::BundleBuilder::create(
+            crate::test_utils::random_memory_url().as_str(),
+            Some(config),
+        )
+        .await?;
+        let ctx = builder.bundle.ctx();
+        assert!(matches!(
+            ctx.runtime_env().memory_pool.memory_limit(),
+            datafusion::execution::memory_pool::MemoryLimit::Finite(limit) if limit == 2 * 1024 * 1024
+        ));
+
+        // Far more distinct groups than fit in 2MB, so the aggregation has to spill to finish
+        let sql = "SELECT v % 250000 AS k, count(*) AS c FROM generate_series(1, 1000000) t(v) GROUP BY k";
+        let batches = ctx.sql(sql).await?.collect().await?;
+        let num_groups: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(250000, num_groups);
+
+        let analyzed = ctx
+            .sql(&format!("EXPLAIN ANALYZE {}", sql))
+            .await?
+            .collect()
+            .await?;
+        let plan = arrow::util::pretty::pretty_format_batches(&analyzed)?.to_string();
+        assert!(
+            regex::Regex::new(r"spill_count=[1-9]")
+                .unwrap()
+                .is_match(&plan),
+            "Expected aggregation to spill:\n{}",
+            plan
+        );
+
+        Ok(())
+    }
 }
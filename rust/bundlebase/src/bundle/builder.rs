@@ -1,35 +1,58 @@
 use crate::bundle::facade::BundleFacade;
 use crate::bundle::init::InitCommit;
-use crate::bundle::operation::SetNameOp;
-use crate::bundle::operation::{AnyOperation, SelectOp};
+use crate::bundle::operation::MaterializeViewOp;
+use crate::bundle::operation::RevertCommitOp;
+use crate::bundle::operation::UnionOp;
+use crate::bundle::operation::{
+    normalize_column_name, AttachBlockOp, AttachBundleOp, AttachSplit, ColumnAliasOp, CreateViewOp,
+    DefineFunctionOp, DefinePackOp, DeleteRowsOp, DropViewOp, FilterOp, JoinOp, MergeOp,
+    RebuildIndexOp, RemoveColumnsOp, RenameColumnOp, RenameViewOp, SchemaConstraintOp, SetConfigOp,
+    SetDescriptionOp,
+};
 use crate::bundle::operation::{
-    AttachBlockOp, CreateViewOp, DefineFunctionOp, DefinePackOp, DropViewOp, FilterOp, JoinOp,
-    RebuildIndexOp, RemoveColumnsOp, RenameColumnOp, RenameViewOp, SetConfigOp, SetDescriptionOp,
+    AddColumnOp, AggFunc, AggregateOp, CastColumnOp, DistinctOp, FillNullOp, LimitOp,
 };
+use crate::bundle::operation::{AnyOperation, SelectOp};
 use crate::bundle::operation::{BundleChange, IndexBlocksOp, Operation};
 use crate::bundle::operation::{CreateIndexOp, DropIndexOp, JoinTypeOption};
+use crate::bundle::operation::{MaskColumnOp, MaskStrategy, PivotAggregate, PivotOp, UnpivotOp};
+use crate::bundle::operation::{OrderByOp, SortDirection};
+use crate::bundle::operation::{RefMapping, RepairRefsOp};
+use crate::bundle::operation::{RegisterUdfOp, SetDefaultViewOp, SetNameOp, SetSecurityFilterOp};
+use crate::bundle::validation::find_duplicate_content;
+use crate::bundle::ValidationWarning;
 use crate::bundle::{commit, INIT_FILENAME, META_DIR};
-use crate::bundle::{sql, Bundle};
-use crate::data::{DataBlock, ObjectId, VersionedBlockId};
+use crate::bundle::{
+    sql, Bundle, ChangeExport, CsvExportOptions, JsonExportOptions, PackInfo, WriteLock,
+};
+use crate::data::{ColumnStats, DataBlock, DataPack, ObjectId, PackJoin, VersionedBlockId};
 use crate::functions::FunctionImpl;
+use crate::functions::FunctionRegistry;
 use crate::functions::FunctionSignature;
 use crate::index::IndexDefinition;
-use crate::io::ObjectStoreDir;
+use crate::io::{unescape_glob, GlobPattern, ObjectStoreDir, ObjectStoreFile};
+use crate::progress::ProgressScope;
 use crate::BundleConfig;
 use crate::BundlebaseError;
-use arrow_schema::SchemaRef;
+use arrow_schema::{DataType, SchemaRef};
 use async_trait::async_trait;
 use chrono::DateTime;
-use datafusion::prelude::DataFrame;
+use datafusion::functions_aggregate::expr_fn::count;
+use datafusion::logical_expr::ScalarUDF;
+use datafusion::prelude::{col, ident, lit, DataFrame};
 use datafusion::scalar::ScalarValue;
-use log::{debug, info};
+use futures::future::try_join_all;
+use futures::TryStreamExt;
+use log::{debug, info, warn};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::future::Future;
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 use std::pin::Pin;
 use std::sync::Arc;
+use tokio::sync::Semaphore;
 use url::Url;
+use uuid::Uuid;
 
 /// Format a system time as ISO8601 UTC string (e.g., "2024-01-01T12:34:56Z")
 fn to_iso(time: std::time::SystemTime) -> String {
@@ -70,6 +93,14 @@ impl BundleStatus {
         &self.changes
     }
 
+    /// Returns true if the change with the given id can still be reverted with `undo`.
+    ///
+    /// Every uncommitted change is undoable: `undo` reverts the newest change first, so older
+    /// ones are reached by calling it repeatedly. Committed changes are not.
+    pub fn is_undoable(&self, change_id: &Uuid) -> bool {
+        self.changes.iter().any(|change| &change.id == change_id)
+    }
+
     pub fn operations(&self) -> Vec<AnyOperation> {
         self.changes
             .iter()
@@ -137,6 +168,10 @@ pub struct BundleBuilder {
     pub bundle: Bundle,
     status: BundleStatus,
     in_progress_change: Option<BundleChange>,
+    /// Held while this builder has the bundle locked for writing, shared with clones
+    write_lock: Option<Arc<WriteLock>>,
+    /// Author recorded on commits, overriding the `author` config, see `with_author`
+    author: Option<String>,
 }
 
 impl Clone for BundleBuilder {
@@ -145,6 +180,8 @@ impl Clone for BundleBuilder {
             bundle: self.bundle.clone(),
             status: self.status.clone(),
             in_progress_change: self.in_progress_change.clone(),
+            write_lock: self.write_lock.clone(),
+            author: self.author.clone(),
         }
     }
 }
@@ -177,9 +214,107 @@ impl BundleBuilder {
             status: BundleStatus::new(),
             bundle: existing,
             in_progress_change: None,
+            write_lock: None,
+            author: None,
         })
     }
 
+    /// Creates a new bundle like `create()`, with the given function signatures and
+    /// implementations already registered so `function://` sources can be attached without
+    /// calling `define_function`/`set_impl` first.
+    ///
+    /// Only functions defined with `define_function` are recorded in the manifest. Reopen with
+    /// `Bundle::open_with_functions` passing the same registry to read the bundle again.
+    pub async fn create_with_functions(
+        path: &str,
+        config: Option<BundleConfig>,
+        functions: &FunctionRegistry,
+    ) -> Result<BundleBuilder, BundlebaseError> {
+        let builder = Self::create(path, config).await?;
+        builder
+            .bundle
+            .function_registry
+            .write()
+            .register_all(functions);
+        Ok(builder)
+    }
+
+    /// Creates a new bundle set up like an existing "template" bundle, without any of its data.
+    ///
+    /// The template's config, defined functions, indexed columns, declared key and column
+    /// nullability are copied into the new bundle and committed as its first commit, so bundles
+    /// created from the same template are defined the same way. Attached data, views and
+    /// transformations are not copied. Indexes are built once data is attached and `reindex()`
+    /// is called.
+    pub async fn create_from_template(
+        path: &str,
+        template: &str,
+        config: Option<BundleConfig>,
+    ) -> Result<BundleBuilder, BundlebaseError> {
+        let template_bundle = Bundle::open(template, config.clone()).await?;
+        let mut builder = Self::create(path, config).await?;
+
+        builder
+            .do_change(&format!("Copy definitions from {}", template), |builder| {
+                Box::pin(async move {
+                    for op in template_bundle.operations() {
+                        if matches!(
+                            op,
+                            AnyOperation::SetConfig(_) | AnyOperation::DefineFunction(_)
+                        ) {
+                            builder.apply_operation(op).await?;
+                        }
+                    }
+
+                    // Copy the template's current state rather than replaying its operations,
+                    // so dropped indexes and replaced keys are left behind
+                    let indexes: Vec<Vec<String>> = template_bundle
+                        .indexes()
+                        .read()
+                        .iter()
+                        .map(|index| index.columns().to_vec())
+                        .collect();
+                    for columns in indexes {
+                        let op = match columns.as_slice() {
+                            [column] => CreateIndexOp::setup(column).await?,
+                            columns => CreateIndexOp::setup_composite(columns).await?,
+                        };
+                        builder.apply_operation(op.into()).await?;
+                    }
+
+                    let key = template_bundle.key_columns();
+                    if !key.is_empty() {
+                        builder
+                            .apply_operation(
+                                SchemaConstraintOp::declare_key(
+                                    key.iter().map(|c| c.as_str()).collect(),
+                                )
+                                .into(),
+                            )
+                            .await?;
+                    }
+
+                    let mut nullable: Vec<_> = template_bundle.nullable_columns.iter().collect();
+                    nullable.sort();
+                    for (column, nullable) in nullable {
+                        builder
+                            .apply_operation(
+                                SchemaConstraintOp::set_nullable(column, *nullable).into(),
+                            )
+                            .await?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await?;
+        builder
+            .commit(&format!("Created from template {}", template))
+            .await?;
+
+        Ok(builder)
+    }
+
     pub fn extend(bundle: Arc<Bundle>, data_dir: Option<&str>) -> Result<BundleBuilder, BundlebaseError> {
         let mut new_bundle = bundle.deref().clone();
 
@@ -197,9 +332,63 @@ impl BundleBuilder {
             bundle: new_bundle,
             status: BundleStatus::new(),
             in_progress_change: None,
+            write_lock: None,
+            author: None,
         })
     }
 
+    /// Takes the bundle's advisory write lock, failing if another writer holds it.
+    ///
+    /// The lock is a file in the metadata directory recording this process's pid, hostname, the
+    /// time it was taken and a token unique to this lock. A lock older than the
+    /// `write_lock_stale_secs` setting is taken over.
+    /// It is released by `unlock()` or when the last clone of this builder is dropped.
+    /// Tar archives are append-only and can't hold a lock, so this does nothing for them, and
+    /// neither does it for object stores without conditional writes, such as plain HTTP.
+    pub async fn lock(&mut self) -> Result<&mut Self, BundlebaseError> {
+        if self.write_lock.is_some() {
+            return Ok(self);
+        }
+        if self.bundle.data_dir.is_tar() {
+            debug!("Not locking tar archive {}", self.bundle.url());
+            return Ok(self);
+        }
+
+        let lock = WriteLock::acquire(
+            &self.bundle.data_dir.subdir(META_DIR)?,
+            self.bundle.config().write_lock_stale_after()?,
+        )
+        .await;
+        match lock {
+            Ok(lock) => self.write_lock = Some(Arc::new(lock)),
+            Err(e) if Self::is_unsupported(e.as_ref()) => {
+                warn!(
+                    "Opening {} without a write lock, its object store can't create files only if they don't exist: {}",
+                    self.bundle.url(),
+                    e
+                );
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(self)
+    }
+
+    /// True if the error is an object store saying it doesn't support the request
+    fn is_unsupported(e: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+        matches!(
+            e.downcast_ref::<object_store::Error>(),
+            Some(object_store::Error::NotSupported { .. } | object_store::Error::NotImplemented)
+        )
+    }
+
+    /// Releases the write lock taken by `lock()`, if any
+    pub async fn unlock(&mut self) -> Result<(), BundlebaseError> {
+        if let Some(lock) = self.write_lock.take() {
+            lock.release().await?;
+        }
+        Ok(())
+    }
+
     /// The bundle being built
     pub fn bundle(&self) -> &Bundle {
         &self.bundle
@@ -210,11 +399,29 @@ impl BundleBuilder {
         &self.status
     }
 
+    /// Sets the author recorded on this builder's commits, instead of the one from the
+    /// `author` config or the environment.
+    pub fn with_author(&mut self, author: &str) -> &mut Self {
+        self.author = Some(author.to_string());
+        self
+    }
+
+    /// The author recorded on commits, see `with_author` and `BundleConfig::author`
+    pub(crate) fn author(&self) -> String {
+        self.author
+            .clone()
+            .unwrap_or_else(|| self.bundle.config().author())
+    }
+
     /// Commits all operations in the bundle to persistent storage.
     ///
     /// # Arguments
     /// * `message` - Human-readable description of the changes (e.g., "Filter to Q4 data")
     ///
+    /// If the bundle is stored in a `.tar` archive, attached files are copied into the archive
+    /// along with the new manifest so the archive stays self-contained. Earlier manifests remain
+    /// in the append-only archive and the latest one is used on open.
+    ///
     /// # Example
     /// bundle.attach("data.parquet").await?;
     /// bundle.filter("amount > 100").await?;
@@ -224,61 +431,225 @@ impl BundleBuilder {
         let manifest_dir = self.bundle.data_dir.subdir(META_DIR)?; //todo rename the dir
 
         if self.bundle.last_manifest_version == 0 {
+            // A retried first commit keeps the init file (and bundle id) written the first time
             let from = self.bundle.from();
             let init_file = manifest_dir.file(INIT_FILENAME)?;
-            init_file.write_yaml(&InitCommit::new(from)).await?;
+            let init = serde_yaml::to_string(&InitCommit::new(from))?;
+            self.write_once(&init_file, bytes::Bytes::from(init))
+                .await?;
         };
 
-        // Calculate next version number
-        let next_version = self.bundle.last_manifest_version + 1;
-
         // Get current timestamp in UTC ISO format
         let now = std::time::SystemTime::now();
         let timestamp = to_iso(now);
 
-        // Get author from environment or use default
-        let author = std::env::var("BUNDLEBASE_AUTHOR")
-            .unwrap_or_else(|_| std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()));
+        let author = self.author();
 
-        let changes = self.status.changes().clone();
+        let mut changes = self.status.changes().clone();
+        if self.bundle.data_dir.is_tar() {
+            self.pack_into_tar(&mut changes).await?;
+        }
 
         let commit_struct = commit::BundleCommit {
+            id: String::new(),
             url: None, //no need to set, we're just writing it and then will re-read it back
             data_dir: None,
+            format_version: commit::FORMAT_VERSION,
             message: message.to_string(),
             author,
             timestamp,
+            amends: None,
             changes,
         };
+        self.write_manifest(&commit_struct).await?;
 
-        // Serialize directly using serde_yaml
-        let yaml = serde_yaml::to_string(&commit_struct)?;
+        // Update base to reflect the committed version
+        // Preserve explicit_config and registered functions from current bundle
+        let config = self.bundle.passed_config.clone();
+        let functions = self.bundle.function_registry.read().clone();
+        self.bundle = Bundle::open_with_functions(self.url().as_str(), config, &functions).await?;
+        // Clear status since the operations have been persisted
+        self.status.clear();
 
-        // Calculate SHA256 hash of the YAML content
-        let mut hasher = Sha256::new();
-        hasher.update(yaml.as_bytes());
-        let hash_bytes = hasher.finalize();
-        let hash_hex = hex::encode(hash_bytes);
-        let hash_short = &hash_hex[..12];
+        info!("Committed version {}", self.bundle.version());
 
-        // Create versioned filename: {5-digit-version}{12-char-hash}.yaml
-        let filename = format!("{:05}{}.yaml", next_version, hash_short);
-        let manifest_file = manifest_dir.file(filename.as_str())?;
+        Ok(())
+    }
 
-        // Write as stream
-        let data = bytes::Bytes::from(yaml);
-        let stream = futures::stream::iter(vec![Ok::<_, std::io::Error>(data)]);
-        manifest_file.write_stream(stream).await?;
+    /// Folds the uncommitted changes into the last commit and, if given, replaces its message.
+    /// The history keeps the same length: the amended commit is written as a new manifest that
+    /// replaces the last one when the bundle is opened, which stays on disk. Like `commit`, this
+    /// fails if another writer committed since the bundle was opened.
+    ///
+    /// Commits inherited from the bundle this one extends can't be amended.
+    ///
+    /// # Example
+    /// bundle.commit("Filter high-value transactions").await?;
+    /// bundle.remove_columns(&["notes"]).await?;
+    /// bundle.amend(Some("Filter high-value transactions, drop notes")).await?;
+    /// ```
+    pub async fn amend(&mut self, new_message: Option<&str>) -> Result<&mut Self, BundlebaseError> {
+        let Some(head) = self.bundle.commits.last().cloned() else {
+            return Err("No commit to amend".into());
+        };
+        if head.data_dir.as_ref() != Some(self.url()) {
+            return Err(format!(
+                "Cannot amend commit '{}': it belongs to the bundle this one extends",
+                head.id
+            )
+            .into());
+        }
+        if self.status.is_empty() && new_message.is_none() {
+            return Err("Nothing to amend: there are no uncommitted changes or new message".into());
+        }
+
+        let mut new_changes = self.status.changes().clone();
+        if self.bundle.data_dir.is_tar() {
+            self.pack_into_tar(&mut new_changes).await?;
+        }
+        let mut changes = head.changes.clone();
+        changes.extend(new_changes);
+        let commit_struct = commit::BundleCommit {
+            id: String::new(),
+            url: None,
+            data_dir: None,
+            format_version: commit::FORMAT_VERSION,
+            message: new_message.unwrap_or(&head.message).to_string(),
+            author: head.author.clone(),
+            timestamp: to_iso(std::time::SystemTime::now()),
+            amends: Some(head.id.clone()),
+            changes,
+        };
+        self.write_manifest(&commit_struct).await?;
 
-        // Update base to reflect the committed version
-        // Preserve explicit_config from current bundle
         let config = self.bundle.passed_config.clone();
-        self.bundle = Bundle::open(self.url().as_str(), config).await?;
-        // Clear status since the operations have been persisted
+        let functions = self.bundle.function_registry.read().clone();
+        self.bundle = Bundle::open_with_functions(self.url().as_str(), config, &functions).await?;
         self.status.clear();
 
-        info!("Committed version {}", self.bundle.version());
+        info!("Amended version {}", self.bundle.version());
+
+        Ok(self)
+    }
+
+    /// Writes `commit_struct` as the manifest of the next version.
+    ///
+    /// The version is first claimed by writing the manifest to `versions/{version}.yaml` only if
+    /// no writer has claimed it yet, and then written under its hashed name. Retrying a commit that
+    /// already landed (e.g. after a network error) is a no-op that also finishes a commit that
+    /// failed between the two writes, but a different commit at this version means another writer
+    /// got there first
+    async fn write_manifest(
+        &self,
+        commit_struct: &commit::BundleCommit,
+    ) -> Result<(), BundlebaseError> {
+        let manifest_dir = self.bundle.data_dir.subdir(META_DIR)?;
+        let next_version = self.bundle.last_manifest_version + 1;
+        let yaml = serde_yaml::to_string(commit_struct)?;
+        let claim_file = manifest_dir
+            .subdir(commit::VERSIONS_DIR)?
+            .file(&format!("{:05}.yaml", next_version))?;
+
+        let yaml = if self
+            .write_once(&claim_file, bytes::Bytes::from(yaml.clone()))
+            .await?
+        {
+            yaml
+        } else {
+            let claimed = claim_file.read_str().await?.ok_or_else(|| {
+                format!(
+                    "Version {} of {} was claimed but its manifest disappeared",
+                    next_version,
+                    self.url()
+                )
+            })?;
+            let existing: commit::BundleCommit = commit::parse_manifest(
+                self.url().as_str(),
+                &format!(
+                    "{}/{}/{:05}.yaml",
+                    META_DIR,
+                    commit::VERSIONS_DIR,
+                    next_version
+                ),
+                &claimed,
+            )?;
+            let existing_ids = existing.changes.iter().map(|c| c.id);
+            if existing.amends != commit_struct.amends
+                || !existing_ids.eq(commit_struct.changes.iter().map(|c| c.id))
+            {
+                return Err(format!(
+                    "Version {} of {} was already committed by another writer. Reopen the bundle and apply your changes again",
+                    next_version,
+                    self.url()
+                )
+                .into());
+            }
+            info!(
+                "Version {} was already committed, not writing it again",
+                next_version
+            );
+            claimed
+        };
+
+        let manifest_file = manifest_dir.file(&Self::manifest_filename(next_version, &yaml))?;
+        self.write_once(&manifest_file, bytes::Bytes::from(yaml))
+            .await?;
+        Ok(())
+    }
+
+    /// Versioned manifest filename, `{5-digit-version}{12-char-hash}.yaml`, where the hash is the
+    /// start of the SHA256 of the manifest's YAML
+    fn manifest_filename(version: u32, yaml: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(yaml.as_bytes());
+        let hash_hex = hex::encode(hasher.finalize());
+        format!("{:05}{}.yaml", version, &hash_hex[..12])
+    }
+
+    /// Writes `data` to `file` unless it already exists, returning false if it did.
+    /// Tar archives are append-only with no conditional write, so they are checked first instead.
+    async fn write_once(
+        &self,
+        file: &ObjectStoreFile,
+        data: bytes::Bytes,
+    ) -> Result<bool, BundlebaseError> {
+        if self.bundle.data_dir.is_tar() {
+            if file.exists().await? {
+                return Ok(false);
+            }
+            file.write(data).await?;
+            return Ok(true);
+        }
+        file.write_if_not_exists(data).await
+    }
+
+    /// For bundles stored in a tar archive, copies externally attached files into the archive
+    /// and rewrites the attach operations to reference the copies by relative path.
+    /// This keeps tar bundles self-contained: each commit appends its data files and manifest.
+    async fn pack_into_tar(&self, changes: &mut [BundleChange]) -> Result<(), BundlebaseError> {
+        for change in changes.iter_mut() {
+            for op in change.operations.iter_mut() {
+                if let AnyOperation::AttachBlock(attach) = op {
+                    // Relative sources already live in the archive
+                    if !attach.source.contains(":") {
+                        continue;
+                    }
+
+                    let source = ObjectStoreFile::from_str(
+                        &attach.source,
+                        self.bundle.data_dir(),
+                        self.bundle.config(),
+                    )?;
+                    let packed_path = format!("blocks/{}-{}", attach.id, source.filename());
+                    source
+                        .copy_to(&self.bundle.data_dir().file(&packed_path)?)
+                        .await?;
 
+                    debug!("Packed {} into tar as {}", attach.source, packed_path);
+                    attach.source = packed_path;
+                }
+            }
+        }
         Ok(())
     }
 
@@ -341,19 +712,75 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Undoes an already committed change by recording and committing a revert of it, see
+    /// `BundleCommit::id`. The reverted commit stays in the history.
+    ///
+    /// Only commits that reshape the data, such as removing columns or filtering rows, can be
+    /// reverted. Commits that attach data or change views, indexes or other definitions return
+    /// an error, as do ones later changes depend on.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use bundlebase::{BundleBuilder, BundlebaseError, BundleFacade};
+    /// # async fn example() -> Result<(), BundlebaseError> {
+    /// # let mut bundle = BundleBuilder::create("memory:///example", None).await?;
+    /// bundle.remove_column("email").await?;
+    /// bundle.commit("Remove email").await?;
+    /// let commit_id = bundle.history().last().unwrap().id.clone();
+    /// bundle.revert_commit(&commit_id).await?; // email is back
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn revert_commit(&mut self, commit_id: &str) -> Result<&mut Self, BundlebaseError> {
+        if !self.status.is_empty() {
+            return Err(
+                "Cannot revert a commit with uncommitted changes, commit or reset first".into(),
+            );
+        }
+        let message = self
+            .bundle
+            .commits
+            .iter()
+            .find(|commit| commit.id == commit_id)
+            .map(|commit| commit.message.clone())
+            .ok_or_else(|| {
+                format!(
+                    "Commit '{}' is not in the history of this bundle",
+                    commit_id
+                )
+            })?;
+
+        let op = RevertCommitOp::setup(commit_id);
+        self.do_change(&format!("Revert commit {}", commit_id), |builder| {
+            Box::pin(async move {
+                op.check(&builder.bundle).await?;
+                op.check_later_operations(&builder.bundle).await?;
+                builder.apply_operation(op.into()).await?;
+                Ok(())
+            })
+        })
+        .await?;
+        self.commit(&format!("Revert \"{}\"", message)).await?;
+
+        info!("Reverted commit {}", commit_id);
+        Ok(self)
+    }
+
     async fn reload_bundle(&mut self) -> Result<(), BundlebaseError> {
         // Reload the bundle from the last committed state
         let empty = self.bundle.commits.is_empty();
+        let functions = self.bundle.function_registry.read().clone();
         self.bundle = if empty {
             let mut new = Bundle::empty().await?;
             new.passed_config = self.bundle.passed_config.clone();
             new.recompute_config()?;
             new.data_dir = ObjectStoreDir::from_url(self.url(), new.config.clone())?;
+            new.function_registry.write().register_all(&functions);
             new
         } else {
-            // Preserve explicit_config when reopening
+            // Preserve explicit_config and registered functions when reopening
             let config = self.bundle.passed_config.clone();
-            Bundle::open(self.url().as_str(), config).await?
+            Bundle::open_with_functions(self.url().as_str(), config, &functions).await?
         };
         Ok(())
     }
@@ -395,24 +822,28 @@ impl BundleBuilder {
         F: for<'a> FnOnce(&'a mut Self) -> BoxFuture<'a, Result<(), BundlebaseError>>,
     {
         // Check for nested changes
-        match &self.in_progress_change {
+        let nested = match &self.in_progress_change {
             Some(in_progress) => {
                 debug!(
                     "Change {} already in progress, not going to separately track {}",
                     in_progress.description, description
                 );
+                true
             }
             None => {
                 let change = BundleChange::new(description);
                 self.in_progress_change = Some(change);
+                false
             }
         };
 
         // Execute the closure
         let result = f(self).await;
 
-        // Move change to status on success, discard on error
+        // Move change to status on success, discard on error.
+        // A nested change leaves that to the change it is part of.
         match result {
+            Ok(_) if nested => Ok(()),
             Ok(_) => {
                 if let Some(change) = self.in_progress_change.take() {
                     self.status.changes.push(change);
@@ -427,34 +858,126 @@ impl BundleBuilder {
     }
 
     /// Attach a data block to the bundle
+    ///
+    /// If the path contains glob metacharacters (`*`, `?`, `[`), the directory before the first
+    /// metacharacter is listed and every matching file is attached as part of a single change.
+    /// Metacharacters can be escaped with a backslash to be treated literally.
+    /// Files larger than the `attach_split_mb` setting are scanned as several partitions.
     pub async fn attach(&mut self, path: &str) -> Result<&mut Self, BundlebaseError> {
+        self.attach_with_split(path, AttachSplit::Auto).await
+    }
+
+    /// Same as `attach`, but chooses how each attached file is split into partitions when scanned.
+    /// More partitions let a large file be read in parallel, while `Single` keeps it as one.
+    pub async fn attach_with_split(
+        &mut self,
+        path: &str,
+        split: AttachSplit,
+    ) -> Result<&mut Self, BundlebaseError> {
+        self.attach_files(path, split, false).await
+    }
+
+    /// Same as `attach`, but writes CSV and JSON files into the bundle as parquet instead of
+    /// reading them in place, so later scans get parquet's row group statistics and pruning.
+    ///
+    /// `split` chooses how many blocks each file is written as: `Single` writes one, `Parts(n)`
+    /// writes `n`, and `Auto` writes one per `attach_split_mb` of the file. Blocks are cut
+    /// between row groups of the `parquet_row_group_size` setting, and each records the file it
+    /// was written from. Parquet files are already in that form and are attached in place.
+    pub async fn attach_as_parquet(
+        &mut self,
+        path: &str,
+        split: AttachSplit,
+    ) -> Result<&mut Self, BundlebaseError> {
+        self.attach_files(path, split, true).await
+    }
+
+    /// Attaches the file or glob matches at `path`, converting them to parquet if `convert` is set
+    async fn attach_files(
+        &mut self,
+        path: &str,
+        split: AttachSplit,
+        convert: bool,
+    ) -> Result<&mut Self, BundlebaseError> {
         let path = path.to_string();
+        let operations_before = self.bundle.operations.len();
 
         self.do_change(&format!("Attach {}", path), |builder| {
             Box::pin(async move {
-                if builder.bundle.base_pack.is_none() {
-                    builder
-                        .apply_operation(DefinePackOp::setup(&ObjectId::generate()).await?.into())
-                        .await?;
-                    info!(
-                        "Created base pack {}",
-                        builder.bundle.base_pack.expect("Base pack not set")
-                    );
+                let files = match GlobPattern::parse(&path)? {
+                    Some(glob) => {
+                        let files = builder.find_glob_matches(&glob).await?;
+                        if files.is_empty() {
+                            return Err(format!("No files match {}", path).into());
+                        }
+                        files
+                    }
+                    None => vec![unescape_glob(&path)],
+                };
+                for file in files {
+                    if convert {
+                        builder.attach_converted(&file, split).await?;
+                    } else {
+                        builder.attach_block(&file, split, None).await?;
+                    }
                 }
+                builder.normalize_column_names().await?;
+
+                Ok(())
+            })
+        })
+        .await?;
+        self.warn_duplicate_blocks(operations_before).await;
+
+        Ok(self)
+    }
+
+    /// Logs a warning for each block attached after the first `operations_before` operations
+    /// whose content is identical to another block of its pack, see `validate`. The attach is
+    /// kept either way, so a failed check is only logged too.
+    async fn warn_duplicate_blocks(&self, operations_before: usize) {
+        let attached: HashSet<ObjectId> = self
+            .bundle
+            .operations
+            .iter()
+            .skip(operations_before)
+            .filter_map(|op| match op {
+                AnyOperation::AttachBlock(attach) => Some(attach.id),
+                _ => None,
+            })
+            .collect();
+        if attached.is_empty() {
+            return;
+        }
+        match find_duplicate_content(&self.bundle, Some(&attached)).await {
+            Ok(warnings) => warnings.iter().for_each(|warning| warn!("{}", warning)),
+            Err(e) => debug!("Could not check attached blocks for duplicates: {}", e),
+        }
+    }
+
+    /// Attaches only the given row groups of a parquet file, such as one date partition of a
+    /// large export, so scans never read the rest of it. Ranges may overlap and are checked
+    /// against the file's row groups when attached.
+    pub async fn attach_row_groups(
+        &mut self,
+        path: &str,
+        row_groups: &[Range<usize>],
+    ) -> Result<&mut Self, BundlebaseError> {
+        let path = path.to_string();
+        let row_groups: Vec<usize> = row_groups
+            .iter()
+            .cloned()
+            .flatten()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
 
+        self.do_change(&format!("Attach row groups of {}", path), |builder| {
+            Box::pin(async move {
                 builder
-                    .apply_operation(
-                        AttachBlockOp::setup(
-                            &builder.bundle.base_pack.expect("Base pack not set"),
-                            &path,
-                            builder,
-                        )
-                        .await?
-                        .into(),
-                    )
+                    .attach_block(&path, AttachSplit::Auto, Some(&row_groups))
                     .await?;
-
-                info!("Attached {} to bundle", path);
+                builder.normalize_column_names().await?;
 
                 Ok(())
             })
@@ -464,21 +987,202 @@ impl BundleBuilder {
         Ok(self)
     }
 
-    /// Attach a view from another BundleBuilder
+    /// Lists the files matching the glob, sorted by URL so the attach order is deterministic.
+    async fn find_glob_matches(&self, glob: &GlobPattern) -> Result<Vec<String>, BundlebaseError> {
+        let dir = if glob.base().contains(":") {
+            ObjectStoreDir::from_str(glob.base(), self.bundle.config())?
+        } else {
+            self.bundle.data_dir().subdir(glob.base())?
+        };
+        let dir_url = dir.url().to_string();
+
+        // Filter as the listing streams in so only the matches are held in memory
+        let mut matches: Vec<String> = dir
+            .list_files_stream()
+            .try_filter_map(|file| {
+                let url = file.url().to_string();
+                let relative = url
+                    .strip_prefix(&dir_url)
+                    .unwrap_or(&url)
+                    .trim_start_matches('/');
+                let matched = glob.matches(relative);
+                async move { Ok(matched.then_some(url)) }
+            })
+            .try_collect()
+            .await?;
+        matches.sort();
+        Ok(matches)
+    }
+
+    /// Attaches a single file to the base pack, creating the base pack if needed.
+    async fn attach_block(
+        &mut self,
+        path: &str,
+        split: AttachSplit,
+        row_groups: Option<&[usize]>,
+    ) -> Result<(), BundlebaseError> {
+        let base_pack = self.base_pack().await?;
+        let op = AttachBlockOp::setup(&base_pack, path, split, row_groups, self).await?;
+        self.apply_operation(op.into()).await?;
+
+        info!("Attached {} to bundle", path);
+
+        Ok(())
+    }
+
+    /// The base pack's id, creating the base pack if needed.
+    async fn base_pack(&mut self) -> Result<ObjectId, BundlebaseError> {
+        if self.bundle.base_pack.is_none() {
+            self.apply_operation(DefinePackOp::setup(&ObjectId::generate()).await?.into())
+                .await?;
+            info!(
+                "Created base pack {}",
+                self.bundle.base_pack.expect("Base pack not set")
+            );
+        }
+        Ok(self.bundle.base_pack.expect("Base pack not set"))
+    }
+
+    /// Writes a file into the bundle as parquet blocks and attaches each of them to the base pack,
+    /// see `attach_as_parquet`.
+    async fn attach_converted(
+        &mut self,
+        path: &str,
+        split: AttachSplit,
+    ) -> Result<(), BundlebaseError> {
+        let blocks = AttachBlockOp::convert_to_parquet(path, split, self).await?;
+        if blocks == [path] {
+            return self.attach_block(path, split, None).await;
+        }
+
+        let base_pack = self.base_pack().await?;
+        for block in blocks {
+            let mut op =
+                AttachBlockOp::setup(&base_pack, &block, AttachSplit::Single, None, self).await?;
+            op.converted_from = Some(path.to_string());
+            self.apply_operation(op.into()).await?;
+            info!("Attached {} to bundle as {}", path, block);
+        }
+        Ok(())
+    }
+
+    /// With the `normalize_column_names` config enabled, aliases every column whose name isn't
+    /// snake_case. Columns whose normalized name is already taken are left as they are.
+    async fn normalize_column_names(&mut self) -> Result<(), BundlebaseError> {
+        if !self.bundle.config().normalize_column_names()? {
+            return Ok(());
+        }
+
+        let schema = self.bundle.raw_schema().await?;
+        let mut taken: HashSet<String> = schema.fields().iter().map(|f| f.name().clone()).collect();
+        for field in schema.fields() {
+            let alias = normalize_column_name(field.name());
+            if alias.is_empty() || &alias == field.name() {
+                continue;
+            }
+            if !taken.insert(alias.clone()) {
+                warn!(
+                    "Not normalizing column '{}': '{}' already exists",
+                    field.name(),
+                    alias
+                );
+                continue;
+            }
+            self.apply_operation(ColumnAliasOp::setup(field.name(), &alias).into())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Attach another bundle as a read-only table named `name`.
     ///
-    /// Creates a named view that captures all uncommitted operations from the source BundleBuilder.
-    /// The view is stored in a subdirectory under view_{id}/ and automatically inherits
-    /// changes from the parent bundle through the FROM mechanism.
+    /// Nothing is copied: the referenced bundle is opened again whenever a query reads `name`,
+    /// so queries see its latest committed data. The reference is stored in the manifest and
+    /// re-linked on open. Queries fail if the referenced bundle is unavailable at the time.
     ///
-    /// # Arguments
-    /// * `name` - Name of the view
-    /// * `source` - BundleBuilder containing the operations to capture (typically from a select())
+    /// # Errors
+    /// Returns an error if the bundle cannot be opened, or if it references this bundle through
+    /// its own attached bundles.
     ///
     /// # Example
     /// ```no_run
     /// # use bundlebase::{BundleBuilder, BundlebaseError, BundleFacade};
     /// # async fn example() -> Result<(), BundlebaseError> {
-    /// let mut c = BundleBuilder::create("memory:///container", None).await?;
+    /// let mut c = BundleBuilder::create("memory:///orders", None).await?;
+    /// c.attach("orders.csv").await?;
+    /// c.attach_bundle("regions", "memory:///regions").await?;
+    /// c.select("select bundle.* from bundle join regions using (region_id)", vec![])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn attach_bundle(
+        &mut self,
+        name: &str,
+        bundle_url: &str,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let name = name.to_string();
+        let bundle_url = bundle_url.to_string();
+
+        self.do_change(
+            &format!("Attach bundle {} as '{}'", bundle_url, name),
+            |builder| {
+                Box::pin(async move {
+                    let op = AttachBundleOp::setup(
+                        &name,
+                        &bundle_url,
+                        builder.bundle.url().as_str(),
+                        builder.bundle.config(),
+                    )
+                    .await?;
+                    builder.apply_operation(op.into()).await?;
+                    info!("Attached bundle {} as \"{}\"", bundle_url, name);
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Append the rows of the bundle at `other_url`, which must have the same columns with the
+    /// same types (mutates self)
+    ///
+    /// Its files are not copied: the bundle is opened again whenever the rows are read, so they
+    /// follow its latest committed version. Operations made after the union apply to its rows
+    /// too.
+    pub async fn union(&mut self, other_url: &str) -> Result<&mut Self, BundlebaseError> {
+        let other_url = other_url.to_string();
+
+        self.do_change(&format!("Union with {}", other_url), |builder| {
+            Box::pin(async move {
+                let op = UnionOp::setup(&other_url, &builder.bundle).await?;
+                builder.apply_operation(op.into()).await?;
+                info!("Unioned with bundle {}", other_url);
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Attach a view from another BundleBuilder
+    ///
+    /// Creates a named view that captures all uncommitted operations from the source BundleBuilder.
+    /// The view is stored in a subdirectory under view_{id}/ and automatically inherits
+    /// changes from the parent bundle through the FROM mechanism.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the view
+    /// * `source` - BundleBuilder containing the operations to capture (typically from a select())
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use bundlebase::{BundleBuilder, BundlebaseError, BundleFacade};
+    /// # async fn example() -> Result<(), BundlebaseError> {
+    /// let mut c = BundleBuilder::create("memory:///container", None).await?;
     /// c.attach("data.csv").await?;
     /// c.commit("Initial").await?;
     ///
@@ -584,7 +1288,7 @@ impl BundleBuilder {
     /// Drop an existing view
     ///
     /// # Arguments
-    /// * `view_name` - The name of the view to drop
+    /// * `view` - The name or ID of the view to drop
     ///
     /// # Example
     /// ```no_run
@@ -599,19 +1303,176 @@ impl BundleBuilder {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn drop_view(
+    pub async fn drop_view(&mut self, view: &str) -> Result<&mut Self, BundlebaseError> {
+        let view = view.to_string();
+
+        self.do_change(&format!("Drop view '{}'", view), |builder| {
+            Box::pin(async move {
+                // Call setup() with bundle reference to look up view_id
+                let op = DropViewOp::setup(&view, &builder.bundle).await?;
+                builder.apply_operation(op.into()).await?;
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Store a view's data as a parquet file in the bundle, so reading the view no longer runs
+    /// its operations against the parent
+    ///
+    /// The stored data doesn't follow later changes to the parent, call `refresh_view` to update it.
+    ///
+    /// # Arguments
+    /// * `view` - The name or ID of the view
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use bundlebase::{BundleBuilder, BundlebaseError, BundleFacade};
+    /// # async fn example() -> Result<(), BundlebaseError> {
+    /// # let mut c = BundleBuilder::create("memory:///example", None).await?;
+    /// # c.attach("data.csv").await?;
+    /// let adults = c.select("select * from bundle where age > 21", vec![]).await?;
+    /// c.create_view("adults", &adults).await?;
+    /// c.materialize_view("adults").await?;
+    /// c.commit("Materialized view").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn materialize_view(&mut self, view: &str) -> Result<&mut Self, BundlebaseError> {
+        let view = view.to_string();
+
+        self.do_change(&format!("Materialize view '{}'", view), |builder| {
+            Box::pin(async move {
+                let op = MaterializeViewOp::setup(&view, &builder.bundle).await?;
+                builder.apply_operation(op.into()).await?;
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Update a materialized view's stored data from the parent's current data
+    ///
+    /// # Arguments
+    /// * `view` - The name or ID of a view materialized with `materialize_view`
+    pub async fn refresh_view(&mut self, view: &str) -> Result<&mut Self, BundlebaseError> {
+        let (view_id, name) = self.bundle.get_view_id_by_name_or_id(view)?;
+        if !self.bundle.materialized_views.contains_key(&view_id) {
+            return Err(format!(
+                "View '{}' is not materialized, use materialize_view first",
+                name
+            )
+            .into());
+        }
+
+        self.do_change(&format!("Refresh view '{}'", name), |builder| {
+            Box::pin(async move {
+                let op = MaterializeViewOp::setup(&view_id.to_string(), &builder.bundle).await?;
+                builder.apply_operation(op.into()).await?;
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Apply an existing view by default whenever the bundle is queried
+    ///
+    /// `dataframe()`, `num_rows()`, `sql()` and Flight queries see the view's data, while
+    /// `raw_dataframe()` and `raw_sql()` still see the bundle's own data. Dropping the view
+    /// also removes it as the default.
+    ///
+    /// # Arguments
+    /// * `view` - The name or ID of the view
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use bundlebase::{BundleBuilder, BundlebaseError, BundleFacade};
+    /// # async fn example() -> Result<(), BundlebaseError> {
+    /// # let mut c = BundleBuilder::create("memory:///example", None).await?;
+    /// # c.attach("data.csv").await?;
+    /// let adults = c.select("select * from bundle where age > 21", vec![]).await?;
+    /// c.create_view("adults", &adults).await?;
+    /// c.set_default_view("adults").await?;
+    /// c.commit("Show adults by default").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_default_view(&mut self, view: &str) -> Result<&mut Self, BundlebaseError> {
+        let view = view.to_string();
+
+        self.do_change(&format!("Set default view '{}'", view), |builder| {
+            Box::pin(async move {
+                let op = SetDefaultViewOp::setup(Some(&view), &builder.bundle)?;
+                builder.apply_operation(op.into()).await?;
+                info!("Set default view '{}'", view);
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Stop applying a default view, so queries see the bundle's own data again
+    pub async fn remove_default_view(&mut self) -> Result<&mut Self, BundlebaseError> {
+        if self.bundle.default_view.is_none() {
+            return Err("No default view is set".into());
+        }
+
+        self.do_change("Remove default view", |builder| {
+            Box::pin(async move {
+                let op = SetDefaultViewOp::setup(None, &builder.bundle)?;
+                builder.apply_operation(op.into()).await?;
+                info!("Removed default view");
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Always filter the bundle's rows by `where_clause`, no matter how the data is read
+    /// (mutates self)
+    ///
+    /// The condition is applied at this point in the operations, so every later operation sees
+    /// only the allowed rows, and `dataframe()`, `raw_dataframe()`, `sql()` and Flight queries all
+    /// run against them, so user SQL cannot get around it. Later operations can't rename, remove,
+    /// cast, mask, fill or redefine a column the filter uses. It is saved in the manifest and
+    /// cannot be removed; setting another security filter AND-s the two.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use bundlebase::{BundleBuilder, BundlebaseError};
+    /// # async fn example() -> Result<(), BundlebaseError> {
+    /// # let mut c = BundleBuilder::create("memory:///example", None).await?;
+    /// # c.attach("data.csv").await?;
+    /// c.set_security_filter("deleted = false").await?;
+    /// c.commit("Hide deleted rows").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_security_filter(
         &mut self,
-        view_name: &str,
+        where_clause: &str,
     ) -> Result<&mut Self, BundlebaseError> {
-        let view_name = view_name.to_string();
+        let where_clause = where_clause.to_string();
 
         self.do_change(
-            &format!("Drop view '{}'", view_name),
+            &format!("Set security filter {}", where_clause),
             |builder| {
                 Box::pin(async move {
-                    // Call setup() with bundle reference to look up view_id
-                    let op = DropViewOp::setup(&view_name, &builder.bundle).await?;
+                    let op = SetSecurityFilterOp::setup(&where_clause);
+                    op.check(&builder.bundle).await?;
+                    op.check_condition(&builder.bundle).await?;
                     builder.apply_operation(op.into()).await?;
+                    info!("Set security filter {}", where_clause);
                     Ok(())
                 })
             },
@@ -642,9 +1503,15 @@ impl BundleBuilder {
             Box::pin(async move {
                 builder
                     .apply_operation(
-                        AttachBlockOp::setup(&pack_join_id, &path, builder)
-                            .await?
-                            .into(),
+                        AttachBlockOp::setup(
+                            &pack_join_id,
+                            &path,
+                            AttachSplit::Auto,
+                            None,
+                            builder,
+                        )
+                        .await?
+                        .into(),
                     )
                     .await?;
 
@@ -656,6 +1523,56 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Add a column computed from a SQL expression over the other columns (mutates self),
+    /// e.g. `"First Name" || ' ' || "Last Name"`. The expression is evaluated each time the
+    /// bundle is read.
+    pub async fn add_column(
+        &mut self,
+        name: &str,
+        expression: &str,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let name = name.to_string();
+        let op = AddColumnOp::setup(&name, expression);
+
+        self.do_change(&format!("Add column {}", name), |builder| {
+            Box::pin(async move {
+                op.check(&builder.bundle).await?;
+                op.check_expression(&builder.bundle).await?;
+                builder.apply_operation(op.into()).await?;
+                info!("Added column {}", name);
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Convert a column to another type (mutates self), e.g. text read from a CSV to `Int64`.
+    /// A value that can't be converted is an error, or becomes null if `safe` is true.
+    pub async fn cast_column(
+        &mut self,
+        name: &str,
+        target: DataType,
+        safe: bool,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let name = name.to_string();
+        let op = CastColumnOp::setup(&name, target.clone(), safe);
+
+        self.do_change(&format!("Cast column {} to {}", name, target), |builder| {
+            Box::pin(async move {
+                op.check(&builder.bundle).await?;
+                op.check_values(&builder.bundle).await?;
+                builder.apply_operation(op.into()).await?;
+                info!("Cast column {} to {}", name, target);
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
     /// Remove a column (mutates self)
     pub async fn remove_column(&mut self, name: &str) -> Result<&mut Self, BundlebaseError> {
         let name = name.to_string();
@@ -704,6 +1621,221 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Expose a column under an alias, keeping its original name in the field metadata
+    /// (`bundlebase.original_name`) so it can still be traced back to the source data.
+    pub async fn column_alias(
+        &mut self,
+        original: &str,
+        alias: &str,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let original = original.to_string();
+        let alias = alias.to_string();
+
+        self.do_change(
+            &format!("Alias column '{}' as '{}'", original, alias),
+            |builder| {
+                Box::pin(async move {
+                    builder
+                        .apply_operation(ColumnAliasOp::setup(&original, &alias).into())
+                        .await?;
+                    info!("Aliased \"{}\" as \"{}\"", original, alias);
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Mask a sensitive column so everything read from the bundle only sees hashes or nulls in
+    /// its place. The attached blocks holding it are rewritten into the bundle with the column
+    /// masked, so data attached afterwards can't have the column. The `Encrypt` strategy needs
+    /// `column_encryption_key` in the config, and only readers configured with that same key see
+    /// the original values, as text. `Hash` uses an unkeyed SHA-256, so low-entropy values like
+    /// emails or phone numbers can be recovered by hashing guesses; it is not a pseudonym.
+    pub async fn mask_column(
+        &mut self,
+        column: &str,
+        strategy: MaskStrategy,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let column = column.to_string();
+
+        self.do_change(
+            &format!("Mask column '{}' with {}", column, strategy),
+            |builder| {
+                Box::pin(async move {
+                    let op = MaskColumnOp::setup(&column, strategy, &builder.bundle.config())?
+                        .rewrite_blocks(&builder.bundle)
+                        .await?;
+                    builder.apply_operation(op.into()).await?;
+                    info!("Masked \"{}\" with {}", column, strategy);
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Reshape from wide to long ("melt"): each row becomes one row per `value_columns` entry,
+    /// keeping `id_columns` and holding the column name in `var_name` and its value in `value_name`.
+    /// Value columns of differing types are converted to strings.
+    pub async fn unpivot(
+        &mut self,
+        id_columns: Vec<&str>,
+        value_columns: Vec<&str>,
+        var_name: &str,
+        value_name: &str,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let op = UnpivotOp::setup(id_columns, value_columns, var_name, value_name);
+
+        self.do_change(
+            &format!("Unpivot {}", op.value_columns.join(", ")),
+            |builder| {
+                Box::pin(async move {
+                    builder.apply_operation(op.into()).await?;
+                    info!("Unpivoted columns");
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Reshape from long to wide: one row per distinct `index` combination, with a column for
+    /// each distinct value of `columns` holding the `agg` of the matching `values`.
+    ///
+    /// The new columns are fixed to the values present now; values added later are left out.
+    /// Rows are grouped as they are read, so with the `memory_limit_mb` setting an `index` with
+    /// millions of distinct values spills to disk rather than running out of memory.
+    ///
+    /// # Errors
+    /// Returns an error if `columns` has more than `MAX_PIVOT_VALUES` distinct values.
+    pub async fn pivot(
+        &mut self,
+        index: Vec<&str>,
+        columns: &str,
+        values: &str,
+        agg: PivotAggregate,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let index = index.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let columns = columns.to_string();
+        let values = values.to_string();
+
+        self.do_change(
+            &format!("Pivot {}({}) by {}", agg, values, columns),
+            |builder| {
+                Box::pin(async move {
+                    let df = builder.bundle.raw_dataframe().await?;
+                    let index = index.iter().map(|c| c.as_str()).collect();
+                    let op = PivotOp::setup(index, &columns, &values, agg, &df).await?;
+                    let count = op.pivot_values.len();
+                    builder.apply_operation(op.into()).await?;
+                    info!("Pivoted \"{}\" into {} columns", columns, count);
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Group rows by the `group_by` columns, leaving one row per group with those columns and a
+    /// column for each `(column, function, alias)` in `aggregates`. Use `COUNT_ALL` ("*") as the
+    /// column to count all rows of a group. Without `group_by` the bundle becomes a single row.
+    ///
+    /// Filters and other operations made before apply to the rows being grouped; ones made after
+    /// apply to the groups.
+    pub async fn aggregate(
+        &mut self,
+        group_by: &[&str],
+        aggregates: &[(&str, AggFunc, &str)],
+    ) -> Result<&mut Self, BundlebaseError> {
+        let op = AggregateOp::setup(group_by, aggregates);
+        let description = if group_by.is_empty() {
+            "Aggregate".to_string()
+        } else {
+            format!("Aggregate by {}", group_by.join(", "))
+        };
+
+        self.do_change(&description, |builder| {
+            Box::pin(async move {
+                builder.apply_operation(op.into()).await?;
+                info!("Aggregated rows");
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Remove duplicate rows, or with `subset`, rows duplicated in those columns, keeping one
+    /// of each (mutates self). Which of the rows sharing a subset key is kept is not defined.
+    pub async fn distinct(
+        &mut self,
+        subset: Option<&[&str]>,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let description = match subset {
+            Some(subset) => format!("Remove rows duplicated in {}", subset.join(", ")),
+            None => "Remove duplicate rows".to_string(),
+        };
+        let op = DistinctOp::setup(subset);
+
+        self.do_change(&description, |builder| {
+            Box::pin(async move {
+                builder.apply_operation(op.into()).await?;
+                info!("Removed duplicate rows");
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Keep only the first `count` rows in the current order (mutates self)
+    pub async fn limit(&mut self, count: usize) -> Result<&mut Self, BundlebaseError> {
+        let op = LimitOp::setup(count);
+
+        self.do_change(&format!("Limit to {} rows", count), |builder| {
+            Box::pin(async move {
+                builder.apply_operation(op.into()).await?;
+                info!("Limited to {} rows", count);
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Replace the nulls in a column with `value`, read as the column's type (mutates self)
+    pub async fn fill_null(
+        &mut self,
+        column: &str,
+        value: &str,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let column = column.to_string();
+        let op = FillNullOp::setup(&column, value);
+
+        self.do_change(&format!("Fill nulls in {}", column), |builder| {
+            Box::pin(async move {
+                builder.apply_operation(op.into()).await?;
+                info!("Filled nulls in {}", column);
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
     /// Filter rows with a WHERE clause (mutates self)
     /// Parameters can be referenced as $1, $2, etc. in the WHERE clause.
     pub async fn filter(
@@ -718,7 +1850,58 @@ impl BundleBuilder {
                 builder
                     .apply_operation(FilterOp::setup(&where_clause, params).await?.into())
                     .await?;
-                info!("Filtered by {}", where_clause);
+                info!("Filtered by {}", where_clause);
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Sort rows by `keys`, each a column and the direction to sort it in (mutates self).
+    /// Later keys break ties left by earlier ones.
+    pub async fn order_by(
+        &mut self,
+        keys: &[(&str, SortDirection)],
+    ) -> Result<&mut Self, BundlebaseError> {
+        let op = OrderByOp::setup(keys);
+        let description = format!(
+            "Order by {}",
+            keys.iter()
+                .map(|(column, direction)| format!("{} {}", column, direction))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        self.do_change(&description, |builder| {
+            Box::pin(async move {
+                builder.apply_operation(op.into()).await?;
+                info!("Ordered rows");
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Delete rows matching a WHERE clause (mutates self)
+    /// Parameters can be referenced as $1, $2, etc. in the WHERE clause.
+    /// Rows where the clause evaluates to NULL are kept, as with SQL `DELETE`.
+    pub async fn delete_rows(
+        &mut self,
+        where_clause: &str,
+        params: Vec<ScalarValue>,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let where_clause = where_clause.to_string();
+
+        self.do_change(&format!("Delete where: {}", where_clause), |builder| {
+            Box::pin(async move {
+                builder
+                    .apply_operation(DeleteRowsOp::setup(&where_clause, params).await?.into())
+                    .await?;
+                info!("Deleted rows where {}", where_clause);
                 Ok(())
             })
         })
@@ -750,13 +1933,27 @@ impl BundleBuilder {
                 // Step 2: Attach the source data to the join pack
                 builder
                     .apply_operation(
-                        AttachBlockOp::setup(&join_pack_id, &source, builder)
-                            .await?
-                            .into(),
+                        AttachBlockOp::setup(
+                            &join_pack_id,
+                            &source,
+                            AttachSplit::Auto,
+                            None,
+                            builder,
+                        )
+                        .await?
+                        .into(),
                     )
                     .await?;
 
-                // Step 3: Create JoinOp that references the pack
+                // Step 3: Index the bundle's join keys, before the joined columns can make
+                // their names ambiguous
+                if builder.bundle.config().auto_index_join_keys()? {
+                    builder
+                        .index_join_keys(&name, &join_pack_id, &expression, &join_type)
+                        .await?;
+                }
+
+                // Step 4: Create JoinOp that references the pack
                 builder
                     .apply_operation(
                         JoinOp::setup(&name, join_pack_id, &expression, join_type, builder)
@@ -775,6 +1972,211 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Indexes the bundle's columns compared to the joined pack in `expression` that aren't
+    /// indexed already, recording the usual `CREATE INDEX` and `INDEX BLOCKS` operations.
+    async fn index_join_keys(
+        &mut self,
+        name: &str,
+        pack_id: &ObjectId,
+        expression: &str,
+        join_type: &JoinTypeOption,
+    ) -> Result<(), BundlebaseError> {
+        let Some(base_pack) = self.bundle.base_pack() else {
+            return Ok(());
+        };
+        let base_table = format!("packs.{}", DataPack::table_name(&base_pack));
+        let join = PackJoin::new(pack_id, name, join_type, expression);
+        let schema = self.bundle.schema().await?;
+
+        for column in sql::join_key_columns(&self.bundle.ctx, &base_table, &join).await? {
+            let indexed = self
+                .bundle
+                .indexes()
+                .read()
+                .iter()
+                .any(|index| index.column() == column.as_str());
+            // Columns renamed or dropped since they were attached can't be indexed by name
+            if indexed || schema.field_with_name(&column).is_err() {
+                continue;
+            }
+            self.index(&column).await?;
+        }
+        Ok(())
+    }
+
+    /// Merge a data source into the bundle, keyed by the given columns (mutates self)
+    ///
+    /// Existing rows with a key matching a row in the source are replaced by the source row,
+    /// and source rows with new keys are added, so re-merging updated data doesn't double-count.
+    /// The source must have the same columns as the bundle, and each of its rows a key without
+    /// nulls that no other source row shares. Existing rows with a null key are always kept.
+    /// If `key_columns` is empty, the key declared with `declare_key` is used.
+    pub async fn merge(
+        &mut self,
+        source: &str,
+        key_columns: Vec<&str>,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let source = source.to_string();
+        let key_columns = if key_columns.is_empty() {
+            self.bundle.key_columns().to_vec()
+        } else {
+            key_columns.iter().map(|c| c.to_string()).collect()
+        };
+        if key_columns.is_empty() {
+            return Err(
+                "Merge requires key columns. Pass them explicitly or declare a key with declare_key"
+                    .into(),
+            );
+        }
+
+        self.do_change(
+            &format!("Merge {} on {}", source, key_columns.join(", ")),
+            |builder| {
+                Box::pin(async move {
+                    // Merged data goes in its own pack so it can be compared against the existing rows
+                    let merge_pack_id = ObjectId::generate();
+                    builder
+                        .apply_operation(DefinePackOp::setup(&merge_pack_id).await?.into())
+                        .await?;
+
+                    builder
+                        .apply_operation(
+                            AttachBlockOp::setup(
+                                &merge_pack_id,
+                                &source,
+                                AttachSplit::Auto,
+                                None,
+                                builder,
+                            )
+                            .await?
+                            .into(),
+                        )
+                        .await?;
+
+                    let key_columns = key_columns.iter().map(|c| c.as_str()).collect();
+                    let op = MergeOp::setup(&merge_pack_id, key_columns).await?;
+                    op.check(&builder.bundle).await?;
+                    op.check_merged_keys(&builder.bundle).await?;
+                    builder.apply_operation(op.into()).await?;
+
+                    info!("Merged {}", source);
+
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Declare the columns that uniquely identify a row (mutates self)
+    ///
+    /// The key is stored in the manifest, marked on the key fields in `schema()`, and used by
+    /// `merge` when no key columns are passed. If `verify_unique` is set, the current data is
+    /// checked and an error is returned if any key value appears in more than one row.
+    pub async fn declare_key(
+        &mut self,
+        columns: Vec<&str>,
+        verify_unique: bool,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let columns = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        self.do_change(
+            &format!("Declare key ({})", columns.join(", ")),
+            |builder| {
+                Box::pin(async move {
+                    let op = SchemaConstraintOp::declare_key(
+                        columns.iter().map(|c| c.as_str()).collect(),
+                    );
+                    op.check(&builder.bundle).await?;
+                    op.check_no_nulls(&builder.bundle).await?;
+
+                    if verify_unique {
+                        let duplicates = builder
+                            .dataframe()
+                            .await?
+                            .as_ref()
+                            .clone()
+                            .aggregate(
+                                columns.iter().map(ident).collect(),
+                                vec![count(lit(1)).alias("rows")],
+                            )?
+                            .filter(col("rows").gt(lit(1)))?
+                            .count()
+                            .await?;
+                        if duplicates > 0 {
+                            return Err(format!(
+                                "Cannot declare key ({}): {} key value(s) appear in more than one row",
+                                columns.join(", "),
+                                duplicates
+                            )
+                            .into());
+                        }
+                    }
+
+                    builder.apply_operation(op.into()).await?;
+                    info!("Declared key ({})", columns.join(", "));
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Declare whether a column may contain nulls (mutates self)
+    ///
+    /// Overrides the nullability reported by the attached data in `schema()`.
+    pub async fn set_nullable(
+        &mut self,
+        column: &str,
+        nullable: bool,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let column = column.to_string();
+
+        self.do_change(
+            &format!(
+                "Set column '{}' {}",
+                column,
+                if nullable { "nullable" } else { "not null" }
+            ),
+            |builder| {
+                Box::pin(async move {
+                    let op = SchemaConstraintOp::set_nullable(&column, nullable);
+                    op.check(&builder.bundle).await?;
+                    op.check_no_nulls(&builder.bundle).await?;
+                    builder.apply_operation(op.into()).await?;
+                    info!("Set nullability of \"{}\" to {}", column, nullable);
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
+    /// Label the most recent uncommitted change with `key=value` (mutates self)
+    ///
+    /// Labels are stored with the change in the manifest and can be used to find its
+    /// operations later via `operations_with_label`. Setting an existing key replaces its value.
+    pub async fn label(&mut self, key: &str, value: &str) -> Result<&mut Self, BundlebaseError> {
+        let change = self
+            .status
+            .changes
+            .last_mut()
+            .ok_or("No uncommitted change to label. Make a change before labeling it")?;
+        change.labels.insert(key.to_string(), value.to_string());
+        debug!(
+            "Labeled change '{}' with {}={}",
+            change.description, key, value
+        );
+
+        Ok(self)
+    }
+
     /// Define a custom function (mutates self)
     pub async fn define_function(
         &mut self,
@@ -805,6 +2207,32 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Registers a scalar UDF so it can be called by its name in `filter`, `select` and query
+    /// expressions (mutates self)
+    ///
+    /// Only the UDF's name is recorded in the manifest since its code can't be saved. To reopen
+    /// the bundle, register the same UDF on a `FunctionRegistry` and pass it to
+    /// `Bundle::open_with_functions`, otherwise opening fails naming the missing UDF.
+    pub async fn register_udf(&mut self, udf: ScalarUDF) -> Result<&mut Self, BundlebaseError> {
+        let name = udf.name().to_string();
+        self.bundle.function_registry.write().register_udf(udf);
+
+        self.do_change(&format!("Register UDF {}", name), |builder| {
+            Box::pin(async move {
+                builder
+                    .apply_operation(RegisterUdfOp::setup(&name).into())
+                    .await?;
+
+                info!("Registered UDF {}", name);
+
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
     /// Set the bundle's name (mutates self)
     pub async fn set_name(&mut self, name: &str) -> Result<&mut Self, BundlebaseError> {
         let name = name.to_string();
@@ -842,6 +2270,25 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Records mappings for moved locations, see `Bundle::repair_refs`
+    pub(crate) async fn record_repair_refs(
+        &mut self,
+        mappings: Vec<RefMapping>,
+    ) -> Result<&mut Self, BundlebaseError> {
+        let op = RepairRefsOp::setup(mappings);
+        op.check(&self.bundle).await?;
+
+        self.do_change(&op.describe(), |builder| {
+            Box::pin(async move {
+                builder.apply_operation(op.into()).await?;
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
     /// Set a configuration value (mutates self)
     ///
     /// Config stored via this operation has the lowest priority:
@@ -905,19 +2352,61 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Create one index keyed on the values of several columns together, in the given order.
+    ///
+    /// Filters with equalities on all the columns, or on just the leading ones, can use it. Drop
+    /// it with `drop_index_composite`, passing the same columns.
+    pub async fn index_composite(
+        &mut self,
+        columns: &[&str],
+    ) -> Result<&mut Self, BundlebaseError> {
+        let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+        let description = format!("Index columns {}", columns.join(", "));
+
+        self.do_change(&description, |builder| {
+            Box::pin(async move {
+                builder
+                    .apply_operation(CreateIndexOp::setup_composite(&columns).await?.into())
+                    .await?;
+
+                builder.reindex().await?;
+
+                info!("Created index on: \"{}\"", columns.join(", "));
+
+                Ok(())
+            })
+        })
+        .await?;
+
+        Ok(self)
+    }
+
     /// Drop an index on a column
     pub async fn drop_index(&mut self, column: &str) -> Result<&mut Self, BundlebaseError> {
-        let column = column.to_string();
+        self.drop_index_on(vec![column.to_string()]).await
+    }
+
+    /// Drop a composite index, given its columns in key order
+    pub async fn drop_index_composite(
+        &mut self,
+        columns: &[&str],
+    ) -> Result<&mut Self, BundlebaseError> {
+        self.drop_index_on(columns.iter().map(|c| c.to_string()).collect())
+            .await
+    }
+
+    async fn drop_index_on(&mut self, columns: Vec<String>) -> Result<&mut Self, BundlebaseError> {
+        let column = columns.join(", ");
 
         self.do_change(&format!("Drop index on column {}", column), |builder| {
             Box::pin(async move {
-                // Find the index ID for the given column
+                // Find the index ID for the given columns
                 let index_id = {
                     let indexes = builder.bundle.indexes().read();
-                    let index = indexes.iter().find(|idx| idx.column() == column.as_str());
+                    let index = indexes.iter().find(|idx| idx.columns() == columns);
 
                     match index {
-                        Some(idx) => idx.id().clone(),
+                        Some(idx) => *idx.id(),
                         None => {
                             return Err(format!("No index found for column '{}'", column).into());
                         }
@@ -963,48 +2452,69 @@ impl BundleBuilder {
 
         self.do_change("Reindex", |builder| {
             Box::pin(async move {
-                // Group blocks by (index_id, column_name) for batching
-                let mut blocks_to_index: HashMap<(ObjectId, String), Vec<(ObjectId, String)>> =
-                    HashMap::new();
+                // Group blocks by (index_id, column names) for batching, in discovery order
+                type BlockGroup = ((ObjectId, Vec<String>), Vec<(ObjectId, String)>);
+                let mut blocks_to_index: Vec<BlockGroup> = Vec::new();
 
                 // Ensure dataframe is set up for queries
-                let df = builder.dataframe().await?;
+                let df = builder.bundle.raw_dataframe().await?;
 
                 // Collect index definitions before the loop to avoid holding the lock across awaits
                 let index_defs: Vec<Arc<IndexDefinition>> =
                     builder.bundle.indexes.read().iter().cloned().collect();
 
                 for index_def in &index_defs {
-                    let logical_col = index_def.column().to_string();
                     let index_id = index_def.id();
-                    debug!("Checking index on {}", &logical_col);
 
-                    // Pass data_packs to expand pack tables into block tables
-                    let sources = match sql::column_sources_from_df(
-                        logical_col.as_str(),
-                        &df,
-                        Some(&builder.bundle.data_packs),
-                    )
-                    .await
-                    {
-                        Ok(Some(s)) => s,
-                        Ok(None) => {
-                            return Err(format!(
-                                "No physical sources found for column '{}'",
-                                logical_col
-                            )
-                            .into());
-                        }
-                        Err(e) => {
-                            return Err(format!(
-                                "Failed to find source for column '{}': {}",
-                                logical_col, e
-                            )
-                            .into());
+                    // The physical columns of each block that has all the indexed columns
+                    let mut sources: Vec<(String, Vec<String>)> = Vec::new();
+                    for (position, logical_col) in index_def.columns().iter().enumerate() {
+                        debug!("Checking index on {}", logical_col);
+
+                        // Pass data_packs to expand pack tables into block tables
+                        let col_sources = match sql::column_sources_from_df(
+                            logical_col.as_str(),
+                            &df,
+                            Some(&builder.bundle.data_packs),
+                        )
+                        .await
+                        {
+                            Ok(Some(s)) => s,
+                            Ok(None) => {
+                                return Err(format!(
+                                    "No physical sources found for column '{}'",
+                                    logical_col
+                                )
+                                .into());
+                            }
+                            Err(e) => {
+                                return Err(format!(
+                                    "Failed to find source for column '{}': {}",
+                                    logical_col, e
+                                )
+                                .into());
+                            }
+                        };
+
+                        if position == 0 {
+                            sources = col_sources
+                                .into_iter()
+                                .map(|(table, col)| (table, vec![col]))
+                                .collect();
+                        } else {
+                            sources.retain_mut(|(table, cols)| {
+                                match col_sources.iter().find(|(t, _)| t == table) {
+                                    Some((_, col)) => {
+                                        cols.push(col.clone());
+                                        true
+                                    }
+                                    None => false,
+                                }
+                            });
                         }
-                    };
+                    }
 
-                    for (source_table, source_col) in sources {
+                    for (source_table, source_cols) in sources {
                         // Extract block ID from table name "blocks.__block_{hex_id}"
                         let block_id = DataBlock::parse_id(&source_table).ok_or_else(|| {
                             BundlebaseError::from(format!("Invalid table: {}", source_table))
@@ -1024,36 +2534,52 @@ impl BundleBuilder {
                             VersionedBlockId::new(block_id.clone(), block_version.clone());
                         let needs_index = builder
                             .bundle()
-                            .get_index(&source_col, &versioned_block)
+                            .get_index(&source_cols, &versioned_block)
                             .is_none();
                         debug!("Needs index? {}", needs_index);
 
                         if needs_index {
-                            blocks_to_index
-                                .entry((index_id.clone(), source_col.clone()))
-                                .or_insert_with(Vec::new)
-                                .push((block_id, block_version));
+                            let key = (*index_id, source_cols);
+                            match blocks_to_index.iter_mut().find(|(k, _)| k == &key) {
+                                Some((_, blocks)) => blocks.push((block_id, block_version)),
+                                None => {
+                                    blocks_to_index.push((key, vec![(block_id, block_version)]))
+                                }
+                            }
                         }
                     }
                 }
 
-                // Create IndexBlocksOp for each group of blocks
-                for ((index_id, column), blocks) in blocks_to_index {
-                    if !blocks.is_empty() {
-                        debug!(
-                            "Creating IndexBlocksOp for column {} with {} blocks",
-                            column,
-                            blocks.len()
-                        );
-
-                        builder
-                            .apply_operation(
-                                IndexBlocksOp::setup(&index_id, &column, blocks, &builder.bundle)
-                                    .await?
-                                    .into(),
+                // Build every group's index at once. The shared semaphore caps how many blocks
+                // are read at a time across all columns, and the ops are applied in group order.
+                let permits =
+                    Arc::new(Semaphore::new(builder.bundle.config().index_concurrency()?));
+                let progress = ProgressScope::new("Reindexing", Some(blocks_to_index.len() as u64));
+                let bundle = &builder.bundle;
+                let ops = try_join_all(blocks_to_index.into_iter().map(
+                    |((index_id, columns), blocks)| {
+                        let permits = permits.clone();
+                        let progress = &progress;
+                        async move {
+                            let column = columns.join(", ");
+                            debug!(
+                                "Creating IndexBlocksOp for column {} with {} blocks",
+                                column,
+                                blocks.len()
+                            );
+                            let op = IndexBlocksOp::setup_limited(
+                                &index_id, &columns, blocks, bundle, permits,
                             )
                             .await?;
-                    }
+                            progress.increment(1, Some(&column));
+                            Ok::<_, BundlebaseError>(op)
+                        }
+                    },
+                ))
+                .await?;
+
+                for op in ops {
+                    builder.apply_operation(op.into()).await?;
                 }
 
                 info!("Reindexed all columns");
@@ -1068,7 +2594,7 @@ impl BundleBuilder {
 
     /// Find the version of a block by its ID
     fn find_block_version(&self, block_id: &ObjectId) -> Option<String> {
-        for (_, pack) in &self.bundle.data_packs.read().clone() {
+        for pack in self.bundle.data_packs.read().values() {
             for block in pack.blocks() {
                 if block.id() == block_id {
                     return Some(block.version());
@@ -1095,6 +2621,29 @@ impl BundleBuilder {
         Ok(self)
     }
 
+    /// Rebuild a composite index, given its columns in key order (mutates self)
+    pub async fn rebuild_index_composite(
+        &mut self,
+        columns: &[&str],
+    ) -> Result<&mut Self, BundlebaseError> {
+        let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+
+        self.do_change(
+            &format!("Rebuild index on column {}", columns.join(", ")),
+            |builder| {
+                Box::pin(async move {
+                    builder
+                        .apply_operation(RebuildIndexOp::setup_composite(&columns).await?.into())
+                        .await?;
+                    Ok(())
+                })
+            },
+        )
+        .await?;
+
+        Ok(self)
+    }
+
     /// Get the physical source (pack name, column name) for a logical column
     ///
     /// This analyzes the DataFusion execution plan to trace a column back to its
@@ -1108,7 +2657,7 @@ impl BundleBuilder {
         logical_name: &str,
     ) -> Result<Option<crate::bundle::ColumnSource>, BundlebaseError> {
         // Get the logical plan
-        let df = self.dataframe().await?;
+        let df = self.bundle.raw_dataframe().await?;
         let plan = df.logical_plan();
 
         // Create analyzer with table-to-pack mappings
@@ -1175,6 +2724,19 @@ impl BundleFacade for BundleBuilder {
         ops
     }
 
+    fn operations_with_label(&self, key: &str, value: &str) -> Vec<AnyOperation> {
+        let mut ops = self.bundle.operations_with_label(key, value);
+        ops.extend(
+            self.status
+                .changes
+                .iter()
+                .filter(|change| change.has_label(key, value))
+                .flat_map(|change| change.operations.clone()),
+        );
+
+        ops
+    }
+
 
     async fn schema(&self) -> Result<SchemaRef, BundlebaseError> {
         self.bundle.schema().await
@@ -1190,19 +2752,14 @@ impl BundleFacade for BundleBuilder {
 
     async fn select(&self, sql: &str, params: Vec<ScalarValue>) -> Result<Self, BundlebaseError> {
         let mut bundle = self.clone();
-        let sql = sql.to_string();
-        let sql = if !sql.to_lowercase().starts_with("select ") {
-            format!("SELECT {}", sql)
-        } else {
-            sql
-        };
+        let sql = sql::with_select_keyword(sql);
 
         bundle
             .do_change(&format!("Query: {}", sql), |builder| {
                 Box::pin(async move {
-                    builder
-                        .apply_operation(SelectOp::setup(sql, params).await?.into())
-                        .await?;
+                    let op = SelectOp::setup(sql, params).await?;
+                    op.check_query(&builder.bundle).await?;
+                    builder.apply_operation(op.into()).await?;
                     info!("Created query");
                     Ok(())
                 })
@@ -1212,6 +2769,14 @@ impl BundleFacade for BundleBuilder {
         Ok(bundle)
     }
 
+    fn packs(&self) -> Vec<PackInfo> {
+        self.bundle.packs()
+    }
+
+    fn block_stats(&self) -> HashMap<ObjectId, Vec<ColumnStats>> {
+        self.bundle.block_stats()
+    }
+
     fn views(&self) -> HashMap<ObjectId, String> {
         self.bundle.views()
     }
@@ -1220,6 +2785,26 @@ impl BundleFacade for BundleBuilder {
         self.bundle.view(identifier).await
     }
 
+    async fn export_csv(
+        &self,
+        path: &str,
+        options: &CsvExportOptions,
+    ) -> Result<u64, BundlebaseError> {
+        self.bundle.export_csv(path, options).await
+    }
+
+    async fn export_json(
+        &self,
+        path: &str,
+        options: &JsonExportOptions,
+    ) -> Result<u64, BundlebaseError> {
+        self.bundle.export_json(path, options).await
+    }
+
+    async fn export_parquet(&self, path: &str) -> Result<u64, BundlebaseError> {
+        self.bundle.export_parquet(path).await
+    }
+
     async fn export_tar(&self, tar_path: &str) -> Result<String, BundlebaseError> {
         // Check for uncommitted changes
         if !self.status().is_empty() {
@@ -1229,6 +2814,34 @@ impl BundleFacade for BundleBuilder {
         // Delegate to the Bundle's implementation via BundleFacade
         self.bundle.export_tar(tar_path).await
     }
+
+    async fn export_changes(
+        &self,
+        since_commit: &str,
+        dest: &str,
+        include_removed: bool,
+    ) -> Result<ChangeExport, BundlebaseError> {
+        if !self.status().is_empty() {
+            return Err(
+                "Cannot export changes with uncommitted changes. Please commit first.".into(),
+            );
+        }
+        self.bundle
+            .export_changes(since_commit, dest, include_removed)
+            .await
+    }
+
+    async fn validate(&self) -> Result<Vec<ValidationWarning>, BundlebaseError> {
+        self.bundle.validate().await
+    }
+
+    async fn verify_schema(&self, expected: SchemaRef) -> Result<(), BundlebaseError> {
+        self.bundle.verify_schema(expected).await
+    }
+
+    async fn verify_schema_file(&self, path: &str) -> Result<(), BundlebaseError> {
+        self.bundle.verify_schema_file(path).await
+    }
 }
 
 #[cfg(test)]
@@ -1461,4 +3074,41 @@ mod tests {
 
         assert_eq!(bundle.bundle.operations.len(), 4);
     }
+
+    #[tokio::test]
+    async fn test_status_change_details() {
+        let mut bundle = BundleBuilder::create("memory:///test_bundle", None)
+            .await
+            .unwrap();
+        bundle
+            .attach(test_datafile("userdata.parquet"))
+            .await
+            .unwrap();
+        bundle.remove_column("title").await.unwrap();
+        bundle
+            .index_composite(&["last_name", "first_name"])
+            .await
+            .unwrap();
+
+        let changes = bundle.status().changes();
+        assert_eq!(3, changes.len());
+
+        let attach = &changes[0];
+        assert_eq!(vec![test_datafile("userdata.parquet")], attach.files());
+        assert!(attach.columns().is_empty());
+        assert!(attach.summary().contains("userdata.parquet"));
+
+        let remove = &changes[1];
+        assert!(remove.files().is_empty());
+        assert_eq!(vec!["title"], remove.columns());
+        assert!(remove.summary().contains("title"));
+
+        // A composite index lists each of its columns
+        assert_eq!(vec!["last_name", "first_name"], changes[2].columns());
+
+        assert!(changes
+            .iter()
+            .all(|change| bundle.status().is_undoable(&change.id)));
+        assert!(!bundle.status().is_undoable(&Uuid::new_v4()));
+    }
 }
@@ -0,0 +1,84 @@
+use crate::bundle::csv_export::{write_csv, CsvExportOptions};
+use crate::io::ObjectStoreDir;
+use crate::BundlebaseError;
+use datafusion::dataframe::DataFrame;
+use serde::{Deserialize, Serialize};
+
+/// File `export_changes` writes the rows added since the earlier commit to
+pub const ADDED_FILENAME: &str = "added.csv";
+
+/// File `export_changes` writes the rows removed since the earlier commit to
+pub const REMOVED_FILENAME: &str = "removed.csv";
+
+/// File `export_changes` records what it exported in
+pub const CHANGES_FILENAME: &str = "changes.yaml";
+
+/// What `export_changes` wrote, also saved to `changes.yaml` next to the exported rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeExport {
+    /// Id of the commit the changes were taken since
+    pub since_commit: String,
+    /// Id of the latest commit included, to pass as `since_commit` on the next export
+    pub commit: String,
+    /// Number of rows written to `added.csv`
+    pub added_rows: u64,
+    /// Number of rows written to `removed.csv`, if removed rows were exported
+    pub removed_rows: Option<u64>,
+}
+
+/// Writes the rows of `after` that aren't in `before` to `dest`, and the reverse too if
+/// `include_removed` is set. Duplicate rows are compared by count, so a row attached twice is
+/// added once. The columns must be the same, otherwise there is no row-level difference to take.
+pub(crate) async fn write_changes(
+    before: DataFrame,
+    after: DataFrame,
+    dest: &ObjectStoreDir,
+    include_removed: bool,
+    since_commit: &str,
+    commit: &str,
+) -> Result<ChangeExport, BundlebaseError> {
+    let columns = |df: &DataFrame| {
+        df.schema()
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), f.data_type().clone()))
+            .collect::<Vec<_>>()
+    };
+    if columns(&before) != columns(&after) {
+        return Err(format!(
+            "The columns changed since commit '{}', so the changes can't be exported as rows. Use export_csv for a full export instead",
+            since_commit
+        )
+        .into());
+    }
+
+    let options = CsvExportOptions::default();
+    let added_rows = write_csv(
+        after.clone().except(before.clone())?,
+        &dest.file(ADDED_FILENAME)?,
+        &options,
+    )
+    .await?;
+    let removed_rows = if include_removed {
+        Some(
+            write_csv(
+                before.except(after)?,
+                &dest.file(REMOVED_FILENAME)?,
+                &options,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let export = ChangeExport {
+        since_commit: since_commit.to_string(),
+        commit: commit.to_string(),
+        added_rows,
+        removed_rows,
+    };
+    dest.file(CHANGES_FILENAME)?.write_yaml(&export).await?;
+    Ok(export)
+}
@@ -1,3 +1,4 @@
+use crate::catalog::ConstrainedTable;
 use datafusion::logical_expr::{Expr, LogicalPlan};
 use std::collections::HashMap;
 
@@ -53,6 +54,9 @@ impl ColumnLineageAnalyzer {
     fn visit_plan(&mut self, plan: &LogicalPlan) -> Result<(), String> {
         match plan {
             LogicalPlan::TableScan(scan) => {
+                if let Some(wrapped) = ConstrainedTable::wrapped_plan(scan) {
+                    return self.visit_plan(&wrapped);
+                }
                 let table_name = scan.table_name.to_string();
                 let pack_name = self
                     .table_to_pack
@@ -29,6 +29,13 @@ pub enum BundleCommand {
         params: Vec<ScalarValue>,
     },
 
+    /// Delete rows matching a WHERE condition
+    /// Maps to: `bundle.delete_rows(&where_clause, params)`
+    DeleteRows {
+        where_clause: String,
+        params: Vec<ScalarValue>,
+    },
+
     /// Remove a column
     /// Maps to: `bundle.remove_column(&name)`
     RemoveColumn { name: String },
@@ -131,6 +138,13 @@ impl BundleCommand {
                 bundle.filter(&where_clause, params).await?;
                 Ok(())
             }
+            BundleCommand::DeleteRows {
+                where_clause,
+                params,
+            } => {
+                bundle.delete_rows(&where_clause, params).await?;
+                Ok(())
+            }
             BundleCommand::RemoveColumn { name } => {
                 bundle.remove_column(&name).await?;
                 Ok(())
@@ -227,6 +241,9 @@ impl BundleCommand {
             BundleCommand::Filter {
                 params: ref mut p, ..
             } => *p = params,
+            BundleCommand::DeleteRows {
+                params: ref mut p, ..
+            } => *p = params,
             BundleCommand::Select {
                 params: ref mut p, ..
             } => *p = params,
@@ -1,9 +1,50 @@
 use crate::bundle::command::parser_pest::parse_custom_pest;
 use crate::bundle::command::BundleCommand;
 use crate::BundlebaseError;
-use sqlparser::ast::{ObjectType, Statement};
+use sqlparser::ast::{Delete, FromTable, ObjectType, Statement, TableFactor};
 use sqlparser::dialect::GenericDialect;
 use sqlparser::parser::Parser;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// SQL dialect used to parse commands.
+///
+/// Both dialects accept the bundlebase extension verbs, which aren't standard SQL:
+/// `ATTACH`, `ATTACH ... TO JOIN`, `FILTER WHERE`, `[join_type] JOIN ... AS ... ON`, `REINDEX`
+/// and `RENAME VIEW`. They differ in how standard SQL queries are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SqlDialect {
+    /// Standard SQL queries are parsed and re-rendered by sqlparser before they run
+    #[default]
+    Bundlebase,
+    /// Standard SQL queries are passed to DataFusion exactly as written
+    Ansi,
+}
+
+impl FromStr for SqlDialect {
+    type Err = BundlebaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bundlebase" => Ok(SqlDialect::Bundlebase),
+            "ansi" => Ok(SqlDialect::Ansi),
+            _ => Err(format!(
+                "Unknown SQL dialect '{}', must be one of: bundlebase, ansi",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+impl Display for SqlDialect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SqlDialect::Bundlebase => write!(f, "bundlebase"),
+            SqlDialect::Ansi => write!(f, "ansi"),
+        }
+    }
+}
 
 /// Parse a command statement into a BundleCommand.
 ///
@@ -12,7 +53,7 @@ use sqlparser::parser::Parser;
 ///
 /// It handles:
 /// 1. Parsing custom bundlebase syntax (FILTER, ATTACH, JOIN, REINDEX) using Pest
-/// 2. Parsing standard SQL (SELECT, CREATE INDEX, etc.) using sqlparser-rs
+/// 2. Parsing standard SQL (SELECT, DELETE, CREATE INDEX, etc.) using sqlparser-rs
 /// 3. Converting parsed statements into BundleCommand variants
 ///
 /// # Arguments
@@ -42,6 +83,16 @@ use sqlparser::parser::Parser;
 /// cmd.execute(&mut bundle).await?;
 /// ```
 pub fn parse_command(command_str: &str) -> Result<BundleCommand, BundlebaseError> {
+    parse_command_with_dialect(command_str, SqlDialect::Bundlebase)
+}
+
+/// Parse a command statement into a BundleCommand using the given SQL dialect.
+///
+/// See [`parse_command`] for details, and [`SqlDialect`] for how the dialects differ.
+pub fn parse_command_with_dialect(
+    command_str: &str,
+    dialect: SqlDialect,
+) -> Result<BundleCommand, BundlebaseError> {
     // First, try Pest grammar for custom bundlebase syntax (FILTER, ATTACH, JOIN, REINDEX)
     if let Some(op) = parse_custom_pest(command_str)? {
         return Ok(op);
@@ -63,8 +114,7 @@ pub fn parse_command(command_str: &str) -> Result<BundleCommand, BundlebaseError
     }
 
     // Otherwise, use sqlparser-rs for standard SQL (SELECT, CREATE INDEX, etc.)
-    let dialect = GenericDialect {};
-    let ast = Parser::parse_sql(&dialect, command_str)
+    let ast = Parser::parse_sql(&GenericDialect {}, command_str)
         .map_err(|e| -> BundlebaseError { format!("SQL parse error: {}", e).into() })?;
 
     if ast.is_empty() {
@@ -79,6 +129,18 @@ pub fn parse_command(command_str: &str) -> Result<BundleCommand, BundlebaseError
 
     let stmt = &ast[0];
 
+    // In ANSI mode queries run exactly as the user wrote them
+    if dialect == SqlDialect::Ansi && matches!(stmt, Statement::Query(_)) {
+        return Ok(BundleCommand::Select {
+            sql: command_str
+                .trim()
+                .trim_end_matches(';')
+                .trim_end()
+                .to_string(),
+            params: vec![],
+        });
+    }
+
     // Dispatch to appropriate operation based on statement type
     dispatch_statement(stmt)
 }
@@ -95,6 +157,9 @@ fn dispatch_statement(stmt: &Statement) -> Result<BundleCommand, BundlebaseError
             params: vec![],
         }),
 
+        // DELETE FROM bundle WHERE ... -> DeleteRows
+        Statement::Delete(delete) => parse_delete(delete),
+
         // CREATE INDEX -> Index
         Statement::CreateIndex { .. } => {
             // sqlparser 0.59 changed CreateIndex structure
@@ -118,6 +183,51 @@ fn dispatch_statement(stmt: &Statement) -> Result<BundleCommand, BundlebaseError
     }
 }
 
+/// Convert a `DELETE FROM bundle WHERE <condition>` statement into a DeleteRows command.
+///
+/// The table may be referred to as either `bundle` or `data`.
+fn parse_delete(delete: &Delete) -> Result<BundleCommand, BundlebaseError> {
+    let tables = match &delete.from {
+        FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables) => tables,
+    };
+    let table_name = match tables.as_slice() {
+        [table] if table.joins.is_empty() => match &table.relation {
+            TableFactor::Table { name, .. } => name.to_string(),
+            _ => return Err("DELETE only supports deleting from the bundle table".into()),
+        },
+        _ => return Err("DELETE only supports deleting from the bundle table".into()),
+    };
+    if !table_name.eq_ignore_ascii_case("bundle") && !table_name.eq_ignore_ascii_case("data") {
+        return Err(format!(
+            "Cannot delete from '{}'. DELETE only supports the bundle table",
+            table_name
+        )
+        .into());
+    }
+
+    if !delete.tables.is_empty()
+        || delete.using.is_some()
+        || delete.returning.is_some()
+        || !delete.order_by.is_empty()
+        || delete.limit.is_some()
+    {
+        return Err("DELETE only supports: DELETE FROM bundle WHERE <condition>".into());
+    }
+
+    let where_clause = delete
+        .selection
+        .as_ref()
+        .ok_or_else(|| -> BundlebaseError {
+            "DELETE requires a WHERE clause. Use FILTER to keep a subset of rows instead".into()
+        })?
+        .to_string();
+
+    Ok(BundleCommand::DeleteRows {
+        where_clause,
+        params: vec![],
+    })
+}
+
 /// Extract column name from index name.
 ///
 /// For now, we assume index names follow the pattern "idx_{column_name}"
@@ -155,4 +265,93 @@ mod tests {
             .to_string()
             .contains("Multiple statements"));
     }
+
+    #[test]
+    fn test_parse_sql_dialect() {
+        assert_eq!(SqlDialect::Ansi, "ANSI".parse::<SqlDialect>().unwrap());
+        assert_eq!(
+            SqlDialect::Bundlebase,
+            "bundlebase".parse::<SqlDialect>().unwrap()
+        );
+        assert_eq!("ansi", SqlDialect::Ansi.to_string());
+        assert!("mysql".parse::<SqlDialect>().is_err());
+    }
+
+    #[test]
+    fn test_ansi_query_passes_through() {
+        let sql =
+            "select  first_name, salary*2 AS double_salary\nFROM bundle WHERE country = 'USA';";
+        match parse_command_with_dialect(sql, SqlDialect::Ansi).unwrap() {
+            BundleCommand::Select { sql: parsed, .. } => assert_eq!(
+                "select  first_name, salary*2 AS double_salary\nFROM bundle WHERE country = 'USA'",
+                parsed
+            ),
+            other => panic!("Expected Select variant, got {:?}", other),
+        }
+
+        // The bundlebase dialect normalizes the query instead
+        match parse_command_with_dialect(sql, SqlDialect::Bundlebase).unwrap() {
+            BundleCommand::Select { sql: parsed, .. } => assert_eq!(
+                "SELECT first_name, salary * 2 AS double_salary FROM bundle WHERE country = 'USA'",
+                parsed
+            ),
+            other => panic!("Expected Select variant, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ansi_keeps_extension_verbs() {
+        assert!(matches!(
+            parse_command_with_dialect("FILTER WHERE age > 21", SqlDialect::Ansi).unwrap(),
+            BundleCommand::Filter { .. }
+        ));
+        assert!(matches!(
+            parse_command_with_dialect("ATTACH 'data.parquet'", SqlDialect::Ansi).unwrap(),
+            BundleCommand::Attach { .. }
+        ));
+        assert!(matches!(
+            parse_command_with_dialect("REINDEX", SqlDialect::Ansi).unwrap(),
+            BundleCommand::Reindex
+        ));
+        assert!(matches!(
+            parse_command_with_dialect("DELETE FROM bundle WHERE id = 1", SqlDialect::Ansi)
+                .unwrap(),
+            BundleCommand::DeleteRows { .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_delete() {
+        match parse_command("DELETE FROM data WHERE Country = 'Chile'").unwrap() {
+            BundleCommand::DeleteRows {
+                where_clause,
+                params,
+            } => {
+                assert_eq!(where_clause, "Country = 'Chile'");
+                assert!(params.is_empty());
+            }
+            other => panic!("Expected DeleteRows variant, got {:?}", other),
+        }
+
+        match parse_command("delete from bundle where id > $1").unwrap() {
+            BundleCommand::DeleteRows { where_clause, .. } => {
+                assert_eq!(where_clause, "id > $1");
+            }
+            other => panic!("Expected DeleteRows variant, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_delete_requires_where() {
+        let err = parse_command("DELETE FROM data").unwrap_err().to_string();
+        assert!(err.contains("requires a WHERE clause"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_delete_other_table() {
+        let err = parse_command("DELETE FROM users WHERE id = 1")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Cannot delete from 'users'"), "{}", err);
+    }
 }
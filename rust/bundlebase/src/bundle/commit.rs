@@ -1,17 +1,43 @@
 use crate::bundle::operation::{AnyOperation, BundleChange};
+use crate::BundlebaseError;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+/// Version of the init and commit manifest format written by this version of bundlebase.
+///
+/// Bump this when a change to the manifests can't be read correctly by older readers,
+/// and add the upgrade from the previous version to `migrate_manifest`.
+///
+/// Version 2 added `amends`, which older readers would ignore and so apply both commits.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Manifests written before the format was versioned are treated as version 1.
+pub(crate) fn legacy_format_version() -> u32 {
+    1
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BundleCommit {
+    /// Identifies the commit within its bundle: the manifest filename without its extension,
+    /// `{5-digit-version}{12-char-hash}`. It is derived from the file, so it stays the same
+    /// across reopens and sorts in commit order. Empty until the commit is written.
+    #[serde(skip)]
+    pub id: String,
     #[serde(skip)]
     pub url: Option<Url>,
     #[serde(skip)]
     pub data_dir: Option<Url>,
+    #[serde(default = "legacy_format_version")]
+    pub format_version: u32,
     pub author: String,
     pub message: String,
     pub timestamp: String,
+    /// The id of the commit this one amends. The amended commit stays on disk but is replaced
+    /// by this one when the bundle is opened, see `BundleBuilder::amend`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amends: Option<String>,
     pub changes: Vec<BundleChange>,
 }
 
@@ -25,6 +51,10 @@ impl BundleCommit {
     }
 }
 
+/// Directory in the metadata directory where each version is claimed by a conditional write of
+/// its manifest before the manifest itself is written, see `BundleBuilder::commit`
+pub(crate) static VERSIONS_DIR: &str = "versions";
+
 /// Extracts the version number from a manifest filename.
 /// Expected format: `{5-digit-version}{12-char-hash}.yaml`
 /// Examples: "00001abc123def456.yaml" -> 1, "00042xyz789abc123.yaml" -> 42
@@ -36,6 +66,77 @@ pub fn manifest_version(filename: &str) -> u32 {
     filename[0..5].parse::<u32>().unwrap_or(1) // Default to version 1 if parsing fails
 }
 
+/// Returns the id of the commit stored in a manifest file, see `BundleCommit::id`.
+/// Example: "00042xyz789abc123.yaml" -> "00042xyz789abc123"
+pub fn manifest_commit_id(filename: &str) -> String {
+    filename
+        .strip_suffix(".yaml")
+        .unwrap_or(filename)
+        .to_string()
+}
+
+/// Parses the contents of an init or commit manifest.
+///
+/// The format version is checked before deserializing so that a manifest written by a newer,
+/// incompatible version of bundlebase is rejected rather than being misread.
+/// Manifests written in an older format are upgraded by `migrate_manifest` first.
+pub(crate) fn parse_manifest<T: DeserializeOwned>(
+    bundle_url: &str,
+    file: &str,
+    contents: &str,
+) -> Result<T, BundlebaseError> {
+    let value: serde_yaml::Value =
+        serde_yaml::from_str(contents).map_err(|e| corrupt_manifest_error(bundle_url, file, e))?;
+
+    let version = match value.get("formatVersion") {
+        None => legacy_format_version(),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Bundle at {} has a corrupt manifest {}: formatVersion must be a positive integer",
+                    bundle_url, file
+                )
+            })?,
+    };
+
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "Bundle at {} was written with manifest format version {} ({}), but this version of bundlebase only supports up to version {}. Upgrade bundlebase to open it",
+            bundle_url, version, file, FORMAT_VERSION
+        )
+        .into());
+    }
+
+    let value = migrate_manifest(value, version)?;
+    serde_yaml::from_value(value).map_err(|e| corrupt_manifest_error(bundle_url, file, e))
+}
+
+/// Upgrades a manifest from an older format version to the current `FORMAT_VERSION`.
+///
+/// Each format bump adds an arm here which rewrites the raw YAML from version `n` to `n + 1`
+/// and recurses, e.g. `1 => migrate_manifest(upgrade_v1_to_v2(value)?, 2)`.
+fn migrate_manifest(
+    value: serde_yaml::Value,
+    from_version: u32,
+) -> Result<serde_yaml::Value, BundlebaseError> {
+    match from_version {
+        // Version 2 only added the optional `amends`
+        1 => migrate_manifest(value, 2),
+        FORMAT_VERSION => Ok(value),
+        _ => Err(format!("No migration from manifest format version {}", from_version).into()),
+    }
+}
+
+fn corrupt_manifest_error(url: &str, file: &str, err: serde_yaml::Error) -> BundlebaseError {
+    format!(
+        "Bundle at {} has a corrupt manifest {}: {}. Repair or restore the file to open the bundle",
+        url, file, err
+    )
+    .into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,16 +153,20 @@ mod tests {
     #[test]
     fn test_serialize_empty_operations() {
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Initial commit".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
 
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Initial commit
 timestamp: 2024-01-01T00:00:00Z
 changes: []
@@ -75,19 +180,24 @@ changes: []
         let change = BundleChange {
             id: test_uuid(),
             description: "Remove columns".to_string(),
+            labels: Default::default(),
             operations: vec![op.into()],
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Remove column".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
 
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Remove column
 timestamp: 2024-01-01T00:00:00Z
 changes:
@@ -110,19 +220,24 @@ changes:
         let change = BundleChange {
             id: test_uuid(),
             description: "Multiple operations".to_string(),
+            labels: Default::default(),
             operations: vec![op1.into(), op2.into(), op3.into()],
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Multiple ops".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
 
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Multiple ops
 timestamp: 2024-01-01T00:00:00Z
 changes:
@@ -141,6 +256,42 @@ changes:
         assert_eq!(yaml, expected);
     }
 
+    #[test]
+    fn test_serialize_labels() {
+        let change = BundleChange {
+            id: test_uuid(),
+            description: "Set name".to_string(),
+            labels: [
+                ("dataset".to_string(), "sales".to_string()),
+                ("owner".to_string(), "finance".to_string()),
+            ]
+            .into(),
+            operations: vec![SetNameOp::setup("Test").into()],
+        };
+        assert_eq!(
+            "Change: Set name [dataset=sales, owner=finance]",
+            change.to_string()
+        );
+        assert!(change.has_label("dataset", "sales"));
+        assert!(!change.has_label("dataset", "marketing"));
+        assert!(!change.has_label("region", "sales"));
+
+        let yaml = serde_yaml::to_string(&change).unwrap();
+        let expected = r"id: 12345678-1234-1234-1234-123456789012
+description: Set name
+labels:
+  dataset: sales
+  owner: finance
+operations:
+- type: setName
+  name: Test
+";
+        assert_eq!(yaml, expected);
+
+        let deserialized: BundleChange = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(change.labels, deserialized.labels);
+    }
+
     #[test]
     fn test_serialize_with_from() {
         let op = SetNameOp::setup("Test");
@@ -148,18 +299,23 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set name".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Extended commit".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
 
         let expected = r"
+formatVersion: 2
 author: test-user
 message: Extended commit
 timestamp: 2024-01-01T00:00:00Z
@@ -245,19 +401,24 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Rename column".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Test camelCase".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
 
         // Should have oldName and newName in camelCase
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Test camelCase
 timestamp: 2024-01-01T00:00:00Z
 changes:
@@ -279,13 +440,17 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Rename".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Test".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
@@ -299,7 +464,8 @@ changes:
         assert!(first_line_after_dash > 0);
 
         // Verify the exact order
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Test
 timestamp: 2024-01-01T00:00:00Z
 changes:
@@ -320,18 +486,23 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set name".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Set bundle name".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
 
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Set bundle name
 timestamp: 2024-01-01T00:00:00Z
 changes:
@@ -351,18 +522,23 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set description".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Set description".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
         let yaml = serde_yaml::to_string(&commit).unwrap();
 
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Set description
 timestamp: 2024-01-01T00:00:00Z
 changes:
@@ -382,14 +558,18 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set name".to_string(),
+            labels: Default::default(),
         };
         let message = "Commit with special chars: !@#$%".to_string();
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: message.clone(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
 
@@ -408,18 +588,23 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Rename".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Rename".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
 
         let yaml = serde_yaml::to_string(&commit).unwrap();
-        let expected = r"author: test-user
+        let expected = r"formatVersion: 2
+author: test-user
 message: Rename
 timestamp: 2024-01-01T00:00:00Z
 changes:
@@ -440,13 +625,17 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set name".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
 
@@ -467,13 +656,17 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set name".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: long_message.clone(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
 
@@ -492,13 +685,17 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set description".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Unicode test".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
 
@@ -515,13 +712,17 @@ changes:
             id: test_uuid(),
             operations: vec![op.into()],
             description: "Set name".to_string(),
+            labels: Default::default(),
         };
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Setup".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
 
@@ -552,9 +753,16 @@ changes:
             version: "v1".to_string(),
             id: ObjectId::from(42u8),
             pack_id: ObjectId::from(53u8),
+            name: None,
+            format: None,
+            compression: None,
             layout: None,
+            partitions: None,
+            row_groups: None,
+            converted_from: None,
             num_rows: Some(100),
             bytes: Some(1000),
+            stats: None,
             schema: Some(schema),
         };
 
@@ -569,14 +777,18 @@ changes:
                 AnyOperation::RemoveColumns(remove_config),
             ],
             description: "Complex operations".to_string(),
+            labels: Default::default(),
         };
 
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: "Complex ops".to_string(),
             author: "test-user".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            amends: None,
             changes: vec![change],
         };
 
@@ -734,4 +946,69 @@ changes:
         assert_eq!(manifest_version("00042xyz123456789.yaml"), 42);
         assert_eq!(manifest_version("01000abc123def456.yaml"), 1000);
     }
+
+    #[test]
+    fn test_manifest_commit_id() {
+        assert_eq!(
+            "00042xyz789abc123",
+            manifest_commit_id("00042xyz789abc123.yaml")
+        );
+        assert_eq!("00001abc", manifest_commit_id("00001abc"));
+    }
+
+    #[test]
+    fn test_parse_manifest_current_version() {
+        let yaml = r"formatVersion: 2
+author: test-user
+message: Current
+timestamp: '2024-01-01T00:00:00Z'
+changes: []
+";
+        let commit: BundleCommit =
+            parse_manifest("memory:///bundle", "manifest.yaml", yaml).unwrap();
+        assert_eq!(commit.format_version, FORMAT_VERSION);
+        assert_eq!(commit.message, "Current");
+    }
+
+    #[test]
+    fn test_parse_manifest_without_version() {
+        let yaml = r"author: test-user
+message: Legacy
+timestamp: '2024-01-01T00:00:00Z'
+changes: []
+";
+        let commit: BundleCommit =
+            parse_manifest("memory:///bundle", "manifest.yaml", yaml).unwrap();
+        assert_eq!(commit.format_version, 1);
+        assert_eq!(commit.message, "Legacy");
+    }
+
+    #[test]
+    fn test_parse_manifest_rejects_newer_version() {
+        // A future format may not look anything like the current one
+        let yaml = r"formatVersion: 999
+commits:
+  - hash: abc
+    entries: [1, 2, 3]
+";
+        let err = parse_manifest::<BundleCommit>("memory:///bundle", "manifest.yaml", yaml)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("format version 999"), "{}", err);
+        assert!(
+            err.contains(&format!("up to version {}", FORMAT_VERSION)),
+            "{}",
+            err
+        );
+        assert!(err.contains("Upgrade bundlebase"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_manifest_invalid_version() {
+        let yaml = "formatVersion: latest\nauthor: test-user\n";
+        let err = parse_manifest::<BundleCommit>("memory:///bundle", "manifest.yaml", yaml)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("corrupt manifest manifest.yaml"), "{}", err);
+    }
 }
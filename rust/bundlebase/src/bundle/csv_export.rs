@@ -0,0 +1,212 @@
+use crate::io::ObjectStoreFile;
+use crate::BundlebaseError;
+use arrow::array::{ArrayRef, AsArray, RecordBatch, StringArray};
+use arrow::csv::{QuoteStyle, WriterBuilder};
+use arrow::datatypes::{DataType, Field, Float32Type, Float64Type, Schema};
+use arrow::error::ArrowError;
+use arrow::util::display::array_value_to_string;
+use datafusion::dataframe::DataFrame;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// How `export_csv` writes values
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvExportOptions {
+    /// Written for null values. Defaults to an empty field
+    pub null_value: String,
+    /// Number of decimal places floats are written with. Defaults to the shortest exact form
+    pub float_precision: Option<usize>,
+    /// Written for NaN floats. Defaults to `NaN`
+    pub nan_value: Option<String>,
+    /// Quote every field rather than only those that need it
+    pub quote_all: bool,
+    /// Write a header row with the column names
+    pub header: bool,
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        Self {
+            null_value: String::new(),
+            float_precision: None,
+            nan_value: None,
+            quote_all: false,
+            header: true,
+        }
+    }
+}
+
+impl CsvExportOptions {
+    fn writer_builder(&self, header: bool) -> WriterBuilder {
+        WriterBuilder::new()
+            .with_header(header)
+            .with_null(self.null_value.clone())
+            .with_quote_style(if self.quote_all {
+                QuoteStyle::Always
+            } else {
+                QuoteStyle::Necessary
+            })
+    }
+
+    /// Replaces float columns with their text at the configured precision and NaN value
+    fn format_floats(&self, batch: RecordBatch) -> Result<RecordBatch, BundlebaseError> {
+        if self.float_precision.is_none() && self.nan_value.is_none() {
+            return Ok(batch);
+        }
+
+        let schema = batch.schema();
+        let mut fields = Vec::with_capacity(schema.fields().len());
+        let mut columns: Vec<ArrayRef> = Vec::with_capacity(batch.num_columns());
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            let values: Option<Vec<Option<f64>>> = match field.data_type() {
+                DataType::Float32 => Some(
+                    column
+                        .as_primitive::<Float32Type>()
+                        .iter()
+                        .map(|v| v.map(f64::from))
+                        .collect(),
+                ),
+                DataType::Float64 => Some(column.as_primitive::<Float64Type>().iter().collect()),
+                _ => None,
+            };
+            match values {
+                Some(values) => {
+                    let formatted = values
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, v)| v.map(|v| self.format_float(column, i, v)).transpose())
+                        .collect::<Result<StringArray, ArrowError>>()?;
+                    fields.push(Arc::new(Field::new(
+                        field.name(),
+                        DataType::Utf8,
+                        field.is_nullable(),
+                    )));
+                    columns.push(Arc::new(formatted));
+                }
+                None => {
+                    fields.push(field.clone());
+                    columns.push(column.clone());
+                }
+            }
+        }
+        Ok(RecordBatch::try_new(
+            Arc::new(Schema::new(fields)),
+            columns,
+        )?)
+    }
+
+    /// Text for `value`, the float at `index` in `column`
+    fn format_float(
+        &self,
+        column: &ArrayRef,
+        index: usize,
+        value: f64,
+    ) -> Result<String, ArrowError> {
+        if let Some(nan_value) = self.nan_value.as_ref().filter(|_| value.is_nan()) {
+            return Ok(nan_value.clone());
+        }
+        match self.float_precision {
+            Some(precision) => Ok(format!("{:.*}", precision, value)),
+            None => array_value_to_string(column, index),
+        }
+    }
+}
+
+/// Writes the dataframe to `file` as CSV, one batch at a time. Returns the number of rows written.
+pub(crate) async fn write_csv(
+    df: DataFrame,
+    file: &ObjectStoreFile,
+    options: &CsvExportOptions,
+) -> Result<u64, BundlebaseError> {
+    let options = options.clone();
+    let rows = Arc::new(AtomicU64::new(0));
+    let counter = rows.clone();
+
+    // A header-only file is written for an empty result, so start with the header on its own
+    let header = if options.header {
+        let schema = df.schema().as_arrow().clone();
+        let mut buffer = Vec::new();
+        options
+            .writer_builder(true)
+            .build(&mut buffer)
+            .write(&RecordBatch::new_empty(Arc::new(schema)))?;
+        buffer
+    } else {
+        Vec::new()
+    };
+
+    let batches = df.execute_stream().await?.map(move |batch| {
+        let batch = batch.map_err(std::io::Error::other)?;
+        let batch = options
+            .format_floats(batch)
+            .map_err(std::io::Error::other)?;
+        counter.fetch_add(batch.num_rows() as u64, Ordering::Relaxed);
+
+        let mut buffer = Vec::new();
+        options
+            .writer_builder(false)
+            .build(&mut buffer)
+            .write(&batch)
+            .map_err(std::io::Error::other)?;
+        Ok(bytes::Bytes::from(buffer))
+    });
+    let stream = futures::stream::iter(vec![Ok(bytes::Bytes::from(header))]).chain(batches);
+    file.write_stream(stream).await?;
+
+    Ok(rows.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::random_memory_file;
+    use arrow::array::{Float64Array, Int32Array};
+    use datafusion::prelude::SessionContext;
+
+    async fn export(options: &CsvExportOptions) -> String {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("a, b"), None, Some("c")])) as ArrayRef,
+            ),
+            (
+                "score",
+                Arc::new(Float64Array::from(vec![Some(1.5), None, Some(f64::NAN)])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let df = SessionContext::new().read_batch(batch).unwrap();
+        let file = random_memory_file("export.csv");
+
+        assert_eq!(3, write_csv(df, &file, options).await.unwrap());
+        file.read_str().await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_csv_defaults() {
+        assert_eq!(
+            "id,name,score\n1,\"a, b\",1.5\n,,\n3,c,NaN\n",
+            export(&CsvExportOptions::default()).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_csv_options() {
+        let options = CsvExportOptions {
+            null_value: "NULL".to_string(),
+            float_precision: Some(2),
+            nan_value: Some("nan".to_string()),
+            quote_all: true,
+            header: false,
+        };
+        assert_eq!(
+            "\"1\",\"a, b\",\"1.50\"\n\"NULL\",\"NULL\",\"NULL\"\n\"3\",\"c\",\"nan\"\n",
+            export(&options).await
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::bundle::operation::{AnyOperation, Operation};
+use arrow_schema::Schema;
+use std::fmt;
+
+/// What changed in a bundle between two commits, see `Bundle::diff`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleDiff {
+    /// Id of the earlier commit
+    pub from_commit: String,
+    /// Id of the later commit
+    pub to_commit: String,
+    /// Operations at `to_commit` that weren't at `from_commit`, in order
+    pub added_operations: Vec<AnyOperation>,
+    /// Operations at `from_commit` that aren't at `to_commit`, such as ones squashed away
+    pub removed_operations: Vec<AnyOperation>,
+    /// Columns at `to_commit` that weren't at `from_commit`, not counting renamed ones
+    pub added_columns: Vec<String>,
+    /// Columns at `from_commit` that aren't at `to_commit`, not counting renamed ones
+    pub removed_columns: Vec<String>,
+    /// Columns renamed in between, as (old name, new name)
+    pub renamed_columns: Vec<(String, String)>,
+}
+
+impl BundleDiff {
+    pub(crate) fn new(
+        from_commit: &str,
+        to_commit: &str,
+        from_operations: &[AnyOperation],
+        to_operations: &[AnyOperation],
+        from_schema: &Schema,
+        to_schema: &Schema,
+    ) -> Self {
+        let common = from_operations
+            .iter()
+            .zip(to_operations)
+            .take_while(|(from, to)| from == to)
+            .count();
+        let added_operations = to_operations[common..].to_vec();
+        let removed_operations = from_operations[common..].to_vec();
+
+        let has = |schema: &Schema, name: &str| schema.field_with_name(name).is_ok();
+        let rename = |op: &AnyOperation| match op {
+            AnyOperation::RenameColumn(rename) => {
+                Some((rename.old_name.clone(), rename.new_name.clone()))
+            }
+            AnyOperation::ColumnAlias(alias) => Some((alias.original.clone(), alias.alias.clone())),
+            _ => None,
+        };
+        // Removed renames count backwards, going from the later name to the earlier one
+        let renamed_columns: Vec<(String, String)> = added_operations
+            .iter()
+            .filter_map(rename)
+            .chain(
+                removed_operations
+                    .iter()
+                    .filter_map(rename)
+                    .map(|(old, new)| (new, old)),
+            )
+            .filter(|(old, new)| {
+                has(from_schema, old)
+                    && !has(to_schema, old)
+                    && has(to_schema, new)
+                    && !has(from_schema, new)
+            })
+            .collect();
+        let renamed = |name: &String| {
+            renamed_columns
+                .iter()
+                .any(|(old, new)| old == name || new == name)
+        };
+
+        let added_columns = to_schema
+            .fields()
+            .iter()
+            .map(|f| f.name())
+            .filter(|name| !has(from_schema, name) && !renamed(name))
+            .cloned()
+            .collect();
+        let removed_columns = from_schema
+            .fields()
+            .iter()
+            .map(|f| f.name())
+            .filter(|name| !has(to_schema, name) && !renamed(name))
+            .cloned()
+            .collect();
+
+        Self {
+            from_commit: from_commit.to_string(),
+            to_commit: to_commit.to_string(),
+            added_operations,
+            removed_operations,
+            added_columns,
+            removed_columns,
+            renamed_columns,
+        }
+    }
+
+    /// True if nothing changed between the two commits
+    pub fn is_empty(&self) -> bool {
+        self.added_operations.is_empty() && self.removed_operations.is_empty()
+    }
+}
+
+impl fmt::Display for BundleDiff {
+    /// A summary like `git diff --stat`: removed and added operations, then the column changes
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "diff {}..{}", self.from_commit, self.to_commit)?;
+        for op in &self.removed_operations {
+            writeln!(f, "- {}", op.describe())?;
+        }
+        for op in &self.added_operations {
+            writeln!(f, "+ {}", op.describe())?;
+        }
+        if !self.added_columns.is_empty()
+            || !self.removed_columns.is_empty()
+            || !self.renamed_columns.is_empty()
+        {
+            writeln!(f, "columns:")?;
+            for column in &self.removed_columns {
+                writeln!(f, "- {}", column)?;
+            }
+            for column in &self.added_columns {
+                writeln!(f, "+ {}", column)?;
+            }
+            for (old, new) in &self.renamed_columns {
+                writeln!(f, "~ {} -> {}", old, new)?;
+            }
+        }
+        write!(
+            f,
+            "{} operations added, {} removed",
+            self.added_operations.len(),
+            self.removed_operations.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::operation::{RemoveColumnsOp, RenameColumnOp, SetNameOp};
+    use arrow_schema::{DataType, Field};
+
+    fn schema(names: &[&str]) -> Schema {
+        Schema::new(
+            names
+                .iter()
+                .map(|name| Field::new(*name, DataType::Utf8, true))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_diff() {
+        let named: AnyOperation = SetNameOp::setup("customers").into();
+        let renamed: AnyOperation = RenameColumnOp::setup("city", "town").into();
+        let removed: AnyOperation = RemoveColumnsOp::setup(vec!["phone"]).into();
+
+        let diff = BundleDiff::new(
+            "00001a",
+            "00002b",
+            std::slice::from_ref(&named),
+            &[named.clone(), renamed, removed],
+            &schema(&["name", "city", "phone"]),
+            &schema(&["name", "town", "email"]),
+        );
+        assert_eq!(2, diff.added_operations.len());
+        assert!(diff.removed_operations.is_empty());
+        assert_eq!(vec!["email"], diff.added_columns);
+        assert_eq!(vec!["phone"], diff.removed_columns);
+        assert_eq!(
+            vec![("city".to_string(), "town".to_string())],
+            diff.renamed_columns
+        );
+        assert_eq!(
+            "diff 00001a..00002b\n+ RENAME COLUMN: city to town\n+ REMOVE COLUMNS: [\"phone\"]\ncolumns:\n- phone\n+ email\n~ city -> town\n2 operations added, 0 removed",
+            diff.to_string()
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use crate::bundle::BundleCommit;
+use crate::bundle::{
+    BundleCommit, ChangeExport, CsvExportOptions, JsonExportOptions, PackInfo, ValidationWarning,
+};
 use crate::io::ObjectId;
-use crate::{AnyOperation, Bundle, BundleBuilder, BundlebaseError};
+use crate::{AnyOperation, Bundle, BundleBuilder, BundlebaseError, ColumnStats};
 use arrow_schema::SchemaRef;
 use async_trait::async_trait;
 use datafusion::common::ScalarValue;
@@ -35,6 +37,16 @@ pub trait BundleFacade {
     /// All operations applied to this bundle
     fn operations(&self) -> Vec<AnyOperation>;
 
+    /// The bundle's packs and the blocks attached to each, in the order they were attached
+    fn packs(&self) -> Vec<PackInfo>;
+
+    /// Per-column min, max and null count of each block, by block id, as recorded when the
+    /// block was attached. Blocks attached without stats are left out
+    fn block_stats(&self) -> HashMap<ObjectId, Vec<ColumnStats>>;
+
+    /// Operations belonging to changes labeled with `key=value`, in the order they were applied
+    fn operations_with_label(&self, key: &str, value: &str) -> Vec<AnyOperation>;
+
     async fn schema(&self) -> Result<SchemaRef, BundlebaseError>;
 
     /// Computes the number of rows in the bundle
@@ -44,6 +56,7 @@ pub trait BundleFacade {
     async fn dataframe(&self) -> Result<Arc<DataFrame>, BundlebaseError>;
 
     /// Executes a SQL query against the bundle data. "SELECT" keyword in SQL is optional.
+    /// Queries may start with `WITH` to define CTEs over `bundle`.
     ///
     /// Returns a new `BundleBuilder` with the query applied as an operation.
     /// Parameters can be used for parameterized queries.
@@ -56,7 +69,8 @@ pub trait BundleFacade {
     /// A new bundle with the query operation added to its operation chain.
     ///
     /// # Errors
-    /// Returns error if the query is invalid or references non-existent columns.
+    /// Returns error if the query is invalid or references non-existent columns. The query is
+    /// planned before it is added, so a failed query leaves the bundle unchanged.
     async fn select(
         &self,
         sql: &str,
@@ -94,6 +108,56 @@ pub trait BundleFacade {
     /// ```
     async fn view(&self, identifier: &str) -> Result<Bundle, BundlebaseError>;
 
+    /// Writes the bundle's current data to a CSV file, returning the number of rows written.
+    ///
+    /// `path` can be a URL or a local filesystem path. `options` controls how nulls, NaNs and
+    /// floats are written and how fields are quoted. Uncommitted changes are included.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let options = CsvExportOptions {
+    ///     null_value: "NULL".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// bundle.export_csv("export.csv", &options).await?;
+    /// ```
+    async fn export_csv(
+        &self,
+        path: &str,
+        options: &CsvExportOptions,
+    ) -> Result<u64, BundlebaseError>;
+
+    /// Writes the bundle's current data to a JSON Lines file, returning the number of rows
+    /// written.
+    ///
+    /// `path` can be a URL or a local filesystem path. `options` controls whether nulls are
+    /// written or left out. Uncommitted changes are included.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let options = JsonExportOptions {
+    ///     explicit_nulls: true,
+    /// };
+    /// bundle.export_json("export.json", &options).await?;
+    /// ```
+    async fn export_json(
+        &self,
+        path: &str,
+        options: &JsonExportOptions,
+    ) -> Result<u64, BundlebaseError>;
+
+    /// Exports the bundle's data to a parquet file, returning the number of rows written.
+    ///
+    /// `path` can be a URL or a local filesystem path. Row groups and compression follow the
+    /// `parquet_row_group_size` and `parquet_compression` settings. Uncommitted changes are
+    /// included.
+    ///
+    /// # Example
+    /// ```ignore
+    /// bundle.export_parquet("export.parquet").await?;
+    /// ```
+    async fn export_parquet(&self, path: &str) -> Result<u64, BundlebaseError>;
+
     /// Exports the bundle's data directory to an uncompressed tar archive.
     ///
     /// Creates a tar file containing all bundle data including:
@@ -122,4 +186,55 @@ pub trait BundleFacade {
     /// let archived = Bundle::open("archive.tar", None).await?;
     /// ```
     async fn export_tar(&self, tar_path: &str) -> Result<String, BundlebaseError>;
+
+    /// Exports only what changed since the commit with the given id, see `BundleCommit::id`.
+    ///
+    /// Rows added since `since_commit` are written to `added.csv` in the `dest` directory and,
+    /// with `include_removed`, rows removed to `removed.csv`. A changed row is both removed and
+    /// added. `changes.yaml` records the latest commit exported, to pass as `since_commit` next
+    /// time, so the bundle can feed downstream consumers incrementally.
+    ///
+    /// # Errors
+    /// Returns an error if `since_commit` is not in the bundle's history, e.g. because the
+    /// history was squashed past it, or if the columns changed since then. Use `export_csv` for
+    /// a full export instead. BundleBuilders must have no uncommitted changes.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let since = bundle.history()[0].id.clone();
+    /// let export = bundle.export_changes(&since, "s3://feed/2", false).await?;
+    /// // Next time, export from export.commit
+    /// ```
+    async fn export_changes(
+        &self,
+        since_commit: &str,
+        dest: &str,
+        include_removed: bool,
+    ) -> Result<ChangeExport, BundlebaseError>;
+
+    /// Checks the bundle for things that are often mistakes, such as the same file attached
+    /// through two paths so its rows are counted twice.
+    ///
+    /// Blocks of a pack with identical content are reported as `DuplicateContent`, found by
+    /// comparing the checksums of files of the same size. Nothing is changed and the warnings
+    /// never block anything, since the duplication may be intentional.
+    ///
+    /// # Example
+    /// ```ignore
+    /// for warning in bundle.validate().await? {
+    ///     println!("{}", warning);
+    /// }
+    /// ```
+    async fn validate(&self) -> Result<Vec<ValidationWarning>, BundlebaseError>;
+
+    /// Checks the bundle's schema matches `expected` in column names, types and nullability,
+    /// for pipelines to fail when a source drifts. Column order is not compared.
+    ///
+    /// # Errors
+    /// Returns an error listing every difference, e.g. `column 'id' is Utf8, expected Int64`.
+    async fn verify_schema(&self, expected: SchemaRef) -> Result<(), BundlebaseError>;
+
+    /// Like `verify_schema`, with the expected schema read from a JSON file in the format of
+    /// `schema_to_json`
+    async fn verify_schema_file(&self, path: &str) -> Result<(), BundlebaseError>;
 }
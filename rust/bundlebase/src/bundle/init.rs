@@ -1,3 +1,4 @@
+use crate::bundle::commit::{legacy_format_version, FORMAT_VERSION};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
@@ -6,6 +7,8 @@ pub static INIT_FILENAME: &str = "00000000000000000.yaml";
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct InitCommit {
+    #[serde(default = "legacy_format_version")]
+    pub format_version: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -17,6 +20,7 @@ pub struct InitCommit {
 impl InitCommit {
     pub fn new(from: Option<&Url>) -> Self {
         Self {
+            format_version: FORMAT_VERSION,
             // Only set id when creating a new bundle (from is None)
             // When extending (from is Some), id should be None and inherited from parent
             id: if from.is_none() {
@@ -31,6 +35,7 @@ impl InitCommit {
 
     pub fn new_view(view_id: &str) -> Self {
         Self {
+            format_version: FORMAT_VERSION,
             id: None,
             from: None,
             view: Some(view_id.to_string()),
@@ -0,0 +1,89 @@
+use crate::io::ObjectStoreFile;
+use crate::BundlebaseError;
+use arrow::json::writer::LineDelimited;
+use arrow::json::WriterBuilder;
+use datafusion::dataframe::DataFrame;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// How `export_json` writes values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonExportOptions {
+    /// Write null values as `"column": null` rather than leaving the key out. NaN and infinite
+    /// floats, which JSON can't represent, are written as nulls too
+    pub explicit_nulls: bool,
+}
+
+/// Writes the dataframe to `file` as JSON Lines, one object per row and one batch at a time.
+/// Returns the number of rows written.
+pub(crate) async fn write_json(
+    df: DataFrame,
+    file: &ObjectStoreFile,
+    options: &JsonExportOptions,
+) -> Result<u64, BundlebaseError> {
+    let options = options.clone();
+    let rows = Arc::new(AtomicU64::new(0));
+    let counter = rows.clone();
+
+    let batches = df.execute_stream().await?.map(move |batch| {
+        let batch = batch.map_err(std::io::Error::other)?;
+        counter.fetch_add(batch.num_rows() as u64, Ordering::Relaxed);
+
+        let mut writer = WriterBuilder::new()
+            .with_explicit_nulls(options.explicit_nulls)
+            .build::<_, LineDelimited>(Vec::new());
+        writer.write(&batch).map_err(std::io::Error::other)?;
+        writer.finish().map_err(std::io::Error::other)?;
+        Ok(bytes::Bytes::from(writer.into_inner()))
+    });
+    file.write_stream(batches).await?;
+
+    Ok(rows.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::random_memory_file;
+    use arrow::array::{ArrayRef, Float64Array, Int32Array, RecordBatch};
+    use datafusion::prelude::SessionContext;
+
+    async fn export(options: &JsonExportOptions) -> String {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from(vec![Some(1), None])) as ArrayRef,
+            ),
+            (
+                "score",
+                Arc::new(Float64Array::from(vec![Some(1.5), Some(f64::NAN)])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let df = SessionContext::new().read_batch(batch).unwrap();
+        let file = random_memory_file("export.json");
+
+        assert_eq!(2, write_json(df, &file, options).await.unwrap());
+        file.read_str().await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_json_defaults() {
+        assert_eq!(
+            "{\"id\":1,\"score\":1.5}\n{\"score\":null}\n",
+            export(&JsonExportOptions::default()).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_json_explicit_nulls() {
+        let options = JsonExportOptions {
+            explicit_nulls: true,
+        };
+        assert_eq!(
+            "{\"id\":1,\"score\":1.5}\n{\"id\":null,\"score\":null}\n",
+            export(&options).await
+        );
+    }
+}
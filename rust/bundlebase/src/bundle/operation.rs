@@ -1,46 +1,96 @@
+mod add_column;
+mod aggregate;
 mod attach_block;
+mod attach_bundle;
+mod cast_column;
+mod column_alias;
+mod create_index;
 mod create_view;
 mod define_function;
-mod create_index;
 mod define_pack;
+mod delete_rows;
+mod distinct;
 mod drop_index;
 mod drop_view;
+mod fill_null;
 mod filter;
 mod index_blocks;
 mod join;
+mod limit;
+mod mask_column;
+mod materialize_view;
+mod merge;
+mod order_by;
+mod pivot;
 mod rebuild_index;
+mod register_udf;
 mod remove_columns;
 mod rename_column;
 mod rename_view;
+mod repair_refs;
+mod revert_commit;
+mod schema_constraint;
 mod select;
 mod serde_util;
 mod set_config;
+mod set_default_view;
 mod set_description;
 mod set_name;
-
-pub use crate::bundle::operation::attach_block::AttachBlockOp;
+mod set_security_filter;
+mod union;
+mod unpivot;
+
+pub use crate::bundle::operation::add_column::AddColumnOp;
+pub use crate::bundle::operation::aggregate::{AggFunc, AggregateOp, COUNT_ALL};
+pub use crate::bundle::operation::attach_block::{AttachBlockOp, AttachSplit};
+pub use crate::bundle::operation::attach_bundle::AttachBundleOp;
+pub(crate) use crate::bundle::operation::attach_bundle::ExternalBundle;
+pub use crate::bundle::operation::cast_column::CastColumnOp;
+pub use crate::bundle::operation::column_alias::{
+    normalize_column_name, ColumnAliasOp, ORIGINAL_NAME_METADATA,
+};
+pub use crate::bundle::operation::create_index::CreateIndexOp;
 pub use crate::bundle::operation::create_view::CreateViewOp;
 pub use crate::bundle::operation::define_function::DefineFunctionOp;
-pub use crate::bundle::operation::create_index::CreateIndexOp;
 pub use crate::bundle::operation::define_pack::DefinePackOp;
+pub use crate::bundle::operation::delete_rows::DeleteRowsOp;
+pub use crate::bundle::operation::distinct::DistinctOp;
 pub use crate::bundle::operation::drop_index::DropIndexOp;
 pub use crate::bundle::operation::drop_view::DropViewOp;
+pub use crate::bundle::operation::fill_null::FillNullOp;
+pub(crate) use crate::bundle::operation::filter::substitute_parameters;
 pub use crate::bundle::operation::filter::FilterOp;
 pub use crate::bundle::operation::index_blocks::IndexBlocksOp;
 pub use crate::bundle::operation::join::{JoinOp, JoinTypeOption};
+pub use crate::bundle::operation::limit::LimitOp;
+pub use crate::bundle::operation::mask_column::{MaskColumnOp, MaskStrategy};
+pub use crate::bundle::operation::materialize_view::MaterializeViewOp;
+pub use crate::bundle::operation::merge::MergeOp;
+pub use crate::bundle::operation::order_by::{OrderByOp, SortDirection};
+pub use crate::bundle::operation::pivot::{PivotAggregate, PivotOp};
 pub use crate::bundle::operation::rebuild_index::RebuildIndexOp;
+pub use crate::bundle::operation::register_udf::RegisterUdfOp;
 pub use crate::bundle::operation::remove_columns::RemoveColumnsOp;
 pub use crate::bundle::operation::rename_column::RenameColumnOp;
 pub use crate::bundle::operation::rename_view::RenameViewOp;
+pub(crate) use crate::bundle::operation::repair_refs::{repair_operation, repair_ref};
+pub use crate::bundle::operation::repair_refs::{RefMapping, RepairRefsOp};
+pub use crate::bundle::operation::revert_commit::RevertCommitOp;
+pub use crate::bundle::operation::schema_constraint::SchemaConstraintOp;
 pub use crate::bundle::operation::select::SelectOp;
 pub use crate::bundle::operation::set_config::SetConfigOp;
+pub use crate::bundle::operation::set_default_view::SetDefaultViewOp;
 pub use crate::bundle::operation::set_description::SetDescriptionOp;
 pub use crate::bundle::operation::set_name::SetNameOp;
+pub use crate::bundle::operation::set_security_filter::SetSecurityFilterOp;
+pub use crate::bundle::operation::union::UnionOp;
+pub use crate::bundle::operation::unpivot::UnpivotOp;
 use crate::{versioning, Bundle, BundlebaseError};
 use async_trait::async_trait;
 use datafusion::error::DataFusionError;
 use datafusion::prelude::{DataFrame, SessionContext};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -51,6 +101,9 @@ use uuid::Uuid;
 pub struct BundleChange {
     pub id: Uuid,
     pub description: String,
+    /// Free-form key/value annotations, e.g. `ticket=JIRA-123`, for organizing history
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
     pub operations: Vec<AnyOperation>,
 }
 
@@ -59,14 +112,119 @@ impl BundleChange {
         Self {
             id: Uuid::new_v4(),
             description: description.to_string(),
+            labels: BTreeMap::new(),
             operations: Vec::new(),
         }
     }
+
+    /// Returns true if this change has the given label set to the given value
+    pub fn has_label(&self, key: &str, value: &str) -> bool {
+        self.labels.get(key).map(|v| v.as_str()) == Some(value)
+    }
+
+    /// Source files attached by this change, in the order they were attached
+    pub fn files(&self) -> Vec<String> {
+        let mut files = Vec::new();
+        for op in &self.operations {
+            if let AnyOperation::AttachBlock(attach) = op {
+                push_unique(&mut files, &attach.source);
+            }
+        }
+        files
+    }
+
+    /// Columns this change adds, removes, renames, converts, fills, indexes or constrains
+    pub fn columns(&self) -> Vec<String> {
+        let mut columns = Vec::new();
+        for op in &self.operations {
+            match op {
+                AnyOperation::RemoveColumns(remove) => remove
+                    .names
+                    .iter()
+                    .for_each(|name| push_unique(&mut columns, name)),
+                AnyOperation::RenameColumn(rename) => {
+                    push_unique(&mut columns, &rename.old_name);
+                    push_unique(&mut columns, &rename.new_name);
+                }
+                AnyOperation::ColumnAlias(alias) => {
+                    push_unique(&mut columns, &alias.original);
+                    push_unique(&mut columns, &alias.alias);
+                }
+                AnyOperation::AddColumn(add) => push_unique(&mut columns, &add.name),
+                AnyOperation::CastColumn(cast) => push_unique(&mut columns, &cast.column),
+                AnyOperation::FillNull(fill) => push_unique(&mut columns, &fill.column),
+                AnyOperation::CreateIndex(index) => index
+                    .indexed_columns()
+                    .iter()
+                    .for_each(|name| push_unique(&mut columns, name)),
+                AnyOperation::RebuildIndex(index) => index
+                    .indexed_columns()
+                    .iter()
+                    .for_each(|name| push_unique(&mut columns, name)),
+                AnyOperation::Merge(merge) => merge
+                    .key_columns
+                    .iter()
+                    .for_each(|name| push_unique(&mut columns, name)),
+                AnyOperation::OrderBy(order_by) => order_by
+                    .keys
+                    .iter()
+                    .for_each(|key| push_unique(&mut columns, &key.column)),
+                AnyOperation::MaskColumn(mask) => push_unique(&mut columns, &mask.column),
+                AnyOperation::Pivot(pivot) => {
+                    push_unique(&mut columns, &pivot.columns);
+                    push_unique(&mut columns, &pivot.values);
+                }
+                AnyOperation::Aggregate(aggregate) => aggregate
+                    .aggregates
+                    .iter()
+                    .filter(|a| a.column != COUNT_ALL)
+                    .for_each(|a| push_unique(&mut columns, &a.column)),
+                AnyOperation::Unpivot(unpivot) => unpivot
+                    .value_columns
+                    .iter()
+                    .for_each(|name| push_unique(&mut columns, name)),
+                AnyOperation::SchemaConstraint(constraint) => {
+                    constraint
+                        .column
+                        .iter()
+                        .chain(constraint.key.iter().flatten())
+                        .for_each(|name| push_unique(&mut columns, name));
+                }
+                _ => {}
+            }
+        }
+        columns
+    }
+
+    /// Human-readable summary of every operation in this change
+    pub fn summary(&self) -> String {
+        self.operations
+            .iter()
+            .map(|op| op.describe())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, value: &str) {
+    if !values.iter().any(|v| v == value) {
+        values.push(value.to_string());
+    }
 }
 
 impl Display for BundleChange {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Change: {}", self.description,)
+        write!(f, "Change: {}", self.description,)?;
+        if !self.labels.is_empty() {
+            let labels = self
+                .labels
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, " [{}]", labels)?;
+        }
+        Ok(())
     }
 }
 
@@ -116,22 +274,44 @@ pub trait Operation: Send + Sync + Clone + Serialize + Debug {
 pub enum AnyOperation {
     RemoveColumns(RemoveColumnsOp),
     RenameColumn(RenameColumnOp),
+    CastColumn(CastColumnOp),
+    ColumnAlias(ColumnAliasOp),
     RenameView(RenameViewOp),
+    AddColumn(AddColumnOp),
+    Aggregate(AggregateOp),
     AttachBlock(AttachBlockOp),
+    AttachBundle(AttachBundleOp),
     CreateView(CreateViewOp),
     DefineFunction(DefineFunctionOp),
+    RegisterUdf(RegisterUdfOp),
+    FillNull(FillNullOp),
     Filter(FilterOp),
     IndexBlocks(IndexBlocksOp),
     CreateIndex(CreateIndexOp),
     DefinePack(DefinePackOp),
+    DeleteRows(DeleteRowsOp),
+    Distinct(DistinctOp),
     DropIndex(DropIndexOp),
     DropView(DropViewOp),
     RebuildIndex(RebuildIndexOp),
+    RepairRefs(RepairRefsOp),
     Join(JoinOp),
+    Limit(LimitOp),
+    MaskColumn(MaskColumnOp),
+    MaterializeView(MaterializeViewOp),
+    Merge(MergeOp),
+    OrderBy(OrderByOp),
+    Pivot(PivotOp),
+    RevertCommit(RevertCommitOp),
+    SchemaConstraint(SchemaConstraintOp),
     Select(SelectOp),
     SetConfig(SetConfigOp),
+    SetDefaultView(SetDefaultViewOp),
     SetName(SetNameOp),
+    SetSecurityFilter(SetSecurityFilterOp),
     SetDescription(SetDescriptionOp),
+    Union(UnionOp),
+    Unpivot(UnpivotOp),
 }
 
 #[async_trait]
@@ -140,22 +320,44 @@ impl Operation for AnyOperation {
         match self {
             AnyOperation::RemoveColumns(op) => op.describe(),
             AnyOperation::RenameColumn(op) => op.describe(),
+            AnyOperation::CastColumn(op) => op.describe(),
+            AnyOperation::ColumnAlias(op) => op.describe(),
             AnyOperation::RenameView(op) => op.describe(),
+            AnyOperation::AddColumn(op) => op.describe(),
+            AnyOperation::Aggregate(op) => op.describe(),
             AnyOperation::AttachBlock(op) => op.describe(),
+            AnyOperation::AttachBundle(op) => op.describe(),
             AnyOperation::CreateView(op) => op.describe(),
             AnyOperation::DefineFunction(op) => op.describe(),
+            AnyOperation::RegisterUdf(op) => op.describe(),
+            AnyOperation::FillNull(op) => op.describe(),
             AnyOperation::Filter(op) => op.describe(),
             AnyOperation::IndexBlocks(op) => op.describe(),
             AnyOperation::CreateIndex(op) => op.describe(),
             AnyOperation::DefinePack(op) => op.describe(),
+            AnyOperation::DeleteRows(op) => op.describe(),
+            AnyOperation::Distinct(op) => op.describe(),
             AnyOperation::DropIndex(op) => op.describe(),
             AnyOperation::DropView(op) => op.describe(),
             AnyOperation::RebuildIndex(op) => op.describe(),
+            AnyOperation::RepairRefs(op) => op.describe(),
             AnyOperation::Join(op) => op.describe(),
+            AnyOperation::Limit(op) => op.describe(),
+            AnyOperation::MaskColumn(op) => op.describe(),
+            AnyOperation::MaterializeView(op) => op.describe(),
+            AnyOperation::Merge(op) => op.describe(),
+            AnyOperation::OrderBy(op) => op.describe(),
+            AnyOperation::Pivot(op) => op.describe(),
+            AnyOperation::RevertCommit(op) => op.describe(),
+            AnyOperation::SchemaConstraint(op) => op.describe(),
             AnyOperation::Select(op) => op.describe(),
             AnyOperation::SetConfig(op) => op.describe(),
+            AnyOperation::SetDefaultView(op) => op.describe(),
             AnyOperation::SetName(op) => op.describe(),
+            AnyOperation::SetSecurityFilter(op) => op.describe(),
             AnyOperation::SetDescription(op) => op.describe(),
+            AnyOperation::Union(op) => op.describe(),
+            AnyOperation::Unpivot(op) => op.describe(),
         }
     }
 
@@ -163,22 +365,44 @@ impl Operation for AnyOperation {
         match self {
             AnyOperation::RemoveColumns(op) => op.check(bundle).await,
             AnyOperation::RenameColumn(op) => op.check(bundle).await,
+            AnyOperation::CastColumn(op) => op.check(bundle).await,
+            AnyOperation::ColumnAlias(op) => op.check(bundle).await,
             AnyOperation::RenameView(op) => op.check(bundle).await,
+            AnyOperation::AddColumn(op) => op.check(bundle).await,
+            AnyOperation::Aggregate(op) => op.check(bundle).await,
             AnyOperation::AttachBlock(op) => op.check(bundle).await,
+            AnyOperation::AttachBundle(op) => op.check(bundle).await,
             AnyOperation::CreateView(op) => op.check(bundle).await,
             AnyOperation::DefineFunction(op) => op.check(bundle).await,
+            AnyOperation::RegisterUdf(op) => op.check(bundle).await,
+            AnyOperation::FillNull(op) => op.check(bundle).await,
             AnyOperation::Filter(op) => op.check(bundle).await,
             AnyOperation::IndexBlocks(op) => op.check(bundle).await,
             AnyOperation::CreateIndex(op) => op.check(bundle).await,
             AnyOperation::DefinePack(op) => op.check(bundle).await,
+            AnyOperation::DeleteRows(op) => op.check(bundle).await,
+            AnyOperation::Distinct(op) => op.check(bundle).await,
             AnyOperation::DropIndex(op) => op.check(bundle).await,
             AnyOperation::DropView(op) => op.check(bundle).await,
             AnyOperation::RebuildIndex(op) => op.check(bundle).await,
+            AnyOperation::RepairRefs(op) => op.check(bundle).await,
             AnyOperation::Join(op) => op.check(bundle).await,
+            AnyOperation::Limit(op) => op.check(bundle).await,
+            AnyOperation::MaskColumn(op) => op.check(bundle).await,
+            AnyOperation::MaterializeView(op) => op.check(bundle).await,
+            AnyOperation::Merge(op) => op.check(bundle).await,
+            AnyOperation::OrderBy(op) => op.check(bundle).await,
+            AnyOperation::Pivot(op) => op.check(bundle).await,
+            AnyOperation::RevertCommit(op) => op.check(bundle).await,
+            AnyOperation::SchemaConstraint(op) => op.check(bundle).await,
             AnyOperation::Select(op) => op.check(bundle).await,
             AnyOperation::SetConfig(op) => op.check(bundle).await,
+            AnyOperation::SetDefaultView(op) => op.check(bundle).await,
             AnyOperation::SetName(op) => op.check(bundle).await,
+            AnyOperation::SetSecurityFilter(op) => op.check(bundle).await,
             AnyOperation::SetDescription(op) => op.check(bundle).await,
+            AnyOperation::Union(op) => op.check(bundle).await,
+            AnyOperation::Unpivot(op) => op.check(bundle).await,
         }
     }
 
@@ -186,22 +410,44 @@ impl Operation for AnyOperation {
         match self {
             AnyOperation::RemoveColumns(op) => op.apply(bundle).await,
             AnyOperation::RenameColumn(op) => op.apply(bundle).await,
+            AnyOperation::CastColumn(op) => op.apply(bundle).await,
+            AnyOperation::ColumnAlias(op) => op.apply(bundle).await,
             AnyOperation::RenameView(op) => op.apply(bundle).await,
+            AnyOperation::AddColumn(op) => op.apply(bundle).await,
+            AnyOperation::Aggregate(op) => op.apply(bundle).await,
             AnyOperation::AttachBlock(op) => op.apply(bundle).await,
+            AnyOperation::AttachBundle(op) => op.apply(bundle).await,
             AnyOperation::CreateView(op) => op.apply(bundle).await,
             AnyOperation::DefineFunction(op) => op.apply(bundle).await,
+            AnyOperation::RegisterUdf(op) => op.apply(bundle).await,
+            AnyOperation::FillNull(op) => op.apply(bundle).await,
             AnyOperation::Filter(op) => op.apply(bundle).await,
             AnyOperation::IndexBlocks(op) => op.apply(bundle).await,
             AnyOperation::CreateIndex(op) => op.apply(bundle).await,
             AnyOperation::DefinePack(op) => op.apply(bundle).await,
+            AnyOperation::DeleteRows(op) => op.apply(bundle).await,
+            AnyOperation::Distinct(op) => op.apply(bundle).await,
             AnyOperation::DropIndex(op) => op.apply(bundle).await,
             AnyOperation::DropView(op) => op.apply(bundle).await,
             AnyOperation::RebuildIndex(op) => op.apply(bundle).await,
+            AnyOperation::RepairRefs(op) => op.apply(bundle).await,
             AnyOperation::Join(op) => op.apply(bundle).await,
+            AnyOperation::Limit(op) => op.apply(bundle).await,
+            AnyOperation::MaskColumn(op) => op.apply(bundle).await,
+            AnyOperation::MaterializeView(op) => op.apply(bundle).await,
+            AnyOperation::Merge(op) => op.apply(bundle).await,
+            AnyOperation::OrderBy(op) => op.apply(bundle).await,
+            AnyOperation::Pivot(op) => op.apply(bundle).await,
+            AnyOperation::RevertCommit(op) => op.apply(bundle).await,
+            AnyOperation::SchemaConstraint(op) => op.apply(bundle).await,
             AnyOperation::Select(op) => op.apply(bundle).await,
             AnyOperation::SetConfig(op) => op.apply(bundle).await,
+            AnyOperation::SetDefaultView(op) => op.apply(bundle).await,
             AnyOperation::SetName(op) => op.apply(bundle).await,
+            AnyOperation::SetSecurityFilter(op) => op.apply(bundle).await,
             AnyOperation::SetDescription(op) => op.apply(bundle).await,
+            AnyOperation::Union(op) => op.apply(bundle).await,
+            AnyOperation::Unpivot(op) => op.apply(bundle).await,
         }
     }
 
@@ -213,22 +459,44 @@ impl Operation for AnyOperation {
         match self {
             AnyOperation::RemoveColumns(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::RenameColumn(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::CastColumn(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::ColumnAlias(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::RenameView(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::AddColumn(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::Aggregate(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::AttachBlock(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::AttachBundle(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::CreateView(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::DefineFunction(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::RegisterUdf(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::FillNull(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::Filter(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::IndexBlocks(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::CreateIndex(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::DefinePack(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::DeleteRows(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::Distinct(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::DropIndex(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::DropView(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::RebuildIndex(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::RepairRefs(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::Join(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::Limit(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::MaskColumn(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::MaterializeView(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::Merge(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::OrderBy(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::Pivot(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::RevertCommit(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::SchemaConstraint(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::Select(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::SetConfig(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::SetDefaultView(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::SetName(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::SetSecurityFilter(op) => op.apply_dataframe(df, ctx).await,
             AnyOperation::SetDescription(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::Union(op) => op.apply_dataframe(df, ctx).await,
+            AnyOperation::Unpivot(op) => op.apply_dataframe(df, ctx).await,
         }
     }
 
@@ -236,22 +504,44 @@ impl Operation for AnyOperation {
         match self {
             AnyOperation::RemoveColumns(op) => op.version(),
             AnyOperation::RenameColumn(op) => op.version(),
+            AnyOperation::CastColumn(op) => op.version(),
+            AnyOperation::ColumnAlias(op) => op.version(),
             AnyOperation::RenameView(op) => op.version(),
+            AnyOperation::AddColumn(op) => op.version(),
+            AnyOperation::Aggregate(op) => op.version(),
             AnyOperation::AttachBlock(op) => op.version(),
+            AnyOperation::AttachBundle(op) => op.version(),
             AnyOperation::CreateView(op) => op.version(),
             AnyOperation::DefineFunction(op) => op.version(),
+            AnyOperation::RegisterUdf(op) => op.version(),
+            AnyOperation::FillNull(op) => op.version(),
             AnyOperation::Filter(op) => op.version(),
             AnyOperation::IndexBlocks(op) => op.version(),
             AnyOperation::CreateIndex(op) => op.version(),
             AnyOperation::DefinePack(op) => op.version(),
+            AnyOperation::DeleteRows(op) => op.version(),
+            AnyOperation::Distinct(op) => op.version(),
             AnyOperation::DropIndex(op) => op.version(),
             AnyOperation::DropView(op) => op.version(),
             AnyOperation::RebuildIndex(op) => op.version(),
+            AnyOperation::RepairRefs(op) => op.version(),
             AnyOperation::Join(op) => op.version(),
+            AnyOperation::Limit(op) => op.version(),
+            AnyOperation::MaskColumn(op) => op.version(),
+            AnyOperation::MaterializeView(op) => op.version(),
+            AnyOperation::Merge(op) => op.version(),
+            AnyOperation::OrderBy(op) => op.version(),
+            AnyOperation::Pivot(op) => op.version(),
+            AnyOperation::RevertCommit(op) => op.version(),
+            AnyOperation::SchemaConstraint(op) => op.version(),
             AnyOperation::Select(op) => op.version(),
             AnyOperation::SetConfig(op) => op.version(),
+            AnyOperation::SetDefaultView(op) => op.version(),
             AnyOperation::SetName(op) => op.version(),
+            AnyOperation::SetSecurityFilter(op) => op.version(),
             AnyOperation::SetDescription(op) => op.version(),
+            AnyOperation::Union(op) => op.version(),
+            AnyOperation::Unpivot(op) => op.version(),
         }
     }
 
@@ -259,22 +549,44 @@ impl Operation for AnyOperation {
         match self {
             AnyOperation::RemoveColumns(op) => op.allowed_on_view(),
             AnyOperation::RenameColumn(op) => op.allowed_on_view(),
+            AnyOperation::CastColumn(op) => op.allowed_on_view(),
+            AnyOperation::ColumnAlias(op) => op.allowed_on_view(),
             AnyOperation::RenameView(op) => op.allowed_on_view(),
+            AnyOperation::AddColumn(op) => op.allowed_on_view(),
+            AnyOperation::Aggregate(op) => op.allowed_on_view(),
             AnyOperation::AttachBlock(op) => op.allowed_on_view(),
+            AnyOperation::AttachBundle(op) => op.allowed_on_view(),
             AnyOperation::CreateView(op) => op.allowed_on_view(),
             AnyOperation::DefineFunction(op) => op.allowed_on_view(),
+            AnyOperation::RegisterUdf(op) => op.allowed_on_view(),
+            AnyOperation::FillNull(op) => op.allowed_on_view(),
             AnyOperation::Filter(op) => op.allowed_on_view(),
             AnyOperation::IndexBlocks(op) => op.allowed_on_view(),
             AnyOperation::CreateIndex(op) => op.allowed_on_view(),
             AnyOperation::DefinePack(op) => op.allowed_on_view(),
+            AnyOperation::DeleteRows(op) => op.allowed_on_view(),
+            AnyOperation::Distinct(op) => op.allowed_on_view(),
             AnyOperation::DropIndex(op) => op.allowed_on_view(),
             AnyOperation::DropView(op) => op.allowed_on_view(),
             AnyOperation::RebuildIndex(op) => op.allowed_on_view(),
+            AnyOperation::RepairRefs(op) => op.allowed_on_view(),
             AnyOperation::Join(op) => op.allowed_on_view(),
+            AnyOperation::Limit(op) => op.allowed_on_view(),
+            AnyOperation::MaskColumn(op) => op.allowed_on_view(),
+            AnyOperation::MaterializeView(op) => op.allowed_on_view(),
+            AnyOperation::Merge(op) => op.allowed_on_view(),
+            AnyOperation::OrderBy(op) => op.allowed_on_view(),
+            AnyOperation::Pivot(op) => op.allowed_on_view(),
+            AnyOperation::RevertCommit(op) => op.allowed_on_view(),
+            AnyOperation::SchemaConstraint(op) => op.allowed_on_view(),
             AnyOperation::Select(op) => op.allowed_on_view(),
             AnyOperation::SetConfig(op) => op.allowed_on_view(),
+            AnyOperation::SetDefaultView(op) => op.allowed_on_view(),
             AnyOperation::SetName(op) => op.allowed_on_view(),
+            AnyOperation::SetSecurityFilter(op) => op.allowed_on_view(),
             AnyOperation::SetDescription(op) => op.allowed_on_view(),
+            AnyOperation::Union(op) => op.allowed_on_view(),
+            AnyOperation::Unpivot(op) => op.allowed_on_view(),
         }
     }
 }
@@ -292,12 +604,30 @@ impl From<RenameColumnOp> for AnyOperation {
     }
 }
 
+impl From<CastColumnOp> for AnyOperation {
+    fn from(config: CastColumnOp) -> Self {
+        AnyOperation::CastColumn(config)
+    }
+}
+
+impl From<ColumnAliasOp> for AnyOperation {
+    fn from(config: ColumnAliasOp) -> Self {
+        AnyOperation::ColumnAlias(config)
+    }
+}
+
 impl From<RenameViewOp> for AnyOperation {
     fn from(config: RenameViewOp) -> Self {
         AnyOperation::RenameView(config)
     }
 }
 
+impl From<AttachBundleOp> for AnyOperation {
+    fn from(config: AttachBundleOp) -> Self {
+        AnyOperation::AttachBundle(config)
+    }
+}
+
 impl From<AttachBlockOp> for AnyOperation {
     fn from(config: AttachBlockOp) -> Self {
         AnyOperation::AttachBlock(config)
@@ -316,6 +646,24 @@ impl From<DefineFunctionOp> for AnyOperation {
     }
 }
 
+impl From<RegisterUdfOp> for AnyOperation {
+    fn from(config: RegisterUdfOp) -> Self {
+        AnyOperation::RegisterUdf(config)
+    }
+}
+
+impl From<DeleteRowsOp> for AnyOperation {
+    fn from(config: DeleteRowsOp) -> Self {
+        AnyOperation::DeleteRows(config)
+    }
+}
+
+impl From<FillNullOp> for AnyOperation {
+    fn from(config: FillNullOp) -> Self {
+        AnyOperation::FillNull(config)
+    }
+}
+
 impl From<FilterOp> for AnyOperation {
     fn from(config: FilterOp) -> Self {
         AnyOperation::Filter(config)
@@ -334,18 +682,108 @@ impl From<JoinOp> for AnyOperation {
     }
 }
 
+impl From<LimitOp> for AnyOperation {
+    fn from(config: LimitOp) -> Self {
+        AnyOperation::Limit(config)
+    }
+}
+
+impl From<MaskColumnOp> for AnyOperation {
+    fn from(config: MaskColumnOp) -> Self {
+        AnyOperation::MaskColumn(config)
+    }
+}
+
+impl From<AddColumnOp> for AnyOperation {
+    fn from(config: AddColumnOp) -> Self {
+        AnyOperation::AddColumn(config)
+    }
+}
+
+impl From<AggregateOp> for AnyOperation {
+    fn from(config: AggregateOp) -> Self {
+        AnyOperation::Aggregate(config)
+    }
+}
+
+impl From<PivotOp> for AnyOperation {
+    fn from(config: PivotOp) -> Self {
+        AnyOperation::Pivot(config)
+    }
+}
+
+impl From<UnionOp> for AnyOperation {
+    fn from(config: UnionOp) -> Self {
+        AnyOperation::Union(config)
+    }
+}
+
+impl From<UnpivotOp> for AnyOperation {
+    fn from(config: UnpivotOp) -> Self {
+        AnyOperation::Unpivot(config)
+    }
+}
+
+impl From<MaterializeViewOp> for AnyOperation {
+    fn from(config: MaterializeViewOp) -> Self {
+        AnyOperation::MaterializeView(config)
+    }
+}
+
+impl From<MergeOp> for AnyOperation {
+    fn from(config: MergeOp) -> Self {
+        AnyOperation::Merge(config)
+    }
+}
+
+impl From<OrderByOp> for AnyOperation {
+    fn from(config: OrderByOp) -> Self {
+        AnyOperation::OrderBy(config)
+    }
+}
+
+impl From<RevertCommitOp> for AnyOperation {
+    fn from(config: RevertCommitOp) -> Self {
+        AnyOperation::RevertCommit(config)
+    }
+}
+
+impl From<SchemaConstraintOp> for AnyOperation {
+    fn from(config: SchemaConstraintOp) -> Self {
+        AnyOperation::SchemaConstraint(config)
+    }
+}
+
 impl From<SelectOp> for AnyOperation {
     fn from(config: SelectOp) -> Self {
         AnyOperation::Select(config)
     }
 }
 
+impl From<SetDefaultViewOp> for AnyOperation {
+    fn from(config: SetDefaultViewOp) -> Self {
+        AnyOperation::SetDefaultView(config)
+    }
+}
+
+impl From<SetSecurityFilterOp> for AnyOperation {
+    fn from(config: SetSecurityFilterOp) -> Self {
+        AnyOperation::SetSecurityFilter(config)
+    }
+}
+
 impl From<SetNameOp> for AnyOperation {
     fn from(config: SetNameOp) -> Self {
         AnyOperation::SetName(config)
     }
 }
 
+impl From<RepairRefsOp> for AnyOperation {
+    fn from(config: RepairRefsOp) -> Self {
+        AnyOperation::RepairRefs(config)
+    }
+}
+
 impl From<SetDescriptionOp> for AnyOperation {
     fn from(config: SetDescriptionOp) -> Self {
         AnyOperation::SetDescription(config)
@@ -364,6 +802,12 @@ impl From<DefinePackOp> for AnyOperation {
     }
 }
 
+impl From<DistinctOp> for AnyOperation {
+    fn from(config: DistinctOp) -> Self {
+        AnyOperation::Distinct(config)
+    }
+}
+
 impl From<DropIndexOp> for AnyOperation {
     fn from(config: DropIndexOp) -> Self {
         AnyOperation::DropIndex(config)
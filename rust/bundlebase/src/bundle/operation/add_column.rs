@@ -0,0 +1,96 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::SessionContext;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Adds a column computed from a SQL expression over the other columns, e.g.
+/// `first || ' ' || last` or `extract(year from date)`. It is computed again each time the
+/// bundle is read, so it follows later changes to the data.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AddColumnOp {
+    pub name: String,
+    pub expression: String,
+}
+
+impl AddColumnOp {
+    pub fn setup(name: &str, expression: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            expression: expression.to_string(),
+        }
+    }
+
+    /// Plans the expression against the bundle's current data so a bad one fails before the
+    /// operation is recorded. This is done when the column is added rather than in `check`,
+    /// which also runs each time the bundle is opened.
+    pub(crate) async fn check_expression(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let df = bundle.raw_dataframe().await?.as_ref().clone();
+        self.apply_dataframe(df, bundle.ctx()).await.map_err(|e| {
+            format!(
+                "Invalid expression for column '{}': {}: {}",
+                self.name, self.expression, e
+            )
+        })?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Operation for AddColumnOp {
+    fn describe(&self) -> String {
+        format!("ADD COLUMN: {} = {}", self.name, self.expression)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        bundle.check_not_security_column(&self.name, "add column")?;
+        if bundle
+            .raw_schema()
+            .await?
+            .field_with_name(&self.name)
+            .is_ok()
+        {
+            return Err(format!("Column '{}' already exists", self.name).into());
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let expr = df.parse_sql_expr(&self.expression)?;
+        Ok(df.with_column(&self.name, expr)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = AddColumnOp::setup("full_name", "first || ' ' || last");
+        assert_eq!(
+            op.describe(),
+            "ADD COLUMN: full_name = first || ' ' || last"
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = AddColumnOp::setup("total", "price * quantity");
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "name: total\nexpression: price * quantity\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
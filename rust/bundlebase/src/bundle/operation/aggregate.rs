@@ -0,0 +1,173 @@
+use crate::bundle::operation::{Operation, PivotAggregate};
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::logical_expr::Expr;
+use datafusion::prelude::{ident, lit, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Function an aggregate column is computed with, the same ones pivoted cells are combined with
+pub type AggFunc = PivotAggregate;
+
+/// Column name that counts every row rather than the non-null values of one column
+pub const COUNT_ALL: &str = "*";
+
+/// One column computed by an `AggregateOp`: `function(column) AS alias`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Aggregation {
+    pub column: String,
+    pub function: AggFunc,
+    pub alias: String,
+}
+
+impl Aggregation {
+    fn expr(&self) -> Expr {
+        let value = if self.column == COUNT_ALL {
+            lit(1)
+        } else {
+            ident(&self.column)
+        };
+        self.function.expr(value).alias(&self.alias)
+    }
+}
+
+/// Groups rows by the `group_by` columns, leaving one row per group with those columns followed
+/// by each of the `aggregates`. Without `group_by` the whole bundle becomes a single row.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateOp {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_by: Vec<String>,
+    pub aggregates: Vec<Aggregation>,
+}
+
+impl AggregateOp {
+    pub fn setup(group_by: &[&str], aggregates: &[(&str, AggFunc, &str)]) -> Self {
+        Self {
+            group_by: group_by.iter().map(|c| c.to_string()).collect(),
+            aggregates: aggregates
+                .iter()
+                .map(|(column, function, alias)| Aggregation {
+                    column: column.to_string(),
+                    function: *function,
+                    alias: alias.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl Operation for AggregateOp {
+    fn describe(&self) -> String {
+        let aggregates = self
+            .aggregates
+            .iter()
+            .map(|a| format!("{}({}) AS {}", a.function, a.column, a.alias))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.group_by.is_empty() {
+            format!("AGGREGATE: {}", aggregates)
+        } else {
+            format!("AGGREGATE: {} BY {}", aggregates, self.group_by.join(", "))
+        }
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.aggregates.is_empty() {
+            return Err("Aggregate needs at least one aggregate column".into());
+        }
+        let schema = bundle.raw_schema().await?;
+        for column in &self.group_by {
+            schema.field_with_name(column)?;
+        }
+        for aggregate in &self.aggregates {
+            if aggregate.column == COUNT_ALL {
+                if aggregate.function != AggFunc::Count {
+                    return Err(format!(
+                        "Cannot {} '{}': only count can be computed over all rows",
+                        aggregate.function, COUNT_ALL
+                    )
+                    .into());
+                }
+            } else {
+                schema.field_with_name(&aggregate.column)?;
+            }
+        }
+
+        let mut names = HashSet::new();
+        for name in self
+            .group_by
+            .iter()
+            .chain(self.aggregates.iter().map(|a| &a.alias))
+        {
+            if !names.insert(name) {
+                return Err(format!("Aggregate would create column '{}' twice", name).into());
+            }
+        }
+        for aggregate in &self.aggregates {
+            bundle.check_not_security_column(&aggregate.alias, "define column")?;
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // The rows are all new, so any declared key no longer applies
+        bundle.key_columns.clear();
+        bundle
+            .nullable_columns
+            .retain(|column, _| self.group_by.contains(column));
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let group_by = self.group_by.iter().map(ident).collect();
+        let aggregates = self.aggregates.iter().map(Aggregation::expr).collect();
+        Ok(df.aggregate(group_by, aggregates)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op() -> AggregateOp {
+        AggregateOp::setup(
+            &["country"],
+            &[
+                (COUNT_ALL, AggFunc::Count, "customers"),
+                ("sales", AggFunc::Sum, "total_sales"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            op().describe(),
+            "AGGREGATE: count(*) AS customers, sum(sales) AS total_sales BY country"
+        );
+        assert_eq!(
+            AggregateOp::setup(&[], &[("sales", AggFunc::Max, "top")]).describe(),
+            "AGGREGATE: max(sales) AS top"
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let serialized = serde_yaml::to_string(&op()).expect("Failed to serialize");
+        assert_eq!(
+            serialized,
+            "groupBy:\n- country\naggregates:\n- column: '*'\n  function: count\n  alias: customers\n- column: sales\n  function: sum\n  alias: total_sales\n"
+        );
+        assert_eq!(op(), serde_yaml::from_str(&serialized).unwrap());
+    }
+}
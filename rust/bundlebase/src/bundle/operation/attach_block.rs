@@ -1,5 +1,9 @@
-use crate::bundle::operation::Operation;
-use crate::data::{DataBlock, ObjectId};
+use crate::bundle::operation::{AnyOperation, Operation};
+use crate::bundle::pack_info::block_name;
+use crate::bundle::write_parquet_files;
+use crate::data::{ColumnStats, DataBlock, DataReader, ObjectId};
+use crate::io::with_metadata_cache;
+use crate::metrics::{OperationCategory, OperationOutcome, OperationTimer};
 use crate::progress::ProgressScope;
 use crate::{Bundle, BundleBuilder, BundlebaseError};
 use arrow_schema::SchemaRef;
@@ -7,7 +11,45 @@ use async_trait::async_trait;
 use datafusion::common::DataFusionError;
 use log::debug;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use std::sync::Arc;
+use uuid::Uuid;
+
+/// How an attached file is divided into partitions when it is scanned.
+///
+/// A file read in place has its scan split into byte ranges, with parquet ranges aligned to row
+/// groups. With `BundleBuilder::attach_as_parquet`, it is instead how many parquet blocks the
+/// file is written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttachSplit {
+    /// Split files larger than the `attach_split_mb` setting into partitions of about that size
+    #[default]
+    Auto,
+    /// Always scan the file as one partition
+    Single,
+    /// Split the file into this many partitions
+    Parts(usize),
+}
+
+impl FromStr for AttachSplit {
+    type Err = BundlebaseError;
+
+    /// Parses `auto`, `single`, or a number of partitions
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(AttachSplit::Auto),
+            "single" => Ok(AttachSplit::Single),
+            other => match other.parse::<usize>() {
+                Ok(parts) if parts > 0 => Ok(AttachSplit::Parts(parts)),
+                _ => Err(format!(
+                    "Invalid split '{}': must be auto, single or a positive number of partitions",
+                    s
+                )
+                .into()),
+            },
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -16,12 +58,35 @@ pub struct AttachBlockOp {
     pub version: String,
     pub id: ObjectId,
     pub pack_id: ObjectId,
+    /// Reproducible name of the block within its pack, see `block_name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Format the source was resolved as when attached, so reopening doesn't depend on detection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Compression the source was read with (e.g. "gzip"), detected from its suffix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub layout: Option<String>,
+    /// Number of partitions the source is scanned as, chosen when attached. `None` leaves it to
+    /// the query planner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partitions: Option<usize>,
+    /// Parquet row groups the scan is limited to, in file order. `None` reads the whole file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row_groups: Option<Vec<usize>>,
+    /// Source this block was written from as parquet, see `BundleBuilder::attach_as_parquet`.
+    /// `None` for a source read in place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted_from: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub num_rows: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bytes: Option<usize>,
+    /// Per-column zone maps read from the block when attached, see `BundleConfig::block_stats`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Vec<ColumnStats>>,
     #[serde(
         skip_serializing_if = "Option::is_none",
         serialize_with = "super::serde_util::serialize_schema_option",
@@ -34,6 +99,31 @@ impl AttachBlockOp {
     pub async fn setup(
         pack_id: &ObjectId,
         source: &str,
+        split: AttachSplit,
+        row_groups: Option<&[usize]>,
+        builder: &BundleBuilder,
+    ) -> Result<Self, BundlebaseError> {
+        let timer =
+            OperationTimer::start(OperationCategory::Attach, "attach").with_label("source", source);
+
+        // The source's metadata is fetched once for the whole attach, and fresh on the next one
+        let result = with_metadata_cache(Self::read_source(
+            pack_id, source, split, row_groups, builder,
+        ))
+        .await;
+        timer.finish(if result.is_ok() {
+            OperationOutcome::Success
+        } else {
+            OperationOutcome::Error
+        });
+        result
+    }
+
+    async fn read_source(
+        pack_id: &ObjectId,
+        source: &str,
+        split: AttachSplit,
+        row_groups: Option<&[usize]>,
         builder: &BundleBuilder,
     ) -> Result<Self, BundlebaseError> {
         // Create progress scope (indeterminate - we don't know how many steps)
@@ -45,10 +135,22 @@ impl AttachBlockOp {
         let block_id = ObjectId::generate();
 
         _progress.update(1, Some("Creating adapter"));
+        let format = builder
+            .bundle
+            .adapter_factory
+            .resolve_format(source, builder.bundle())
+            .await?;
         let adapter = builder
             .bundle
             .adapter_factory
-            .reader(source, &block_id, builder.bundle(), None, None)
+            .reader(
+                source,
+                &block_id,
+                builder.bundle(),
+                None,
+                None,
+                format.clone(),
+            )
             .await?;
 
         _progress.update(2, Some("Reading version"));
@@ -57,15 +159,30 @@ impl AttachBlockOp {
         _progress.update(3, Some("Reading schema"));
         let schema = adapter.read_schema().await?;
 
+        let sequence = builder
+            .bundle
+            .get_pack(pack_id)
+            .map(|pack| pack.blocks().len())
+            .unwrap_or(0)
+            + 1;
+        let name = block_name(source, &version, sequence);
+
         let mut op = AttachBlockOp {
             source: source.to_string(),
             num_rows: None,
             bytes: None,
+            stats: None,
             version,
             schema,
             id: block_id,
             pack_id: pack_id.clone(),
+            name: Some(name),
+            format,
+            compression: adapter.compression(),
             layout: None,
+            partitions: None,
+            row_groups: None,
+            converted_from: None,
         };
 
         _progress.update(4, Some("Reading statistics"));
@@ -79,11 +196,210 @@ impl AttachBlockOp {
             }
         }
 
+        if let Some(row_groups) = row_groups {
+            let group_rows = adapter.read_row_groups().await?.ok_or_else(|| {
+                format!(
+                    "Cannot select row groups of {}: only parquet files have row groups",
+                    source
+                )
+            })?;
+            if row_groups.is_empty() {
+                return Err(format!("No row groups selected from {}", source).into());
+            }
+            if let Some(row_group) = row_groups.iter().find(|&&g| g >= group_rows.len()) {
+                return Err(format!(
+                    "Row group {} is out of range: {} has {} row groups",
+                    row_group,
+                    source,
+                    group_rows.len()
+                )
+                .into());
+            }
+            op.num_rows = Some(row_groups.iter().map(|&g| group_rows[g]).sum());
+            op.row_groups = Some(row_groups.to_vec());
+        }
+
         _progress.update(5, Some("Building layout"));
         op.layout = adapter.build_layout(builder.data_dir()).await?;
 
+        if let Some(schema) = &op.schema {
+            if builder.bundle.config().block_stats()? {
+                _progress.update(6, Some("Computing column statistics"));
+                // The adapter was created before the schema was known, so scan with one that has it
+                let reader = builder
+                    .bundle
+                    .adapter_factory
+                    .reader(
+                        source,
+                        &block_id,
+                        builder.bundle(),
+                        Some(schema.clone()),
+                        op.layout.clone(),
+                        op.format.clone(),
+                    )
+                    .await?;
+                let mut data = reader.data_source(None, &[], None, None).await?;
+                if let Some(row_groups) = &op.row_groups {
+                    data = reader.select_row_groups(data, row_groups).await?;
+                }
+                op.stats = Some(ColumnStats::compute(data, schema, builder.bundle.ctx()).await?);
+            }
+        }
+
+        op.partitions = match split {
+            // A compressed file can only be read from its start
+            AttachSplit::Parts(parts) if parts > 1 && op.compression.is_some() => {
+                return Err(format!(
+                    "Cannot split {} into {} partitions: compressed files can't be split",
+                    source, parts
+                )
+                .into())
+            }
+            AttachSplit::Auto if op.compression.is_some() => None,
+            AttachSplit::Single => Some(1),
+            AttachSplit::Parts(parts) => Some(parts),
+            AttachSplit::Auto if op.format.is_some() => {
+                let size = adapter.read_size().await?.unwrap_or(0) as usize;
+                let parts = size.div_ceil(builder.bundle.config().attach_split_size()?);
+                (parts > 1).then_some(parts)
+            }
+            // Function sources aren't files, so there is nothing to split
+            AttachSplit::Auto => None,
+        };
+
         Ok(op)
     }
+
+    /// Writes a CSV or JSON source into the bundle's data directory as parquet, split into
+    /// files of whole row groups as `split` asks, and returns their paths relative to the data
+    /// directory. Parquet and function sources are returned as they are, since they are read in
+    /// place.
+    ///
+    /// Splitting into several files counts the source's rows first, so it is read twice.
+    pub async fn convert_to_parquet(
+        source: &str,
+        split: AttachSplit,
+        builder: &BundleBuilder,
+    ) -> Result<Vec<String>, BundlebaseError> {
+        let bundle = builder.bundle();
+        let format = bundle
+            .adapter_factory
+            .resolve_format(source, bundle)
+            .await?;
+        if format.as_deref().is_none_or(|format| format == "parquet") {
+            return Ok(vec![source.to_string()]);
+        }
+
+        let _progress = ProgressScope::new(&format!("Converting '{}' to parquet", source), None);
+        let block_id = ObjectId::generate();
+        let reader = bundle
+            .adapter_factory
+            .reader(source, &block_id, bundle, None, None, format.clone())
+            .await?;
+        let schema = reader
+            .read_schema()
+            .await?
+            .ok_or_else(|| format!("Cannot convert {}: its schema can't be read", source))?;
+        let version = reader.read_version().await?;
+        let size = reader.read_size().await?.unwrap_or(0) as usize;
+        // The first reader was created before the schema was known, so scan with one that has it
+        let reader = bundle
+            .adapter_factory
+            .reader(
+                source,
+                &block_id,
+                bundle,
+                Some(schema.clone()),
+                None,
+                format,
+            )
+            .await?;
+        let block = DataBlock::new(
+            block_id,
+            schema,
+            &version,
+            reader,
+            bundle.indexes().clone(),
+            Arc::new(bundle.data_dir().clone()),
+            bundle.config(),
+        );
+        let df = bundle.ctx().read_table(Arc::new(block))?;
+
+        let parts = match split {
+            AttachSplit::Single => 1,
+            AttachSplit::Parts(parts) => parts,
+            AttachSplit::Auto => size.div_ceil(bundle.config().attach_split_size()?),
+        };
+        let properties = bundle.config().parquet_writer_properties()?;
+        // Files are cut between row groups, so each keeps the statistics of whole row groups
+        let rows_per_file = if parts > 1 {
+            let row_group_size = properties.max_row_group_size();
+            df.clone()
+                .count()
+                .await?
+                .div_ceil(parts)
+                .div_ceil(row_group_size)
+                .max(1)
+                * row_group_size
+        } else {
+            usize::MAX
+        };
+
+        let files = write_parquet_files(df, rows_per_file, properties, || {
+            bundle
+                .data_dir()
+                .file(&format!("converted_{}.parquet", Uuid::new_v4()))
+        })
+        .await?;
+        Ok(files
+            .into_iter()
+            .map(|(file, rows)| {
+                debug!("Wrote {} rows of {} to {}", rows, source, file.url());
+                file.filename().to_string()
+            })
+            .collect())
+    }
+
+    /// Opens a reader for the source, with its schema inferred again if the config asks for it
+    async fn open_reader(
+        &self,
+        bundle: &Bundle,
+    ) -> Result<(Option<SchemaRef>, Arc<dyn DataReader>), DataFusionError> {
+        let mut schema = self.schema.clone();
+        let mut reader = bundle
+            .adapter_factory
+            .reader(
+                self.source.as_str(),
+                &self.id,
+                bundle,
+                schema.clone(),
+                self.layout.clone(),
+                self.format.clone(),
+            )
+            .await?;
+
+        // Sources whose files change shape get their schema inferred again rather than trusting
+        // the one recorded when they were attached, see `BundleConfig::reinfer_schema`
+        if bundle.config().reinfer_schema(reader.url()) {
+            let inferred = reader.read_schema().await?;
+            if inferred.is_some() && inferred != schema {
+                debug!("Schema of {} changed since it was attached", self.source);
+                schema = inferred;
+                reader = bundle
+                    .adapter_factory
+                    .reader(
+                        self.source.as_str(),
+                        &self.id,
+                        bundle,
+                        schema.clone(),
+                        self.layout.clone(),
+                        self.format.clone(),
+                    )
+                    .await?;
+            }
+        }
+        Ok((schema, reader))
+    }
 }
 
 #[async_trait]
@@ -92,7 +408,22 @@ impl Operation for AttachBlockOp {
         format!("ATTACH: {}", self.source)
     }
 
-    async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        // Masking rewrote the blocks attached before it, so a new one would show the raw values
+        let Some(schema) = &self.schema else {
+            return Ok(());
+        };
+        for op in &bundle.operations {
+            if let AnyOperation::MaskColumn(mask) = op {
+                if !mask.blocks.is_empty() && schema.field_with_name(&mask.column).is_ok() {
+                    return Err(format!(
+                        "Cannot attach '{}': its column '{}' is masked. Attach data before masking it",
+                        self.source, mask.column
+                    )
+                    .into());
+                }
+            }
+        }
         Ok(())
     }
 
@@ -101,26 +432,51 @@ impl Operation for AttachBlockOp {
     }
 
     async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
-        let reader = bundle
-            .adapter_factory
-            .reader(
-                self.source.as_str(),
-                &self.id,
-                bundle,
-                self.schema.clone(),
-                self.layout.clone(),
-            )
-            .await?;
+        let (schema, reader) = match bundle.reloaded_reader(self) {
+            // Unchanged since the snapshot being reloaded, so its reader and store are kept
+            Some(reader) => (self.schema.clone(), reader),
+            None => self.open_reader(bundle).await?,
+        };
 
-        let block = Arc::new(DataBlock::new(
-            self.id.clone(),
-            self.schema.clone().unwrap(),
-            &self.version,
-            reader,
-            bundle.indexes().clone(),
-            Arc::new(bundle.data_dir().clone()),
-            bundle.config(),
-        ));
+        // Zone maps describe the data as attached, so they can't be used once the source changed
+        let stats = match &self.stats {
+            Some(stats)
+                if schema == self.schema
+                    && reader
+                        .read_version()
+                        .await
+                        .map_err(DataFusionError::External)?
+                        == self.version =>
+            {
+                Some(stats.clone())
+            }
+            Some(_) => {
+                debug!(
+                    "Not using column statistics of {}, it changed since it was attached",
+                    self.source
+                );
+                None
+            }
+            None => None,
+        };
+
+        let schema = schema.ok_or_else(|| {
+            DataFusionError::Plan(format!("No schema recorded for {}", self.source))
+        })?;
+        let block = Arc::new(
+            DataBlock::new(
+                self.id,
+                schema,
+                &self.version,
+                reader,
+                bundle.indexes().clone(),
+                Arc::new(bundle.data_dir().clone()),
+                bundle.config(),
+            )
+            .with_partitions(self.partitions)
+            .with_row_groups(self.row_groups.clone())
+            .with_stats(stats),
+        );
 
         let pack = bundle.get_pack(&self.pack_id).expect("Cannot find pack");
         pack.add_block(block);
@@ -145,20 +501,44 @@ mod tests {
             version: "test-version".to_string(),
             id: ObjectId::from(1),
             pack_id: ObjectId::from(2),
+            name: None,
+            format: None,
+            compression: None,
             num_rows: None,
             bytes: None,
+            stats: None,
             schema: None,
             layout: None,
+            partitions: None,
+            row_groups: None,
+            converted_from: None,
         };
 
         assert_eq!(op.describe(), "ATTACH: file:///test/data.csv");
     }
 
+    #[test]
+    fn test_split_from_str() {
+        assert_eq!(AttachSplit::Auto, "AUTO".parse().unwrap());
+        assert_eq!(AttachSplit::Single, "single".parse().unwrap());
+        assert_eq!(AttachSplit::Parts(4), "4".parse().unwrap());
+        assert_eq!(
+            "Invalid split '0': must be auto, single or a positive number of partitions",
+            "0".parse::<AttachSplit>().err().unwrap().to_string()
+        );
+    }
+
     #[tokio::test]
     async fn test_setup() -> Result<(), BundlebaseError> {
         let datafile = test_datafile("userdata.parquet");
-        let op =
-            AttachBlockOp::setup(&ObjectId::generate(), datafile, &empty_bundle().await).await?;
+        let op = AttachBlockOp::setup(
+            &ObjectId::generate(),
+            datafile,
+            AttachSplit::Auto,
+            None,
+            &empty_bundle().await,
+        )
+        .await?;
         let block_id = String::from(op.id.clone());
         let pack_id = String::from(op.pack_id.clone());
         let version = ObjectStoreFile::from_url(
@@ -167,6 +547,7 @@ mod tests {
         )?
         .version()
         .await?;
+        let name = block_name(datafile, &version, 1);
 
         assert_eq!(
             format!(
@@ -174,8 +555,63 @@ mod tests {
 version: {}
 id: {}
 packId: {}
+name: {}
+format: parquet
 numRows: 1000
 bytes: 113629
+stats:
+- column: registration_dttm
+  min: 2016-02-03T00:01:00
+  max: 2016-02-03T23:59:55
+  nullCount: 0
+- column: id
+  min: '1'
+  max: '1000'
+  nullCount: 0
+- column: first_name
+  min: ''
+  max: Willie
+  nullCount: 0
+- column: last_name
+  min: Adams
+  max: Young
+  nullCount: 0
+- column: email
+  min: ''
+  max: wweaver2r@google.de
+  nullCount: 0
+- column: gender
+  min: ''
+  max: Male
+  nullCount: 0
+- column: ip_address
+  min: 0.14.221.162
+  max: 99.159.168.233
+  nullCount: 0
+- column: cc
+  min: ''
+  max: '67718647521473678'
+  nullCount: 0
+- column: country
+  min: '"Bonaire'
+  max: Zimbabwe
+  nullCount: 0
+- column: birthdate
+  min: ''
+  max: 9/9/1981
+  nullCount: 0
+- column: salary
+  min: '12380.49'
+  max: '286592.99'
+  nullCount: 68
+- column: title
+  min: ''
+  max: Web Developer IV
+  nullCount: 0
+- column: comments
+  min: ''
+  max: 𠜎𠜱𠝹𠱓𠱸𠲖𠳏
+  nullCount: 6
 schema:
   fields:
   - name: registration_dttm
@@ -264,6 +700,7 @@ schema:
                 for_yaml(version),
                 for_yaml(block_id),
                 for_yaml(pack_id),
+                name,
             ),
             serde_yaml::to_string(&op)?
         );
@@ -314,10 +751,17 @@ schema:
             version: "test-version".to_string(),
             id: ObjectId::from(1),
             pack_id: ObjectId::from(2),
+            name: None,
+            format: None,
+            compression: None,
             num_rows: None,
             bytes: None,
+            stats: None,
             schema: None,
             layout: None,
+            partitions: None,
+            row_groups: None,
+            converted_from: None,
         };
 
         let version = op.version();
@@ -0,0 +1,203 @@
+use crate::bundle::operation::Operation;
+use crate::bundle::{BundleFacade, DATAFRAME_ALIAS};
+use crate::io::ObjectStoreDir;
+use crate::{Bundle, BundleConfig, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::catalog::TableProvider;
+use datafusion::common::DataFusionError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+tokio::task_local! {
+    /// URLs of the attached bundles the current task is in the middle of opening, outermost first
+    static OPENING: Vec<String>;
+}
+
+/// A bundle attached under a name by `AttachBundleOp`.
+///
+/// Nothing is copied from it: it is opened again each time a query reads it, so queries always
+/// see its latest committed version.
+#[derive(Debug, Clone)]
+pub(crate) struct ExternalBundle {
+    pub url: String,
+    pub config: Arc<BundleConfig>,
+}
+
+impl ExternalBundle {
+    /// Opens the referenced bundle, failing if it is unavailable or if opening it
+    /// would end up opening itself again through its own attached bundles.
+    pub async fn open(&self) -> Result<Bundle, BundlebaseError> {
+        OPENING.scope(self.opening()?, self.open_bundle()).await
+    }
+
+    /// The referenced bundle's current dataframe, as a read-only table
+    pub async fn table(&self) -> Result<Arc<dyn TableProvider>, DataFusionError> {
+        let opening = self.opening().map_err(DataFusionError::External)?;
+        // Building the dataframe is what reads the bundle's own attached bundles, so it needs
+        // to happen in the same scope as the open for cycles through them to be caught
+        OPENING
+            .scope(opening, async {
+                let bundle = self
+                    .open_bundle()
+                    .await
+                    .map_err(DataFusionError::External)?;
+                let df = bundle
+                    .dataframe()
+                    .await
+                    .map_err(DataFusionError::External)?;
+                Ok(df.as_ref().clone().into_view())
+            })
+            .await
+    }
+
+    /// The bundles being opened once this one is added, or an error if it is already among them
+    fn opening(&self) -> Result<Vec<String>, BundlebaseError> {
+        let mut opening = OPENING.try_with(|urls| urls.clone()).unwrap_or_default();
+        let is_cycle = opening.contains(&self.url);
+        opening.push(self.url.clone());
+        if is_cycle {
+            return Err(format!(
+                "Attached bundles reference each other in a cycle: {}",
+                opening.join(" -> ")
+            )
+            .into());
+        }
+        Ok(opening)
+    }
+
+    async fn open_bundle(&self) -> Result<Bundle, BundlebaseError> {
+        Bundle::open(&self.url, Some((*self.config).clone()))
+            .await
+            .map_err(|e| format!("Attached bundle {} is unavailable: {}", self.url, e).into())
+    }
+}
+
+/// Makes another bundle's data queryable under `name`, without copying it into this bundle
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachBundleOp {
+    pub name: String,
+    pub url: String,
+}
+
+impl AttachBundleOp {
+    /// Resolves `url` and checks the bundle can be opened and does not, directly or through
+    /// its own attached bundles, reference the bundle at `self_url`.
+    pub async fn setup(
+        name: &str,
+        url: &str,
+        self_url: &str,
+        config: Arc<BundleConfig>,
+    ) -> Result<Self, BundlebaseError> {
+        let url = ObjectStoreDir::from_str(url, config.clone())?
+            .url()
+            .to_string();
+
+        let mut visited = HashSet::new();
+        let mut pending = vec![url.clone()];
+        while let Some(next) = pending.pop() {
+            if next == self_url {
+                return Err(format!(
+                    "Cannot attach bundle {} as '{}': it references this bundle, which would create a cycle",
+                    url, name
+                )
+                .into());
+            }
+            if !visited.insert(next.clone()) {
+                continue;
+            }
+
+            let referenced = ExternalBundle {
+                url: next,
+                config: config.clone(),
+            }
+            .open()
+            .await?;
+            pending.extend(referenced.attached_bundles().into_values());
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            url,
+        })
+    }
+}
+
+#[async_trait]
+impl Operation for AttachBundleOp {
+    fn describe(&self) -> String {
+        format!("ATTACH BUNDLE: {} AS {}", self.url, self.name)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.name == DATAFRAME_ALIAS {
+            return Err(
+                format!("'{}' cannot be used as an attached bundle name", self.name).into(),
+            );
+        }
+        if bundle.external_bundles.read().contains_key(&self.name) {
+            return Err(format!("A bundle is already attached as '{}'", self.name).into());
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        let external = ExternalBundle {
+            url: self.url.clone(),
+            config: bundle.config(),
+        };
+        bundle
+            .external_bundles
+            .write()
+            .insert(self.name.clone(), external);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = AttachBundleOp {
+            name: "regions".to_string(),
+            url: "memory:///regions".to_string(),
+        };
+        assert_eq!(op.describe(), "ATTACH BUNDLE: memory:///regions AS regions");
+    }
+
+    #[test]
+    fn test_config_serialization() {
+        let op = AttachBundleOp {
+            name: "regions".to_string(),
+            url: "memory:///regions".to_string(),
+        };
+
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "name: regions\nurl: memory:///regions\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_open_cycle() {
+        let external = ExternalBundle {
+            url: "memory:///a".to_string(),
+            config: Arc::new(BundleConfig::new()),
+        };
+        let err = OPENING
+            .scope(
+                vec!["memory:///a".to_string(), "memory:///b".to_string()],
+                external.open(),
+            )
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            err,
+            "Attached bundles reference each other in a cycle: memory:///a -> memory:///b -> memory:///a"
+        );
+    }
+}
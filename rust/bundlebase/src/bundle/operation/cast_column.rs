@@ -0,0 +1,145 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use arrow::datatypes::DataType;
+use arrow::util::display::array_value_to_string;
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::logical_expr::{cast, try_cast};
+use datafusion::prelude::{ident, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Converts a column to another type, e.g. text read from a CSV to `Int64` or `Date32`.
+///
+/// Values that can't be converted are an error, unless `safe` is set, which makes them null.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CastColumnOp {
+    pub column: String,
+    #[serde(
+        serialize_with = "super::serde_util::serialize_type",
+        deserialize_with = "super::serde_util::deserialize_type"
+    )]
+    pub data_type: DataType,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub safe: bool,
+}
+
+impl CastColumnOp {
+    pub fn setup(column: &str, data_type: DataType, safe: bool) -> Self {
+        Self {
+            column: column.to_string(),
+            data_type,
+            safe,
+        }
+    }
+
+    /// Checks that every current value converts, so a bad one fails now rather than on read.
+    /// This scans the data, so it is only done when the cast is added, not in `check`, which
+    /// also runs each time the bundle is opened.
+    pub async fn check_values(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.safe {
+            return Ok(());
+        }
+
+        let column = ident(&self.column);
+        let unconvertible = bundle
+            .raw_dataframe()
+            .await?
+            .as_ref()
+            .clone()
+            .filter(
+                column
+                    .clone()
+                    .is_not_null()
+                    .and(try_cast(column.clone(), self.data_type.clone()).is_null()),
+            )?
+            .select(vec![column])?
+            .limit(0, Some(1))?
+            .collect()
+            .await?;
+        if let Some(batch) = unconvertible.iter().find(|b| b.num_rows() > 0) {
+            return Err(format!(
+                "Cannot cast column '{}' to {}: '{}' is not a valid value",
+                self.column,
+                self.data_type,
+                array_value_to_string(batch.column(0), 0)?
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Operation for CastColumnOp {
+    fn describe(&self) -> String {
+        format!(
+            "CAST COLUMN: {} TO {}{}",
+            self.column,
+            self.data_type,
+            if self.safe {
+                " (unconvertible as null)"
+            } else {
+                ""
+            }
+        )
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        bundle.raw_schema().await?.field_with_name(&self.column)?;
+        bundle.check_not_security_column(&self.column, "cast column")?;
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // Values that didn't convert are now null, whatever was declared
+        if self.safe {
+            bundle.nullable_columns.remove(&self.column);
+        }
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let converted = if self.safe {
+            try_cast(ident(&self.column), self.data_type.clone())
+        } else {
+            cast(ident(&self.column), self.data_type.clone())
+        };
+        Ok(df.with_column(&self.column, converted)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            CastColumnOp::setup("age", DataType::Int64, false).describe(),
+            "CAST COLUMN: age TO Int64"
+        );
+        assert_eq!(
+            CastColumnOp::setup("joined", DataType::Date32, true).describe(),
+            "CAST COLUMN: joined TO Date32 (unconvertible as null)"
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = CastColumnOp::setup("age", DataType::Int64, false);
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "column: age\ndataType: Int64\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+
+        let op = CastColumnOp::setup("price", DataType::Decimal128(10, 2), true);
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::logical_expr::expr::FieldMetadata;
+use datafusion::prelude::{ident, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Field metadata key holding the name a column had in the source data
+pub const ORIGINAL_NAME_METADATA: &str = "bundlebase.original_name";
+
+/// Exposes a column under an alias, keeping its source name in the field metadata.
+///
+/// Unlike `RenameColumnOp`, the original name stays discoverable through
+/// `ORIGINAL_NAME_METADATA`, which is how normalized column names are recorded.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnAliasOp {
+    pub original: String,
+    pub alias: String,
+}
+
+impl ColumnAliasOp {
+    pub fn setup(original: &str, alias: &str) -> Self {
+        Self {
+            original: original.to_string(),
+            alias: alias.to_string(),
+        }
+    }
+}
+
+/// Maps a column name to snake_case, e.g. `First Name` and `firstName` become `first_name`
+pub fn normalize_column_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            let word_break = c.is_uppercase()
+                && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit());
+            if word_break && !normalized.ends_with('_') {
+                normalized.push('_');
+            }
+            normalized.extend(c.to_lowercase());
+        } else if !normalized.is_empty() && !normalized.ends_with('_') {
+            normalized.push('_');
+        }
+        previous = Some(c);
+    }
+    normalized.trim_end_matches('_').to_string()
+}
+
+#[async_trait]
+impl Operation for ColumnAliasOp {
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let schema = bundle.raw_schema().await?;
+        schema.field_with_name(&self.original)?;
+        bundle.check_not_security_column(&self.original, "alias column")?;
+        bundle.check_not_security_column(&self.alias, "alias a column as")?;
+        if self.alias != self.original && schema.field_with_name(&self.alias).is_ok() {
+            return Err(format!(
+                "Cannot alias '{}' as '{}': a column named '{}' already exists",
+                self.original, self.alias, self.alias
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // Declared constraints follow the column to its alias
+        for key in bundle.key_columns.iter_mut() {
+            if *key == self.original {
+                *key = self.alias.clone();
+            }
+        }
+        if let Some(nullable) = bundle.nullable_columns.remove(&self.original) {
+            bundle.nullable_columns.insert(self.alias.clone(), nullable);
+        }
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let exprs = df
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                if field.name() == &self.original {
+                    let mut metadata: BTreeMap<String, String> = field
+                        .metadata()
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect();
+                    metadata.insert(ORIGINAL_NAME_METADATA.to_string(), self.original.clone());
+                    ident(field.name())
+                        .alias_with_metadata(&self.alias, Some(FieldMetadata::from(metadata)))
+                } else {
+                    ident(field.name())
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(df.select(exprs)?)
+    }
+
+    fn describe(&self) -> String {
+        format!("ALIAS COLUMN: {} AS {}", self.original, self.alias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = ColumnAliasOp::setup("First Name", "first_name");
+        assert_eq!(op.describe(), "ALIAS COLUMN: First Name AS first_name");
+    }
+
+    #[test]
+    fn test_config_serialization() {
+        let op = ColumnAliasOp::setup("First Name", "first_name");
+
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "original: First Name\nalias: first_name\n");
+    }
+
+    #[test]
+    fn test_normalize_column_name() {
+        let cases = vec![
+            ("First Name", "first_name"),
+            ("Customer Id", "customer_id"),
+            ("Phone 1", "phone_1"),
+            ("firstName", "first_name"),
+            ("  Total ($) ", "total"),
+            ("already_snake", "already_snake"),
+            ("Index", "index"),
+            ("a--b", "a_b"),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(expected, normalize_column_name(name), "{}", name);
+        }
+    }
+}
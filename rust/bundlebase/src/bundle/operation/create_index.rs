@@ -1,5 +1,5 @@
 use crate::bundle::operation::Operation;
-use crate::bundle::{Bundle, BundleFacade};
+use crate::bundle::Bundle;
 use crate::index::IndexDefinition;
 use crate::io::ObjectId;
 use crate::BundlebaseError;
@@ -11,8 +11,13 @@ use std::sync::Arc;
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateIndexOp {
+    /// The indexed column. Empty for a composite index, which lists its `columns` instead.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub column: String,
     pub id: ObjectId,
+    /// Columns of a composite index, in key order. Empty for an index on a single column.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
 }
 
 impl CreateIndexOp {
@@ -20,41 +25,64 @@ impl CreateIndexOp {
         Ok(Self {
             id: ObjectId::generate(),
             column: column.to_string(),
+            columns: vec![],
         })
     }
+
+    pub async fn setup_composite(columns: &[String]) -> Result<Self, BundlebaseError> {
+        if columns.len() < 2 {
+            return Err("A composite index needs at least two columns".into());
+        }
+        Ok(Self {
+            id: ObjectId::generate(),
+            column: String::new(),
+            columns: columns.to_vec(),
+        })
+    }
+
+    /// The indexed columns, in key order, whether the index is composite or not
+    pub fn indexed_columns(&self) -> &[String] {
+        if self.columns.is_empty() {
+            std::slice::from_ref(&self.column)
+        } else {
+            &self.columns
+        }
+    }
 }
 
 #[async_trait]
 impl Operation for CreateIndexOp {
     fn describe(&self) -> String {
-        format!("CREATE INDEX on {}", self.column)
+        format!("CREATE INDEX on {}", self.indexed_columns().join(", "))
     }
 
     async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
-        // Verify column exists in schema
-        if !bundle
-            .schema()
-            .await?
-            .column_with_name(&self.column)
-            .is_some()
-        {
-            return Err(format!("Column '{}' not found in schema", self.column).into());
+        // Verify column exists in schema. A bundle without data yet (e.g. one created from a
+        // template) has no columns to check against
+        let schema = bundle.raw_schema().await?;
+        let columns = self.indexed_columns();
+        for column in columns {
+            if !schema.fields().is_empty() && schema.column_with_name(column).is_none() {
+                return Err(format!("Column '{}' not found in schema", column).into());
+            }
         }
 
         // Check if an index already exists for this column
         let indexes = bundle.indexes().read();
-        if indexes.iter().any(|idx| idx.column() == &self.column) {
-            return Err(format!("Index already exists for column '{}'", self.column).into());
+        if indexes.iter().any(|idx| idx.columns() == columns) {
+            return Err(format!("Index already exists for column '{}'", columns.join(", ")).into());
         }
 
         Ok(())
     }
 
     async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
-        bundle
-            .indexes
-            .write()
-            .push(Arc::new(IndexDefinition::new(&self.id, &self.column)));
+        let index = if self.columns.is_empty() {
+            IndexDefinition::new(&self.id, &self.column)
+        } else {
+            IndexDefinition::new_composite(&self.id, &self.columns)
+        };
+        bundle.indexes.write().push(Arc::new(index));
 
         Ok(())
     }
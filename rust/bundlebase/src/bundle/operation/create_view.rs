@@ -1,4 +1,4 @@
-use crate::bundle::commit::BundleCommit;
+use crate::bundle::commit::{BundleCommit, FORMAT_VERSION};
 use crate::bundle::operation::{AnyOperation, BundleChange, Operation};
 use crate::bundle::META_DIR;
 use crate::data::ObjectId;
@@ -70,19 +70,22 @@ impl CreateViewOp {
             datetime.format("%Y-%m-%dT%H:%M:%SZ").to_string()
         };
 
-        let author = std::env::var("BUNDLEBASE_AUTHOR")
-            .unwrap_or_else(|_| std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()));
+        let author = parent_builder.author();
 
         // Create commit structure
         let commit = BundleCommit {
+            id: String::new(),
             url: None,
             data_dir: None,
+            format_version: FORMAT_VERSION,
             message: format!("View: {}", name),
             author,
             timestamp,
+            amends: None,
             changes: vec![BundleChange {
                 id: Uuid::new_v4(),
                 description: format!("Define view '{}'", name),
+                labels: Default::default(),
                 operations: operations.clone(),
             }],
         };
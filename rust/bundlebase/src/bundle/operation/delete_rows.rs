@@ -0,0 +1,134 @@
+use crate::bundle::operation::filter::{substitute_parameters, ParameterValue};
+use crate::bundle::operation::Operation;
+use crate::bundle::sql::with_temp_table;
+use crate::metrics::{start_span, OperationCategory, OperationOutcome, OperationTimer};
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::SessionContext;
+use datafusion::scalar::ScalarValue;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Removes the rows matching a WHERE clause.
+///
+/// This is the inverse of `FilterOp`, kept as its own operation so the history reads as a deletion.
+/// Rows where the clause evaluates to NULL are kept, matching SQL `DELETE` semantics.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRowsOp {
+    pub where_clause: String,
+    pub parameters: Vec<ParameterValue>,
+}
+
+impl DeleteRowsOp {
+    pub async fn setup(
+        where_clause: &str,
+        parameters: Vec<ScalarValue>,
+    ) -> Result<Self, BundlebaseError> {
+        Ok(Self {
+            where_clause: where_clause.to_string(),
+            parameters: parameters.into_iter().map(ParameterValue::from).collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl Operation for DeleteRowsOp {
+    fn describe(&self) -> String {
+        format!("DELETE WHERE {}", self.where_clause)
+    }
+
+    async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.where_clause.trim().is_empty() {
+            return Err("DELETE requires a WHERE clause".into());
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // Deleting rows doesn't change the schema, so no reconfiguration needed
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let mut span = start_span(OperationCategory::Select, "delete_rows");
+        span.set_attribute("expression", &self.where_clause);
+
+        let timer = OperationTimer::start(OperationCategory::Select, "delete_rows")
+            .with_label("expression", &self.where_clause);
+
+        let where_clause = self.where_clause.clone();
+        let parameters = self.parameters.clone();
+        let ctx_for_closure = ctx.clone();
+
+        let result = with_temp_table(&ctx, df, |temp_table| async move {
+            let substituted_clause = substitute_parameters(&where_clause, &parameters);
+
+            let sql = format!(
+                "SELECT * FROM {} WHERE NOT COALESCE(({}), false)",
+                temp_table, substituted_clause
+            );
+            ctx_for_closure
+                .sql(&sql)
+                .await
+                .map_err(|e| Box::new(e) as BundlebaseError)
+        })
+        .await;
+
+        match &result {
+            Ok(_) => {
+                span.set_outcome(OperationOutcome::Success);
+                timer.finish(OperationOutcome::Success);
+            }
+            Err(e) => {
+                span.record_error(&e.to_string());
+                timer.finish(OperationOutcome::Error);
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = DeleteRowsOp {
+            where_clause: "Country = 'Chile'".to_string(),
+            parameters: vec![],
+        };
+        assert_eq!(op.describe(), "DELETE WHERE Country = 'Chile'");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = DeleteRowsOp {
+            where_clause: "salary < $1".to_string(),
+            parameters: vec![ParameterValue::Int64(1000)],
+        };
+
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert!(serialized.contains("whereClause"));
+
+        let deserialized: DeleteRowsOp =
+            serde_yaml::from_str(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, op);
+    }
+
+    #[tokio::test]
+    async fn test_check_requires_where_clause() -> Result<(), BundlebaseError> {
+        let bundle = Bundle::empty().await?;
+        let op = DeleteRowsOp::setup(" ", vec![]).await?;
+        assert!(op.check(&bundle).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::{ident, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Removes duplicate rows: ones equal in every column, or with `subset` set, ones equal in those
+/// columns. Of rows sharing a subset key, one is kept whole. Rows are read from several blocks
+/// in parallel, so which one is not defined.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DistinctOp {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subset: Option<Vec<String>>,
+}
+
+impl DistinctOp {
+    pub fn setup(subset: Option<&[&str]>) -> Self {
+        Self {
+            subset: subset.map(|columns| columns.iter().map(|c| c.to_string()).collect()),
+        }
+    }
+}
+
+#[async_trait]
+impl Operation for DistinctOp {
+    fn describe(&self) -> String {
+        match &self.subset {
+            Some(subset) => format!("DISTINCT ON: {}", subset.join(", ")),
+            None => "DISTINCT".to_string(),
+        }
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if let Some(subset) = &self.subset {
+            if subset.is_empty() {
+                return Err("Distinct on a subset needs at least one column".into());
+            }
+            let schema = bundle.raw_schema().await?;
+            for column in subset {
+                schema.field_with_name(column)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        match &self.subset {
+            Some(subset) => {
+                let on = subset.iter().map(ident).collect();
+                let columns = df
+                    .schema()
+                    .fields()
+                    .iter()
+                    .map(|f| ident(f.name()))
+                    .collect();
+                Ok(df.distinct_on(on, columns, None)?)
+            }
+            None => Ok(df.distinct()?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(DistinctOp::setup(None).describe(), "DISTINCT");
+        assert_eq!(
+            DistinctOp::setup(Some(&["id", "day"])).describe(),
+            "DISTINCT ON: id, day"
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = DistinctOp::setup(Some(&["id"]));
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "subset:\n- id\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+
+        let op = DistinctOp::setup(None);
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "{}\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
@@ -15,28 +15,8 @@ pub struct DropViewOp {
 }
 
 impl DropViewOp {
-    pub async fn setup(view_name: &str, bundle: &Bundle) -> Result<Self, BundlebaseError> {
-        // Look up the view ID from the name
-        let view_id = bundle
-            .views
-            .get(view_name)
-            .ok_or_else(|| {
-                let available_views: Vec<String> = bundle
-                    .views
-                    .iter()
-                    .map(|(name, id)| format!("{} ({})", name, id))
-                    .collect();
-                let available_list = if available_views.is_empty() {
-                    "none".to_string()
-                } else {
-                    available_views.join(", ")
-                };
-                BundlebaseError::from(format!(
-                    "View '{}' not found. Available views: {}",
-                    view_name, available_list
-                ))
-            })?
-            .clone();
+    pub async fn setup(identifier: &str, bundle: &Bundle) -> Result<Self, BundlebaseError> {
+        let (view_id, _) = bundle.get_view_id_by_name_or_id(identifier)?;
 
         Ok(Self { view_id })
     }
@@ -61,6 +41,10 @@ impl Operation for DropViewOp {
     async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
         // Find and remove the name->id mapping
         bundle.views.retain(|_, id| id != &self.view_id);
+        if bundle.default_view.as_ref() == Some(&self.view_id) {
+            bundle.default_view = None;
+        }
+        bundle.materialized_views.remove(&self.view_id);
 
         log::info!("Dropped view {}", self.view_id);
 
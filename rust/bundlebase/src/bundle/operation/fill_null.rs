@@ -0,0 +1,108 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use arrow::datatypes::DataType;
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::functions::expr_fn::coalesce;
+use datafusion::prelude::{ident, lit, SessionContext};
+use datafusion::scalar::ScalarValue;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Replaces the nulls in a column with a fixed value, written as text and read as the column's
+/// type, e.g. `0` for a number column or `2024-01-01` for a date one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FillNullOp {
+    pub column: String,
+    pub value: String,
+}
+
+impl FillNullOp {
+    pub fn setup(column: &str, value: &str) -> Self {
+        Self {
+            column: column.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /// The value as a literal of the column's type
+    fn literal(&self, data_type: &DataType) -> Result<ScalarValue, BundlebaseError> {
+        ScalarValue::try_from_string(self.value.clone(), data_type).map_err(|e| {
+            format!(
+                "Cannot fill nulls in column '{}' with '{}': not a valid {}: {}",
+                self.column, self.value, data_type, e
+            )
+            .into()
+        })
+    }
+}
+
+#[async_trait]
+impl Operation for FillNullOp {
+    fn describe(&self) -> String {
+        format!("FILL NULL: {} WITH '{}'", self.column, self.value)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let schema = bundle.raw_schema().await?;
+        let field = schema.field_with_name(&self.column)?;
+        self.literal(field.data_type())?;
+        bundle.check_not_security_column(&self.column, "fill nulls in column")?;
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let data_type = df
+            .schema()
+            .field_with_unqualified_name(&self.column)?
+            .data_type()
+            .clone();
+        let filled = coalesce(vec![ident(&self.column), lit(self.literal(&data_type)?)]);
+        Ok(df.with_column(&self.column, filled)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = FillNullOp::setup("score", "0");
+        assert_eq!(op.describe(), "FILL NULL: score WITH '0'");
+    }
+
+    #[test]
+    fn test_literal() {
+        let op = FillNullOp::setup("score", "12");
+        assert_eq!(
+            ScalarValue::Int64(Some(12)),
+            op.literal(&DataType::Int64).unwrap()
+        );
+        assert_eq!(
+            "Cannot fill nulls in column 'score' with 'lots': not a valid Int64: Arrow error: Cast error: Cannot cast string 'lots' to value of Int64 type",
+            FillNullOp::setup("score", "lots")
+                .literal(&DataType::Int64)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = FillNullOp::setup("score", "0");
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "column: score\nvalue: '0'\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
@@ -51,6 +51,17 @@ impl From<ScalarValue> for ParameterValue {
     }
 }
 
+/// Replaces the `$1`, `$2`, etc. placeholders in a WHERE clause with SQL literals for the parameters
+pub(crate) fn substitute_parameters(where_clause: &str, parameters: &[ParameterValue]) -> String {
+    let mut substituted_clause = where_clause.to_string();
+    for (i, param) in parameters.iter().enumerate() {
+        let placeholder = format!("${}", i + 1);
+        let value_str = crate::bundle::scalar_value_to_sql_literal(&param.to_scalar_value());
+        substituted_clause = substituted_clause.replace(&placeholder, &value_str);
+    }
+    substituted_clause
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FilterOp {
@@ -102,13 +113,7 @@ impl Operation for FilterOp {
         let ctx_for_closure = ctx.clone();
 
         let result = with_temp_table(&ctx, df, |temp_table| async move {
-            let mut substituted_clause = where_clause;
-            for (i, param) in parameters.iter().enumerate() {
-                let placeholder = format!("${}", i + 1);
-                let value_str =
-                    crate::bundle::scalar_value_to_sql_literal(&param.to_scalar_value());
-                substituted_clause = substituted_clause.replace(&placeholder, &value_str);
-            }
+            let substituted_clause = substitute_parameters(&where_clause, &parameters);
 
             let sql = format!("SELECT * FROM {} WHERE {}", temp_table, substituted_clause);
             ctx_for_closure
@@ -1,16 +1,20 @@
 use crate::bundle::operation::Operation;
-use crate::data::{DataBlock, ObjectId, RowId, VersionedBlockId};
+use crate::data::{DataBlock, DataReader, ObjectId, RowId, VersionedBlockId};
 use crate::index::{ColumnIndex, IndexedValue};
+use crate::metrics::{OperationCategory, OperationOutcome, OperationTimer};
 use crate::progress::ProgressScope;
 use crate::{Bundle, BundlebaseError};
 use arrow_schema::DataType;
 use async_trait::async_trait;
 use datafusion::error::DataFusionError;
+use datafusion::prelude::SessionContext;
 use datafusion::scalar::ScalarValue;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -20,11 +24,15 @@ pub struct IndexBlocksOp {
     pub blocks: Vec<VersionedBlockId>,
     pub path: String,
     pub cardinality: u64,
+    /// Columns the keys of a composite index were read from, in key order. Empty for an index on
+    /// a single column.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
 }
 
 /// Finds a block by ID in the bundle's data packs.
 fn find_block(bundle: &Bundle, block_id: &ObjectId) -> Result<Arc<DataBlock>, BundlebaseError> {
-    for (_, pack) in &bundle.data_packs.read().clone() {
+    for pack in bundle.data_packs.read().values() {
         for block in &pack.blocks() {
             if block.id() == block_id {
                 return Ok(block.clone());
@@ -66,23 +74,56 @@ impl IndexBlocksOp {
         column: &str,
         blocks: Vec<(ObjectId, String)>,
         bundle: &Bundle,
+    ) -> Result<Self, BundlebaseError> {
+        let permits = Arc::new(Semaphore::new(bundle.config().index_concurrency()?));
+        Self::setup_limited(index_id, &[column.to_string()], blocks, bundle, permits).await
+    }
+
+    /// Same as `setup`, but every block read first takes one of the shared `permits`.
+    ///
+    /// Sharing one semaphore lets several columns be indexed at once while still reading no more
+    /// than `index_concurrency` blocks in total. Dropping the returned future aborts any reads
+    /// still in flight.
+    ///
+    /// Several `columns` build a composite index, keyed on the tuple of their values.
+    pub(crate) async fn setup_limited(
+        index_id: &ObjectId,
+        columns: &[String],
+        blocks: Vec<(ObjectId, String)>,
+        bundle: &Bundle,
+        permits: Arc<Semaphore>,
+    ) -> Result<Self, BundlebaseError> {
+        let column = columns.join(", ");
+        let timer =
+            OperationTimer::start(OperationCategory::Index, "build").with_label("column", &column);
+
+        let result = Self::build(index_id, columns, blocks, bundle, permits).await;
+        timer.finish(if result.is_ok() {
+            OperationOutcome::Success
+        } else {
+            OperationOutcome::Error
+        });
+        result
+    }
+
+    async fn build(
+        index_id: &ObjectId,
+        columns: &[String],
+        blocks: Vec<(ObjectId, String)>,
+        bundle: &Bundle,
+        permits: Arc<Semaphore>,
     ) -> Result<Self, BundlebaseError> {
         // Validate blocks is non-empty early
         if blocks.is_empty() {
             return Err(BundlebaseError::from("Cannot create index with no blocks"));
         }
+        let column = columns.join(", ");
 
-        let mut all_value_to_rowids: HashMap<IndexedValue, Vec<RowId>> = HashMap::new();
-        let mut data_type: Option<DataType> = None;
-
-        // Create progress scope for tracking
-        let _progress = ProgressScope::new(
-            &format!("Indexing column '{}'", column),
-            Some(blocks.len() as u64),
-        );
+        let mut data_types: Vec<Option<DataType>> = vec![None; columns.len()];
+        let mut readers = Vec::with_capacity(blocks.len());
 
-        // For each block, stream data and build index mappings
-        for (idx, (block_id, _version)) in blocks.iter().enumerate() {
+        // Resolve every block and check its column before starting any reads
+        for (block_id, _version) in &blocks {
             // Get the block from data_packs
             let block = find_block(bundle, block_id).map_err(|e| {
                 BundlebaseError::from(format!(
@@ -93,73 +134,88 @@ impl IndexBlocksOp {
 
             // Get schema to find column index and data type
             let schema = block.schema();
-            let (col_idx, field) = schema.column_with_name(column).ok_or_else(|| {
-                BundlebaseError::from(format!(
-                    "Column '{}' not found in block {}",
-                    column, block_id,
-                ))
-            })?;
-
-            // Validate data type consistency across blocks
-            if let Some(ref existing_type) = data_type {
-                if existing_type != field.data_type() {
-                    return Err(BundlebaseError::from(format!(
-                        "Data type mismatch for column '{}': {:?} in previous blocks vs {:?} in block {}",
-                        column, existing_type, field.data_type(), block_id
-                    )));
-                }
-            } else {
-                data_type = Some(field.data_type().clone());
-            }
-
-            // Stream through block data
-            let projection = Some(vec![col_idx]);
-            let reader = block.reader();
-            let mut rowid_stream = reader
-                .extract_rowids_stream(bundle.ctx(), projection.as_ref())
-                .await
-                .map_err(|e| {
+            let mut col_idxs = Vec::with_capacity(columns.len());
+            for (column, data_type) in columns.iter().zip(data_types.iter_mut()) {
+                let (col_idx, field) = schema.column_with_name(column).ok_or_else(|| {
                     BundlebaseError::from(format!(
-                        "Failed to stream data from block {} for indexing: {}",
-                        block_id, e
+                        "Column '{}' not found in block {}",
+                        column, block_id,
                     ))
                 })?;
 
-            while let Some(batch_result) = rowid_stream.next().await {
-                let rowid_batch = batch_result.map_err(|e| {
-                    BundlebaseError::from(format!(
-                        "Failed to read row batch from block {}: {}",
-                        block_id, e
-                    ))
-                })?;
-                let batch = rowid_batch.batch;
-                let row_ids = rowid_batch.row_ids;
+                // Validate data type consistency across blocks
+                if let Some(ref existing_type) = data_type {
+                    if existing_type != field.data_type() {
+                        return Err(BundlebaseError::from(format!(
+                            "Data type mismatch for column '{}': {:?} in previous blocks vs {:?} in block {}",
+                            column, existing_type, field.data_type(), block_id
+                        )));
+                    }
+                } else {
+                    *data_type = Some(field.data_type().clone());
+                }
+                col_idxs.push(col_idx);
+            }
 
-                let array = batch.column(0);
+            readers.push((block.reader(), col_idxs));
+        }
 
-                // Build value -> rowid mapping
-                for (row, row_id) in row_ids.iter().enumerate() {
-                    let scalar = ScalarValue::try_from_array(array, row)?;
-                    let indexed_value = IndexedValue::from_scalar(&scalar)?;
+        // Create progress scope for tracking
+        let _progress = ProgressScope::new(
+            &format!("Indexing column '{}'", column),
+            Some(blocks.len() as u64),
+        );
 
-                    all_value_to_rowids
-                        .entry(indexed_value)
-                        .or_insert_with(Vec::new)
-                        .push(*row_id);
-                }
-            }
+        // Read the blocks concurrently. Dropping the set aborts any reads still running.
+        let mut reads = JoinSet::new();
+        for (idx, (reader, col_idxs)) in readers.into_iter().enumerate() {
+            let ctx = bundle.ctx();
+            let permits = permits.clone();
+            reads.spawn(async move {
+                let _permit = permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| BundlebaseError::from(e.to_string()))?;
+                let values = read_block_values(reader, col_idxs, ctx).await?;
+                Ok::<_, BundlebaseError>((idx, values))
+            });
+        }
+
+        let mut block_values: Vec<Option<HashMap<IndexedValue, Vec<RowId>>>> =
+            (0..blocks.len()).map(|_| None).collect();
+        let mut finished = 0;
+        while let Some(joined) = reads.join_next().await {
+            let (idx, values) = joined
+                .map_err(|e| format!("Indexing task for column '{}' failed: {}", column, e))??;
+            block_values[idx] = Some(values);
 
             // Update progress after each block
-            let msg = format!("Block {}/{}", idx + 1, blocks.len());
-            _progress.update((idx + 1) as u64, Some(&msg));
+            finished += 1;
+            let msg = format!("Block {}/{}", finished, blocks.len());
+            _progress.update(finished as u64, Some(&msg));
+        }
+
+        // Merge in block order so each value lists its row ids the same way a serial read would
+        let mut all_value_to_rowids: HashMap<IndexedValue, Vec<RowId>> = HashMap::new();
+        for values in block_values.into_iter().flatten() {
+            for (indexed_value, row_ids) in values {
+                all_value_to_rowids
+                    .entry(indexed_value)
+                    .or_default()
+                    .extend(row_ids);
+            }
         }
 
         // Build the combined index
-        // Now guaranteed to have data_type if we reach here (blocks is non-empty)
-        let data_type_ref = data_type.as_ref().ok_or("No data type found for column")?;
+        // Now guaranteed to have data_type if we reach here (blocks is non-empty).
+        // A composite index is typed by its leading column.
+        let data_type_ref = data_types
+            .first()
+            .and_then(|data_type| data_type.as_ref())
+            .ok_or("No data type found for column")?;
 
         let index =
-            ColumnIndex::build(column, data_type_ref, all_value_to_rowids).map_err(|e| {
+            ColumnIndex::build(&column, data_type_ref, all_value_to_rowids).map_err(|e| {
                 BundlebaseError::from(format!(
                     "Failed to build index for column '{}': {}",
                     column, e
@@ -192,10 +248,67 @@ impl IndexBlocksOp {
                 .collect(),
             path: rel_path,
             cardinality: total_cardinality,
+            columns: if columns.len() > 1 {
+                columns.to_vec()
+            } else {
+                vec![]
+            },
         })
     }
 }
 
+/// Streams the indexed columns out of a block and maps each of their values to the rows holding
+/// it. Several columns are combined into one tuple value per row.
+async fn read_block_values(
+    reader: Arc<dyn DataReader>,
+    col_idxs: Vec<usize>,
+    ctx: Arc<SessionContext>,
+) -> Result<HashMap<IndexedValue, Vec<RowId>>, BundlebaseError> {
+    let block_id = reader.block_id();
+    let composite = col_idxs.len() > 1;
+    let projection = Some(col_idxs);
+    let mut rowid_stream = reader
+        .extract_rowids_stream(ctx, projection.as_ref())
+        .await
+        .map_err(|e| {
+            BundlebaseError::from(format!(
+                "Failed to stream data from block {} for indexing: {}",
+                block_id, e
+            ))
+        })?;
+
+    let mut value_to_rowids: HashMap<IndexedValue, Vec<RowId>> = HashMap::new();
+    while let Some(batch_result) = rowid_stream.next().await {
+        let rowid_batch = batch_result.map_err(|e| {
+            BundlebaseError::from(format!(
+                "Failed to read row batch from block {}: {}",
+                block_id, e
+            ))
+        })?;
+        let arrays = rowid_batch.batch.columns();
+
+        // Build value -> rowid mapping
+        for (row, row_id) in rowid_batch.row_ids.iter().enumerate() {
+            let mut values = arrays
+                .iter()
+                .map(|array| IndexedValue::from_scalar(&ScalarValue::try_from_array(array, row)?))
+                .collect::<Result<Vec<_>, BundlebaseError>>()?;
+            let indexed_value = if composite {
+                IndexedValue::Tuple(values)
+            } else {
+                values.remove(0)
+            };
+
+            value_to_rowids
+                .entry(indexed_value)
+                .or_default()
+                .push(*row_id);
+        }
+    }
+
+    Ok(value_to_rowids)
+}
+
 #[async_trait]
 impl Operation for IndexBlocksOp {
     fn describe(&self) -> String {
@@ -272,6 +385,7 @@ mod tests {
             ],
             path: "idx_01_abc.idx".to_string(),
             cardinality: 100,
+            columns: vec![],
         };
 
         let json = serde_json::to_string(&op).unwrap();
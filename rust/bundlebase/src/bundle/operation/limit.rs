@@ -0,0 +1,63 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::SessionContext;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Keeps only the first `count` rows in the current order, e.g. after a sort to make a small
+/// sample of a larger bundle
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitOp {
+    pub count: usize,
+}
+
+impl LimitOp {
+    pub fn setup(count: usize) -> Self {
+        Self { count }
+    }
+}
+
+#[async_trait]
+impl Operation for LimitOp {
+    fn describe(&self) -> String {
+        format!("LIMIT: {}", self.count)
+    }
+
+    async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        Ok(df.limit(0, Some(self.count))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(LimitOp::setup(100).describe(), "LIMIT: 100");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = LimitOp::setup(100);
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "count: 100\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
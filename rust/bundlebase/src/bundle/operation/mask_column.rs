@@ -0,0 +1,521 @@
+use crate::bundle::operation::Operation;
+use crate::bundle::write_parquet;
+use crate::bundle_config::COLUMN_ENCRYPTION_KEY;
+use crate::data::{DataBlock, ObjectId};
+use crate::{Bundle, BundleConfig, BundlebaseError};
+use arrow::array::{Array, ArrayRef, StringArray};
+use arrow::datatypes::{DataType, Field, SchemaRef};
+use async_trait::async_trait;
+use datafusion::common::{DataFusionError, ScalarValue};
+use datafusion::dataframe::DataFrame;
+use datafusion::functions::expr_fn::{encode, sha256};
+use datafusion::logical_expr::{cast, create_udf, ColumnarValue, Expr, Volatility};
+use datafusion::prelude::{ident, lit, SessionContext};
+use log::info;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// How a masked column's values are hidden
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MaskStrategy {
+    /// Replace values with the hex SHA-256 digest of their text form. The digest is unkeyed, so
+    /// it is not a pseudonym: guessable values such as emails, phone numbers or SSNs can be
+    /// recovered by hashing candidates and comparing. Use `Encrypt` or `Null` for those.
+    Hash,
+    /// Replace values with null
+    Null,
+    /// Only show values to readers configured with the `column_encryption_key` used to mask it
+    Encrypt,
+}
+
+impl MaskStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaskStrategy::Hash => "hash",
+            MaskStrategy::Null => "null",
+            MaskStrategy::Encrypt => "encrypt",
+        }
+    }
+}
+
+impl FromStr for MaskStrategy {
+    type Err = BundlebaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hash" => Ok(MaskStrategy::Hash),
+            "null" => Ok(MaskStrategy::Null),
+            "encrypt" => Ok(MaskStrategy::Encrypt),
+            _ => Err(format!(
+                "Unknown mask strategy '{}': must be hash, null or encrypt",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+impl Display for MaskStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A block rewritten with its masked column, read in place of the attached source
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MaskedBlock {
+    pub id: ObjectId,
+    /// The parquet file, relative to the bundle's data directory
+    pub file: String,
+    pub version: String,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "super::serde_util::serialize_schema_option",
+        deserialize_with = "super::serde_util::deserialize_schema_option"
+    )]
+    pub schema: Option<SchemaRef>,
+}
+
+/// Hides a sensitive column's values from everything read out of the bundle.
+///
+/// Every attached block holding the column is rewritten into a parquet file in the bundle's
+/// data directory with the column already masked, and read from there instead of its source.
+/// `Hash` stores the unkeyed hex SHA-256 digests, which keep equal values equal but can be
+/// reversed with a dictionary of likely values, and `Null` stores nulls. `Encrypt` stores each
+/// value's text sealed with AES-256-GCM under the key, which readers holding the same key get
+/// back as text, while everyone else reads nulls. The column name sealed under the key is recorded as
+/// `key_check`, so the key itself is never stored.
+///
+/// Operations before the mask see the stored values too. Columns that aren't stored in any
+/// block, and masks recorded before blocks were rewritten, are masked as the data is read.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MaskColumnOp {
+    pub column: String,
+    pub strategy: MaskStrategy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_check: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocks: Vec<MaskedBlock>,
+}
+
+impl MaskColumnOp {
+    pub fn setup(
+        column: &str,
+        strategy: MaskStrategy,
+        config: &BundleConfig,
+    ) -> Result<Self, BundlebaseError> {
+        let key_check = match strategy {
+            MaskStrategy::Encrypt => {
+                let key = config.column_encryption_key().ok_or_else(|| {
+                    format!(
+                        "Cannot encrypt column '{}': no {} is configured",
+                        column, COLUMN_ENCRYPTION_KEY
+                    )
+                })?;
+                Some(seal_key_check(key, column)?)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            column: column.to_string(),
+            strategy,
+            key_check,
+            blocks: vec![],
+        })
+    }
+
+    /// Writes a masked copy of every block holding the column to the bundle's data directory
+    pub async fn rewrite_blocks(mut self, bundle: &Bundle) -> Result<Self, BundlebaseError> {
+        let config = bundle.config();
+        let blocks: Vec<Arc<DataBlock>> = bundle
+            .data_packs
+            .read()
+            .values()
+            .flat_map(|pack| pack.blocks())
+            .filter(|block| block.schema().field_with_name(&self.column).is_ok())
+            .collect();
+
+        for block in blocks {
+            let df = bundle
+                .ctx
+                .table(format!("blocks.{}", DataBlock::table_name(block.id())))
+                .await?;
+            let exprs = df
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| {
+                    if field.name() == &self.column {
+                        Ok(self.stored_expr(field, &config)?.alias(field.name()))
+                    } else {
+                        Ok(ident(field.name()))
+                    }
+                })
+                .collect::<Result<Vec<_>, BundlebaseError>>()?;
+
+            let df = df.select(exprs)?;
+            let schema = Arc::new(df.schema().as_arrow().clone());
+            let file = format!("masked_{}.parquet", Uuid::new_v4());
+            let out = bundle.data_dir.file(&file)?;
+            let rows = write_parquet(df, &out, config.parquet_writer_properties()?).await?;
+            info!(
+                "Wrote {} rows of block {} with '{}' masked to {}",
+                rows,
+                block.id(),
+                self.column,
+                file
+            );
+
+            self.blocks.push(MaskedBlock {
+                id: *block.id(),
+                file,
+                version: out.version().await?,
+                schema: Some(schema),
+            });
+        }
+        Ok(self)
+    }
+
+    /// Whether the given config holds the key this column was encrypted with
+    fn can_decrypt(&self, config: Option<&BundleConfig>) -> bool {
+        match (
+            config.and_then(|c| c.column_encryption_key()),
+            &self.key_check,
+        ) {
+            (Some(key), Some(check)) => open_key_check(key, &self.column, check),
+            _ => false,
+        }
+    }
+
+    /// The column's values as written to rewritten blocks
+    fn stored_expr(&self, field: &Field, config: &BundleConfig) -> Result<Expr, BundlebaseError> {
+        if self.strategy != MaskStrategy::Encrypt {
+            return self.mask_expr(field);
+        }
+        let key = config.column_encryption_key().ok_or_else(|| {
+            format!(
+                "Cannot encrypt column '{}': no {} is configured",
+                self.column, COLUMN_ENCRYPTION_KEY
+            )
+        })?;
+        let key = aead_key(key)?;
+        let column = self.column.clone();
+        let seal = move |value: &str| seal(&key, &column, value.as_bytes()).map(Some);
+        Ok(string_udf("mask_encrypt", seal).call(vec![cast(ident(field.name()), DataType::Utf8)]))
+    }
+
+    /// What the column reads as, or None if it reads as it is stored
+    fn read_expr(
+        &self,
+        field: &Field,
+        config: Option<&BundleConfig>,
+    ) -> Result<Option<Expr>, BundlebaseError> {
+        let key = match self.strategy {
+            MaskStrategy::Encrypt if self.can_decrypt(config) => {
+                config.and_then(|c| c.column_encryption_key())
+            }
+            _ => None,
+        };
+        if self.blocks.is_empty() {
+            // Masked as the data is read, unless the reader holds the key
+            return Ok(match key {
+                Some(_) => None,
+                None => Some(self.mask_expr(field)?),
+            });
+        }
+
+        match (self.strategy, key) {
+            (MaskStrategy::Encrypt, Some(key)) => {
+                let key = aead_key(key)?;
+                let column = self.column.clone();
+                let open = move |value: &str| {
+                    Ok(open(&key, &column, value)
+                        .map(|opened| String::from_utf8_lossy(&opened).into_owned()))
+                };
+                Ok(Some(
+                    string_udf("mask_decrypt", open).call(vec![ident(field.name())]),
+                ))
+            }
+            (MaskStrategy::Encrypt, None) => Ok(Some(lit(ScalarValue::Utf8(None)))),
+            // The stored values are already masked
+            _ => Ok(None),
+        }
+    }
+
+    fn mask_expr(&self, field: &Field) -> Result<Expr, BundlebaseError> {
+        Ok(match self.strategy {
+            MaskStrategy::Hash => encode(
+                sha256(cast(ident(field.name()), DataType::Utf8)),
+                lit("hex"),
+            ),
+            MaskStrategy::Null | MaskStrategy::Encrypt => {
+                lit(ScalarValue::try_from(field.data_type())?)
+            }
+        })
+    }
+}
+
+fn aead_key(key: &str) -> Result<LessSafeKey, BundlebaseError> {
+    let unbound = UnboundKey::new(&AES_256_GCM, &Sha256::digest(key.as_bytes()))
+        .map_err(|_| format!("Invalid {}", COLUMN_ENCRYPTION_KEY))?;
+    Ok(LessSafeKey::new(unbound))
+}
+
+/// Seals `data` under `key`, bound to `column`, returned as hex `nonce || ciphertext || tag`
+fn seal(key: &LessSafeKey, column: &str, data: &[u8]) -> Result<String, BundlebaseError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| "Unable to generate an encryption nonce")?;
+
+    let mut sealed = data.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(column.as_bytes()),
+        &mut sealed,
+    )
+    .map_err(|_| format!("Unable to encrypt column '{}'", column))?;
+
+    Ok(hex::encode([nonce.as_slice(), sealed.as_slice()].concat()))
+}
+
+/// The data `seal` sealed under the same key and column, or None if it doesn't open
+fn open(key: &LessSafeKey, column: &str, sealed: &str) -> Option<Vec<u8>> {
+    let bytes = hex::decode(sealed).ok()?;
+    if bytes.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+
+    let mut sealed = sealed.to_vec();
+    key.open_in_place(nonce, Aad::from(column.as_bytes()), &mut sealed)
+        .ok()
+        .map(|opened| opened.to_vec())
+}
+
+/// Seals the column name under `key`, so readers can tell whether they hold the same key
+fn seal_key_check(key: &str, column: &str) -> Result<String, BundlebaseError> {
+    seal(&aead_key(key)?, "", column.as_bytes())
+}
+
+/// True if `check` was made by `seal_key_check` with the same key and column
+fn open_key_check(key: &str, column: &str, check: &str) -> bool {
+    aead_key(key)
+        .ok()
+        .and_then(|key| open(&key, "", check))
+        .is_some_and(|opened| opened == column.as_bytes())
+}
+
+/// A string-to-string UDF applying `f` to each non-null value
+fn string_udf<F>(name: &str, f: F) -> datafusion::logical_expr::ScalarUDF
+where
+    F: Fn(&str) -> Result<Option<String>, BundlebaseError> + Send + Sync + 'static,
+{
+    create_udf(
+        name,
+        vec![DataType::Utf8],
+        DataType::Utf8,
+        Volatility::Volatile,
+        Arc::new(move |args: &[ColumnarValue]| {
+            let arrays = ColumnarValue::values_to_arrays(args)?;
+            let values = arrays[0]
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| DataFusionError::Internal("Expected a string array".to_string()))?;
+            let result = values
+                .iter()
+                .map(|value| value.map(&f).transpose().map(Option::flatten))
+                .collect::<Result<StringArray, BundlebaseError>>()
+                .map_err(DataFusionError::External)?;
+            Ok(ColumnarValue::Array(Arc::new(result) as ArrayRef))
+        }),
+    )
+}
+
+#[async_trait]
+impl Operation for MaskColumnOp {
+    fn describe(&self) -> String {
+        format!("MASK COLUMN: {} WITH {}", self.column, self.strategy)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        bundle.raw_schema().await?.field_with_name(&self.column)?;
+        bundle.check_not_security_column(&self.column, "mask column")?;
+
+        // Index files hold the column's raw values, which would defeat the mask
+        if bundle
+            .indexes()
+            .read()
+            .iter()
+            .any(|index| index.columns().contains(&self.column))
+        {
+            return Err(format!(
+                "Cannot mask column '{}': it is indexed. Drop the index first",
+                self.column
+            )
+            .into());
+        }
+
+        if self.strategy == MaskStrategy::Encrypt && self.key_check.is_none() {
+            return Err(format!(
+                "Cannot encrypt column '{}': no key check recorded",
+                self.column
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        for masked in &self.blocks {
+            let pack = bundle
+                .data_packs
+                .read()
+                .values()
+                .find(|pack| pack.blocks().iter().any(|block| block.id() == &masked.id))
+                .cloned()
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!("Cannot find block {} to mask", masked.id))
+                })?;
+
+            let schema = masked.schema.clone().ok_or_else(|| {
+                DataFusionError::Plan(format!("No schema recorded for {}", masked.file))
+            })?;
+            let reader = bundle
+                .adapter_factory
+                .reader(
+                    &masked.file,
+                    &masked.id,
+                    bundle,
+                    Some(schema.clone()),
+                    None,
+                    Some("parquet".to_string()),
+                )
+                .await?;
+            pack.replace_block(Arc::new(DataBlock::new(
+                masked.id,
+                schema,
+                &masked.version,
+                reader,
+                bundle.indexes().clone(),
+                Arc::new(bundle.data_dir().clone()),
+                bundle.config(),
+            )));
+        }
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let config = ctx
+            .state_ref()
+            .read()
+            .config()
+            .get_extension::<BundleConfig>();
+        let Some(field) = df
+            .schema()
+            .fields()
+            .iter()
+            .find(|field| field.name() == &self.column)
+            .cloned()
+        else {
+            return Ok(df);
+        };
+        let Some(expr) = self.read_expr(&field, config.as_deref())? else {
+            return Ok(df);
+        };
+
+        let exprs = df
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                if field.name() == &self.column {
+                    expr.clone().alias(field.name())
+                } else {
+                    ident(field.name())
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(df.select(exprs)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_key(key: &str) -> BundleConfig {
+        let mut config = BundleConfig::new();
+        config.set(COLUMN_ENCRYPTION_KEY, key, None);
+        config
+    }
+
+    #[test]
+    fn test_describe() {
+        let op = MaskColumnOp::setup("email", MaskStrategy::Hash, &BundleConfig::new()).unwrap();
+        assert_eq!(op.describe(), "MASK COLUMN: email WITH hash");
+    }
+
+    #[test]
+    fn test_config_serialization() {
+        let op = MaskColumnOp::setup("email", MaskStrategy::Null, &BundleConfig::new()).unwrap();
+
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "column: email\nstrategy: 'null'\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+
+    #[test]
+    fn test_strategy_from_str() {
+        assert_eq!(MaskStrategy::Hash, "HASH".parse().unwrap());
+        assert_eq!(MaskStrategy::Encrypt, "encrypt".parse().unwrap());
+        assert_eq!(
+            "Unknown mask strategy 'rot13': must be hash, null or encrypt",
+            "rot13".parse::<MaskStrategy>().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_encrypt_requires_key() {
+        assert_eq!(
+            "Cannot encrypt column 'ssn': no column_encryption_key is configured",
+            MaskColumnOp::setup("ssn", MaskStrategy::Encrypt, &BundleConfig::new())
+                .err()
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_key_check() {
+        let op =
+            MaskColumnOp::setup("ssn", MaskStrategy::Encrypt, &config_with_key("secret")).unwrap();
+        let check = op.key_check.clone().unwrap();
+        assert!(!check.contains("secret"));
+
+        assert!(op.can_decrypt(Some(&config_with_key("secret"))));
+        assert!(!op.can_decrypt(Some(&config_with_key("guess"))));
+        assert!(!op.can_decrypt(Some(&BundleConfig::new())));
+        assert!(!op.can_decrypt(None));
+
+        // The check is bound to the column it was made for
+        assert!(!open_key_check("secret", "email", &check));
+        assert!(!open_key_check("secret", "ssn", "not hex"));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::bundle::operation::Operation;
+use crate::bundle::write_parquet;
+use crate::data::ObjectId;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::SessionContext;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Stores a view's data in a parquet file in the view's directory, so reading the view reads the
+/// file instead of running its operations against the parent again.
+///
+/// The file is a snapshot: later changes to the parent aren't seen until the view is
+/// materialized again.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MaterializeViewOp {
+    pub view_id: ObjectId,
+    /// The parquet file, relative to the view's directory
+    pub file: String,
+}
+
+impl MaterializeViewOp {
+    /// Runs the view against the bundle's current data and writes the result
+    pub async fn setup(identifier: &str, bundle: &Bundle) -> Result<Self, BundlebaseError> {
+        let (view_id, name) = bundle.get_view_id_by_name_or_id(identifier)?;
+
+        let file = format!("materialized_{}.parquet", Uuid::new_v4());
+        let df = bundle.lazy_view_dataframe(&view_id).await?;
+        let rows = write_parquet(
+            df.as_ref().clone(),
+            &bundle.view_dir(&view_id)?.file(&file)?,
+            bundle.config().parquet_writer_properties()?,
+        )
+        .await?;
+        info!("Wrote {} rows of view '{}' to {}", rows, name, file);
+
+        Ok(Self { view_id, file })
+    }
+}
+
+#[async_trait]
+impl Operation for MaterializeViewOp {
+    fn describe(&self) -> String {
+        format!("MATERIALIZE VIEW {}", self.view_id)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if !bundle.views.values().any(|id| id == &self.view_id) {
+            return Err(format!("View with ID '{}' not found", self.view_id).into());
+        }
+        Ok(())
+    }
+
+    fn allowed_on_view(&self) -> bool {
+        false
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        bundle
+            .materialized_views
+            .insert(self.view_id, self.file.clone());
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        // MaterializeViewOp doesn't modify the dataframe (metadata-only operation)
+        Ok(df)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let view_id: ObjectId = "a5".try_into().unwrap();
+        let op = MaterializeViewOp {
+            view_id,
+            file: "materialized_1.parquet".to_string(),
+        };
+        assert_eq!(op.describe(), "MATERIALIZE VIEW a5");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = MaterializeViewOp {
+            view_id: "a5".try_into().unwrap(),
+            file: "materialized_1.parquet".to_string(),
+        };
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "viewId: a5\nfile: materialized_1.parquet\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
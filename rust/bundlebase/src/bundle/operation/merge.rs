@@ -0,0 +1,209 @@
+use crate::bundle::operation::Operation;
+use crate::bundle::sql::with_temp_table;
+use crate::data::{DataPack, ObjectId};
+use crate::metrics::{start_span, OperationCategory, OperationOutcome, OperationTimer};
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::functions_aggregate::expr_fn::count;
+use datafusion::prelude::{col, ident, lit, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Merges the data in a separate pack into the bundle, keyed by one or more columns.
+///
+/// Rows in the bundle whose key matches a row in the merged pack are replaced by the merged row,
+/// and merged rows with new keys are added. The merge is applied at query time, so the merged
+/// pack must have the same columns as the bundle at the point the merge was recorded.
+///
+/// Each merged row must have a complete key that no other merged row shares, see
+/// `check_merged_keys`. Existing rows with a null key match nothing, so they are always kept.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeOp {
+    pub pack_id: ObjectId,
+    pub key_columns: Vec<String>,
+}
+
+impl MergeOp {
+    pub async fn setup(
+        pack_id: &ObjectId,
+        key_columns: Vec<&str>,
+    ) -> Result<Self, BundlebaseError> {
+        Ok(Self {
+            pack_id: *pack_id,
+            key_columns: key_columns.iter().map(|c| c.to_string()).collect(),
+        })
+    }
+
+    /// Checks that every merged row has a key without nulls, and that no two merged rows share
+    /// a key, since then it would be ambiguous which of them replaces the existing row.
+    /// This scans the merged data, so it is only done when the merge is added rather than in
+    /// `check`, which also runs each time the bundle is opened.
+    pub async fn check_merged_keys(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let keys = self.key_columns.join(", ");
+        let merged = bundle
+            .ctx()
+            .table(format!("packs.{}", DataPack::table_name(&self.pack_id)))
+            .await?;
+
+        let null_key = self
+            .key_columns
+            .iter()
+            .map(|k| ident(k).is_null())
+            .reduce(|a, b| a.or(b))
+            .ok_or("Merge requires at least one key column")?;
+        let nulls = merged.clone().filter(null_key)?.count().await?;
+        if nulls > 0 {
+            return Err(format!(
+                "Cannot merge on ({}): {} merged row(s) have a null key",
+                keys, nulls
+            )
+            .into());
+        }
+
+        let duplicates = merged
+            .aggregate(
+                self.key_columns.iter().map(ident).collect(),
+                vec![count(lit(1)).alias("rows")],
+            )?
+            .filter(col("rows").gt(lit(1)))?
+            .count()
+            .await?;
+        if duplicates > 0 {
+            return Err(format!(
+                "Cannot merge on ({}): {} key value(s) appear in more than one merged row",
+                keys, duplicates
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[async_trait]
+impl Operation for MergeOp {
+    fn describe(&self) -> String {
+        format!("MERGE ON {}", self.key_columns.join(", "))
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.key_columns.is_empty() {
+            return Err("Merge requires at least one key column".into());
+        }
+        if bundle.base_pack.is_none() || bundle.base_pack.as_ref() == Some(&self.pack_id) {
+            return Err("Merge requires existing data in the bundle. Use attach first".into());
+        }
+        if bundle.get_pack(&self.pack_id).is_none() {
+            return Err(format!("Pack {} not found for merge", self.pack_id).into());
+        }
+        Ok(())
+    }
+
+    fn allowed_on_view(&self) -> bool {
+        false
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // Merging replaces rows but doesn't change the schema, so no reconfiguration needed
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let keys = self.key_columns.join(", ");
+        let mut span = start_span(OperationCategory::Select, "merge");
+        span.set_attribute("keys", &keys);
+
+        let timer =
+            OperationTimer::start(OperationCategory::Select, "merge").with_label("keys", &keys);
+
+        let merge_table = format!("packs.{}", DataPack::table_name(&self.pack_id));
+        let columns = df
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| quote_ident(f.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let key_match = self
+            .key_columns
+            .iter()
+            .map(|k| format!("m.{} = b.{}", quote_ident(k), quote_ident(k)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let ctx_for_closure = ctx.clone();
+
+        let result = with_temp_table(&ctx, df, |temp_table| async move {
+            // Keep existing rows without a replacement, then add every merged row. A null key
+            // compares as unknown, so existing rows with one are never replaced
+            let sql = format!(
+                "SELECT * FROM {} b WHERE NOT EXISTS (SELECT 1 FROM {} m WHERE {}) \
+                 UNION ALL SELECT {} FROM {}",
+                temp_table, merge_table, key_match, columns, merge_table
+            );
+            ctx_for_closure
+                .sql(&sql)
+                .await
+                .map_err(|e| Box::new(e) as BundlebaseError)
+        })
+        .await;
+
+        match &result {
+            Ok(_) => {
+                span.set_outcome(OperationOutcome::Success);
+                timer.finish(OperationOutcome::Success);
+            }
+            Err(e) => {
+                span.record_error(&e.to_string());
+                timer.finish(OperationOutcome::Error);
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = MergeOp {
+            pack_id: ObjectId::from(1),
+            key_columns: vec!["Index".to_string(), "Customer Id".to_string()],
+        };
+        assert_eq!(op.describe(), "MERGE ON Index, Customer Id");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = MergeOp {
+            pack_id: ObjectId::from(1),
+            key_columns: vec!["id".to_string()],
+        };
+
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert!(serialized.contains("packId"));
+        assert!(serialized.contains("keyColumns"));
+
+        let deserialized: MergeOp =
+            serde_yaml::from_str(&serialized).expect("Failed to deserialize");
+        assert_eq!(deserialized, op);
+    }
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("Customer Id"), "\"Customer Id\"");
+        assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+    }
+}
@@ -0,0 +1,176 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::{ident, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Which way a sort key orders rows, and where rows with a null key go. Plain ascending puts
+/// nulls last and plain descending puts them first, as in SQL.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+    AscNullsFirst,
+    DescNullsLast,
+}
+
+impl SortDirection {
+    pub fn ascending(&self) -> bool {
+        matches!(self, SortDirection::Asc | SortDirection::AscNullsFirst)
+    }
+
+    pub fn nulls_first(&self) -> bool {
+        matches!(self, SortDirection::Desc | SortDirection::AscNullsFirst)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+            SortDirection::AscNullsFirst => "ASC NULLS FIRST",
+            SortDirection::DescNullsLast => "DESC NULLS LAST",
+        }
+    }
+}
+
+impl FromStr for SortDirection {
+    type Err = BundlebaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        match words.to_uppercase().as_str() {
+            "ASC" | "ASC NULLS LAST" => Ok(SortDirection::Asc),
+            "DESC" | "DESC NULLS FIRST" => Ok(SortDirection::Desc),
+            "ASC NULLS FIRST" => Ok(SortDirection::AscNullsFirst),
+            "DESC NULLS LAST" => Ok(SortDirection::DescNullsLast),
+            _ => Err(format!(
+                "Unknown sort direction '{}': must be ASC or DESC, optionally followed by NULLS FIRST or NULLS LAST",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+impl Display for SortDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// One column an `OrderByOp` sorts on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SortKey {
+    pub column: String,
+    pub direction: SortDirection,
+}
+
+/// Sorts the rows by `keys`, the first key deciding and each following one breaking ties
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderByOp {
+    pub keys: Vec<SortKey>,
+}
+
+impl OrderByOp {
+    pub fn setup(keys: &[(&str, SortDirection)]) -> Self {
+        Self {
+            keys: keys
+                .iter()
+                .map(|(column, direction)| SortKey {
+                    column: column.to_string(),
+                    direction: *direction,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl Operation for OrderByOp {
+    fn describe(&self) -> String {
+        let keys = self
+            .keys
+            .iter()
+            .map(|k| format!("{} {}", k.column, k.direction))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("ORDER BY: {}", keys)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.keys.is_empty() {
+            return Err("Order by needs at least one sort key".into());
+        }
+        let schema = bundle.raw_schema().await?;
+        for key in &self.keys {
+            schema.field_with_name(&key.column)?;
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let sort = self
+            .keys
+            .iter()
+            .map(|k| ident(&k.column).sort(k.direction.ascending(), k.direction.nulls_first()))
+            .collect();
+        Ok(df.sort(sort)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op() -> OrderByOp {
+        OrderByOp::setup(&[
+            ("country", SortDirection::Asc),
+            ("sales", SortDirection::DescNullsLast),
+        ])
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            op().describe(),
+            "ORDER BY: country ASC, sales DESC NULLS LAST"
+        );
+    }
+
+    #[test]
+    fn test_direction_from_str() {
+        assert_eq!(SortDirection::Asc, "asc".parse().unwrap());
+        assert_eq!(SortDirection::Desc, "DESC NULLS FIRST".parse().unwrap());
+        assert_eq!(
+            SortDirection::AscNullsFirst,
+            "asc  nulls first".parse().unwrap()
+        );
+        assert!("sideways".parse::<SortDirection>().is_err());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let serialized = serde_yaml::to_string(&op()).expect("Failed to serialize");
+        assert_eq!(
+            serialized,
+            "keys:\n- column: country\n  direction: asc\n- column: sales\n  direction: descNullsLast\n"
+        );
+        assert_eq!(op(), serde_yaml::from_str(&serialized).unwrap());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::DataType;
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::functions_aggregate::expr_fn::{avg, count, max, min, sum};
+use datafusion::logical_expr::{cast, when, Expr};
+use datafusion::prelude::{ident, lit, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Most distinct values a pivoted column may have, each of which becomes a column
+pub const MAX_PIVOT_VALUES: usize = 1000;
+
+/// How the values landing in the same pivoted cell are combined
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PivotAggregate {
+    Sum,
+    Count,
+    Min,
+    Max,
+    Avg,
+}
+
+impl PivotAggregate {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PivotAggregate::Sum => "sum",
+            PivotAggregate::Count => "count",
+            PivotAggregate::Min => "min",
+            PivotAggregate::Max => "max",
+            PivotAggregate::Avg => "avg",
+        }
+    }
+
+    pub(crate) fn expr(&self, value: Expr) -> Expr {
+        match self {
+            PivotAggregate::Sum => sum(value),
+            PivotAggregate::Count => count(value),
+            PivotAggregate::Min => min(value),
+            PivotAggregate::Max => max(value),
+            PivotAggregate::Avg => avg(value),
+        }
+    }
+}
+
+impl FromStr for PivotAggregate {
+    type Err = BundlebaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sum" => Ok(PivotAggregate::Sum),
+            "count" => Ok(PivotAggregate::Count),
+            "min" => Ok(PivotAggregate::Min),
+            "max" => Ok(PivotAggregate::Max),
+            "avg" => Ok(PivotAggregate::Avg),
+            _ => Err(format!(
+                "Unknown pivot aggregate '{}': must be sum, count, min, max or avg",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+impl Display for PivotAggregate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Reshapes long data to wide: one row per distinct `index` combination, with a column for each
+/// distinct value of `columns` holding the aggregated `values` for it.
+///
+/// The distinct values are read when the pivot is set up and recorded as `pivot_values`, so the
+/// bundle's schema doesn't change as data is added. Values that show up later are left out.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PivotOp {
+    pub index: Vec<String>,
+    pub columns: String,
+    pub values: String,
+    pub aggregate: PivotAggregate,
+    pub pivot_values: Vec<String>,
+}
+
+impl PivotOp {
+    pub async fn setup(
+        index: Vec<&str>,
+        columns: &str,
+        values: &str,
+        aggregate: PivotAggregate,
+        df: &DataFrame,
+    ) -> Result<Self, BundlebaseError> {
+        // One more than allowed is enough to know there are too many, so a column with millions
+        // of distinct values isn't read into memory only to be rejected
+        let batches = df
+            .clone()
+            .select(vec![cast(ident(columns), DataType::Utf8).alias(columns)])?
+            .distinct()?
+            .limit(0, Some(MAX_PIVOT_VALUES + 1))?
+            .collect()
+            .await?;
+
+        let mut pivot_values = Vec::new();
+        for batch in &batches {
+            let array = batch.column(0).as_string::<i32>();
+            for i in 0..array.len() {
+                if array.is_valid(i) {
+                    pivot_values.push(array.value(i).to_string());
+                }
+            }
+        }
+        if pivot_values.len() > MAX_PIVOT_VALUES {
+            return Err(format!(
+                "Cannot pivot on '{}': it has more than the {} distinct values allowed",
+                columns, MAX_PIVOT_VALUES
+            )
+            .into());
+        }
+        pivot_values.sort();
+
+        Ok(Self {
+            index: index.iter().map(|c| c.to_string()).collect(),
+            columns: columns.to_string(),
+            values: values.to_string(),
+            aggregate,
+            pivot_values,
+        })
+    }
+}
+
+#[async_trait]
+impl Operation for PivotOp {
+    fn describe(&self) -> String {
+        format!(
+            "PIVOT: {}({}) FOR {} BY {}",
+            self.aggregate,
+            self.values,
+            self.columns,
+            self.index.join(", ")
+        )
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let schema = bundle.raw_schema().await?;
+        for column in self.index.iter().chain([&self.columns, &self.values]) {
+            schema.field_with_name(column)?;
+        }
+        for column in [&self.columns, &self.values] {
+            if self.index.contains(column) {
+                return Err(
+                    format!("Column '{}' cannot be pivoted and in the index", column).into(),
+                );
+            }
+        }
+        if let Some(value) = self.pivot_values.iter().find(|v| self.index.contains(v)) {
+            return Err(format!(
+                "Pivot value '{}' would replace the index column of the same name",
+                value
+            )
+            .into());
+        }
+        for value in &self.pivot_values {
+            bundle.check_not_security_column(value, "define column")?;
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // The rows are all new, so any declared key no longer applies
+        bundle.key_columns.clear();
+        bundle.nullable_columns.remove(&self.columns);
+        bundle.nullable_columns.remove(&self.values);
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let group_by = self.index.iter().map(ident).collect();
+        let mut aggregates = Vec::with_capacity(self.pivot_values.len());
+        for value in &self.pivot_values {
+            let matches = cast(ident(&self.columns), DataType::Utf8).eq(lit(value.as_str()));
+            let cell = when(matches, ident(&self.values)).end()?;
+            aggregates.push(self.aggregate.expr(cell).alias(value));
+        }
+        Ok(df.aggregate(group_by, aggregates)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op() -> PivotOp {
+        PivotOp {
+            index: vec!["id".to_string()],
+            columns: "quarter".to_string(),
+            values: "sales".to_string(),
+            aggregate: PivotAggregate::Sum,
+            pivot_values: vec!["q1".to_string(), "q2".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(op().describe(), "PIVOT: sum(sales) FOR quarter BY id");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let serialized = serde_yaml::to_string(&op()).expect("Failed to serialize");
+        assert_eq!(
+            serialized,
+            "index:\n- id\ncolumns: quarter\nvalues: sales\naggregate: sum\npivotValues:\n- q1\n- q2\n"
+        );
+        assert_eq!(op(), serde_yaml::from_str(&serialized).unwrap());
+    }
+
+    #[test]
+    fn test_aggregate_from_str() {
+        assert_eq!(PivotAggregate::Avg, "AVG".parse().unwrap());
+        assert_eq!(
+            "Unknown pivot aggregate 'median': must be sum, count, min, max or avg",
+            "median"
+                .parse::<PivotAggregate>()
+                .err()
+                .unwrap()
+                .to_string()
+        );
+    }
+}
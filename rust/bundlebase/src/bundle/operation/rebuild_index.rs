@@ -9,7 +9,12 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct RebuildIndexOp {
+    /// The indexed column. Empty for a composite index, which lists its `columns` instead.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub column: String,
+    /// Columns of a composite index, in key order. Empty for an index on a single column.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
 }
 
 impl RebuildIndexOp {
@@ -99,25 +104,48 @@ impl RebuildIndexOp {
         // // Save to disk
         // let _ = ctx.index_manager.save_index(&self.column).await?;
 
-        Ok(Self { column })
+        Ok(Self {
+            column,
+            columns: vec![],
+        })
+    }
+
+    pub async fn setup_composite(columns: &[String]) -> Result<Self, BundlebaseError> {
+        if columns.len() < 2 {
+            return Err("A composite index needs at least two columns".into());
+        }
+        Ok(Self {
+            column: String::new(),
+            columns: columns.to_vec(),
+        })
+    }
+
+    /// The indexed columns, in key order, whether the index is composite or not
+    pub fn indexed_columns(&self) -> &[String] {
+        if self.columns.is_empty() {
+            std::slice::from_ref(&self.column)
+        } else {
+            &self.columns
+        }
     }
 }
 
 #[async_trait]
 impl Operation for RebuildIndexOp {
     fn describe(&self) -> String {
-        format!("REBUILD INDEX on column '{}'", self.column)
+        format!(
+            "REBUILD INDEX on column '{}'",
+            self.indexed_columns().join(", ")
+        )
     }
 
     async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
-        // Verify column exists in schema
-        if !bundle
-            .schema()
-            .await?
-            .column_with_name(&self.column)
-            .is_some()
-        {
-            return Err(format!("Column '{}' not found in schema", self.column).into());
+        // Verify the columns exist in schema
+        let schema = bundle.schema().await?;
+        for column in self.indexed_columns() {
+            if schema.column_with_name(column).is_none() {
+                return Err(format!("Column '{}' not found in schema", column).into());
+            }
         }
 
         Ok(())
@@ -0,0 +1,78 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::logical_expr::ScalarUDF;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Makes a scalar UDF callable by name in the bundle's expressions and queries.
+///
+/// A UDF's code can't be saved, so only its name is recorded. The UDF itself comes from the
+/// bundle's function registry, which the embedder has to fill again before reopening the bundle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterUdfOp {
+    pub name: String,
+}
+
+impl RegisterUdfOp {
+    pub fn setup(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    fn udf(&self, bundle: &Bundle) -> Result<Arc<ScalarUDF>, BundlebaseError> {
+        let udf = bundle.function_registry.read().get_udf(&self.name);
+        udf.ok_or_else(|| {
+            format!(
+                "UDF '{}' is not registered. UDFs aren't saved in the bundle, so register it on \
+                 a FunctionRegistry and open the bundle with Bundle::open_with_functions",
+                self.name
+            )
+            .into()
+        })
+    }
+}
+
+#[async_trait]
+impl Operation for RegisterUdfOp {
+    fn describe(&self) -> String {
+        format!("REGISTER UDF: {}", self.name)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        self.udf(bundle)?;
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        let udf = self
+            .udf(bundle)
+            .map_err(|e| DataFusionError::Plan(e.to_string()))?;
+        bundle.ctx.register_udf(udf.as_ref().clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            RegisterUdfOp::setup("geohash").describe(),
+            "REGISTER UDF: geohash"
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = RegisterUdfOp::setup("geohash");
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "name: geohash\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
@@ -27,11 +27,21 @@ impl Operation for RemoveColumnsOp {
         format!("REMOVE COLUMNS: {:?}", self.names)
     }
 
-    async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        for name in &self.names {
+            bundle.check_not_security_column(name, "remove column")?;
+        }
         Ok(())
     }
 
-    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // A key missing one of its columns no longer identifies rows, so drop it entirely
+        if bundle.key_columns.iter().any(|k| self.names.contains(k)) {
+            bundle.key_columns.clear();
+        }
+        for name in &self.names {
+            bundle.nullable_columns.remove(name);
+        }
         Ok(())
     }
 
@@ -1,5 +1,4 @@
 use crate::bundle::operation::Operation;
-use crate::bundle::BundleFacade;
 use crate::{Bundle, BundlebaseError};
 use async_trait::async_trait;
 use datafusion::common::DataFusionError;
@@ -27,13 +26,26 @@ impl RenameColumnOp {
 #[async_trait]
 impl Operation for RenameColumnOp {
     async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
-        let schema = bundle.schema().await?;
+        let schema = bundle.raw_schema().await?;
         schema.field_with_name(&self.old_name)?;
+        bundle.check_not_security_column(&self.old_name, "rename column")?;
+        bundle.check_not_security_column(&self.new_name, "rename a column to")?;
 
         Ok(())
     }
 
-    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // Declared constraints follow the column to its new name
+        for key in bundle.key_columns.iter_mut() {
+            if *key == self.old_name {
+                *key = self.new_name.clone();
+            }
+        }
+        if let Some(nullable) = bundle.nullable_columns.remove(&self.old_name) {
+            bundle
+                .nullable_columns
+                .insert(self.new_name.clone(), nullable);
+        }
         Ok(())
     }
 
@@ -0,0 +1,122 @@
+use crate::bundle::operation::{AnyOperation, Operation};
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use serde::{Deserialize, Serialize};
+
+/// A location that moved: references starting with `old_prefix` now start with `new_prefix`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RefMapping {
+    pub old_prefix: String,
+    pub new_prefix: String,
+}
+
+impl RefMapping {
+    pub fn new(old_prefix: &str, new_prefix: &str) -> Self {
+        Self {
+            old_prefix: old_prefix.to_string(),
+            new_prefix: new_prefix.to_string(),
+        }
+    }
+
+    /// The reference with `old_prefix` replaced, if it starts with it.
+    /// The prefix must end at a path boundary, so `s3://a/data` doesn't move `s3://a/data2`.
+    pub fn apply(&self, reference: &str) -> Option<String> {
+        let old = self.old_prefix.trim_end_matches('/');
+        let rest = reference.strip_prefix(old)?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        Some(format!("{}{}", self.new_prefix.trim_end_matches('/'), rest))
+    }
+}
+
+/// Rewrites `reference` with the first of `mappings` that applies to it
+pub(crate) fn repair_ref(reference: &str, mappings: &[RefMapping]) -> Option<String> {
+    mappings.iter().find_map(|mapping| mapping.apply(reference))
+}
+
+/// Rewrites the locations `op` refers to with `mappings`, returning true if any changed.
+/// Relative paths are left alone, since they move along with the bundle.
+pub(crate) fn repair_operation(op: &mut AnyOperation, mappings: &[RefMapping]) -> bool {
+    let reference = match op {
+        AnyOperation::AttachBlock(attach) => &mut attach.source,
+        AnyOperation::AttachBundle(attach) => &mut attach.url,
+        AnyOperation::IndexBlocks(index) => &mut index.path,
+        _ => return false,
+    };
+    match repair_ref(reference, mappings) {
+        Some(repaired) => {
+            *reference = repaired;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Records that locations the bundle refers to have moved, see `Bundle::repair_refs`.
+///
+/// The mappings are read before any of the bundle's commits are replayed, so they apply to its
+/// `from` link and to operations committed both before and after this one, including those of
+/// the bundles it extends. Applying the operation itself changes nothing.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairRefsOp {
+    pub mappings: Vec<RefMapping>,
+}
+
+impl RepairRefsOp {
+    pub fn setup(mappings: Vec<RefMapping>) -> Self {
+        Self { mappings }
+    }
+}
+
+#[async_trait]
+impl Operation for RepairRefsOp {
+    fn describe(&self) -> String {
+        let mappings: Vec<String> = self
+            .mappings
+            .iter()
+            .map(|m| format!("{} -> {}", m.old_prefix, m.new_prefix))
+            .collect();
+        format!("REPAIR REFS: {}", mappings.join(", "))
+    }
+
+    async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.mappings.is_empty() {
+            return Err("No reference mappings given".into());
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_mapping() {
+        let mapping = RefMapping::new("s3://old/parent/", "s3://new/parent");
+        assert_eq!(
+            Some("s3://new/parent/data/a.csv".to_string()),
+            mapping.apply("s3://old/parent/data/a.csv")
+        );
+        assert_eq!(
+            Some("s3://new/parent".to_string()),
+            mapping.apply("s3://old/parent")
+        );
+        assert_eq!(None, mapping.apply("s3://old/parent2/a.csv"));
+        assert_eq!(None, mapping.apply("data/a.csv"));
+    }
+
+    #[test]
+    fn test_describe() {
+        let op = RepairRefsOp::setup(vec![RefMapping::new("s3://old/", "s3://new/")]);
+        assert_eq!("REPAIR REFS: s3://old/ -> s3://new/", op.describe());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::bundle::operation::{AnyOperation, Operation};
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::SessionContext;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Undoes an earlier commit by leaving its operations out when the data is built, so a removed
+/// column comes back as long as the data it came from is still attached. The commit itself stays
+/// in the history.
+///
+/// Only commits whose operations just reshape the data can be reverted. Ones that attach data,
+/// define views, indexes or the like, or rename columns are refused. Key and nullability
+/// declarations a reverted operation dropped are not restored.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertCommitOp {
+    pub commit_id: String,
+}
+
+impl RevertCommitOp {
+    pub fn setup(commit_id: &str) -> Self {
+        Self {
+            commit_id: commit_id.to_string(),
+        }
+    }
+
+    /// Positions of the commit's operations in the bundle's operations
+    fn positions(&self, bundle: &Bundle) -> Result<Range<usize>, BundlebaseError> {
+        let mut start = 0;
+        for commit in &bundle.commits {
+            let count = commit.operations().len();
+            if commit.id == self.commit_id {
+                return Ok(start..start + count);
+            }
+            start += count;
+        }
+        Err(format!(
+            "Commit '{}' is not in the history of this bundle",
+            self.commit_id
+        )
+        .into())
+    }
+
+    /// Checks that the data still builds without the commit's operations, since later operations
+    /// may use what it changed. This plans the data, so it is done when the revert is added
+    /// rather than in `check`, which also runs each time the bundle is opened.
+    pub(crate) async fn check_later_operations(
+        &self,
+        bundle: &Bundle,
+    ) -> Result<(), BundlebaseError> {
+        let mut reverted = bundle.clone();
+        reverted.reverted_operations.extend(self.positions(bundle)?);
+        reverted.dataframe.clear();
+        reverted.raw_dataframe().await.map_err(|e| {
+            format!(
+                "Cannot revert commit '{}': later changes depend on it: {}",
+                self.commit_id, e
+            )
+        })?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Operation for RevertCommitOp {
+    fn describe(&self) -> String {
+        format!("REVERT COMMIT: {}", self.commit_id)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let positions = self.positions(bundle)?;
+        if positions.is_empty() {
+            return Err(format!("Commit '{}' has no operations to revert", self.commit_id).into());
+        }
+        if positions
+            .clone()
+            .all(|i| bundle.reverted_operations.contains(&i))
+        {
+            return Err(format!("Commit '{}' is already reverted", self.commit_id).into());
+        }
+        for op in &bundle.operations[positions] {
+            match op {
+                AnyOperation::Filter(_)
+                | AnyOperation::DeleteRows(_)
+                | AnyOperation::Select(_)
+                | AnyOperation::RemoveColumns(_)
+                | AnyOperation::AddColumn(_)
+                | AnyOperation::CastColumn(_)
+                | AnyOperation::FillNull(_)
+                | AnyOperation::Distinct(_)
+                | AnyOperation::Limit(_)
+                | AnyOperation::OrderBy(_) => {}
+                _ => {
+                    return Err(format!(
+                        "Cannot revert commit '{}': {} can't be undone",
+                        self.commit_id,
+                        op.describe()
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn allowed_on_view(&self) -> bool {
+        false
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        let positions = self.positions(bundle).map_err(DataFusionError::External)?;
+        bundle.reverted_operations.extend(positions);
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        // The reverted operations are skipped when the dataframe is built, see `Bundle::raw_dataframe`
+        Ok(df)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = RevertCommitOp::setup("00002abc");
+        assert_eq!(op.describe(), "REVERT COMMIT: 00002abc");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = RevertCommitOp::setup("00002abc");
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "commitId: 00002abc\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
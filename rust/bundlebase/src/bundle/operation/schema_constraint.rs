@@ -0,0 +1,180 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::prelude::ident;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Declares a constraint on the bundle's schema: either the columns making up the primary key,
+/// or whether a single column may contain nulls.
+///
+/// Constraints don't change the data. They are recorded in the manifest and surfaced as Arrow
+/// field metadata/nullability in `schema()` and the dataframe, and the declared key is used as
+/// the default key by operations such as `merge`. Columns holding nulls can't be declared not
+/// null.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaConstraintOp {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
+}
+
+impl SchemaConstraintOp {
+    pub fn declare_key(columns: Vec<&str>) -> Self {
+        Self {
+            key: Some(columns.iter().map(|c| c.to_string()).collect()),
+            column: None,
+            nullable: None,
+        }
+    }
+
+    pub fn set_nullable(column: &str, nullable: bool) -> Self {
+        Self {
+            key: None,
+            column: Some(column.to_string()),
+            nullable: Some(nullable),
+        }
+    }
+
+    /// Checks that the columns declared not null, including key columns, hold no nulls.
+    /// This scans the data, so it is only done when the constraint is declared rather than in
+    /// `check`, which also runs each time the bundle is opened.
+    pub async fn check_no_nulls(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let columns = match (&self.key, &self.column, self.nullable) {
+            (Some(key), _, _) => key.clone(),
+            (None, Some(column), Some(false)) => vec![column.clone()],
+            _ => return Ok(()),
+        };
+        if bundle.raw_schema().await?.fields().is_empty() {
+            return Ok(());
+        }
+
+        let df = bundle.raw_dataframe().await?;
+        for column in &columns {
+            let nulls = df
+                .as_ref()
+                .clone()
+                .filter(ident(column).is_null())?
+                .count()
+                .await?;
+            if nulls > 0 {
+                return Err(format!(
+                    "Column '{}' has {} null values and cannot be declared not null",
+                    column, nulls
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Operation for SchemaConstraintOp {
+    fn describe(&self) -> String {
+        match (&self.key, &self.column, self.nullable) {
+            (Some(key), _, _) => format!("DECLARE KEY ({})", key.join(", ")),
+            (None, Some(column), Some(false)) => format!("SET COLUMN {} NOT NULL", column),
+            (None, Some(column), _) => format!("SET COLUMN {} NULL", column),
+            (None, None, _) => "SCHEMA CONSTRAINT".to_string(),
+        }
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let columns = match (&self.key, &self.column) {
+            (Some(key), None) => {
+                if key.is_empty() {
+                    return Err("A key must have at least one column".into());
+                }
+                let mut seen = HashSet::new();
+                for column in key {
+                    if !seen.insert(column) {
+                        return Err(format!("Column '{}' is listed twice in key", column).into());
+                    }
+                }
+                key.clone()
+            }
+            (None, Some(column)) if self.nullable.is_some() => {
+                if self.nullable == Some(true) && bundle.key_columns().contains(column) {
+                    return Err(format!(
+                        "Column '{}' is part of the declared key and cannot be nullable",
+                        column
+                    )
+                    .into());
+                }
+                vec![column.clone()]
+            }
+            _ => {
+                return Err(
+                    "Schema constraint must declare either a key or a column's nullability".into(),
+                )
+            }
+        };
+
+        // Constraints can be declared before any data is attached (e.g. from a template)
+        let schema = bundle.raw_schema().await?;
+        if schema.fields().is_empty() {
+            return Ok(());
+        }
+        for column in &columns {
+            if schema.field_with_name(column).is_err() {
+                return Err(format!("Column '{}' not found in bundle", column).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        if let Some(key) = &self.key {
+            bundle.key_columns = key.clone();
+        }
+        if let (Some(column), Some(nullable)) = (&self.column, self.nullable) {
+            bundle.nullable_columns.insert(column.clone(), nullable);
+        }
+        Ok(())
+    }
+
+    fn allowed_on_view(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            "DECLARE KEY (id, region)",
+            SchemaConstraintOp::declare_key(vec!["id", "region"]).describe()
+        );
+        assert_eq!(
+            "SET COLUMN email NOT NULL",
+            SchemaConstraintOp::set_nullable("email", false).describe()
+        );
+        assert_eq!(
+            "SET COLUMN email NULL",
+            SchemaConstraintOp::set_nullable("email", true).describe()
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let key = SchemaConstraintOp::declare_key(vec!["id"]);
+        assert_eq!("key:\n- id\n", serde_yaml::to_string(&key).unwrap());
+
+        let nullable = SchemaConstraintOp::set_nullable("email", false);
+        let serialized = serde_yaml::to_string(&nullable).unwrap();
+        assert_eq!("column: email\nnullable: false\n", serialized);
+
+        let deserialized: SchemaConstraintOp = serde_yaml::from_str(&serialized).unwrap();
+        assert_eq!(nullable, deserialized);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::bundle::operation::Operation;
-use crate::bundle::sql::with_temp_table;
+use crate::bundle::sql::{defined_columns, with_default_from, with_temp_table};
 use crate::metrics::{start_span, OperationCategory, OperationOutcome, OperationTimer};
 use crate::{Bundle, BundlebaseError};
 use async_trait::async_trait;
@@ -191,36 +191,18 @@ impl SelectOp {
     }
 }
 
-#[async_trait]
-impl Operation for SelectOp {
-    fn describe(&self) -> String {
-        format!("{}", self.sql)
-    }
-
-    async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
-        Ok(())
-    }
-
-    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
-        Ok(())
-    }
-
-    async fn apply_dataframe(
+impl SelectOp {
+    /// Build the logical plan for the query over `df`, which is referenced as `bundle` in the SQL
+    async fn plan(
         &self,
         df: DataFrame,
         ctx: Arc<SessionContext>,
     ) -> Result<DataFrame, BundlebaseError> {
-        let mut span = start_span(OperationCategory::Select, "sql");
-        span.set_attribute("sql", &self.sql);
-        span.set_attribute("param_count", &self.parameters.len().to_string());
-
-        let timer = OperationTimer::start(OperationCategory::Select, "sql");
-
         let user_sql = self.sql.clone();
         let parameters = self.parameters.clone();
         let ctx_for_closure = ctx.clone();
 
-        let result = with_temp_table(&ctx, df, |table_name| {
+        with_temp_table(&ctx, df, |table_name| {
             async move {
                 // Substitute parameters into SQL
                 let mut sql = user_sql;
@@ -231,17 +213,66 @@ impl Operation for SelectOp {
                     sql = sql.replace(&placeholder, &value_str);
                 }
 
+                // Queries without a FROM clause select from the bundle
+                sql = with_default_from(&sql, "bundle");
+
                 // Replace "bundle" references with table_name in user SQL
                 sql = sql.replace("bundle", &table_name);
 
-                // Execute the SQL query
+                // Plan the SQL query
                 ctx_for_closure
                     .sql(&sql)
                     .await
                     .map_err(|e| Box::new(e) as BundlebaseError)
             }
         })
-        .await;
+        .await
+    }
+
+    /// Plans the query against the bundle's current data so invalid SQL fails before the
+    /// operation is recorded. This is done when the query is added rather than in `check`, which
+    /// also runs each time the bundle is opened.
+    pub(crate) async fn check_query(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        let df = bundle.raw_dataframe().await?;
+        self.plan(df.as_ref().clone(), bundle.ctx())
+            .await
+            .map_err(|e| format!("Invalid query '{}': {}", self.sql, e))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Operation for SelectOp {
+    fn describe(&self) -> String {
+        self.sql.to_string()
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if bundle.security_filters.is_empty() {
+            return Ok(());
+        }
+        for name in defined_columns(&self.sql)? {
+            bundle.check_not_security_column(&name, "define column")?;
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, _bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let mut span = start_span(OperationCategory::Select, "sql");
+        span.set_attribute("sql", &self.sql);
+        span.set_attribute("param_count", self.parameters.len().to_string());
+
+        let timer = OperationTimer::start(OperationCategory::Select, "sql");
+
+        let result = self.plan(df, ctx).await;
 
         match &result {
             Ok(_) => {
@@ -477,6 +477,25 @@ where
     }
 }
 
+/// Serialize a DataType on its own, in the same format as schema fields' types
+pub fn serialize_type<S>(data_type: &DataType, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_data_type(data_type)
+        .map_err(serde::ser::Error::custom)
+        .and_then(|v| v.serialize(serializer))
+}
+
+/// Deserialize a DataType written by `serialize_type`
+pub fn deserialize_type<'de, D>(deserializer: D) -> Result<DataType, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    deserialize_data_type(&value).map_err(serde::de::Error::custom)
+}
+
 fn deserialize_schema_internal(value: &serde_yaml::Value) -> Result<Arc<Schema>, String> {
     let map = value
         .as_mapping()
@@ -1,4 +1,5 @@
 use crate::bundle::operation::Operation;
+use crate::bundle_config::COLUMN_ENCRYPTION_KEY;
 use crate::{Bundle, BundlebaseError};
 use async_trait::async_trait;
 use datafusion::common::DataFusionError;
@@ -45,7 +46,14 @@ impl SetConfigOp {
 #[async_trait]
 impl Operation for SetConfigOp {
     async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
-        // No validation needed - config values are validated when used
+        // Config values are validated when used, but secrets must not end up in the manifest
+        if self.key == COLUMN_ENCRYPTION_KEY {
+            return Err(format!(
+                "{} cannot be stored in the bundle, pass it in the config when opening the bundle instead",
+                COLUMN_ENCRYPTION_KEY
+            )
+            .into());
+        }
         Ok(())
     }
 
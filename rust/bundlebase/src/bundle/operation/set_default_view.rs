@@ -0,0 +1,89 @@
+use crate::bundle::operation::Operation;
+use crate::data::ObjectId;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use serde::{Deserialize, Serialize};
+
+/// Sets the view applied by default when the bundle is queried, or removes it when `view_id`
+/// is `None`.
+///
+/// The bundle's own operations are unchanged: the view's operations are only layered on top
+/// when the data is read, so callers can still get the raw data with `raw_dataframe()`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDefaultViewOp {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_id: Option<ObjectId>,
+}
+
+impl SetDefaultViewOp {
+    /// Looks up the view by name or ID. `None` removes the default view.
+    pub fn setup(identifier: Option<&str>, bundle: &Bundle) -> Result<Self, BundlebaseError> {
+        let view_id = match identifier {
+            Some(identifier) => Some(bundle.get_view_id_by_name_or_id(identifier)?.0),
+            None => None,
+        };
+        Ok(Self { view_id })
+    }
+}
+
+#[async_trait]
+impl Operation for SetDefaultViewOp {
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if let Some(view_id) = &self.view_id {
+            if !bundle.views.values().any(|id| id == view_id) {
+                return Err(format!("View with ID '{}' not found", view_id).into());
+            }
+        }
+        Ok(())
+    }
+
+    fn allowed_on_view(&self) -> bool {
+        false
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        bundle.default_view = self.view_id;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        match &self.view_id {
+            Some(view_id) => format!("SET DEFAULT VIEW: {}", view_id),
+            None => "REMOVE DEFAULT VIEW".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = SetDefaultViewOp {
+            view_id: Some("a5".try_into().unwrap()),
+        };
+        assert_eq!(op.describe(), "SET DEFAULT VIEW: a5");
+        assert_eq!(
+            SetDefaultViewOp { view_id: None }.describe(),
+            "REMOVE DEFAULT VIEW"
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = SetDefaultViewOp {
+            view_id: Some("a5".try_into().unwrap()),
+        };
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "viewId: a5\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+
+        let op = SetDefaultViewOp { view_id: None };
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "{}\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::bundle::operation::{FilterOp, Operation};
+use crate::bundle::sql;
+use crate::{Bundle, BundlebaseError};
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::prelude::SessionContext;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Adds a row filter that is always applied when the bundle's data is read.
+///
+/// It filters the rows at its place in the operations, so later operations and user SQL only
+/// ever see the rows it lets through. Unlike `FilterOp`, it can't be reverted, and later
+/// operations can't rename, remove, cast, mask, fill or redefine the columns it uses, so what
+/// it filtered on keeps its meaning. Operations before it still see every row.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SetSecurityFilterOp {
+    pub where_clause: String,
+}
+
+impl SetSecurityFilterOp {
+    pub fn setup(where_clause: &str) -> Self {
+        Self {
+            where_clause: where_clause.to_string(),
+        }
+    }
+
+    /// Plans the filter against the bundle's current data so a bad column fails when the filter
+    /// is set, not on read. Like `SelectOp::check_query`, this isn't part of `check` so it doesn't
+    /// run each time the bundle is opened.
+    pub(crate) async fn check_condition(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        sql::referenced_columns(&self.where_clause)?;
+        if bundle.base_pack.is_some() {
+            FilterOp::setup(&self.where_clause, vec![])
+                .await?
+                .apply_dataframe(bundle.raw_dataframe().await?.as_ref().clone(), bundle.ctx())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Operation for SetSecurityFilterOp {
+    async fn check(&self, _bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.where_clause.trim().is_empty() {
+            return Err("Security filter cannot be empty".into());
+        }
+        Ok(())
+    }
+
+    fn allowed_on_view(&self) -> bool {
+        false
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        bundle.security_filters.push(self.where_clause.clone());
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        FilterOp::setup(&self.where_clause, vec![])
+            .await?
+            .apply_dataframe(df, ctx)
+            .await
+    }
+
+    fn describe(&self) -> String {
+        format!("SET SECURITY FILTER: WHERE {}", self.where_clause)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = SetSecurityFilterOp::setup("deleted = false");
+        assert_eq!(op.describe(), "SET SECURITY FILTER: WHERE deleted = false");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = SetSecurityFilterOp::setup("deleted = false");
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "whereClause: deleted = false\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
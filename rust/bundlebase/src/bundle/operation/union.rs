@@ -0,0 +1,276 @@
+use crate::bundle::operation::{ExternalBundle, Operation};
+use crate::bundle::BundleFacade;
+use crate::data::ObjectId;
+use crate::io::ObjectStoreDir;
+use crate::{Bundle, BundlebaseError};
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::logical_expr::{Expr, TableType};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::{ident, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::sync::Arc;
+
+/// Appends the rows of another bundle, read from wherever that bundle keeps its data.
+///
+/// Nothing is copied: the other bundle is opened when a query reads its rows, so they are the
+/// ones of its latest committed version. Operations before the union only apply to this
+/// bundle's own rows, ones after it apply to both.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnionOp {
+    pub id: ObjectId,
+    pub url: String,
+}
+
+impl UnionOp {
+    /// Resolves `url` and checks that bundle has the same columns, of the same types, as `bundle`
+    pub async fn setup(url: &str, bundle: &Bundle) -> Result<Self, BundlebaseError> {
+        let url = ObjectStoreDir::from_str(url, bundle.config())?
+            .url()
+            .to_string();
+        if url == bundle.url().as_str() {
+            return Err(format!("Cannot union bundle {} with itself", url).into());
+        }
+
+        let other = ExternalBundle {
+            url: url.clone(),
+            config: bundle.config(),
+        }
+        .open()
+        .await?;
+        let schema = bundle.raw_schema().await?;
+        let other_schema = other.schema().await?;
+        check_compatible(&url, &schema, &other_schema)?;
+
+        Ok(Self {
+            id: ObjectId::generate(),
+            url,
+        })
+    }
+
+    pub(crate) fn table_name(id: &ObjectId) -> String {
+        format!("__union_{}", id)
+    }
+}
+
+/// Errors unless `other` has exactly the columns of `schema`, in any order, with the same types
+fn check_compatible(url: &str, schema: &Schema, other: &Schema) -> Result<(), BundlebaseError> {
+    for field in schema.fields() {
+        match other.field_with_name(field.name()) {
+            Ok(other_field) if other_field.data_type() != field.data_type() => {
+                return Err(format!(
+                    "Cannot union bundle {}: column '{}' is {} here but {} there",
+                    url,
+                    field.name(),
+                    field.data_type(),
+                    other_field.data_type()
+                )
+                .into())
+            }
+            Ok(_) => {}
+            Err(_) => {
+                return Err(format!(
+                    "Cannot union bundle {}: it has no column '{}'",
+                    url,
+                    field.name()
+                )
+                .into())
+            }
+        }
+    }
+    if let Some(extra) = other
+        .fields()
+        .iter()
+        .find(|f| schema.field_with_name(f.name()).is_err())
+    {
+        return Err(format!(
+            "Cannot union bundle {}: its column '{}' is not in this bundle",
+            url,
+            extra.name()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl Operation for UnionOp {
+    fn describe(&self) -> String {
+        format!("UNION: {}", self.url)
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.url == bundle.url().as_str() {
+            return Err(format!("Cannot union bundle {} with itself", self.url).into());
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        let schema = bundle
+            .raw_dataframe()
+            .await
+            .map_err(DataFusionError::External)?
+            .schema()
+            .as_arrow()
+            .clone();
+        let table = UnionTable {
+            external: ExternalBundle {
+                url: self.url.clone(),
+                config: bundle.config(),
+            },
+            // The other bundle may have nulls where this one doesn't
+            schema: Arc::new(Schema::new(
+                schema
+                    .fields()
+                    .iter()
+                    .map(|f| Field::new(f.name(), f.data_type().clone(), true))
+                    .collect::<Vec<_>>(),
+            )),
+        };
+        bundle.ctx.register_table(
+            format!("unions.{}", UnionOp::table_name(&self.id)),
+            Arc::new(table),
+        )?;
+        // Rows can now repeat a key of this bundle's
+        bundle.key_columns.clear();
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let other = ctx
+            .table(&format!("unions.{}", UnionOp::table_name(&self.id)))
+            .await?;
+        Ok(df.union_by_name(other)?)
+    }
+
+    fn allowed_on_view(&self) -> bool {
+        false
+    }
+}
+
+/// The rows of a bundle added by `UnionOp`, opened each time they are scanned and lined up with
+/// this bundle's columns
+#[derive(Debug)]
+struct UnionTable {
+    external: ExternalBundle,
+    schema: SchemaRef,
+}
+
+#[async_trait]
+impl TableProvider for UnionTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::View
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
+        let bundle = self
+            .external
+            .open()
+            .await
+            .map_err(DataFusionError::External)?;
+        let df = bundle
+            .dataframe()
+            .await
+            .map_err(DataFusionError::External)?;
+        let columns: Vec<_> = self
+            .schema
+            .fields()
+            .iter()
+            .map(|f| ident(f.name()))
+            .collect();
+        df.as_ref()
+            .clone()
+            .select(columns)?
+            .into_view()
+            .scan(state, projection, filters, limit)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::DataType;
+
+    #[test]
+    fn test_describe() {
+        let op = UnionOp {
+            id: 5.into(),
+            url: "memory:///february".to_string(),
+        };
+        assert_eq!(op.describe(), "UNION: memory:///february");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = UnionOp {
+            id: 5.into(),
+            url: "memory:///february".to_string(),
+        };
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(serialized, "id: '05'\nurl: memory:///february\n");
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+
+    #[test]
+    fn test_check_compatible() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let reordered = Schema::new(vec![
+            Field::new("name", DataType::Utf8, true),
+            Field::new("id", DataType::Int64, true),
+        ]);
+        assert!(check_compatible("memory:///b", &schema, &reordered).is_ok());
+
+        let retyped = Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        assert_eq!(
+            "Cannot union bundle memory:///b: column 'id' is Int64 here but Utf8 there",
+            check_compatible("memory:///b", &schema, &retyped)
+                .unwrap_err()
+                .to_string()
+        );
+
+        let missing = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+        assert_eq!(
+            "Cannot union bundle memory:///b: it has no column 'name'",
+            check_compatible("memory:///b", &schema, &missing)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "Cannot union bundle memory:///b: its column 'name' is not in this bundle",
+            check_compatible("memory:///b", &missing, &schema)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
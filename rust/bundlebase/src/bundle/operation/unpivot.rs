@@ -0,0 +1,159 @@
+use crate::bundle::operation::Operation;
+use crate::{Bundle, BundlebaseError};
+use arrow::datatypes::DataType;
+use async_trait::async_trait;
+use datafusion::common::DataFusionError;
+use datafusion::dataframe::DataFrame;
+use datafusion::logical_expr::cast;
+use datafusion::prelude::{ident, lit, SessionContext};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Reshapes wide data to long ("melt"): each row becomes one row per value column, holding the
+/// id columns, the value column's name in `var_name` and its value in `value_name`.
+///
+/// Value columns of differing types are cast to strings so they fit in one column.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnpivotOp {
+    pub id_columns: Vec<String>,
+    pub value_columns: Vec<String>,
+    pub var_name: String,
+    pub value_name: String,
+}
+
+impl UnpivotOp {
+    pub fn setup(
+        id_columns: Vec<&str>,
+        value_columns: Vec<&str>,
+        var_name: &str,
+        value_name: &str,
+    ) -> Self {
+        Self {
+            id_columns: id_columns.iter().map(|c| c.to_string()).collect(),
+            value_columns: value_columns.iter().map(|c| c.to_string()).collect(),
+            var_name: var_name.to_string(),
+            value_name: value_name.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Operation for UnpivotOp {
+    fn describe(&self) -> String {
+        format!(
+            "UNPIVOT: {} INTO {}, {} BY {}",
+            self.value_columns.join(", "),
+            self.var_name,
+            self.value_name,
+            self.id_columns.join(", ")
+        )
+    }
+
+    async fn check(&self, bundle: &Bundle) -> Result<(), BundlebaseError> {
+        if self.value_columns.is_empty() {
+            return Err("Unpivot requires at least one value column".into());
+        }
+        if self.var_name == self.value_name {
+            return Err(format!(
+                "Unpivot name and value columns must differ, both are '{}'",
+                self.var_name
+            )
+            .into());
+        }
+
+        let schema = bundle.raw_schema().await?;
+        for column in self.id_columns.iter().chain(&self.value_columns) {
+            schema.field_with_name(column)?;
+        }
+        if let Some(column) = self
+            .value_columns
+            .iter()
+            .find(|c| self.id_columns.contains(c))
+        {
+            return Err(format!(
+                "Column '{}' cannot be both an id and a value column",
+                column
+            )
+            .into());
+        }
+        if let Some(name) = [&self.var_name, &self.value_name]
+            .into_iter()
+            .find(|name| self.id_columns.contains(name))
+        {
+            return Err(format!("Unpivot column '{}' conflicts with an id column", name).into());
+        }
+        for name in [&self.var_name, &self.value_name] {
+            bundle.check_not_security_column(name, "define column")?;
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, bundle: &mut Bundle) -> Result<(), DataFusionError> {
+        // Each id now appears once per value column, so a key no longer identifies rows
+        bundle.key_columns.clear();
+        for column in &self.value_columns {
+            bundle.nullable_columns.remove(column);
+        }
+        Ok(())
+    }
+
+    async fn apply_dataframe(
+        &self,
+        df: DataFrame,
+        _ctx: Arc<SessionContext>,
+    ) -> Result<DataFrame, BundlebaseError> {
+        let schema = df.schema().clone();
+        let mut types = Vec::with_capacity(self.value_columns.len());
+        for column in &self.value_columns {
+            types.push(
+                schema
+                    .field_with_unqualified_name(column)?
+                    .data_type()
+                    .clone(),
+            );
+        }
+        let value_type = if types.iter().all(|t| t == &types[0]) {
+            types[0].clone()
+        } else {
+            DataType::Utf8
+        };
+
+        let mut result: Option<DataFrame> = None;
+        for column in &self.value_columns {
+            let mut exprs: Vec<_> = self.id_columns.iter().map(ident).collect();
+            exprs.push(lit(column.as_str()).alias(&self.var_name));
+            exprs.push(cast(ident(column), value_type.clone()).alias(&self.value_name));
+
+            let projected = df.clone().select(exprs)?;
+            result = Some(match result {
+                Some(unioned) => unioned.union(projected)?,
+                None => projected,
+            });
+        }
+        Ok(result.expect("Unpivot requires at least one value column"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let op = UnpivotOp::setup(vec!["id"], vec!["q1", "q2"], "quarter", "sales");
+        assert_eq!(op.describe(), "UNPIVOT: q1, q2 INTO quarter, sales BY id");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = UnpivotOp::setup(vec!["id"], vec!["q1", "q2"], "quarter", "sales");
+
+        let serialized = serde_yaml::to_string(&op).expect("Failed to serialize");
+        assert_eq!(
+            serialized,
+            "idColumns:\n- id\nvalueColumns:\n- q1\n- q2\nvarName: quarter\nvalueName: sales\n"
+        );
+        assert_eq!(op, serde_yaml::from_str(&serialized).unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+use crate::io::ObjectId;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// A pack and the blocks attached to it, as listed by `BundleFacade::packs()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackInfo {
+    pub id: ObjectId,
+    pub blocks: Vec<BlockInfo>,
+}
+
+impl PackInfo {
+    /// Total rows across the pack's blocks, if known for all of them
+    pub fn num_rows(&self) -> Option<usize> {
+        self.blocks.iter().map(|b| b.num_rows).sum()
+    }
+
+    /// Total size in bytes of the pack's blocks, if known for all of them
+    pub fn bytes(&self) -> Option<usize> {
+        self.blocks.iter().map(|b| b.bytes).sum()
+    }
+}
+
+/// A block attached to a pack
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockInfo {
+    /// Reproducible, human-readable name (see `block_name`)
+    pub name: String,
+    pub id: ObjectId,
+    /// The source as passed to `attach`
+    pub source: String,
+    /// The file the block reads from
+    pub url: Option<Url>,
+    pub num_rows: Option<usize>,
+    pub bytes: Option<usize>,
+}
+
+/// Name for the `sequence`th (1-based) block of a pack, made of the source's file stem, a hash
+/// of the source and its version, and the sequence, e.g. `userdata-1a2b3c4d-1`.
+///
+/// Attaching the same unchanged file in the same position always gives the same name.
+pub(crate) fn block_name(source: &str, version: &str, sequence: usize) -> String {
+    let file_name = source
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or("");
+    let stem: String = file_name
+        .split('.')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let stem = stem.trim_matches('-');
+
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(version.as_bytes());
+    let hash = hex::encode(&hasher.finalize()[..4]);
+
+    format!(
+        "{}-{}-{}",
+        if stem.is_empty() { "block" } else { stem },
+        hash,
+        sequence
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_name() {
+        let name = block_name("memory:///test_data/userdata.parquet", "v1", 1);
+        assert!(name.starts_with("userdata-"), "{}", name);
+        assert!(name.ends_with("-1"), "{}", name);
+        assert_eq!(
+            name,
+            block_name("memory:///test_data/userdata.parquet", "v1", 1)
+        );
+
+        // A changed file or position gets a different name
+        assert_ne!(
+            name,
+            block_name("memory:///test_data/userdata.parquet", "v2", 1)
+        );
+        assert_ne!(
+            name,
+            block_name("memory:///test_data/userdata.parquet", "v1", 2)
+        );
+
+        assert!(block_name("s3://bucket/Sales 2024.csv", "v1", 3).starts_with("sales-2024-"));
+        assert!(block_name("memory:///.hidden", "v1", 1).starts_with("block-"));
+    }
+
+    #[test]
+    fn test_pack_totals() {
+        let block = |num_rows, bytes| BlockInfo {
+            name: "b".to_string(),
+            id: ObjectId::from(1),
+            source: "b.csv".to_string(),
+            url: None,
+            num_rows,
+            bytes,
+        };
+        let pack = PackInfo {
+            id: ObjectId::from(2),
+            blocks: vec![block(Some(10), Some(100)), block(Some(5), None)],
+        };
+        assert_eq!(Some(15), pack.num_rows());
+        assert_eq!(None, pack.bytes());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::io::ObjectStoreFile;
+use crate::BundlebaseError;
+use arrow::array::RecordBatch;
+use datafusion::dataframe::DataFrame;
+use futures::StreamExt;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Writes the dataframe to `file` as parquet, one batch at a time. Returns the number of rows
+/// written.
+///
+/// Each row group is uploaded as soon as the writer finishes it, so only one is held in memory.
+pub(crate) async fn write_parquet(
+    df: DataFrame,
+    file: &ObjectStoreFile,
+    properties: WriterProperties,
+) -> Result<u64, BundlebaseError> {
+    let schema = Arc::new(df.schema().as_arrow().clone());
+    let writer = ArrowWriter::try_new(Vec::new(), schema, Some(properties))?;
+    let rows = Arc::new(AtomicU64::new(0));
+    let counter = rows.clone();
+
+    let batches = df.execute_stream().await?;
+    let stream =
+        futures::stream::try_unfold((batches, Some(writer)), move |(mut batches, writer)| {
+            let counter = counter.clone();
+            async move {
+                let Some(mut writer) = writer else {
+                    return Ok(None);
+                };
+                match batches.next().await {
+                    Some(batch) => {
+                        let batch = batch.map_err(std::io::Error::other)?;
+                        counter.fetch_add(batch.num_rows() as u64, Ordering::Relaxed);
+                        writer.write(&batch).map_err(std::io::Error::other)?;
+                        let written = std::mem::take(writer.inner_mut());
+                        Ok(Some((bytes::Bytes::from(written), (batches, Some(writer)))))
+                    }
+                    None => {
+                        // Writes the last row group and the footer
+                        writer.finish().map_err(std::io::Error::other)?;
+                        let written = std::mem::take(writer.inner_mut());
+                        Ok(Some((bytes::Bytes::from(written), (batches, None))))
+                    }
+                }
+            }
+        });
+    file.write_stream(Box::pin(stream)).await?;
+
+    Ok(rows.load(Ordering::Relaxed))
+}
+
+/// Writes the dataframe as parquet split across files of at most `rows_per_file` rows each,
+/// created with `next_file` as they are needed. Returns each file with the number of rows
+/// written to it, in the order the rows were read.
+///
+/// A file is only started once there is a row for it, except that an empty dataframe is still
+/// written as one file so its schema is kept. Like `write_parquet`, only one row group per file
+/// is held in memory.
+pub(crate) async fn write_parquet_files<F>(
+    df: DataFrame,
+    rows_per_file: usize,
+    properties: WriterProperties,
+    mut next_file: F,
+) -> Result<Vec<(ObjectStoreFile, u64)>, BundlebaseError>
+where
+    F: FnMut() -> Result<ObjectStoreFile, BundlebaseError>,
+{
+    let schema = Arc::new(df.schema().as_arrow().clone());
+    let mut batches = df.execute_stream().await?;
+    // Rows read but not yet written, left over when a batch spans two files
+    let mut pending: Option<RecordBatch> = None;
+    let mut files = Vec::new();
+
+    loop {
+        while pending.as_ref().is_none_or(|batch| batch.num_rows() == 0) {
+            match batches.next().await {
+                Some(batch) => pending = Some(batch?),
+                None => break,
+            }
+        }
+        let has_rows = pending.is_some();
+        if !has_rows && !files.is_empty() {
+            return Ok(files);
+        }
+
+        let file = next_file()?;
+        let writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(properties.clone()))?;
+        let rows = Arc::new(AtomicU64::new(0));
+        let counter = rows.clone();
+        let stream = futures::stream::try_unfold(
+            (&mut batches, &mut pending, Some(writer)),
+            move |(batches, pending, writer)| {
+                let counter = counter.clone();
+                async move {
+                    let Some(mut writer) = writer else {
+                        return Ok(None);
+                    };
+                    let written = counter.load(Ordering::Relaxed) as usize;
+                    let batch = match pending.take() {
+                        Some(batch) if written < rows_per_file => Some(batch),
+                        Some(batch) => {
+                            *pending = Some(batch);
+                            None
+                        }
+                        None if written < rows_per_file => batches
+                            .next()
+                            .await
+                            .transpose()
+                            .map_err(std::io::Error::other)?,
+                        None => None,
+                    };
+                    match batch {
+                        Some(batch) => {
+                            let take = batch.num_rows().min(rows_per_file - written);
+                            if take < batch.num_rows() {
+                                *pending = Some(batch.slice(take, batch.num_rows() - take));
+                            }
+                            counter.fetch_add(take as u64, Ordering::Relaxed);
+                            writer
+                                .write(&batch.slice(0, take))
+                                .map_err(std::io::Error::other)?;
+                            let written = std::mem::take(writer.inner_mut());
+                            Ok(Some((
+                                bytes::Bytes::from(written),
+                                (batches, pending, Some(writer)),
+                            )))
+                        }
+                        None => {
+                            // Writes the last row group and the footer
+                            writer.finish().map_err(std::io::Error::other)?;
+                            let written = std::mem::take(writer.inner_mut());
+                            Ok(Some((
+                                bytes::Bytes::from(written),
+                                (batches, pending, None),
+                            )))
+                        }
+                    }
+                }
+            },
+        );
+        file.write_stream(Box::pin(stream)).await?;
+        files.push((file, rows.load(Ordering::Relaxed)));
+
+        if !has_rows {
+            return Ok(files);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle_config::{BundleConfig, PARQUET_ROW_GROUP_SIZE};
+    use crate::test_utils::random_memory_file;
+    use arrow::array::{record_batch, AsArray};
+    use arrow::datatypes::Int64Type;
+    use datafusion::prelude::SessionContext;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[tokio::test]
+    async fn test_write_parquet() {
+        let batch = record_batch!(("id", Int64, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9])).unwrap();
+        let df = SessionContext::new().read_batch(batch).unwrap();
+
+        let mut config = BundleConfig::new();
+        config.set(PARQUET_ROW_GROUP_SIZE, "4", None);
+        let file = random_memory_file("rows.parquet");
+        let rows = write_parquet(df, &file, config.parquet_writer_properties().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(10, rows);
+
+        let contents = file.read_bytes().await.unwrap().unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(contents).unwrap();
+        assert_eq!(3, reader.metadata().num_row_groups());
+        let ids: Vec<i64> = reader
+            .build()
+            .unwrap()
+            .flat_map(|b| {
+                b.unwrap()
+                    .column(0)
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!((0..10).collect::<Vec<i64>>(), ids);
+    }
+
+    #[tokio::test]
+    async fn test_write_parquet_files() {
+        let batch = record_batch!(("id", Int64, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9])).unwrap();
+        let ctx = SessionContext::new();
+        let df = ctx.read_batch(batch).unwrap();
+
+        let mut config = BundleConfig::new();
+        config.set(PARQUET_ROW_GROUP_SIZE, "2", None);
+        let files = write_parquet_files(df, 4, config.parquet_writer_properties().unwrap(), || {
+            Ok(random_memory_file("part.parquet"))
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            vec![4, 4, 2],
+            files.iter().map(|(_, rows)| *rows).collect::<Vec<_>>()
+        );
+
+        let mut ids = Vec::new();
+        for (file, _) in &files {
+            let contents = file.read_bytes().await.unwrap().unwrap();
+            let reader = ParquetRecordBatchReaderBuilder::try_new(contents).unwrap();
+            assert!(reader.metadata().num_row_groups() <= 2);
+            for batch in reader.build().unwrap() {
+                ids.extend_from_slice(
+                    batch
+                        .unwrap()
+                        .column(0)
+                        .as_primitive::<Int64Type>()
+                        .values(),
+                );
+            }
+        }
+        assert_eq!((0..10).collect::<Vec<i64>>(), ids);
+
+        // An empty dataframe is still written, so its schema is kept
+        let empty = ctx
+            .read_batch(record_batch!(("id", Int64, [0])).unwrap())
+            .unwrap()
+            .limit(0, Some(0))
+            .unwrap();
+        let files = write_parquet_files(
+            empty,
+            4,
+            config.parquet_writer_properties().unwrap(),
+            || Ok(random_memory_file("empty.parquet")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(1, files.len());
+        assert_eq!(0, files[0].1);
+    }
+}
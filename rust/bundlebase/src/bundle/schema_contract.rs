@@ -0,0 +1,166 @@
+use crate::BundlebaseError;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A column of an expected schema as written in a schema JSON file
+#[derive(Debug, Serialize, Deserialize)]
+struct ContractField {
+    name: String,
+    /// The Arrow type as displayed by `SCHEMA`, e.g. `Int64` or `Utf8`
+    #[serde(rename = "type")]
+    data_type: String,
+    #[serde(default = "default_nullable")]
+    nullable: bool,
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Contract {
+    fields: Vec<ContractField>,
+}
+
+/// Parses an expected schema from JSON such as
+/// `{"fields": [{"name": "id", "type": "Int64", "nullable": false}]}`.
+/// `nullable` defaults to true.
+pub fn schema_from_json(json: &str) -> Result<SchemaRef, BundlebaseError> {
+    let contract: Contract =
+        serde_json::from_str(json).map_err(|e| format!("Invalid schema JSON: {}", e))?;
+    let fields = contract
+        .fields
+        .into_iter()
+        .map(|field| {
+            let data_type = DataType::from_str(&field.data_type).map_err(|e| {
+                format!(
+                    "Invalid type '{}' for column '{}': {}",
+                    field.data_type, field.name, e
+                )
+            })?;
+            Ok(Field::new(field.name, data_type, field.nullable))
+        })
+        .collect::<Result<Vec<_>, BundlebaseError>>()?;
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Writes `schema` in the format read by `schema_from_json`, to start a schema contract from a
+/// bundle as it is now
+pub fn schema_to_json(schema: &Schema) -> Result<String, BundlebaseError> {
+    let contract = Contract {
+        fields: schema
+            .fields()
+            .iter()
+            .map(|field| ContractField {
+                name: field.name().clone(),
+                data_type: field.data_type().to_string(),
+                nullable: field.is_nullable(),
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&contract)?)
+}
+
+/// Every difference between `actual` and `expected` in column names, types and nullability,
+/// in the order of the expected columns followed by unexpected ones
+pub(crate) fn schema_differences(expected: &Schema, actual: &Schema) -> Vec<String> {
+    let mut differences = vec![];
+    for field in expected.fields() {
+        let Ok(found) = actual.field_with_name(field.name()) else {
+            differences.push(format!(
+                "missing column '{}' ({})",
+                field.name(),
+                field.data_type()
+            ));
+            continue;
+        };
+        if found.data_type() != field.data_type() {
+            differences.push(format!(
+                "column '{}' is {}, expected {}",
+                field.name(),
+                found.data_type(),
+                field.data_type()
+            ));
+        }
+        if found.is_nullable() != field.is_nullable() {
+            differences.push(format!(
+                "column '{}' is {}, expected {}",
+                field.name(),
+                nullability(found.is_nullable()),
+                nullability(field.is_nullable())
+            ));
+        }
+    }
+    for field in actual.fields() {
+        if expected.field_with_name(field.name()).is_err() {
+            differences.push(format!(
+                "unexpected column '{}' ({})",
+                field.name(),
+                field.data_type()
+            ));
+        }
+    }
+    differences
+}
+
+fn nullability(nullable: bool) -> &'static str {
+    if nullable {
+        "nullable"
+    } else {
+        "not nullable"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let json = schema_to_json(&schema).unwrap();
+        assert_eq!(schema, *schema_from_json(&json).unwrap());
+
+        let parsed = schema_from_json(r#"{"fields": [{"name": "id", "type": "Int64"}]}"#).unwrap();
+        assert!(parsed.field(0).is_nullable());
+        let err = schema_from_json(r#"{"fields": [{"name": "id", "type": "Integer"}]}"#)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(
+            err.starts_with("Invalid type 'Integer' for column 'id': "),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_schema_differences() {
+        let expected = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("email", DataType::Utf8, true),
+        ]);
+        assert!(schema_differences(&expected, &expected).is_empty());
+
+        let actual = Schema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("phone", DataType::Utf8, true),
+        ]);
+        assert_eq!(
+            vec![
+                "column 'id' is Utf8, expected Int64",
+                "column 'id' is nullable, expected not nullable",
+                "missing column 'email' (Utf8)",
+                "unexpected column 'phone' (Utf8)",
+            ],
+            schema_differences(&expected, &actual)
+        );
+    }
+}
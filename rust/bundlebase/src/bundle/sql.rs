@@ -1,4 +1,5 @@
 use crate::bundle::JoinTypeOption;
+use crate::catalog::ConstrainedTable;
 use crate::data::{DataBlock, DataPack, PackJoin};
 use crate::{catalog, BundlebaseError};
 use datafusion::common::DataFusionError;
@@ -7,6 +8,14 @@ use datafusion::logical_expr::{Expr, LogicalPlan, Operator};
 use datafusion::prelude::Expr::BinaryExpr;
 use datafusion::prelude::SessionContext;
 use datafusion::sql::TableReference;
+use sqlparser::ast::{
+    visit_expressions, Expr as SqlExpr, Query, RenameSelectItem, Select, SelectItem, SetExpr,
+    Statement, TableAlias, TableFactor, Visit, Visitor,
+};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
 use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, OnceLock};
 
@@ -172,6 +181,10 @@ fn find_orig(plan: &LogicalPlan, target: &str, sources: &mut Vec<(String, String
 
     // For TableScan nodes, add the table and column directly
     if let LogicalPlan::TableScan(scan) = plan {
+        if let Some(wrapped) = ConstrainedTable::wrapped_plan(scan) {
+            find_orig(&wrapped, target, sources);
+            return;
+        }
         let table_name = scan.table_name.to_string();
         // Check if the column is in the projected schema
         for field in scan.projected_schema.fields() {
@@ -264,6 +277,36 @@ pub(crate) async fn parse_join_expr(
     Ok(preds)
 }
 
+/// Names of the base table's columns compared for equality with the joined pack in `join`'s
+/// expression, in the order they appear. Keys computed from an expression aren't included.
+pub(crate) async fn join_key_columns(
+    ctx: &SessionContext,
+    table: &str,
+    join: &PackJoin,
+) -> Result<Vec<String>, DataFusionError> {
+    let mut columns: Vec<String> = vec![];
+    for pred in parse_join_expr(ctx, table, join).await? {
+        let BinaryExpr(binary) = pred else {
+            continue;
+        };
+        if binary.op != Operator::Eq {
+            continue;
+        }
+        for side in [binary.left.as_ref(), binary.right.as_ref()] {
+            if let Expr::Column(column) = side {
+                let joined = column
+                    .relation
+                    .as_ref()
+                    .is_some_and(|relation| relation.table() == join.name());
+                if !joined && !columns.contains(&column.name) {
+                    columns.push(column.name.clone());
+                }
+            }
+        }
+    }
+    Ok(columns)
+}
+
 fn collect_join_exprs(plan: &LogicalPlan, out: &mut Vec<Expr>) {
     match plan {
         LogicalPlan::Join(filter) => {
@@ -288,6 +331,168 @@ fn collect_join_exprs(plan: &LogicalPlan, out: &mut Vec<Expr>) {
     }
 }
 
+/// Adds the "SELECT" keyword to a query that omits it. Queries starting with `WITH` are left as is.
+pub(crate) fn with_select_keyword(sql: &str) -> String {
+    let lower = sql.trim_start().to_lowercase();
+    if !lower.starts_with("select ") && !lower.starts_with("with ") {
+        format!("SELECT {}", sql)
+    } else {
+        sql.to_string()
+    }
+}
+
+/// Makes a query without a FROM clause, such as `SELECT * WHERE x > 1`, select from `table`.
+/// Anything else, including SQL that doesn't parse, is returned unchanged.
+pub(crate) fn with_default_from(sql: &str, table: &str) -> String {
+    fn select_mut(statement: &mut Statement) -> Option<&mut Select> {
+        match statement {
+            Statement::Query(query) => match query.body.as_mut() {
+                SetExpr::Select(select) => Some(select.as_mut()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    let dialect = GenericDialect {};
+    let (Ok(mut statements), Ok(mut default)) = (
+        Parser::parse_sql(&dialect, sql),
+        Parser::parse_sql(&dialect, &format!("SELECT * FROM {}", table)),
+    ) else {
+        return sql.to_string();
+    };
+    if statements.len() != 1 {
+        return sql.to_string();
+    }
+    match (
+        select_mut(&mut statements[0]),
+        default.first_mut().and_then(select_mut),
+    ) {
+        (Some(select), Some(default)) if select.from.is_empty() => {
+            select.from = std::mem::take(&mut default.from);
+            statements[0].to_string()
+        }
+        _ => sql.to_string(),
+    }
+}
+
+/// Names of the columns a SQL condition, such as a WHERE clause, refers to. Qualified names like
+/// `t.deleted` are taken by their last part.
+pub(crate) fn referenced_columns(condition: &str) -> Result<HashSet<String>, BundlebaseError> {
+    let expr = Parser::new(&GenericDialect {})
+        .try_with_sql(condition)?
+        .parse_expr()?;
+    let mut columns = HashSet::new();
+    let _ = visit_expressions(&expr, |expr| {
+        match expr {
+            SqlExpr::Identifier(ident) => {
+                columns.insert(ident.value.clone());
+            }
+            SqlExpr::CompoundIdentifier(parts) => {
+                if let Some(last) = parts.last() {
+                    columns.insert(last.value.clone());
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    Ok(columns)
+}
+
+/// Names of the columns a query gives a value of its own, at any depth: aliased expressions,
+/// `SELECT * REPLACE` and `RENAME` targets, and the column lists of table aliases and CTEs.
+/// Selecting a column under its own name passes it through, so doesn't count.
+pub(crate) fn defined_columns(sql: &str) -> Result<HashSet<String>, BundlebaseError> {
+    #[derive(Default)]
+    struct Defined(HashSet<String>);
+
+    impl Defined {
+        fn alias_columns(&mut self, alias: &TableAlias) {
+            self.0
+                .extend(alias.columns.iter().map(|column| column.name.value.clone()));
+        }
+
+        fn body(&mut self, body: &SetExpr) {
+            match body {
+                SetExpr::Select(select) => {
+                    for item in &select.projection {
+                        match item {
+                            SelectItem::ExprWithAlias { expr, alias } => {
+                                let passed_through = match expr {
+                                    SqlExpr::Identifier(ident) => ident.value == alias.value,
+                                    SqlExpr::CompoundIdentifier(parts) => {
+                                        parts.last().is_some_and(|p| p.value == alias.value)
+                                    }
+                                    _ => false,
+                                };
+                                if !passed_through {
+                                    self.0.insert(alias.value.clone());
+                                }
+                            }
+                            SelectItem::Wildcard(options)
+                            | SelectItem::QualifiedWildcard(_, options) => {
+                                if let Some(replace) = &options.opt_replace {
+                                    self.0.extend(
+                                        replace.items.iter().map(|r| r.column_name.value.clone()),
+                                    );
+                                }
+                                match &options.opt_rename {
+                                    Some(RenameSelectItem::Single(rename)) => {
+                                        self.0.insert(rename.alias.value.clone());
+                                    }
+                                    Some(RenameSelectItem::Multiple(renames)) => {
+                                        self.0
+                                            .extend(renames.iter().map(|r| r.alias.value.clone()));
+                                    }
+                                    None => {}
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                SetExpr::SetOperation { left, right, .. } => {
+                    self.body(left);
+                    self.body(right);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    impl Visitor for Defined {
+        type Break = ();
+
+        fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+            if let Some(with) = &query.with {
+                for cte in &with.cte_tables {
+                    self.alias_columns(&cte.alias);
+                }
+            }
+            self.body(&query.body);
+            ControlFlow::Continue(())
+        }
+
+        fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+            match table_factor {
+                TableFactor::Table {
+                    alias: Some(alias), ..
+                }
+                | TableFactor::Derived {
+                    alias: Some(alias), ..
+                } => self.alias_columns(alias),
+                _ => {}
+            }
+            ControlFlow::Continue(())
+        }
+    }
+
+    let mut defined = Defined::default();
+    let _ = Parser::parse_sql(&GenericDialect {}, sql)?.visit(&mut defined);
+    Ok(defined.0)
+}
+
 /// Execute a closure with a temporary table registered in the temp schema.
 ///
 /// This helper handles the common pattern of:
@@ -369,6 +574,67 @@ mod tests {
     use datafusion::datasource::empty::EmptyTable;
     use std::sync::Arc;
 
+    #[test]
+    fn test_referenced_columns() {
+        let columns = referenced_columns("deleted = false AND t.\"Tenant\" IN (1, 2)").unwrap();
+        assert_eq!(
+            HashSet::from(["deleted".to_string(), "Tenant".to_string()]),
+            columns
+        );
+        assert!(referenced_columns("deleted = ").is_err());
+    }
+
+    #[test]
+    fn test_defined_columns() {
+        let defined = |sql: &str| {
+            let mut columns: Vec<String> = defined_columns(sql).unwrap().into_iter().collect();
+            columns.sort();
+            columns
+        };
+        assert!(defined("SELECT *, deleted AS deleted, b.x AS x FROM bundle b").is_empty());
+        assert_eq!(
+            vec!["deleted", "n"],
+            defined("SELECT count(*) AS n, false AS deleted FROM bundle")
+        );
+        assert_eq!(
+            vec!["deleted"],
+            defined("SELECT deleted FROM (SELECT id, NOT deleted AS deleted FROM bundle)")
+        );
+        assert_eq!(
+            vec!["deleted", "id"],
+            defined("WITH t(id, deleted) AS (SELECT id, x FROM bundle) SELECT * FROM t")
+        );
+        assert_eq!(
+            vec!["deleted", "id"],
+            defined("SELECT * FROM bundle AS b(id, deleted)")
+        );
+        assert_eq!(
+            vec!["x"],
+            defined("SELECT id FROM bundle UNION ALL SELECT 1 AS x FROM bundle")
+        );
+    }
+
+    #[test]
+    fn test_with_default_from() {
+        assert_eq!(
+            "SELECT * FROM bundle WHERE \"Index\" > 50",
+            with_default_from("select * where \"Index\" > 50", "bundle")
+        );
+        assert_eq!(
+            "SELECT * FROM bundle LIMIT 5",
+            with_default_from("select * limit 5", "bundle")
+        );
+        // Queries with a FROM clause, and ones that don't parse, are left as written
+        assert_eq!(
+            "select * from other where x > 1",
+            with_default_from("select * from other where x > 1", "bundle")
+        );
+        assert_eq!(
+            "select * where",
+            with_default_from("select * where", "bundle")
+        );
+    }
+
     #[tokio::test]
     async fn test_parse_join() {
         let ctx = SessionContext::new();
@@ -0,0 +1,382 @@
+use crate::bundle::operation::{
+    substitute_parameters, AnyOperation, JoinTypeOption, MaskStrategy, Operation, COUNT_ALL,
+};
+use crate::bundle::{scalar_value_to_sql_literal, BundleFacade};
+use crate::data::ObjectId;
+use crate::io::ObjectStoreFile;
+use crate::Bundle;
+use datafusion::scalar::ScalarValue;
+use std::collections::HashMap;
+
+/// Lowers a bundle's operation chain into a single SQL query over the attached source files.
+///
+/// Each attached pack becomes a `SELECT * FROM '<url>'` (combined with `UNION ALL BY NAME` when
+/// it has several files), and each data-changing operation becomes a CTE reading from the
+/// previous one. Joins are applied before the other operations, matching how the bundle
+/// builds its dataframe. Operations without a SQL equivalent are listed as comments at the top,
+/// and operations that only change metadata (name, indexes, views, ...) are left out.
+///
+/// Source URLs are queried as URL tables, so the SQL runs as-is in a DataFusion session with
+/// `enable_url_table()` and the matching object stores registered.
+pub(crate) fn bundle_to_sql(bundle: &Bundle) -> String {
+    let operations = bundle.operations();
+
+    let mut sources: HashMap<ObjectId, Vec<String>> = HashMap::new();
+    for op in &operations {
+        if let AnyOperation::AttachBlock(attach) = op {
+            let url = ObjectStoreFile::from_str(&attach.source, bundle.data_dir(), bundle.config())
+                .map(|file| file.url().to_string())
+                .unwrap_or_else(|_| attach.source.clone());
+            sources.entry(attach.pack_id).or_default().push(url);
+        }
+    }
+
+    let mut notes = Vec::new();
+    let mut ctes: Vec<(String, String)> = Vec::new();
+
+    match bundle.base_pack.and_then(|pack| sources.get(&pack)) {
+        Some(files) => ctes.push(("base".to_string(), union_sql(files, &mut notes))),
+        None => {
+            return format!(
+                "-- Bundle {} has no attached data\nSELECT NULL WHERE false",
+                bundle.url()
+            )
+        }
+    }
+
+    for op in &operations {
+        if let AnyOperation::Join(join) = op {
+            let previous = &ctes[ctes.len() - 1].0;
+            let join_type = match join.join_type {
+                JoinTypeOption::Inner => "INNER JOIN",
+                JoinTypeOption::Left => "LEFT JOIN",
+                JoinTypeOption::Right => "RIGHT JOIN",
+                JoinTypeOption::Full => "FULL OUTER JOIN",
+            };
+            let joined = sources
+                .get(&join.pack_id)
+                .map(|files| union_sql(files, &mut notes))
+                .unwrap_or_else(|| "SELECT NULL WHERE false".to_string());
+            let sql = format!(
+                "SELECT * FROM {previous} {join_type} ({joined}) AS {} ON {}",
+                join.name,
+                join.expression.replace("$base", previous)
+            );
+            ctes.push((format!("join_{}", join.name), sql));
+        }
+    }
+
+    for (i, op) in operations.iter().enumerate() {
+        if bundle.reverted_operations.contains(&i) {
+            continue;
+        }
+        let previous = ctes[ctes.len() - 1].0.clone();
+        let sql = match op {
+            AnyOperation::Filter(filter) => format!(
+                "SELECT * FROM {previous} WHERE {}",
+                substitute_parameters(&filter.where_clause, &filter.parameters)
+            ),
+            AnyOperation::SetSecurityFilter(security) => {
+                format!("SELECT * FROM {previous} WHERE {}", security.where_clause)
+            }
+            AnyOperation::DeleteRows(delete) => format!(
+                "SELECT * FROM {previous} WHERE NOT COALESCE(({}), false)",
+                substitute_parameters(&delete.where_clause, &delete.parameters)
+            ),
+            AnyOperation::Select(select) => {
+                let mut sql = select.sql.clone();
+                for (i, param) in select.parameters.iter().enumerate() {
+                    sql = sql.replace(
+                        &format!("${}", i + 1),
+                        &scalar_value_to_sql_literal(&param.to_scalar_value()),
+                    );
+                }
+                sql.replace("bundle", &previous)
+            }
+            AnyOperation::RemoveColumns(remove) => format!(
+                "SELECT * EXCLUDE ({}) FROM {previous}",
+                remove
+                    .names
+                    .iter()
+                    .map(|name| quote_identifier(name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            AnyOperation::RenameColumn(rename) => {
+                notes.push(format!(
+                    "{}: the renamed column is moved to the end of the schema",
+                    op.describe()
+                ));
+                format!(
+                    "SELECT * EXCLUDE ({old}), {old} AS {new} FROM {previous}",
+                    old = quote_identifier(&rename.old_name),
+                    new = quote_identifier(&rename.new_name)
+                )
+            }
+            AnyOperation::ColumnAlias(alias) => {
+                notes.push(format!(
+                    "{}: the aliased column is moved to the end of the schema",
+                    op.describe()
+                ));
+                format!(
+                    "SELECT * EXCLUDE ({old}), {old} AS {new} FROM {previous}",
+                    old = quote_identifier(&alias.original),
+                    new = quote_identifier(&alias.alias)
+                )
+            }
+            AnyOperation::AddColumn(add) => format!(
+                "SELECT *, {} AS {} FROM {previous}",
+                add.expression,
+                quote_identifier(&add.name)
+            ),
+            AnyOperation::CastColumn(cast) => {
+                notes.push(format!(
+                    "{}: the converted column is moved to the end of the schema",
+                    op.describe()
+                ));
+                if cast.safe {
+                    notes.push(format!(
+                        "{}: values that can't be converted fail the query instead of becoming null",
+                        op.describe()
+                    ));
+                }
+                format!(
+                    "SELECT * EXCLUDE ({column}), arrow_cast({column}, {data_type}) AS {column} FROM {previous}",
+                    column = quote_identifier(&cast.column),
+                    data_type =
+                        scalar_value_to_sql_literal(&ScalarValue::from(cast.data_type.to_string()))
+                )
+            }
+            AnyOperation::FillNull(fill) => {
+                notes.push(format!(
+                    "{}: the filled column is moved to the end of the schema",
+                    op.describe()
+                ));
+                // Numbers and booleans are left unquoted so they keep the column's type
+                let value = if fill.value.parse::<f64>().is_ok()
+                    || fill.value.eq_ignore_ascii_case("true")
+                    || fill.value.eq_ignore_ascii_case("false")
+                {
+                    fill.value.clone()
+                } else {
+                    scalar_value_to_sql_literal(&ScalarValue::from(fill.value.as_str()))
+                };
+                format!(
+                    "SELECT * EXCLUDE ({column}), coalesce({column}, {value}) AS {column} FROM {previous}",
+                    column = quote_identifier(&fill.column)
+                )
+            }
+            AnyOperation::MaskColumn(mask) => {
+                notes.push(format!(
+                    "{}: the masked column is moved to the end of the schema",
+                    op.describe()
+                ));
+                let masked = match mask.strategy {
+                    MaskStrategy::Hash => format!(
+                        "encode(sha256(CAST({} AS VARCHAR)), 'hex')",
+                        quote_identifier(&mask.column)
+                    ),
+                    MaskStrategy::Null | MaskStrategy::Encrypt => "NULL".to_string(),
+                };
+                format!(
+                    "SELECT * EXCLUDE ({column}), {masked} AS {column} FROM {previous}",
+                    column = quote_identifier(&mask.column)
+                )
+            }
+            AnyOperation::Unpivot(unpivot) => unpivot
+                .value_columns
+                .iter()
+                .map(|column| {
+                    let mut exprs: Vec<String> = unpivot
+                        .id_columns
+                        .iter()
+                        .map(|c| quote_identifier(c))
+                        .collect();
+                    exprs.push(format!(
+                        "{} AS {}",
+                        scalar_value_to_sql_literal(&ScalarValue::from(column.as_str())),
+                        quote_identifier(&unpivot.var_name)
+                    ));
+                    exprs.push(format!(
+                        "{} AS {}",
+                        quote_identifier(column),
+                        quote_identifier(&unpivot.value_name)
+                    ));
+                    format!("SELECT {} FROM {previous}", exprs.join(", "))
+                })
+                .collect::<Vec<_>>()
+                .join(" UNION ALL "),
+            AnyOperation::Pivot(pivot) => {
+                let index: Vec<String> = pivot.index.iter().map(|c| quote_identifier(c)).collect();
+                let mut exprs = index.clone();
+                for value in &pivot.pivot_values {
+                    exprs.push(format!(
+                        "{}(CASE WHEN CAST({} AS VARCHAR) = {} THEN {} END) AS {}",
+                        pivot.aggregate,
+                        quote_identifier(&pivot.columns),
+                        scalar_value_to_sql_literal(&ScalarValue::from(value.as_str())),
+                        quote_identifier(&pivot.values),
+                        quote_identifier(value)
+                    ));
+                }
+                let mut sql = format!("SELECT {} FROM {previous}", exprs.join(", "));
+                if !index.is_empty() {
+                    sql.push_str(&format!(" GROUP BY {}", index.join(", ")));
+                }
+                sql
+            }
+            AnyOperation::Aggregate(aggregate) => {
+                let group_by: Vec<String> = aggregate
+                    .group_by
+                    .iter()
+                    .map(|c| quote_identifier(c))
+                    .collect();
+                let mut exprs = group_by.clone();
+                for a in &aggregate.aggregates {
+                    let column = if a.column == COUNT_ALL {
+                        COUNT_ALL.to_string()
+                    } else {
+                        quote_identifier(&a.column)
+                    };
+                    exprs.push(format!(
+                        "{}({}) AS {}",
+                        a.function,
+                        column,
+                        quote_identifier(&a.alias)
+                    ));
+                }
+                let mut sql = format!("SELECT {} FROM {previous}", exprs.join(", "));
+                if !group_by.is_empty() {
+                    sql.push_str(&format!(" GROUP BY {}", group_by.join(", ")));
+                }
+                sql
+            }
+            AnyOperation::OrderBy(order_by) => {
+                let keys: Vec<String> = order_by
+                    .keys
+                    .iter()
+                    .map(|k| format!("{} {}", quote_identifier(&k.column), k.direction))
+                    .collect();
+                format!("SELECT * FROM {previous} ORDER BY {}", keys.join(", "))
+            }
+            AnyOperation::Limit(limit) => {
+                format!("SELECT * FROM {previous} LIMIT {}", limit.count)
+            }
+            AnyOperation::Distinct(distinct) => match &distinct.subset {
+                Some(subset) => {
+                    let on: Vec<String> = subset.iter().map(|c| quote_identifier(c)).collect();
+                    format!("SELECT DISTINCT ON ({}) * FROM {previous}", on.join(", "))
+                }
+                None => format!("SELECT DISTINCT * FROM {previous}"),
+            },
+            AnyOperation::Merge(_) => {
+                notes.push(format!(
+                    "{}: no SQL equivalent, not included",
+                    op.describe()
+                ));
+                continue;
+            }
+            AnyOperation::AttachBundle(attach) => {
+                notes.push(format!(
+                    "{}: export that bundle separately as '{}'",
+                    op.describe(),
+                    attach.name
+                ));
+                continue;
+            }
+            AnyOperation::Union(_) => {
+                notes.push(format!(
+                    "{}: export that bundle separately and UNION ALL its rows",
+                    op.describe()
+                ));
+                continue;
+            }
+            AnyOperation::RegisterUdf(_) => {
+                notes.push(format!(
+                    "{}: register the UDF in the session running the query",
+                    op.describe()
+                ));
+                continue;
+            }
+            AnyOperation::SetDefaultView(default) if default.view_id.is_some() => {
+                notes.push(format!(
+                    "{}: the query returns the raw data, without the view applied",
+                    op.describe()
+                ));
+                continue;
+            }
+            _ => continue,
+        };
+        ctes.push((format!("step_{}", ctes.len()), sql));
+    }
+
+    let mut out = format!(
+        "-- Generated from bundle {} version {}\n",
+        bundle.url(),
+        bundle.version()
+    );
+    for note in notes {
+        out.push_str(&format!("-- NOTE: {}\n", note.replace('\n', " ")));
+    }
+    out.push_str("WITH\n");
+    out.push_str(
+        &ctes
+            .iter()
+            .map(|(name, sql)| format!("  {} AS (\n    {}\n  )", name, sql))
+            .collect::<Vec<_>>()
+            .join(",\n"),
+    );
+    out.push_str(&format!("\nSELECT * FROM {}", ctes[ctes.len() - 1].0));
+    out
+}
+
+/// SQL reading all the files of a pack
+fn union_sql(files: &[String], notes: &mut Vec<String>) -> String {
+    files
+        .iter()
+        .map(|url| {
+            if url.starts_with("function://") {
+                notes.push(format!(
+                    "{} is generated by a function and cannot be read outside the bundle",
+                    url
+                ));
+            }
+            format!("SELECT * FROM '{}'", url.replace('\'', "''"))
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ALL BY NAME ")
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!("\"Index\"", quote_identifier("Index"));
+        assert_eq!("\"a\"\"b\"", quote_identifier("a\"b"));
+    }
+
+    #[test]
+    fn test_union_sql() {
+        let mut notes = Vec::new();
+        assert_eq!(
+            "SELECT * FROM 'memory:///a.parquet' UNION ALL BY NAME SELECT * FROM 'memory:///it''s.csv'",
+            union_sql(
+                &[
+                    "memory:///a.parquet".to_string(),
+                    "memory:///it's.csv".to_string()
+                ],
+                &mut notes
+            )
+        );
+        assert!(notes.is_empty());
+
+        union_sql(&["function://gen/data".to_string()], &mut notes);
+        assert_eq!(1, notes.len());
+    }
+}
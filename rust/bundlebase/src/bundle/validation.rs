@@ -0,0 +1,130 @@
+use crate::bundle::{AnyOperation, BlockInfo, Bundle, BundleFacade};
+use crate::data::ObjectId;
+use crate::io::ObjectStoreFile;
+use crate::BundlebaseError;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// Something `validate` found that is often a mistake. Warnings never stop the bundle from
+/// being used, since the duplication may be intentional.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationWarning {
+    /// Blocks of the same pack whose files have identical content, so each of their rows is
+    /// counted once per block. Usually the same file attached through two paths.
+    DuplicateContent {
+        /// Names of the blocks, in attach order
+        blocks: Vec<String>,
+        /// Sources the blocks were attached from, in the same order
+        sources: Vec<String>,
+        /// SHA256 checksum of the shared content
+        checksum: String,
+    },
+}
+
+impl Display for ValidationWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationWarning::DuplicateContent {
+                blocks, sources, ..
+            } => {
+                let described: Vec<String> = blocks
+                    .iter()
+                    .zip(sources)
+                    .map(|(block, source)| format!("{} ({})", block, source))
+                    .collect();
+                write!(
+                    f,
+                    "Duplicate content: blocks {} are identical, so their rows are counted {} times",
+                    described.join(", "),
+                    blocks.len()
+                )
+            }
+        }
+    }
+}
+
+/// A block's size and row groups. Only blocks with the same key can have the same content
+type DuplicateKey = (u64, Option<Vec<usize>>);
+
+/// Finds blocks of the same pack with identical file content.
+///
+/// Only blocks of the same size and row groups are checksummed, so files are read only when
+/// they are likely duplicates. With `only`, just the groups containing one of those blocks are
+/// reported, for checking newly attached blocks against the rest.
+pub(crate) async fn find_duplicate_content(
+    bundle: &Bundle,
+    only: Option<&HashSet<ObjectId>>,
+) -> Result<Vec<ValidationWarning>, BundlebaseError> {
+    let row_groups: HashMap<ObjectId, Option<Vec<usize>>> = bundle
+        .operations
+        .iter()
+        .filter_map(|op| match op {
+            AnyOperation::AttachBlock(attach) => Some((attach.id, attach.row_groups.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let mut warnings = vec![];
+    for pack in bundle.packs() {
+        // Blocks grouped by their key
+        let mut candidates: Vec<(DuplicateKey, Vec<BlockInfo>)> = vec![];
+        for block in pack.blocks {
+            if block.url.is_none() {
+                continue;
+            }
+            let size = match block.bytes {
+                Some(bytes) => bytes as u64,
+                None => match block_file(bundle, &block)?.metadata().await? {
+                    Some(meta) => meta.size,
+                    None => continue,
+                },
+            };
+            let key = (size, row_groups.get(&block.id).cloned().flatten());
+            match candidates.iter_mut().find(|(k, _)| k == &key) {
+                Some((_, group)) => group.push(block),
+                None => candidates.push((key, vec![block])),
+            }
+        }
+
+        for (_, group) in candidates {
+            if group.len() < 2
+                || only.is_some_and(|ids| !group.iter().any(|block| ids.contains(&block.id)))
+            {
+                continue;
+            }
+            let mut by_checksum: Vec<(String, Vec<BlockInfo>)> = vec![];
+            for block in group {
+                let checksum = block_file(bundle, &block)?.checksum().await?;
+                match by_checksum.iter_mut().find(|(c, _)| c == &checksum) {
+                    Some((_, blocks)) => blocks.push(block),
+                    None => by_checksum.push((checksum, vec![block])),
+                }
+            }
+            for (checksum, blocks) in by_checksum {
+                if blocks.len() < 2
+                    || only.is_some_and(|ids| !blocks.iter().any(|block| ids.contains(&block.id)))
+                {
+                    continue;
+                }
+                let (blocks, sources) = blocks
+                    .into_iter()
+                    .map(|block| (block.name, block.source))
+                    .unzip();
+                warnings.push(ValidationWarning::DuplicateContent {
+                    blocks,
+                    sources,
+                    checksum,
+                });
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+fn block_file(bundle: &Bundle, block: &BlockInfo) -> Result<ObjectStoreFile, BundlebaseError> {
+    let url = block
+        .url
+        .as_ref()
+        .ok_or_else(|| format!("Block {} has no file", block.name))?;
+    ObjectStoreFile::from_url(url, bundle.config())
+}
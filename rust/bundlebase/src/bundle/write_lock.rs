@@ -0,0 +1,434 @@
+use crate::io::{ObjectStoreDir, ObjectStoreFile};
+use crate::BundlebaseError;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Name of the advisory lock file written to the metadata directory while a writer has the bundle open
+pub static LOCK_FILENAME: &str = "write.lock";
+
+/// The writer holding a bundle's lock, as stored in the lock file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub hostname: String,
+    pub timestamp: DateTime<Utc>,
+    /// Unique to each time the lock is taken, so a writer only ever refreshes or removes its own lock
+    pub token: String,
+}
+
+impl LockInfo {
+    fn new(token: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            hostname: hostname(),
+            timestamp: Utc::now(),
+            token: token.to_string(),
+        }
+    }
+
+    fn is_stale(&self, stale_after: Duration) -> bool {
+        Utc::now()
+            .signed_duration_since(self.timestamp)
+            .to_std()
+            .is_ok_and(|age| age > stale_after)
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|name| name.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// An advisory lock that keeps other writers from modifying a bundle at the same time.
+///
+/// The lock file is created with `write_if_not_exists`, so this works on object stores without
+/// native locking. It is removed by `release()`, or on a best-effort basis when dropped, but only
+/// while it still holds this lock's token.
+///
+/// While held, the lock's timestamp is refreshed in the background well within `stale_after`, so
+/// a long-running writer isn't mistaken for one that died.
+#[derive(Debug)]
+pub struct WriteLock {
+    file: ObjectStoreFile,
+    token: String,
+    released: AtomicBool,
+    heartbeat: tokio::task::JoinHandle<()>,
+}
+
+impl WriteLock {
+    /// Acquires the lock in `manifest_dir`, failing if another writer holds it.
+    /// A lock older than `stale_after` is assumed to be left over from a writer that died, and is taken over.
+    ///
+    /// Only one writer can take over a given stale lock: it first has to create a takeover file
+    /// named after the stale lock's token, and the lock is only replaced if it still holds that token.
+    /// A takeover file also goes stale after `stale_after`, in case its writer died mid-takeover.
+    pub async fn acquire(
+        manifest_dir: &ObjectStoreDir,
+        stale_after: Duration,
+    ) -> Result<Self, BundlebaseError> {
+        let file = manifest_dir.file(LOCK_FILENAME)?;
+        let token = Uuid::new_v4().to_string();
+        let contents = bytes::Bytes::from(serde_yaml::to_string(&LockInfo::new(&token))?);
+
+        if !file.write_if_not_exists(contents.clone()).await? {
+            if let Some(holder) = file.read_yaml::<LockInfo>().await? {
+                if !holder.is_stale(stale_after) {
+                    return Err(format!(
+                        "Bundle is locked by another writer (pid {} on {} since {}). If that process is no longer running, delete {}",
+                        holder.pid,
+                        holder.hostname,
+                        holder.timestamp.format("%Y-%m-%dT%H:%M:%SZ"),
+                        file.url()
+                    )
+                    .into());
+                }
+                let takeover = manifest_dir.file(&format!("{}.{}", LOCK_FILENAME, holder.token))?;
+                if !Self::start_takeover(&takeover, contents.clone(), stale_after).await? {
+                    return Err(Self::lost_race_error(&file));
+                }
+                // The stale writer may have come back, or another writer finished its takeover first
+                let still_stale = file
+                    .read_yaml::<LockInfo>()
+                    .await?
+                    .is_some_and(|current| current.token == holder.token);
+                if still_stale {
+                    warn!(
+                        "Taking over stale write lock held by pid {} on {} since {}",
+                        holder.pid, holder.hostname, holder.timestamp
+                    );
+                    file.delete().await?;
+                }
+                takeover.delete().await?;
+                if !still_stale {
+                    return Err(Self::lost_race_error(&file));
+                }
+            }
+
+            if !file.write_if_not_exists(contents).await? {
+                return Err(Self::lost_race_error(&file));
+            }
+        }
+
+        debug!("Acquired write lock {}", file.url());
+        Ok(Self {
+            heartbeat: tokio::spawn(Self::heartbeat(
+                file.clone(),
+                token.clone(),
+                stale_after / 3,
+            )),
+            file,
+            token,
+            released: AtomicBool::new(false),
+        })
+    }
+
+    /// Creates the takeover file, returning false if another writer is already taking over.
+    /// A takeover file older than `stale_after` was left by a writer that died mid-takeover, so
+    /// it is replaced rather than blocking every later takeover.
+    async fn start_takeover(
+        takeover: &ObjectStoreFile,
+        contents: bytes::Bytes,
+        stale_after: Duration,
+    ) -> Result<bool, BundlebaseError> {
+        if takeover.write_if_not_exists(contents.clone()).await? {
+            return Ok(true);
+        }
+        match takeover.read_yaml::<LockInfo>().await? {
+            Some(taker) if taker.is_stale(stale_after) => {
+                warn!(
+                    "Removing stale takeover file {} left by pid {} on {} since {}",
+                    takeover.url(),
+                    taker.pid,
+                    taker.hostname,
+                    taker.timestamp
+                );
+                takeover.delete().await?;
+                takeover.write_if_not_exists(contents).await
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn lost_race_error(file: &ObjectStoreFile) -> BundlebaseError {
+        format!(
+            "Bundle was locked by another writer while acquiring {}",
+            file.url()
+        )
+        .into()
+    }
+
+    /// Rewrites the lock file with the current time every `every`, until the lock is released or
+    /// another writer has taken it over
+    async fn heartbeat(file: ObjectStoreFile, token: String, every: Duration) {
+        let mut interval = tokio::time::interval(every.max(Duration::from_secs(1)));
+        // The first tick completes immediately, and the lock was only just written
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match file.read_yaml::<LockInfo>().await {
+                Ok(Some(holder)) if holder.token == token => {}
+                Ok(_) => {
+                    warn!("Write lock {} was taken over by another writer", file.url());
+                    return;
+                }
+                Err(e) => {
+                    warn!("Failed to read write lock {}: {}", file.url(), e);
+                    continue;
+                }
+            }
+            match file.write_yaml(&LockInfo::new(&token)).await {
+                Ok(()) => debug!("Refreshed write lock {}", file.url()),
+                Err(e) => warn!("Failed to refresh write lock {}: {}", file.url(), e),
+            }
+        }
+    }
+
+    /// Removes the lock file so other writers can open the bundle
+    pub async fn release(&self) -> Result<(), BundlebaseError> {
+        if !self.released.swap(true, Ordering::SeqCst) {
+            self.heartbeat.abort();
+            Self::delete_if_held(&self.file, &self.token).await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the lock file unless another writer has since taken the lock over
+    async fn delete_if_held(file: &ObjectStoreFile, token: &str) -> Result<(), BundlebaseError> {
+        match file.read_yaml::<LockInfo>().await? {
+            Some(holder) if holder.token == token => {
+                file.delete().await?;
+                debug!("Released write lock {}", file.url());
+            }
+            _ => warn!(
+                "Write lock {} was taken over by another writer, not removing it",
+                file.url()
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+        if self.released.load(Ordering::SeqCst) {
+            return;
+        }
+        // Deleting is async, so it can only be done if there is still a runtime to run it on
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                let file = self.file.clone();
+                let token = self.token.clone();
+                handle.spawn(async move {
+                    if let Err(e) = Self::delete_if_held(&file, &token).await {
+                        warn!("Failed to release write lock {}: {}", file.url(), e);
+                    }
+                });
+            }
+            Err(_) => warn!(
+                "Write lock {} was not released; delete it or wait for it to go stale",
+                self.file.url()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::random_memory_dir;
+
+    #[tokio::test]
+    async fn test_acquire_release() {
+        let dir = random_memory_dir();
+        let lock = WriteLock::acquire(&dir, Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let info: LockInfo = dir
+            .file(LOCK_FILENAME)
+            .unwrap()
+            .read_yaml()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::process::id(), info.pid);
+
+        let err = WriteLock::acquire(&dir, Duration::from_secs(60))
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(
+            err.starts_with("Bundle is locked by another writer"),
+            "{}",
+            err
+        );
+
+        lock.release().await.unwrap();
+        assert!(!dir.file(LOCK_FILENAME).unwrap().exists().await.unwrap());
+        WriteLock::acquire(&dir, Duration::from_secs(60))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_refreshes_timestamp() {
+        let dir = random_memory_dir();
+        // Refreshed every second
+        let lock = WriteLock::acquire(&dir, Duration::from_secs(3))
+            .await
+            .unwrap();
+        let file = dir.file(LOCK_FILENAME).unwrap();
+        let taken: LockInfo = file.read_yaml().await.unwrap().unwrap();
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        let refreshed: LockInfo = file.read_yaml().await.unwrap().unwrap();
+        assert!(refreshed.timestamp > taken.timestamp);
+
+        lock.release().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stale_lock_is_taken_over() {
+        let dir = random_memory_dir();
+        let stale = LockInfo {
+            pid: 1,
+            hostname: "elsewhere".to_string(),
+            timestamp: Utc::now() - chrono::Duration::hours(2),
+            token: "stale".to_string(),
+        };
+        dir.file(LOCK_FILENAME)
+            .unwrap()
+            .write_yaml(&stale)
+            .await
+            .unwrap();
+
+        let _lock = WriteLock::acquire(&dir, Duration::from_secs(60))
+            .await
+            .unwrap();
+        let info: LockInfo = dir
+            .file(LOCK_FILENAME)
+            .unwrap()
+            .read_yaml()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::process::id(), info.pid);
+        assert!(!dir
+            .file("write.lock.stale")
+            .unwrap()
+            .exists()
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stale_lock_is_taken_over_once() {
+        let dir = random_memory_dir();
+        let stale = LockInfo {
+            pid: 1,
+            hostname: "elsewhere".to_string(),
+            timestamp: Utc::now() - chrono::Duration::hours(2),
+            token: "stale".to_string(),
+        };
+        dir.file(LOCK_FILENAME)
+            .unwrap()
+            .write_yaml(&stale)
+            .await
+            .unwrap();
+
+        let (first, second) = tokio::join!(
+            WriteLock::acquire(&dir, Duration::from_secs(60)),
+            WriteLock::acquire(&dir, Duration::from_secs(60))
+        );
+        assert!(first.is_ok() != second.is_ok(), "{:?}, {:?}", first, second);
+        let lock = first.or(second).unwrap();
+        let info: LockInfo = dir
+            .file(LOCK_FILENAME)
+            .unwrap()
+            .read_yaml()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lock.token, info.token);
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_takeover_file_expires() {
+        let dir = random_memory_dir();
+        let stale = LockInfo {
+            pid: 1,
+            hostname: "elsewhere".to_string(),
+            timestamp: Utc::now() - chrono::Duration::hours(2),
+            token: "stale".to_string(),
+        };
+        dir.file(LOCK_FILENAME)
+            .unwrap()
+            .write_yaml(&stale)
+            .await
+            .unwrap();
+
+        // A writer taking over right now still blocks others
+        let takeover = dir.file("write.lock.stale").unwrap();
+        takeover.write_yaml(&LockInfo::new("taker")).await.unwrap();
+        let err = WriteLock::acquire(&dir, Duration::from_secs(60))
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(
+            err.starts_with("Bundle was locked by another writer"),
+            "{}",
+            err
+        );
+
+        // One that crashed mid-takeover leaves its file behind, which expires like the lock
+        let orphaned = LockInfo {
+            timestamp: Utc::now() - chrono::Duration::hours(1),
+            ..LockInfo::new("taker")
+        };
+        takeover.write_yaml(&orphaned).await.unwrap();
+        let lock = WriteLock::acquire(&dir, Duration::from_secs(60))
+            .await
+            .unwrap();
+        let info: LockInfo = dir
+            .file(LOCK_FILENAME)
+            .unwrap()
+            .read_yaml()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lock.token, info.token);
+        assert!(!takeover.exists().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_release_keeps_lock_taken_over() {
+        let dir = random_memory_dir();
+        let lock = WriteLock::acquire(&dir, Duration::from_secs(60))
+            .await
+            .unwrap();
+        lock.heartbeat.abort();
+
+        // Another writer took the lock over, e.g. after this one was paused past `stale_after`
+        let file = dir.file(LOCK_FILENAME).unwrap();
+        let other = LockInfo::new("other");
+        file.write_yaml(&other).await.unwrap();
+
+        lock.release().await.unwrap();
+        assert_eq!(Some(other), file.read_yaml().await.unwrap());
+    }
+}
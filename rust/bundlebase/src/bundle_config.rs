@@ -1,8 +1,16 @@
 use crate::BundlebaseError;
+use datafusion::execution::memory_pool::{FairSpillPool, TrackConsumersPool};
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
 use lazy_static::lazy_static;
+use object_store::ClientConfigKey;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
 use url::Url;
 
 // Valid config keys for different cloud providers
@@ -18,6 +26,8 @@ lazy_static! {
             "bucket",
             "allow_http",
             "skip_signature",
+            "anonymous",
+            "request_payer",
             "virtual_hosted_style_request",
             "token",
             "imdsv1_fallback",
@@ -57,8 +67,147 @@ lazy_static! {
         .into_iter()
         .collect()
     };
+    /// Retry and concurrency keys understood for every object store, whatever the provider.
+    /// They are handled by bundlebase rather than passed to the provider's builder as-is.
+    static ref STORE_KEYS: HashSet<&'static str> = {
+        vec![
+            MAX_RETRIES,
+            RETRY_TIMEOUT_SECS,
+            RETRY_BACKOFF_MS,
+            RETRY_MAX_BACKOFF_MS,
+            MAX_CONCURRENT_REQUESTS,
+        ]
+        .into_iter()
+        .collect()
+    };
+    /// Keys that configure bundlebase itself rather than an object store.
+    /// These are only valid as defaults and are never passed to object_store builders.
+    static ref BUNDLE_KEYS: HashSet<&'static str> = {
+        vec![
+            PARQUET_ROW_GROUP_SIZE,
+            PARQUET_COMPRESSION,
+            MEMORY_LIMIT_MB,
+            SPILL_DIR,
+            NORMALIZE_COLUMN_NAMES,
+            MULTIPART_THRESHOLD_MB,
+            WRITE_LOCK_STALE_SECS,
+            FILE_FORMATS,
+            COLUMN_ENCRYPTION_KEY,
+            INDEX_CONCURRENCY,
+            ATTACH_SPLIT_MB,
+            PARQUET_FILTER_PUSHDOWN,
+            TIME_ZONE,
+            REINFER_SCHEMA,
+            AUTO_INDEX_JOIN_KEYS,
+            BLOCK_STATS,
+            AUTHOR,
+            CSV_NULL_REGEX,
+        ]
+        .into_iter()
+        .collect()
+    };
 }
 
+/// Config key for the maximum number of rows per row group in written parquet files
+pub const PARQUET_ROW_GROUP_SIZE: &str = "parquet_row_group_size";
+
+/// Config key for the compression codec of written parquet files (zstd, snappy, none)
+pub const PARQUET_COMPRESSION: &str = "parquet_compression";
+
+/// Default maximum rows per row group for written parquet files
+pub const DEFAULT_PARQUET_ROW_GROUP_SIZE: usize = 1024 * 1024;
+
+/// Config key for the memory, in megabytes, queries may use before spilling to disk
+pub const MEMORY_LIMIT_MB: &str = "memory_limit_mb";
+
+/// Config key for the directory spill files are written to. Defaults to the OS temp directory
+pub const SPILL_DIR: &str = "spill_dir";
+
+/// Config key that, when `true`, gives attached columns snake_case aliases (`First Name` -> `first_name`)
+pub const NORMALIZE_COLUMN_NAMES: &str = "normalize_column_names";
+
+/// Config key for the size, in megabytes, above which files are written with multipart uploads
+pub const MULTIPART_THRESHOLD_MB: &str = "multipart_threshold_mb";
+
+/// Default size, in megabytes, above which files are written with multipart uploads
+pub const DEFAULT_MULTIPART_THRESHOLD_MB: usize = 8;
+
+/// Config key for the age, in seconds, after which another writer's lock file is considered stale
+pub const WRITE_LOCK_STALE_SECS: &str = "write_lock_stale_secs";
+
+/// Default age, in seconds, after which another writer's lock file is considered stale
+pub const DEFAULT_WRITE_LOCK_STALE_SECS: u64 = 60 * 60;
+
+/// Config key mapping file extensions to the format they are attached as (`tsv=tsv,pq=parquet`)
+pub const FILE_FORMATS: &str = "file_formats";
+
+/// Config key for the key that unlocks columns masked with the `encrypt` strategy.
+/// Only accepted as passed-in config so it is never written to the manifest
+pub const COLUMN_ENCRYPTION_KEY: &str = "column_encryption_key";
+
+/// Config key for how many blocks may be read at once while building indexes
+pub const INDEX_CONCURRENCY: &str = "index_concurrency";
+
+/// Config key for the size, in megabytes, of each scan partition an attached file is split into
+pub const ATTACH_SPLIT_MB: &str = "attach_split_mb";
+
+/// Default size, in megabytes, of each scan partition an attached file is split into
+pub const DEFAULT_ATTACH_SPLIT_MB: usize = 128;
+
+/// Config key that, when `false`, stops query filters from being used to skip parquet row groups
+/// and pages by their statistics, bloom filters and page index
+pub const PARQUET_FILTER_PUSHDOWN: &str = "parquet_filter_pushdown";
+
+/// Config key for the session time zone (`+02:00`, `Europe/Berlin`) that `now()` and
+/// `TIMESTAMP WITH TIME ZONE` values use. Defaults to UTC
+pub const TIME_ZONE: &str = "time_zone";
+
+/// Time zone queries run in unless `time_zone` is set
+pub const DEFAULT_TIME_ZONE: &str = "+00:00";
+
+/// Config key listing URL prefixes of sources (`file:///exports/,s3://bucket/daily/`) whose
+/// schema is inferred again each time the bundle is opened, instead of the one recorded when
+/// they were attached. `*` matches every source
+pub const REINFER_SCHEMA: &str = "reinfer_schema";
+
+/// Config key that, when `false`, stops `join` from indexing the bundle's join key columns
+pub const AUTO_INDEX_JOIN_KEYS: &str = "auto_index_join_keys";
+
+/// Config key that, when `false`, stops `attach` from reading each block for the per-column
+/// min/max used to skip blocks a filter rules out
+pub const BLOCK_STATS: &str = "block_stats";
+
+/// Config key for the author recorded on commits. Defaults to the `BUNDLEBASE_AUTHOR`
+/// environment variable, then the current user
+pub const AUTHOR: &str = "author";
+
+/// Config key for a regex (`^NULL$`) matching CSV fields that are read as null, such as the
+/// `null_value` a CSV was exported with. By default only empty fields are null
+pub const CSV_NULL_REGEX: &str = "csv_null_regex";
+
+/// Config key for how many times a failed object store request is retried. `0` disables retries
+pub const MAX_RETRIES: &str = "max_retries";
+
+/// Config key for the time, in seconds, after the first attempt that no more retries are made
+pub const RETRY_TIMEOUT_SECS: &str = "retry_timeout_secs";
+
+/// Config key for the delay, in milliseconds, before the first retry. Later delays double
+pub const RETRY_BACKOFF_MS: &str = "retry_backoff_ms";
+
+/// Config key for the longest delay, in milliseconds, between retries
+pub const RETRY_MAX_BACKOFF_MS: &str = "retry_max_backoff_ms";
+
+/// Config key for how many requests may be in flight to an object store at once
+pub const MAX_CONCURRENT_REQUESTS: &str = "max_concurrent_requests";
+
+/// Config key for a bearer token sent as the `Authorization` header to an `http://` or
+/// `https://` URL prefix
+pub const BEARER_TOKEN: &str = "bearer_token";
+
+/// Prefix of config keys setting an HTTP header sent to an `http://` or `https://` URL prefix,
+/// e.g. `header.X-Api-Key`
+pub const HTTP_HEADER_PREFIX: &str = "header.";
+
 /// Configuration for container storage and cloud providers
 ///
 /// # Format
@@ -191,8 +340,13 @@ impl BundleConfig {
     /// # Returns
     /// HashMap of config key-value pairs applicable to this URL
     pub(crate) fn get_config_for_url(&self, url: &Url) -> HashMap<String, String> {
-        // 1. Start with defaults
-        let mut config = self.defaults.clone();
+        // 1. Start with defaults, minus the bundle-level settings
+        let mut config: HashMap<String, String> = self
+            .defaults
+            .iter()
+            .filter(|(key, _)| !BUNDLE_KEYS.contains(key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
 
         // 2. Find longest matching URL prefix
         let url_str = url.to_string();
@@ -218,6 +372,315 @@ impl BundleConfig {
         config
     }
 
+    /// Get a default (non-URL-specific) config value
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.defaults.get(key)
+    }
+
+    /// Whether attached column names are normalized to snake_case. Defaults to false.
+    ///
+    /// # Errors
+    /// Returns error if `normalize_column_names` is set to something other than true or false
+    pub fn normalize_column_names(&self) -> Result<bool, BundlebaseError> {
+        self.get_bool(NORMALIZE_COLUMN_NAMES, false)
+    }
+
+    /// Whether query filters are pushed into parquet scans, so row groups and pages whose
+    /// statistics, bloom filters or page index rule them out are never read. Defaults to true.
+    ///
+    /// # Errors
+    /// Returns error if `parquet_filter_pushdown` is set to something other than true or false
+    pub fn parquet_filter_pushdown(&self) -> Result<bool, BundlebaseError> {
+        self.get_bool(PARQUET_FILTER_PUSHDOWN, true)
+    }
+
+    /// Whether `join` indexes the bundle's columns used as join keys, so filters on them
+    /// find rows through the index. Defaults to true.
+    ///
+    /// # Errors
+    /// Returns error if `auto_index_join_keys` is set to something other than true or false
+    pub fn auto_index_join_keys(&self) -> Result<bool, BundlebaseError> {
+        self.get_bool(AUTO_INDEX_JOIN_KEYS, true)
+    }
+
+    /// Whether `attach` records the min, max and null count of each column of a block, so
+    /// queries skip blocks whose values can't pass their filters. Defaults to true.
+    ///
+    /// # Errors
+    /// Returns error if `block_stats` is set to something other than true or false
+    pub fn block_stats(&self) -> Result<bool, BundlebaseError> {
+        self.get_bool(BLOCK_STATS, true)
+    }
+
+    /// The time zone queries run in, from the `time_zone` setting. Defaults to UTC.
+    ///
+    /// Timestamp columns without a time zone are never converted, so filters such as
+    /// `ts > '2024-01-01'` compare them as written. Columns with a time zone compare instants,
+    /// reading literals without an offset in the column's own time zone.
+    ///
+    /// # Errors
+    /// Returns error if `time_zone` is not an offset like `+02:00` or a name like `Europe/Berlin`
+    pub fn time_zone(&self) -> Result<String, BundlebaseError> {
+        match self.get(TIME_ZONE) {
+            Some(value) => match value.parse::<arrow::array::timezone::Tz>() {
+                Ok(_) => Ok(value.clone()),
+                Err(_) => Err(format!(
+                    "Invalid {} '{}': must be an offset like +02:00 or a name like Europe/Berlin",
+                    TIME_ZONE, value
+                )
+                .into()),
+            },
+            None => Ok(DEFAULT_TIME_ZONE.to_string()),
+        }
+    }
+
+    /// The author recorded on commits: the `author` setting, else the `BUNDLEBASE_AUTHOR`
+    /// environment variable, else `USER` or `USERNAME`, else "unknown".
+    pub fn author(&self) -> String {
+        self.get(AUTHOR)
+            .cloned()
+            .or_else(|| std::env::var("BUNDLEBASE_AUTHOR").ok())
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Whether the schema of the source at `url` is inferred again each time the bundle is
+    /// opened, because its URL starts with one of the `reinfer_schema` prefixes. Defaults to
+    /// false, reusing the schema recorded in the manifest when the source was attached.
+    ///
+    /// Re-inferring picks up columns added to a file since, at the cost of reading the start
+    /// of every matching file whenever the bundle is opened.
+    pub fn reinfer_schema(&self, url: &Url) -> bool {
+        self.get(REINFER_SCHEMA).is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|prefix| !prefix.is_empty())
+                .any(|prefix| prefix == "*" || url.as_str().starts_with(prefix))
+        })
+    }
+
+    /// The pattern CSV fields read as null match, if `csv_null_regex` is set
+    pub fn csv_null_regex(&self) -> Result<Option<Regex>, BundlebaseError> {
+        match self.get(CSV_NULL_REGEX) {
+            Some(value) => match Regex::new(value) {
+                Ok(regex) => Ok(Some(regex)),
+                Err(e) => Err(format!("Invalid {} '{}': {}", CSV_NULL_REGEX, value, e).into()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn get_bool(&self, key: &str, default: bool) -> Result<bool, BundlebaseError> {
+        match self.get(key) {
+            Some(value) => match value.to_lowercase().as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(format!("Invalid {} '{}': must be true or false", key, value).into()),
+            },
+            None => Ok(default),
+        }
+    }
+
+    /// Size in bytes above which writes use a multipart upload instead of a single put, from
+    /// the `multipart_threshold_mb` setting. Defaults to 8 MB.
+    ///
+    /// # Errors
+    /// Returns error if `multipart_threshold_mb` is not a non-negative integer
+    pub fn multipart_threshold(&self) -> Result<usize, BundlebaseError> {
+        let mb = match self.get(MULTIPART_THRESHOLD_MB) {
+            Some(value) => value.parse::<usize>().map_err(|_| {
+                format!(
+                    "Invalid {} '{}': must be a non-negative integer",
+                    MULTIPART_THRESHOLD_MB, value
+                )
+            })?,
+            None => DEFAULT_MULTIPART_THRESHOLD_MB,
+        };
+        Ok(mb * 1024 * 1024)
+    }
+
+    /// Size in bytes of each scan partition a large attached file is automatically split into,
+    /// from the `attach_split_mb` setting. Defaults to 128 MB.
+    ///
+    /// # Errors
+    /// Returns error if `attach_split_mb` is not a positive integer
+    pub fn attach_split_size(&self) -> Result<usize, BundlebaseError> {
+        let mb = match self.get(ATTACH_SPLIT_MB) {
+            Some(value) => match value.parse::<usize>() {
+                Ok(mb) if mb > 0 => mb,
+                _ => {
+                    return Err(format!(
+                        "Invalid {} '{}': must be a positive integer",
+                        ATTACH_SPLIT_MB, value
+                    )
+                    .into())
+                }
+            },
+            None => DEFAULT_ATTACH_SPLIT_MB,
+        };
+        Ok(mb * 1024 * 1024)
+    }
+
+    /// How old another writer's lock file must be before it can be taken over, from the
+    /// `write_lock_stale_secs` setting. Defaults to one hour.
+    ///
+    /// # Errors
+    /// Returns error if `write_lock_stale_secs` is not a non-negative integer
+    pub fn write_lock_stale_after(&self) -> Result<std::time::Duration, BundlebaseError> {
+        let secs = match self.get(WRITE_LOCK_STALE_SECS) {
+            Some(value) => value.parse::<u64>().map_err(|_| {
+                format!(
+                    "Invalid {} '{}': must be a non-negative integer",
+                    WRITE_LOCK_STALE_SECS, value
+                )
+            })?,
+            None => DEFAULT_WRITE_LOCK_STALE_SECS,
+        };
+        Ok(std::time::Duration::from_secs(secs))
+    }
+
+    /// How many blocks may be read at once while building indexes, from the `index_concurrency`
+    /// setting. Defaults to the number of available CPUs.
+    ///
+    /// # Errors
+    /// Returns error if `index_concurrency` is not a positive integer
+    pub fn index_concurrency(&self) -> Result<usize, BundlebaseError> {
+        match self.get(INDEX_CONCURRENCY) {
+            Some(value) => match value.parse::<usize>() {
+                Ok(concurrency) if concurrency > 0 => Ok(concurrency),
+                _ => Err(format!(
+                    "Invalid {} '{}': must be a positive integer",
+                    INDEX_CONCURRENCY, value
+                )
+                .into()),
+            },
+            None => Ok(std::thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1)),
+        }
+    }
+
+    /// The key for columns masked with the `encrypt` strategy, if one is configured
+    pub fn column_encryption_key(&self) -> Option<&str> {
+        self.get(COLUMN_ENCRYPTION_KEY)
+            .map(|key| key.as_str())
+            .filter(|key| !key.is_empty())
+    }
+
+    /// Extension to format overrides from the `file_formats` setting, written as comma-separated
+    /// `extension=format` pairs such as `txt=csv,pq=parquet`. Extensions are lowercased and
+    /// may include a leading dot. Defaults to no overrides.
+    ///
+    /// # Errors
+    /// Returns error if an entry is not in `extension=format` form
+    pub fn file_formats(&self) -> Result<HashMap<String, String>, BundlebaseError> {
+        let mut formats = HashMap::new();
+        let Some(value) = self.get(FILE_FORMATS) else {
+            return Ok(formats);
+        };
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((extension, format))
+                    if !extension.trim().trim_start_matches('.').is_empty()
+                        && !format.trim().is_empty() =>
+                {
+                    formats.insert(
+                        extension.trim().trim_start_matches('.').to_lowercase(),
+                        format.trim().to_lowercase(),
+                    );
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid {} entry '{}': must be extension=format",
+                        FILE_FORMATS, entry
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(formats)
+    }
+
+    /// Build parquet writer properties from the `parquet_row_group_size` and
+    /// `parquet_compression` settings. Defaults to 1M-row row groups and zstd compression.
+    ///
+    /// # Errors
+    /// Returns error if either setting has an unparseable value
+    pub fn parquet_writer_properties(&self) -> Result<WriterProperties, BundlebaseError> {
+        let row_group_size = match self.get(PARQUET_ROW_GROUP_SIZE) {
+            Some(value) => match value.parse::<usize>() {
+                Ok(size) if size > 0 => size,
+                _ => {
+                    return Err(format!(
+                        "Invalid {} '{}': must be a positive integer",
+                        PARQUET_ROW_GROUP_SIZE, value
+                    )
+                    .into())
+                }
+            },
+            None => DEFAULT_PARQUET_ROW_GROUP_SIZE,
+        };
+
+        let compression = match self.get(PARQUET_COMPRESSION) {
+            Some(value) => match value.to_lowercase().as_str() {
+                "zstd" => Compression::ZSTD(ZstdLevel::default()),
+                "snappy" => Compression::SNAPPY,
+                "none" | "uncompressed" => Compression::UNCOMPRESSED,
+                _ => {
+                    return Err(format!(
+                        "Invalid {} '{}': must be one of zstd, snappy, none",
+                        PARQUET_COMPRESSION, value
+                    )
+                    .into())
+                }
+            },
+            None => Compression::ZSTD(ZstdLevel::default()),
+        };
+
+        Ok(WriterProperties::builder()
+            .set_max_row_group_size(row_group_size)
+            .set_compression(compression)
+            .build())
+    }
+
+    /// Build a DataFusion runtime for the `memory_limit_mb` and `spill_dir` settings,
+    /// keeping the object stores registered in `existing`.
+    ///
+    /// With a memory limit, queries that need more memory than allowed spill to disk instead
+    /// of growing without bound. Without one, memory use is unbounded.
+    ///
+    /// # Errors
+    /// Returns error if `memory_limit_mb` has an unparseable value or the runtime can't be built
+    pub fn runtime_env(&self, existing: &RuntimeEnv) -> Result<Arc<RuntimeEnv>, BundlebaseError> {
+        let mut builder = RuntimeEnvBuilder::new()
+            .with_object_store_registry(existing.object_store_registry.clone());
+
+        if let Some(value) = self.get(MEMORY_LIMIT_MB) {
+            let limit_mb = match value.parse::<usize>() {
+                Ok(limit) if limit > 0 => limit,
+                _ => {
+                    return Err(format!(
+                        "Invalid {} '{}': must be a positive integer",
+                        MEMORY_LIMIT_MB, value
+                    )
+                    .into())
+                }
+            };
+            builder = builder.with_memory_pool(Arc::new(TrackConsumersPool::new(
+                FairSpillPool::new(limit_mb * 1024 * 1024),
+                NonZeroUsize::new(5).expect("5 is non-zero"),
+            )));
+        }
+
+        if let Some(dir) = self.get(SPILL_DIR) {
+            builder = builder.with_temp_file_path(dir);
+        }
+
+        Ok(builder.build_arc()?)
+    }
+
     /// Check if a key looks like a URL (contains "://")
     fn is_url_key(key: &str) -> bool {
         key.contains("://")
@@ -232,6 +695,13 @@ impl BundleConfig {
     /// # Errors
     /// Returns error if the key is not valid for the specified cloud provider
     fn validate_key(url_prefix: &str, key: &str) -> Result<(), BundlebaseError> {
+        if url_prefix.is_empty() && BUNDLE_KEYS.contains(key) {
+            return Ok(());
+        }
+        if STORE_KEYS.contains(key) {
+            return Ok(());
+        }
+
         // Determine cloud provider from URL prefix
         let valid_keys = if url_prefix.starts_with("s3://") || url_prefix.is_empty() {
             &*VALID_S3_KEYS // Default to S3 keys for validation
@@ -239,6 +709,18 @@ impl BundleConfig {
             &*VALID_GCS_KEYS
         } else if url_prefix.starts_with("azure://") || url_prefix.starts_with("az://") {
             &*VALID_AZURE_KEYS
+        } else if url_prefix.starts_with("http://") || url_prefix.starts_with("https://") {
+            if key == BEARER_TOKEN
+                || key.starts_with(HTTP_HEADER_PREFIX)
+                || key.parse::<ClientConfigKey>().is_ok()
+            {
+                return Ok(());
+            }
+            return Err(format!(
+                "Invalid config key '{}' for {}. Expected {}, {}<name> or an HTTP client option such as timeout",
+                key, url_prefix, BEARER_TOKEN, HTTP_HEADER_PREFIX
+            )
+            .into());
         } else {
             // Unknown scheme, allow any keys
             return Ok(());
@@ -351,6 +833,328 @@ mod tests {
     fn test_validate_key_valid_s3() {
         assert!(BundleConfig::validate_key("", "region").is_ok());
         assert!(BundleConfig::validate_key("s3://bucket/", "access_key_id").is_ok());
+        assert!(BundleConfig::validate_key("s3://bucket/", "anonymous").is_ok());
+        assert!(BundleConfig::validate_key("s3://bucket/", "request_payer").is_ok());
+    }
+
+    #[test]
+    fn test_validate_key_bundle_keys() {
+        assert!(BundleConfig::validate_key("", PARQUET_ROW_GROUP_SIZE).is_ok());
+        assert!(BundleConfig::validate_key("", PARQUET_COMPRESSION).is_ok());
+        assert!(BundleConfig::validate_key("", MEMORY_LIMIT_MB).is_ok());
+        assert!(BundleConfig::validate_key("", SPILL_DIR).is_ok());
+        assert!(BundleConfig::validate_key("s3://bucket/", PARQUET_COMPRESSION).is_err());
+    }
+
+    #[test]
+    fn test_validate_key_store_keys() {
+        assert!(BundleConfig::validate_key("", MAX_RETRIES).is_ok());
+        assert!(BundleConfig::validate_key("s3://bucket/", RETRY_BACKOFF_MS).is_ok());
+        assert!(BundleConfig::validate_key("gs://bucket/", RETRY_TIMEOUT_SECS).is_ok());
+        assert!(BundleConfig::validate_key("az://container/", MAX_CONCURRENT_REQUESTS).is_ok());
+
+        let mut config = BundleConfig::new();
+        config.set(MAX_RETRIES, "3", None);
+        let url_config = config.get_config_for_url(&Url::parse("s3://bucket/data").unwrap());
+        assert_eq!(Some(&"3".to_string()), url_config.get(MAX_RETRIES));
+    }
+
+    #[test]
+    fn test_validate_key_http() {
+        let prefix = "https://data.example.com/";
+        assert!(BundleConfig::validate_key(prefix, BEARER_TOKEN).is_ok());
+        assert!(BundleConfig::validate_key(prefix, "header.X-Api-Key").is_ok());
+        assert!(BundleConfig::validate_key(prefix, "timeout").is_ok());
+        assert!(BundleConfig::validate_key(prefix, MAX_RETRIES).is_ok());
+        assert!(BundleConfig::validate_key(prefix, "region").is_err());
+        // Tokens are only sent to the URLs they are set for
+        assert!(BundleConfig::validate_key("", BEARER_TOKEN).is_err());
+    }
+
+    #[test]
+    fn test_bundle_keys_not_passed_to_store() {
+        let mut config = BundleConfig::new();
+        config.set("region", "us-west-2", None);
+        config.set(PARQUET_COMPRESSION, "snappy", None);
+
+        let url_config = config.get_config_for_url(&Url::parse("s3://bucket/data").unwrap());
+        assert_eq!(1, url_config.len());
+        assert_eq!(Some(&"us-west-2".to_string()), url_config.get("region"));
+    }
+
+    #[test]
+    fn test_parquet_writer_properties_defaults() {
+        let props = BundleConfig::new().parquet_writer_properties().unwrap();
+        assert_eq!(DEFAULT_PARQUET_ROW_GROUP_SIZE, props.max_row_group_size());
+        assert_eq!(
+            Compression::ZSTD(ZstdLevel::default()),
+            props.compression(&parquet::schema::types::ColumnPath::from("any"))
+        );
+    }
+
+    #[test]
+    fn test_parquet_writer_properties_invalid() {
+        let mut config = BundleConfig::new();
+        config.set(PARQUET_COMPRESSION, "brotli9000", None);
+        assert_eq!(
+            "Invalid parquet_compression 'brotli9000': must be one of zstd, snappy, none",
+            config
+                .parquet_writer_properties()
+                .err()
+                .unwrap()
+                .to_string()
+        );
+
+        let mut config = BundleConfig::new();
+        config.set(PARQUET_ROW_GROUP_SIZE, "0", None);
+        assert!(config.parquet_writer_properties().is_err());
+    }
+
+    #[test]
+    fn test_normalize_column_names() {
+        let mut config = BundleConfig::new();
+        assert!(!config.normalize_column_names().unwrap());
+
+        config.set(NORMALIZE_COLUMN_NAMES, "TRUE", None);
+        assert!(config.normalize_column_names().unwrap());
+
+        config.set(NORMALIZE_COLUMN_NAMES, "yes", None);
+        assert_eq!(
+            "Invalid normalize_column_names 'yes': must be true or false",
+            config.normalize_column_names().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_time_zone() {
+        let mut config = BundleConfig::new();
+        assert_eq!("+00:00", config.time_zone().unwrap());
+
+        config.set(TIME_ZONE, "Europe/Berlin", None);
+        assert_eq!("Europe/Berlin", config.time_zone().unwrap());
+
+        config.set(TIME_ZONE, "Mars/Olympus", None);
+        assert_eq!(
+            "Invalid time_zone 'Mars/Olympus': must be an offset like +02:00 or a name like Europe/Berlin",
+            config.time_zone().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parquet_filter_pushdown() {
+        let mut config = BundleConfig::new();
+        assert!(config.parquet_filter_pushdown().unwrap());
+
+        config.set(PARQUET_FILTER_PUSHDOWN, "false", None);
+        assert!(!config.parquet_filter_pushdown().unwrap());
+        assert!(config
+            .get_config_for_url(&Url::parse("s3://bucket/").unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_auto_index_join_keys() {
+        let mut config = BundleConfig::new();
+        assert!(config.auto_index_join_keys().unwrap());
+
+        config.set(AUTO_INDEX_JOIN_KEYS, "false", None);
+        assert!(!config.auto_index_join_keys().unwrap());
+    }
+
+    #[test]
+    fn test_block_stats() {
+        let mut config = BundleConfig::new();
+        assert!(config.block_stats().unwrap());
+
+        config.set(BLOCK_STATS, "false", None);
+        assert!(!config.block_stats().unwrap());
+    }
+
+    #[test]
+    fn test_reinfer_schema() {
+        let daily = Url::parse("file:///exports/daily/2024-01-01.csv").unwrap();
+        let other = Url::parse("s3://bucket/other.csv").unwrap();
+        let mut config = BundleConfig::new();
+        assert!(!config.reinfer_schema(&daily));
+
+        config.set(
+            REINFER_SCHEMA,
+            "file:///exports/daily/, s3://bucket/daily/",
+            None,
+        );
+        assert!(config.reinfer_schema(&daily));
+        assert!(!config.reinfer_schema(&other));
+
+        config.set(REINFER_SCHEMA, "*", None);
+        assert!(config.reinfer_schema(&other));
+        assert!(config.get_config_for_url(&other).is_empty());
+    }
+
+    #[test]
+    fn test_multipart_threshold() {
+        let mut config = BundleConfig::new();
+        assert_eq!(8 * 1024 * 1024, config.multipart_threshold().unwrap());
+
+        config.set(MULTIPART_THRESHOLD_MB, "100", None);
+        assert_eq!(100 * 1024 * 1024, config.multipart_threshold().unwrap());
+        assert!(config
+            .get_config_for_url(&Url::parse("s3://bucket/").unwrap())
+            .is_empty());
+
+        config.set(MULTIPART_THRESHOLD_MB, "lots", None);
+        assert_eq!(
+            "Invalid multipart_threshold_mb 'lots': must be a non-negative integer",
+            config.multipart_threshold().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_attach_split_size() {
+        let mut config = BundleConfig::new();
+        assert_eq!(128 * 1024 * 1024, config.attach_split_size().unwrap());
+
+        config.set(ATTACH_SPLIT_MB, "16", None);
+        assert_eq!(16 * 1024 * 1024, config.attach_split_size().unwrap());
+
+        config.set(ATTACH_SPLIT_MB, "0", None);
+        assert_eq!(
+            "Invalid attach_split_mb '0': must be a positive integer",
+            config.attach_split_size().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_file_formats() {
+        let mut config = BundleConfig::new();
+        assert!(config.file_formats().unwrap().is_empty());
+
+        config.set(FILE_FORMATS, "TSV=tsv, .pq = Parquet", None);
+        let formats = config.file_formats().unwrap();
+        assert_eq!(2, formats.len());
+        assert_eq!(Some(&"tsv".to_string()), formats.get("tsv"));
+        assert_eq!(Some(&"parquet".to_string()), formats.get("pq"));
+
+        config.set(FILE_FORMATS, "txt", None);
+        assert_eq!(
+            "Invalid file_formats entry 'txt': must be extension=format",
+            config.file_formats().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_index_concurrency() {
+        let mut config = BundleConfig::new();
+        assert!(config.index_concurrency().unwrap() >= 1);
+
+        config.set(INDEX_CONCURRENCY, "4", None);
+        assert_eq!(4, config.index_concurrency().unwrap());
+
+        config.set(INDEX_CONCURRENCY, "0", None);
+        assert_eq!(
+            "Invalid index_concurrency '0': must be a positive integer",
+            config.index_concurrency().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_lock_stale_after() {
+        let mut config = BundleConfig::new();
+        assert_eq!(
+            std::time::Duration::from_secs(3600),
+            config.write_lock_stale_after().unwrap()
+        );
+
+        config.set(WRITE_LOCK_STALE_SECS, "30", None);
+        assert_eq!(
+            std::time::Duration::from_secs(30),
+            config.write_lock_stale_after().unwrap()
+        );
+
+        config.set(WRITE_LOCK_STALE_SECS, "-1", None);
+        assert_eq!(
+            "Invalid write_lock_stale_secs '-1': must be a non-negative integer",
+            config.write_lock_stale_after().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_runtime_env_memory_limit() {
+        use datafusion::execution::memory_pool::MemoryLimit;
+
+        let existing = RuntimeEnv::default();
+        let unbounded = BundleConfig::new().runtime_env(&existing).unwrap();
+        assert!(matches!(
+            unbounded.memory_pool.memory_limit(),
+            MemoryLimit::Infinite
+        ));
+
+        let mut config = BundleConfig::new();
+        config.set(MEMORY_LIMIT_MB, "64", None);
+        let limited = config.runtime_env(&existing).unwrap();
+        assert!(matches!(
+            limited.memory_pool.memory_limit(),
+            MemoryLimit::Finite(limit) if limit == 64 * 1024 * 1024
+        ));
+        assert!(Arc::ptr_eq(
+            &existing.object_store_registry,
+            &limited.object_store_registry
+        ));
+    }
+
+    #[test]
+    fn test_runtime_env_invalid_memory_limit() {
+        let mut config = BundleConfig::new();
+        config.set(MEMORY_LIMIT_MB, "lots", None);
+        assert_eq!(
+            "Invalid memory_limit_mb 'lots': must be a positive integer",
+            config
+                .runtime_env(&RuntimeEnv::default())
+                .err()
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_parquet_writer_properties_applied() {
+        use arrow::array::Int64Array;
+        use arrow::datatypes::{DataType, Field, Schema};
+        use arrow::record_batch::RecordBatch;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        use parquet::arrow::ArrowWriter;
+        use std::sync::Arc;
+
+        let mut config = BundleConfig::new();
+        config.set(PARQUET_ROW_GROUP_SIZE, "4", None);
+        config.set(PARQUET_COMPRESSION, "snappy", None);
+
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from((0..10).collect::<Vec<i64>>()))],
+        )
+        .unwrap();
+
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(
+            &mut buffer,
+            schema,
+            Some(config.parquet_writer_properties().unwrap()),
+        )
+        .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(buffer)).unwrap();
+        let metadata = reader.metadata().clone();
+        assert_eq!(3, metadata.num_row_groups());
+        assert_eq!(
+            Compression::SNAPPY,
+            metadata.row_group(0).column(0).compression()
+        );
+
+        let rows: usize = reader.build().unwrap().map(|b| b.unwrap().num_rows()).sum();
+        assert_eq!(10, rows);
     }
 
     #[test]
@@ -1,10 +1,12 @@
 mod block_schema_provider;
 mod bundle_schema_provider;
+mod constrained_table;
 mod pack_schema_provider;
 mod pack_union_table;
 
 pub use block_schema_provider::BlockSchemaProvider;
 pub use bundle_schema_provider::BundleSchemaProvider;
+pub(crate) use constrained_table::ConstrainedTable;
 pub use pack_schema_provider::PackSchemaProvider;
 pub use pack_union_table::PackUnionTable;
 
@@ -1,22 +1,28 @@
-use crate::bundle::DataFrameHolder;
+use crate::bundle::{DataFrameHolder, ExternalBundles};
 use crate::catalog;
 use arrow_schema::SchemaRef;
 use async_trait::async_trait;
 use datafusion::catalog::{SchemaProvider, Session, TableProvider};
+use datafusion::common::Column;
 use datafusion::error::DataFusionError;
 use datafusion::logical_expr::{Expr, TableType};
 use datafusion::physical_plan::ExecutionPlan;
 use std::sync::Arc;
 
-/// SchemaProvider that exposes the bundle's cached dataframe as a "bundle" table
+/// SchemaProvider that exposes the bundle's cached dataframe as a "bundle" table,
+/// and each attached bundle under the name it was attached as
 #[derive(Debug)]
 pub struct BundleSchemaProvider {
     dataframe: DataFrameHolder,
+    external_bundles: ExternalBundles,
 }
 
 impl BundleSchemaProvider {
-    pub fn new(dataframe: DataFrameHolder) -> Self {
-        Self { dataframe }
+    pub(crate) fn new(dataframe: DataFrameHolder, external_bundles: ExternalBundles) -> Self {
+        Self {
+            dataframe,
+            external_bundles,
+        }
     }
 }
 
@@ -27,7 +33,9 @@ impl SchemaProvider for BundleSchemaProvider {
     }
 
     fn table_names(&self) -> Vec<String> {
-        vec![catalog::DATAFRAME_ALIAS.to_string()]
+        let mut names = vec![catalog::DATAFRAME_ALIAS.to_string()];
+        names.extend(self.external_bundles.read().keys().cloned());
+        names
     }
 
     async fn table(&self, name: &str) -> datafusion::error::Result<Option<Arc<dyn TableProvider>>> {
@@ -36,12 +44,17 @@ impl SchemaProvider for BundleSchemaProvider {
                 self.dataframe.clone(),
             ))))
         } else {
-            Ok(None)
+            // Clone out of the lock so it isn't held while the bundle is opened
+            let external = self.external_bundles.read().get(name).cloned();
+            match external {
+                Some(external) => Ok(Some(external.table().await?)),
+                None => Ok(None),
+            }
         }
     }
 
     fn table_exist(&self, name: &str) -> bool {
-        name == catalog::DATAFRAME_ALIAS
+        name == catalog::DATAFRAME_ALIAS || self.external_bundles.read().contains_key(name)
     }
 }
 
@@ -93,9 +106,10 @@ impl TableProvider for CachedDataFrameTable {
         // Apply projection if specified
         if let Some(proj_indices) = projection {
             let schema = df_filtered.schema();
+            // Use the exact column, `col()` would parse the name and lowercase it
             let proj_exprs: Vec<Expr> = proj_indices
                 .iter()
-                .map(|&i| datafusion::logical_expr::col(schema.field(i).name()))
+                .map(|&i| Expr::Column(Column::from(schema.qualified_field(i))))
                 .collect();
             df_filtered = df_filtered.select(proj_exprs)?;
         }
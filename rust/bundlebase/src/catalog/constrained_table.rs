@@ -0,0 +1,179 @@
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use arrow_schema::SchemaRef;
+use async_trait::async_trait;
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::Column;
+use datafusion::dataframe::DataFrame;
+use datafusion::datasource::{source_as_provider, TableType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{Expr, LogicalPlan, TableProviderFilterPushDown, TableScan};
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties};
+use futures::StreamExt;
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// TableProvider that reads a DataFrame under a schema carrying the bundle's declared
+/// constraints, so non-nullable columns are non-nullable in the plan as well as in `schema()`.
+///
+/// Batches are relabelled as they are read, which fails if a non-nullable column holds nulls.
+#[derive(Debug)]
+pub(crate) struct ConstrainedTable {
+    dataframe: DataFrame,
+    schema: SchemaRef,
+}
+
+impl ConstrainedTable {
+    pub(crate) fn new(dataframe: DataFrame, schema: SchemaRef) -> Self {
+        Self { dataframe, schema }
+    }
+
+    /// The plan a scan of a ConstrainedTable reads from, so code tracing columns back to
+    /// their blocks can look through it
+    pub(crate) fn wrapped_plan(scan: &TableScan) -> Option<LogicalPlan> {
+        let provider = source_as_provider(&scan.source).ok()?;
+        let table = provider.as_any().downcast_ref::<ConstrainedTable>()?;
+        Some(table.dataframe.logical_plan().clone())
+    }
+}
+
+#[async_trait]
+impl TableProvider for ConstrainedTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::View
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        // Passed on to the wrapped dataframe so indexes and pruning still see them
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut df = self.dataframe.clone();
+        for filter in filters {
+            df = df.filter(filter.clone())?;
+        }
+
+        let schema = match projection {
+            Some(indices) => {
+                let df_schema = df.schema();
+                // Use the exact column, `col()` would parse the name and lowercase it
+                let exprs: Vec<Expr> = indices
+                    .iter()
+                    .map(|&i| Expr::Column(Column::from(df_schema.qualified_field(i))))
+                    .collect();
+                df = df.select(exprs)?;
+                Arc::new(self.schema.project(indices)?)
+            }
+            None => self.schema.clone(),
+        };
+
+        if let Some(n) = limit {
+            df = df.limit(0, Some(n))?;
+        }
+
+        let input = state.create_physical_plan(df.logical_plan()).await?;
+        Ok(Arc::new(ConstrainedExec::new(input, schema)))
+    }
+}
+
+/// Passes its input's batches through under the constrained schema
+#[derive(Debug)]
+struct ConstrainedExec {
+    input: Arc<dyn ExecutionPlan>,
+    schema: SchemaRef,
+    properties: PlanProperties,
+}
+
+impl ConstrainedExec {
+    fn new(input: Arc<dyn ExecutionPlan>, schema: SchemaRef) -> Self {
+        let properties = input
+            .properties()
+            .clone()
+            .with_eq_properties(EquivalenceProperties::new(schema.clone()));
+        Self {
+            input,
+            schema,
+            properties,
+        }
+    }
+}
+
+impl DisplayAs for ConstrainedExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ConstrainedExec")
+    }
+}
+
+impl ExecutionPlan for ConstrainedExec {
+    fn name(&self) -> &str {
+        "ConstrainedExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.as_slice() {
+            [input] => Ok(Arc::new(Self::new(input.clone(), self.schema.clone()))),
+            _ => Err(DataFusionError::Internal(
+                "ConstrainedExec takes exactly one child".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let schema = self.schema.clone();
+        let batches = self.input.execute(partition, context)?.map(move |batch| {
+            let batch = batch?;
+            // The row count keeps batches without columns, such as for `count()`
+            let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+            RecordBatch::try_new_with_options(schema.clone(), batch.columns().to_vec(), &options)
+                .map_err(|e| DataFusionError::ArrowError(Box::new(e), None))
+        });
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            batches,
+        )))
+    }
+}
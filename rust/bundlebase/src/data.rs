@@ -1,3 +1,4 @@
+mod block_stats;
 mod data_block;
 mod data_pack;
 mod object_id;
@@ -15,6 +16,7 @@ use crate::io::ObjectStoreDir;
 use crate::BundlebaseError;
 use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
+pub use block_stats::ColumnStats;
 pub use data_block::DataBlock;
 pub use data_pack::DataPack;
 use datafusion::common::{DataFusionError, Statistics};
@@ -51,6 +53,16 @@ pub trait DataReader: Sync + Send + Debug {
 
     async fn read_version(&self) -> Result<String, BundlebaseError>;
 
+    /// Size in bytes of the file being read, or `None` if the reader isn't backed by a file
+    async fn read_size(&self) -> Result<Option<u64>, BundlebaseError> {
+        Ok(None)
+    }
+
+    /// Compression the file is read with (e.g. "gzip"), or `None` if it isn't compressed
+    fn compression(&self) -> Option<String> {
+        None
+    }
+
     async fn data_source(
         &self,
         projection: Option<&Vec<usize>>,
@@ -59,6 +71,23 @@ pub trait DataReader: Sync + Send + Debug {
         row_ids: Option<&[RowId]>,
     ) -> Result<Arc<dyn DataSource>, DataFusionError>;
 
+    /// Number of rows in each row group of the file, or `None` if the format has no row groups
+    async fn read_row_groups(&self) -> Result<Option<Vec<usize>>, BundlebaseError> {
+        Ok(None)
+    }
+
+    /// Limits a source returned by `data_source` to the given row groups of the file
+    async fn select_row_groups(
+        &self,
+        _source: Arc<dyn DataSource>,
+        _row_groups: &[usize],
+    ) -> Result<Arc<dyn DataSource>, DataFusionError> {
+        Err(DataFusionError::NotImplemented(format!(
+            "Row groups can't be selected from {}",
+            self.url()
+        )))
+    }
+
     async fn build_layout(
         &self,
         _data_dir: &ObjectStoreDir,
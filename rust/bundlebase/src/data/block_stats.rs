@@ -0,0 +1,259 @@
+use crate::index::{FilterAnalyzer, IndexPredicate, IndexedValue};
+use crate::BundlebaseError;
+use arrow::datatypes::{DataType, SchemaRef};
+use datafusion::common::ScalarValue;
+use datafusion::datasource::source::DataSource;
+use datafusion::functions_aggregate::min_max::{MaxAccumulator, MinAccumulator};
+use datafusion::logical_expr::{Accumulator, Expr};
+use datafusion::prelude::SessionContext;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Zone map of one column of a block: the smallest and largest value it holds and how many of
+/// its values are null. Computed when the block is attached and used to skip the block when a
+/// filter can't match any of its rows.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnStats {
+    pub column: String,
+    /// Smallest non-null value, as text. `None` if all values are null or the column's type
+    /// isn't ordered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<String>,
+    /// Largest non-null value, as text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<String>,
+    pub null_count: usize,
+}
+
+impl ColumnStats {
+    /// Computes the stats of every column by reading all rows of `source`
+    pub(crate) async fn compute(
+        source: Arc<dyn DataSource>,
+        schema: &SchemaRef,
+        ctx: Arc<SessionContext>,
+    ) -> Result<Vec<ColumnStats>, BundlebaseError> {
+        let mut columns = Vec::with_capacity(schema.fields().len());
+        for field in schema.fields() {
+            let bounds = if has_bounds(field.data_type()) {
+                Some((
+                    MinAccumulator::try_new(field.data_type())?,
+                    MaxAccumulator::try_new(field.data_type())?,
+                ))
+            } else {
+                None
+            };
+            columns.push((bounds, 0));
+        }
+
+        let partitions = source.output_partitioning().partition_count();
+        for partition in 0..partitions {
+            let mut stream = source.open(partition, ctx.task_ctx())?;
+            while let Some(batch) = stream.next().await {
+                let batch = batch?;
+                for ((bounds, null_count), array) in columns.iter_mut().zip(batch.columns()) {
+                    *null_count += array.null_count();
+                    if let Some((min, max)) = bounds {
+                        min.update_batch(std::slice::from_ref(array))?;
+                        max.update_batch(std::slice::from_ref(array))?;
+                    }
+                }
+            }
+        }
+
+        let mut stats = Vec::with_capacity(columns.len());
+        for (field, (bounds, null_count)) in schema.fields().iter().zip(columns) {
+            let (min, max) = match bounds {
+                Some((mut min, mut max)) => (as_text(min.evaluate()?)?, as_text(max.evaluate()?)?),
+                None => (None, None),
+            };
+            stats.push(ColumnStats {
+                column: field.name().clone(),
+                min,
+                max,
+                null_count,
+            });
+        }
+        Ok(stats)
+    }
+
+    /// Whether the block these stats describe has no rows that can pass all of `filters`
+    pub(crate) fn excludes(stats: &[ColumnStats], schema: &SchemaRef, filters: &[Expr]) -> bool {
+        let find = |column: &str| stats.iter().find(|s| s.column == column);
+
+        let by_range = FilterAnalyzer::extract_indexable(filters)
+            .into_iter()
+            .any(|filter| {
+                let Some(column_stats) = find(&filter.column) else {
+                    return false;
+                };
+                let Ok(field) = schema.field_with_name(&filter.column) else {
+                    return false;
+                };
+                let (Some(min), Some(max)) = (
+                    column_stats.bound(&column_stats.min, field.data_type()),
+                    column_stats.bound(&column_stats.max, field.data_type()),
+                ) else {
+                    return false;
+                };
+                let outside = |value: &IndexedValue| value < &min || value > &max;
+                match &filter.predicate {
+                    IndexPredicate::Exact(value) => outside(value),
+                    IndexPredicate::In(values) => values.iter().all(outside),
+                    IndexPredicate::Range {
+                        min: low,
+                        max: high,
+                    } => high < &min || low > &max,
+                    IndexPredicate::Prefix(_) => false,
+                }
+            });
+
+        // `col IS NULL` can't match a block without nulls in the column
+        by_range
+            || filters.iter().any(|filter| match filter {
+                Expr::IsNull(inner) => match inner.as_ref() {
+                    Expr::Column(column) => find(&column.name).is_some_and(|s| s.null_count == 0),
+                    _ => false,
+                },
+                _ => false,
+            })
+    }
+
+    /// Parses a recorded min or max back into a value comparable with filter literals
+    fn bound(&self, text: &Option<String>, data_type: &DataType) -> Option<IndexedValue> {
+        let text = text.as_ref()?;
+        if matches!(
+            data_type,
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+        ) {
+            return Some(IndexedValue::Utf8(text.clone()));
+        }
+        let scalar = ScalarValue::try_from_string(text.clone(), data_type).ok()?;
+        IndexedValue::from_scalar(&scalar).ok()
+    }
+}
+
+/// Whether min and max are recorded for columns of this type. Limited to the types filter
+/// literals are compared as, see `IndexedValue::from_scalar`
+fn has_bounds(data_type: &DataType) -> bool {
+    match data_type {
+        // Doesn't fit in the i64 values are compared as
+        DataType::UInt64 => false,
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Utf8View
+        | DataType::Boolean
+        | DataType::Timestamp(_, _) => true,
+        _ => false,
+    }
+}
+
+fn as_text(value: ScalarValue) -> Result<Option<String>, BundlebaseError> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    match value.cast_to(&DataType::Utf8)? {
+        ScalarValue::Utf8(text) => Ok(text),
+        other => Err(format!("Cannot record {} as a block statistic", other).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::datasource::memory::MemorySourceConfig;
+    use datafusion::prelude::{col, lit};
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("value", DataType::Int32, true),
+            Field::new("name", DataType::Utf8, true),
+        ]))
+    }
+
+    fn stats() -> Vec<ColumnStats> {
+        vec![
+            ColumnStats {
+                column: "value".to_string(),
+                min: Some("10".to_string()),
+                max: Some("20".to_string()),
+                null_count: 0,
+            },
+            ColumnStats {
+                column: "name".to_string(),
+                min: Some("b".to_string()),
+                max: Some("d".to_string()),
+                null_count: 1,
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_compute() -> Result<(), BundlebaseError> {
+        let batch = RecordBatch::try_new(
+            schema(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(15), None, Some(-3), Some(8)])),
+                Arc::new(StringArray::from(vec![None::<&str>; 4])),
+            ],
+        )?;
+        let source = MemorySourceConfig::try_new(&[vec![batch]], schema(), None)?;
+
+        let stats =
+            ColumnStats::compute(Arc::new(source), &schema(), Arc::new(SessionContext::new()))
+                .await?;
+
+        assert_eq!(
+            vec![
+                ColumnStats {
+                    column: "value".to_string(),
+                    min: Some("-3".to_string()),
+                    max: Some("15".to_string()),
+                    null_count: 1,
+                },
+                ColumnStats {
+                    column: "name".to_string(),
+                    min: None,
+                    max: None,
+                    null_count: 4,
+                },
+            ],
+            stats
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_excludes() {
+        let excludes = |filter: Expr| ColumnStats::excludes(&stats(), &schema(), &[filter]);
+
+        assert!(excludes(col("value").gt(lit(25))));
+        assert!(excludes(col("value").lt_eq(lit(5))));
+        assert!(excludes(col("value").eq(lit(21))));
+        assert!(excludes(col("value").in_list(vec![lit(1), lit(30)], false)));
+        assert!(excludes(col("name").eq(lit("a"))));
+        assert!(excludes(col("value").is_null()));
+
+        assert!(!excludes(col("value").gt(lit(15))));
+        assert!(!excludes(col("value").eq(lit(10))));
+        assert!(!excludes(
+            col("value").in_list(vec![lit(1), lit(20)], false)
+        ));
+        assert!(!excludes(col("name").gt(lit("c"))));
+        assert!(!excludes(col("name").is_null()));
+        assert!(!excludes(col("other").gt(lit(100))));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::data::{DataReader, VersionedBlockId};
+use crate::data::{ColumnStats, DataReader, RowId, VersionedBlockId};
 use crate::index::{
     ColumnIndex, FilterAnalyzer, IndexDefinition, IndexPredicate, IndexSelector, IndexableFilter,
 };
@@ -9,16 +9,19 @@ use arrow_schema::SchemaRef;
 use async_trait::async_trait;
 use datafusion::catalog::memory::DataSourceExec;
 use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::project_schema;
+use datafusion::datasource::source::DataSource;
 use datafusion::datasource::TableType;
 use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::empty::EmptyExec;
 use datafusion::physical_plan::ExecutionPlan;
 use parking_lot::RwLock;
 use std::any::Any;
 use std::sync::Arc;
 
 /// Candidate index for a query with its estimated selectivity
-struct IndexCandidate<'a> {
-    filter: &'a IndexableFilter,
+struct IndexCandidate {
+    filter: IndexableFilter,
     index_def: Arc<IndexDefinition>,
     index_path: String,
     selectivity: f64,
@@ -34,6 +37,9 @@ pub struct DataBlock {
     indexes: Arc<RwLock<Vec<Arc<IndexDefinition>>>>,
     data_dir: Arc<ObjectStoreDir>,
     config: Arc<BundleConfig>,
+    partitions: Option<usize>,
+    row_groups: Option<Vec<usize>>,
+    stats: Option<Vec<ColumnStats>>,
 }
 
 impl DataBlock {
@@ -67,6 +73,47 @@ impl DataBlock {
             indexes,
             data_dir,
             config,
+            partitions: None,
+            row_groups: None,
+            stats: None,
+        }
+    }
+
+    /// Scans the block as exactly `partitions` byte ranges instead of letting the query planner
+    /// decide. Parquet ranges are aligned to row groups. `None` leaves the choice to the planner.
+    pub fn with_partitions(mut self, partitions: Option<usize>) -> Self {
+        self.partitions = partitions;
+        self
+    }
+
+    /// Scans only these row groups of the file. `None` scans them all.
+    pub fn with_row_groups(mut self, row_groups: Option<Vec<usize>>) -> Self {
+        self.row_groups = row_groups;
+        self
+    }
+
+    /// Per-column min/max recorded when the block was attached, used to skip the block when
+    /// filters rule out all of its rows. `None` always scans it.
+    pub fn with_stats(mut self, stats: Option<Vec<ColumnStats>>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// The reader's data source, limited to the selected row groups
+    async fn data_source(
+        &self,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+        row_ids: Option<&[RowId]>,
+    ) -> datafusion::common::Result<Arc<dyn DataSource>> {
+        let source = self
+            .reader
+            .data_source(projection, filters, limit, row_ids)
+            .await?;
+        match &self.row_groups {
+            Some(row_groups) => self.reader.select_row_groups(source, row_groups).await,
+            None => Ok(source),
         }
     }
 
@@ -164,9 +211,14 @@ impl DataBlock {
                 row_ids
             }
             IndexPredicate::Range { min, max } => index.lookup_range(min, max),
+            IndexPredicate::Prefix(prefix) => index.lookup_prefix(prefix),
         };
 
-        Ok(row_ids)
+        // An index can span several blocks, so only keep the rows stored in this one
+        Ok(row_ids
+            .into_iter()
+            .filter(|row_id| row_id.block_id() == self.id)
+            .collect())
     }
 
     pub fn schema(&self) -> SchemaRef {
@@ -182,59 +234,88 @@ impl DataBlock {
         self.reader.clone()
     }
 
+    /// Returns the config the block was read with
+    pub(crate) fn config(&self) -> &BundleConfig {
+        &self.config
+    }
+
     /// Evaluate all indexable filters and select the most selective index
     /// Returns None if no suitable index is found or all have selectivity above threshold
-    async fn select_best_index<'a>(
+    async fn select_best_index(
         &self,
-        indexable_filters: &'a [IndexableFilter],
+        indexable_filters: &[IndexableFilter],
+        filters: &[Expr],
         versioned_block: &VersionedBlockId,
-    ) -> Option<IndexCandidate<'a>> {
+    ) -> Option<IndexCandidate> {
         let mut candidates = Vec::new();
 
-        // Evaluate each indexable filter
-        for filter in indexable_filters {
-            // Try to find an index for this column
-            if let Some(index_def) =
+        // Pair each indexable filter with an index on its column
+        let mut usable: Vec<(IndexableFilter, Arc<IndexDefinition>)> = indexable_filters
+            .iter()
+            .filter_map(|filter| {
                 IndexSelector::select_index_from_ref(&filter.column, versioned_block, &self.indexes)
+                    .map(|index_def| (filter.clone(), index_def))
+            })
+            .collect();
+
+        // Composite indexes are usable when the filters cover a prefix of their columns
+        let composites: Vec<Arc<IndexDefinition>> = self
+            .indexes
+            .read()
+            .iter()
+            .filter(|index_def| index_def.is_composite())
+            .cloned()
+            .collect();
+        for index_def in composites {
+            if let Some(predicate) = FilterAnalyzer::extract_composite(index_def.columns(), filters)
             {
-                // Get the index file path
-                if let Some(indexed_blocks) = index_def.indexed_blocks(versioned_block) {
-                    let index_path = indexed_blocks.path();
-
-                    // Check selectivity
-                    match self
-                        .check_index_selectivity(index_path, &filter.column, &filter.predicate)
-                        .await
-                    {
-                        Ok(Some(selectivity)) => {
-                            // This index is usable - add to candidates
-                            log::debug!(
-                                "Index candidate on column '{}': selectivity {:.1}%",
-                                filter.column,
-                                selectivity * 100.0
-                            );
-                            candidates.push(IndexCandidate {
-                                filter,
-                                index_def,
-                                index_path: index_path.to_string(),
-                                selectivity,
-                            });
-                        }
-                        Ok(None) => {
-                            // Selectivity too high - skip this index
-                            log::debug!(
-                                "Skipping index on column '{}' (selectivity too high)",
-                                filter.column
-                            );
-                        }
-                        Err(e) => {
-                            // Selectivity check failed - skip this index
-                            log::debug!(
-                                "Skipping index on column '{}' (selectivity check failed: {})",
-                                filter.column,
-                                e
-                            );
-                        }
+                let filter = IndexableFilter {
+                    column: index_def.columns().join(", "),
+                    predicate,
+                };
+                usable.push((filter, index_def));
+            }
+        }
+
+        // Evaluate each usable index
+        for (filter, index_def) in usable {
+            // Get the index file path
+            if let Some(indexed_blocks) = index_def.indexed_blocks(versioned_block) {
+                let index_path = indexed_blocks.path();
+
+                // Check selectivity
+                match self
+                    .check_index_selectivity(index_path, &filter.column, &filter.predicate)
+                    .await
+                {
+                    Ok(Some(selectivity)) => {
+                        // This index is usable - add to candidates
+                        log::debug!(
+                            "Index candidate on column '{}': selectivity {:.1}%",
+                            filter.column,
+                            selectivity * 100.0
+                        );
+                        candidates.push(IndexCandidate {
+                            filter,
+                            index_def,
+                            index_path: index_path.to_string(),
+                            selectivity,
+                        });
+                    }
+                    Ok(None) => {
+                        // Selectivity too high - skip this index
+                        log::debug!(
+                            "Skipping index on column '{}' (selectivity too high)",
+                            filter.column
+                        );
+                    }
+                    Err(e) => {
+                        // Selectivity check failed - skip this index
+                        log::debug!(
+                            "Skipping index on column '{}' (selectivity check failed: {})",
+                            filter.column,
+                            e
+                        );
                     }
                 }
             }
@@ -270,6 +351,19 @@ impl TableProvider for DataBlock {
         filters: &[Expr],
         limit: Option<usize>,
     ) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
+        // Phase 0: Skip the block if its zone maps show no row can match
+        if let Some(stats) = &self.stats {
+            if ColumnStats::excludes(stats, &self.schema, filters) {
+                log::debug!(
+                    "Skipping block {}: no rows can match {:?}",
+                    self.id,
+                    filters
+                );
+                let schema = project_schema(&self.schema, projection)?;
+                return Ok(Arc::new(EmptyExec::new(schema)));
+            }
+        }
+
         // Phase 1: Try index optimization
         let indexable_filters = FilterAnalyzer::extract_indexable(filters);
 
@@ -279,7 +373,7 @@ impl TableProvider for DataBlock {
 
             // Evaluate all indexable filters and select the best index
             if let Some(best) = self
-                .select_best_index(&indexable_filters, &versioned_block)
+                .select_best_index(&indexable_filters, filters, &versioned_block)
                 .await
             {
                 // Start span and timer for index lookup
@@ -329,10 +423,8 @@ impl TableProvider for DataBlock {
 
                         // Use optimized data source with row IDs
                         let exec = DataSourceExec::new(
-                            self.reader
-                                .data_source(projection, filters, limit, Some(&row_ids))
-                                .await?
-                                .clone(),
+                            self.data_source(projection, filters, limit, Some(&row_ids))
+                                .await?,
                         );
                         return Ok(Arc::new(exec));
                     }
@@ -358,13 +450,14 @@ impl TableProvider for DataBlock {
         }
 
         // Phase 2: Fall back to full scan
-        let exec = DataSourceExec::new(
-            self.reader
-                .data_source(projection, filters, limit, None)
-                .await?
-                .clone(),
-        );
-        Ok(Arc::new(exec))
+        let source = self.data_source(projection, filters, limit, None).await?;
+
+        // Ranged files are never split again by the planner, so this also pins a single partition
+        let source = match self.partitions {
+            Some(partitions) => source.repartitioned(partitions, 0, None)?.unwrap_or(source),
+            None => source,
+        };
+        Ok(Arc::new(DataSourceExec::new(source)))
     }
 }
 
@@ -44,6 +44,21 @@ impl DataPack {
         self.blocks.write().push(block);
     }
 
+    /// Replaces the block with the same id, returning false if the pack doesn't have it
+    pub fn replace_block(&self, block: Arc<DataBlock>) -> bool {
+        let mut blocks = self.blocks.write();
+        match blocks
+            .iter_mut()
+            .find(|existing| existing.id() == block.id())
+        {
+            Some(existing) => {
+                *existing = block;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn blocks(&self) -> Vec<Arc<DataBlock>> {
         self.blocks.read().clone()
     }
@@ -2,6 +2,7 @@ mod csv_reader;
 mod file_reader;
 mod function_reader;
 mod json_reader;
+mod null_regex_source;
 mod parquet_reader;
 
 #[cfg(test)]
@@ -11,6 +12,7 @@ use crate::data::DataReader;
 use arrow_schema::SchemaRef;
 use async_trait::async_trait;
 pub use csv_reader::CsvPlugin;
+pub use file_reader::strip_compression;
 pub use function_reader::DataGenerator;
 pub use function_reader::FunctionPlugin;
 pub use json_reader::JsonPlugin;
@@ -32,5 +34,6 @@ pub trait ReaderPlugin: Send + Sync {
         bundle: &Bundle,
         schema: Option<SchemaRef>,
         layout: Option<String>,
+        format: Option<&str>,
     ) -> Result<Option<Arc<dyn DataReader>>, BundlebaseError>;
 }
@@ -1,5 +1,9 @@
+use crate::bundle_config::BundleConfig;
 use crate::data::object_id::ObjectId;
-use crate::data::plugin::file_reader::{FileFormatConfig, FilePlugin, FileReader};
+use crate::data::plugin::file_reader::{
+    compression_name, FileFormatConfig, FilePlugin, FileReader,
+};
+use crate::data::plugin::null_regex_source::NullRegexSource;
 use crate::data::plugin::ReaderPlugin;
 use crate::data::{DataReader, LayoutRowIdProvider, LineOrientedFormat, RowId, RowIdProvider};
 use crate::index::RowIdIndex;
@@ -10,45 +14,109 @@ use async_trait::async_trait;
 use datafusion::common::stats::Precision;
 use datafusion::common::{DataFusionError, Statistics};
 use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
 use datafusion::datasource::file_format::FileFormat;
 use datafusion::datasource::physical_plan::{CsvSource, FileSource};
 use datafusion::datasource::source::DataSource;
 use datafusion::logical_expr::Expr;
 use futures::stream::StreamExt;
+use regex::Regex;
 use std::sync::Arc;
 use url::Url;
 
 /// Configuration for CSV format
-#[derive(Debug, Clone, Default)]
-pub struct CsvFormatConfig;
+#[derive(Debug, Clone)]
+pub struct CsvFormatConfig {
+    delimiter: u8,
+    /// Fields matching this are read as null, see `csv_null_regex`
+    null_regex: Option<Regex>,
+}
+
+impl CsvFormatConfig {
+    /// Configuration for tab-delimited CSV (TSV) files
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: b'\t',
+            null_regex: None,
+        }
+    }
+}
+
+impl Default for CsvFormatConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            null_regex: None,
+        }
+    }
+}
 
 impl FileFormatConfig for CsvFormatConfig {
+    fn name(&self) -> &'static str {
+        match self.delimiter {
+            b'\t' => "tsv",
+            _ => "csv",
+        }
+    }
+
     fn extension(&self) -> &'static str {
-        ".csv"
+        match self.delimiter {
+            b'\t' => ".tsv",
+            _ => ".csv",
+        }
     }
 
-    fn file_format(&self) -> Arc<dyn FileFormat> {
-        Arc::new(CsvFormat::default())
+    fn file_format(&self, compression: FileCompressionType) -> Arc<dyn FileFormat> {
+        Arc::new(
+            CsvFormat::default()
+                .with_delimiter(self.delimiter)
+                .with_null_regex(self.null_regex.as_ref().map(|r| r.as_str().to_string()))
+                .with_file_compression_type(compression),
+        )
     }
 
     fn file_source(&self) -> Arc<dyn FileSource> {
-        Arc::new(CsvSource::new(true, b',', b'"'))
+        let source = Arc::new(CsvSource::new(true, self.delimiter, b'"'));
+        match &self.null_regex {
+            Some(regex) => Arc::new(NullRegexSource::new(source, regex.clone())),
+            None => source,
+        }
     }
 
     fn line_oriented_format(&self) -> Option<LineOrientedFormat> {
-        Some(LineOrientedFormat::Csv)
+        // Rows read by offset are parsed without the null regex, so scan the file instead
+        if self.null_regex.is_some() {
+            return None;
+        }
+        match self.delimiter {
+            b'\t' => Some(LineOrientedFormat::Tsv),
+            _ => Some(LineOrientedFormat::Csv),
+        }
+    }
+
+    fn compressible(&self) -> bool {
+        true
+    }
+
+    fn with_bundle_config(&self, config: &BundleConfig) -> Result<Self, BundlebaseError> {
+        Ok(Self {
+            null_regex: config.csv_null_regex()?,
+            ..self.clone()
+        })
     }
 }
 
-/// CSV plugin - uses generic FilePlugin and creates CsvReader
+/// CSV plugin - uses generic FilePlugin and creates CsvReader for comma and tab-delimited files
 pub struct CsvPlugin {
     inner: FilePlugin<CsvFormatConfig>,
+    tsv: FilePlugin<CsvFormatConfig>,
 }
 
 impl Default for CsvPlugin {
     fn default() -> Self {
         Self {
             inner: FilePlugin::default(),
+            tsv: FilePlugin::new(CsvFormatConfig::tsv()),
         }
     }
 }
@@ -62,12 +130,17 @@ impl ReaderPlugin for CsvPlugin {
         bundle: &Bundle,
         schema: Option<SchemaRef>,
         layout: Option<String>,
+        format: Option<&str>,
     ) -> Result<Option<Arc<dyn DataReader>>, BundlebaseError> {
-        if !self.inner.handles(source) {
+        let plugin = if self.inner.handles(source, format) {
+            &self.inner
+        } else if self.tsv.handles(source, format) {
+            &self.tsv
+        } else {
             return Ok(None);
-        }
+        };
 
-        let reader = self.inner.reader(source, bundle, schema).await?;
+        let reader = plugin.reader(source, bundle, schema).await?;
         let layout = match layout {
             None => None,
             Some(x) => Some(ObjectStoreFile::from_str(
@@ -148,6 +221,14 @@ impl DataReader for CsvReader {
         self.inner.version().await
     }
 
+    async fn read_size(&self) -> Result<Option<u64>, BundlebaseError> {
+        self.inner.size().await
+    }
+
+    fn compression(&self) -> Option<String> {
+        compression_name(self.inner.compression())
+    }
+
     async fn read_statistics(&self) -> Result<Option<Statistics>, BundlebaseError> {
         let (num_rows, file_bytes) = self.compute_statistics().await?;
 
@@ -163,6 +244,11 @@ impl DataReader for CsvReader {
         &self,
         data_dir: &ObjectStoreDir,
     ) -> Result<Option<String>, BundlebaseError> {
+        // Row offsets can't be used to read into a compressed file
+        if self.inner.compression().is_compressed() {
+            return Ok(None);
+        }
+
         let index = RowIdIndex::new()
             .build(&self.inner.file(), data_dir, &self.block_id(), true)
             .await?;
@@ -171,10 +257,13 @@ impl DataReader for CsvReader {
     }
 
     fn rowid_provider(&self) -> Result<Arc<dyn RowIdProvider>, BundlebaseError> {
-        Ok(self
-            .rowid_provider
-            .clone()
-            .expect("CSV rowid_generator requires a layout file".into()))
+        self.rowid_provider.clone().ok_or_else(|| {
+            format!(
+                "Cannot index {}: CSV row ids require a layout file, which compressed files don't have",
+                self.url()
+            )
+            .into()
+        })
     }
 }
 
@@ -201,8 +290,9 @@ impl CsvReader {
             content.extend_from_slice(&chunk);
         }
 
-        // Get file size
+        // Get file size, then count rows in the decompressed content
         let file_size = content.len();
+        let content = self.inner.decompress(content)?;
 
         // Count newlines to determine number of rows (including header)
         let mut row_count = content.iter().filter(|&&b| b == b'\n').count();
@@ -234,7 +324,14 @@ mod tests {
 
         let binding = Bundle::empty().await?;
         let result = plugin
-            .reader("file:///test.parquet", &1.into(), &binding, None, None)
+            .reader(
+                "file:///test.parquet",
+                &1.into(),
+                &binding,
+                None,
+                None,
+                None,
+            )
             .await?;
 
         assert!(result.is_none());
@@ -248,7 +345,7 @@ mod tests {
 
         let binding = Bundle::empty().await?;
         let invalid_reader = plugin
-            .reader("file:///invalid.csv", &1.into(), &binding, None, None)
+            .reader("file:///invalid.csv", &1.into(), &binding, None, None, None)
             .await?;
 
         assert!(invalid_reader.is_some());
@@ -276,6 +373,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .unwrap();
@@ -317,6 +415,7 @@ mod tests {
                 &binding,
                 Some(schema),
                 None,
+                None,
             )
             .await?
             .unwrap();
@@ -376,6 +475,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .unwrap();
@@ -421,6 +521,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .unwrap();
@@ -475,7 +576,7 @@ mod tests {
         // First, create a reader to build the layout
         let csv_url = test_datafile("customers-0-100.csv");
         let temp_reader = plugin
-            .reader(csv_url, &block_id, binding, None, None)
+            .reader(csv_url, &block_id, binding, None, None, None)
             .await?
             .unwrap();
 
@@ -498,6 +599,7 @@ mod tests {
                 binding,
                 schema,
                 Some(layout_file.url().as_str().to_string()),
+                None,
             )
             .await?
             .unwrap();
@@ -568,7 +670,7 @@ mod tests {
         // First, create a reader to build the layout
         let csv_url = test_datafile("customers-0-100.csv");
         let temp_reader = plugin
-            .reader(csv_url, &block_id, binding, None, None)
+            .reader(csv_url, &block_id, binding, None, None, None)
             .await?
             .unwrap();
 
@@ -591,6 +693,7 @@ mod tests {
                 binding,
                 schema,
                 Some(layout_file.url().as_str().to_string()),
+                None,
             )
             .await?
             .unwrap();
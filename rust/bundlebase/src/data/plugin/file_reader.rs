@@ -1,24 +1,65 @@
+use crate::bundle_config::BundleConfig;
 use crate::data::{LineOrientedFormat, RowId, RowIdOffsetDataSource};
 use crate::io::ObjectStoreFile;
 use crate::{Bundle, BundlebaseError};
 use arrow::datatypes::SchemaRef;
+use datafusion::common::parsers::CompressionTypeVariant;
 use datafusion::common::DataFusionError;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
 use datafusion::datasource::file_format::FileFormat;
 use datafusion::datasource::listing::PartitionedFile;
 use datafusion::datasource::physical_plan::{FileScanConfigBuilder, FileSource};
 use datafusion::datasource::source::DataSource;
 use datafusion::logical_expr::Expr;
 use datafusion::prelude::SessionContext;
+use std::io::Read;
 use std::sync::Arc;
 use url::Url;
 
+/// Suffixes of compressed files, which can follow the format's extension (e.g. `data.csv.gz`)
+const COMPRESSION_EXTENSIONS: [(&str, CompressionTypeVariant); 3] = [
+    (".gz", CompressionTypeVariant::GZIP),
+    (".zst", CompressionTypeVariant::ZSTD),
+    (".bz2", CompressionTypeVariant::BZIP2),
+];
+
+/// The compression of `source`, detected from its suffix
+pub fn source_compression(source: &str) -> FileCompressionType {
+    let lowercase = source.to_lowercase();
+    COMPRESSION_EXTENSIONS
+        .iter()
+        .find(|(suffix, _)| lowercase.ends_with(suffix))
+        .map(|(_, variant)| FileCompressionType::from(*variant))
+        .unwrap_or(FileCompressionType::UNCOMPRESSED)
+}
+
+/// `source` without its compression suffix, if it has one
+pub fn strip_compression(source: &str) -> &str {
+    let lowercase = source.to_lowercase();
+    COMPRESSION_EXTENSIONS
+        .iter()
+        .find(|(suffix, _)| lowercase.ends_with(suffix))
+        .map(|(suffix, _)| &source[..source.len() - suffix.len()])
+        .unwrap_or(source)
+}
+
+/// Name a compression is recorded as in the manifest (e.g. "gzip"), or `None` if uncompressed
+pub fn compression_name(compression: FileCompressionType) -> Option<String> {
+    compression
+        .is_compressed()
+        .then(|| compression.get_variant().to_string().to_lowercase())
+}
+
 /// Configuration for a file-based format (CSV, JSON, Parquet, etc.)
 pub trait FileFormatConfig: Send + Sync + Default + Clone {
+    /// Format name used to select this format regardless of extension (e.g., "csv")
+    fn name(&self) -> &'static str;
+
     /// File extension this format handles (e.g., ".csv")
     fn extension(&self) -> &'static str;
 
     /// Get the FileFormat object for schema inference
-    fn file_format(&self) -> Arc<dyn FileFormat>;
+    fn file_format(&self, compression: FileCompressionType) -> Arc<dyn FileFormat>;
 
     /// Get the FileSource for this format (e.g., CsvSource, JsonSource, ParquetSource)
     fn file_source(&self) -> Arc<dyn FileSource>;
@@ -28,6 +69,16 @@ pub trait FileFormatConfig: Send + Sync + Default + Clone {
     fn line_oriented_format(&self) -> Option<LineOrientedFormat> {
         None
     }
+
+    /// Whether files in this format can be read gzip, zstd or bzip2 compressed
+    fn compressible(&self) -> bool {
+        false
+    }
+
+    /// This format with any reading options set in the bundle's config applied
+    fn with_bundle_config(&self, _config: &BundleConfig) -> Result<Self, BundlebaseError> {
+        Ok(self.clone())
+    }
 }
 
 /// Generic plugin for file-based data formats
@@ -41,9 +92,15 @@ impl<C: FileFormatConfig> FilePlugin<C> {
         Self { config }
     }
 
-    /// Check if this plugin handles the given URL (by extension)
-    pub fn handles(&self, source: &str) -> bool {
-        source.ends_with(self.config.extension())
+    /// Check if this plugin handles the given URL (by the resolved format if known, otherwise by extension)
+    pub fn handles(&self, source: &str, format: Option<&str>) -> bool {
+        match format {
+            Some(format) => format == self.config.name(),
+            None if self.config.compressible() => {
+                strip_compression(source).ends_with(self.config.extension())
+            }
+            None => source.ends_with(self.config.extension()),
+        }
     }
 
     pub async fn reader(
@@ -52,12 +109,18 @@ impl<C: FileFormatConfig> FilePlugin<C> {
         bundle: &Bundle,
         schema: Option<SchemaRef>,
     ) -> Result<FileReader<C>, BundlebaseError> {
+        let compression = if self.config.compressible() {
+            source_compression(source)
+        } else {
+            FileCompressionType::UNCOMPRESSED
+        };
         Ok(FileReader::new(
             &ObjectStoreFile::from_str(source, bundle.data_dir(), bundle.config())?,
-            self.config.clone(),
+            self.config.with_bundle_config(&bundle.config())?,
             bundle.ctx(),
             schema,
-        ))
+        )
+        .with_compression(compression))
     }
 }
 
@@ -72,6 +135,7 @@ pub struct FileReader<C: FileFormatConfig> {
     config: C,
     ctx: Arc<SessionContext>,
     schema: Option<SchemaRef>,
+    compression: FileCompressionType,
 }
 
 impl<C: FileFormatConfig> FileReader<C> {
@@ -86,8 +150,15 @@ impl<C: FileFormatConfig> FileReader<C> {
             ctx,
             schema,
             config,
+            compression: FileCompressionType::UNCOMPRESSED,
         }
     }
+
+    /// Read the file through the given decompression
+    pub fn with_compression(mut self, compression: FileCompressionType) -> Self {
+        self.compression = compression;
+        self
+    }
 }
 
 impl<C: FileFormatConfig> FileReader<C> {
@@ -101,6 +172,23 @@ impl<C: FileFormatConfig> FileReader<C> {
         self.file.url()
     }
 
+    /// Get the compression the file is read with
+    pub fn compression(&self) -> FileCompressionType {
+        self.compression
+    }
+
+    /// Decompress bytes read from the file, returned as-is if it isn't compressed
+    pub fn decompress(&self, content: Vec<u8>) -> Result<Vec<u8>, BundlebaseError> {
+        if !self.compression.is_compressed() {
+            return Ok(content);
+        }
+        let mut decompressed = Vec::new();
+        self.compression
+            .convert_read(std::io::Cursor::new(content))?
+            .read_to_end(&mut decompressed)?;
+        Ok(decompressed)
+    }
+
     /// Get the object store
     pub fn object_store(&self) -> Arc<dyn object_store::ObjectStore> {
         self.file.store()
@@ -116,7 +204,7 @@ impl<C: FileFormatConfig> FileReader<C> {
 
         Ok(Some(
             self.config
-                .file_format()
+                .file_format(self.compression)
                 .infer_schema(&self.ctx.state(), &self.file.store(), &[metadata])
                 .await?,
         ))
@@ -127,6 +215,11 @@ impl<C: FileFormatConfig> FileReader<C> {
         self.file.version().await
     }
 
+    /// Get the size of the file in bytes, or `None` if it doesn't exist
+    pub async fn size(&self) -> Result<Option<u64>, BundlebaseError> {
+        Ok(self.file.metadata().await?.map(|meta| meta.size))
+    }
+
     /// Generic data_source implementation for file-based readers
     pub async fn data_source(
         &self,
@@ -136,7 +229,8 @@ impl<C: FileFormatConfig> FileReader<C> {
         row_ids: Option<&[RowId]>,
     ) -> Result<Arc<dyn DataSource>, DataFusionError> {
         // Return RowIdOffsetDataSource for selective row reading if format supports it
-        if let Some(ids) = row_ids {
+        // Compressed files can't be read from an offset, so always scan them in full
+        if let Some(ids) = row_ids.filter(|_| !self.compression.is_compressed()) {
             if let Some(format) = self.config.line_oriented_format() {
                 return Ok(Arc::new(RowIdOffsetDataSource::new(
                     &self.file,
@@ -159,12 +253,20 @@ impl<C: FileFormatConfig> FileReader<C> {
 
         let partitioned_file = PartitionedFile::from(metadata);
 
+        // Stores such as tar archives aren't registered globally, so register this file's store
+        let store_url = self.file.store_url().map_err(DataFusionError::External)?;
+        if self.ctx.runtime_env().object_store(&store_url).is_err() {
+            self.ctx
+                .register_object_store(store_url.as_ref(), self.file.store());
+        }
+
         let mut builder = FileScanConfigBuilder::new(
-            self.file.store_url(),
+            store_url,
             self.schema.clone().expect("No schema set"),
             self.config.file_source(),
         )
-        .with_file(partitioned_file);
+        .with_file(partitioned_file)
+        .with_file_compression_type(self.compression);
 
         if let Some(proj) = projection {
             builder = builder.with_projection_indices(Some(proj.to_vec()));
@@ -192,6 +294,7 @@ impl<C: FileFormatConfig> std::fmt::Debug for FileReader<C> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("FileReader")
             .field("file", &self.file)
+            .field("compression", &self.compression)
             .finish()
     }
 }
@@ -1,5 +1,7 @@
 use crate::data::object_id::ObjectId;
-use crate::data::plugin::file_reader::{FileFormatConfig, FilePlugin, FileReader};
+use crate::data::plugin::file_reader::{
+    compression_name, FileFormatConfig, FilePlugin, FileReader,
+};
 use crate::data::plugin::ReaderPlugin;
 use crate::data::{DataReader, LineOrientedFormat};
 use crate::index::RowIdIndex;
@@ -9,6 +11,7 @@ use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
 use datafusion::common::stats::Precision;
 use datafusion::common::{DataFusionError, Statistics};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
 use datafusion::datasource::file_format::json::JsonFormat;
 use datafusion::datasource::file_format::FileFormat;
 use datafusion::datasource::physical_plan::{FileSource, JsonSource};
@@ -23,12 +26,16 @@ use url::Url;
 pub struct JsonFormatConfig;
 
 impl FileFormatConfig for JsonFormatConfig {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
     fn extension(&self) -> &'static str {
         ".json"
     }
 
-    fn file_format(&self) -> Arc<dyn FileFormat> {
-        Arc::new(JsonFormat::default())
+    fn file_format(&self, compression: FileCompressionType) -> Arc<dyn FileFormat> {
+        Arc::new(JsonFormat::default().with_file_compression_type(compression))
     }
 
     fn file_source(&self) -> Arc<dyn FileSource> {
@@ -38,6 +45,10 @@ impl FileFormatConfig for JsonFormatConfig {
     fn line_oriented_format(&self) -> Option<LineOrientedFormat> {
         Some(LineOrientedFormat::JsonLines)
     }
+
+    fn compressible(&self) -> bool {
+        true
+    }
 }
 
 /// JSON plugin - uses generic FilePlugin and creates JsonReader
@@ -62,8 +73,9 @@ impl ReaderPlugin for JsonPlugin {
         bundle: &Bundle,
         schema: Option<SchemaRef>,
         _layout: Option<String>,
+        format: Option<&str>,
     ) -> Result<Option<Arc<dyn DataReader>>, BundlebaseError> {
-        if !self.inner.handles(source) {
+        if !self.inner.handles(source, format) {
             return Ok(None);
         }
 
@@ -114,6 +126,14 @@ impl DataReader for JsonReader {
         self.inner.version().await
     }
 
+    async fn read_size(&self) -> Result<Option<u64>, BundlebaseError> {
+        self.inner.size().await
+    }
+
+    fn compression(&self) -> Option<String> {
+        compression_name(self.inner.compression())
+    }
+
     async fn read_statistics(&self) -> Result<Option<Statistics>, BundlebaseError> {
         let (num_rows, file_bytes) = self.compute_statistics().await?;
 
@@ -129,6 +149,11 @@ impl DataReader for JsonReader {
         &self,
         data_dir: &ObjectStoreDir,
     ) -> Result<Option<String>, BundlebaseError> {
+        // Row offsets can't be used to read into a compressed file
+        if self.inner.compression().is_compressed() {
+            return Ok(None);
+        }
+
         let index = RowIdIndex::new()
             .build(&self.inner.file(), data_dir, &self.block_id(), false)
             .await?;
@@ -160,8 +185,9 @@ impl JsonReader {
             content.extend_from_slice(&chunk);
         }
 
-        // Get file size
+        // Get file size, then count rows in the decompressed content
         let file_size = content.len();
+        let content = self.inner.decompress(content)?;
 
         // Count newlines (each line is a JSON object in JSONL format)
         let row_count = content.iter().filter(|&&b| b == b'\n').count();
@@ -194,7 +220,7 @@ mod tests {
 
         let binding = Bundle::empty().await?;
         let result = plugin
-            .reader("file:///test.csv", &1.into(), &binding, None, None)
+            .reader("file:///test.csv", &1.into(), &binding, None, None, None)
             .await?;
 
         assert!(result.is_none());
@@ -208,7 +234,14 @@ mod tests {
 
         let binding = Bundle::empty().await?;
         let invalid_reader = plugin
-            .reader("file:///invalid.json", &1.into(), &binding, None, None)
+            .reader(
+                "file:///invalid.json",
+                &1.into(),
+                &binding,
+                None,
+                None,
+                None,
+            )
             .await?;
 
         assert!(
@@ -239,6 +272,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .ok_or_else(|| BundlebaseError::from("Expected reader"))?;
@@ -267,6 +301,7 @@ mod tests {
                 &binding,
                 Some(schema),
                 None,
+                None,
             )
             .await?
             .ok_or_else(|| BundlebaseError::from("Expected reader"))?;
@@ -326,6 +361,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .unwrap();
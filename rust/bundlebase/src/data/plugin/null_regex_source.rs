@@ -0,0 +1,235 @@
+use arrow::array::{ArrayRef, AsArray, RecordBatch, RecordBatchOptions, StringArray};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::{
+    FileOpenFuture, FileOpener, FileScanConfig, FileSource,
+};
+use datafusion::datasource::schema_adapter::SchemaAdapterFactory;
+use datafusion::datasource::table_schema::TableSchema;
+use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+use datafusion::physical_plan::DisplayFormatType;
+use futures::{StreamExt, TryStreamExt};
+use object_store::ObjectStore;
+use regex::Regex;
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// FileSource for text formats that reads fields matching `regex` as null.
+///
+/// The wrapped source reads every column as text, then matching values are nulled and the rest
+/// are cast to the column's type. DataFusion's `CsvSource` only treats empty fields as null.
+#[derive(Clone)]
+pub(crate) struct NullRegexSource {
+    inner: Arc<dyn FileSource>,
+    regex: Regex,
+    file_schema: Option<SchemaRef>,
+    projection: Option<Vec<usize>>,
+}
+
+impl NullRegexSource {
+    pub(crate) fn new(inner: Arc<dyn FileSource>, regex: Regex) -> Self {
+        Self {
+            inner,
+            regex,
+            file_schema: None,
+            projection: None,
+        }
+    }
+
+    fn with_inner(&self, inner: Arc<dyn FileSource>) -> Arc<dyn FileSource> {
+        Arc::new(Self {
+            inner,
+            ..self.clone()
+        })
+    }
+}
+
+impl FileSource for NullRegexSource {
+    fn create_file_opener(
+        &self,
+        object_store: Arc<dyn ObjectStore>,
+        base_config: &FileScanConfig,
+        partition: usize,
+    ) -> Arc<dyn FileOpener> {
+        let file_schema = self
+            .file_schema
+            .clone()
+            .expect("Schema must be set before opening files");
+        let schema = match &self.projection {
+            Some(indices) => Arc::new(file_schema.project(indices).expect("Invalid projection")),
+            None => file_schema,
+        };
+        Arc::new(NullRegexOpener {
+            inner: self
+                .inner
+                .create_file_opener(object_store, base_config, partition),
+            regex: self.regex.clone(),
+            schema,
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn with_batch_size(&self, batch_size: usize) -> Arc<dyn FileSource> {
+        self.with_inner(self.inner.with_batch_size(batch_size))
+    }
+
+    fn with_schema(&self, schema: TableSchema) -> Arc<dyn FileSource> {
+        let text_fields: Vec<Field> = schema
+            .file_schema()
+            .fields()
+            .iter()
+            .map(|f| Field::new(f.name(), DataType::Utf8, true))
+            .collect();
+        let text_schema = TableSchema::new(
+            Arc::new(Schema::new(text_fields)),
+            schema.table_partition_cols().clone(),
+        );
+        Arc::new(Self {
+            inner: self.inner.with_schema(text_schema),
+            file_schema: Some(schema.file_schema().clone()),
+            ..self.clone()
+        })
+    }
+
+    fn with_projection(&self, config: &FileScanConfig) -> Arc<dyn FileSource> {
+        Arc::new(Self {
+            inner: self.inner.with_projection(config),
+            projection: config.file_column_projection_indices(),
+            ..self.clone()
+        })
+    }
+
+    fn with_statistics(&self, statistics: Statistics) -> Arc<dyn FileSource> {
+        self.with_inner(self.inner.with_statistics(statistics))
+    }
+
+    fn metrics(&self) -> &ExecutionPlanMetricsSet {
+        self.inner.metrics()
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        self.inner.statistics()
+    }
+
+    fn file_type(&self) -> &str {
+        self.inner.file_type()
+    }
+
+    fn fmt_extra(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt_extra(t, f)?;
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, ", null_regex={}", self.regex)
+            }
+            DisplayFormatType::TreeRender => Ok(()),
+        }
+    }
+
+    fn with_schema_adapter_factory(
+        &self,
+        factory: Arc<dyn SchemaAdapterFactory>,
+    ) -> Result<Arc<dyn FileSource>> {
+        Ok(self.with_inner(self.inner.with_schema_adapter_factory(factory)?))
+    }
+
+    fn schema_adapter_factory(&self) -> Option<Arc<dyn SchemaAdapterFactory>> {
+        self.inner.schema_adapter_factory()
+    }
+}
+
+/// Converts the text batches the wrapped opener reads into `schema`
+struct NullRegexOpener {
+    inner: Arc<dyn FileOpener>,
+    regex: Regex,
+    schema: SchemaRef,
+}
+
+impl FileOpener for NullRegexOpener {
+    fn open(&self, partitioned_file: PartitionedFile) -> Result<FileOpenFuture> {
+        let opened = self.inner.open(partitioned_file)?;
+        let regex = self.regex.clone();
+        let schema = self.schema.clone();
+        Ok(Box::pin(async move {
+            let batches = opened.await?;
+            Ok(batches
+                .and_then(move |batch| {
+                    futures::future::ready(convert_batch(&batch, &regex, &schema))
+                })
+                .boxed())
+        }))
+    }
+}
+
+/// Nulls the text values matching `regex` and casts each column to its type in `schema`
+fn convert_batch(batch: &RecordBatch, regex: &Regex, schema: &SchemaRef) -> Result<RecordBatch> {
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| {
+            let text: StringArray = column
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.filter(|v| !regex.is_match(v)))
+                .collect();
+            // Unlike the default, values that don't parse as the column's type are an error
+            let options = CastOptions {
+                safe: false,
+                ..Default::default()
+            };
+            Ok(cast_with_options(&text, field.data_type(), &options)?)
+        })
+        .collect::<Result<Vec<ArrayRef>>>()?;
+
+    // The row count keeps batches without columns, such as for `count()`
+    let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+    RecordBatch::try_new_with_options(schema.clone(), columns, &options)
+        .map_err(|e| DataFusionError::ArrowError(Box::new(e), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::Int64Type;
+
+    #[test]
+    fn test_convert_batch() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(StringArray::from(vec![Some("1"), Some("NULL"), None])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("NULL"),
+                    Some("NULLs"),
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+
+        let converted = convert_batch(&batch, &Regex::new("^NULL$").unwrap(), &schema).unwrap();
+        assert_eq!(schema, converted.schema());
+        assert_eq!(
+            &Int64Array::from(vec![Some(1), None, None]),
+            converted.column(0).as_primitive::<Int64Type>()
+        );
+        assert_eq!(
+            &StringArray::from(vec![Some("a"), None, Some("NULLs")]),
+            converted.column(1).as_string::<i32>()
+        );
+    }
+}
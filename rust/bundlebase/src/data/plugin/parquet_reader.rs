@@ -6,15 +6,22 @@ use crate::{Bundle, BundlebaseError};
 use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
 use datafusion::common::stats::Precision;
-use datafusion::common::{DataFusionError, Statistics};
+use datafusion::common::{DFSchema, DataFusionError, Statistics};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::file_format::FileFormat;
-use datafusion::datasource::physical_plan::{FileSource, ParquetSource};
+use datafusion::datasource::physical_plan::parquet::ParquetAccessPlan;
+use datafusion::datasource::physical_plan::{
+    FileGroup, FileScanConfig, FileScanConfigBuilder, FileSource, ParquetSource,
+};
 use datafusion::datasource::source::DataSource;
+use datafusion::logical_expr::execution_props::ExecutionProps;
+use datafusion::logical_expr::utils::conjunction;
 use datafusion::logical_expr::Expr;
 use datafusion::parquet::arrow::async_reader::{
     ParquetObjectReader, ParquetRecordBatchStreamBuilder,
 };
+use datafusion::physical_expr::create_physical_expr;
 use datafusion::prelude::SessionContext;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -26,12 +33,17 @@ use url::Url;
 pub struct ParquetFormatConfig;
 
 impl FileFormatConfig for ParquetFormatConfig {
+    fn name(&self) -> &'static str {
+        "parquet"
+    }
+
     fn extension(&self) -> &'static str {
         ".parquet"
     }
 
-    fn file_format(&self) -> Arc<dyn FileFormat> {
-        Arc::new(ParquetFormat::default())
+    fn file_format(&self, _compression: FileCompressionType) -> Arc<dyn FileFormat> {
+        // Keep the embedded Arrow field metadata (units, descriptions, etc.) in the inferred schema
+        Arc::new(ParquetFormat::default().with_skip_metadata(false))
     }
 
     fn file_source(&self) -> Arc<dyn FileSource> {
@@ -60,16 +72,17 @@ impl ReaderPlugin for ParquetPlugin {
         bundle: &Bundle,
         schema: Option<SchemaRef>,
         _layout: Option<String>,
+        format: Option<&str>,
     ) -> Result<Option<Arc<dyn DataReader>>, BundlebaseError> {
-        if !self.inner.handles(&source) {
+        if !self.inner.handles(source, format) {
             return Ok(None);
         }
 
         let reader = self.inner.reader(source, bundle, schema).await?;
-        Ok(Some(Arc::new(ParquetDataReader::new(
-            reader,
-            block_id.clone(),
-        ))))
+        Ok(Some(Arc::new(
+            ParquetDataReader::new(reader, *block_id)
+                .with_filter_pushdown(bundle.config().parquet_filter_pushdown()?),
+        )))
     }
 }
 
@@ -77,11 +90,56 @@ impl ReaderPlugin for ParquetPlugin {
 pub struct ParquetDataReader {
     inner: FileReader<ParquetFormatConfig>,
     block_id: ObjectId,
+    filter_pushdown: bool,
 }
 
 impl ParquetDataReader {
     pub fn new(inner: FileReader<ParquetFormatConfig>, block_id: ObjectId) -> Self {
-        Self { inner, block_id }
+        Self {
+            inner,
+            block_id,
+            filter_pushdown: true,
+        }
+    }
+
+    /// Whether scans use their filters to skip row groups and pages, see `with_predicate`
+    pub fn with_filter_pushdown(mut self, filter_pushdown: bool) -> Self {
+        self.filter_pushdown = filter_pushdown;
+        self
+    }
+
+    /// Gives a parquet scan `filters` as its predicate, so row groups ruled out by their
+    /// statistics or bloom filters and pages ruled out by the page index are never read.
+    /// Rows are still filtered afterwards, so filters that can't be converted are just skipped.
+    fn with_predicate(
+        &self,
+        source: Arc<dyn DataSource>,
+        filters: &[Expr],
+    ) -> Result<Arc<dyn DataSource>, DataFusionError> {
+        let Some(config) = source.as_any().downcast_ref::<FileScanConfig>() else {
+            return Ok(source);
+        };
+        let Some(filter) = conjunction(filters.iter().cloned()) else {
+            return Ok(source);
+        };
+        let schema = DFSchema::try_from(config.file_schema().clone())?;
+        let predicate = match create_physical_expr(&filter, &schema, &ExecutionProps::new()) {
+            Ok(predicate) => predicate,
+            Err(e) => {
+                log::debug!("Not pushing filter {} into {}: {}", filter, self.url(), e);
+                return Ok(source);
+            }
+        };
+
+        let file_source = ParquetSource::default()
+            .with_bloom_filter_on_read(true)
+            .with_enable_page_index(true)
+            .with_predicate(predicate);
+        Ok(Arc::new(
+            FileScanConfigBuilder::from(config.clone())
+                .with_source(Arc::new(file_source))
+                .build(),
+        ))
     }
 }
 
@@ -106,15 +164,87 @@ impl DataReader for ParquetDataReader {
         limit: Option<usize>,
         row_ids: Option<&[RowId]>,
     ) -> Result<Arc<dyn DataSource>, DataFusionError> {
-        self.inner
+        let source = self
+            .inner
             .data_source(projection, filters, limit, row_ids)
-            .await
+            .await?;
+        if !self.filter_pushdown {
+            return Ok(source);
+        }
+        self.with_predicate(source, filters)
     }
 
     async fn read_version(&self) -> Result<String, BundlebaseError> {
         self.inner.version().await
     }
 
+    async fn read_row_groups(&self) -> Result<Option<Vec<usize>>, BundlebaseError> {
+        let store = self.inner.file().store();
+        let path = self.inner.file().store_path().clone();
+        let object_reader = ParquetObjectReader::new(store, path);
+        let builder = ParquetRecordBatchStreamBuilder::new(object_reader)
+            .await
+            .map_err(|e| Box::new(e) as BundlebaseError)?;
+
+        Ok(Some(
+            builder
+                .metadata()
+                .row_groups()
+                .iter()
+                .map(|row_group| row_group.num_rows() as usize)
+                .collect(),
+        ))
+    }
+
+    async fn select_row_groups(
+        &self,
+        source: Arc<dyn DataSource>,
+        row_groups: &[usize],
+    ) -> Result<Arc<dyn DataSource>, DataFusionError> {
+        let Some(config) = source.as_any().downcast_ref::<FileScanConfig>() else {
+            return Ok(source);
+        };
+        let count = self
+            .read_row_groups()
+            .await
+            .map_err(DataFusionError::External)?
+            .map(|rows| rows.len())
+            .unwrap_or(0);
+
+        // The access plan travels with the file, so it still applies once the scan is split
+        let mut plan = ParquetAccessPlan::new_none(count);
+        for &row_group in row_groups {
+            if row_group >= count {
+                return Err(DataFusionError::Plan(format!(
+                    "Row group {} is out of range: {} has {} row groups",
+                    row_group,
+                    self.url(),
+                    count
+                )));
+            }
+            plan.scan(row_group);
+        }
+        let plan = Arc::new(plan);
+
+        let mut config = config.clone();
+        config.file_groups = config
+            .file_groups
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_inner()
+                    .into_iter()
+                    .map(|file| file.with_extensions(plan.clone()))
+                    .collect::<FileGroup>()
+            })
+            .collect();
+        Ok(Arc::new(config))
+    }
+
+    async fn read_size(&self) -> Result<Option<u64>, BundlebaseError> {
+        self.inner.size().await
+    }
+
     async fn read_statistics(&self) -> Result<Option<Statistics>, BundlebaseError> {
         // Get object store components for stream-based reading
         let store = self.inner.file().store();
@@ -240,7 +370,7 @@ mod tests {
 
         let binding = Bundle::empty().await?;
         let result = plugin
-            .reader("file:///test.csv", &1.into(), &binding, None, None)
+            .reader("file:///test.csv", &1.into(), &binding, None, None, None)
             .await?;
 
         assert!(result.is_none());
@@ -254,7 +384,14 @@ mod tests {
 
         let binding = Bundle::empty().await?;
         let invalid_reader = plugin
-            .reader("file:///invalid.parquet", &1.into(), &binding, None, None)
+            .reader(
+                "file:///invalid.parquet",
+                &1.into(),
+                &binding,
+                None,
+                None,
+                None,
+            )
             .await?;
 
         assert!(invalid_reader.is_some());
@@ -282,6 +419,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .ok_or_else(|| BundlebaseError::from("Expected reader"))?;
@@ -324,6 +462,7 @@ mod tests {
                 &binding,
                 Some(schema),
                 None,
+                None,
             )
             .await?
             .ok_or_else(|| BundlebaseError::from("Expected reader"))?;
@@ -376,6 +515,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .unwrap();
@@ -422,6 +562,7 @@ mod tests {
                 &binding,
                 None,
                 None,
+                None,
             )
             .await?
             .unwrap();
@@ -463,4 +604,72 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_filter_pushdown_uses_bloom_filters() -> Result<(), BundlebaseError> {
+        use arrow::array::{RecordBatch, StringArray};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use datafusion::datasource::source::DataSourceExec;
+        use datafusion::physical_plan::metrics::MetricValue;
+        use datafusion::physical_plan::{collect, ExecutionPlan};
+        use datafusion::prelude::{col, lit};
+        use parquet::arrow::ArrowWriter;
+        use parquet::file::properties::WriterProperties;
+
+        // Both row groups' min/max statistics include "yak", only the bloom filters rule one out
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("names.parquet");
+        let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+        let props = WriterProperties::builder()
+            .set_bloom_filter_enabled(true)
+            .set_max_row_group_size(2)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(std::fs::File::create(&path)?, schema.clone(), Some(props))?;
+        writer.write(&RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec![
+                "apple", "zebra", "banana", "yak",
+            ]))],
+        )?)?;
+        writer.close()?;
+
+        let bundle = Bundle::empty().await?;
+        let url = Url::from_file_path(&path)
+            .map_err(|_| BundlebaseError::from("Invalid path"))?
+            .to_string();
+        for (pushdown, rows_read, pruned) in [(true, 2, 1), (false, 4, 0)] {
+            let reader = ParquetDataReader::new(
+                FilePlugin::<ParquetFormatConfig>::default()
+                    .reader(&url, &bundle, Some(schema.clone()))
+                    .await?,
+                1.into(),
+            )
+            .with_filter_pushdown(pushdown);
+
+            let filter = col("name").eq(lit("yak"));
+            let exec: Arc<dyn ExecutionPlan> = Arc::new(DataSourceExec::new(
+                reader.data_source(None, &[filter], None, None).await?,
+            ));
+            let batches = collect(exec.clone(), bundle.ctx().task_ctx()).await?;
+            assert_eq!(
+                rows_read,
+                batches.iter().map(|b| b.num_rows()).sum::<usize>()
+            );
+
+            let bloom_pruned = exec
+                .metrics()
+                .and_then(|m| m.sum_by_name("row_groups_pruned_bloom_filter"))
+                .map(|value| match value {
+                    MetricValue::PruningMetrics {
+                        pruning_metrics, ..
+                    } => pruning_metrics.pruned(),
+                    _ => 0,
+                })
+                .unwrap_or(0);
+            assert_eq!(pruned, bloom_pruned);
+        }
+
+        Ok(())
+    }
 }
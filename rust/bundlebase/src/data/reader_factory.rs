@@ -1,13 +1,21 @@
-use crate::data::plugin::{CsvPlugin, FunctionPlugin, JsonPlugin, ParquetPlugin, ReaderPlugin};
+use crate::data::plugin::{
+    strip_compression, CsvPlugin, FunctionPlugin, JsonPlugin, ParquetPlugin, ReaderPlugin,
+};
 use crate::data::{DataReader, ObjectId};
 use crate::functions::FunctionRegistry;
-use crate::io::DataStorage;
+use crate::io::{DataStorage, ObjectStoreFile};
 use crate::{Bundle, BundlebaseError};
 use arrow_schema::SchemaRef;
 use datafusion::common::DataFusionError;
 use parking_lot::RwLock;
 use std::sync::Arc;
 
+/// File formats with a built-in reader, which are also the extensions they are detected by
+const BUILTIN_FORMATS: [&str; 4] = ["csv", "tsv", "json", "parquet"];
+
+/// How many bytes at the start of a file are read to sniff its format
+const SNIFF_BYTES: u64 = 4096;
+
 pub struct DataReaderFactory {
     plugins: Vec<Arc<dyn ReaderPlugin>>,
     storage: Arc<DataStorage>,
@@ -40,15 +48,120 @@ impl DataReaderFactory {
         bundle: &Bundle,
         schema: Option<SchemaRef>,
         layout: Option<String>,
+        format: Option<String>,
     ) -> Result<Arc<dyn DataReader>, BundlebaseError> {
         for plugin in &self.plugins {
             let reader = plugin
-                .reader(source, block_id, bundle, schema.clone(), layout.clone())
+                .reader(
+                    source,
+                    block_id,
+                    bundle,
+                    schema.clone(),
+                    layout.clone(),
+                    format.as_deref(),
+                )
                 .await?;
             if reader.is_some() {
                 return Ok(reader.unwrap());
             }
         }
-        Err(DataFusionError::NotImplemented(format!("No reader found for {}", source)).into())
+        let reason = match format {
+            Some(format) => format!("No reader found for {} with format '{}'", source, format),
+            None => format!("No reader found for {}", source),
+        };
+        Err(DataFusionError::NotImplemented(reason).into())
+    }
+
+    /// Determine the format to read `source` as. The `file_formats` config is checked for the
+    /// source's extension first, then the built-in extensions, and finally the start of the file
+    /// is sniffed. Returns None for sources that aren't files, or whose format can't be told.
+    pub async fn resolve_format(
+        &self,
+        source: &str,
+        bundle: &Bundle,
+    ) -> Result<Option<String>, BundlebaseError> {
+        if source.starts_with("function://") {
+            return Ok(None);
+        }
+
+        if let Some(extension) = extension(source) {
+            if let Some(format) = bundle.config().file_formats()?.remove(&extension) {
+                return Ok(Some(format));
+            }
+            if BUILTIN_FORMATS.contains(&extension.as_str()) {
+                return Ok(Some(extension));
+            }
+        }
+
+        let file = ObjectStoreFile::from_str(source, bundle.data_dir(), bundle.config())?;
+        Ok(match file.read_range(0..SNIFF_BYTES).await? {
+            Some(head) => sniff_format(&head).map(str::to_string),
+            None => None,
+        })
+    }
+}
+
+/// The lowercased extension of the last path segment of `source`, if it has one. A compression
+/// suffix is skipped, so `data.csv.gz` has the extension `csv`.
+fn extension(source: &str) -> Option<String> {
+    let filename = strip_compression(source).rsplit('/').next()?;
+    let (_, extension) = filename.rsplit_once('.')?;
+    if extension.is_empty() {
+        None
+    } else {
+        Some(extension.to_lowercase())
+    }
+}
+
+/// Guess a file's format from its first bytes
+fn sniff_format(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"PAR1") {
+        return Some("parquet");
+    }
+    if head.contains(&0) {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with('{') {
+        return Some("json");
+    }
+
+    let first_line = text.lines().next()?;
+    if first_line.contains('\t') {
+        Some("tsv")
+    } else if first_line.contains(',') {
+        Some("csv")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(Some("csv".to_string()), extension("file:///data/a.CSV"));
+        assert_eq!(Some("pq".to_string()), extension("s3://bucket/dir.v2/a.pq"));
+        assert_eq!(None, extension("file:///data.v2/readme"));
+        assert_eq!(None, extension("file:///data/a."));
+        assert_eq!(Some("csv".to_string()), extension("file:///data/a.csv.GZ"));
+        assert_eq!(
+            Some("json".to_string()),
+            extension("file:///data/a.json.zst")
+        );
+    }
+
+    #[test]
+    fn test_sniff_format() {
+        assert_eq!(Some("parquet"), sniff_format(b"PAR1\x15\x04"));
+        assert_eq!(Some("json"), sniff_format(b"  {\"a\": 1}\n{\"a\": 2}"));
+        assert_eq!(Some("tsv"), sniff_format(b"id\tname\n1\tAnn, B\n"));
+        assert_eq!(Some("csv"), sniff_format(b"\xef\xbb\xbfid,name\n1,Ann\n"));
+        assert_eq!(None, sniff_format(b"just some text\n"));
+        assert_eq!(None, sniff_format(b"\x00\x01,\x02"));
     }
 }
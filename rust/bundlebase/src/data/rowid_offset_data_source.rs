@@ -23,6 +23,8 @@ use std::sync::Arc;
 pub enum LineOrientedFormat {
     /// CSV format with header
     Csv,
+    /// Tab-delimited CSV format with header
+    Tsv,
     /// JSON Lines format (newline-delimited JSON)
     JsonLines,
 }
@@ -303,14 +305,20 @@ impl DataSource for RowIdOffsetDataSource {
                 }
 
                 let batch = match format {
-                    LineOrientedFormat::Csv => {
+                    LineOrientedFormat::Csv | LineOrientedFormat::Tsv => {
+                        let delimiter = if format == LineOrientedFormat::Tsv {
+                            b'\t'
+                        } else {
+                            b','
+                        };
+
                         // Prepend schema header to CSV data
                         let header = schema
                             .fields()
                             .iter()
                             .map(|f| f.name().as_str())
                             .collect::<Vec<_>>()
-                            .join(",");
+                            .join(&(delimiter as char).to_string());
 
                         let mut csv_data = header + "\n";
                         for line in lines {
@@ -322,6 +330,7 @@ impl DataSource for RowIdOffsetDataSource {
                         let cursor = Cursor::new(csv_data.as_bytes());
                         let mut reader = CsvReaderBuilder::new(schema.clone())
                             .with_header(true)
+                            .with_delimiter(delimiter)
                             .build(cursor)
                             .map_err(|e| DataFusionError::ArrowError(Box::new(e), None))?;
 
@@ -413,8 +422,8 @@ impl DataSource for RowIdOffsetDataSource {
         &self,
         _partition: Option<usize>,
     ) -> datafusion::common::Result<Statistics> {
-        // Return statistics based on the row IDs we'll read
-        let mut stats = Statistics::new_unknown(&self.schema);
+        // Return statistics based on the row IDs we'll read, with a column for each one produced
+        let mut stats = Statistics::new_unknown(&self.projected_schema);
         stats.num_rows = datafusion::common::stats::Precision::Exact(self.row_ids.len());
         Ok(stats)
     }
@@ -488,6 +497,25 @@ mod tests {
         assert_eq!(stats.num_rows.get_value(), Some(&2));
     }
 
+    #[test]
+    fn test_partition_statistics_projected() {
+        let file = ObjectStoreFile::from_url(
+            &Url::parse("file:///test.csv").unwrap(),
+            BundleConfig::default().into(),
+        )
+        .unwrap();
+        let schema = Arc::new(arrow::datatypes::Schema::new(vec![
+            arrow::datatypes::Field::new("a", arrow::datatypes::DataType::Int64, true),
+            arrow::datatypes::Field::new("b", arrow::datatypes::DataType::Int64, true),
+            arrow::datatypes::Field::new("c", arrow::datatypes::DataType::Int64, true),
+        ]));
+        let source = RowIdOffsetDataSource::new_csv(&file, schema, vec![], Some(vec![2, 0]));
+
+        // Filters above the scan match column statistics to the projected columns
+        let stats = source.partition_statistics(None).unwrap();
+        assert_eq!(2, stats.column_statistics.len());
+    }
+
     #[test]
     fn test_batch_row_ids_single_batch() {
         // RowIds that are close together should be batched
@@ -1,6 +1,7 @@
 use crate::functions::function_impl::FunctionImpl;
 use crate::BundlebaseError;
 use arrow_schema::SchemaRef;
+use datafusion::logical_expr::ScalarUDF;
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -10,6 +11,7 @@ use std::sync::Arc;
 pub struct FunctionRegistry {
     functions: HashMap<String, FunctionSignature>,
     impls: HashMap<String, Arc<dyn FunctionImpl>>,
+    udfs: HashMap<String, Arc<ScalarUDF>>,
 }
 
 impl Clone for FunctionRegistry {
@@ -17,6 +19,7 @@ impl Clone for FunctionRegistry {
         Self {
             functions: self.functions.clone(),
             impls: self.impls.clone(),
+            udfs: self.udfs.clone(),
         }
     }
 }
@@ -27,6 +30,7 @@ impl FunctionRegistry {
         Self {
             functions: HashMap::new(),
             impls: HashMap::new(),
+            udfs: HashMap::new(),
         }
     }
 
@@ -70,6 +74,39 @@ impl FunctionRegistry {
         self.impls.insert(name.to_string(), def);
         Ok(())
     }
+
+    /// Adds a scalar UDF that can be called by its name in bundle expressions and queries.
+    /// UDFs aren't saved in the bundle, so register them again before reopening one that uses them.
+    pub fn register_udf(&mut self, udf: ScalarUDF) {
+        debug!("Registering UDF: {}", udf.name());
+        self.udfs.insert(udf.name().to_string(), Arc::new(udf));
+    }
+
+    pub fn get_udf(&self, name: &str) -> Option<Arc<ScalarUDF>> {
+        self.udfs.get(name).cloned()
+    }
+
+    pub fn udfs(&self) -> Vec<Arc<ScalarUDF>> {
+        self.udfs.values().cloned().collect()
+    }
+
+    /// Copies all signatures, implementations and UDFs from `other` into this registry,
+    /// replacing any existing entries with the same name.
+    pub fn register_all(&mut self, other: &FunctionRegistry) {
+        for signature in other.functions.values() {
+            debug!("Registering function: {}", signature.name());
+            self.functions
+                .insert(signature.name().to_string(), signature.clone());
+        }
+        for (name, def) in &other.impls {
+            debug!("Registering function implementation: {}", name);
+            self.impls.insert(name.clone(), def.clone());
+        }
+        for (name, udf) in &other.udfs {
+            debug!("Registering UDF: {}", name);
+            self.udfs.insert(name.clone(), udf.clone());
+        }
+    }
 }
 
 #[cfg(test)]
@@ -97,6 +134,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_register_all() {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let mut provided = FunctionRegistry::new();
+        provided
+            .register(FunctionSignature::new("catalog", schema.clone()))
+            .unwrap();
+        provided
+            .set_impl("catalog", Arc::new(MockFunctionImpl))
+            .unwrap();
+
+        let mut registry = FunctionRegistry::new();
+        registry
+            .register(FunctionSignature::new("existing", schema))
+            .unwrap();
+        registry.register_all(&provided);
+
+        assert!(registry.get_function("existing").is_some());
+        assert!(registry.get_function("catalog").is_some());
+        assert_eq!("MOCK-v1", registry.get_impl("catalog").unwrap().version());
+        assert!(registry.get_impl("existing").is_none());
+    }
+
     #[test]
     fn test_new_registry_is_empty() {
         let registry = FunctionRegistry::new();
@@ -20,6 +20,8 @@ pub enum IndexedValue {
     Boolean(bool),
     Timestamp(i64), // Nanoseconds since epoch
     Null,
+    /// Values of each column of a composite index, in the index's column order
+    Tuple(Vec<IndexedValue>),
 }
 
 // Wrapper for f64 that implements Eq and Hash
@@ -119,6 +121,13 @@ impl IndexedValue {
             IndexedValue::Null => {
                 buf.put_u8(0);
             }
+            IndexedValue::Tuple(values) => {
+                buf.put_u8(6);
+                buf.put_u16(values.len() as u16);
+                for value in values {
+                    buf.put(value.serialize());
+                }
+            }
         }
         buf.freeze()
     }
@@ -144,6 +153,14 @@ impl IndexedValue {
             }
             4 => Ok(IndexedValue::Boolean(cursor.get_u8() != 0)),
             5 => Ok(IndexedValue::Timestamp(cursor.get_i64())),
+            6 => {
+                let len = cursor.get_u16() as usize;
+                let mut values = Vec::with_capacity(len);
+                for _ in 0..len {
+                    values.push(IndexedValue::deserialize(cursor)?);
+                }
+                Ok(IndexedValue::Tuple(values))
+            }
             _ => Err(format!("Unknown index value type tag: {}", type_tag).into()),
         }
     }
@@ -157,6 +174,7 @@ impl IndexedValue {
             IndexedValue::Utf8(s) => 5 + s.len(),
             IndexedValue::Boolean(_) => 2,
             IndexedValue::Timestamp(_) => 9,
+            IndexedValue::Tuple(values) => 3 + values.iter().map(|v| v.size_bytes()).sum::<usize>(),
         }
     }
 
@@ -169,6 +187,9 @@ impl IndexedValue {
             IndexedValue::Boolean(_) => IndexedValue::Boolean(false),
             IndexedValue::Timestamp(_) => IndexedValue::Timestamp(i64::MIN),
             IndexedValue::Null => IndexedValue::Null,
+            IndexedValue::Tuple(values) => {
+                IndexedValue::Tuple(values.iter().map(|v| v.min_for_type()).collect())
+            }
         }
     }
 
@@ -181,6 +202,17 @@ impl IndexedValue {
             IndexedValue::Boolean(_) => IndexedValue::Boolean(true),
             IndexedValue::Timestamp(_) => IndexedValue::Timestamp(i64::MAX),
             IndexedValue::Null => IndexedValue::Null,
+            IndexedValue::Tuple(values) => {
+                IndexedValue::Tuple(values.iter().map(|v| v.max_for_type()).collect())
+            }
+        }
+    }
+
+    /// The first `len` values of a composite key, or the value itself for a single column
+    fn leading(&self, len: usize) -> &[IndexedValue] {
+        match self {
+            IndexedValue::Tuple(values) => &values[..len.min(values.len())],
+            value => std::slice::from_ref(value),
         }
     }
 }
@@ -207,6 +239,8 @@ impl Ord for IndexedValue {
             (IndexedValue::Utf8(a), IndexedValue::Utf8(b)) => a.cmp(b),
             (IndexedValue::Boolean(a), IndexedValue::Boolean(b)) => a.cmp(b),
             (IndexedValue::Timestamp(a), IndexedValue::Timestamp(b)) => a.cmp(b),
+            // Column by column, so all keys sharing a prefix sort next to each other
+            (IndexedValue::Tuple(a), IndexedValue::Tuple(b)) => a.cmp(b),
             _ => Ordering::Equal, // Mixed types (shouldn't happen)
         }
     }
@@ -370,6 +404,7 @@ impl BlockDirectory {
             IndexedValue::Timestamp(v) => v.hash(&mut hasher),
             IndexedValue::Float64(v) => v.hash(&mut hasher),
             IndexedValue::Null => 0u64.hash(&mut hasher),
+            IndexedValue::Tuple(v) => v.hash(&mut hasher),
         }
         hasher.finish()
     }
@@ -618,6 +653,38 @@ impl ColumnIndex {
         result
     }
 
+    /// Prefix lookup on a composite index - find all RowIds whose key starts with `prefix`
+    pub fn lookup_prefix(&self, prefix: &[IndexedValue]) -> Vec<RowId> {
+        let mut result = Vec::new();
+
+        for idx in self.prefix_blocks(prefix) {
+            for block_entry in &self.blocks[idx].entries {
+                if block_entry.value.leading(prefix.len()) == prefix {
+                    result.extend_from_slice(&block_entry.row_ids);
+                }
+            }
+        }
+
+        result.sort_unstable_by_key(|r| r.as_u64());
+        result.dedup();
+        result
+    }
+
+    /// Indexes of the blocks that can hold keys starting with `prefix`. Keys are sorted column by
+    /// column, so these are the blocks whose first and last keys bracket the prefix.
+    fn prefix_blocks(&self, prefix: &[IndexedValue]) -> Vec<usize> {
+        self.directory
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry.min_value.leading(prefix.len()) <= prefix
+                    && entry.max_value.leading(prefix.len()) >= prefix
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
     /// Get metadata about this index
     pub fn column_name(&self) -> &str {
         &self.column_name
@@ -692,6 +759,24 @@ impl ColumnIndex {
         estimate.min(1.0)
     }
 
+    /// Estimate selectivity for a prefix of a composite key
+    /// Returns fraction of rows expected to match (0.0 to 1.0)
+    pub fn estimate_prefix_selectivity(&self, prefix: &[IndexedValue]) -> f64 {
+        if self.total_rows == 0 {
+            return 0.0;
+        }
+
+        // The index is already in memory, so count the matching rows rather than guess
+        let matching: usize = self
+            .prefix_blocks(prefix)
+            .into_iter()
+            .flat_map(|idx| &self.blocks[idx].entries)
+            .filter(|entry| entry.value.leading(prefix.len()) == prefix)
+            .map(|entry| entry.row_ids.len())
+            .sum();
+        matching as f64 / self.total_rows as f64
+    }
+
     /// Estimate selectivity for any predicate type
     /// Returns fraction of rows expected to match (0.0 to 1.0)
     pub fn estimate_selectivity(&self, predicate: &crate::index::IndexPredicate) -> f64 {
@@ -701,6 +786,7 @@ impl ColumnIndex {
             IndexPredicate::Exact(value) => self.estimate_exact_selectivity(value),
             IndexPredicate::In(values) => self.estimate_in_selectivity(values),
             IndexPredicate::Range { min, max } => self.estimate_range_selectivity(min, max),
+            IndexPredicate::Prefix(prefix) => self.estimate_prefix_selectivity(prefix),
         }
     }
 
@@ -762,6 +848,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tuple_value() {
+        let key = |tenant: i64, day: &str| {
+            IndexedValue::Tuple(vec![
+                IndexedValue::Int64(tenant),
+                IndexedValue::Utf8(day.to_string()),
+            ])
+        };
+        // Ordered by the first column, then the next
+        assert!(key(1, "b") < key(2, "a"));
+        assert!(key(1, "a") < key(1, "b"));
+
+        let bytes = key(7, "monday").serialize();
+        assert_eq!(bytes.len(), key(7, "monday").size_bytes());
+        let mut cursor = Cursor::new(bytes.as_ref());
+        assert_eq!(
+            key(7, "monday"),
+            IndexedValue::deserialize(&mut cursor).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_composite_prefix_lookup() {
+        // 100 tenants with 100 days each, spread over several index blocks
+        let mut value_map = HashMap::new();
+        for tenant in 0..100i64 {
+            for day in 0..100i64 {
+                let key = IndexedValue::Tuple(vec![
+                    IndexedValue::Int64(tenant),
+                    IndexedValue::Int64(day),
+                ]);
+                value_map.insert(key, vec![RowId::from((tenant * 100 + day) as u64)]);
+            }
+        }
+        let index = ColumnIndex::build("tenant, day", &DataType::Int64, value_map).unwrap();
+        let bytes = index.serialize().unwrap();
+        let index = ColumnIndex::deserialize(bytes, "tenant, day".to_string()).unwrap();
+        assert!(index.block_count() > 2, "{}", index.block_count());
+
+        let prefix = vec![IndexedValue::Int64(42)];
+        let result = index.lookup_prefix(&prefix);
+        assert_eq!(100, result.len());
+        assert_eq!(4200, result[0].as_u64());
+        assert_eq!(4299, result[99].as_u64());
+
+        // Only the blocks holding the tenant are read
+        assert!(index.prefix_blocks(&prefix).len() <= 2);
+        assert!((index.estimate_prefix_selectivity(&prefix) - 0.01).abs() < 0.001);
+
+        // The whole key is an exact lookup
+        let key = IndexedValue::Tuple(vec![IndexedValue::Int64(42), IndexedValue::Int64(7)]);
+        assert_eq!(4207, index.lookup_exact(&key)[0].as_u64());
+        assert!(index.lookup_prefix(&[IndexedValue::Int64(100)]).is_empty());
+    }
+
     #[test]
     fn test_column_index_build_and_lookup() {
         let mut value_map = HashMap::new();
@@ -1,5 +1,6 @@
 use crate::index::IndexedValue;
 use crate::BundlebaseError;
+use datafusion::logical_expr::utils::split_conjunction;
 use datafusion::logical_expr::{expr, BinaryExpr, Expr, Operator};
 
 /// Represents the type of index predicate extracted from a filter expression
@@ -16,6 +17,9 @@ pub enum IndexPredicate {
         min: IndexedValue,
         max: IndexedValue,
     },
+
+    /// Equality on the leading columns of a composite index: key starts with (value1, value2, ...)
+    Prefix(Vec<IndexedValue>),
 }
 
 /// Represents a filter expression that can be optimized with an index
@@ -48,6 +52,49 @@ impl FilterAnalyzer {
             .collect()
     }
 
+    /// Build the predicate for a composite index on `columns` from the equality filters that are
+    /// ANDed together
+    ///
+    /// Equalities on every column give an `Exact` match on the whole key. Equalities on only the
+    /// leading columns give a `Prefix`. Returns None if the first column isn't filtered on, since
+    /// the index is sorted by it first.
+    pub fn extract_composite(columns: &[String], filters: &[Expr]) -> Option<IndexPredicate> {
+        let equalities: Vec<IndexableFilter> = filters
+            .iter()
+            .flat_map(split_conjunction)
+            .filter_map(|expr| match expr {
+                Expr::BinaryExpr(BinaryExpr {
+                    left,
+                    op: Operator::Eq,
+                    right,
+                }) => Self::extract_equality(left, right).ok(),
+                _ => None,
+            })
+            .collect();
+
+        let mut prefix = Vec::new();
+        for column in columns {
+            let value = equalities
+                .iter()
+                .find_map(|filter| match &filter.predicate {
+                    IndexPredicate::Exact(value) if &filter.column == column => Some(value.clone()),
+                    _ => None,
+                });
+            match value {
+                Some(value) => prefix.push(value),
+                None => break,
+            }
+        }
+
+        if prefix.is_empty() {
+            None
+        } else if prefix.len() == columns.len() {
+            Some(IndexPredicate::Exact(IndexedValue::Tuple(prefix)))
+        } else {
+            Some(IndexPredicate::Prefix(prefix))
+        }
+    }
+
     /// Analyze a single expression to determine if it's indexable
     fn analyze_expr(expr: &Expr) -> Result<IndexableFilter, BundlebaseError> {
         match expr {
@@ -462,6 +509,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extract_composite() {
+        let columns = vec!["tenant_id".to_string(), "day".to_string()];
+        let tenant = col("tenant_id").eq(Expr::Literal(ScalarValue::Int64(Some(7)), None));
+        let day = col("day").eq(Expr::Literal(ScalarValue::Int64(Some(3)), None));
+
+        // Equalities on the whole key, whether passed separately or ANDed together
+        let whole_key = Some(IndexPredicate::Exact(IndexedValue::Tuple(vec![
+            IndexedValue::Int64(7),
+            IndexedValue::Int64(3),
+        ])));
+        assert_eq!(
+            whole_key,
+            FilterAnalyzer::extract_composite(&columns, &[day.clone(), tenant.clone()])
+        );
+        assert_eq!(
+            whole_key,
+            FilterAnalyzer::extract_composite(&columns, &[tenant.clone().and(day.clone())])
+        );
+
+        // Only the leading column is a prefix of the key
+        assert_eq!(
+            Some(IndexPredicate::Prefix(vec![IndexedValue::Int64(7)])),
+            FilterAnalyzer::extract_composite(&columns, &[tenant])
+        );
+
+        // Without the leading column the index can't be used
+        assert_eq!(None, FilterAnalyzer::extract_composite(&columns, &[day]));
+    }
+
     #[test]
     fn test_extract_range_reversed_literal() {
         // Test: 18 < age (reversed)
@@ -8,6 +8,7 @@ use std::sync::Arc;
 pub struct IndexDefinition {
     id: ObjectId,
     column: String,
+    columns: Vec<String>,
     blocks: RwLock<Vec<Arc<IndexedBlocks>>>, //todo: use BlockIdAndVersion
 }
 
@@ -16,6 +17,18 @@ impl IndexDefinition {
         Self {
             id: id.clone(),
             column: column.clone(),
+            columns: vec![column.clone()],
+            blocks: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// An index keyed on the values of several columns together, in the given order.
+    /// It has no single `column`.
+    pub(crate) fn new_composite(id: &ObjectId, columns: &[String]) -> IndexDefinition {
+        Self {
+            id: *id,
+            column: String::new(),
+            columns: columns.to_vec(),
             blocks: RwLock::new(Vec::new()),
         }
     }
@@ -24,10 +37,20 @@ impl IndexDefinition {
         &self.id
     }
 
+    /// The indexed column. Empty for a composite index, see `columns`.
     pub fn column(&self) -> &String {
         &self.column
     }
 
+    /// The indexed columns, in key order. Holds just `column` unless the index is composite.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn is_composite(&self) -> bool {
+        self.columns.len() > 1
+    }
+
     pub fn indexed_blocks(&self, versioned_block: &VersionedBlockId) -> Option<Arc<IndexedBlocks>> {
         for blocks in self.blocks.read().iter() {
             if blocks.contains(&versioned_block.block, &versioned_block.version) {
@@ -1,10 +1,14 @@
+mod glob;
 mod object_store_dir;
 mod object_store_file;
 mod tar_object_store;
 mod util;
 
 pub use crate::data::ObjectId;
+pub use crate::io::glob::{unescape_glob, GlobPattern};
+pub(crate) use crate::io::object_store_dir::str_to_url;
 pub use crate::io::object_store_dir::ObjectStoreDir;
+pub(crate) use crate::io::object_store_file::with_metadata_cache;
 pub use crate::io::object_store_file::ObjectStoreFile;
 pub use crate::io::tar_object_store::TarObjectStore;
 use object_store::memory::InMemory;
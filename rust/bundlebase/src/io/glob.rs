@@ -0,0 +1,260 @@
+use crate::BundlebaseError;
+use regex::Regex;
+
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+/// A path or URL containing glob metacharacters, split into the directory to list
+/// and the pattern to match files in that directory against.
+///
+/// Supported syntax:
+/// * `*` matches any characters except `/`
+/// * `**` matches any characters including `/`
+/// * `?` matches a single character except `/`
+/// * `[abc]` / `[a-z]` / `[!abc]` match character classes
+///
+/// A metacharacter can be escaped with a backslash (e.g. `\*`) to be treated literally.
+/// In URLs only the path is checked for metacharacters, not the host (e.g. an IPv6 `[::1]`) or a
+/// query string, which is a `?` followed by `key=value` pairs.
+#[derive(Debug, Clone)]
+pub struct GlobPattern {
+    base: String,
+    pattern: String,
+    regex: Regex,
+}
+
+impl GlobPattern {
+    /// Parses the given path as a glob.
+    /// Returns `None` if the path contains no unescaped glob metacharacters.
+    pub fn parse(path: &str) -> Result<Option<GlobPattern>, BundlebaseError> {
+        let (start, end) = path_range(path);
+        let first_glob = match find_unescaped_glob(&path[start..end]) {
+            Some(idx) => start + idx,
+            None => return Ok(None),
+        };
+        if end < path.len() {
+            return Err(format!("Glob patterns can't have a query string: {}", path).into());
+        }
+
+        let split = path[..first_glob]
+            .rfind('/')
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        let base = unescape(&path[..split]);
+        let pattern = path[split..].to_string();
+        let regex = Regex::new(&glob_to_regex(&pattern)?)?;
+
+        Ok(Some(GlobPattern {
+            base,
+            pattern,
+            regex,
+        }))
+    }
+
+    /// The directory portion of the glob, before any metacharacters.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// The pattern portion of the glob, relative to `base()`.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns true if the given path, relative to `base()`, matches the pattern.
+    pub fn matches(&self, relative_path: &str) -> bool {
+        self.regex.is_match(relative_path)
+    }
+}
+
+/// Removes glob escapes from a path, so `data\*.csv` becomes `data*.csv`.
+pub fn unescape_glob(path: &str) -> String {
+    unescape(path)
+}
+
+/// The byte range of the path in `path`: after a URL's scheme and host, and before its query string
+fn path_range(path: &str) -> (usize, usize) {
+    let Some(scheme_end) = path.find("://").filter(|&idx| {
+        idx > 0
+            && path[..idx]
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    }) else {
+        return (0, path.len());
+    };
+    let host_start = scheme_end + 3;
+    let start = path[host_start..]
+        .find('/')
+        .map(|idx| host_start + idx)
+        .unwrap_or(path.len());
+    let end = path[start..]
+        .match_indices('?')
+        .map(|(idx, _)| start + idx)
+        .find(|&idx| {
+            let query = &path[idx + 1..];
+            query.contains('=') && !query.contains('/')
+        })
+        .unwrap_or(path.len());
+    (start, end)
+}
+
+fn find_unescaped_glob(path: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in path.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if GLOB_CHARS.contains(&c) {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+fn unescape(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.peek() {
+                if GLOB_CHARS.contains(next) || *next == '\\' || *next == ']' {
+                    result.push(*next);
+                    chars.next();
+                    continue;
+                }
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn glob_to_regex(pattern: &str) -> Result<String, BundlebaseError> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) => regex.push_str(&regex::escape(&next.to_string())),
+                None => regex.push_str(r"\\"),
+            },
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    // "**/" also matches zero directories
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                } else {
+                    regex.push_str("[^/]*");
+                }
+            }
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let mut class = String::from("[");
+                if let Some(&first) = chars.peek() {
+                    if first == '!' || first == '^' {
+                        chars.next();
+                        class.push('^');
+                    }
+                }
+                let mut closed = false;
+                for next in chars.by_ref() {
+                    if next == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if next == '\\' || next == '[' {
+                        class.push('\\');
+                    }
+                    class.push(next);
+                }
+                if !closed {
+                    return Err(format!("Unclosed '[' in glob pattern: {}", pattern).into());
+                }
+                class.push(']');
+                regex.push_str(&class);
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("memory:///data/file.csv")]
+    #[case("data/file.csv")]
+    #[case(r"memory:///data/file\*.csv")]
+    #[case(r"data/\[1\].csv")]
+    #[case("https://example.com/data/file.csv?X-Amz-Signature=abc*def&X-Amz-Expires=60")]
+    #[case("http://[::1]:8080/data/file.csv")]
+    fn not_a_glob(#[case] path: &str) {
+        assert!(GlobPattern::parse(path).unwrap().is_none());
+    }
+
+    #[rstest]
+    #[case("memory:///data/*.csv", "memory:///data/", "*.csv")]
+    #[case("data/*.csv", "data/", "*.csv")]
+    #[case("*.csv", "", "*.csv")]
+    #[case(
+        "memory:///data/2024-*/part?.csv",
+        "memory:///data/",
+        "2024-*/part?.csv"
+    )]
+    #[case(r"memory:///da\*ta/*.csv", "memory:///da*ta/", "*.csv")]
+    #[case("http://[::1]:8080/data/*.csv", "http://[::1]:8080/data/", "*.csv")]
+    fn parse(#[case] path: &str, #[case] base: &str, #[case] pattern: &str) {
+        let glob = GlobPattern::parse(path).unwrap().unwrap();
+        assert_eq!(base, glob.base());
+        assert_eq!(pattern, glob.pattern());
+    }
+
+    #[rstest]
+    #[case("*.csv", "a.csv", true)]
+    #[case("*.csv", "a.json", false)]
+    #[case("*.csv", "sub/a.csv", false)]
+    #[case("**/*.csv", "a.csv", true)]
+    #[case("**/*.csv", "sub/deeper/a.csv", true)]
+    #[case("part?.csv", "part1.csv", true)]
+    #[case("part?.csv", "part10.csv", false)]
+    #[case("part[0-4].csv", "part3.csv", true)]
+    #[case("part[0-4].csv", "part5.csv", false)]
+    #[case("part[!0-4].csv", "part5.csv", true)]
+    #[case(r"*\*.csv", "a*.csv", true)]
+    #[case(r"*\*.csv", "ab.csv", false)]
+    #[case("a.b*", "axb", false)]
+    fn matches(#[case] pattern: &str, #[case] path: &str, #[case] expected: bool) {
+        let glob = GlobPattern::parse(pattern).unwrap().unwrap();
+        assert_eq!(expected, glob.matches(path), "{} vs {}", pattern, path);
+    }
+
+    #[test]
+    fn unclosed_class() {
+        assert!(GlobPattern::parse("data/[abc.csv").is_err());
+    }
+
+    #[test]
+    fn glob_with_query_string() {
+        assert_eq!(
+            "Glob patterns can't have a query string: https://example.com/*.csv?sig=abc",
+            GlobPattern::parse("https://example.com/*.csv?sig=abc")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn unescape() {
+        assert_eq!("data/file*.csv", unescape_glob(r"data/file\*.csv"));
+        assert_eq!(r"data\file.csv", unescape_glob(r"data\file.csv"));
+    }
+}
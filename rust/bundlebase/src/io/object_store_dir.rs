@@ -1,4 +1,5 @@
 use crate::BundleConfig;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 use object_store::{path::Path as ObjectPath, ObjectStore};
 use std::env;
 
@@ -6,7 +7,7 @@ use crate::io::util::{join_path, join_url, parse_url};
 use crate::io::{ObjectStoreFile, EMPTY_SCHEME, EMPTY_URL};
 use crate::BundlebaseError;
 use env::current_dir;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Display;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -65,30 +66,94 @@ impl ObjectStoreDir {
         &self.url
     }
 
+    /// Returns true if this directory is the root of a `.tar` archive
+    pub fn is_tar(&self) -> bool {
+        self.url.scheme() == "file" && self.url.path().trim_end_matches('/').ends_with(".tar")
+    }
+
     /// Lists all files in the directory.
     pub async fn list_files(&self) -> Result<Vec<ObjectStoreFile>, BundlebaseError> {
-        use futures::stream::StreamExt;
-
-        let mut files = Vec::new();
-        let mut list_iter = self.store.list(Some(&self.path));
-
-        while let Some(meta_result) = list_iter.next().await {
-            let location = meta_result?.location;
-            // Get the relative path from self.path to location by stripping the prefix
-            let location_str = location.as_ref();
-            let prefix_str = self.path.as_ref();
-            let relative_path = if let Some(stripped) = location_str.strip_prefix(prefix_str) {
-                stripped.trim_start_matches('/')
-            } else {
-                location_str
-            };
-            files.push(ObjectStoreFile::new(
-                &join_url(&self.url, relative_path)?,
-                self.store.clone(),
-                &location,
-            )?)
+        self.list_files_stream().try_collect().await
+    }
+
+    /// Lists the files in the directory as a stream, following the store's paginated listing
+    /// rather than collecting everything first. Prefer this over `list_files()` for
+    /// directories that may contain very many objects.
+    pub fn list_files_stream(
+        &self,
+    ) -> BoxStream<'static, Result<ObjectStoreFile, BundlebaseError>> {
+        let url = self.url.clone();
+        let store = self.store.clone();
+        let prefix = self.path.clone();
+
+        self.store
+            .list(Some(&self.path))
+            .map(move |meta_result| {
+                let location = meta_result?.location;
+                // Get the relative path from the prefix to location by stripping the prefix
+                let location_str = location.as_ref();
+                let relative_path = match location_str.strip_prefix(prefix.as_ref()) {
+                    Some(stripped) => stripped.trim_start_matches('/'),
+                    None => location_str,
+                };
+                ObjectStoreFile::new(&join_url(&url, relative_path)?, store.clone(), &location)
+            })
+            .boxed()
+    }
+
+    /// Lists the immediate children of this directory: the subdirectories and the files directly
+    /// inside it, without descending further. Uses the store's delimiter listing where it is
+    /// supported, otherwise groups a recursive listing by its first path segment.
+    pub async fn list_level(
+        &self,
+    ) -> Result<(Vec<ObjectStoreDir>, Vec<ObjectStoreFile>), BundlebaseError> {
+        let mut dir_names = BTreeSet::new();
+        let mut file_names = BTreeSet::new();
+
+        match self.store.list_with_delimiter(Some(&self.path)).await {
+            Ok(result) => {
+                for prefix in result.common_prefixes {
+                    if let Some(name) = prefix.filename() {
+                        dir_names.insert(name.to_string());
+                    }
+                }
+                for meta in result.objects {
+                    if let Some(name) = meta.location.filename() {
+                        file_names.insert(name.to_string());
+                    }
+                }
+            }
+            Err(object_store::Error::NotImplemented) => {
+                let mut files = self.store.list(Some(&self.path));
+                while let Some(meta) = files.next().await {
+                    let location = meta?.location;
+                    let relative = location
+                        .prefix_match(&self.path)
+                        .map(|parts| parts.map(|p| p.as_ref().to_string()).collect::<Vec<_>>())
+                        .unwrap_or_default();
+                    match relative.as_slice() {
+                        [] => {}
+                        [name] => {
+                            file_names.insert(name.clone());
+                        }
+                        [name, ..] => {
+                            dir_names.insert(name.clone());
+                        }
+                    }
+                }
+            }
+            Err(e) => return Err(e.into()),
         }
-        Ok(files)
+
+        let dirs = dir_names
+            .iter()
+            .map(|name| self.subdir(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let files = file_names
+            .iter()
+            .map(|name| self.file(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((dirs, files))
     }
 
     /// Returns a new directory object representing a subdirectory of this directory.
@@ -109,7 +174,10 @@ impl ObjectStoreDir {
         // Reuse the existing store instead of creating a new one
         // This is important for stores like TarObjectStore where the URL might not
         // indicate the store type (e.g., file:///path.tar/subdir/file.txt)
-        ObjectStoreFile::new(&file_url, self.store.clone(), &object_path)
+        Ok(
+            ObjectStoreFile::new(&file_url, self.store.clone(), &object_path)?
+                .with_multipart_threshold(self.config.multipart_threshold()?),
+        )
     }
 
     /// Creates a memory-backed directory for storing index and metadata files
@@ -127,7 +195,7 @@ impl Display for ObjectStoreDir {
     }
 }
 
-fn str_to_url(path: &str) -> Result<Url, BundlebaseError> {
+pub(crate) fn str_to_url(path: &str) -> Result<Url, BundlebaseError> {
     if path.contains(":") {
         Ok(Url::parse(path)?)
     } else {
@@ -228,6 +296,208 @@ mod tests {
         assert_eq!(0, dir.list_files().await.unwrap().len())
     }
 
+    #[tokio::test]
+    async fn test_list_files_stream() {
+        let dir = crate::test_utils::random_memory_dir();
+        for name in ["a.csv", "b.json", "sub/c.csv"] {
+            dir.file(name)
+                .unwrap()
+                .write(bytes::Bytes::from("x"))
+                .await
+                .unwrap();
+        }
+
+        let mut urls: Vec<String> = dir
+            .list_files_stream()
+            .map(|file| file.unwrap().url().to_string())
+            .collect()
+            .await;
+        urls.sort();
+        assert_eq!(
+            vec![
+                format!("{}/a.csv", dir.url()),
+                format!("{}/b.json", dir.url()),
+                format!("{}/sub/c.csv", dir.url()),
+            ],
+            urls
+        );
+    }
+
+    async fn assert_level(dir: &ObjectStoreDir, expected_dirs: &[&str], expected_files: &[&str]) {
+        let (dirs, files) = dir.list_level().await.unwrap();
+        let dirs: Vec<String> = dirs.iter().map(|d| d.url().to_string()).collect();
+        let files: Vec<String> = files.iter().map(|f| f.url().to_string()).collect();
+        let expected_dirs: Vec<String> = expected_dirs
+            .iter()
+            .map(|name| format!("{}/{}", dir.url(), name))
+            .collect();
+        let expected_files: Vec<String> = expected_files
+            .iter()
+            .map(|name| format!("{}/{}", dir.url(), name))
+            .collect();
+        assert_eq!(expected_dirs, dirs);
+        assert_eq!(expected_files, files);
+    }
+
+    #[tokio::test]
+    async fn test_list_level() {
+        let dir = crate::test_utils::random_memory_dir();
+        for name in [
+            "a.csv",
+            "sub/b.csv",
+            "sub/deep/c.csv",
+            "sub2/d.csv",
+            "subway.csv",
+        ] {
+            dir.file(name)
+                .unwrap()
+                .write(bytes::Bytes::from("x"))
+                .await
+                .unwrap();
+        }
+
+        assert_level(&dir, &["sub", "sub2"], &["a.csv", "subway.csv"]).await;
+        assert_level(&dir.subdir("sub").unwrap(), &["deep"], &["b.csv"]).await;
+        assert_level(&dir.subdir("missing").unwrap(), &[], &[]).await;
+    }
+
+    #[tokio::test]
+    async fn test_list_level_tar() {
+        let tar_file = tempfile::NamedTempFile::new().unwrap();
+        let store =
+            Arc::new(crate::io::TarObjectStore::new(tar_file.path().to_path_buf()).unwrap());
+        for name in ["a.csv", "sub/b.csv", "sub/deep/c.csv", "subway.csv"] {
+            store
+                .put(&ObjectPath::from(name), bytes::Bytes::from("x").into())
+                .await
+                .unwrap();
+        }
+
+        let dir = ObjectStoreDir::new(
+            &Url::parse("file:///data.tar").unwrap(),
+            store,
+            &ObjectPath::from(""),
+            BundleConfig::default().into(),
+        )
+        .unwrap();
+        assert_level(&dir, &["sub"], &["a.csv", "subway.csv"]).await;
+        assert_level(&dir.subdir("sub").unwrap(), &["deep"], &["b.csv"]).await;
+    }
+
+    /// A store that generates a huge listing on demand and counts how much of it was consumed
+    #[derive(Debug, Default)]
+    struct SyntheticListingStore {
+        listed: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Display for SyntheticListingStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SyntheticListingStore")
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ObjectStore for SyntheticListingStore {
+        async fn put_opts(
+            &self,
+            _location: &ObjectPath,
+            _payload: object_store::PutPayload,
+            _opts: object_store::PutOptions,
+        ) -> object_store::Result<object_store::PutResult> {
+            Err(object_store::Error::NotImplemented)
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            _location: &ObjectPath,
+            _opts: object_store::PutMultipartOptions,
+        ) -> object_store::Result<Box<dyn object_store::MultipartUpload>> {
+            Err(object_store::Error::NotImplemented)
+        }
+
+        async fn get_opts(
+            &self,
+            _location: &ObjectPath,
+            _options: object_store::GetOptions,
+        ) -> object_store::Result<object_store::GetResult> {
+            Err(object_store::Error::NotImplemented)
+        }
+
+        async fn delete(&self, _location: &ObjectPath) -> object_store::Result<()> {
+            Err(object_store::Error::NotImplemented)
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&ObjectPath>,
+        ) -> BoxStream<'static, object_store::Result<object_store::ObjectMeta>> {
+            let prefix = prefix.cloned().unwrap_or_default();
+            let listed = self.listed.clone();
+            futures::stream::iter(0..10_000_000)
+                .map(move |i| {
+                    listed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Ok(object_store::ObjectMeta {
+                        location: prefix.child(format!("part-{}.csv", i)),
+                        last_modified: chrono::Utc::now(),
+                        size: 1,
+                        e_tag: None,
+                        version: None,
+                    })
+                })
+                .boxed()
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            _prefix: Option<&ObjectPath>,
+        ) -> object_store::Result<object_store::ListResult> {
+            Err(object_store::Error::NotImplemented)
+        }
+
+        async fn copy(&self, _from: &ObjectPath, _to: &ObjectPath) -> object_store::Result<()> {
+            Err(object_store::Error::NotImplemented)
+        }
+
+        async fn copy_if_not_exists(
+            &self,
+            _from: &ObjectPath,
+            _to: &ObjectPath,
+        ) -> object_store::Result<()> {
+            Err(object_store::Error::NotImplemented)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_files_stream_is_lazy() {
+        let store = Arc::new(SyntheticListingStore::default());
+        let dir = ObjectStoreDir::new(
+            &Url::parse("synthetic://bucket/data").unwrap(),
+            store.clone(),
+            &ObjectPath::from("data"),
+            BundleConfig::default().into(),
+        )
+        .unwrap();
+
+        let glob = crate::io::GlobPattern::parse("part-1?.csv")
+            .unwrap()
+            .unwrap();
+        let matches: Vec<String> = dir
+            .list_files_stream()
+            .try_filter_map(|file| {
+                let name = file.filename().to_string();
+                let matched = glob.matches(&name);
+                async move { Ok(matched.then_some(name)) }
+            })
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["part-10.csv", "part-11.csv", "part-12.csv"], matches);
+        // Only the prefix of the listing needed to find the matches was generated
+        assert_eq!(13, store.listed.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
     #[tokio::test]
     async fn test_null_url() {
         let dir = ObjectStoreDir::from_str(EMPTY_URL, BundleConfig::default().into()).unwrap();
@@ -1,23 +1,51 @@
+use crate::bundle_config::DEFAULT_MULTIPART_THRESHOLD_MB;
 use crate::BundleConfig;
 use object_store::path::Path as ObjectPath;
 use object_store::{ObjectMeta, ObjectStore};
 
 use crate::io::util::{compute_store_url, parse_url};
 use crate::io::{ObjectStoreDir, EMPTY_SCHEME};
+use crate::progress::ProgressScope;
 use crate::BundlebaseError;
 use datafusion::execution::object_store::ObjectStoreUrl;
 use futures::stream::{StreamExt, TryStreamExt};
+use object_store::WriteMultipart;
+use parking_lot::Mutex;
 use serde::ser;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
 use url::Url;
 
+/// Part size used when streaming writes through a multipart upload
+const WRITE_CHUNK_SIZE: usize = 5 * 1024 * 1024;
+
+/// Maximum number of parts uploading at once while streaming a write
+const WRITE_MAX_CONCURRENCY: usize = 2;
+
+tokio::task_local! {
+    /// Metadata fetched by `head` so far in the current `with_metadata_cache` call, by file URL
+    static METADATA_CACHE: Mutex<HashMap<Url, ObjectMeta>>;
+}
+
+/// Runs `f` with files only calling `head` on the store the first time their metadata is needed,
+/// so checks like `exists`, `metadata` and `version` on the same file share one round-trip.
+/// The cache is dropped when `f` finishes, and writes through any file clear its entry.
+pub async fn with_metadata_cache<F: Future>(f: F) -> F::Output {
+    METADATA_CACHE.scope(Mutex::new(HashMap::new()), f).await
+}
+
 #[derive(Debug, Clone)]
 pub struct ObjectStoreFile {
     url: Url,
     store: Arc<dyn ObjectStore>,
     path: ObjectPath,
+    /// Writes larger than this many bytes use a multipart upload
+    multipart_threshold: usize,
 }
 
 impl ObjectStoreFile {
@@ -28,7 +56,7 @@ impl ObjectStoreFile {
         let config_map = config.get_config_for_url(url);
         let (store, path) = parse_url(url, &config_map)?;
 
-        Self::new(&url, store, &path)
+        Ok(Self::new(url, store, &path)?.with_multipart_threshold(config.multipart_threshold()?))
     }
 
     /// Creates a file from the passed string. The string can be either a URL or a path relative to the passed base_dir.
@@ -55,9 +83,16 @@ impl ObjectStoreFile {
             url: url.clone(),
             store,
             path: path.clone(),
+            multipart_threshold: DEFAULT_MULTIPART_THRESHOLD_MB * 1024 * 1024,
         })
     }
 
+    /// Sets the size in bytes above which writes use a multipart upload instead of a single put
+    pub fn with_multipart_threshold(mut self, bytes: usize) -> Self {
+        self.multipart_threshold = bytes;
+        self
+    }
+
     pub fn filename(&self) -> &str {
         self.path.filename().unwrap()
     }
@@ -70,7 +105,7 @@ impl ObjectStoreFile {
         self.store.clone()
     }
 
-    pub fn store_url(&self) -> ObjectStoreUrl {
+    pub fn store_url(&self) -> Result<ObjectStoreUrl, BundlebaseError> {
         compute_store_url(self.url())
     }
 
@@ -78,8 +113,30 @@ impl ObjectStoreFile {
         &self.path
     }
 
+    /// Returns the file's metadata, reusing what `head` returned earlier in the same
+    /// `with_metadata_cache` call. Missing files aren't cached, so a file created elsewhere is
+    /// seen on the next call.
+    async fn head(&self) -> object_store::Result<ObjectMeta> {
+        let cached = METADATA_CACHE
+            .try_with(|cache| cache.lock().get(&self.url).cloned())
+            .ok()
+            .flatten();
+        if let Some(meta) = cached {
+            return Ok(meta);
+        }
+        let meta = self.store.head(&self.path).await?;
+        let _ =
+            METADATA_CACHE.try_with(|cache| cache.lock().insert(self.url.clone(), meta.clone()));
+        Ok(meta)
+    }
+
+    /// Drops the cached metadata before the file is changed
+    fn invalidate_metadata(&self) {
+        let _ = METADATA_CACHE.try_with(|cache| cache.lock().remove(&self.url));
+    }
+
     pub async fn exists(&self) -> Result<bool, BundlebaseError> {
-        match self.store.head(&self.path).await {
+        match self.head().await {
             Ok(_) => Ok(true),
             Err(e) => {
                 if matches!(e, object_store::Error::NotFound { .. }) {
@@ -107,6 +164,25 @@ impl ObjectStoreFile {
         }
     }
 
+    /// Reads the given byte range of the file, returning `None` if the file doesn't exist.
+    /// A range extending past the end of the file returns the bytes up to the end.
+    /// Uses the store's native range support, so only the requested bytes are fetched.
+    pub async fn read_range(
+        &self,
+        range: std::ops::Range<u64>,
+    ) -> Result<Option<bytes::Bytes>, BundlebaseError> {
+        match self.store.get_range(&self.path, range).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) => {
+                if matches!(e, object_store::Error::NotFound { .. }) {
+                    Ok(None)
+                } else {
+                    Err(Box::new(e))
+                }
+            }
+        }
+    }
+
     pub async fn read_yaml<T>(&self) -> Result<Option<T>, BundlebaseError>
     where
         T: serde::de::DeserializeOwned,
@@ -168,18 +244,104 @@ impl ObjectStoreFile {
     }
 
     /// Writes data to the file, overwriting if it exists.
+    /// Data larger than the multipart threshold is uploaded in parts, see `write_stream`.
     pub async fn write(&self, data: bytes::Bytes) -> Result<(), BundlebaseError> {
         if self.url.scheme() == EMPTY_SCHEME {
             return Err(format!("Cannot write to {}:// URL: {}", EMPTY_SCHEME, self.url).into());
         }
+        self.invalidate_metadata();
+
+        if data.len() > self.multipart_threshold {
+            return self
+                .write_stream(futures::stream::iter(vec![Ok::<_, std::io::Error>(data)]))
+                .await;
+        }
 
         let put_result = object_store::PutPayload::from_bytes(data);
         self.store.put(&self.path, put_result).await?;
         Ok(())
     }
 
+    /// Writes data to the file only if it doesn't already exist, returning false if it did.
+    ///
+    /// The data is written to a temporary sibling object which is then moved into place with
+    /// `copy_if_not_exists`, so only one of several concurrent writers can succeed.
+    pub async fn write_if_not_exists(&self, data: bytes::Bytes) -> Result<bool, BundlebaseError> {
+        if self.url.scheme() == EMPTY_SCHEME {
+            return Err(format!("Cannot write to {}:// URL: {}", EMPTY_SCHEME, self.url).into());
+        }
+        self.invalidate_metadata();
+
+        let temp_path = ObjectPath::from(format!("{}.{}.tmp", self.path, uuid::Uuid::new_v4()));
+        self.store
+            .put(&temp_path, object_store::PutPayload::from_bytes(data))
+            .await?;
+        let result = self.store.copy_if_not_exists(&temp_path, &self.path).await;
+        self.store.delete(&temp_path).await?;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::AlreadyExists { .. }) => Ok(false),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    /// Appends data to the end of the file, creating it if it doesn't exist.
+    ///
+    /// Local files are opened in append mode. Object stores have no append, so the existing
+    /// content is read and written back with `data` added, which is only suitable for small
+    /// files. Entries in a tar archive cannot be modified, so appending to an existing one fails.
+    pub async fn append(&self, data: bytes::Bytes) -> Result<(), BundlebaseError> {
+        if self.url.scheme() == EMPTY_SCHEME {
+            return Err(format!("Cannot write to {}:// URL: {}", EMPTY_SCHEME, self.url).into());
+        }
+        self.invalidate_metadata();
+
+        if self.store_url()?.as_str().starts_with("tar://") {
+            if self.exists().await? {
+                return Err(format!(
+                    "Cannot append to {}: existing tar archive entries cannot be modified",
+                    self.url
+                )
+                .into());
+            }
+            return self.write(data).await;
+        }
+
+        if self.url.scheme() == "file" {
+            if let Ok(path) = self.url.to_file_path() {
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .await?;
+                file.write_all(&data).await?;
+                file.flush().await?;
+                return Ok(());
+            }
+        }
+
+        let combined = match self.read_bytes().await? {
+            Some(existing) => {
+                let mut buffer = Vec::with_capacity(existing.len() + data.len());
+                buffer.extend_from_slice(&existing);
+                buffer.extend_from_slice(&data);
+                bytes::Bytes::from(buffer)
+            }
+            None => data,
+        };
+        self.write(combined).await
+    }
+
     /// Writes a stream of bytes to the file, overwriting if it exists.
-    /// The stream is collected into a buffer before writing.
+    ///
+    /// Streams up to the multipart threshold are collected and written with a single put. Larger
+    /// ones are uploaded in `WRITE_CHUNK_SIZE` parts so memory use stays bounded regardless of
+    /// the file size. Stores without multipart support (such as tar archives) get the stream
+    /// collected into a buffer.
     pub async fn write_stream<S>(&self, mut source: S) -> Result<(), BundlebaseError>
     where
         S: futures::stream::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Unpin,
@@ -187,7 +349,49 @@ impl ObjectStoreFile {
         if self.url.scheme() == EMPTY_SCHEME {
             return Err(format!("Cannot write to {}:// URL: {}", EMPTY_SCHEME, self.url).into());
         }
+        self.invalidate_metadata();
+
+        let mut head = Vec::new();
+        while head.len() <= self.multipart_threshold {
+            match source.next().await {
+                Some(chunk) => head.extend_from_slice(&chunk?),
+                None => {
+                    let payload = object_store::PutPayload::from_bytes(bytes::Bytes::from(head));
+                    self.store.put(&self.path, payload).await?;
+                    return Ok(());
+                }
+            }
+        }
+        let mut source = futures::stream::iter(vec![Ok(bytes::Bytes::from(head))]).chain(source);
 
+        let upload = match self.store.put_multipart(&self.path).await {
+            Ok(upload) => upload,
+            Err(object_store::Error::NotImplemented) => {
+                return self.write_stream_buffered(source).await
+            }
+            Err(e) => return Err(Box::new(e)),
+        };
+
+        let mut writer = WriteMultipart::new_with_chunk_size(upload, WRITE_CHUNK_SIZE);
+        while let Some(chunk_result) = source.next().await {
+            let chunk = match chunk_result {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    let _ = writer.abort().await;
+                    return Err(Box::new(e));
+                }
+            };
+            writer.wait_for_capacity(WRITE_MAX_CONCURRENCY).await?;
+            writer.put(chunk);
+        }
+        writer.finish().await?;
+        Ok(())
+    }
+
+    async fn write_stream_buffered<S>(&self, mut source: S) -> Result<(), BundlebaseError>
+    where
+        S: futures::stream::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Unpin,
+    {
         // Collect stream into a single buffer
         let mut buffer = Vec::new();
         while let Some(chunk_result) = source.next().await {
@@ -200,6 +404,84 @@ impl ObjectStoreFile {
         Ok(())
     }
 
+    /// Copies this file to `dest`, streaming the content rather than loading it into memory.
+    /// Progress is reported in bytes copied. Returns the number of bytes copied.
+    ///
+    /// The copy is written to a temporary sibling of `dest` and only renamed into place once
+    /// its size and SHA256 checksum match what was read, so an interrupted copy never leaves a
+    /// truncated file at `dest`. Tar archives cannot rename, so there `dest` is written directly
+    /// and is only referenced once the commit that packed it is written.
+    pub async fn copy_to(&self, dest: &ObjectStoreFile) -> Result<u64, BundlebaseError> {
+        let size = self.metadata().await?.map(|meta| meta.size);
+        let progress = ProgressScope::new(&format!("Copying '{}'", self.url), size);
+
+        let target = if dest.store_url()?.as_str().starts_with("tar://") {
+            dest.clone()
+        } else {
+            let suffix = format!("{}.tmp", uuid::Uuid::new_v4());
+            ObjectStoreFile::new(
+                &Url::parse(&format!("{}.{}", dest.url, suffix))?,
+                dest.store.clone(),
+                &ObjectPath::from(format!("{}.{}", dest.path, suffix)),
+            )?
+            .with_multipart_threshold(dest.multipart_threshold)
+        };
+
+        let copied = Arc::new(AtomicU64::new(0));
+        let counter = copied.clone();
+        let hasher = Arc::new(Mutex::new(Sha256::new()));
+        let source_hasher = hasher.clone();
+        let stream = self.read_existing().await?.map(move |chunk| {
+            let chunk = chunk.map_err(std::io::Error::other)?;
+            source_hasher.lock().update(&chunk);
+            let total =
+                counter.fetch_add(chunk.len() as u64, Ordering::Relaxed) + chunk.len() as u64;
+            progress.update(total, None);
+            Ok(chunk)
+        });
+        target.write_stream(stream).await?;
+        let copied = copied.load(Ordering::Relaxed);
+        let expected = hex::encode(hasher.lock().clone().finalize());
+
+        if let Err(e) = target.verify_copy(size.unwrap_or(copied), &expected).await {
+            if target.url != dest.url {
+                target.delete().await?;
+            }
+            return Err(format!("Incomplete copy of {} to {}: {}", self.url, dest.url, e).into());
+        }
+
+        if target.url != dest.url {
+            dest.invalidate_metadata();
+            dest.store.rename(&target.path, &dest.path).await?;
+        }
+
+        Ok(copied)
+    }
+
+    /// Checks that the file has the given size and SHA256 checksum, as written by `copy_to`.
+    async fn verify_copy(&self, size: u64, checksum: &str) -> Result<(), BundlebaseError> {
+        let actual_size = self.metadata().await?.map(|meta| meta.size).unwrap_or(0);
+        if actual_size != size {
+            return Err(format!("expected {} bytes but found {}", size, actual_size).into());
+        }
+
+        let actual = self.checksum().await?;
+        if actual != checksum {
+            return Err(format!("expected checksum {} but found {}", checksum, actual).into());
+        }
+        Ok(())
+    }
+
+    /// Hex-encoded SHA256 checksum of the file's content, read as a stream.
+    pub async fn checksum(&self) -> Result<String, BundlebaseError> {
+        let mut hasher = Sha256::new();
+        let mut stream = self.read_existing().await?;
+        while let Some(chunk) = stream.next().await {
+            hasher.update(&chunk?);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
     pub async fn write_yaml<T>(&self, value: &T) -> Result<(), BundlebaseError>
     where
         T: ?Sized + ser::Serialize,
@@ -213,7 +495,7 @@ impl ObjectStoreFile {
 
     /// Returns file metadata, or `None` if the file doesn't exist.
     pub async fn metadata(&self) -> Result<Option<ObjectMeta>, BundlebaseError> {
-        match self.store.head(&self.path).await {
+        match self.head().await {
             Ok(meta) => Ok(Some(meta)),
             Err(e) => {
                 if matches!(e, object_store::Error::NotFound { .. }) {
@@ -227,7 +509,7 @@ impl ObjectStoreFile {
 
     /// Returns a version identifier for the file (e.g., ETag, last modified time, or version ID).
     pub async fn version(&self) -> Result<String, BundlebaseError> {
-        let meta = self.store.head(&self.path).await?;
+        let meta = self.head().await?;
         // Priority: Version (S3 style) → ETag (HTTP standard) → LastModified (hashed timestamp)
         let version = if meta
             .version
@@ -253,6 +535,7 @@ impl ObjectStoreFile {
 
     /// Deletes the file, returning Ok even if the file doesn't exist.
     pub async fn delete(&self) -> Result<(), BundlebaseError> {
+        self.invalidate_metadata();
         match self.store.delete(&self.path).await {
             Ok(_) => Ok(()),
             Err(e) => {
@@ -275,6 +558,8 @@ impl Display for ObjectStoreFile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::progress::mock::{MockTracker, ProgressCall};
+    use crate::progress::with_tracker;
     use crate::test_utils::random_memory_file;
     use crate::BundleConfig;
 
@@ -297,6 +582,31 @@ mod tests {
         assert_eq!(file.filename(), "test.json");
     }
 
+    #[tokio::test]
+    async fn test_read_range() {
+        let file = random_memory_file("range.txt");
+        assert_eq!(None, file.read_range(0..5).await.unwrap());
+
+        file.write(bytes::Bytes::from("0123456789")).await.unwrap();
+        assert_eq!(
+            Some(bytes::Bytes::from("234")),
+            file.read_range(2..5).await.unwrap()
+        );
+
+        // Matches slicing the full contents
+        let all = file.read_bytes().await.unwrap().unwrap();
+        assert_eq!(
+            Some(all.slice(4..10)),
+            file.read_range(4..10).await.unwrap()
+        );
+
+        // Past the end returns the remainder
+        assert_eq!(
+            Some(bytes::Bytes::from("89")),
+            file.read_range(8..100).await.unwrap()
+        );
+    }
+
     #[tokio::test]
     async fn test_read_write() {
         let file = random_memory_file("test.json");
@@ -312,6 +622,84 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_append() {
+        let file = random_memory_file("log.txt");
+        file.append(bytes::Bytes::from("first\n")).await.unwrap();
+        file.append(bytes::Bytes::from("second\n")).await.unwrap();
+        assert_eq!(
+            Some("first\nsecond\n".to_string()),
+            file.read_str().await.unwrap()
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let local = ObjectStoreFile::from_url(
+            &Url::from_file_path(dir.path().join("logs/log.txt")).unwrap(),
+            BundleConfig::default().into(),
+        )
+        .unwrap();
+        local.append(bytes::Bytes::from("first\n")).await.unwrap();
+        local.append(bytes::Bytes::from("second\n")).await.unwrap();
+        assert_eq!(
+            "first\nsecond\n",
+            std::fs::read_to_string(dir.path().join("logs/log.txt")).unwrap()
+        );
+
+        let archive = ObjectStoreDir::from_url(
+            &Url::from_file_path(dir.path().join("archive.tar")).unwrap(),
+            BundleConfig::default().into(),
+        )
+        .unwrap();
+        archive
+            .file("other.txt")
+            .unwrap()
+            .write(bytes::Bytes::from("other"))
+            .await
+            .unwrap();
+        let tar = archive.file("log.txt").unwrap();
+        tar.append(bytes::Bytes::from("first\n")).await.unwrap();
+        assert!(tar.append(bytes::Bytes::from("second\n")).await.is_err());
+        assert_eq!(Some("first\n".to_string()), tar.read_str().await.unwrap());
+
+        let null = ObjectStoreFile::from_url(
+            &Url::parse("empty:///log.txt").unwrap(),
+            BundleConfig::default().into(),
+        )
+        .unwrap();
+        assert!(null.append(bytes::Bytes::from("data")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_if_not_exists() {
+        let file = random_memory_file("lock");
+        assert!(file
+            .write_if_not_exists(bytes::Bytes::from("first"))
+            .await
+            .unwrap());
+        assert!(!file
+            .write_if_not_exists(bytes::Bytes::from("second"))
+            .await
+            .unwrap());
+        assert_eq!(Some("first".to_string()), file.read_str().await.unwrap());
+
+        let dir = tempfile::tempdir().unwrap();
+        let local = ObjectStoreFile::from_url(
+            &Url::from_file_path(dir.path().join("lock")).unwrap(),
+            BundleConfig::default().into(),
+        )
+        .unwrap();
+        assert!(local
+            .write_if_not_exists(bytes::Bytes::from("first"))
+            .await
+            .unwrap());
+        assert!(!local
+            .write_if_not_exists(bytes::Bytes::from("second"))
+            .await
+            .unwrap());
+        // The temporary object is cleaned up
+        assert_eq!(1, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+
     #[tokio::test]
     async fn test_null() {
         let file = ObjectStoreFile::from_url(
@@ -348,4 +736,344 @@ mod tests {
             bytes::Bytes::from(buffer)
         );
     }
+
+    /// Wraps an in-memory store and records the size of every write and the number of heads.
+    /// With `truncate_puts` set, puts store at most that many bytes, like an interrupted write.
+    #[derive(Debug, Default)]
+    struct RecordingStore {
+        inner: object_store::memory::InMemory,
+        truncate_puts: Option<usize>,
+        puts: Arc<AtomicU64>,
+        heads: Arc<AtomicU64>,
+        part_sizes: Arc<parking_lot::Mutex<Vec<usize>>>,
+    }
+
+    impl Display for RecordingStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "RecordingStore")
+        }
+    }
+
+    #[derive(Debug)]
+    struct RecordingUpload {
+        inner: Box<dyn object_store::MultipartUpload>,
+        part_sizes: Arc<parking_lot::Mutex<Vec<usize>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl object_store::MultipartUpload for RecordingUpload {
+        fn put_part(&mut self, data: object_store::PutPayload) -> object_store::UploadPart {
+            self.part_sizes.lock().push(data.content_length());
+            self.inner.put_part(data)
+        }
+
+        async fn complete(&mut self) -> object_store::Result<object_store::PutResult> {
+            self.inner.complete().await
+        }
+
+        async fn abort(&mut self) -> object_store::Result<()> {
+            self.inner.abort().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ObjectStore for RecordingStore {
+        async fn put_opts(
+            &self,
+            location: &ObjectPath,
+            payload: object_store::PutPayload,
+            opts: object_store::PutOptions,
+        ) -> object_store::Result<object_store::PutResult> {
+            self.puts.fetch_add(1, Ordering::SeqCst);
+            let payload = match self.truncate_puts {
+                Some(len) => {
+                    let data: Vec<u8> = payload.iter().flatten().copied().take(len).collect();
+                    object_store::PutPayload::from(data)
+                }
+                None => payload,
+            };
+            self.inner.put_opts(location, payload, opts).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &ObjectPath,
+            opts: object_store::PutMultipartOptions,
+        ) -> object_store::Result<Box<dyn object_store::MultipartUpload>> {
+            Ok(Box::new(RecordingUpload {
+                inner: self.inner.put_multipart_opts(location, opts).await?,
+                part_sizes: self.part_sizes.clone(),
+            }))
+        }
+
+        async fn get_opts(
+            &self,
+            location: &ObjectPath,
+            options: object_store::GetOptions,
+        ) -> object_store::Result<object_store::GetResult> {
+            if options.head {
+                self.heads.fetch_add(1, Ordering::SeqCst);
+            }
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &ObjectPath) -> object_store::Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&ObjectPath>,
+        ) -> futures::stream::BoxStream<'static, object_store::Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            prefix: Option<&ObjectPath>,
+        ) -> object_store::Result<object_store::ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &ObjectPath, to: &ObjectPath) -> object_store::Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(
+            &self,
+            from: &ObjectPath,
+            to: &ObjectPath,
+        ) -> object_store::Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    fn recording_file(store: &Arc<RecordingStore>, name: &str) -> ObjectStoreFile {
+        ObjectStoreFile::new(
+            &Url::parse(&format!("recording:///{}", name)).unwrap(),
+            store.clone(),
+            &ObjectPath::from(name),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_metadata_is_cached_within_scope() {
+        let store = Arc::new(RecordingStore::default());
+        let file = recording_file(&store, "cached.txt");
+        file.write(bytes::Bytes::from("hello")).await.unwrap();
+
+        // Without a cache every check is its own head
+        assert!(file.exists().await.unwrap());
+        file.version().await.unwrap();
+        assert_eq!(2, store.heads.load(Ordering::SeqCst));
+        store.heads.store(0, Ordering::SeqCst);
+
+        with_metadata_cache(async {
+            // Missing files aren't cached
+            let missing = recording_file(&store, "missing.txt");
+            assert!(!missing.exists().await.unwrap());
+            assert!(!missing.exists().await.unwrap());
+            assert_eq!(2, store.heads.load(Ordering::SeqCst));
+            store.heads.store(0, Ordering::SeqCst);
+
+            // exists, metadata and version share one head, as during an attach
+            assert!(file.exists().await.unwrap());
+            let version = file.version().await.unwrap();
+            assert_eq!(5, file.metadata().await.unwrap().unwrap().size);
+            assert_eq!(1, store.heads.load(Ordering::SeqCst));
+
+            // A write through another file for the same path clears the entry
+            recording_file(&store, "cached.txt")
+                .write(bytes::Bytes::from("hello world"))
+                .await
+                .unwrap();
+            assert_eq!(11, file.metadata().await.unwrap().unwrap().size);
+            assert_ne!(version, file.version().await.unwrap());
+            assert_eq!(2, store.heads.load(Ordering::SeqCst));
+        })
+        .await;
+
+        // The cache doesn't outlive the scope
+        file.version().await.unwrap();
+        assert_eq!(3, store.heads.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_small_uses_put() {
+        let store = Arc::new(RecordingStore::default());
+        let file = recording_file(&store, "small.txt");
+
+        let chunks = (0..3).map(|_| Ok::<_, std::io::Error>(bytes::Bytes::from("hello ")));
+        file.write_stream(futures::stream::iter(chunks))
+            .await
+            .unwrap();
+
+        assert_eq!(1, store.puts.load(Ordering::SeqCst));
+        assert!(store.part_sizes.lock().is_empty());
+        assert_eq!(
+            Some("hello hello hello ".to_string()),
+            file.read_str().await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_large_uses_multipart() {
+        let store = Arc::new(RecordingStore::default());
+        let file = recording_file(&store, "large.bin").with_multipart_threshold(1024 * 1024);
+
+        // Generated as it is read, so the whole file never exists in memory on the writing side
+        let chunk_size = 64 * 1024;
+        let total = WRITE_CHUNK_SIZE * 3;
+        let chunks = futures::stream::iter((0..total / chunk_size).map(move |i| {
+            Ok::<_, std::io::Error>(bytes::Bytes::from(vec![(i % 251) as u8; chunk_size]))
+        }));
+        file.write_stream(chunks).await.unwrap();
+
+        assert_eq!(0, store.puts.load(Ordering::SeqCst));
+        assert_eq!(
+            vec![WRITE_CHUNK_SIZE, WRITE_CHUNK_SIZE, WRITE_CHUNK_SIZE],
+            *store.part_sizes.lock()
+        );
+        assert_eq!(total, file.read_bytes().await.unwrap().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn test_write_over_threshold_uses_multipart() {
+        let store = Arc::new(RecordingStore::default());
+        let file = recording_file(&store, "large.bin").with_multipart_threshold(1024);
+
+        file.write(bytes::Bytes::from(vec![1u8; 4096]))
+            .await
+            .unwrap();
+        assert_eq!(0, store.puts.load(Ordering::SeqCst));
+        assert_eq!(vec![4096], *store.part_sizes.lock());
+
+        file.write(bytes::Bytes::from(vec![1u8; 512]))
+            .await
+            .unwrap();
+        assert_eq!(1, store.puts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_write_over_threshold_to_tar() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = BundleConfig::new();
+        config.set(crate::bundle_config::MULTIPART_THRESHOLD_MB, "0", None);
+        let archive = ObjectStoreDir::from_url(
+            &Url::from_file_path(dir.path().join("archive.tar")).unwrap(),
+            config.into(),
+        )
+        .unwrap();
+
+        // Tar archives have no multipart support, so large writes fall back to a single put
+        let file = archive.file("data.bin").unwrap();
+        file.write(bytes::Bytes::from(vec![7u8; 4096]))
+            .await
+            .unwrap();
+        assert_eq!(
+            Some(bytes::Bytes::from(vec![7u8; 4096])),
+            file.read_bytes().await.unwrap()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial_test::serial]
+    async fn test_copy_to_streams_in_parts() {
+        let source = random_memory_file("big.parquet");
+        let data: bytes::Bytes = (0..WRITE_CHUNK_SIZE * 5 / 2)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<u8>>()
+            .into();
+        source.write(data.clone()).await.unwrap();
+
+        let store = Arc::new(RecordingStore::default());
+        let dest = ObjectStoreFile::new(
+            &Url::parse("recording:///copy/big.parquet").unwrap(),
+            store.clone(),
+            &ObjectPath::from("copy/big.parquet"),
+        )
+        .unwrap();
+
+        let mock = MockTracker::new();
+        let copied = with_tracker(Box::new(mock.clone()), || {
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(source.copy_to(&dest))
+            })
+        })
+        .unwrap();
+
+        assert_eq!(data.len() as u64, copied);
+        assert_eq!(Some(data.clone()), dest.read_bytes().await.unwrap());
+
+        // Written as bounded parts rather than a single put of the whole file
+        assert_eq!(0, store.puts.load(Ordering::SeqCst));
+        assert_eq!(
+            vec![WRITE_CHUNK_SIZE, WRITE_CHUNK_SIZE, WRITE_CHUNK_SIZE / 2],
+            *store.part_sizes.lock()
+        );
+
+        // Progress is reported for the file, in bytes
+        let operation = format!("Copying '{}'", source.url());
+        let (id, total) = mock
+            .starts()
+            .into_iter()
+            .find_map(|call| match call {
+                ProgressCall::Start {
+                    id,
+                    operation: op,
+                    total,
+                } if op == operation => Some((id, total)),
+                _ => None,
+            })
+            .expect("Copy progress should be started");
+        assert_eq!(Some(data.len() as u64), total);
+        let last_update = mock
+            .updates()
+            .into_iter()
+            .rev()
+            .find_map(|call| match call {
+                ProgressCall::Update {
+                    id: update_id,
+                    current,
+                    ..
+                } if update_id == id => Some(current),
+                _ => None,
+            });
+        assert_eq!(Some(data.len() as u64), last_update);
+        assert!(mock.finishes().contains(&ProgressCall::Finish { id }));
+    }
+
+    #[tokio::test]
+    async fn test_copy_to_interrupted_leaves_no_partial_file() {
+        let source = random_memory_file("data.csv");
+        source
+            .write(bytes::Bytes::from(vec![3u8; 1000]))
+            .await
+            .unwrap();
+
+        let store = Arc::new(RecordingStore {
+            truncate_puts: Some(400),
+            ..Default::default()
+        });
+        let dest = recording_file(&store, "copy/data.csv");
+
+        let err = source.copy_to(&dest).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("expected 1000 bytes but found 400"));
+
+        // Neither the partial copy nor its temporary file are left behind
+        assert!(!dest.exists().await.unwrap());
+        let remaining: Vec<_> = store.list(None).try_collect().await.unwrap();
+        assert!(remaining.is_empty());
+
+        // Retrying against a store that completes its writes succeeds
+        let store = Arc::new(RecordingStore::default());
+        let dest = recording_file(&store, "copy/data.csv");
+        assert_eq!(1000, source.copy_to(&dest).await.unwrap());
+        assert_eq!(1000, dest.read_bytes().await.unwrap().unwrap().len());
+        let remaining: Vec<_> = store.list(None).try_collect().await.unwrap();
+        assert_eq!(1, remaining.len());
+    }
 }
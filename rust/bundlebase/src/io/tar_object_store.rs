@@ -3,17 +3,17 @@ use bytes::Bytes;
 use futures::stream::{self, BoxStream, StreamExt};
 use object_store::path::Path as ObjectPath;
 use object_store::{
-    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutOptions,
-    PutPayload, PutResult, Result as ObjectStoreResult,
+    GetOptions, GetRange, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutOptions, PutPayload, PutResult, Result as ObjectStoreResult,
 };
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::fs::{File, OpenOptions};
-use std::io::Read;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::ops::Range;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tar::{Archive, Builder, Header};
 
@@ -29,11 +29,23 @@ use tar::{Archive, Builder, Header};
 /// - No compression support (uncompressed tar only)
 /// - Cannot delete or modify existing entries
 /// - Concurrent writes from multiple processes not supported
+/// - Archives nested inside another archive are read-only
 #[derive(Clone, Debug)]
 pub struct TarObjectStore {
-    tar_path: Arc<PathBuf>,
+    source: Arc<TarSource>,
     index: Arc<RwLock<TarIndex>>,
     indexed: Arc<AtomicBool>,
+    /// How many times file contents were read from the archive
+    reads: Arc<AtomicUsize>,
+}
+
+/// Where the archive's bytes come from
+#[derive(Debug)]
+enum TarSource {
+    /// A tar file on the local filesystem
+    File(PathBuf),
+    /// An archive extracted from inside another archive, identified by its path for display
+    Nested { name: String, bytes: Bytes },
 }
 
 #[derive(Clone, Debug)]
@@ -43,6 +55,7 @@ struct TarIndex {
 
 #[derive(Clone, Debug)]
 struct TarEntry {
+    /// Position of the file's contents in the archive, after its header
     offset: u64,
     size: u64,
     modified: chrono::DateTime<chrono::Utc>,
@@ -55,13 +68,48 @@ impl TarObjectStore {
     /// existing entries and appending new ones. If the file doesn't exist,
     /// it will be created.
     pub fn new(tar_path: PathBuf) -> ObjectStoreResult<Self> {
-        Ok(Self {
-            tar_path: Arc::new(tar_path),
+        Ok(Self::with_source(TarSource::File(tar_path)))
+    }
+
+    /// Opens the archive stored at `path` inside this archive as a read-only store.
+    ///
+    /// The inner archive is read into memory once; writes to the returned store fail.
+    pub fn nested(&self, path: &ObjectPath) -> ObjectStoreResult<Self> {
+        let bytes = self.read_entry(path)?;
+        Ok(Self::with_source(TarSource::Nested {
+            name: format!("{}/{}", self.source, path),
+            bytes,
+        }))
+    }
+
+    /// Whether the archive has a file at `path`
+    pub fn has_entry(&self, path: &ObjectPath) -> ObjectStoreResult<bool> {
+        self.ensure_indexed()?;
+        Ok(self.index.read().entries.contains_key(path))
+    }
+
+    fn with_source(source: TarSource) -> Self {
+        Self {
+            source: Arc::new(source),
             index: Arc::new(RwLock::new(TarIndex {
                 entries: HashMap::new(),
             })),
             indexed: Arc::new(AtomicBool::new(false)),
-        })
+            reads: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Opens the archive for reading from the start
+    fn open(&self) -> ObjectStoreResult<Box<dyn ReadSeek>> {
+        match &*self.source {
+            TarSource::File(path) => Ok(Box::new(File::open(path).map_err(|e| {
+                object_store::Error::Generic {
+                    store: "TarObjectStore",
+                    source: Box::new(e),
+                }
+            })?)),
+            TarSource::Nested { bytes, .. } => Ok(Box::new(Cursor::new(bytes.clone()))),
+        }
     }
 
     /// Builds the index by scanning through the tar file.
@@ -72,21 +120,15 @@ impl TarObjectStore {
             return Ok(());
         }
 
-        let file = File::open(&*self.tar_path).map_err(|e| {
-            object_store::Error::Generic {
-                store: "TarObjectStore",
-                source: Box::new(e),
-            }
-        })?;
-
-        let mut archive = Archive::new(file);
+        let mut archive = Archive::new(self.open()?);
         let mut entries = HashMap::new();
 
-        for (_i, entry_result) in archive.entries().map_err(|e| object_store::Error::Generic {
+        // Seeking past each file's contents means only the headers are read
+        let entries_result = archive.entries_with_seek();
+        for entry_result in entries_result.map_err(|e| object_store::Error::Generic {
             store: "TarObjectStore",
             source: Box::new(e),
-        })?.enumerate()
-        {
+        })? {
             let entry = entry_result.map_err(|e| object_store::Error::Generic {
                 store: "TarObjectStore",
                 source: Box::new(e),
@@ -120,10 +162,8 @@ impl TarObjectStore {
                 })
                 .unwrap_or_else(|| chrono::DateTime::UNIX_EPOCH);
 
-            // Calculate offset - this is an approximation since we're iterating
-            // We'll recalculate precise offsets when we actually need to read
             let tar_entry = TarEntry {
-                offset: 0, // Will be set to proper value when reading
+                offset: entry.raw_file_position(),
                 size,
                 modified,
             };
@@ -147,54 +187,56 @@ impl TarObjectStore {
         Ok(())
     }
 
-    /// Reads a file from the tar archive by scanning to find it.
-    /// This is less efficient than using byte offsets, but tar format
-    /// requires sequential reading for accurate positioning.
+    /// Reads a whole file from the tar archive
     fn read_entry(&self, path: &ObjectPath) -> ObjectStoreResult<Bytes> {
-        let file = File::open(&*self.tar_path).map_err(|e| {
-            object_store::Error::Generic {
-                store: "TarObjectStore",
-                source: Box::new(e),
-            }
-        })?;
-
-        let mut archive = Archive::new(file);
-
-        for entry_result in archive.entries().map_err(|e| object_store::Error::Generic {
-            store: "TarObjectStore",
-            source: Box::new(e),
-        })? {
-            let mut entry = entry_result.map_err(|e| object_store::Error::Generic {
-                store: "TarObjectStore",
-                source: Box::new(e),
-            })?;
+        self.read_entry_range(path, None)
+    }
 
-            let entry_path = entry.path().map_err(|e| object_store::Error::Generic {
-                store: "TarObjectStore",
-                source: Box::new(e),
-            })?;
-            let entry_path_str = entry_path.to_str().ok_or_else(|| object_store::Error::Generic {
-                store: "TarObjectStore",
-                source: "Invalid UTF-8 in tar entry path".into(),
+    /// Reads `range` of a file, or all of it, seeking straight to it with the offset recorded
+    /// in the index. A range extending past the end of the file returns the remainder.
+    fn read_entry_range(
+        &self,
+        path: &ObjectPath,
+        range: Option<Range<u64>>,
+    ) -> ObjectStoreResult<Bytes> {
+        self.ensure_indexed()?;
+        let entry = self
+            .index
+            .read()
+            .entries
+            .get(path)
+            .cloned()
+            .ok_or_else(|| object_store::Error::NotFound {
+                path: path.to_string(),
+                source: "File not found in tar archive".into(),
             })?;
-
-            if entry_path_str == path.as_ref() {
-                // Found the entry, read its contents
-                let mut buffer = Vec::new();
-                entry.read_to_end(&mut buffer).map_err(|e| {
-                    object_store::Error::Generic {
+        let range = match range {
+            Some(range) => GetRange::Bounded(range).as_range(entry.size).map_err(|e| {
+                object_store::Error::Generic {
+                    store: "TarObjectStore",
+                    source: Box::new(e),
+                }
+            })?,
+            None => 0..entry.size,
+        };
+        let start = entry.offset + range.start;
+        let end = entry.offset + range.end;
+        self.reads.fetch_add(1, Ordering::Relaxed);
+
+        match &*self.source {
+            TarSource::File(_) => {
+                let mut file = self.open()?;
+                let mut buffer = vec![0; (end - start) as usize];
+                file.seek(SeekFrom::Start(start))
+                    .and_then(|_| file.read_exact(&mut buffer))
+                    .map_err(|e| object_store::Error::Generic {
                         store: "TarObjectStore",
                         source: Box::new(e),
-                    }
-                })?;
-                return Ok(Bytes::from(buffer));
+                    })?;
+                Ok(Bytes::from(buffer))
             }
+            TarSource::Nested { bytes, .. } => Ok(bytes.slice(start as usize..end as usize)),
         }
-
-        Err(object_store::Error::NotFound {
-            path: path.to_string(),
-            source: "File not found in tar archive".into(),
-        })
     }
 
     /// Appends a new file to the tar archive.
@@ -206,16 +248,22 @@ impl TarObjectStore {
     fn append_entry(&self, path: &ObjectPath, data: Bytes) -> ObjectStoreResult<()> {
         use std::io::{Cursor, Seek};
 
-        // If the tar file exists, read all existing entries first
-        let existing_entries: Vec<(ObjectPath, Bytes)> = if self.tar_path.exists() {
-            let file = File::open(&*self.tar_path).map_err(|e| {
-                object_store::Error::Generic {
-                    store: "TarObjectStore",
-                    source: Box::new(e),
-                }
-            })?;
+        let tar_path = match &*self.source {
+            TarSource::File(tar_path) => tar_path,
+            TarSource::Nested { name, .. } => {
+                return Err(object_store::Error::NotSupported {
+                    source: format!(
+                        "Cannot write to {}: nested tar archives are read-only",
+                        name
+                    )
+                    .into(),
+                })
+            }
+        };
 
-            let mut archive = Archive::new(file);
+        // If the tar file exists, read all existing entries (and their mtimes) first
+        let existing_entries: Vec<(ObjectPath, u64, Bytes)> = if tar_path.exists() {
+            let mut archive = Archive::new(self.open()?);
             let mut entries = Vec::new();
 
             for entry_result in archive.entries().map_err(|e| object_store::Error::Generic {
@@ -236,6 +284,8 @@ impl TarObjectStore {
                     source: "Invalid UTF-8 in tar entry path".into(),
                 })?.to_string();
 
+                let mtime = entry.header().mtime().unwrap_or(0);
+
                 let mut buffer = Vec::new();
                 entry.read_to_end(&mut buffer).map_err(|e| {
                     object_store::Error::Generic {
@@ -244,7 +294,7 @@ impl TarObjectStore {
                     }
                 })?;
 
-                entries.push((ObjectPath::from(path_string), Bytes::from(buffer)));
+                entries.push((ObjectPath::from(path_string), mtime, Bytes::from(buffer)));
             }
             entries
         } else {
@@ -252,26 +302,19 @@ impl TarObjectStore {
         };
 
         // Rewrite the entire tar file with all entries plus the new one
-        let file = File::create(&*self.tar_path).map_err(|e| {
-            object_store::Error::Generic {
-                store: "TarObjectStore",
-                source: Box::new(e),
-            }
+        let file = File::create(tar_path).map_err(|e| object_store::Error::Generic {
+            store: "TarObjectStore",
+            source: Box::new(e),
         })?;
 
         let mut builder = Builder::new(file);
 
         // Write all existing entries
-        for (existing_path, existing_data) in existing_entries {
+        for (existing_path, existing_mtime, existing_data) in existing_entries {
             let mut header = Header::new_gnu();
             header.set_size(existing_data.len() as u64);
             header.set_mode(0o644);
-            header.set_mtime(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            );
+            header.set_mtime(existing_mtime);
             header.set_cksum();
 
             builder
@@ -286,12 +329,7 @@ impl TarObjectStore {
         let mut header = Header::new_gnu();
         header.set_size(data.len() as u64);
         header.set_mode(0o644);
-        header.set_mtime(
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        );
+        header.set_mtime(now_secs());
         header.set_cksum();
 
         builder
@@ -315,12 +353,35 @@ impl TarObjectStore {
     }
 }
 
+/// A source that can be read sequentially or from a given position
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
 impl Display for TarObjectStore {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "TarObjectStore({})", self.tar_path.display())
+        write!(f, "TarObjectStore({})", self.source)
+    }
+}
+
+impl Display for TarSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TarSource::File(path) => write!(f, "{}", path.display()),
+            TarSource::Nested { name, .. } => write!(f, "{}", name),
+        }
     }
 }
 
+/// Seconds since the Unix epoch, for entry mtimes. Falls back to the epoch if the clock is set
+/// before it rather than failing the write.
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
 #[async_trait]
 impl ObjectStore for TarObjectStore {
     async fn put(&self, location: &ObjectPath, payload: PutPayload) -> ObjectStoreResult<PutResult> {
@@ -356,47 +417,43 @@ impl ObjectStore for TarObjectStore {
     }
 
     async fn get(&self, location: &ObjectPath) -> ObjectStoreResult<GetResult> {
-        self.ensure_indexed()?;
+        self.get_opts(location, GetOptions::default()).await
+    }
 
-        let bytes = self.read_entry(location)?;
-        let size = bytes.len() as u64;
+    async fn get_opts(
+        &self,
+        location: &ObjectPath,
+        options: GetOptions,
+    ) -> ObjectStoreResult<GetResult> {
+        // Only range requests are supported, other options are ignored
+        let meta = self.head(location).await?;
+        let (range, bytes) = match options.range {
+            Some(range) => {
+                let range =
+                    range
+                        .as_range(meta.size)
+                        .map_err(|e| object_store::Error::Generic {
+                            store: "TarObjectStore",
+                            source: Box::new(e),
+                        })?;
+                (range.clone(), self.read_entry_range(location, Some(range))?)
+            }
+            None => (0..meta.size, self.read_entry(location)?),
+        };
 
         Ok(GetResult {
             payload: object_store::GetResultPayload::Stream(Box::pin(stream::once(async move {
                 Ok(bytes)
             }))),
-            meta: ObjectMeta {
-                location: location.clone(),
-                last_modified: chrono::Utc::now(),
-                size,
-                e_tag: None,
-                version: None,
-            },
-            range: 0..size,
+            meta,
+            range,
             attributes: Default::default(),
         })
     }
 
-    async fn get_opts(&self, location: &ObjectPath, _options: GetOptions) -> ObjectStoreResult<GetResult> {
-        // For simplicity, ignore options and use regular get
-        // A full implementation would handle range requests
-        self.get(location).await
-    }
-
     async fn get_range(&self, location: &ObjectPath, range: Range<u64>) -> ObjectStoreResult<Bytes> {
-        let bytes = self.read_entry(location)?;
-
-        let start = range.start as usize;
-        let end = range.end as usize;
-
-        if end > bytes.len() {
-            return Err(object_store::Error::Generic {
-                store: "TarObjectStore",
-                source: "Range out of bounds".into(),
-            });
-        }
-
-        Ok(bytes.slice(start..end))
+        // Same semantics as other stores: a range extending past the end returns the remainder
+        self.read_entry_range(location, Some(range))
     }
 
     async fn head(&self, location: &ObjectPath) -> ObjectStoreResult<ObjectMeta> {
@@ -462,14 +519,18 @@ impl ObjectStore for TarObjectStore {
         self.ensure_indexed()?;
 
         let index = self.index.read();
-        let prefix_str = prefix.map(|p| p.as_ref()).unwrap_or("");
+        // The prefix names a directory, so only match entries below it
+        let prefix_str = match prefix.map(|p| p.as_ref()) {
+            Some(p) if !p.is_empty() => format!("{}/", p),
+            _ => String::new(),
+        };
 
         let mut objects = Vec::new();
         let mut common_prefixes = std::collections::HashSet::new();
 
         for (path, entry) in &index.entries {
             let path_str = path.as_ref();
-            if !path_str.starts_with(prefix_str) {
+            if !path_str.starts_with(&prefix_str) {
                 continue;
             }
 
@@ -553,6 +614,41 @@ mod tests {
         assert_eq!(read_data, data);
     }
 
+    #[tokio::test]
+    async fn test_tar_store_get_range() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let store = TarObjectStore::new(temp_file.path().to_path_buf()).unwrap();
+        let path = ObjectPath::from("range.txt");
+
+        store
+            .put(&path, PutPayload::from_bytes(Bytes::from("0123456789")))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            Bytes::from("234"),
+            store.get_range(&path, 2..5).await.unwrap()
+        );
+        assert_eq!(
+            Bytes::from("789"),
+            store.get_range(&path, 7..20).await.unwrap()
+        );
+        assert!(store.get_range(&path, 20..25).await.is_err());
+
+        let result = store
+            .get_opts(
+                &path,
+                GetOptions {
+                    range: Some(GetRange::Suffix(3)),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(7..10, result.range);
+        assert_eq!(Bytes::from("789"), result.bytes().await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_tar_store_head() {
         let temp_file = NamedTempFile::new().unwrap();
@@ -625,6 +721,98 @@ mod tests {
         assert_eq!(prefix_results.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_tar_store_nested() {
+        let inner_file = NamedTempFile::new().unwrap();
+        let inner = TarObjectStore::new(inner_file.path().to_path_buf()).unwrap();
+        inner
+            .put(
+                &ObjectPath::from("dir/file.txt"),
+                PutPayload::from_bytes(Bytes::from("inner data")),
+            )
+            .await
+            .unwrap();
+
+        let outer_file = NamedTempFile::new().unwrap();
+        let outer = TarObjectStore::new(outer_file.path().to_path_buf()).unwrap();
+        outer
+            .put(
+                &ObjectPath::from("archives/inner.tar"),
+                PutPayload::from_bytes(Bytes::from(std::fs::read(inner_file.path()).unwrap())),
+            )
+            .await
+            .unwrap();
+
+        let nested = outer
+            .nested(&ObjectPath::from("archives/inner.tar"))
+            .unwrap();
+        let path = ObjectPath::from("dir/file.txt");
+        assert_eq!(
+            Bytes::from("inner data"),
+            nested.get(&path).await.unwrap().bytes().await.unwrap()
+        );
+        assert_eq!(10, nested.head(&path).await.unwrap().size);
+        assert!(nested.to_string().ends_with("/archives/inner.tar)"));
+
+        let result = nested
+            .put(&path, PutPayload::from_bytes(Bytes::from("new")))
+            .await;
+        assert!(matches!(
+            result,
+            Err(object_store::Error::NotSupported { .. })
+        ));
+
+        assert!(matches!(
+            outer.nested(&ObjectPath::from("missing.tar")),
+            Err(object_store::Error::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tar_store_reads_at_offset() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let tar_path = temp_file.path().to_path_buf();
+
+        let mut builder = Builder::new(File::create(&tar_path).unwrap());
+        for i in 0..500 {
+            let data = format!("contents of file {}", i);
+            let mut header = Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("files/{}.txt", i), data.as_bytes())
+                .unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+
+        let store = TarObjectStore::new(tar_path).unwrap();
+        let last = ObjectPath::from("files/499.txt");
+        assert_eq!(
+            Bytes::from("contents of file 499"),
+            store.get(&last).await.unwrap().bytes().await.unwrap()
+        );
+        assert_eq!(1, store.reads.load(Ordering::Relaxed));
+
+        // A range only reads its own bytes, from within the file
+        assert_eq!(
+            Bytes::from("file 499"),
+            store.get_range(&last, 12..30).await.unwrap()
+        );
+        assert_eq!(2, store.reads.load(Ordering::Relaxed));
+        assert_eq!(
+            Bytes::from("contents of file 0"),
+            store
+                .get(&ObjectPath::from("files/0.txt"))
+                .await
+                .unwrap()
+                .bytes()
+                .await
+                .unwrap()
+        );
+    }
+
     #[tokio::test]
     async fn test_tar_store_not_found() {
         let temp_file = NamedTempFile::new().unwrap();
@@ -636,4 +824,40 @@ mod tests {
         let result = store.get(&path).await;
         assert!(matches!(result, Err(object_store::Error::NotFound { .. })));
     }
+
+    #[tokio::test]
+    async fn test_tar_store_append_keeps_mtime() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let tar_path = temp_file.path().to_path_buf();
+
+        let mut builder = Builder::new(File::create(&tar_path).unwrap());
+        let mut header = Header::new_gnu();
+        header.set_size(3);
+        header.set_mode(0o644);
+        header.set_mtime(1_000_000);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "old.txt", &b"old"[..])
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let store = TarObjectStore::new(tar_path).unwrap();
+        let old = ObjectPath::from("old.txt");
+        let modified = store.head(&old).await.unwrap().last_modified;
+        assert_eq!(1_000_000, modified.timestamp());
+
+        let new = ObjectPath::from("new.txt");
+        store
+            .put(&new, PutPayload::from_bytes(Bytes::from("new")))
+            .await
+            .unwrap();
+
+        assert_eq!(modified, store.head(&old).await.unwrap().last_modified);
+        assert!(store.head(&new).await.unwrap().last_modified > modified);
+        assert_eq!(
+            Bytes::from("old"),
+            store.get(&old).await.unwrap().bytes().await.unwrap()
+        );
+    }
 }
@@ -1,14 +1,46 @@
+use crate::bundle_config::{
+    BEARER_TOKEN, HTTP_HEADER_PREFIX, MAX_CONCURRENT_REQUESTS, MAX_RETRIES, RETRY_BACKOFF_MS,
+    RETRY_MAX_BACKOFF_MS, RETRY_TIMEOUT_SECS,
+};
 use crate::io::{get_memory_store, get_null_store, TarObjectStore, EMPTY_SCHEME};
 use crate::BundlebaseError;
 use datafusion::datasource::object_store::ObjectStoreUrl;
+use object_store::limit::LimitStore;
 use object_store::path::Path;
-use object_store::{path::Path as ObjectPath, ObjectStore};
+use object_store::{
+    path::Path as ObjectPath, ClientConfigKey, ClientOptions, HeaderMap, ObjectStore, RetryConfig,
+};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
-pub(super) fn compute_store_url(url: &Url) -> ObjectStoreUrl {
-    ObjectStoreUrl::parse(format!("{}://{}", url.scheme(), url.authority())).unwrap()
+pub(super) fn compute_store_url(url: &Url) -> Result<ObjectStoreUrl, BundlebaseError> {
+    // Files inside a tar archive are served by that archive's TarObjectStore, not the local
+    // filesystem, so they need a store URL distinct from the generic file:// one
+    if url.scheme() == "file" {
+        // Keyed on the archive the path resolves to, the innermost one when nested, so
+        // directories named `*.tar` stay on the filesystem store
+        let archive = url
+            .to_file_path()
+            .ok()
+            .and_then(|p| p.to_str().map(String::from))
+            .and_then(|path| open_archive_path(&path).ok().flatten());
+        if let Some((_, archive, _)) = archive {
+            let mut hasher = Sha256::new();
+            hasher.update(&archive);
+            let hash = hex::encode(hasher.finalize());
+            return Ok(ObjectStoreUrl::parse(format!("tar://{}", &hash[..16]))?);
+        }
+    }
+    Ok(ObjectStoreUrl::parse(format!(
+        "{}://{}",
+        url.scheme(),
+        url.authority()
+    ))?)
 }
 
 /// Parse a URL and return an ObjectStore and Path
@@ -20,14 +52,15 @@ pub(super) fn parse_url(
     url: &Url,
     config: &HashMap<String, String>,
 ) -> Result<(Arc<dyn ObjectStore>, Path), BundlebaseError> {
-    // Check for .tar file extension first (before other file:// handling)
+    // Check for .tar archives in the path first (before other file:// handling)
     if url.scheme() == "file" {
-        if let Ok(path) = url.to_file_path() {
-            if path.extension().and_then(|s| s.to_str()) == Some("tar") {
-                let store = TarObjectStore::new(path).map_err(|e| {
-                    format!("Failed to create TarObjectStore: {}", e)
-                })?;
-                return Ok((Arc::new(store), ObjectPath::from("/")));
+        if let Some(path) = url
+            .to_file_path()
+            .ok()
+            .and_then(|p| p.to_str().map(String::from))
+        {
+            if let Some((store, _, inner_path)) = open_archive_path(&path)? {
+                return Ok((Arc::new(store), ObjectPath::from(inner_path)));
             }
         }
     }
@@ -44,8 +77,17 @@ pub(super) fn parse_url(
             return Err("Memory URL must be memory:///<path>".into());
         }
         Ok((get_memory_store(), url.path().into()))
-    } else if !config.is_empty() {
-        // Use config to build ObjectStore
+    } else if !config.is_empty() || matches!(url.scheme(), "http" | "https") {
+        // Requests are made to the path alone, so a query string such as a presigned URL's
+        // signature would be dropped. Set credentials with bearer_token or header.* instead
+        if matches!(url.scheme(), "http" | "https") && url.query().is_some() {
+            return Err(format!(
+                "URLs with a query string are not supported: {}. Use the bearer_token or header.<name> settings to authenticate instead",
+                url
+            )
+            .into());
+        }
+        // Use config to build ObjectStore. Web servers always go through it so plain http is allowed
         let store = build_object_store(url, config)?;
         let path = Path::from(url.path());
         Ok((Arc::new(store), path))
@@ -56,31 +98,133 @@ pub(super) fn parse_url(
     }
 }
 
+/// Opens the `.tar` archive(s) a local path goes through, returning the innermost archive's
+/// store and the path within it, or `None` if the path does not go through an archive.
+///
+/// A segment ending in `.tar` is only treated as an archive if it is one: a directory named
+/// `backup.tar` on the filesystem is walked into like any other, and inside an archive only an
+/// existing entry is opened as a nested archive. So `/data/a.tar/inner/real.tar/x` reads `x`
+/// from `real.tar` whether `a.tar` is a directory holding it or an archive containing it.
+///
+/// An archive that does not exist yet (e.g. a bundle about to be created) can only be told
+/// apart from a directory when it is the last `.tar` segment; otherwise the path is ambiguous
+/// and an error is returned.
+///
+/// Returns the store, the path of the archive it reads (ending at the innermost nested
+/// archive), and the path inside that archive.
+fn open_archive_path(
+    path: &str,
+) -> Result<Option<(TarObjectStore, String, String)>, BundlebaseError> {
+    let segments: Vec<&str> = path.split('/').collect();
+    let candidates: Vec<usize> = segments
+        .iter()
+        .enumerate()
+        .filter(|(_, segment)| segment.ends_with(".tar"))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Find the archive on the filesystem
+    let mut outer = None;
+    for (n, &i) in candidates.iter().enumerate() {
+        let candidate = PathBuf::from(segments[..=i].join("/"));
+        if candidate.is_dir() {
+            continue;
+        }
+        if !candidate.exists() && n + 1 < candidates.len() {
+            return Err(format!(
+                "Ambiguous tar path '{}': '{}' does not exist, so it is unclear which .tar segment is the archive",
+                path,
+                candidate.display()
+            )
+            .into());
+        }
+        outer = Some(i);
+        break;
+    }
+    let Some(outer) = outer else {
+        return Ok(None);
+    };
+
+    let archive = PathBuf::from(segments[..=outer].join("/"));
+    let exists = archive.is_file();
+    let mut store = TarObjectStore::new(archive)
+        .map_err(|e| format!("Failed to create TarObjectStore: {}", e))?;
+    let mut start = outer + 1;
+    if exists {
+        for &i in candidates.iter().filter(|&&i| i > outer) {
+            let nested = ObjectPath::from(segments[start..=i].join("/"));
+            if store.has_entry(&nested)? {
+                store = store.nested(&nested).map_err(|e| {
+                    format!("Failed to open nested tar archive '{}': {}", nested, e)
+                })?;
+                start = i + 1;
+            }
+        }
+    }
+    Ok(Some((
+        store,
+        segments[..start].join("/"),
+        segments[start..].join("/"),
+    )))
+}
+
 /// Build an ObjectStore with configuration
 ///
 /// Starts with Builder::from_env() to pick up environment variables,
 /// then applies config values on top (config overrides env vars).
+/// The retry settings are applied to cloud stores, and `max_concurrent_requests`
+/// wraps any store in a [`LimitStore`].
 fn build_object_store(
     url: &Url,
     config: &HashMap<String, String>,
+) -> Result<Box<dyn ObjectStore>, BundlebaseError> {
+    let mut config = config.clone();
+    let retry = retry_config(&mut config)?;
+    let max_requests = take_config::<usize>(&mut config, MAX_CONCURRENT_REQUESTS)?;
+    if max_requests == Some(0) {
+        return Err(format!(
+            "Invalid {} '0': must be a positive integer",
+            MAX_CONCURRENT_REQUESTS
+        )
+        .into());
+    }
+
+    let store = build_provider_store(url, &config, retry)?;
+    Ok(match max_requests {
+        Some(max_requests) => Box::new(LimitStore::new(store, max_requests)),
+        None => store,
+    })
+}
+
+fn build_provider_store(
+    url: &Url,
+    config: &HashMap<String, String>,
+    retry: RetryConfig,
 ) -> Result<Box<dyn ObjectStore>, BundlebaseError> {
     use object_store::aws::AmazonS3Builder;
     use object_store::azure::MicrosoftAzureBuilder;
     use object_store::gcp::GoogleCloudStorageBuilder;
+    use object_store::http::HttpBuilder;
+    use url::Position;
 
     match url.scheme() {
         "s3" => {
-            let mut builder = AmazonS3Builder::from_env().with_url(url.as_str());
+            let mut builder = AmazonS3Builder::from_env()
+                .with_url(url.as_str())
+                .with_retry(retry);
 
             // Apply config values
             for (key, value) in config {
-                builder = builder.with_config(key.parse()?, value);
+                let (key, value) = s3_config(key, value)?;
+                builder = builder.with_config(key, value);
             }
 
             Ok(Box::new(builder.build()?))
         }
         "gs" => {
-            let mut builder = GoogleCloudStorageBuilder::from_env().with_url(url.as_str());
+            let mut builder = GoogleCloudStorageBuilder::from_env()
+                .with_url(url.as_str())
+                .with_retry(retry);
 
             // Apply config values
             for (key, value) in config {
@@ -90,7 +234,9 @@ fn build_object_store(
             Ok(Box::new(builder.build()?))
         }
         "azure" | "az" => {
-            let mut builder = MicrosoftAzureBuilder::from_env().with_url(url.as_str());
+            let mut builder = MicrosoftAzureBuilder::from_env()
+                .with_url(url.as_str())
+                .with_retry(retry);
 
             // Apply config values
             for (key, value) in config {
@@ -99,6 +245,29 @@ fn build_object_store(
 
             Ok(Box::new(builder.build()?))
         }
+        "http" | "https" => {
+            let mut options = ClientOptions::new().with_allow_http(url.scheme() == "http");
+            let mut headers = HashMap::new();
+            for (key, value) in config {
+                if key == BEARER_TOKEN {
+                    headers.insert("authorization".to_string(), format!("Bearer {}", value));
+                } else if let Some(name) = key.strip_prefix(HTTP_HEADER_PREFIX) {
+                    headers.insert(name.to_string(), value.clone());
+                } else if let Ok(key) = key.parse::<ClientConfigKey>() {
+                    options = options.with_config(key, value);
+                }
+                // Other keys are for other providers, e.g. a default region for S3
+            }
+            let headers = HeaderMap::try_from(&headers)
+                .map_err(|e| format!("Invalid HTTP header for {}: {}", url, e))?;
+
+            // The store serves the whole server; paths are relative to its root
+            let builder = HttpBuilder::new()
+                .with_url(&url[..Position::BeforePath])
+                .with_retry(retry)
+                .with_client_options(options.with_default_headers(headers));
+            Ok(Box::new(builder.build()?))
+        }
         scheme => {
             // For unknown schemes, fall back to object_store::parse_url
             let (store, _) = object_store::parse_url(url)
@@ -108,6 +277,74 @@ fn build_object_store(
     }
 }
 
+/// Removes the retry settings from `config` and returns the retry policy they describe.
+/// Settings that aren't given keep object_store's defaults.
+fn retry_config(config: &mut HashMap<String, String>) -> Result<RetryConfig, BundlebaseError> {
+    let mut retry = RetryConfig::default();
+    if let Some(max_retries) = take_config(config, MAX_RETRIES)? {
+        retry.max_retries = max_retries;
+    }
+    if let Some(secs) = take_config(config, RETRY_TIMEOUT_SECS)? {
+        retry.retry_timeout = Duration::from_secs(secs);
+    }
+    if let Some(ms) = take_config(config, RETRY_BACKOFF_MS)? {
+        retry.backoff.init_backoff = Duration::from_millis(ms);
+    }
+    if let Some(ms) = take_config(config, RETRY_MAX_BACKOFF_MS)? {
+        retry.backoff.max_backoff = Duration::from_millis(ms);
+    }
+    Ok(retry)
+}
+
+/// Removes `key` from `config` and parses its value, if set
+fn take_config<T: FromStr>(
+    config: &mut HashMap<String, String>,
+    key: &str,
+) -> Result<Option<T>, BundlebaseError> {
+    config
+        .remove(key)
+        .map(|value| {
+            value.trim().parse::<T>().map_err(|_| {
+                format!(
+                    "Invalid {} '{}': must be a non-negative integer",
+                    key, value
+                )
+                .into()
+            })
+        })
+        .transpose()
+}
+
+/// Maps bundlebase-specific S3 config options onto object_store config keys.
+///
+/// * `anonymous=true` skips request signing, allowing access to public buckets without credentials
+/// * `request_payer=requester` enables requester-pays buckets
+fn s3_config(
+    key: &str,
+    value: &str,
+) -> Result<(object_store::aws::AmazonS3ConfigKey, String), BundlebaseError> {
+    use object_store::aws::AmazonS3ConfigKey;
+
+    match key {
+        "anonymous" => Ok((AmazonS3ConfigKey::SkipSignature, value.to_string())),
+        "request_payer" => {
+            let enabled = match value.to_lowercase().as_str() {
+                "requester" | "true" => "true",
+                "" | "bucket_owner" | "false" => "false",
+                _ => {
+                    return Err(format!(
+                        "Invalid request_payer '{}'. Expected 'requester' or 'bucket_owner'",
+                        value
+                    )
+                    .into())
+                }
+            };
+            Ok((AmazonS3ConfigKey::RequestPayer, enabled.to_string()))
+        }
+        _ => Ok((key.parse()?, value.to_string())),
+    }
+}
+
 /// Like Url::join but allows an input with multiple sub-paths. The appended path is always treated as a relative path.
 pub(super) fn join_url(base: &Url, append: &str) -> Result<Url, BundlebaseError> {
     let base = if !base.path().ends_with('/') {
@@ -151,16 +388,414 @@ pub(super) fn join_path(base: &Path, append: &str) -> Result<Path, BundlebaseErr
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bundle::BundleFacade;
+    use crate::io::ObjectStoreFile;
+    use crate::BundleConfig;
     use rstest::rstest;
 
+    #[test]
+    fn s3_anonymous_without_credentials() {
+        let url = Url::parse("s3://public-bucket/data/file.parquet").unwrap();
+        let config = HashMap::from([
+            ("anonymous".to_string(), "true".to_string()),
+            ("region".to_string(), "us-east-1".to_string()),
+        ]);
+        let (_, path) = parse_url(&url, &config).unwrap();
+        assert_eq!("data/file.parquet", path.as_ref());
+    }
+
+    #[rstest]
+    #[case("anonymous", "true", "aws_skip_signature", "true")]
+    #[case("request_payer", "requester", "aws_request_payer", "true")]
+    #[case("request_payer", "bucket_owner", "aws_request_payer", "false")]
+    #[case("region", "us-west-2", "aws_region", "us-west-2")]
+    fn test_s3_config(
+        #[case] key: &str,
+        #[case] value: &str,
+        #[case] expected_key: &str,
+        #[case] expected_value: &str,
+    ) {
+        let (key, value) = s3_config(key, value).unwrap();
+        assert_eq!(expected_key, key.as_ref());
+        assert_eq!(expected_value, value);
+    }
+
+    #[test]
+    fn test_s3_config_invalid_request_payer() {
+        assert_eq!(
+            "Invalid request_payer 'someone'. Expected 'requester' or 'bucket_owner'",
+            s3_config("request_payer", "someone")
+                .err()
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    /// Starts an HTTP server that answers every request with a 503, returning its address and
+    /// a count of the requests it has received
+    fn unavailable_server() -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::io::{Read, Write};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            }
+        });
+        (address, requests)
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(2)]
+    #[tokio::test]
+    async fn test_s3_retries(#[case] max_retries: usize) {
+        let (endpoint, requests) = unavailable_server();
+        let url = Url::parse("s3://bucket/data/file.csv").unwrap();
+        let config = HashMap::from([
+            ("endpoint".to_string(), endpoint),
+            ("allow_http".to_string(), "true".to_string()),
+            ("anonymous".to_string(), "true".to_string()),
+            ("region".to_string(), "us-east-1".to_string()),
+            (MAX_RETRIES.to_string(), max_retries.to_string()),
+            (RETRY_BACKOFF_MS.to_string(), "1".to_string()),
+            (RETRY_MAX_BACKOFF_MS.to_string(), "5".to_string()),
+            (MAX_CONCURRENT_REQUESTS.to_string(), "4".to_string()),
+        ]);
+        let (store, path) = parse_url(&url, &config).unwrap();
+
+        assert!(store.get(&path).await.is_err());
+        assert_eq!(
+            max_retries + 1,
+            requests.load(std::sync::atomic::Ordering::SeqCst)
+        );
+    }
+
+    #[test]
+    fn test_max_concurrent_requests() {
+        let url = Url::parse("s3://bucket/data/file.csv").unwrap();
+        let mut config = HashMap::from([
+            ("region".to_string(), "us-east-1".to_string()),
+            (MAX_CONCURRENT_REQUESTS.to_string(), "8".to_string()),
+        ]);
+        let (store, _) = parse_url(&url, &config).unwrap();
+        assert!(store.to_string().starts_with("LimitStore(8, "));
+
+        config.insert(MAX_CONCURRENT_REQUESTS.to_string(), "0".to_string());
+        assert_eq!(
+            "Invalid max_concurrent_requests '0': must be a positive integer",
+            parse_url(&url, &config).err().unwrap().to_string()
+        );
+    }
+
+    /// Starts an HTTP server serving `body` as `/data/file.csv` to requests carrying a
+    /// `Bearer secret` token, answering HEAD and ranged GETs. Returns its address and the
+    /// `<method> <path> <range>` of each request it received
+    fn file_server(body: &'static str) -> (String, Arc<parking_lot::Mutex<Vec<String>>>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(parking_lot::Mutex::new(vec![]));
+        let log = requests.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(": ") {
+                        Some((name, value)) => {
+                            headers.insert(name.to_lowercase(), value.to_string());
+                        }
+                        None => break,
+                    }
+                }
+
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+                let range = headers.get("range").cloned().unwrap_or_default();
+                log.lock()
+                    .push(format!("{} {} {}", method, path, range).trim().to_string());
+
+                let (status, content, extra) =
+                    if headers.get("authorization").map(String::as_str) != Some("Bearer secret") {
+                        ("401 Unauthorized", "", String::new())
+                    } else if path != "/data/file.csv" {
+                        ("404 Not Found", "", String::new())
+                    } else if let Some(range) = range.strip_prefix("bytes=") {
+                        let (start, end) = range.split_once('-').unwrap();
+                        let start: usize = start.parse().unwrap();
+                        let end = end.parse::<usize>().unwrap().min(body.len() - 1);
+                        (
+                            "206 Partial Content",
+                            &body[start..=end],
+                            format!("Content-Range: bytes {}-{}/{}\r\n", start, end, body.len()),
+                        )
+                    } else {
+                        ("200 OK", body, String::new())
+                    };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nETag: \"v1\"\r\nLast-Modified: Tue, 01 Oct 2024 00:00:00 GMT\r\n{}Connection: close\r\n\r\n",
+                    status,
+                    content.len(),
+                    extra
+                );
+                let _ = stream.write_all(response.as_bytes());
+                if method != "HEAD" {
+                    let _ = stream.write_all(content.as_bytes());
+                }
+            }
+        });
+        (address, requests)
+    }
+
+    #[tokio::test]
+    async fn test_http_store() {
+        let (address, requests) = file_server("a,b\n1,2\n3,4\n");
+        let url = Url::parse(&format!("{}/data/file.csv", address)).unwrap();
+        let config = HashMap::from([(BEARER_TOKEN.to_string(), "secret".to_string())]);
+        let (store, path) = parse_url(&url, &config).unwrap();
+        assert_eq!("data/file.csv", path.as_ref());
+
+        let meta = store.head(&path).await.unwrap();
+        assert_eq!(12, meta.size);
+        assert_eq!(Some("\"v1\"".to_string()), meta.e_tag);
+        assert_eq!(
+            bytes::Bytes::from("1,2"),
+            store.get_range(&path, 4..7).await.unwrap()
+        );
+        assert_eq!(
+            vec!["HEAD /data/file.csv", "GET /data/file.csv bytes=4-6"],
+            *requests.lock()
+        );
+
+        // Headers can be set directly too, and a request without the token is refused
+        let config = HashMap::from([(
+            "header.Authorization".to_string(),
+            "Bearer wrong".to_string(),
+        )]);
+        let (store, path) = parse_url(&url, &config).unwrap();
+        assert!(store.head(&path).await.is_err());
+
+        // The query string wouldn't be sent, so it is refused rather than dropped
+        let url = Url::parse(&format!("{}/data/file.csv?signature=abc", address)).unwrap();
+        let err = parse_url(&url, &HashMap::new()).err().unwrap().to_string();
+        assert!(
+            err.starts_with("URLs with a query string are not supported"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_file() {
+        let (address, _) = file_server("a,b\n1,2\n3,4\n");
+        let mut config = BundleConfig::new();
+        config.set(BEARER_TOKEN, "secret", Some(&format!("{}/", address)));
+        let config = Arc::new(config);
+
+        let url = Url::parse(&format!("{}/data/file.csv", address)).unwrap();
+        let file = ObjectStoreFile::from_url(&url, config.clone()).unwrap();
+        assert!(file.exists().await.unwrap());
+        assert_eq!("\"v1\"", file.version().await.unwrap());
+        assert_eq!("a,b\n1,2\n3,4\n", file.read_str().await.unwrap().unwrap());
+
+        let missing = Url::parse(&format!("{}/data/missing.csv", address)).unwrap();
+        let missing = ObjectStoreFile::from_url(&missing, config.clone()).unwrap();
+        assert!(!missing.exists().await.unwrap());
+
+        let mut builder = crate::BundleBuilder::create(
+            "memory:///http_attach_test",
+            Some(config.as_ref().clone()),
+        )
+        .await
+        .unwrap();
+        builder.attach(url.as_str()).await.unwrap();
+        assert_eq!(2, builder.bundle.num_rows().await.unwrap());
+    }
+
+    #[test]
+    fn test_retry_config_invalid() {
+        let url = Url::parse("s3://bucket/data/file.csv").unwrap();
+        let config = HashMap::from([(MAX_RETRIES.to_string(), "lots".to_string())]);
+        assert_eq!(
+            "Invalid max_retries 'lots': must be a non-negative integer",
+            parse_url(&url, &config).err().unwrap().to_string()
+        );
+    }
+
     #[rstest]
     #[case("s3://bucket/path/to/dir", "s3://bucket/")]
     #[case("s3://bucket/path/to/dir", "s3://bucket/")]
     #[case("memory:///path/to/dir", "memory:///")]
     #[case("memory:///path/to/dir", "memory:///")]
+    #[case("file:///path/to/file.csv", "file:///")]
+    #[case("file:///path/bundle.tar/blocks/a.csv", "tar://0d1b54cc37a159f5/")]
     fn test_compute_store_url(#[case] url: &str, #[case] expected: &str) {
         let url = Url::parse(url).unwrap();
-        assert_eq!(expected, compute_store_url(&url).as_str());
+        assert_eq!(expected, compute_store_url(&url).unwrap().as_str());
+    }
+
+    #[test]
+    fn test_compute_store_url_resolves_archive() {
+        let inner = tar_bytes(&[("file.csv", b"a\n")]);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a.tar")).unwrap();
+        std::fs::write(dir.path().join("a.tar/plain.csv"), "plain").unwrap();
+        std::fs::write(
+            dir.path().join("a.tar/real.tar"),
+            tar_bytes(&[("inner.tar", &inner), ("dir.tar/file.csv", b"b\n")]),
+        )
+        .unwrap();
+        let store_url = |path: &str| {
+            compute_store_url(&Url::from_file_path(dir.path().join(path)).unwrap())
+                .unwrap()
+                .to_string()
+        };
+
+        // A directory named like an archive is on the filesystem
+        assert_eq!("file:///", store_url("a.tar/plain.csv"));
+
+        // A nested archive gets its own store, a directory inside an archive doesn't
+        let real = store_url("a.tar/real.tar/x.csv");
+        assert!(real.starts_with("tar://"), "{}", real);
+        assert_eq!(real, store_url("a.tar/real.tar/dir.tar/file.csv"));
+        let nested = store_url("a.tar/real.tar/inner.tar/file.csv");
+        assert!(nested.starts_with("tar://"), "{}", nested);
+        assert_ne!(real, nested);
+    }
+
+    fn tar_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    async fn read(url: &Url) -> Result<String, BundlebaseError> {
+        let (store, path) = parse_url(url, &HashMap::new())?;
+        let bytes = store.get(&path).await?.bytes().await?;
+        Ok(String::from_utf8(bytes.to_vec())?)
+    }
+
+    #[tokio::test]
+    async fn test_parse_url_nested_tar() {
+        let inner = tar_bytes(&[("data/file.csv", b"a,b\n1,2\n")]);
+        let outer = tar_bytes(&[("readme.txt", b"outer"), ("nested/inner.tar", &inner)]);
+        let dir = tempfile::tempdir().unwrap();
+        let outer_path = dir.path().join("outer.tar");
+        std::fs::write(&outer_path, outer).unwrap();
+
+        let url = Url::from_file_path(outer_path.join("nested/inner.tar/data/file.csv")).unwrap();
+        let (store, path) = parse_url(&url, &HashMap::new()).unwrap();
+        assert_eq!("data/file.csv", path.as_ref());
+        assert_eq!("a,b\n1,2\n", read(&url).await.unwrap());
+
+        let err = store
+            .put(&path, object_store::PutPayload::from_static(b"new"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("read-only"), "{}", err);
+
+        // Not an entry of the outer archive, so it is looked up as a plain path inside it
+        let missing = Url::from_file_path(outer_path.join("nested/missing.tar/file.csv")).unwrap();
+        let (_, path) = parse_url(&missing, &HashMap::new()).unwrap();
+        assert_eq!("nested/missing.tar/file.csv", path.as_ref());
+        assert!(read(&missing).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_url_tar_named_directory() {
+        // a.tar is a directory holding the real archive
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a.tar/inner")).unwrap();
+        std::fs::write(
+            dir.path().join("a.tar/inner/real.tar"),
+            tar_bytes(&[("x", b"real")]),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("a.tar/plain.csv"), "plain").unwrap();
+
+        let url = Url::from_file_path(dir.path().join("a.tar/inner/real.tar/x")).unwrap();
+        let (_, path) = parse_url(&url, &HashMap::new()).unwrap();
+        assert_eq!("x", path.as_ref());
+        assert_eq!("real", read(&url).await.unwrap());
+
+        // No archive at all along the path
+        let url = Url::from_file_path(dir.path().join("a.tar/plain.csv")).unwrap();
+        assert_eq!("plain", read(&url).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_parse_url_tar_named_directory_in_archive() {
+        // inner/real.tar is a directory inside a.tar, not a nested archive
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.tar"),
+            tar_bytes(&[("inner/real.tar/x", b"in a.tar")]),
+        )
+        .unwrap();
+
+        let url = Url::from_file_path(dir.path().join("a.tar/inner/real.tar/x")).unwrap();
+        let (_, path) = parse_url(&url, &HashMap::new()).unwrap();
+        assert_eq!("inner/real.tar/x", path.as_ref());
+        assert_eq!("in a.tar", read(&url).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_parse_url_tar_suffix_in_name() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.tar"),
+            tar_bytes(&[("b.tar.backup/file", b"backup")]),
+        )
+        .unwrap();
+
+        let url = Url::from_file_path(dir.path().join("a.tar/b.tar.backup/file")).unwrap();
+        let (_, path) = parse_url(&url, &HashMap::new()).unwrap();
+        assert_eq!("b.tar.backup/file", path.as_ref());
+        assert_eq!("backup", read(&url).await.unwrap());
+    }
+
+    #[test]
+    fn test_parse_url_new_tar() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = Url::from_file_path(dir.path().join("new.tar/blocks/a.csv")).unwrap();
+        let (_, path) = parse_url(&url, &HashMap::new()).unwrap();
+        assert_eq!("blocks/a.csv", path.as_ref());
+    }
+
+    #[test]
+    fn test_parse_url_ambiguous_tar() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = Url::from_file_path(dir.path().join("a.tar/inner/real.tar/x")).unwrap();
+        let err = parse_url(&url, &HashMap::new()).err().unwrap();
+        assert!(err.to_string().contains("Ambiguous tar path"), "{}", err);
     }
 
     #[rstest]
@@ -14,11 +14,12 @@ pub mod test_utils;
 mod versioning;
 
 pub use crate::bundle::{
-    AnyOperation, Bundle, BundleBuilder, BundleChange, BundleCommit, BundleFacade, BundleStatus,
-    JoinTypeOption, Operation,
+    AggFunc, AnyOperation, AttachSplit, Bundle, BundleBuilder, BundleChange, BundleCommit,
+    BundleDiff, BundleFacade, BundleStatus, JoinTypeOption, MaskStrategy, Operation,
+    PivotAggregate, RefMapping, SortDirection,
 };
 pub use crate::bundle_config::BundleConfig;
-pub use crate::data::DataGenerator;
+pub use crate::data::{ColumnStats, DataGenerator};
 pub use crate::progress::{get_tracker, set_tracker, with_tracker, ProgressId, ProgressTracker};
 pub use functions::{FunctionImpl, FunctionSignature};
 use std::error::Error;
@@ -12,8 +12,11 @@
 use opentelemetry::{
     global::BoxedSpan,
     metrics::{Counter, Histogram, Meter, ObservableGauge},
-    trace::{Span as OtelSpanTrait, Status, Tracer},
+    propagation::TextMapPropagator,
+    trace::{Span as OtelSpanTrait, Status, TraceContextExt, Tracer},
 };
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use std::collections::HashMap;
 
 
 use lazy_static::lazy_static;
@@ -21,6 +24,10 @@ use lazy_static::lazy_static;
 // Re-export KeyValue for use by callers
 pub use opentelemetry::KeyValue;
 
+// Re-export trace context types so callers can propagate parents across async boundaries
+pub use opentelemetry::trace::FutureExt as TraceFutureExt;
+pub use opentelemetry::Context as TraceContext;
+
 mod logging;
 pub use logging::{init_logging_metrics, init_logging_metrics_with_interval, log_current_metrics};
 
@@ -29,6 +36,12 @@ pub mod progress;
 
 pub use progress::{CompositeTracker, SpanProgressTracker};
 
+// In-process timing summaries (e.g. the CLI's --profile flag)
+mod profile;
+pub use profile::{
+    disable_profiling, enable_profiling, is_profiling_enabled, profile_summary, CategoryProfile,
+};
+
 /// Generic outcome for all operations
 #[derive(Debug, Clone, Copy)]
 pub enum OperationOutcome {
@@ -118,10 +131,20 @@ pub struct Span {
 impl Span {
     /// Start a new span
     pub fn start(category: OperationCategory, operation: impl Into<String>) -> Self {
+        Self::start_with_parent(category, operation, &TraceContext::current())
+    }
+
+    /// Start a new span as a child of the span in the given context
+    pub fn start_with_parent(
+        category: OperationCategory,
+        operation: impl Into<String>,
+        parent: &TraceContext,
+    ) -> Self {
         let operation = operation.into();
 
         {
-            let mut span = TRACER.start(format!("{}.{}", category.as_str(), operation));
+            let mut span =
+                TRACER.start_with_context(format!("{}.{}", category.as_str(), operation), parent);
             span.set_attribute(KeyValue::new("category", category.as_str()));
             span.set_attribute(KeyValue::new("operation", operation.clone()));
 
@@ -158,6 +181,16 @@ impl Span {
             .set_attribute(KeyValue::new("outcome", outcome.as_str()));
     }
 
+    /// The trace id of this span, as a hex string
+    pub fn trace_id(&self) -> String {
+        self.inner.span_context().trace_id().to_string()
+    }
+
+    /// A context with this span as the parent, for use with `TraceFutureExt::with_context`
+    pub fn context(&self) -> TraceContext {
+        TraceContext::current().with_remote_span_context(self.inner.span_context().clone())
+    }
+
     /// Record an error in the span
     pub fn record_error(&mut self, error: &str) {
         self.inner.set_status(Status::error(error.to_string()));
@@ -180,6 +213,18 @@ pub fn start_span(category: OperationCategory, operation: impl Into<String>) ->
     Span::start(category, operation)
 }
 
+/// Extract a W3C trace context (`traceparent`/`tracestate`) from request headers.
+///
+/// Header names are matched case-insensitively. Returns an empty context if no valid
+/// `traceparent` is present, so spans started with it become new root spans.
+pub fn extract_trace_context(headers: &HashMap<String, String>) -> TraceContext {
+    let headers: HashMap<String, String> = headers
+        .iter()
+        .map(|(k, v)| (k.to_lowercase(), v.clone()))
+        .collect();
+    TraceContextPropagator::new().extract(&headers)
+}
+
 lazy_static! {
     /// Global tracer for distributed tracing
     static ref TRACER: opentelemetry::global::BoxedTracer = {
@@ -259,6 +304,7 @@ pub fn record_duration(
     ];
     attrs.extend_from_slice(labels);
     OPERATION_DURATION.record(duration_ms, &attrs);
+    profile::record(category, duration_ms);
 }
 
 /// Records bytes processed (read/written)
@@ -315,6 +361,11 @@ impl OperationTimer {
         self
     }
 
+    /// Time since the timer was started
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.start.elapsed()
+    }
+
     /// Finish timing and record the outcome
     pub fn finish(self, outcome: OperationOutcome) {
         let duration_ms = self.start.elapsed().as_secs_f64() * 1000.0;
@@ -371,6 +422,28 @@ mod tests {
         record_cache_operation("test_cache", true);
     }
 
+    #[test]
+    fn test_span_with_extracted_parent() {
+        let headers = HashMap::from([(
+            "Traceparent".to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+        )]);
+        let parent = extract_trace_context(&headers);
+
+        let span = Span::start_with_parent(OperationCategory::Select, "test_op", &parent);
+        assert_eq!("4bf92f3577b34da6a3ce929d0e0e4736", span.trace_id());
+
+        // Children started from the span's context stay in the same trace
+        let child = Span::start_with_parent(OperationCategory::Select, "child_op", &span.context());
+        assert_eq!("4bf92f3577b34da6a3ce929d0e0e4736", child.trace_id());
+    }
+
+    #[test]
+    fn test_extract_trace_context_missing() {
+        let parent = extract_trace_context(&HashMap::new());
+        assert!(!parent.span().span_context().is_valid());
+    }
+
     #[test]
     fn test_timer() {
         let timer = OperationTimer::start(OperationCategory::Select, "test_op")
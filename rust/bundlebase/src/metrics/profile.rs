@@ -0,0 +1,90 @@
+/// In-process collection of operation timings
+///
+/// When enabled, every duration passed to `record_duration` (including those from
+/// `OperationTimer`) is also accumulated per category in memory. This allows a summary
+/// of where time went to be printed without an OpenTelemetry backend.
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+use super::OperationCategory;
+
+/// Accumulated timings for one operation category
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryProfile {
+    pub category: &'static str,
+    pub count: u64,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+lazy_static! {
+    static ref PROFILE: Mutex<Option<HashMap<&'static str, CategoryProfile>>> = Mutex::new(None);
+}
+
+/// Start collecting operation timings, discarding any previously collected
+pub fn enable_profiling() {
+    *PROFILE.lock() = Some(HashMap::new());
+}
+
+/// Stop collecting operation timings
+pub fn disable_profiling() {
+    *PROFILE.lock() = None;
+}
+
+pub fn is_profiling_enabled() -> bool {
+    PROFILE.lock().is_some()
+}
+
+/// Adds a duration to the profile, if profiling is enabled
+pub(super) fn record(category: OperationCategory, duration_ms: f64) {
+    if let Some(profile) = PROFILE.lock().as_mut() {
+        let entry = profile
+            .entry(category.as_str())
+            .or_insert_with(|| CategoryProfile {
+                category: category.as_str(),
+                count: 0,
+                total_ms: 0.0,
+                max_ms: 0.0,
+            });
+        entry.count += 1;
+        entry.total_ms += duration_ms;
+        entry.max_ms = entry.max_ms.max(duration_ms);
+    }
+}
+
+/// Returns the timings collected so far, slowest category first
+pub fn profile_summary() -> Vec<CategoryProfile> {
+    let mut summary: Vec<CategoryProfile> = PROFILE
+        .lock()
+        .as_ref()
+        .map(|profile| profile.values().cloned().collect())
+        .unwrap_or_default();
+    summary.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        enable_profiling();
+        record(OperationCategory::Attach, 5.0);
+        record(OperationCategory::Attach, 15.0);
+        record(OperationCategory::Index, 30.0);
+
+        // Other tests may record concurrently, so only lower bounds are checked
+        let summary = profile_summary();
+        let index = summary.iter().find(|p| p.category == "index").unwrap();
+        assert!(index.count >= 1);
+        let attach = summary.iter().find(|p| p.category == "attach").unwrap();
+        assert!(attach.count >= 2);
+        assert!(attach.total_ms >= 20.0);
+        assert!(attach.max_ms >= 15.0);
+        assert!(summary
+            .windows(2)
+            .all(|pair| pair[0].total_ms >= pair[1].total_ms));
+    }
+}
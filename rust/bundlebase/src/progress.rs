@@ -69,7 +69,7 @@ mod logging;
 mod registry;
 
 #[cfg(test)]
-mod mock;
+pub(crate) mod mock;
 
 pub use logging::LoggingTracker;
 pub use registry::{get_tracker, set_tracker, with_tracker};
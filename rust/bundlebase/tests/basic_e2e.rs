@@ -1,12 +1,22 @@
+use arrow::array::{Float64Array, Int64Array, RecordBatch, StringArray};
 use arrow_schema::{DataType, Field, Schema, SchemaRef};
 use bundlebase;
-use bundlebase::bundle::{BundleFacade, INIT_FILENAME, META_DIR};
+use bundlebase::bundle::{
+    BundleFacade, ValidationWarning, ADDED_FILENAME, CHANGES_FILENAME, FORMAT_VERSION,
+    INIT_FILENAME, META_DIR,
+};
+use bundlebase::bundle_config::{ATTACH_SPLIT_MB, AUTHOR, FILE_FORMATS, PARQUET_ROW_GROUP_SIZE};
 use bundlebase::io::ObjectStoreFile;
 use bundlebase::test_utils::{random_memory_dir, random_memory_url, test_datafile};
-use bundlebase::BundleConfig;
 use bundlebase::FunctionSignature;
-use bundlebase::{op_field, AnyOperation};
+use bundlebase::{op_field, AnyOperation, Operation};
 use bundlebase::{test_utils, Bundle, BundlebaseError};
+use bundlebase::{AttachSplit, BundleConfig};
+use datafusion::physical_plan::collect;
+use datafusion::physical_plan::display::DisplayableExecutionPlan;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::sync::Arc;
 use url::Url;
 
 mod common;
@@ -40,14 +50,15 @@ async fn test_basic_e2e() -> Result<(), BundlebaseError> {
         .expect("init commit doesn't exist");
     assert_eq!(
         init_content.trim(),
-        format!("id: {}", bundle.bundle.id()).trim()
+        format!("formatVersion: 2\nid: {}", bundle.bundle.id()).trim()
     );
 
     // Find and read the versioned manifest file
     let (contents, commit, url) = common::latest_commit(bundle.data_dir()).await?.unwrap();
 
     let expected = format!(
-        r#"author: {}
+        r#"formatVersion: 2
+author: {}
 message: First commit
 timestamp: {}
 changes:
@@ -61,8 +72,63 @@ changes:
     version: {}
     id: {}
     packId: {}
+    name: {}
+    format: parquet
     numRows: 1000
     bytes: 113629
+    stats:
+    - column: registration_dttm
+      min: 2016-02-03T00:01:00
+      max: 2016-02-03T23:59:55
+      nullCount: 0
+    - column: id
+      min: '1'
+      max: '1000'
+      nullCount: 0
+    - column: first_name
+      min: ''
+      max: Willie
+      nullCount: 0
+    - column: last_name
+      min: Adams
+      max: Young
+      nullCount: 0
+    - column: email
+      min: ''
+      max: wweaver2r@google.de
+      nullCount: 0
+    - column: gender
+      min: ''
+      max: Male
+      nullCount: 0
+    - column: ip_address
+      min: 0.14.221.162
+      max: 99.159.168.233
+      nullCount: 0
+    - column: cc
+      min: ''
+      max: '67718647521473678'
+      nullCount: 0
+    - column: country
+      min: '"Bonaire'
+      max: Zimbabwe
+      nullCount: 0
+    - column: birthdate
+      min: ''
+      max: 9/9/1981
+      nullCount: 0
+    - column: salary
+      min: '12380.49'
+      max: '286592.99'
+      nullCount: 68
+    - column: title
+      min: ''
+      max: Web Developer IV
+      nullCount: 0
+    - column: comments
+      min: ''
+      max: 𠜎𠜱𠝹𠱓𠱸𠲖𠳏
+      nullCount: 6
     schema:
       fields:
       - name: registration_dttm
@@ -179,6 +245,7 @@ changes:
             AnyOperation::AttachBlock,
             pack_id
         ))),
+        op_field!(&commit.operations()[1], AnyOperation::AttachBlock, name).unwrap(),
         commit.changes[1].id,
         commit.changes[2].id,
     );
@@ -221,6 +288,522 @@ async fn test_empty_bundle() -> Result<(), BundlebaseError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_commit_ids_stable_across_reopen() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("First").await?;
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    bundle.commit("Second").await?;
+
+    let ids: Vec<String> = bundle.history().iter().map(|c| c.id.clone()).collect();
+    assert_eq!(2, ids.len());
+    assert!(ids[0] < ids[1]);
+
+    let reopened = Bundle::open(data_dir.url().as_str(), None).await?;
+    let reopened_ids: Vec<String> = reopened.history().iter().map(|c| c.id.clone()).collect();
+    assert_eq!(ids, reopened_ids);
+
+    let first = Bundle::open_at(data_dir.url().as_str(), &ids[0], None).await?;
+    assert_eq!(1, first.history().len());
+    assert_eq!(ids[0], first.history()[0].id);
+    assert_eq!(100, first.num_rows().await?);
+    assert_eq!(150, reopened.num_rows().await?);
+
+    let err = Bundle::open_at(data_dir.url().as_str(), "00099missing", None)
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("No commit '00099missing'"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_at_earlier_schema() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("First").await?;
+    let first_ops = bundle.bundle.operations().len();
+    bundle.remove_column("Phone 1").await?;
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    bundle.commit("Second").await?;
+
+    // Opening at the first commit replays only the operations up to it
+    let first_id = bundle.history()[0].id.clone();
+    let first = Bundle::open_at(data_dir.url().as_str(), &first_id, None).await?;
+    assert_eq!(first_ops, first.operations().len());
+    assert_eq!(100, first.num_rows().await?);
+    assert!(first.schema().await?.field_with_name("Phone 1").is_ok());
+
+    let latest = Bundle::open(data_dir.url().as_str(), None).await?;
+    assert_eq!(first_ops + 2, latest.operations().len());
+    assert_eq!(150, latest.num_rows().await?);
+    assert!(latest.schema().await?.field_with_name("Phone 1").is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diff_between_commits() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let url = data_dir.url().to_string();
+    let mut bundle = bundlebase::BundleBuilder::create(&url, None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("First").await?;
+    bundle.remove_column("Phone 1").await?;
+    bundle.rename_column("City", "Town").await?;
+    bundle.commit("Second").await?;
+
+    let ids: Vec<String> = bundle.history().iter().map(|c| c.id.clone()).collect();
+    let diff = Bundle::diff(&url, &ids[0], &ids[1], None).await?;
+    assert_eq!(
+        vec![
+            "REMOVE COLUMNS: [\"Phone 1\"]",
+            "RENAME COLUMN: City to Town"
+        ],
+        diff.added_operations
+            .iter()
+            .map(|op| op.describe())
+            .collect::<Vec<_>>()
+    );
+    assert!(diff.removed_operations.is_empty());
+    assert_eq!(vec!["Phone 1"], diff.removed_columns);
+    assert!(diff.added_columns.is_empty());
+    assert_eq!(
+        vec![("City".to_string(), "Town".to_string())],
+        diff.renamed_columns
+    );
+    assert_eq!(
+        format!(
+            "diff {}..{}\n+ REMOVE COLUMNS: [\"Phone 1\"]\n+ RENAME COLUMN: City to Town\ncolumns:\n- Phone 1\n~ City -> Town\n2 operations added, 0 removed",
+            ids[0], ids[1]
+        ),
+        diff.to_string()
+    );
+
+    // Going backwards reports the same operations as removed
+    let reverse = Bundle::diff(&url, &ids[1], &ids[0], None).await?;
+    assert_eq!(2, reverse.removed_operations.len());
+    assert_eq!(vec!["Phone 1"], reverse.added_columns);
+    assert!(Bundle::diff(&url, &ids[0], &ids[0], None).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revert_commit() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let url = data_dir.url().to_string();
+    let mut bundle = bundlebase::BundleBuilder::create(&url, None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("Attach customers").await?;
+    bundle.remove_column("Phone 1").await?;
+    bundle.commit("Remove phone").await?;
+    bundle.add_column("upper_city", "upper(\"City\")").await?;
+    bundle.commit("Add upper city").await?;
+    bundle.filter("upper_city like 'A%'", vec![]).await?;
+    bundle.commit("Cities starting with A").await?;
+    let ids: Vec<String> = bundle.history().iter().map(|c| c.id.clone()).collect();
+    let rows = bundle.num_rows().await?;
+    assert!(bundle.schema().await?.field_with_name("Phone 1").is_err());
+
+    // The removed column comes back, the rest is unchanged
+    bundle.revert_commit(&ids[1]).await?;
+    assert!(bundle.schema().await?.field_with_name("Phone 1").is_ok());
+    assert_eq!(rows, bundle.num_rows().await?);
+    let history = bundle.history();
+    assert_eq!(5, history.len());
+    assert_eq!("Revert \"Remove phone\"", history[4].message);
+    assert_eq!(
+        format!("REVERT COMMIT: {}", ids[1]),
+        history[4].operations()[0].describe()
+    );
+
+    let reopened = Bundle::open(&url, None).await?;
+    assert!(reopened.schema().await?.field_with_name("Phone 1").is_ok());
+    assert_eq!(rows, reopened.num_rows().await?);
+
+    let err = bundle
+        .revert_commit(&ids[1])
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(format!("Commit '{}' is already reverted", ids[1]), err);
+
+    // Attaching data can't be undone
+    let err = bundle
+        .revert_commit(&ids[0])
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.ends_with("can't be undone"), "{}", err);
+
+    // The filter uses the added column
+    let err = bundle
+        .revert_commit(&ids[2])
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.starts_with(&format!(
+            "Cannot revert commit '{}': later changes depend on it",
+            ids[2]
+        )),
+        "{}",
+        err
+    );
+
+    let err = bundle
+        .revert_commit("00099missing")
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        "Commit '00099missing' is not in the history of this bundle",
+        err
+    );
+    assert!(bundle.status().is_empty());
+
+    // Reverting the filter brings back all rows
+    bundle.revert_commit(&ids[3]).await?;
+    assert_eq!(100, bundle.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_commit_author() -> Result<(), BundlebaseError> {
+    let mut config = BundleConfig::new();
+    config.set(AUTHOR, "config-author", None);
+    let mut bundle =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("First").await?;
+    let (contents, commit, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("author: config-author\n"));
+    assert_eq!("config-author", commit.author);
+
+    // with_author wins over the config
+    bundle.with_author("builder-author");
+    bundle.filter("Index > 10", vec![]).await?;
+    bundle.commit("Second").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("author: builder-author\n"));
+
+    let authors: Vec<String> = bundle.history().iter().map(|c| c.author.clone()).collect();
+    assert_eq!(vec!["config-author", "builder-author"], authors);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_amend() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("Atach customers").await?;
+    let first_id = bundle.history()[0].id.clone();
+
+    // Fix the message
+    bundle.amend(Some("Attach customers")).await?;
+    let history = bundle.history();
+    assert_eq!(1, history.len());
+    assert_eq!("Attach customers", history[0].message);
+    assert_ne!(first_id, history[0].id);
+    // The init file, the amended manifest and the one replacing it, each with its version claim
+    assert_eq!(5, data_dir.subdir(META_DIR)?.list_files().await?.len());
+
+    // Fold a forgotten operation in, keeping the message
+    bundle.remove_column("Phone 1").await?;
+    bundle.amend(None).await?;
+    let history = bundle.history();
+    assert_eq!(1, history.len());
+    assert_eq!("Attach customers", history[0].message);
+    assert_eq!(
+        "REMOVE COLUMNS: [\"Phone 1\"]",
+        history[0].operations().last().unwrap().describe()
+    );
+    assert!(bundle.status().is_empty());
+
+    let reopened = Bundle::open(data_dir.url().as_str(), None).await?;
+    assert_eq!(1, reopened.history().len());
+    assert!(reopened.schema().await?.field_with_name("Phone 1").is_err());
+    assert_eq!(100, reopened.num_rows().await?);
+
+    assert_eq!(
+        "Nothing to amend: there are no uncommitted changes or new message",
+        bundle.amend(None).await.err().unwrap().to_string()
+    );
+
+    // Another writer committing first wins, and the amend leaves its commit in place
+    let mut other = Bundle::open(data_dir.url().as_str(), None)
+        .await?
+        .extend(None)?;
+    other.set_description("From the other writer").await?;
+    other.commit("Describe").await?;
+    bundle.set_name("Customers").await?;
+    let err = bundle.amend(None).await.err().unwrap().to_string();
+    assert!(
+        err.contains("was already committed by another writer"),
+        "{}",
+        err
+    );
+    let reopened = Bundle::open(data_dir.url().as_str(), None).await?;
+    let messages: Vec<String> = reopened
+        .history()
+        .iter()
+        .map(|c| c.message.clone())
+        .collect();
+    assert_eq!(vec!["Attach customers", "Describe"], messages);
+
+    // The head commit of an extended bundle belongs to the bundle it came from
+    let mut extended = reopened.extend(Some(random_memory_url().as_str()))?;
+    assert_eq!(
+        format!(
+            "Cannot amend commit '{}': it belongs to the bundle this one extends",
+            reopened.history().last().unwrap().id
+        ),
+        extended
+            .amend(Some("Renamed"))
+            .await
+            .err()
+            .unwrap()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_changes_since_commit() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("First").await?;
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    bundle.commit("Second").await?;
+    let history = bundle.history();
+
+    let dest = random_memory_dir();
+    let export = bundle
+        .export_changes(&history[0].id, dest.url().as_str(), true)
+        .await?;
+    assert_eq!(history[0].id, export.since_commit);
+    assert_eq!(history[1].id, export.commit);
+    assert_eq!(50, export.added_rows);
+    assert_eq!(Some(0), export.removed_rows);
+
+    let added = dest.file(ADDED_FILENAME)?.read_str().await?.unwrap();
+    assert_eq!(51, added.lines().count());
+    assert!(added.contains("olivia.reyes@armstrong.com"), "{}", added);
+    let recorded = dest.file(CHANGES_FILENAME)?.read_str().await?.unwrap();
+    assert!(recorded.contains(&format!("commit: {}", history[1].id)));
+
+    // Nothing has changed since the latest commit
+    let export = bundle
+        .export_changes(&export.commit, dest.url().as_str(), false)
+        .await?;
+    assert_eq!(0, export.added_rows);
+    assert_eq!(None, export.removed_rows);
+
+    let err = bundle
+        .export_changes("00001squashed", dest.url().as_str(), false)
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("Use export_csv for a full export"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validate_warns_about_duplicate_content() -> Result<(), BundlebaseError> {
+    let source = ObjectStoreFile::from_url(
+        &Url::parse(test_datafile("customers-0-100.csv"))?,
+        BundleConfig::default().into(),
+    )?
+    .read_bytes()
+    .await?
+    .unwrap();
+    let files = random_memory_dir();
+    files.file("customers.csv")?.write(source.clone()).await?;
+    files.file("customers-copy.csv")?.write(source).await?;
+
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle
+        .attach(files.file("customers.csv")?.url().as_str())
+        .await?;
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    assert_eq!(Vec::<ValidationWarning>::new(), bundle.validate().await?);
+
+    // Attaching the copy still works, the rows are just counted twice
+    bundle
+        .attach(files.file("customers-copy.csv")?.url().as_str())
+        .await?;
+    assert_eq!(250, bundle.num_rows().await?);
+    bundle.commit("Attach customers twice").await?;
+
+    let bundle = Bundle::open(data_dir.url().as_str(), None).await?;
+    let warnings = bundle.validate().await?;
+    assert_eq!(1, warnings.len(), "{:?}", warnings);
+    match &warnings[0] {
+        ValidationWarning::DuplicateContent { sources, .. } => {
+            assert_eq!(2, sources.len());
+            assert!(sources[0].ends_with("/customers.csv"), "{:?}", sources);
+            assert!(sources[1].ends_with("/customers-copy.csv"), "{:?}", sources);
+        }
+    }
+    assert!(warnings[0]
+        .to_string()
+        .starts_with("Duplicate content: blocks customers-"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_missing_directory() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_url();
+
+    let err = Bundle::open(data_dir.as_str(), None).await.err().unwrap();
+    let message = err.to_string();
+    assert!(
+        message.starts_with("Bundle directory does not exist"),
+        "{}",
+        message
+    );
+    assert!(message.contains("--create"), "{}", message);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_missing_local_directory() -> Result<(), BundlebaseError> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let url = Url::from_directory_path(temp_dir.path().join("missing")).unwrap();
+
+    let err = Bundle::open(url.as_str(), None).await.err().unwrap();
+    let message = err.to_string();
+    assert!(
+        message.starts_with("Bundle directory does not exist"),
+        "{}",
+        message
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_empty_local_directory() -> Result<(), BundlebaseError> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let url = Url::from_directory_path(temp_dir.path()).unwrap();
+
+    let err = Bundle::open(url.as_str(), None).await.err().unwrap();
+    let message = err.to_string();
+    assert!(
+        message.starts_with("Not a bundle") && message.contains("is an empty directory"),
+        "{}",
+        message
+    );
+    assert!(message.contains("--create"), "{}", message);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_directory_that_is_not_a_bundle() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    data_dir
+        .file("data.csv")?
+        .write(bytes::Bytes::from("a,b\n1,2\n"))
+        .await?;
+
+    let err = Bundle::open(data_dir.url().as_str(), None)
+        .await
+        .err()
+        .unwrap();
+    let message = err.to_string();
+    assert!(message.starts_with("Not a bundle"), "{}", message);
+    assert!(message.contains(INIT_FILENAME), "{}", message);
+    assert!(message.contains("--create"), "{}", message);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_corrupt_manifest() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.set_name("Corrupt").await?;
+    bundle.commit("Initial commit").await?;
+
+    let (_, _, manifest_url) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    ObjectStoreFile::from_url(&manifest_url, BundleConfig::default().into())?
+        .write(bytes::Bytes::from("changes: [not, a, change"))
+        .await?;
+
+    let err = Bundle::open(data_dir.url().as_str(), None)
+        .await
+        .err()
+        .unwrap();
+    let message = err.to_string();
+    assert!(message.contains("corrupt manifest"), "{}", message);
+    assert!(
+        message.contains(manifest_url.path_segments().unwrap().next_back().unwrap()),
+        "{}",
+        message
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_newer_manifest_format() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.set_name("From the future").await?;
+    bundle.commit("Initial commit").await?;
+
+    let (contents, _, manifest_url) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    let future_contents = contents.replace(
+        &format!("formatVersion: {}\n", FORMAT_VERSION),
+        &format!("formatVersion: {}\n", FORMAT_VERSION + 1),
+    );
+    ObjectStoreFile::from_url(&manifest_url, BundleConfig::default().into())?
+        .write(bytes::Bytes::from(future_contents))
+        .await?;
+
+    let err = Bundle::open(data_dir.url().as_str(), None)
+        .await
+        .err()
+        .unwrap();
+    let message = err.to_string();
+    assert!(
+        message.contains(&format!("format version {}", FORMAT_VERSION + 1)),
+        "{}",
+        message
+    );
+    assert!(message.contains("Upgrade bundlebase"), "{}", message);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_save_multiple_operations() -> Result<(), BundlebaseError> {
     let temp_dir = random_memory_dir();
@@ -240,6 +823,7 @@ async fn test_save_multiple_operations() -> Result<(), BundlebaseError> {
 
     let expected = format!(
         r#"
+formatVersion: 2
 author: {}
 message: Commit changes
 timestamp: {}
@@ -254,8 +838,63 @@ changes:
     version: {}
     id: {}
     packId: {}
+    name: {}
+    format: parquet
     numRows: 1000
     bytes: 113629
+    stats:
+    - column: registration_dttm
+      min: 2016-02-03T00:01:00
+      max: 2016-02-03T23:59:55
+      nullCount: 0
+    - column: id
+      min: '1'
+      max: '1000'
+      nullCount: 0
+    - column: first_name
+      min: ''
+      max: Willie
+      nullCount: 0
+    - column: last_name
+      min: Adams
+      max: Young
+      nullCount: 0
+    - column: email
+      min: ''
+      max: wweaver2r@google.de
+      nullCount: 0
+    - column: gender
+      min: ''
+      max: Male
+      nullCount: 0
+    - column: ip_address
+      min: 0.14.221.162
+      max: 99.159.168.233
+      nullCount: 0
+    - column: cc
+      min: ''
+      max: '67718647521473678'
+      nullCount: 0
+    - column: country
+      min: '"Bonaire'
+      max: Zimbabwe
+      nullCount: 0
+    - column: birthdate
+      min: ''
+      max: 9/9/1981
+      nullCount: 0
+    - column: salary
+      min: '12380.49'
+      max: '286592.99'
+      nullCount: 68
+    - column: title
+      min: ''
+      max: Web Developer IV
+      nullCount: 0
+    - column: comments
+      min: ''
+      max: 𠜎𠜱𠝹𠱓𠱸𠲖𠳏
+      nullCount: 6
     schema:
       fields:
       - name: registration_dttm
@@ -387,6 +1026,7 @@ changes:
             AnyOperation::AttachBlock,
             pack_id
         ))),
+        op_field!(&commit.operations()[1], AnyOperation::AttachBlock, name).unwrap(),
         commit.changes[1].id,
         commit.changes[2].id,
         commit.changes[3].id,
@@ -448,6 +1088,51 @@ async fn test_name_and_description() -> Result<(), BundlebaseError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_labels() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+
+    assert_eq!(
+        "No uncommitted change to label. Make a change before labeling it",
+        bundle
+            .label("dataset", "sales")
+            .await
+            .err()
+            .unwrap()
+            .to_string()
+    );
+
+    bundle
+        .attach(test_datafile("userdata.parquet"))
+        .await?
+        .label("dataset", "sales")
+        .await?
+        .set_name("Labeled")
+        .await?;
+
+    let labeled = bundle.operations_with_label("dataset", "sales");
+    assert_eq!(2, labeled.len());
+    assert!(matches!(labeled[0], AnyOperation::DefinePack(_)));
+    assert!(matches!(labeled[1], AnyOperation::AttachBlock(_)));
+
+    bundle.commit("Labeled attach").await?;
+
+    let loaded = Bundle::open(data_dir.as_str(), None).await?;
+    let labeled = loaded.operations_with_label("dataset", "sales");
+    assert_eq!(2, labeled.len());
+    assert!(matches!(labeled[1], AnyOperation::AttachBlock(_)));
+    assert!(loaded
+        .operations_with_label("dataset", "marketing")
+        .is_empty());
+
+    let changes = &loaded.history()[0].changes;
+    assert!(changes[0].to_string().ends_with(" [dataset=sales]"));
+    assert!(changes[1].labels.is_empty());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_attach_csv() -> Result<(), BundlebaseError> {
     let data_dir = random_memory_dir();
@@ -463,6 +1148,7 @@ async fn test_attach_csv() -> Result<(), BundlebaseError> {
     assert_eq!(
         format!(
             r"
+formatVersion: 2
 author: {}
 message: CSV commit
 timestamp: {}
@@ -477,9 +1163,58 @@ changes:
     version: {}
     id: {}
     packId: {}
+    name: {}
+    format: csv
     layout: {}
     numRows: 100
     bytes: 17160
+    stats:
+    - column: Index
+      min: '1'
+      max: '100'
+      nullCount: 0
+    - column: Customer Id
+      min: 010468dAA11382c
+      max: faCEF517ae7D8eB
+      nullCount: 0
+    - column: First Name
+      min: Aimee
+      max: Yvonne
+      nullCount: 0
+    - column: Last Name
+      min: Alvarado
+      max: Zuniga
+      nullCount: 0
+    - column: Company
+      min: Acosta, Petersen and Morrow
+      max: Winters-Mendoza
+      nullCount: 0
+    - column: City
+      min: Acevedoville
+      max: Zimmermanland
+      nullCount: 0
+    - column: Country
+      min: Albania
+      max: Zimbabwe
+      nullCount: 0
+    - column: Phone 1
+      min: (041)737-3846
+      max: 981-544-9452
+      nullCount: 0
+    - column: Phone 2
+      min: (026)401-7353x2417
+      max: 999-728-1637
+      nullCount: 0
+    - column: Email
+      min: aharper@maddox-townsend.org
+      max: zvalencia@phelps.com
+      nullCount: 0
+    - column: Subscription Date
+      nullCount: 0
+    - column: Website
+      min: http://acosta.org/
+      max: https://www.vang.com/
+      nullCount: 0
     schema:
       fields:
       - name: Index
@@ -573,6 +1308,7 @@ changes:
             test_utils::for_yaml(
                 op_field!(commit.operations()[1], AnyOperation::AttachBlock, pack_id).into()
             ),
+            op_field!(commit.operations()[1], AnyOperation::AttachBlock, name).unwrap(),
             test_utils::for_yaml(
                 op_field!(commit.operations()[1], AnyOperation::AttachBlock, layout).unwrap()
             )
@@ -655,3 +1391,535 @@ async fn test_attach_json() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_attach_compressed_csv() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle
+        .attach(test_datafile("customers-0-100.csv.gz"))
+        .await?;
+    bundle.commit("Compressed commit").await?;
+
+    let (contents, commit, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("format: csv"), "{}", contents);
+    assert!(contents.contains("compression: gzip"), "{}", contents);
+    assert_eq!(
+        Some(100),
+        op_field!(commit.operations()[1], AnyOperation::AttachBlock, num_rows)
+    );
+
+    // Reads the same rows as the uncompressed original, also after reopening
+    let mut original =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    original
+        .attach(test_datafile("customers-0-100.csv"))
+        .await?;
+    let sql = "SELECT * FROM bundle ORDER BY \"Index\"";
+    let expected = original.bundle.sql(sql, vec![]).await?;
+
+    let loaded_bundle = Bundle::open(data_dir.url().as_str(), None).await?;
+    for actual in [
+        bundle.bundle.sql(sql, vec![]).await?,
+        loaded_bundle.sql(sql, vec![]).await?,
+    ] {
+        assert_eq!(
+            arrow::util::pretty::pretty_format_batches(&expected)?.to_string(),
+            arrow::util::pretty::pretty_format_batches(&actual)?.to_string()
+        );
+    }
+
+    // A compressed file can only be read from the start
+    let err = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None)
+        .await?
+        .attach_with_split(
+            test_datafile("customers-0-100.csv.gz"),
+            AttachSplit::Parts(2),
+        )
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("compressed files can't be split"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attach_with_configured_formats() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    data_dir
+        .file("people.tsv")?
+        .write(bytes::Bytes::from("id\tname\n1\tAnn, Jr\n2\tBob\n"))
+        .await?;
+    // No extension mapping for .txt, so its format is sniffed from the content
+    data_dir
+        .file("more_people.txt")?
+        .write(bytes::Bytes::from("id,name\n3,Cal\n"))
+        .await?;
+
+    let schema: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("score", DataType::Float64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(Float64Array::from(vec![1.5, 2.5, 3.5])),
+        ],
+    )?;
+    let mut parquet = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut parquet, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    data_dir
+        .file("scores.pq")?
+        .write(bytes::Bytes::from(parquet))
+        .await?;
+
+    let mut config = BundleConfig::new();
+    config.set(FILE_FORMATS, "tsv=tsv,pq=parquet", None);
+
+    let people_url = random_memory_url();
+    let mut people =
+        bundlebase::BundleBuilder::create(people_url.as_str(), Some(config.clone())).await?;
+    people
+        .attach(data_dir.file("people.tsv")?.url().as_str())
+        .await?;
+    people
+        .attach(data_dir.file("more_people.txt")?.url().as_str())
+        .await?;
+    people.commit("Attach people").await?;
+
+    let scores_url = random_memory_url();
+    let mut scores = bundlebase::BundleBuilder::create(scores_url.as_str(), Some(config)).await?;
+    scores
+        .attach(data_dir.file("scores.pq")?.url().as_str())
+        .await?;
+    scores.commit("Attach scores").await?;
+
+    let formats = |commit: &bundlebase::bundle::BundleCommit| {
+        commit
+            .operations()
+            .iter()
+            .filter_map(|op| match op {
+                AnyOperation::AttachBlock(op) => op.format.clone(),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let (_, commit, _) = common::latest_commit(people.data_dir()).await?.unwrap();
+    assert_eq!(vec!["tsv", "csv"], formats(&commit));
+    let (_, commit, _) = common::latest_commit(scores.data_dir()).await?.unwrap();
+    assert_eq!(vec!["parquet"], formats(&commit));
+
+    // The recorded formats are used on reopen, even without the config
+    let people = Bundle::open(people_url.as_str(), None).await?;
+    let batches = people
+        .sql("SELECT name FROM bundle ORDER BY id", vec![])
+        .await?;
+    let names = arrow::compute::concat_batches(&batches[0].schema(), &batches)?;
+    let names = names
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(
+        vec!["Ann, Jr", "Bob", "Cal"],
+        names.iter().flatten().collect::<Vec<_>>()
+    );
+
+    let scores = Bundle::open(scores_url.as_str(), None).await?;
+    assert_eq!(3, scores.num_rows().await?);
+    let batches = scores
+        .sql("SELECT score FROM bundle WHERE id = 2", vec![])
+        .await?;
+    let score = batches[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(2.5, score.value(0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_from_bytes() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    data_dir
+        .file("people.csv")?
+        .write(bytes::Bytes::from("id,name\n1,Ann\n2,Bob\n3,Cal\n"))
+        .await?;
+
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.attach("people.csv").await?;
+    bundle.commit("Embedded data").await?;
+
+    let mut files = std::collections::HashMap::new();
+    for file in data_dir.list_files().await? {
+        let path = file.url().as_str()[data_dir.url().as_str().len()..].to_string();
+        files.insert(path, file.read_bytes().await?.unwrap());
+    }
+
+    let embedded = Bundle::from_bytes(files, None).await?;
+    assert_ne!(data_dir.url(), embedded.data_dir().url());
+    assert_eq!(1, embedded.history().len());
+    assert_eq!(3, embedded.num_rows().await?);
+
+    let df = embedded
+        .dataframe()
+        .await?
+        .as_ref()
+        .clone()
+        .filter(datafusion::prelude::col("name").eq(datafusion::prelude::lit("Bob")))?;
+    let batches = df.collect().await?;
+    assert_eq!(1, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_from_bytes_not_a_bundle() -> Result<(), BundlebaseError> {
+    let mut files = std::collections::HashMap::new();
+    files.insert("data.csv".to_string(), bytes::Bytes::from("a\n1\n"));
+
+    let err = Bundle::from_bytes(files, None).await.err().unwrap();
+    assert!(err.to_string().contains("Not a bundle"), "{}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload_picks_up_external_commits() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut builder = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    builder.attach(test_datafile("customers-0-100.csv")).await?;
+    builder.commit("Initial").await?;
+
+    let bundle = Bundle::open(data_dir.url().as_str(), None).await?;
+    assert_eq!(100, bundle.num_rows().await?);
+
+    // Nothing new committed yet
+    assert!(!bundle.has_new_commits().await?);
+    assert_eq!(bundle.version(), bundle.reload().await?.version());
+
+    // Another "process" commits to the same location
+    let mut other = Bundle::open(data_dir.url().as_str(), None)
+        .await?
+        .extend(None)?;
+    other.attach(test_datafile("customers-101-150.csv")).await?;
+    other.commit("More customers").await?;
+
+    assert!(bundle.has_new_commits().await?);
+    let reloaded = bundle.reload().await?;
+    assert_eq!(150, reloaded.num_rows().await?);
+    assert_eq!(2, reloaded.history().len());
+    assert!(!reloaded.has_new_commits().await?);
+
+    // The original handle keeps its snapshot
+    assert_eq!(100, bundle.num_rows().await?);
+    assert_eq!(1, bundle.history().len());
+
+    Ok(())
+}
+
+/// Runs the bundle's query and returns its physical plan, with metrics, and the row count
+async fn run_with_metrics(
+    bundle: &bundlebase::BundleBuilder,
+) -> Result<(String, usize), BundlebaseError> {
+    let df = bundle.dataframe().await?.as_ref().clone();
+    let task_ctx = Arc::new(df.task_ctx());
+    let plan = df.create_physical_plan().await?;
+    let batches = collect(plan.clone(), task_ctx).await?;
+    let rows = batches.iter().map(|b| b.num_rows()).sum();
+    let plan = DisplayableExecutionPlan::with_metrics(plan.as_ref())
+        .indent(true)
+        .to_string();
+    Ok((plan, rows))
+}
+
+#[tokio::test]
+async fn test_attach_split() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut csv = String::from("id,name\n");
+    for id in 0..100_000 {
+        csv.push_str(&format!("{},name_{}\n", id, id));
+    }
+    assert!(csv.len() > 1024 * 1024);
+    data_dir
+        .file("big.csv")?
+        .write(bytes::Bytes::from(csv))
+        .await?;
+    let csv_url = data_dir.file("big.csv")?.url().to_string();
+
+    // The split is recorded when attached, so it survives reopening
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle
+        .attach_with_split(&csv_url, AttachSplit::Parts(4))
+        .await?;
+    bundle.commit("Attach split").await?;
+    let bundle = Bundle::open(url.as_str(), None).await?.extend(None)?;
+
+    let (plan, rows) = run_with_metrics(&bundle).await?;
+    assert!(plan.contains("file_groups={4 groups"), "{}", plan);
+    assert_eq!(100_000, rows);
+
+    // Rows at the range boundaries are read exactly once
+    let totals = bundle
+        .select("select count(distinct id), sum(id) from bundle", vec![])
+        .await?
+        .dataframe()
+        .await?
+        .as_ref()
+        .clone()
+        .collect()
+        .await?;
+    let distinct = totals[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .value(0);
+    let sum = totals[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .value(0);
+    assert_eq!(100_000, distinct);
+    assert_eq!((0..100_000i64).sum::<i64>(), sum);
+
+    // Forcing a single partition
+    let mut single = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    single
+        .attach_with_split(&csv_url, AttachSplit::Single)
+        .await?;
+    let (plan, rows) = run_with_metrics(&single).await?;
+    assert!(plan.contains("file_groups={1 group"), "{}", plan);
+    assert_eq!(100_000, rows);
+
+    // Auto splits by the configured size
+    let mut config = BundleConfig::new();
+    config.set(ATTACH_SPLIT_MB, "1", None);
+    let mut auto =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+    auto.attach(&csv_url).await?;
+    let (plan, rows) = run_with_metrics(&auto).await?;
+    assert!(plan.contains("file_groups={2 groups"), "{}", plan);
+    assert_eq!(100_000, rows);
+
+    // Parquet partitions line up with row groups, so a filter prunes the partitions it can't match
+    let schema: SchemaRef = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int64Array::from((0..4000).collect::<Vec<i64>>()))],
+    )?;
+    let mut parquet = Vec::new();
+    let props = WriterProperties::builder()
+        .set_max_row_group_size(1000)
+        .build();
+    let mut writer = ArrowWriter::try_new(&mut parquet, schema, Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    data_dir
+        .file("ids.parquet")?
+        .write(bytes::Bytes::from(parquet))
+        .await?;
+
+    let mut ids = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    ids.attach_with_split(
+        data_dir.file("ids.parquet")?.url().as_str(),
+        AttachSplit::Parts(4),
+    )
+    .await?;
+    let filtered = ids
+        .select("select id from bundle where id < 10", vec![])
+        .await?;
+    let (plan, rows) = run_with_metrics(&filtered).await?;
+    assert!(plan.contains("file_groups={4 groups"), "{}", plan);
+    assert!(
+        plan.contains("row_groups_pruned_statistics=4 total → 1 matched"),
+        "{}",
+        plan
+    );
+    assert_eq!(10, rows);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attach_as_parquet() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut csv = String::from("id,name\n");
+    for id in 0..1000 {
+        csv.push_str(&format!("{},name_{}\n", id, id));
+    }
+    data_dir
+        .file("ids.csv")?
+        .write(bytes::Bytes::from(csv))
+        .await?;
+    let csv_url = data_dir.file("ids.csv")?.url().to_string();
+
+    // Four blocks of 250 rows, rounded up to whole 100 row groups
+    let url = random_memory_url();
+    let mut config = BundleConfig::new();
+    config.set(PARQUET_ROW_GROUP_SIZE, "100", None);
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), Some(config)).await?;
+    bundle
+        .attach_as_parquet(&csv_url, AttachSplit::Parts(4))
+        .await?;
+    bundle.commit("Attach as parquet").await?;
+    let bundle = Bundle::open(url.as_str(), None).await?.extend(None)?;
+
+    let blocks = &bundle.packs()[0].blocks;
+    assert_eq!(
+        vec![Some(300), Some(300), Some(300), Some(100)],
+        blocks.iter().map(|b| b.num_rows).collect::<Vec<_>>()
+    );
+    for op in bundle.operations() {
+        if let AnyOperation::AttachBlock(op) = op {
+            assert!(op.source.ends_with(".parquet"), "{}", op.source);
+            assert_eq!(Some(&csv_url), op.converted_from.as_ref());
+        }
+    }
+
+    // Together the blocks hold every row exactly once
+    let totals = bundle
+        .select("select count(distinct id), sum(id) from bundle", vec![])
+        .await?
+        .dataframe()
+        .await?
+        .as_ref()
+        .clone()
+        .collect()
+        .await?;
+    let distinct = totals[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .value(0);
+    let sum = totals[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .value(0);
+    assert_eq!(1000, distinct);
+    assert_eq!((0..1000i64).sum::<i64>(), sum);
+
+    // Only the first block can have ids below 100, so the others aren't read
+    let filtered = bundle
+        .select("select * from bundle where id < 100", vec![])
+        .await?;
+    assert_eq!(100, filtered.num_rows().await?);
+    let explain = filtered.bundle.explain().await?;
+    assert!(explain.contains(&blocks[0].source), "{}", explain);
+    for block in &blocks[1..] {
+        assert!(!explain.contains(&block.source), "{}", explain);
+    }
+
+    // Forcing a single block
+    let mut single = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    single
+        .attach_as_parquet(&csv_url, AttachSplit::Single)
+        .await?;
+    assert_eq!(
+        vec![Some(1000)],
+        single.packs()[0]
+            .blocks
+            .iter()
+            .map(|b| b.num_rows)
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attach_row_groups() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let schema: SchemaRef = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int64Array::from((0..4000).collect::<Vec<i64>>()))],
+    )?;
+    let mut parquet = Vec::new();
+    let props = WriterProperties::builder()
+        .set_max_row_group_size(1000)
+        .build();
+    let mut writer = ArrowWriter::try_new(&mut parquet, schema, Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    data_dir
+        .file("ids.parquet")?
+        .write(bytes::Bytes::from(parquet))
+        .await?;
+    let parquet_url = data_dir.file("ids.parquet")?.url().to_string();
+
+    // The selection is recorded when attached, so it survives reopening
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach_row_groups(&parquet_url, &[0..1]).await?;
+    bundle.commit("Attach row group 0").await?;
+    let bundle = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(
+        Some(vec![0]),
+        op_field!(
+            &bundle.history()[0].operations()[1],
+            AnyOperation::AttachBlock,
+            row_groups
+        )
+    );
+    assert_eq!(1000, bundle.num_rows().await?);
+
+    let totals = bundle
+        .sql("select count(*), min(id), max(id) from bundle", vec![])
+        .await?;
+    let values: Vec<i64> = (0..3)
+        .map(|i| {
+            totals[0]
+                .column(i)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .value(0)
+        })
+        .collect();
+    assert_eq!(vec![1000, 0, 999], values);
+
+    // Ranges are checked against the file's row groups
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    assert_eq!(
+        format!(
+            "Row group 4 is out of range: {} has 4 row groups",
+            parquet_url
+        ),
+        bundle
+            .attach_row_groups(&parquet_url, &[3..5])
+            .await
+            .err()
+            .unwrap()
+            .to_string()
+    );
+    assert_eq!(
+        format!(
+            "Cannot select row groups of {}: only parquet files have row groups",
+            test_datafile("customers-0-100.csv")
+        ),
+        bundle
+            .attach_row_groups(test_datafile("customers-0-100.csv"), &[0..1])
+            .await
+            .err()
+            .unwrap()
+            .to_string()
+    );
+
+    Ok(())
+}
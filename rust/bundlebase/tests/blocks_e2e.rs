@@ -1,8 +1,11 @@
 use bundlebase;
 use bundlebase::bundle::BundleFacade;
+use bundlebase::bundle_config::BLOCK_STATS;
+use bundlebase::io::ObjectStoreFile;
 use bundlebase::test_utils::{assert_vec_regexp, random_memory_url, test_datafile};
-use bundlebase::BundlebaseError;
 use bundlebase::Operation;
+use bundlebase::{BundleConfig, BundlebaseError};
+use url::Url;
 
 mod common;
 
@@ -52,3 +55,200 @@ async fn test_adding_blocks() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_attach_glob() -> Result<(), BundlebaseError> {
+    // Make sure the test data files are loaded into memory
+    test_datafile("customers-0-100.csv");
+
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+
+    bundle.attach("memory:///test_data/customers-*.csv").await?;
+
+    assert_vec_regexp(
+        vec![
+            "CREATE PACK \\w+",
+            "ATTACH: memory:///test_data/customers-0-100.csv",
+            "ATTACH: memory:///test_data/customers-101-150.csv",
+        ],
+        bundle
+            .status()
+            .operations()
+            .iter()
+            .map(|x| x.describe())
+            .collect::<Vec<String>>(),
+    );
+    assert_eq!(1, bundle.status().changes().len());
+
+    assert_eq!(150, bundle.num_rows().await?);
+    assert_eq!(12, bundle.schema().await?.fields().len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attach_glob_no_matches() -> Result<(), BundlebaseError> {
+    test_datafile("customers-0-100.csv");
+
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+
+    let result = bundle.attach("memory:///test_data/missing-*.csv").await;
+    assert_eq!(
+        "No files match memory:///test_data/missing-*.csv",
+        result.err().unwrap().to_string()
+    );
+
+    // An escaped '*' is treated as a literal character rather than a glob
+    let result = bundle.attach(r"memory:///test_data/customers-\*.csv").await;
+    assert!(result.is_err());
+    assert!(bundle.status().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_packs() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+
+    let packs = bundle.packs();
+    assert_eq!(1, packs.len());
+    let pack = &packs[0];
+    assert_eq!(Some(150), pack.num_rows());
+    assert_eq!(2, pack.blocks.len());
+
+    let first = &pack.blocks[0];
+    assert_eq!("memory:///test_data/customers-0-100.csv", first.source);
+    assert_eq!(
+        Some("memory:///test_data/customers-0-100.csv"),
+        first.url.as_ref().map(|u| u.as_str())
+    );
+    assert_eq!(Some(100), first.num_rows);
+    assert!(first.name.starts_with("customers-0-100-"), "{}", first.name);
+    assert!(first.name.ends_with("-1"), "{}", first.name);
+    assert!(
+        pack.blocks[1].name.ends_with("-2"),
+        "{}",
+        pack.blocks[1].name
+    );
+
+    // Attaching the same file to another bundle gives the same name
+    let mut other = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    other.attach(test_datafile("customers-0-100.csv")).await?;
+    assert_eq!(first.name, other.packs()[0].blocks[0].name);
+
+    // Names are kept in the manifest
+    bundle.commit("Attached").await?;
+    let reopened = bundlebase::Bundle::open(data_dir.as_str(), None).await?;
+    assert_eq!(packs, reopened.packs());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_stats() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    bundle.commit("Attached").await?;
+
+    let blocks = &bundle.packs()[0].blocks;
+    let stats = bundle.block_stats();
+    let index = |block: usize| {
+        stats[&blocks[block].id]
+            .iter()
+            .find(|s| s.column == "Index")
+            .cloned()
+            .expect("no stats for Index")
+    };
+    assert_eq!(Some("1"), index(0).min.as_deref());
+    assert_eq!(Some("100"), index(0).max.as_deref());
+    assert_eq!(Some("101"), index(1).min.as_deref());
+    assert_eq!(Some("150"), index(1).max.as_deref());
+    assert_eq!(0, index(1).null_count);
+
+    // Only the second block can have rows past 120, so the first isn't read
+    let rs = bundle
+        .select("select * from bundle where \"Index\" > 120", vec![])
+        .await?;
+    assert_eq!(30, rs.num_rows().await?);
+    let explain = rs.bundle.explain().await?;
+    assert!(explain.contains("EmptyExec"), "{}", explain);
+    assert!(!explain.contains("customers-0-100.csv"), "{}", explain);
+    assert!(explain.contains("customers-101-150.csv"), "{}", explain);
+
+    // A range both blocks overlap reads both
+    let rs = bundle
+        .select(
+            "select * from bundle where \"Index\" between 90 and 110",
+            vec![],
+        )
+        .await?;
+    assert_eq!(21, rs.num_rows().await?);
+    let explain = rs.bundle.explain().await?;
+    assert!(!explain.contains("EmptyExec"), "{}", explain);
+
+    // Stats are kept in the manifest
+    let reopened = bundlebase::Bundle::open(data_dir.as_str(), None).await?;
+    assert_eq!(stats, reopened.block_stats());
+    let rs = reopened
+        .select("select * from bundle where \"Index\" <= 10", vec![])
+        .await?;
+    assert_eq!(10, rs.num_rows().await?);
+    let explain = rs.bundle.explain().await?;
+    assert!(!explain.contains("customers-101-150.csv"), "{}", explain);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_stats_source_changed() -> Result<(), BundlebaseError> {
+    let source = Url::parse(&format!("{}/readings.csv", random_memory_url()))?;
+    let file = ObjectStoreFile::from_url(&source, BundleConfig::default().into())?;
+    file.write(bytes::Bytes::from("id,reading\n1,10\n2,20\n"))
+        .await?;
+
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+    bundle.attach(source.as_str()).await?;
+    bundle.commit("Attached").await?;
+
+    // The source is rewritten with values outside the recorded range
+    file.write(bytes::Bytes::from("id,reading\n1,10\n2,500\n"))
+        .await?;
+    let reopened = bundlebase::Bundle::open(data_dir.as_str(), None).await?;
+    let rs = reopened
+        .select("select * from bundle where reading > 100", vec![])
+        .await?;
+    assert_eq!(1, rs.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_stats_disabled() -> Result<(), BundlebaseError> {
+    let mut config = BundleConfig::new();
+    config.set(BLOCK_STATS, "false", None);
+    let mut bundle =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+
+    assert!(bundle.block_stats().is_empty());
+    let rs = bundle
+        .select("select * from bundle where \"Index\" > 120", vec![])
+        .await?;
+    assert_eq!(0, rs.num_rows().await?);
+    let explain = rs.bundle.explain().await?;
+    assert!(explain.contains("customers-0-100.csv"), "{}", explain);
+
+    Ok(())
+}
@@ -1,10 +1,13 @@
 use bundlebase;
-use bundlebase::bundle::{AnyOperation, BundleFacade, InitCommit, INIT_FILENAME, META_DIR};
+use bundlebase::bundle::{
+    AnyOperation, BundleFacade, InitCommit, INIT_FILENAME, LOCK_FILENAME, META_DIR,
+};
 use bundlebase::io::ObjectStoreFile;
 use bundlebase::test_utils::{random_memory_dir, random_memory_url, test_datafile};
 use bundlebase::Bundle;
 use bundlebase::BundleConfig;
 use bundlebase::BundlebaseError;
+use bundlebase::RefMapping;
 use url::Url;
 
 mod common;
@@ -295,3 +298,254 @@ async fn test_extend_inherits_same_id() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_create_from_template() -> Result<(), BundlebaseError> {
+    let template_url = random_memory_url();
+    let mut template = bundlebase::BundleBuilder::create(template_url.as_str(), None).await?;
+    template
+        .attach(test_datafile("customers-0-100.csv"))
+        .await?;
+    template.index("Email").await?;
+    template.index("City").await?;
+    template.drop_index("City").await?;
+    template.declare_key(vec!["Customer Id"], false).await?;
+    template.set_nullable("Email", false).await?;
+    template.commit("Template").await?;
+
+    let url = random_memory_url();
+    let mut bundle =
+        bundlebase::BundleBuilder::create_from_template(url.as_str(), template_url.as_str(), None)
+            .await?;
+    assert!(bundle.status().is_empty());
+    assert_eq!(0, bundle.num_rows().await?);
+
+    let indexed_columns = |bundle: &Bundle| {
+        bundle
+            .operations()
+            .iter()
+            .filter_map(|op| match op {
+                AnyOperation::CreateIndex(op) => Some(op.column.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["Email"], indexed_columns(bundle.bundle()));
+    assert_eq!(["Customer Id"], bundle.bundle().key_columns());
+
+    // Data attached later is indexed under the template's definitions
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.reindex().await?;
+    assert!(bundle
+        .status()
+        .operations()
+        .iter()
+        .any(|op| matches!(op, AnyOperation::IndexBlocks(_))));
+    assert_eq!(100, bundle.num_rows().await?);
+    assert!(!bundle
+        .schema()
+        .await?
+        .field_with_name("Email")?
+        .is_nullable());
+    bundle.commit("Attached data").await?;
+
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(vec!["Email"], indexed_columns(&reopened));
+    assert_eq!(["Customer Id"], reopened.key_columns());
+    assert_eq!(2, reopened.history().len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_lock_blocks_second_writer() -> Result<(), BundlebaseError> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let url = Url::from_directory_path(temp_dir.path()).unwrap();
+
+    let mut first = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    first.lock().await?;
+    first.attach(test_datafile("customers-0-100.csv")).await?;
+    first.commit("Initial commit").await?;
+    assert!(temp_dir.path().join(META_DIR).join(LOCK_FILENAME).exists());
+
+    // Read-only opens don't need the lock
+    let opened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(1, opened.history().len());
+
+    let mut second = opened.extend(None)?;
+    let err = second.lock().await.err().unwrap().to_string();
+    assert!(
+        err.starts_with("Bundle is locked by another writer"),
+        "{}",
+        err
+    );
+
+    first.unlock().await?;
+    assert!(!temp_dir.path().join(META_DIR).join(LOCK_FILENAME).exists());
+    second.lock().await?;
+    second.unlock().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retried_commit_writes_one_manifest() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut builder = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    builder.attach(test_datafile("customers-0-100.csv")).await?;
+
+    // A retry commits the same pending changes again, e.g. after a network error
+    let mut retry = builder.clone();
+    builder.commit("Initial commit").await?;
+    let init = data_dir
+        .subdir(META_DIR)?
+        .file(INIT_FILENAME)?
+        .read_str()
+        .await?;
+    retry.commit("Initial commit").await?;
+
+    let manifest_dir = data_dir.subdir(META_DIR)?;
+    let manifests = manifest_dir
+        .list_files()
+        .await?
+        .into_iter()
+        .filter(|f| f.filename() != INIT_FILENAME)
+        .collect::<Vec<_>>();
+    // The manifest and the claim on its version
+    assert_eq!(2, manifests.len());
+    assert_eq!(init, manifest_dir.file(INIT_FILENAME)?.read_str().await?);
+
+    let bundle = Bundle::open(data_dir.url().as_str(), None).await?;
+    assert_eq!(1, bundle.history().len());
+    assert_eq!(100, bundle.num_rows().await?);
+
+    // A different commit at the same version is a conflict
+    let mut first = bundle.extend(None)?;
+    let mut second = bundle.extend(None)?;
+    first.set_name("first").await?;
+    second.set_name("second").await?;
+    first.commit("First").await?;
+    let err = second.commit("Second").await.err().unwrap().to_string();
+    assert!(
+        err.contains("Version 2") && err.contains("already committed by another writer"),
+        "{}",
+        err
+    );
+    assert_eq!(
+        Some("first"),
+        Bundle::open(data_dir.url().as_str(), None).await?.name()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_commits_at_same_version() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    let mut builder = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    builder.attach(test_datafile("customers-0-100.csv")).await?;
+    builder.commit("Initial commit").await?;
+
+    let mut first = builder.bundle.extend(None)?;
+    let mut second = builder.bundle.extend(None)?;
+    first.set_name("first").await?;
+    second.set_name("second").await?;
+    let (first_result, second_result) =
+        tokio::join!(first.commit("First"), second.commit("Second"));
+
+    // Exactly one of the racing writers gets version 2
+    let (winner, err) = match (first_result, second_result) {
+        (Ok(()), Err(e)) => ("first", e.to_string()),
+        (Err(e), Ok(())) => ("second", e.to_string()),
+        (first, second) => panic!("Expected one commit to fail: {:?}, {:?}", first, second),
+    };
+    assert!(
+        err.contains("Version 2") && err.contains("already committed by another writer"),
+        "{}",
+        err
+    );
+
+    let bundle = Bundle::open(data_dir.url().as_str(), None).await?;
+    assert_eq!(2, bundle.history().len());
+    assert_eq!(Some(winner), bundle.name());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_repair_refs_after_moving_parent() -> Result<(), BundlebaseError> {
+    let old_root = random_memory_dir();
+    let new_root = random_memory_dir();
+    let child_dir = random_memory_dir();
+
+    // The parent and a file it attaches by absolute path both live under old_root
+    let csv = old_root.file("files/customers.csv")?;
+    ObjectStoreFile::from_url(
+        &Url::parse(test_datafile("customers-0-100.csv"))?,
+        BundleConfig::default().into(),
+    )?
+    .copy_to(&csv)
+    .await?;
+    let parent_dir = old_root.subdir("parent")?;
+    let mut parent = bundlebase::BundleBuilder::create(parent_dir.url().as_str(), None).await?;
+    parent.attach(csv.url().as_str()).await?;
+    parent.commit("Attach customers").await?;
+
+    let mut child = Bundle::open(parent_dir.url().as_str(), None)
+        .await?
+        .extend(Some(child_dir.url().as_str()))?;
+    child.remove_column("Country").await?;
+    child.commit("Remove country").await?;
+
+    // Move everything under old_root to new_root
+    for file in old_root.list_files().await? {
+        let relative = &file.url().as_str()[old_root.url().as_str().len()..];
+        file.copy_to(&new_root.file(relative.trim_start_matches('/'))?)
+            .await?;
+        file.delete().await?;
+    }
+    assert!(Bundle::open(child_dir.url().as_str(), None).await.is_err());
+
+    // A mapping to somewhere the bundle isn't doesn't write anything
+    let err = Bundle::repair_refs(
+        child_dir.url().as_str(),
+        vec![RefMapping::new(
+            old_root.url().as_str(),
+            "memory:///nowhere",
+        )],
+        None,
+    )
+    .await
+    .err()
+    .unwrap()
+    .to_string();
+    assert!(err.contains("still can't be opened"), "{}", err);
+
+    let repaired = Bundle::repair_refs(
+        child_dir.url().as_str(),
+        vec![RefMapping::new(
+            old_root.url().as_str(),
+            new_root.url().as_str(),
+        )],
+        None,
+    )
+    .await?;
+    assert_eq!(100, repaired.num_rows().await?);
+    assert!(repaired
+        .history()
+        .last()
+        .unwrap()
+        .message
+        .starts_with("REPAIR REFS: "));
+
+    // The repair commit is picked up by a plain open
+    let reopened = Bundle::open(child_dir.url().as_str(), None).await?;
+    assert_eq!(100, reopened.num_rows().await?);
+    assert!(!common::has_column(&reopened.schema().await?, "Country"));
+    assert!(matches!(
+        reopened.operations().last(),
+        Some(AnyOperation::RepairRefs(_))
+    ));
+
+    Ok(())
+}
@@ -26,6 +26,12 @@ pub async fn latest_commit(
     let mut files = files
         .iter()
         .filter(|x| x.filename() != INIT_FILENAME)
+        // Skip the version claims and other files in subdirectories
+        .filter(|x| {
+            meta_dir
+                .file(x.filename())
+                .is_ok_and(|f| f.url() == x.url())
+        })
         .collect::<Vec<_>>();
 
     files.sort_by_key(|f| manifest_version(f.filename()));
@@ -1,8 +1,11 @@
 use bundlebase;
-use bundlebase::bundle::BundleFacade;
+use bundlebase::bundle::{parse_command, BundleFacade};
+use bundlebase::bundle_config::TIME_ZONE;
+use bundlebase::io::ObjectStoreFile;
 use bundlebase::test_utils::{random_memory_url, test_datafile};
-use bundlebase::BundlebaseError;
+use bundlebase::{Bundle, BundleConfig, BundlebaseError, Operation};
 use datafusion::scalar::ScalarValue;
+use url::Url;
 
 mod common;
 
@@ -136,3 +139,215 @@ async fn test_select_with_filter() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_delete_rows_sql() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    assert_eq!(100, bundle.num_rows().await?);
+
+    parse_command("DELETE FROM data WHERE Country = 'Chile'")?
+        .execute(&mut bundle)
+        .await?;
+    assert_eq!(99, bundle.num_rows().await?);
+    assert_eq!(
+        "DELETE WHERE Country = 'Chile'",
+        bundle.status().changes().last().unwrap().operations[0].describe()
+    );
+
+    let chile_rows = bundle
+        .select("SELECT * FROM bundle WHERE \"Country\" = 'Chile'", vec![])
+        .await?
+        .num_rows()
+        .await?;
+    assert_eq!(0, chile_rows);
+
+    bundle.commit("Remove Chile").await?;
+
+    let reopened = Bundle::open(data_dir.as_str(), None).await?;
+    assert_eq!(99, reopened.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_rows_keeps_null_matches() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+
+    // Filter and delete with the same clause should partition the rows,
+    // with rows where the clause is NULL kept by delete_rows
+    bundle
+        .delete_rows("salary > $1", vec![ScalarValue::Float64(Some(50000.0))])
+        .await?;
+    assert_eq!(202, bundle.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_security_filter() -> Result<(), BundlebaseError> {
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    let mut bundle = bundle
+        .select(
+            r#"SELECT *, "Index" % 2 = 0 AS deleted FROM bundle"#,
+            vec![],
+        )
+        .await?;
+    bundle.set_security_filter("deleted = false").await?;
+    assert_eq!(50, bundle.num_rows().await?);
+
+    // A bad condition is rejected when set, not when the data is read
+    assert!(bundle
+        .set_security_filter("no_such_column = 1")
+        .await
+        .is_err());
+
+    // Later operations only see the allowed rows, and can't bring the others back
+    bundle.filter(r#""Index" <= 20"#, vec![]).await?;
+    assert_eq!(10, bundle.num_rows().await?);
+    bundle.commit("Hide deleted rows").await?;
+
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(
+        vec!["deleted = false".to_string()],
+        reopened.security_filters()
+    );
+    for bundle in [reopened.clone(), bundle.bundle.clone()] {
+        for batches in [
+            bundle.sql("SELECT * FROM bundle", vec![]).await?,
+            bundle
+                .raw_sql("SELECT * FROM bundle WHERE deleted OR NOT deleted", vec![])
+                .await?,
+            bundle
+                .sql("SELECT * FROM bundle WHERE deleted = true", vec![])
+                .await?,
+        ] {
+            for batch in &batches {
+                let deleted = batch.column_by_name("deleted").unwrap();
+                let deleted = deleted
+                    .as_any()
+                    .downcast_ref::<arrow::array::BooleanArray>()
+                    .unwrap();
+                assert!(deleted.iter().all(|d| d == Some(false)));
+            }
+        }
+    }
+    assert_eq!(10, reopened.num_rows().await?);
+
+    // The raw pack and block tables in the session context aren't filtered
+    let err = reopened.query_ctx().err().unwrap().to_string();
+    assert!(err.contains("has a security filter"), "{}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_security_filter_later_operations() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    let mut bundle = bundle
+        .select(
+            r#"SELECT *, "Index" % 2 = 0 AS deleted FROM bundle"#,
+            vec![],
+        )
+        .await?;
+    bundle.set_security_filter("deleted = false").await?;
+
+    // Moving the column away and re-creating it would let the hidden rows through
+    let err = bundle
+        .rename_column("deleted", "x")
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("security filter"), "{}", err);
+    let err = bundle
+        .add_column("deleted", "false")
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("security filter"), "{}", err);
+    let err = bundle
+        .select("SELECT count(*) AS n, false AS deleted FROM bundle", vec![])
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("security filter"), "{}", err);
+
+    // Counting in a later select only sees the allowed rows
+    let bundle = bundle
+        .select("SELECT count(*) AS n FROM bundle", vec![])
+        .await?;
+    let batches = bundle.bundle.sql("SELECT n FROM bundle", vec![]).await?;
+    let n = batches[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<arrow::array::Int64Array>()
+        .unwrap()
+        .value(0);
+    assert_eq!(50, n);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_security_filter_export_changes() -> Result<(), BundlebaseError> {
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    let mut bundle = bundle
+        .select(
+            r#"SELECT *, "Index" % 2 = 0 AS deleted FROM bundle"#,
+            vec![],
+        )
+        .await?;
+    bundle.commit("Attach customers").await?;
+    let since = bundle.history()[0].id.clone();
+    assert!(bundle.bundle.query_ctx().is_ok());
+
+    bundle.set_security_filter("deleted = false").await?;
+    bundle.commit("Hide deleted rows").await?;
+
+    // The hidden rows aren't exported as removed
+    let dest = random_memory_url();
+    let export = bundle.export_changes(&since, dest.as_str(), true).await?;
+    assert_eq!(0, export.added_rows);
+    assert_eq!(Some(0), export.removed_rows);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filter_timestamp_literal() -> Result<(), BundlebaseError> {
+    let path = format!("{}/readings.csv", random_memory_url());
+    ObjectStoreFile::from_url(&Url::parse(&path)?, BundleConfig::default().into())?
+        .write(bytes::Bytes::from(
+            "ts,reading\n2023-12-31T23:30:00,1\n2024-01-02T08:15:00,2\n",
+        ))
+        .await?;
+
+    let mut config = BundleConfig::new();
+    config.set(TIME_ZONE, "Europe/Berlin", None);
+    let mut bundle =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+    bundle.attach(&path).await?;
+
+    // Timestamps without a time zone are compared as written, whatever the session time zone
+    bundle.filter("ts > '2024-01-01'", vec![]).await?;
+    assert_eq!(1, bundle.num_rows().await?);
+
+    let batches = bundle
+        .bundle()
+        .sql("SELECT arrow_typeof(now()) AS now_type", vec![])
+        .await?;
+    let formatted = arrow::util::pretty::pretty_format_batches(&batches)?.to_string();
+    assert!(formatted.contains("Europe/Berlin"), "{}", formatted);
+
+    Ok(())
+}
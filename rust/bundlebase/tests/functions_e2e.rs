@@ -1,10 +1,11 @@
-use arrow::array::record_batch;
-use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::array::{record_batch, AsArray, Int64Array};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
 use bundlebase;
 use bundlebase::bundle::BundleFacade;
-use bundlebase::functions::{FunctionSignature, StaticImpl};
-use bundlebase::test_utils::random_memory_url;
+use bundlebase::functions::{FunctionRegistry, FunctionSignature, StaticImpl};
+use bundlebase::test_utils::{random_memory_url, test_datafile};
 use bundlebase::BundlebaseError;
+use datafusion::logical_expr::{create_udf, ColumnarValue, ScalarUDF, Volatility};
 use std::sync::Arc;
 
 mod common;
@@ -332,3 +333,108 @@ async fn test_multiple_function_definitions() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_create_and_open_with_functions() -> Result<(), BundlebaseError> {
+    // An embedder-provided function, registered up front rather than via define_function
+    let mut functions = FunctionRegistry::new();
+    functions.register(FunctionSignature::new(
+        "my_catalog",
+        SchemaRef::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("table_name", DataType::Utf8, true),
+        ])),
+    ))?;
+    functions.set_impl(
+        "my_catalog",
+        Arc::new(StaticImpl::new(
+            vec![record_batch!(
+                ("id", Int64, [1_i64, 2_i64]),
+                ("table_name", Utf8, ["orders", "customers"])
+            )?],
+            "v1".to_string(),
+        )),
+    )?;
+
+    let url = random_memory_url();
+    let mut bundle =
+        bundlebase::BundleBuilder::create_with_functions(url.as_str(), None, &functions).await?;
+    bundle.attach("function://my_catalog").await?;
+    assert_eq!(2, bundle.num_rows().await?);
+    bundle.commit("Catalog tables").await?;
+
+    // The implementation isn't stored in the bundle, so it has to be provided again on open
+    let err = bundlebase::Bundle::open(url.as_str(), None)
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("my_catalog"), "{}", err);
+
+    let reopened = bundlebase::Bundle::open_with_functions(url.as_str(), None, &functions).await?;
+    assert_eq!(2, reopened.num_rows().await?);
+
+    Ok(())
+}
+
+fn double_udf() -> ScalarUDF {
+    create_udf(
+        "double_it",
+        vec![DataType::Int64],
+        DataType::Int64,
+        Volatility::Immutable,
+        Arc::new(|args: &[ColumnarValue]| {
+            let arrays = ColumnarValue::values_to_arrays(args)?;
+            let doubled: Int64Array = arrays[0]
+                .as_primitive::<Int64Type>()
+                .iter()
+                .map(|value| value.map(|value| value * 2))
+                .collect();
+            Ok(ColumnarValue::Array(Arc::new(doubled)))
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_register_udf() -> Result<(), BundlebaseError> {
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.register_udf(double_udf()).await?;
+    bundle.filter(r#"double_it("Index") <= 20"#, vec![]).await?;
+    let mut bundle = bundle
+        .select(
+            r#"SELECT "Index", double_it("Index") AS doubled FROM bundle"#,
+            vec![],
+        )
+        .await?;
+    assert_eq!(10, bundle.num_rows().await?);
+    bundle.commit("Doubled").await?;
+
+    // The UDF isn't saved in the bundle, so it has to be registered again on open
+    let err = bundlebase::Bundle::open(url.as_str(), None)
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string()
+            .contains("UDF 'double_it' is not registered"),
+        "{}",
+        err
+    );
+
+    let mut functions = FunctionRegistry::new();
+    functions.register_udf(double_udf());
+    let reopened = bundlebase::Bundle::open_with_functions(url.as_str(), None, &functions).await?;
+    assert_eq!(10, reopened.num_rows().await?);
+    let batches = reopened
+        .sql(
+            r#"SELECT doubled, double_it(doubled) AS quadrupled FROM bundle WHERE "Index" = 7"#,
+            vec![],
+        )
+        .await?;
+    let doubled = batches[0].column(0).as_primitive::<Int64Type>().value(0);
+    let quadrupled = batches[0].column(1).as_primitive::<Int64Type>().value(0);
+    assert_eq!((14, 28), (doubled, quadrupled));
+
+    Ok(())
+}
@@ -1,8 +1,11 @@
 use arrow::record_batch::RecordBatch;
-use bundlebase::bundle::BundleFacade;
-use bundlebase::test_utils::{random_memory_dir, test_datafile};
-use bundlebase::{assert_regexp, Bundle, BundlebaseError, Operation};
+use bundlebase::bundle::{parse_command, BundleFacade};
+use bundlebase::bundle_config::{BLOCK_STATS, INDEX_CONCURRENCY};
+use bundlebase::io::ObjectStoreFile;
+use bundlebase::test_utils::{random_memory_dir, random_memory_url, test_datafile};
+use bundlebase::{assert_regexp, AnyOperation, Bundle, BundleConfig, BundlebaseError, Operation};
 use datafusion::common::ScalarValue;
+use url::Url;
 
 mod common;
 
@@ -175,6 +178,120 @@ async fn test_select_with_indexed_column_in_list() -> Result<(), BundlebaseError
     Ok(())
 }
 
+#[tokio::test]
+async fn test_drop_index() -> Result<(), BundlebaseError> {
+    common::enable_logging();
+    let data_dir = random_memory_dir();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.index("Email").await?;
+    bundle.commit("Created index on Email").await?;
+
+    parse_command("DROP INDEX Email")?
+        .execute(&mut bundle)
+        .await?;
+    bundle.commit("Dropped index on Email").await?;
+
+    // The drop is part of the history, so a reopened bundle no longer uses the index
+    let mut reopened = Bundle::open(data_dir.url().as_str(), None)
+        .await?
+        .extend(None)?;
+    reopened
+        .filter("Email = 'elizabethbarr@ewing.com'", vec![])
+        .await?;
+    assert_eq!(1, reopened.num_rows().await?);
+    let explain = reopened.bundle().explain().await?;
+    assert!(!explain.contains("RowIdOffsetDataSource"), "{}", explain);
+    assert!(explain.contains("file_groups="), "{}", explain);
+
+    let err = reopened.drop_index("Email").await.err().unwrap();
+    assert_eq!("No index found for column 'Email'", err.to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_composite_index() -> Result<(), BundlebaseError> {
+    common::enable_logging();
+    let files = random_memory_url();
+    // Without block stats, so it's the index that keeps the other file from being read
+    let mut config = BundleConfig::new();
+    config.set(BLOCK_STATS, "false", None);
+    let mut bundle =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+
+    // Two files of 5 tenants with 10 days each. The index lists its columns in the opposite
+    // order to the files.
+    for (name, tenants) in [("first.csv", 1..=5), ("second.csv", 6..=10)] {
+        let mut csv = "day,tenant_id,value\n".to_string();
+        for tenant in tenants {
+            for day in 1..=10 {
+                csv.push_str(&format!("{},{},{}\n", day, tenant, tenant * 100 + day));
+            }
+        }
+        let url = format!("{}/{}", files, name);
+        ObjectStoreFile::from_url(&Url::parse(&url)?, BundleConfig::default().into())?
+            .write(bytes::Bytes::from(csv))
+            .await?;
+        bundle.attach(&url).await?;
+    }
+    bundle.index_composite(&["tenant_id", "day"]).await?;
+    bundle.commit("Indexed tenant and day").await?;
+
+    let ops: Vec<String> = bundle
+        .bundle()
+        .operations()
+        .iter()
+        .map(|op| op.describe())
+        .collect();
+    assert!(
+        ops.contains(&"CREATE INDEX on tenant_id, day".to_string()),
+        "{:?}",
+        ops
+    );
+
+    // The whole key reads the one matching row, and nothing from the other file
+    let rs = bundle
+        .select(
+            "select value from bundle where tenant_id = 7 and day = 3",
+            vec![],
+        )
+        .await?;
+    assert_eq!(1, rs.num_rows().await?);
+    let explain = rs.bundle.explain().await?;
+    assert!(explain.contains("first.csv, rows=0,"), "{}", explain);
+    assert!(explain.contains("second.csv, rows=1,"), "{}", explain);
+
+    // A prefix of the key reads just that tenant's rows
+    let rs = bundle
+        .select("select value from bundle where tenant_id = 7", vec![])
+        .await?;
+    assert_eq!(10, rs.num_rows().await?);
+    let explain = rs.bundle.explain().await?;
+    assert!(explain.contains("first.csv, rows=0,"), "{}", explain);
+    assert!(explain.contains("second.csv, rows=10,"), "{}", explain);
+
+    // Without the leading column the index can't be used
+    let rs = bundle
+        .select("select value from bundle where day = 3", vec![])
+        .await?;
+    assert_eq!(10, rs.num_rows().await?);
+    let explain = rs.bundle.explain().await?;
+    assert!(!explain.contains("RowIdOffsetDataSource"), "{}", explain);
+
+    // The index is dropped by its columns, not by any one of them
+    let err = bundle.drop_index("tenant_id").await.err().unwrap();
+    assert_eq!("No index found for column 'tenant_id'", err.to_string());
+    bundle.drop_index_composite(&["tenant_id", "day"]).await?;
+    let rs = bundle
+        .select("select value from bundle where tenant_id = 7", vec![])
+        .await?;
+    let explain = rs.bundle.explain().await?;
+    assert!(!explain.contains("RowIdOffsetDataSource"), "{}", explain);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_select_without_index_falls_back() -> Result<(), BundlebaseError> {
     common::enable_logging();
@@ -271,3 +388,95 @@ async fn test_index_selectivity() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+/// Reindexes a bundle whose `id` and `city` indexes are each missing three blocks, so both
+/// columns are built at once. Returns each new index file's contents in order.
+async fn reindex_with_concurrency(
+    url: &str,
+    concurrency: &str,
+) -> Result<Vec<bytes::Bytes>, BundlebaseError> {
+    let mut config = BundleConfig::new();
+    config.set(INDEX_CONCURRENCY, concurrency, None);
+    let mut bundle = Bundle::open(url, Some(config)).await?.extend(None)?;
+    bundle.reindex().await?;
+
+    let index_ops = bundle
+        .status()
+        .changes()
+        .last()
+        .unwrap()
+        .operations
+        .iter()
+        .filter_map(|op| match op {
+            AnyOperation::IndexBlocks(op) => Some(op.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(2, index_ops.len());
+    assert!(index_ops.iter().all(|op| op.blocks.len() == 3));
+
+    // Each id is in a different block, and the index must only return rows from that block
+    for id in [7, 57, 107, 157] {
+        let rs = bundle
+            .select(
+                "select * from bundle where id = $1",
+                vec![ScalarValue::Int64(Some(id))],
+            )
+            .await?;
+        let batches = rs.dataframe().await?.as_ref().clone().collect().await?;
+        assert_eq!(1, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+    }
+
+    let mut contents = Vec::new();
+    for op in index_ops {
+        contents.push(
+            bundle
+                .data_dir()
+                .file(&op.path)?
+                .read_bytes()
+                .await?
+                .unwrap(),
+        );
+    }
+    Ok(contents)
+}
+
+#[tokio::test]
+async fn test_concurrent_reindex_matches_serial() -> Result<(), BundlebaseError> {
+    let source_dir = random_memory_dir();
+    for part in 0..4 {
+        let mut csv = String::from("id,city\n");
+        for row in 0..50 {
+            csv.push_str(&format!("{},city_{}\n", part * 50 + row, row % 7));
+        }
+        source_dir
+            .file(&format!("part-{}.csv", part))?
+            .write(bytes::Bytes::from(csv))
+            .await?;
+    }
+
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle
+        .attach(source_dir.file("part-0.csv")?.url().as_str())
+        .await?;
+    bundle.index("id").await?;
+    bundle.index("city").await?;
+    bundle.commit("Indexed first part").await?;
+    for part in 1..4 {
+        bundle
+            .attach(
+                source_dir
+                    .file(&format!("part-{}.csv", part))?
+                    .url()
+                    .as_str(),
+            )
+            .await?;
+    }
+    bundle.commit("Attached remaining parts").await?;
+
+    let serial = reindex_with_concurrency(url.as_str(), "1").await?;
+    let concurrent = reindex_with_concurrency(url.as_str(), "8").await?;
+    assert_eq!(serial, concurrent);
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use bundlebase;
 use bundlebase::bundle::{BundleFacade, JoinTypeOption};
+use bundlebase::bundle_config::AUTO_INDEX_JOIN_KEYS;
+use bundlebase::io::ObjectStoreFile;
 use bundlebase::test_utils::{field_names, random_memory_url, test_datafile};
-use bundlebase::BundlebaseError;
+use bundlebase::{Bundle, BundleConfig, BundlebaseError, Operation};
+use url::Url;
 
 mod common;
 
@@ -93,6 +96,92 @@ async fn test_join_appending() -> Result<(), BundlebaseError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_join_indexes_join_keys() -> Result<(), BundlebaseError> {
+    // A key named differently from the bundle's, so the joined bundle can be filtered on it
+    let regions = format!("{}/regions.csv", random_memory_url());
+    ObjectStoreFile::from_url(&Url::parse(&regions)?, BundleConfig::default().into())?
+        .write(bytes::Bytes::from(
+            "Nation,Sales Region\nChile,South America\nDjibouti,Africa\n",
+        ))
+        .await?;
+
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle
+        .join(
+            "regions",
+            &regions,
+            r#"$base."Country" = regions."Nation""#,
+            JoinTypeOption::Inner,
+        )
+        .await?;
+
+    let ops: Vec<String> = bundle
+        .bundle()
+        .operations()
+        .iter()
+        .map(|op| op.describe())
+        .collect();
+    assert!(
+        ops.contains(&"CREATE INDEX on Country".to_string()),
+        "{:?}",
+        ops
+    );
+    assert!(
+        ops.iter().any(|op| op.starts_with("INDEX BLOCKS")),
+        "{:?}",
+        ops
+    );
+
+    // Filtering on the key reads only the matching customers through the index
+    bundle.filter("\"Country\" = 'Chile'", vec![]).await?;
+    let explain = bundle.bundle().explain().await?;
+    assert!(
+        explain.contains("customers-0-100.csv, rows=1,"),
+        "{}",
+        explain
+    );
+
+    // Joining again on the same key doesn't index it twice
+    bundle
+        .join(
+            "more_regions",
+            &regions,
+            r#"$base."Country" = more_regions."Nation""#,
+            JoinTypeOption::Left,
+        )
+        .await?;
+    let creates = bundle
+        .bundle()
+        .operations()
+        .iter()
+        .filter(|op| op.describe().starts_with("CREATE INDEX"))
+        .count();
+    assert_eq!(1, creates);
+
+    let mut config = BundleConfig::new();
+    config.set(AUTO_INDEX_JOIN_KEYS, "false", None);
+    let mut bundle =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle
+        .join(
+            "regions",
+            test_datafile("sales-regions.csv"),
+            r#"$base."Country" = regions."Country""#,
+            JoinTypeOption::Inner,
+        )
+        .await?;
+    assert!(!bundle
+        .bundle()
+        .operations()
+        .iter()
+        .any(|op| op.describe().starts_with("CREATE INDEX")));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_join_with_left_join_type() -> Result<(), BundlebaseError> {
     let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
@@ -117,3 +206,183 @@ async fn test_join_with_left_join_type() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_attach_bundle() -> Result<(), BundlebaseError> {
+    let regions_dir = tempfile::TempDir::new()?;
+    let regions_path = regions_dir.path().to_str().unwrap();
+    let mut regions = bundlebase::BundleBuilder::create(regions_path, None).await?;
+    regions.attach(test_datafile("sales-regions.csv")).await?;
+    regions.commit("Regions").await?;
+
+    let customers_url = random_memory_url().to_string();
+    let mut customers = bundlebase::BundleBuilder::create(&customers_url, None).await?;
+    customers
+        .attach(test_datafile("customers-0-100.csv"))
+        .await?;
+    customers.attach_bundle("regions", regions_path).await?;
+    let mut joined = customers
+        .select(
+            r#"select c."Customer Id", r."Sales Region" from bundle c join regions r on c."Country" = r."Country""#,
+            vec![],
+        )
+        .await?;
+    assert_eq!(99, joined.num_rows().await?);
+    joined.commit("Customers with regions").await?;
+
+    // The reference is re-linked on open, and reads the referenced bundle's latest commit
+    let mut regions = Bundle::open(regions_path, None).await?.extend(None)?;
+    regions.attach(test_datafile("sales-regions-2.csv")).await?;
+    regions.commit("More regions").await?;
+    let reopened = Bundle::open(&customers_url, None).await?;
+    assert_eq!(
+        vec![("regions".to_string(), regions.url().to_string())],
+        reopened.attached_bundles().into_iter().collect::<Vec<_>>()
+    );
+    assert_eq!(100, reopened.num_rows().await?);
+
+    // A bundle attached as regions cannot attach the bundle that references it
+    let err = Bundle::open(regions_path, None)
+        .await?
+        .extend(None)?
+        .attach_bundle("customers", &customers_url)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("would create a cycle"), "{}", err);
+
+    // Queries fail clearly once the referenced bundle is gone
+    let mut unselected =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    unselected
+        .attach(test_datafile("customers-0-100.csv"))
+        .await?;
+    unselected.attach_bundle("regions", regions_path).await?;
+    unselected.commit("Reference regions").await?;
+    drop(regions_dir);
+    let err = Bundle::open(unselected.url().as_str(), None)
+        .await?
+        .sql("select count(*) from regions", vec![])
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("is unavailable"), "{}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attach_bundle_cycle() -> Result<(), BundlebaseError> {
+    let dir = tempfile::TempDir::new()?;
+    let a_path = dir.path().join("a");
+    let b_path = dir.path().join("b");
+    let a_url = a_path.to_str().unwrap();
+    let b_url = b_path.to_str().unwrap();
+
+    let mut a = bundlebase::BundleBuilder::create(a_url, None).await?;
+    a.attach(test_datafile("sales-regions.csv")).await?;
+    a.commit("Regions").await?;
+
+    let mut b = bundlebase::BundleBuilder::create(b_url, None).await?;
+    b.attach(test_datafile("customers-0-100.csv")).await?;
+    b.attach_bundle("a", a_url).await?;
+    let mut b = b
+        .select(
+            r#"select c."Customer Id", c."Country" from bundle c join a r on c."Country" = r."Country""#,
+            vec![],
+        )
+        .await?;
+    b.commit("Customers in a region").await?;
+
+    // attach_bundle refuses cycles, so build one by replacing a with a bundle reading b
+    let replacement_path = dir.path().join("replacement");
+    let mut replacement =
+        bundlebase::BundleBuilder::create(replacement_path.to_str().unwrap(), None).await?;
+    replacement
+        .attach(test_datafile("sales-regions.csv"))
+        .await?;
+    replacement.attach_bundle("b", b_url).await?;
+    let mut replacement = replacement
+        .select(
+            r#"select r.* from bundle r where r."Country" in (select "Country" from b)"#,
+            vec![],
+        )
+        .await?;
+    replacement.commit("Regions with customers").await?;
+    std::fs::remove_dir_all(&a_path)?;
+    std::fs::rename(&replacement_path, &a_path)?;
+
+    let err = Bundle::open(a_url, None)
+        .await?
+        .num_rows()
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.contains("Attached bundles reference each other in a cycle"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_union() -> Result<(), BundlebaseError> {
+    let february_url = random_memory_url().to_string();
+    let mut february = bundlebase::BundleBuilder::create(&february_url, None).await?;
+    february
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    february.commit("February customers").await?;
+
+    let january_url = random_memory_url().to_string();
+    let mut january = bundlebase::BundleBuilder::create(&january_url, None).await?;
+    january.attach(test_datafile("customers-0-100.csv")).await?;
+    january.union(&february_url).await?;
+    assert_eq!(150, january.num_rows().await?);
+    assert_eq!(
+        format!("UNION: {}", february_url),
+        january.bundle.operations().last().unwrap().describe()
+    );
+
+    // Operations after the union apply to the other bundle's rows too
+    january.filter("\"Index\" > 90", vec![]).await?;
+    assert_eq!(60, january.num_rows().await?);
+    january.commit("Both months").await?;
+
+    // The other bundle is read again on open, so its later commits show up
+    let mut february = Bundle::open(&february_url, None).await?.extend(None)?;
+    february.filter("\"Index\" > 140", vec![]).await?;
+    february.commit("Late February only").await?;
+    let reopened = Bundle::open(&january_url, None).await?;
+    assert_eq!(20, reopened.num_rows().await?);
+
+    // Bundles with other columns cannot be unioned
+    let mut trimmed = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    trimmed
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    trimmed.remove_column("Email").await?;
+    trimmed.commit("Without emails").await?;
+    let err = Bundle::open(&january_url, None)
+        .await?
+        .extend(None)?
+        .union(trimmed.url().as_str())
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        format!(
+            "Cannot union bundle {}: it has no column 'Email'",
+            trimmed.url()
+        ),
+        err
+    );
+
+    Ok(())
+}
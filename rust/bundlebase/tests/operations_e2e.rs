@@ -1,10 +1,20 @@
-use arrow::array::record_batch;
-use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::array::{record_batch, AsArray, StringArray, StringViewArray};
+use arrow::datatypes::{DataType, Field, Float64Type, Int64Type, Schema, SchemaRef};
 use bundlebase;
-use bundlebase::bundle::BundleFacade;
+use bundlebase::bundle::{BundleFacade, ORIGINAL_NAME_METADATA};
+use bundlebase::bundle_config::{
+    COLUMN_ENCRYPTION_KEY, MEMORY_LIMIT_MB, NORMALIZE_COLUMN_NAMES, SPILL_DIR,
+};
 use bundlebase::functions::{FunctionSignature, StaticImpl};
-use bundlebase::test_utils::{field_names, random_memory_url, test_datafile};
-use bundlebase::BundlebaseError;
+use bundlebase::test_utils::{
+    assert_vec_regexp, describe_ops, field_names, random_memory_dir, random_memory_file,
+    random_memory_url, test_datafile,
+};
+use bundlebase::{
+    AggFunc, AnyOperation, Bundle, BundleConfig, BundlebaseError, MaskStrategy, Operation,
+    PivotAggregate, SortDirection,
+};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use url::Url;
 
@@ -169,6 +179,98 @@ async fn test_rename_case_sensitive() -> Result<(), BundlebaseError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_normalize_column_names() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle
+        .set_config(NORMALIZE_COLUMN_NAMES, "true", None)
+        .await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+
+    let expected = vec![
+        "index",
+        "customer_id",
+        "first_name",
+        "last_name",
+        "company",
+        "city",
+        "country",
+        "phone_1",
+        "phone_2",
+        "email",
+        "subscription_date",
+        "website",
+    ];
+    assert_eq!(expected, field_names(&bundle.schema().await?));
+
+    let schema = bundle.schema().await?;
+    assert_eq!(
+        Some(&"First Name".to_string()),
+        schema
+            .field_with_name("first_name")?
+            .metadata()
+            .get(ORIGINAL_NAME_METADATA)
+    );
+
+    // Files attached later with the same headers line up with the normalized names
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await?;
+    assert_eq!(expected, field_names(&bundle.schema().await?));
+    assert_eq!(150, bundle.num_rows().await?);
+
+    let selected = bundle
+        .select("SELECT first_name FROM bundle WHERE index = 1", vec![])
+        .await?;
+    let batches = selected
+        .dataframe()
+        .await?
+        .as_ref()
+        .clone()
+        .collect()
+        .await?;
+    let first_names = batches[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringViewArray>()
+        .map(|a| a.value(0).to_string())
+        .or_else(|| {
+            batches[0]
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .map(|a| a.value(0).to_string())
+        });
+    assert_eq!(Some("Sheryl".to_string()), first_names);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_column_alias() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+
+    // Normalization is off by default
+    assert!(common::has_column(&bundle.schema().await?, "First Name"));
+
+    bundle.column_alias("First Name", "given_name").await?;
+    let schema = bundle.schema().await?;
+    assert!(!common::has_column(&schema, "First Name"));
+    assert_eq!(
+        Some(&"First Name".to_string()),
+        schema
+            .field_with_name("given_name")?
+            .metadata()
+            .get(ORIGINAL_NAME_METADATA)
+    );
+
+    assert!(bundle.column_alias("First Name", "other").await.is_err());
+    assert!(bundle.column_alias("Email", "given_name").await.is_err());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_function_source() -> Result<(), BundlebaseError> {
     let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
@@ -261,3 +363,1006 @@ async fn test_attach_invalid_function_error() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_merge() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.commit("Initial customers").await?;
+
+    // Customer 1 moved cities and customer 101 is new
+    let updates = random_memory_file("customers-update.csv");
+    updates
+        .write(bytes::Bytes::from(
+            "Index,Customer Id,First Name,Last Name,Company,City,Country,Phone 1,Phone 2,Email,Subscription Date,Website
+1,DD37Cf93aecA6Dc,Sheryl,Baxter,Rasmussen Group,New Leonard,Chile,229.077.5154,397.884.0519x718,zunigavanessa@smith.info,2020-08-24,http://www.stephenson.com/
+101,9bF3a2D1e5A8c7B,Olivia,Reyes,Armstrong-Hayden,North Emery,Marshall Islands,001-541-867-5309x101,+(305)555-0134,olivia.reyes@armstrong.com,2022-07-19,http://www.armstrong-hayden.com/
+",
+        ))
+        .await?;
+
+    bundle.merge(updates.url().as_str(), vec!["Index"]).await?;
+    assert_eq!(101, bundle.num_rows().await?);
+
+    let count = |sql: &'static str| {
+        let bundle = bundle.clone();
+        async move { bundle.select(sql, vec![]).await?.num_rows().await }
+    };
+    assert_eq!(1, count("SELECT * FROM bundle WHERE \"Index\" = 1").await?);
+    assert_eq!(
+        1,
+        count("SELECT * FROM bundle WHERE \"City\" = 'New Leonard'").await?
+    );
+    assert_eq!(
+        0,
+        count("SELECT * FROM bundle WHERE \"City\" = 'East Leonard'").await?
+    );
+    assert_eq!(
+        1,
+        count("SELECT * FROM bundle WHERE \"Index\" = 101").await?
+    );
+
+    bundle.commit("Merge updates").await?;
+
+    let reopened = Bundle::open(data_dir.as_str(), None).await?;
+    assert_eq!(101, reopened.num_rows().await?);
+    assert_eq!(
+        "MERGE ON Index",
+        reopened.operations().last().unwrap().describe()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merge_null_and_duplicate_keys() -> Result<(), BundlebaseError> {
+    let csv = |name: &str, contents: &'static str| {
+        let file = random_memory_file(name);
+        async move {
+            file.write(bytes::Bytes::from(contents)).await?;
+            Ok::<_, BundlebaseError>(file.url().to_string())
+        }
+    };
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle
+        .attach(&csv("base.csv", "id,name\n1,a\n,b\n").await?)
+        .await?;
+
+    // Existing rows with a null key are never replaced
+    bundle
+        .merge(&csv("update.csv", "id,name\n1,x\n2,y\n").await?, vec!["id"])
+        .await?;
+    let batches = bundle
+        .bundle()
+        .sql("SELECT name FROM bundle ORDER BY name", vec![])
+        .await?;
+    let mut names = Vec::new();
+    for batch in &batches {
+        let column = arrow::compute::cast(batch.column(0), &DataType::Utf8)?;
+        names.extend(column.as_string::<i32>().iter().flatten().map(String::from));
+    }
+    assert_eq!(vec!["b", "x", "y"], names);
+
+    // Merged rows need a key without nulls
+    let err = bundle
+        .merge(&csv("nulls.csv", "id,name\n3,z\n,w\n").await?, vec!["id"])
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.contains("Cannot merge on (id): 1 merged row(s) have a null key"),
+        "{}",
+        err
+    );
+
+    // Which of two merged rows with the same key wins would be ambiguous
+    let err = bundle
+        .merge(&csv("dupes.csv", "id,name\n2,z\n2,w\n").await?, vec!["id"])
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.contains("Cannot merge on (id): 1 key value(s) appear in more than one merged row"),
+        "{}",
+        err
+    );
+    assert_eq!(3, bundle.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merge_requires_existing_data() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+
+    let result = bundle
+        .merge(test_datafile("customers-0-100.csv"), vec!["Index"])
+        .await;
+    assert!(result.is_err());
+    assert!(result
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("requires existing data"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_declare_key() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle.declare_key(vec!["Index"], true).await?;
+    bundle.set_nullable("Email", false).await?;
+    bundle.commit("Customers keyed by index").await?;
+
+    let reopened = Bundle::open(data_dir.as_str(), None).await?;
+    assert_eq!(vec!["Index".to_string()], reopened.key_columns());
+
+    let schema = reopened.schema().await?;
+    let index = schema.field_with_name("Index")?;
+    assert!(!index.is_nullable());
+    assert_eq!(
+        Some(&"1".to_string()),
+        index.metadata().get(bundlebase::bundle::KEY_FIELD_METADATA)
+    );
+    assert!(!schema.field_with_name("Email")?.is_nullable());
+    assert!(schema.field_with_name("City")?.is_nullable());
+    assert!(schema
+        .field_with_name("City")?
+        .metadata()
+        .get(bundlebase::bundle::KEY_FIELD_METADATA)
+        .is_none());
+
+    // The dataframe queries run against carries the same nullability
+    let df = reopened.dataframe().await?;
+    assert!(!df
+        .schema()
+        .field_with_unqualified_name("Email")?
+        .is_nullable());
+    assert!(df
+        .schema()
+        .field_with_unqualified_name("City")?
+        .is_nullable());
+    let batches = df.as_ref().clone().limit(0, Some(5))?.collect().await?;
+    assert!(!batches[0].schema().field_with_name("Index")?.is_nullable());
+
+    // The declared key drives merge when no key columns are given
+    let updates = random_memory_file("customers-update.csv");
+    updates
+        .write(bytes::Bytes::from(
+            "Index,Customer Id,First Name,Last Name,Company,City,Country,Phone 1,Phone 2,Email,Subscription Date,Website
+1,DD37Cf93aecA6Dc,Sheryl,Baxter,Rasmussen Group,New Leonard,Chile,229.077.5154,397.884.0519x718,zunigavanessa@smith.info,2020-08-24,http://www.stephenson.com/
+",
+        ))
+        .await?;
+    let mut extended = reopened.extend(None)?;
+    extended.merge(updates.url().as_str(), vec![]).await?;
+    assert_eq!(100, extended.num_rows().await?);
+    assert_eq!(
+        "MERGE ON Index",
+        extended.operations().last().unwrap().describe()
+    );
+
+    // Renaming a key column keeps it in the key
+    extended.rename_column("Index", "Row").await?;
+    assert_eq!(vec!["Row".to_string()], extended.bundle.key_columns());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_declare_key_verify_unique() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+
+    let err = bundle
+        .declare_key(vec!["Country"], true)
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string()
+            .contains("Cannot declare key (Country): 13 key value(s) appear in more than one row"),
+        "{}",
+        err
+    );
+    assert!(bundle.bundle.key_columns().is_empty());
+
+    // Without verification the key is recorded as given
+    bundle.declare_key(vec!["Country"], false).await?;
+    assert_eq!(vec!["Country".to_string()], bundle.bundle.key_columns());
+
+    let err = bundle
+        .declare_key(vec!["Missing"], false)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!("Column 'Missing' not found in bundle", err.to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_nullable_with_nulls() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    data_dir
+        .file("people.csv")?
+        .write(bytes::Bytes::from("id,email\n1,ann@example.com\n2,\n3,\n"))
+        .await?;
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle
+        .attach(data_dir.file("people.csv")?.url().as_str())
+        .await?;
+
+    let err = bundle.set_nullable("email", false).await.err().unwrap();
+    assert_eq!(
+        "Column 'email' has 2 null values and cannot be declared not null",
+        err.to_string()
+    );
+    let err = bundle
+        .declare_key(vec!["email"], false)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        "Column 'email' has 2 null values and cannot be declared not null",
+        err.to_string()
+    );
+
+    bundle.set_nullable("id", false).await?;
+    assert!(!bundle
+        .dataframe()
+        .await?
+        .schema()
+        .field_with_unqualified_name("id")?
+        .is_nullable());
+    assert_eq!(3, bundle.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merge_without_key() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+
+    let err = bundle
+        .merge(test_datafile("customers-101-150.csv"), vec![])
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("declare_key"), "{}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mask_column() -> Result<(), BundlebaseError> {
+    let data_dir = random_memory_dir();
+    data_dir
+        .file("people.csv")?
+        .write(bytes::Bytes::from(
+            "id,email,ssn\n1,ann@example.com,111-22-3333\n2,bob@example.com,444-55-6666\n",
+        ))
+        .await?;
+    let mut config = BundleConfig::new();
+    config.set(COLUMN_ENCRYPTION_KEY, "correct horse", None);
+
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), Some(config.clone())).await?;
+    bundle
+        .attach(data_dir.file("people.csv")?.url().as_str())
+        .await?;
+    bundle.mask_column("email", MaskStrategy::Hash).await?;
+    bundle.mask_column("ssn", MaskStrategy::Encrypt).await?;
+    bundle.commit("Mask PII").await?;
+
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: maskColumn"));
+    assert!(!contents.contains("correct horse"));
+
+    // Each mask rewrote the block into the bundle, and the copy read now shows neither column
+    let rewritten = |op: &AnyOperation| match op {
+        AnyOperation::MaskColumn(mask) => mask.blocks.iter().map(|b| b.file.clone()).collect(),
+        _ => vec![],
+    };
+    let files: Vec<String> = bundle
+        .bundle
+        .operations()
+        .iter()
+        .flat_map(rewritten)
+        .collect();
+    assert_eq!(2, files.len());
+    let contains = |bytes: &[u8], plain: &str| {
+        bytes
+            .windows(plain.len())
+            .any(|window| window == plain.as_bytes())
+    };
+    let bytes = bundle
+        .data_dir()
+        .file(&files[1])?
+        .read_bytes()
+        .await?
+        .unwrap();
+    for plain in ["ann@example.com", "111-22-3333", "444-55-6666"] {
+        assert!(!contains(&bytes, plain), "{} is readable", plain);
+    }
+
+    // Data attached later would bring the raw values back
+    let err = bundle
+        .attach(data_dir.file("people.csv")?.url().as_str())
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("its column 'email' is masked"),
+        "{}",
+        err
+    );
+    assert!(bundle
+        .set_config(COLUMN_ENCRYPTION_KEY, "correct horse", None)
+        .await
+        .is_err());
+
+    let read = |bundle: Bundle| async move {
+        let batches = bundle
+            .sql("SELECT email, ssn FROM bundle ORDER BY id", vec![])
+            .await?;
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches)?;
+        let strings = |index: usize| -> Result<Vec<Option<String>>, BundlebaseError> {
+            let column = arrow::compute::cast(batch.column(index), &DataType::Utf8)?;
+            Ok(column
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+                .iter()
+                .map(|value| value.map(str::to_string))
+                .collect())
+        };
+        Ok::<_, BundlebaseError>((strings(0)?, strings(1)?))
+    };
+    let hashed = vec![
+        Some(hex::encode(Sha256::digest("ann@example.com"))),
+        Some(hex::encode(Sha256::digest("bob@example.com"))),
+    ];
+
+    // Without the key the encrypted column is unreadable
+    let (emails, ssns) = read(Bundle::open(url.as_str(), None).await?).await?;
+    assert_eq!(hashed, emails);
+    assert_eq!(vec![None, None], ssns);
+
+    let mut wrong_key = BundleConfig::new();
+    wrong_key.set(COLUMN_ENCRYPTION_KEY, "battery staple", None);
+    let (_, ssns) = read(Bundle::open(url.as_str(), Some(wrong_key)).await?).await?;
+    assert_eq!(vec![None, None], ssns);
+
+    // With the key it is recovered, while the hashed column stays hashed
+    let (emails, ssns) = read(Bundle::open(url.as_str(), Some(config)).await?).await?;
+    assert_eq!(hashed, emails);
+    assert_eq!(
+        vec![
+            Some("111-22-3333".to_string()),
+            Some("444-55-6666".to_string())
+        ],
+        ssns
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unpivot_and_pivot() -> Result<(), BundlebaseError> {
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    let mut bundle = bundle
+        .select(
+            r#"SELECT "Index" AS id, "Index" * 2 AS doubled, "Index" * 3 AS tripled FROM bundle"#,
+            vec![],
+        )
+        .await?;
+    let wide = |bundle: Bundle| async move {
+        bundle
+            .sql(
+                "SELECT id, doubled, tripled FROM bundle ORDER BY id",
+                vec![],
+            )
+            .await
+    };
+    let expected = wide(bundle.bundle.clone()).await?;
+
+    bundle
+        .unpivot(vec!["id"], vec!["doubled", "tripled"], "metric", "amount")
+        .await?;
+    assert_eq!(
+        vec!["id", "metric", "amount"],
+        field_names(&bundle.schema().await?)
+    );
+    assert_eq!(200, bundle.num_rows().await?);
+    let batches = bundle
+        .bundle
+        .sql(
+            "SELECT metric, amount FROM bundle WHERE id = 7 ORDER BY metric",
+            vec![],
+        )
+        .await?;
+    assert_eq!(
+        vec![
+            "+---------+--------+",
+            "| metric  | amount |",
+            "+---------+--------+",
+            "| doubled | 14     |",
+            "| tripled | 21     |",
+            "+---------+--------+"
+        ],
+        arrow::util::pretty::pretty_format_batches(&batches)?
+            .to_string()
+            .lines()
+            .collect::<Vec<_>>()
+    );
+
+    bundle
+        .pivot(vec!["id"], "metric", "amount", PivotAggregate::Max)
+        .await?;
+    assert_eq!(
+        vec!["id", "doubled", "tripled"],
+        field_names(&bundle.schema().await?)
+    );
+    assert_eq!(100, bundle.num_rows().await?);
+    assert_eq!(expected, wide(bundle.bundle.clone()).await?);
+
+    // Both reshapes are replayed when the bundle is reopened
+    bundle.commit("Reshape").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: unpivot"));
+    assert!(contents.contains("type: pivot"));
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(expected, wide(reopened).await?);
+
+    // Pivoting on a column that is also in the index is rejected
+    assert!(bundle
+        .pivot(vec!["id"], "id", "doubled", PivotAggregate::Sum)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate() -> Result<(), BundlebaseError> {
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle
+        .aggregate(
+            &["Country"],
+            &[
+                ("*", AggFunc::Count, "customers"),
+                ("Index", AggFunc::Max, "last"),
+            ],
+        )
+        .await?;
+    assert_eq!(
+        vec!["Country", "customers", "last"],
+        field_names(&bundle.schema().await?)
+    );
+    assert_eq!(85, bundle.num_rows().await?);
+    assert_eq!(
+        "AGGREGATE: count(*) AS customers, max(Index) AS last BY Country",
+        bundle.bundle.operations().last().unwrap().describe()
+    );
+
+    let solomon = |bundle: Bundle| async move {
+        let batches = bundle
+            .sql("SELECT \"Country\", customers FROM bundle", vec![])
+            .await?;
+        let mut count = None;
+        for batch in &batches {
+            let countries = arrow::compute::cast(batch.column(0), &DataType::Utf8)?;
+            let countries = countries.as_string::<i32>();
+            count = count.or_else(|| {
+                (0..batch.num_rows())
+                    .find(|&i| countries.value(i) == "Solomon Islands")
+                    .map(|i| batch.column(1).as_primitive::<Int64Type>().value(i))
+            });
+        }
+        Ok::<_, BundlebaseError>(count)
+    };
+    assert_eq!(Some(4), solomon(bundle.bundle.clone()).await?);
+
+    // The aggregate is replayed when the bundle is reopened
+    bundle.commit("Count by country").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: aggregate"), "{}", contents);
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(85, reopened.num_rows().await?);
+    assert_eq!(Some(4), solomon(reopened).await?);
+
+    // A filter made before aggregating limits the rows that are grouped
+    let mut filtered =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    filtered
+        .attach(test_datafile("customers-0-100.csv"))
+        .await?;
+    filtered.filter("\"Index\" > 50", vec![]).await?;
+    filtered
+        .aggregate(&["Country"], &[("*", AggFunc::Count, "customers")])
+        .await?;
+    assert_eq!(47, filtered.num_rows().await?);
+    assert_eq!(Some(3), solomon(filtered.bundle.clone()).await?);
+
+    // Unknown columns, and aggregates other than count over all rows, are rejected
+    assert!(filtered
+        .aggregate(&["Country"], &[("missing", AggFunc::Sum, "total")])
+        .await
+        .is_err());
+    assert!(filtered
+        .aggregate(&[], &[("*", AggFunc::Sum, "total")])
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_order_by() -> Result<(), BundlebaseError> {
+    let file = random_memory_file("scores.csv");
+    file.write(bytes::Bytes::from(
+        "name,team,score\na,x,3\nb,y,\nc,x,1\nd,y,5\ne,x,2\n",
+    ))
+    .await?;
+
+    let names = |bundle: Bundle| async move {
+        let batches = bundle.sql("SELECT name FROM bundle", vec![]).await?;
+        let mut names = Vec::new();
+        for batch in &batches {
+            let column = arrow::compute::cast(batch.column(0), &DataType::Utf8)?;
+            names.extend(column.as_string::<i32>().iter().flatten().map(String::from));
+        }
+        Ok::<_, BundlebaseError>(names)
+    };
+
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(file.url().as_str()).await?;
+    bundle
+        .order_by(&[
+            ("team", SortDirection::Asc),
+            ("score", SortDirection::DescNullsLast),
+        ])
+        .await?;
+    assert_eq!(
+        vec!["a", "e", "c", "d", "b"],
+        names(bundle.bundle.clone()).await?
+    );
+    assert_vec_regexp(
+        vec![
+            "CREATE PACK \\w+",
+            "ATTACH: memory:///.*scores.csv",
+            "ORDER BY: team ASC, score DESC NULLS LAST",
+        ],
+        describe_ops(&bundle),
+    );
+
+    // The sort is replayed when the bundle is reopened
+    bundle.commit("Sort by team").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: orderBy"), "{}", contents);
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(vec!["a", "e", "c", "d", "b"], names(reopened).await?);
+
+    // Sorting again orders by the new keys
+    bundle
+        .order_by(&[("score", SortDirection::AscNullsFirst)])
+        .await?;
+    assert_eq!(
+        vec!["b", "c", "e", "a", "d"],
+        names(bundle.bundle.clone()).await?
+    );
+
+    assert!(bundle
+        .order_by(&[("missing", SortDirection::Asc)])
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_add_column() -> Result<(), BundlebaseError> {
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle
+        .add_column("full_name", "\"First Name\" || ' ' || \"Last Name\"")
+        .await?;
+    bundle.add_column("index_x10", "\"Index\" * 10").await?;
+
+    let schema = bundle.schema().await?;
+    assert_eq!(
+        vec!["full_name", "index_x10"],
+        field_names(&schema)[12..].to_vec()
+    );
+    assert_eq!(
+        &DataType::Int64,
+        schema.field_with_name("index_x10")?.data_type()
+    );
+    assert_eq!(
+        "ADD COLUMN: full_name = \"First Name\" || ' ' || \"Last Name\"",
+        bundle.bundle.operations()[2].describe()
+    );
+
+    let first = |bundle: Bundle| async move {
+        let batches = bundle
+            .sql(
+                "SELECT full_name, index_x10 FROM bundle WHERE \"Index\" = 1",
+                vec![],
+            )
+            .await?;
+        let full_name = arrow::compute::cast(batches[0].column(0), &DataType::Utf8)?;
+        Ok::<_, BundlebaseError>((
+            full_name.as_string::<i32>().value(0).to_string(),
+            batches[0].column(1).as_primitive::<Int64Type>().value(0),
+        ))
+    };
+    assert_eq!(
+        ("Sheryl Baxter".to_string(), 10),
+        first(bundle.bundle.clone()).await?
+    );
+
+    // The columns are computed again when the bundle is reopened
+    bundle.commit("Computed columns").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: addColumn"), "{}", contents);
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(14, reopened.schema().await?.fields().len());
+    assert_eq!(("Sheryl Baxter".to_string(), 10), first(reopened).await?);
+
+    // Names already in use and bad expressions are rejected
+    let err = bundle
+        .add_column("Country", "upper(\"Country\")")
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!("Column 'Country' already exists", err);
+    assert!(bundle
+        .add_column("doubled", "\"missing\" * 2")
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cast_column() -> Result<(), BundlebaseError> {
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(test_datafile("customers-0-100.csv")).await?;
+    bundle
+        .cast_column("Index", DataType::Utf8, false)
+        .await?
+        .cast_column("Index", DataType::Int64, false)
+        .await?;
+    let schema = bundle.schema().await?;
+    assert_eq!(
+        &DataType::Int64,
+        schema.field_with_name("Index")?.data_type()
+    );
+    assert_eq!("Index", schema.field(0).name());
+    assert_eq!(
+        "CAST COLUMN: Index TO Int64",
+        bundle.bundle.operations().last().unwrap().describe()
+    );
+    assert_eq!(100, bundle.num_rows().await?);
+
+    // The conversion is replayed when the bundle is reopened
+    bundle.commit("Typed index").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: castColumn"), "{}", contents);
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(
+        &DataType::Int64,
+        reopened
+            .schema()
+            .await?
+            .field_with_name("Index")?
+            .data_type()
+    );
+
+    // Values that can't be converted are an error, or null when casting safely
+    let file = random_memory_file("codes.csv");
+    file.write(bytes::Bytes::from("code\n1\n2\nabc\n4\n"))
+        .await?;
+    let mut codes = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    codes.attach(file.url().as_str()).await?;
+    let err = codes
+        .cast_column("code", DataType::Int64, false)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        "Cannot cast column 'code' to Int64: 'abc' is not a valid value",
+        err
+    );
+
+    codes.cast_column("code", DataType::Int64, true).await?;
+    let batches = codes
+        .bundle
+        .sql("SELECT sum(code), count(code) FROM bundle", vec![])
+        .await?;
+    assert_eq!(7, batches[0].column(0).as_primitive::<Int64Type>().value(0));
+    assert_eq!(3, batches[0].column(1).as_primitive::<Int64Type>().value(0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fill_null() -> Result<(), BundlebaseError> {
+    let file = random_memory_file("scores.csv");
+    file.write(bytes::Bytes::from(
+        "name,score,team\na,3,x\nb,,y\nc,1,\nd,,\n",
+    ))
+    .await?;
+
+    let nulls = |bundle: Bundle| async move {
+        let batches = bundle
+            .sql(
+                "SELECT count(*) - count(score), count(*) - count(team), sum(score) FROM bundle",
+                vec![],
+            )
+            .await?;
+        Ok::<_, BundlebaseError>(
+            (0..3)
+                .map(|i| batches[0].column(i).as_primitive::<Int64Type>().value(0))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(file.url().as_str()).await?;
+    assert_eq!(vec![2, 2, 4], nulls(bundle.bundle.clone()).await?);
+
+    bundle.fill_null("score", "10").await?;
+    bundle.fill_null("team", "none").await?;
+    assert_eq!(vec![0, 0, 24], nulls(bundle.bundle.clone()).await?);
+    assert_eq!(
+        "FILL NULL: team WITH 'none'",
+        bundle.bundle.operations().last().unwrap().describe()
+    );
+    assert_eq!(
+        vec!["name", "score", "team"],
+        field_names(&bundle.schema().await?)
+    );
+
+    // The nulls are filled again when the bundle is reopened
+    bundle.commit("Fill nulls").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: fillNull"), "{}", contents);
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(vec![0, 0, 24], nulls(reopened).await?);
+
+    // The value must be readable as the column's type
+    let err = bundle
+        .fill_null("score", "lots")
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.starts_with("Cannot fill nulls in column 'score' with 'lots': not a valid Int64"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_distinct() -> Result<(), BundlebaseError> {
+    // Two source files that overlap: b,2 is in both, and c is in the second twice
+    let first = random_memory_file("first.csv");
+    first
+        .write(bytes::Bytes::from("id,day\na,1\nb,2\n"))
+        .await?;
+    let second = random_memory_file("second.csv");
+    second
+        .write(bytes::Bytes::from("id,day\nb,2\nc,3\nc,3\nc,4\n"))
+        .await?;
+
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(first.url().as_str()).await?;
+    bundle.attach(second.url().as_str()).await?;
+    assert_eq!(6, bundle.num_rows().await?);
+
+    bundle.distinct(None).await?;
+    assert_eq!(4, bundle.num_rows().await?);
+
+    bundle.distinct(Some(&["id"])).await?;
+    assert_eq!(3, bundle.num_rows().await?);
+    assert_eq!(vec!["id", "day"], field_names(&bundle.schema().await?));
+    assert_eq!(
+        vec!["DISTINCT", "DISTINCT ON: id"],
+        bundle
+            .bundle
+            .operations()
+            .iter()
+            .rev()
+            .take(2)
+            .rev()
+            .map(|op| op.describe())
+            .collect::<Vec<_>>()
+    );
+
+    // The duplicates are removed again when the bundle is reopened
+    bundle.commit("Remove duplicates").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: distinct"), "{}", contents);
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(3, reopened.num_rows().await?);
+
+    let err = bundle
+        .distinct(Some(&["missing"]))
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("missing"), "{}", err);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_distinct_subset_across_blocks() -> Result<(), BundlebaseError> {
+    // Each key has a row in both blocks with a different day
+    let first = random_memory_file("first.csv");
+    first
+        .write(bytes::Bytes::from(
+            "id,day
+a,1
+b,2
+",
+        ))
+        .await?;
+    let second = random_memory_file("second.csv");
+    second
+        .write(bytes::Bytes::from(
+            "id,day
+b,3
+a,4
+c,5
+",
+        ))
+        .await?;
+
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(first.url().as_str()).await?;
+    bundle.attach(second.url().as_str()).await?;
+    bundle.distinct(Some(&["id"])).await?;
+
+    // One row is kept per key, taken whole from one of the rows sharing it
+    let batches = bundle
+        .bundle
+        .sql("SELECT id, day FROM bundle ORDER BY id", vec![])
+        .await?;
+    let ids = arrow::compute::cast(batches[0].column(0), &DataType::Utf8)?;
+    let kept: Vec<(String, i64)> = (0..batches[0].num_rows())
+        .map(|i| {
+            (
+                ids.as_string::<i32>().value(i).to_string(),
+                batches[0].column(1).as_primitive::<Int64Type>().value(i),
+            )
+        })
+        .collect();
+    assert_eq!(3, kept.len(), "{:?}", kept);
+    assert!([1, 4].contains(&kept[0].1), "{:?}", kept);
+    assert!([2, 3].contains(&kept[1].1), "{:?}", kept);
+    assert_eq!(("c".to_string(), 5), kept[2]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit() -> Result<(), BundlebaseError> {
+    let lowest = |bundle: Bundle| async move {
+        let batches = bundle.sql("SELECT min(salary) FROM bundle", vec![]).await?;
+        Ok::<_, BundlebaseError>(batches[0].column(0).as_primitive::<Float64Type>().value(0))
+    };
+
+    let url = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(url.as_str(), None).await?;
+    bundle.attach(&test_datafile("userdata.parquet")).await?;
+    bundle.filter("salary > 100000", vec![]).await?;
+    bundle.order_by(&[("salary", SortDirection::Desc)]).await?;
+    let tenth_highest = bundle
+        .bundle
+        .sql("SELECT salary FROM bundle LIMIT 1 OFFSET 9", vec![])
+        .await?[0]
+        .column(0)
+        .as_primitive::<Float64Type>()
+        .value(0);
+
+    bundle.limit(10).await?;
+    assert_eq!(10, bundle.num_rows().await?);
+    assert_eq!(
+        "LIMIT: 10",
+        bundle.bundle.operations().last().unwrap().describe()
+    );
+    // The limit applies after the sort, so these are the highest salaries
+    assert_eq!(tenth_highest, lowest(bundle.bundle.clone()).await?);
+
+    // Reopening still enforces the limit
+    bundle.commit("Top salaries").await?;
+    let (contents, _, _) = common::latest_commit(bundle.data_dir()).await?.unwrap();
+    assert!(contents.contains("type: limit"), "{}", contents);
+    let reopened = Bundle::open(url.as_str(), None).await?;
+    assert_eq!(10, reopened.num_rows().await?);
+    assert_eq!(tenth_highest, lowest(reopened).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pivot_many_groups_spills_under_memory_limit() -> Result<(), BundlebaseError> {
+    let spill_dir = tempfile::tempdir()?;
+    let mut config = BundleConfig::new();
+    config.set(MEMORY_LIMIT_MB, "2", None);
+    config.set(SPILL_DIR, spill_dir.path().to_str().unwrap(), None);
+
+    // 200,000 ids, each with a row per quarter: far more groups than fit in 2MB
+    let groups = 200_000;
+    let mut csv = String::from("id,quarter,sales\n");
+    for id in 0..groups {
+        csv.push_str(&format!("{},q1,{}\n{},q2,{}\n", id, id % 7, id, id % 11));
+    }
+    let file = random_memory_file("sales.csv");
+    file.write(csv.into()).await?;
+
+    let mut bundle =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+    bundle.attach(file.url().as_str()).await?;
+    bundle
+        .pivot(vec!["id"], "quarter", "sales", PivotAggregate::Sum)
+        .await?;
+    assert_eq!(vec!["id", "q1", "q2"], field_names(&bundle.schema().await?));
+    assert_eq!(groups, bundle.num_rows().await?);
+
+    let analyzed = bundle
+        .dataframe()
+        .await?
+        .as_ref()
+        .clone()
+        .explain(false, true)?
+        .collect()
+        .await?;
+    let plan = arrow::util::pretty::pretty_format_batches(&analyzed)?.to_string();
+    assert!(
+        regex::Regex::new(r"spill_count=[1-9]")
+            .unwrap()
+            .is_match(&plan),
+        "Expected the pivot to spill:\n{}",
+        plan
+    );
+
+    // Too many distinct values to pivot on is found without reading them all
+    let err = bundle
+        .pivot(vec!["q1"], "id", "q2", PivotAggregate::Sum)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        "Cannot pivot on 'id': it has more than the 1000 distinct values allowed",
+        err
+    );
+
+    Ok(())
+}
@@ -1,10 +1,15 @@
-use arrow::array::record_batch;
+use arrow::array::{record_batch, Float64Array, Int64Array, RecordBatch};
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use bundlebase;
+use bundlebase::bundle::schema_to_json;
 use bundlebase::bundle::BundleFacade;
+use bundlebase::bundle_config::REINFER_SCHEMA;
 use bundlebase::functions::{FunctionSignature, StaticImpl};
-use bundlebase::test_utils::{random_memory_url, test_datafile};
-use bundlebase::BundlebaseError;
+use bundlebase::test_utils::{random_memory_dir, random_memory_url, test_datafile};
+use bundlebase::{Bundle, BundleConfig, BundlebaseError};
+use bytes::Bytes;
+use parquet::arrow::ArrowWriter;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 mod common;
@@ -213,3 +218,173 @@ async fn test_column_with_special_characters() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_parquet_field_metadata_preserved() -> Result<(), BundlebaseError> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let parquet_path = temp_dir.path().join("measurements.parquet");
+
+    let schema: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("temperature", DataType::Float64, true).with_metadata(HashMap::from([
+            ("unit".to_string(), "celsius".to_string()),
+            ("description".to_string(), "Air temperature".to_string()),
+        ])),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+            Arc::new(Float64Array::from(vec![20.5, 21.0, 19.8])),
+        ],
+    )?;
+    let mut writer = ArrowWriter::try_new(std::fs::File::create(&parquet_path)?, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    let assert_metadata = |schema: SchemaRef| {
+        let field = schema.field_with_name("temperature").unwrap();
+        assert_eq!(Some(&"celsius".to_string()), field.metadata().get("unit"));
+        assert_eq!(
+            Some(&"Air temperature".to_string()),
+            field.metadata().get("description")
+        );
+        assert!(schema.field_with_name("id").unwrap().metadata().is_empty());
+    };
+
+    let bundle_dir = temp_dir.path().join("bundle");
+    let mut bundle = bundlebase::BundleBuilder::create(bundle_dir.to_str().unwrap(), None).await?;
+    bundle
+        .attach(url::Url::from_file_path(&parquet_path).unwrap().as_str())
+        .await?;
+    assert_metadata(bundle.schema().await?);
+    assert_eq!(3, bundle.num_rows().await?);
+    bundle.commit("Attach measurements").await?;
+
+    // Metadata survives being written to and read back from the manifest
+    let reopened = bundlebase::Bundle::open(bundle_dir.to_str().unwrap(), None).await?;
+    assert_metadata(reopened.schema().await?);
+    assert_eq!(3, reopened.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reinfer_schema_picks_up_added_columns() -> Result<(), BundlebaseError> {
+    let exports = random_memory_dir();
+    let export = exports.file("daily/2024-01-01.csv")?;
+    export.write(Bytes::from("id,name\n1,a\n2,b\n")).await?;
+
+    let data_dir = random_memory_url();
+    let mut bundle = bundlebase::BundleBuilder::create(data_dir.as_str(), None).await?;
+    bundle.attach(export.url().as_str()).await?;
+    bundle.commit("Attach daily export").await?;
+
+    // The export gains a column after it was attached
+    export
+        .write(Bytes::from("id,name,score\n1,a,5\n2,b,7\n"))
+        .await?;
+
+    let cached = Bundle::open(data_dir.as_str(), None).await?;
+    assert_eq!(2, cached.schema().await?.fields().len());
+
+    let mut config = BundleConfig::new();
+    config.set(
+        REINFER_SCHEMA,
+        exports.subdir("daily")?.url().as_str(),
+        None,
+    );
+    let reinferred = Bundle::open(data_dir.as_str(), Some(config)).await?;
+    let schema = reinferred.schema().await?;
+    assert_eq!(
+        vec!["id", "name", "score"],
+        schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>()
+    );
+    let rows = reinferred
+        .select("SELECT sum(score) AS total FROM bundle", vec![])
+        .await?
+        .dataframe()
+        .await?
+        .as_ref()
+        .clone()
+        .collect()
+        .await?;
+    assert_eq!(
+        12,
+        rows[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_schema() -> Result<(), BundlebaseError> {
+    let dir = random_memory_dir();
+    let bundle_from = |csv: &'static str| {
+        let dir = dir.clone();
+        async move {
+            let file = dir.file(&format!("{}.csv", rand::random::<u64>()))?;
+            file.write(Bytes::from(csv)).await?;
+            let mut bundle =
+                bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+            bundle.attach(file.url().as_str()).await?;
+            Ok::<_, BundlebaseError>(bundle)
+        }
+    };
+
+    let bundle = bundle_from("id,name\n1,alice\n2,bob\n").await?;
+    let contract = dir.file("schema.json")?;
+    contract
+        .write(Bytes::from(schema_to_json(
+            bundle.schema().await?.as_ref(),
+        )?))
+        .await?;
+    bundle.verify_schema(bundle.schema().await?).await?;
+    bundle.verify_schema_file(contract.url().as_str()).await?;
+
+    // The source drifted: ids became strings and a column was added
+    let drifted = bundle_from("id,name,score\nA1,alice,3\n").await?;
+    let err = drifted
+        .verify_schema_file(contract.url().as_str())
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        format!(
+            "Schema of {} does not match the expected schema:\n  column 'id' is Utf8, expected Int64\n  unexpected column 'score' (Int64)",
+            drifted.url()
+        ),
+        err
+    );
+
+    let expected = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("email", DataType::Utf8, true),
+    ]));
+    let err = bundle
+        .verify_schema(expected)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.ends_with(
+            "\n  column 'id' is nullable, expected not nullable\n  missing column 'email' (Utf8)"
+        ),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
@@ -1,8 +1,22 @@
+use arrow::array::{Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
 use bundlebase;
-use bundlebase::bundle::BundleFacade;
+use bundlebase::bundle::{BundleFacade, CsvExportOptions, JsonExportOptions};
+use bundlebase::bundle_config::{CSV_NULL_REGEX, PARQUET_COMPRESSION, PARQUET_ROW_GROUP_SIZE};
+use bundlebase::io::ObjectStoreFile;
 use bundlebase::test_utils::{random_memory_url, test_datafile};
-use bundlebase::BundlebaseError;
+use bundlebase::{BundleConfig, BundlebaseError};
+use datafusion::arrow::util::pretty::pretty_format_batches;
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::physical_plan::{collect, ExecutionPlan};
+use datafusion::prelude::{DataFrame, SessionContext};
 use datafusion::scalar::ScalarValue;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::sync::Arc;
+use url::Url;
 
 mod common;
 
@@ -120,6 +134,87 @@ async fn test_select_with_aggregation() -> Result<(), BundlebaseError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_select_with_ctes() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+
+    let queried = bundle
+        .select(
+            "WITH women AS (SELECT * FROM bundle WHERE gender = 'Female'),
+                  high_paid AS (SELECT * FROM women WHERE salary > $1)
+             SELECT first_name, salary FROM high_paid",
+            vec![ScalarValue::Float64(Some(250000.0))],
+        )
+        .await?;
+
+    let expected = bundle
+        .select(
+            "SELECT first_name, salary FROM bundle WHERE gender = 'Female' AND salary > 250000",
+            vec![],
+        )
+        .await?
+        .num_rows()
+        .await?;
+    assert!(expected > 0);
+    assert_eq!(expected, queried.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_without_from() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+
+    // A query without a FROM clause selects from the bundle
+    let queried = bundle
+        .select(
+            "* where salary > $1",
+            vec![ScalarValue::Float64(Some(250000.0))],
+        )
+        .await?;
+    let expected = bundle
+        .select("select * from bundle where salary > 250000", vec![])
+        .await?
+        .num_rows()
+        .await?;
+    assert!(expected > 0);
+    assert_eq!(expected, queried.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_invalid_sql() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+    let operation_count = bundle.operations().len();
+
+    let err = bundle
+        .select("SELECT first_name FROM bundle WHERE", vec![])
+        .await
+        .err()
+        .expect("Syntax error should fail");
+    assert!(err
+        .to_string()
+        .starts_with("Invalid query 'SELECT first_name FROM bundle WHERE': "));
+    assert_eq!(operation_count, bundle.operations().len());
+
+    let err = bundle
+        .select("SELECT no_such_column FROM bundle", vec![])
+        .await
+        .err()
+        .expect("Unknown column should fail");
+    assert!(err.to_string().contains("no_such_column"), "{}", err);
+    assert_eq!(operation_count, bundle.operations().len());
+
+    // The bundle is still usable after a failed query
+    assert_eq!(1000, bundle.num_rows().await?);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_explain_basic() -> Result<(), BundlebaseError> {
     let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
@@ -159,3 +254,294 @@ async fn test_explain_with_filter() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_to_sql_matches_bundle() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+    bundle
+        .filter("salary > $1", vec![ScalarValue::Float64(Some(50000.0))])
+        .await?;
+    bundle
+        .select(
+            "SELECT first_name, salary FROM bundle WHERE first_name LIKE $1",
+            vec![ScalarValue::Utf8(Some("J%".to_string()))],
+        )
+        .await?;
+
+    let sql = bundle.bundle.to_sql();
+    assert!(sql.contains("salary > 50000"), "{}", sql);
+
+    let expected = bundle.dataframe().await?.as_ref().clone().collect().await?;
+
+    let ctx = SessionContext::new().enable_url_table();
+    ctx.register_object_store(
+        ObjectStoreUrl::parse("memory://")?.as_ref(),
+        bundlebase::io::get_memory_store(),
+    );
+    let actual = ctx.sql(&sql).await?.collect().await?;
+
+    let sorted_rows = |batches| -> Result<Vec<String>, BundlebaseError> {
+        let mut rows: Vec<String> = pretty_format_batches(batches)?
+            .to_string()
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        rows.sort();
+        Ok(rows)
+    };
+    assert!(expected.iter().map(|b| b.num_rows()).sum::<usize>() > 0);
+    assert_eq!(sorted_rows(&expected)?, sorted_rows(&actual)?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_to_sql_notes_unsupported_operations() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+    bundle.rename_column("first_name", "given_name").await?;
+
+    let sql = bundle.bundle.to_sql();
+    assert!(sql.contains("-- NOTE: "), "{}", sql);
+    assert!(sql.contains("\"first_name\" AS \"given_name\""), "{}", sql);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_csv_round_trip() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+    let queried = bundle
+        .select(
+            "SELECT id, CASE WHEN id % 2 = 0 THEN first_name END AS first_name FROM bundle WHERE id <= 10",
+            vec![],
+        )
+        .await?;
+
+    let path = format!("{}/export.csv", random_memory_url());
+    let rows = queried
+        .export_csv(&path, &CsvExportOptions::default())
+        .await?;
+    assert_eq!(10, rows);
+
+    // Empty fields read back as nulls
+    let mut reattached =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    reattached.attach(&path).await?;
+    assert_eq!(10, reattached.num_rows().await?);
+    let nulls = reattached
+        .select("SELECT * FROM bundle WHERE first_name IS NULL", vec![])
+        .await?;
+    assert_eq!(5, nulls.num_rows().await?);
+
+    let options = CsvExportOptions {
+        null_value: "NULL".to_string(),
+        ..Default::default()
+    };
+    queried.export_csv(&path, &options).await?;
+    let contents = ObjectStoreFile::from_url(&Url::parse(&path)?, BundleConfig::default().into())?
+        .read_str()
+        .await?
+        .unwrap();
+    assert!(
+        contents.starts_with("id,first_name\n1,NULL\n"),
+        "{}",
+        contents
+    );
+    assert_eq!(5, contents.matches(",NULL\n").count());
+
+    // The NULL tokens read back as nulls with a matching null regex
+    let mut config = BundleConfig::new();
+    config.set(CSV_NULL_REGEX, "^NULL$", None);
+    let mut reattached =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+    reattached.attach(&path).await?;
+    assert_eq!(10, reattached.num_rows().await?);
+    let nulls = reattached
+        .select("SELECT * FROM bundle WHERE first_name IS NULL", vec![])
+        .await?;
+    assert_eq!(5, nulls.num_rows().await?);
+    let named = reattached
+        .select("SELECT * FROM bundle WHERE first_name = 'NULL'", vec![])
+        .await?;
+    assert_eq!(0, named.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_json_round_trip() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+    let queried = bundle
+        .select(
+            "SELECT id, CASE WHEN id % 2 = 0 THEN first_name END AS first_name FROM bundle WHERE id <= 10",
+            vec![],
+        )
+        .await?;
+
+    let path = format!("{}/export.json", random_memory_url());
+    let options = JsonExportOptions {
+        explicit_nulls: true,
+    };
+    assert_eq!(10, queried.export_json(&path, &options).await?);
+    let contents = ObjectStoreFile::from_url(&Url::parse(&path)?, BundleConfig::default().into())?
+        .read_str()
+        .await?
+        .unwrap();
+    assert!(
+        contents.starts_with("{\"id\":1,\"first_name\":null}\n"),
+        "{}",
+        contents
+    );
+
+    let mut reattached =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    reattached.attach(&path).await?;
+    assert_eq!(10, reattached.num_rows().await?);
+    let nulls = reattached
+        .select("SELECT * FROM bundle WHERE first_name IS NULL", vec![])
+        .await?;
+    assert_eq!(5, nulls.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_parquet() -> Result<(), BundlebaseError> {
+    let mut config = BundleConfig::new();
+    config.set(PARQUET_ROW_GROUP_SIZE, "100", None);
+    config.set(PARQUET_COMPRESSION, "snappy", None);
+    let mut bundle =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), Some(config)).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+    bundle.filter("id <= 250", vec![]).await?;
+
+    let path = format!("{}/export.parquet", random_memory_url());
+    assert_eq!(250, bundle.export_parquet(&path).await?);
+
+    let contents = ObjectStoreFile::from_url(&Url::parse(&path)?, BundleConfig::default().into())?
+        .read_bytes()
+        .await?
+        .unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(contents)?;
+    assert_eq!(3, reader.metadata().num_row_groups());
+    assert_eq!(
+        Compression::SNAPPY,
+        reader.metadata().row_group(0).column(0).compression()
+    );
+
+    let mut reattached =
+        bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    reattached.attach(&path).await?;
+    assert_eq!(250, reattached.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sql_does_not_add_operation() -> Result<(), BundlebaseError> {
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(test_datafile("userdata.parquet")).await?;
+    let operations = bundle.operations().len();
+
+    let batches = bundle
+        .bundle()
+        .sql(
+            "id, first_name FROM bundle WHERE id <= $1 ORDER BY id",
+            vec![ScalarValue::Int64(Some(3))],
+        )
+        .await?;
+    assert_eq!(
+        pretty_format_batches(&batches)?.to_string(),
+        "\
++----+------------+
+| id | first_name |
++----+------------+
+| 1  | Amanda     |
+| 2  | Albert     |
+| 3  | Evelyn     |
++----+------------+"
+    );
+
+    assert_eq!(operations, bundle.operations().len());
+    assert_eq!(1000, bundle.num_rows().await?);
+
+    Ok(())
+}
+
+/// Sums the bytes the parquet scans in `plan` read from storage
+fn bytes_scanned(plan: &Arc<dyn ExecutionPlan>) -> usize {
+    let own = plan
+        .metrics()
+        .and_then(|m| m.sum_by_name("bytes_scanned"))
+        .map(|v| v.as_usize())
+        .unwrap_or(0);
+    own + plan
+        .children()
+        .into_iter()
+        .map(bytes_scanned)
+        .sum::<usize>()
+}
+
+async fn execute_counting_bytes(df: DataFrame) -> Result<(usize, usize), BundlebaseError> {
+    let task_ctx = Arc::new(df.task_ctx());
+    let plan = df.create_physical_plan().await?;
+    let batches = collect(plan.clone(), task_ctx).await?;
+    let rows = batches.iter().map(|b| b.num_rows()).sum();
+    Ok((rows, bytes_scanned(&plan)))
+}
+
+#[tokio::test]
+async fn test_limit_is_pushed_into_scan() -> Result<(), BundlebaseError> {
+    // 20 row groups of 1000 rows, so a limited scan can stop after the first
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let mut buffer = Vec::new();
+    let props = WriterProperties::builder()
+        .set_max_row_group_size(1000)
+        .build();
+    let mut writer = ArrowWriter::try_new(&mut buffer, schema.clone(), Some(props))?;
+    for chunk in 0..20 {
+        let ids = (chunk * 1000..(chunk + 1) * 1000).collect::<Vec<i64>>();
+        let names = ids
+            .iter()
+            .map(|id| format!("name {}", id))
+            .collect::<Vec<_>>();
+        writer.write(&RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(ids)),
+                Arc::new(StringArray::from(names)),
+            ],
+        )?)?;
+    }
+    writer.close()?;
+
+    let path = format!("{}/large.parquet", random_memory_url());
+    ObjectStoreFile::from_url(&Url::parse(&path)?, BundleConfig::default().into())?
+        .write(buffer.into())
+        .await?;
+
+    let mut bundle = bundlebase::BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    bundle.attach(&path).await?;
+    let df = bundle.dataframe().await?.as_ref().clone();
+
+    let (full_rows, full_bytes) = execute_counting_bytes(df.clone()).await?;
+    assert_eq!(20_000, full_rows);
+
+    let (limited_rows, limited_bytes) = execute_counting_bytes(df.limit(0, Some(10))?).await?;
+    assert_eq!(10, limited_rows);
+    assert!(
+        limited_bytes * 10 < full_bytes,
+        "limited scan read {} of {} bytes",
+        limited_bytes,
+        full_bytes
+    );
+
+    Ok(())
+}
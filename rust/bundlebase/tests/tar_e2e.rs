@@ -74,6 +74,38 @@ async fn test_commit_to_tar() {
     assert_eq!(reopened_history[1].message, "v2 - filtered");
 }
 
+/// Tests amending the last commit of a tar bundle, which appends the amended manifest
+#[tokio::test]
+async fn test_amend_in_tar() {
+    let temp_dir = TempDir::new().unwrap();
+    let tar_path = temp_dir.path().join("amended.tar");
+
+    let mut bundle = BundleBuilder::create(random_memory_url().as_str(), None)
+        .await
+        .unwrap();
+    bundle
+        .attach(test_datafile("userdata.parquet"))
+        .await
+        .unwrap();
+    bundle.commit("v1").await.unwrap();
+    bundle.export_tar(tar_path.to_str().unwrap()).await.unwrap();
+
+    let opened = Bundle::open(tar_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    let mut tar_builder = BundleBuilder::extend(opened.into(), None).unwrap();
+    tar_builder.filter("id > 100", vec![]).await.unwrap();
+    tar_builder.amend(Some("v1 - filtered")).await.unwrap();
+
+    let reopened = Bundle::open(tar_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    let history = reopened.history();
+    assert_eq!(history.len(), 1, "Amending keeps 1 commit in history");
+    assert_eq!(history[0].message, "v1 - filtered");
+    assert_eq!(reopened.num_rows().await.unwrap(), 900);
+}
+
 /// Tests multiple commits to tar bundle
 #[tokio::test]
 async fn test_multiple_commits_to_tar() {
@@ -349,3 +381,80 @@ async fn test_tar_file_listing() {
         "Should have manifest files in _bundlebase directory"
     );
 }
+
+/// Tests creating a bundle directly in a tar file and committing to it repeatedly
+#[tokio::test]
+async fn test_create_and_commit_directly_to_tar() {
+    let temp_dir = TempDir::new().unwrap();
+    let tar_path = temp_dir.path().join("direct.tar");
+    let tar_path = tar_path.to_str().unwrap();
+
+    let mut bundle = BundleBuilder::create(tar_path, None).await.unwrap();
+    bundle
+        .attach(test_datafile("customers-0-100.csv"))
+        .await
+        .unwrap();
+    bundle.commit("v1").await.unwrap();
+
+    bundle
+        .attach(test_datafile("customers-101-150.csv"))
+        .await
+        .unwrap();
+    bundle.commit("v2").await.unwrap();
+
+    let reopened = Bundle::open(tar_path, None).await.unwrap();
+    let history = reopened.history();
+    assert_eq!(2, history.len());
+    assert_eq!("v1", history[0].message);
+    assert_eq!("v2", history[1].message);
+    assert_eq!(150, reopened.num_rows().await.unwrap());
+
+    // Attached files were packed into the archive rather than referenced externally
+    let files: Vec<String> = reopened
+        .data_dir()
+        .list_files()
+        .await
+        .unwrap()
+        .iter()
+        .map(|f| f.url().to_string())
+        .collect();
+    assert!(
+        files.iter().any(|f| f.ends_with("-customers-0-100.csv")),
+        "{:?}",
+        files
+    );
+    assert!(
+        files.iter().any(|f| f.ends_with("-customers-101-150.csv")),
+        "{:?}",
+        files
+    );
+
+    // The archive is usable without the original files
+    let mut moved = BundleBuilder::extend(reopened.into(), None).unwrap();
+    moved.filter("\"Index\" > 140", vec![]).await.unwrap();
+    assert_eq!(10, moved.num_rows().await.unwrap());
+}
+
+/// Tests opening a tar bundle from in-memory bytes once the archive file is gone
+#[tokio::test]
+async fn test_open_from_tar_bytes() {
+    let temp_dir = TempDir::new().unwrap();
+    let tar_path = temp_dir.path().join("embedded.tar");
+
+    let mut bundle = BundleBuilder::create(tar_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    bundle
+        .attach(test_datafile("customers-0-100.csv"))
+        .await
+        .unwrap();
+    bundle.commit("v1").await.unwrap();
+
+    let tar_bytes = std::fs::read(&tar_path).unwrap();
+    drop(temp_dir);
+
+    let embedded = Bundle::from_tar_bytes(&tar_bytes, None).await.unwrap();
+    assert_eq!(1, embedded.history().len());
+    assert_eq!("v1", embedded.history()[0].message);
+    assert_eq!(100, embedded.num_rows().await.unwrap());
+}
@@ -1,3 +1,5 @@
+use arrow::array::AsArray;
+use arrow::datatypes::Int64Type;
 use bundlebase::test_utils::{
     assert_vec_regexp, describe_ops, field_names, random_memory_url, test_datafile,
 };
@@ -873,3 +875,206 @@ async fn test_drop_view_twice_fails() -> Result<(), BundlebaseError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_drop_view_by_id() -> Result<(), BundlebaseError> {
+    let container_url = random_memory_url().to_string();
+    let mut c = BundleBuilder::create(&container_url, None).await?;
+    c.attach(&test_datafile("customers-0-100.csv")).await?;
+    c.commit("Initial data").await?;
+
+    let adults = c
+        .select("select * from bundle where \"Index\" > 21", vec![])
+        .await?;
+    c.create_view("adults", &adults).await?;
+    c.commit("Add adults view").await?;
+    let view_id = c.views().keys().next().unwrap().to_string();
+
+    // Drop the view by its ID rather than its name
+    c.drop_view(&view_id).await?;
+    c.commit("Dropped view").await?;
+    assert!(c.views().is_empty());
+
+    let bundle = Bundle::open(&container_url, None).await?;
+    let result = bundle.view("adults").await;
+    assert!(result.is_err());
+    assert!(result.err().unwrap().to_string().contains("not found"));
+
+    // An unknown ID is reported as such
+    let result = c.drop_view(&view_id).await;
+    assert!(result.is_err());
+    let err_msg = result.err().unwrap().to_string();
+    assert!(
+        err_msg.contains("View with ID"),
+        "Error should mention ID not found"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_materialize_view() -> Result<(), BundlebaseError> {
+    let container_url = random_memory_url().to_string();
+    let mut c = BundleBuilder::create(&container_url, None).await?;
+    c.attach(&test_datafile("customers-0-100.csv")).await?;
+    let adults = c
+        .select("select * from bundle where \"Index\" > 21", vec![])
+        .await?;
+    c.create_view("adults", &adults).await?;
+    c.commit("Add adults view").await?;
+    let lazy_rows = c.view("adults").await?.num_rows().await?;
+    assert_eq!(79, lazy_rows);
+
+    c.materialize_view("adults").await?;
+    c.commit("Materialized view").await?;
+    let view = c.view("adults").await?;
+    assert_eq!(lazy_rows, view.num_rows().await?);
+    assert!(view.explain().await?.contains("materialized_"));
+    let batches = view
+        .sql("select count(*) from bundle where \"Index\" > 50", vec![])
+        .await?;
+    assert_eq!(
+        50,
+        batches[0].column(0).as_primitive::<Int64Type>().value(0)
+    );
+
+    // The stored data is a snapshot, more data in the parent only shows after a refresh
+    c.attach(&test_datafile("customers-101-150.csv")).await?;
+    c.commit("More data").await?;
+    assert_eq!(79, c.view("adults").await?.num_rows().await?);
+
+    let result = c.refresh_view("nonexistent").await;
+    assert!(result
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("View 'nonexistent' not found"));
+
+    c.refresh_view("adults").await?;
+    c.commit("Refreshed view").await?;
+    assert_eq!(129, c.view("adults").await?.num_rows().await?);
+
+    // Reopening reads the refreshed data
+    let bundle = Bundle::open(&container_url, None).await?;
+    assert_eq!(129, bundle.view("adults").await?.num_rows().await?);
+    assert!(bundle
+        .operations()
+        .last()
+        .unwrap()
+        .describe()
+        .starts_with("MATERIALIZE VIEW"));
+
+    // Only materialized views can be refreshed
+    let others = c
+        .select("select * from bundle where \"Index\" <= 21", vec![])
+        .await?;
+    c.create_view("others", &others).await?;
+    let result = c.refresh_view("others").await;
+    assert_eq!(
+        "View 'others' is not materialized, use materialize_view first",
+        result.err().unwrap().to_string()
+    );
+
+    // A materialized default view also reads the stored data
+    c.set_default_view("adults").await?;
+    assert_eq!(129, c.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_view_built_from_view_does_not_depend_on_it() -> Result<(), BundlebaseError> {
+    let mut c = BundleBuilder::create(random_memory_url().as_str(), None).await?;
+    c.attach(&test_datafile("customers-0-100.csv")).await?;
+    c.commit("Initial data").await?;
+
+    let adults = c
+        .select("select * from bundle where \"Index\" > 21", vec![])
+        .await?;
+    c.create_view("adults", &adults).await?;
+    c.commit("Add adults view").await?;
+
+    // Build a second view from a query against the first view
+    let adults_view = c.view("adults").await?.extend(None)?;
+    let seniors = adults_view
+        .select("select * from bundle where \"Index\" > 65", vec![])
+        .await?;
+    c.create_view("seniors", &seniors).await?;
+    c.commit("Add seniors view").await?;
+
+    // Views are keyed by ID and always load from the container, so renaming or dropping
+    // the first view cannot orphan the second
+    c.rename_view("adults", "grownups").await?;
+    c.drop_view("grownups").await?;
+    c.commit("Rename and drop adults").await?;
+
+    let seniors_view = c.view("seniors").await?;
+    assert_eq!(35, seniors_view.num_rows().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_view() -> Result<(), BundlebaseError> {
+    let url = random_memory_url().to_string();
+    let mut c = BundleBuilder::create(&url, None).await?;
+    c.attach(&test_datafile("customers-0-100.csv")).await?;
+    c.commit("Initial data").await?;
+
+    let islands = c
+        .select(
+            "select * from bundle where Country = 'Solomon Islands'",
+            vec![],
+        )
+        .await?;
+    c.create_view("islands", &islands).await?;
+    c.set_default_view("islands").await?;
+    c.commit("Show islands by default").await?;
+
+    let raw_count = |bundle: Bundle| async move {
+        let batches = bundle.raw_sql("SELECT * FROM bundle", vec![]).await?;
+        Ok::<_, BundlebaseError>(batches.iter().map(|b| b.num_rows()).sum::<usize>())
+    };
+
+    // Queries see the default view, raw queries the bundle's own data
+    let bundle = Bundle::open(&url, None).await?;
+    assert_eq!(Some("islands".to_string()), bundle.default_view());
+    assert_eq!(4, bundle.num_rows().await?);
+    assert_eq!(
+        100,
+        bundle
+            .raw_dataframe()
+            .await?
+            .as_ref()
+            .clone()
+            .count()
+            .await?
+    );
+    assert_eq!(100, raw_count(bundle.clone()).await?);
+    let batches = bundle
+        .sql("SELECT DISTINCT Country FROM bundle", vec![])
+        .await?;
+    assert_eq!(1, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+
+    // Operations added later still apply to the raw data, under the default view
+    c.filter("\"Index\" <= 50", vec![]).await?;
+    assert_eq!(50, raw_count(c.bundle.clone()).await?);
+    assert_eq!(1, c.num_rows().await?);
+
+    // The view itself is unaffected by being the default
+    assert_eq!(4, c.view("islands").await?.num_rows().await?);
+
+    c.remove_default_view().await?;
+    assert_eq!(None, c.bundle.default_view());
+    assert_eq!(50, c.num_rows().await?);
+    assert!(c.remove_default_view().await.is_err());
+
+    c.set_default_view("islands").await?;
+    c.drop_view("islands").await?;
+    assert_eq!(None, c.bundle.default_view());
+    assert_eq!(50, c.num_rows().await?);
+
+    assert!(c.set_default_view("nonexistent").await.is_err());
+
+    Ok(())
+}